@@ -1,13 +1,14 @@
-//! Benchmark for GRC-20 serialization using city data.
+//! Converts the cities dataset (153k cities) to GRC-20 and reports sizes.
 //!
-//! Demonstrates the builder API with a large dataset (153k cities).
+//! Demonstrates the builder API with a large dataset. Codec timings live in
+//! the criterion suite (`cargo bench -p grc-20`).
 
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use std::time::Instant;
 
-use grc_20::{EditBuilder, EncodeOptions, EntityBuilder, Id, Op, derived_uuid};
+use grc_20::{EditBuilder, EncodeOptions, EntityBuilder, Id, derived_uuid};
 
 /// Creates a deterministic relation ID from from+to+type (to maintain same behavior as removed unique mode).
 fn make_relation_id(from: Id, to: Id, rel_type: Id) -> Id {
@@ -23,83 +24,61 @@ use serde::Deserialize;
 // HARDCODED UUIDs FOR SCHEMA
 // =============================================================================
 
-const fn hex(s: &str) -> [u8; 16] {
-    let bytes = s.as_bytes();
-    let mut result = [0u8; 16];
-    let mut i = 0;
-    while i < 16 {
-        let hi = hex_digit(bytes[i * 2]);
-        let lo = hex_digit(bytes[i * 2 + 1]);
-        result[i] = (hi << 4) | lo;
-        i += 1;
-    }
-    result
-}
-
-const fn hex_digit(c: u8) -> u8 {
-    match c {
-        b'0'..=b'9' => c - b'0',
-        b'a'..=b'f' => c - b'a' + 10,
-        b'A'..=b'F' => c - b'A' + 10,
-        _ => 0,
-    }
-}
-
 /// Property IDs
 mod props {
-    use super::hex;
-
-    pub const NAME: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const CODE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const NATIVE_NAME: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d6");
-    pub const POPULATION: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d7");
-    pub const LOCATION: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d8");
-    pub const TIMEZONE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d9");
-    pub const WIKIDATA_ID: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3da");
-    pub const CITY_TYPE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3db");
+    grc_20::grc20_ids! {
+        pub NAME = "a1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub CODE = "a1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub NATIVE_NAME = "a1b2c3d4e5f68718a9304050a1b2c3d6";
+        pub POPULATION = "a1b2c3d4e5f68718a9304050a1b2c3d7";
+        pub LOCATION = "a1b2c3d4e5f68718a9304050a1b2c3d8";
+        pub TIMEZONE = "a1b2c3d4e5f68718a9304050a1b2c3d9";
+        pub WIKIDATA_ID = "a1b2c3d4e5f68718a9304050a1b2c3da";
+        pub CITY_TYPE = "a1b2c3d4e5f68718a9304050a1b2c3db";
+    }
 }
 
 /// Type IDs
 mod types {
-    use super::hex;
-
-    pub const CITY: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const STATE: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const COUNTRY: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d6");
+    grc_20::grc20_ids! {
+        pub CITY = "b1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub STATE = "b1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub COUNTRY = "b1b2c3d4e5f68718a9304050a1b2c3d6";
+    }
 }
 
 /// Relation type IDs
 mod rel_types {
-    use super::hex;
-
-    pub const TYPES: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const IN_STATE: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const IN_COUNTRY: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d6");
+    grc_20::grc20_ids! {
+        pub TYPES = "c1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub IN_STATE = "c1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub IN_COUNTRY = "c1b2c3d4e5f68718a9304050a1b2c3d6";
+    }
 }
 
 /// Language IDs
 mod langs {
-    use super::hex;
-
-    pub const BRETON: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d0");
-    pub const KOREAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d1");
-    pub const PORTUGUESE_BR: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d2");
-    pub const PORTUGUESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d3");
-    pub const DUTCH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const CROATIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const PERSIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d6");
-    pub const GERMAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d7");
-    pub const SPANISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d8");
-    pub const FRENCH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d9");
-    pub const JAPANESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3da");
-    pub const ITALIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3db");
-    pub const CHINESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3dc");
-    pub const TURKISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3dd");
-    pub const RUSSIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3de");
-    pub const UKRAINIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3df");
-    pub const POLISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e0");
-    pub const ARABIC: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e1");
-    pub const HINDI: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e2");
+    grc_20::grc20_ids! {
+        pub BRETON = "d1b2c3d4e5f68718a9304050a1b2c3d0";
+        pub KOREAN = "d1b2c3d4e5f68718a9304050a1b2c3d1";
+        pub PORTUGUESE_BR = "d1b2c3d4e5f68718a9304050a1b2c3d2";
+        pub PORTUGUESE = "d1b2c3d4e5f68718a9304050a1b2c3d3";
+        pub DUTCH = "d1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub CROATIAN = "d1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub PERSIAN = "d1b2c3d4e5f68718a9304050a1b2c3d6";
+        pub GERMAN = "d1b2c3d4e5f68718a9304050a1b2c3d7";
+        pub SPANISH = "d1b2c3d4e5f68718a9304050a1b2c3d8";
+        pub FRENCH = "d1b2c3d4e5f68718a9304050a1b2c3d9";
+        pub JAPANESE = "d1b2c3d4e5f68718a9304050a1b2c3da";
+        pub ITALIAN = "d1b2c3d4e5f68718a9304050a1b2c3db";
+        pub CHINESE = "d1b2c3d4e5f68718a9304050a1b2c3dc";
+        pub TURKISH = "d1b2c3d4e5f68718a9304050a1b2c3dd";
+        pub RUSSIAN = "d1b2c3d4e5f68718a9304050a1b2c3de";
+        pub UKRAINIAN = "d1b2c3d4e5f68718a9304050a1b2c3df";
+        pub POLISH = "d1b2c3d4e5f68718a9304050a1b2c3e0";
+        pub ARABIC = "d1b2c3d4e5f68718a9304050a1b2c3e1";
+        pub HINDI = "d1b2c3d4e5f68718a9304050a1b2c3e2";
+    }
 }
 
 // =============================================================================
@@ -213,11 +192,8 @@ fn build_city_entity<'a>(city: &'a City) -> EntityBuilder<'a> {
 
     // Translations (multi-value TEXT with language)
     if let Some(ref translations) = city.translations {
-        for (lang_code, translation) in translations {
-            if let Some(lang_id) = get_language_id(lang_code) {
-                builder = builder.text(props::NAME, translation.as_str(), Some(lang_id));
-            }
-        }
+        let texts = translations.iter().filter_map(|(code, text)| Some((text.as_str(), Some(get_language_id(code)?))));
+        builder = builder.texts(props::NAME, texts);
     }
 
     builder
@@ -335,19 +311,9 @@ fn main() {
     let convert_time = convert_start.elapsed();
 
     // Count statistics
-    let mut entity_count = 0;
-    let mut relation_count = 0;
-    let mut total_values = 0;
-    for op in &edit.ops {
-        match op {
-            Op::CreateEntity(e) => {
-                entity_count += 1;
-                total_values += e.values.len();
-            }
-            Op::CreateRelation(_) => relation_count += 1,
-            _ => {}
-        }
-    }
+    let entity_count = edit.entities().count();
+    let relation_count = edit.relations().count();
+    let total_values: usize = edit.entities().map(|e| e.values.len()).sum();
 
     println!(
         "Converted to {} operations in {:?}",
@@ -359,144 +325,29 @@ fn main() {
         entity_count, relation_count, total_values
     );
 
-    // Benchmark encoding (uncompressed, fast mode)
-    let encode_start = Instant::now();
     let encoded = grc_20::encode_edit(&edit).expect("Failed to encode");
-    let encode_time = encode_start.elapsed();
-
-    println!(
-        "\nUncompressed (fast): {} bytes in {:?}",
-        encoded.len(),
-        encode_time
-    );
-    println!(
-        "  Throughput: {:.2} MB/s",
-        (encoded.len() as f64 / 1_000_000.0) / encode_time.as_secs_f64()
-    );
-
-    // Benchmark encoding (uncompressed, canonical mode)
-    let canonical_start = Instant::now();
     let canonical_encoded = grc_20::encode_edit_with_options(&edit, EncodeOptions::canonical())
         .expect("Failed to encode canonical");
-    let canonical_time = canonical_start.elapsed();
-
-    println!(
-        "\nUncompressed (canonical): {} bytes in {:?}",
-        canonical_encoded.len(),
-        canonical_time
-    );
-    println!(
-        "  Throughput: {:.2} MB/s",
-        (canonical_encoded.len() as f64 / 1_000_000.0) / canonical_time.as_secs_f64()
-    );
-    println!(
-        "  Overhead vs fast: {:.1}x slower",
-        canonical_time.as_secs_f64() / encode_time.as_secs_f64()
-    );
+    let compressed = grc_20::encode_edit_compressed(&edit, 3).expect("Failed to compress");
 
-    // Verify canonical encoding is deterministic
+    // Verify canonical encoding is deterministic and everything round-trips
     let canonical_encoded2 = grc_20::encode_edit_with_options(&edit, EncodeOptions::canonical())
         .expect("Failed to encode canonical");
     assert_eq!(
         canonical_encoded, canonical_encoded2,
         "Canonical encoding should be deterministic"
     );
-
-    // Benchmark encoding (compressed)
-    let compress_start = Instant::now();
-    let compressed = grc_20::encode_edit_compressed(&edit, 3).expect("Failed to compress");
-    let compress_time = compress_start.elapsed();
-
-    println!(
-        "\nCompressed (level 3): {} bytes in {:?}",
-        compressed.len(),
-        compress_time
-    );
-    println!(
-        "  Compression ratio: {:.1}x",
-        encoded.len() as f64 / compressed.len() as f64
-    );
-    println!(
-        "  Throughput: {:.2} MB/s (uncompressed equivalent)",
-        (encoded.len() as f64 / 1_000_000.0) / compress_time.as_secs_f64()
-    );
-
-    // Benchmark decoding (uncompressed)
-    const DECODE_ITERS: u32 = 10; // Fewer iterations due to larger data
-
-    // Warmup
-    for _ in 0..3 {
-        let _ = grc_20::decode_edit(&encoded).expect("Failed to decode");
-    }
-
-    let decode_start = Instant::now();
-    let mut decoded = None;
-    for _ in 0..DECODE_ITERS {
-        decoded = Some(grc_20::decode_edit(&encoded).expect("Failed to decode"));
-    }
-    let decode_time = decode_start.elapsed() / DECODE_ITERS;
-    let decoded = decoded.unwrap();
-
-    println!(
-        "\nDecode (uncompressed): {:?} (avg of {} iterations)",
-        decode_time, DECODE_ITERS
-    );
-    println!(
-        "  Throughput: {:.2} MB/s",
-        (encoded.len() as f64 / 1_000_000.0) / decode_time.as_secs_f64()
-    );
-    assert_eq!(decoded.ops.len(), edit.ops.len());
-
-    // Benchmark decoding (compressed, allocating)
-    for _ in 0..3 {
-        let _ = grc_20::decode_edit(&compressed).expect("Failed to decode compressed");
-    }
-
-    let decode_compressed_start = Instant::now();
-    let mut decoded_compressed = None;
-    for _ in 0..DECODE_ITERS {
-        decoded_compressed =
-            Some(grc_20::decode_edit(&compressed).expect("Failed to decode compressed"));
-    }
-    let decode_compressed_time = decode_compressed_start.elapsed() / DECODE_ITERS;
-    let decoded_compressed = decoded_compressed.unwrap();
-
-    println!(
-        "\nDecode (compressed, allocating): {:?} (avg of {} iterations)",
-        decode_compressed_time, DECODE_ITERS
-    );
-    println!(
-        "  Throughput: {:.2} MB/s (uncompressed equivalent)",
-        (encoded.len() as f64 / 1_000_000.0) / decode_compressed_time.as_secs_f64()
-    );
-    assert_eq!(decoded_compressed.ops.len(), edit.ops.len());
-
-    // Benchmark decoding (compressed, zero-copy)
-    for _ in 0..3 {
-        let decompressed = grc_20::decompress(&compressed).expect("Failed to decompress");
-        let _ = grc_20::decode_edit(&decompressed).expect("Failed to decode");
-    }
-
-    let decode_zc_start = Instant::now();
-    for _ in 0..DECODE_ITERS {
-        let decompressed = grc_20::decompress(&compressed).expect("Failed to decompress");
-        let decoded = grc_20::decode_edit(&decompressed).expect("Failed to decode");
+    for bytes in [&encoded, &canonical_encoded, &compressed] {
+        let decoded = grc_20::decode_edit(bytes).expect("Failed to decode");
         assert_eq!(decoded.ops.len(), edit.ops.len());
     }
-    let decode_zc_time = decode_zc_start.elapsed() / DECODE_ITERS;
 
+    println!("\nUncompressed (fast): {} bytes", encoded.len());
+    println!("Uncompressed (canonical): {} bytes", canonical_encoded.len());
     println!(
-        "\nDecode (compressed, zero-copy): {:?} (avg of {} iterations)",
-        decode_zc_time, DECODE_ITERS
-    );
-    println!(
-        "  Throughput: {:.2} MB/s (uncompressed equivalent)",
-        (encoded.len() as f64 / 1_000_000.0) / decode_zc_time.as_secs_f64()
-    );
-    println!(
-        "  Speedup vs allocating: {:.1}%",
-        100.0 * (decode_compressed_time.as_secs_f64() - decode_zc_time.as_secs_f64())
-            / decode_compressed_time.as_secs_f64()
+        "Compressed (level 3): {} bytes ({:.1}x)",
+        compressed.len(),
+        encoded.len() as f64 / compressed.len() as f64
     );
 
     // Write output files
@@ -29,75 +29,57 @@ pub mod proto {
 // SHARED CONSTANTS
 // =============================================================================
 
-const fn hex(s: &str) -> [u8; 16] {
-    let bytes = s.as_bytes();
-    let mut result = [0u8; 16];
-    let mut i = 0;
-    while i < 16 {
-        let hi = hex_digit(bytes[i * 2]);
-        let lo = hex_digit(bytes[i * 2 + 1]);
-        result[i] = (hi << 4) | lo;
-        i += 1;
-    }
-    result
-}
-
-const fn hex_digit(c: u8) -> u8 {
-    match c {
-        b'0'..=b'9' => c - b'0',
-        b'a'..=b'f' => c - b'a' + 10,
-        b'A'..=b'F' => c - b'A' + 10,
-        _ => 0,
-    }
-}
-
 mod props {
-    use super::hex;
-    pub const NAME: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const CODE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const NATIVE_NAME: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d6");
-    pub const POPULATION: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d7");
-    pub const LOCATION: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d8");
-    pub const TIMEZONE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d9");
-    pub const WIKIDATA_ID: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3da");
-    pub const CITY_TYPE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3db");
+    grc_20::grc20_ids! {
+        pub NAME = "a1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub CODE = "a1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub NATIVE_NAME = "a1b2c3d4e5f68718a9304050a1b2c3d6";
+        pub POPULATION = "a1b2c3d4e5f68718a9304050a1b2c3d7";
+        pub LOCATION = "a1b2c3d4e5f68718a9304050a1b2c3d8";
+        pub TIMEZONE = "a1b2c3d4e5f68718a9304050a1b2c3d9";
+        pub WIKIDATA_ID = "a1b2c3d4e5f68718a9304050a1b2c3da";
+        pub CITY_TYPE = "a1b2c3d4e5f68718a9304050a1b2c3db";
+    }
 }
 
 mod types {
-    use super::hex;
-    pub const CITY: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const STATE: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const COUNTRY: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d6");
+    grc_20::grc20_ids! {
+        pub CITY = "b1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub STATE = "b1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub COUNTRY = "b1b2c3d4e5f68718a9304050a1b2c3d6";
+    }
 }
 
 mod rel_types {
-    use super::hex;
-    pub const TYPES: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const IN_STATE: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const IN_COUNTRY: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d6");
+    grc_20::grc20_ids! {
+        pub TYPES = "c1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub IN_STATE = "c1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub IN_COUNTRY = "c1b2c3d4e5f68718a9304050a1b2c3d6";
+    }
 }
 
 mod langs {
-    use super::hex;
-    pub const BRETON: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d0");
-    pub const KOREAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d1");
-    pub const PORTUGUESE_BR: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d2");
-    pub const PORTUGUESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d3");
-    pub const DUTCH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const CROATIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const PERSIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d6");
-    pub const GERMAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d7");
-    pub const SPANISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d8");
-    pub const FRENCH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d9");
-    pub const JAPANESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3da");
-    pub const ITALIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3db");
-    pub const CHINESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3dc");
-    pub const TURKISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3dd");
-    pub const RUSSIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3de");
-    pub const UKRAINIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3df");
-    pub const POLISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e0");
-    pub const ARABIC: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e1");
-    pub const HINDI: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e2");
+    grc_20::grc20_ids! {
+        pub BRETON = "d1b2c3d4e5f68718a9304050a1b2c3d0";
+        pub KOREAN = "d1b2c3d4e5f68718a9304050a1b2c3d1";
+        pub PORTUGUESE_BR = "d1b2c3d4e5f68718a9304050a1b2c3d2";
+        pub PORTUGUESE = "d1b2c3d4e5f68718a9304050a1b2c3d3";
+        pub DUTCH = "d1b2c3d4e5f68718a9304050a1b2c3d4";
+        pub CROATIAN = "d1b2c3d4e5f68718a9304050a1b2c3d5";
+        pub PERSIAN = "d1b2c3d4e5f68718a9304050a1b2c3d6";
+        pub GERMAN = "d1b2c3d4e5f68718a9304050a1b2c3d7";
+        pub SPANISH = "d1b2c3d4e5f68718a9304050a1b2c3d8";
+        pub FRENCH = "d1b2c3d4e5f68718a9304050a1b2c3d9";
+        pub JAPANESE = "d1b2c3d4e5f68718a9304050a1b2c3da";
+        pub ITALIAN = "d1b2c3d4e5f68718a9304050a1b2c3db";
+        pub CHINESE = "d1b2c3d4e5f68718a9304050a1b2c3dc";
+        pub TURKISH = "d1b2c3d4e5f68718a9304050a1b2c3dd";
+        pub RUSSIAN = "d1b2c3d4e5f68718a9304050a1b2c3de";
+        pub UKRAINIAN = "d1b2c3d4e5f68718a9304050a1b2c3df";
+        pub POLISH = "d1b2c3d4e5f68718a9304050a1b2c3e0";
+        pub ARABIC = "d1b2c3d4e5f68718a9304050a1b2c3e1";
+        pub HINDI = "d1b2c3d4e5f68718a9304050a1b2c3e2";
+    }
 
     pub fn from_code(code: &str) -> Option<[u8; 16]> {
         match code {
@@ -221,11 +203,8 @@ fn build_city_entity_grc20<'a>(city: &'a City) -> EntityBuilder<'a> {
     }
 
     if let Some(ref translations) = city.translations {
-        for (lang_code, translation) in translations {
-            if let Some(lang_id) = langs::from_code(lang_code) {
-                builder = builder.text(props::NAME, translation.as_str(), Some(lang_id));
-            }
-        }
+        let texts = translations.iter().filter_map(|(code, text)| Some((text.as_str(), Some(langs::from_code(code)?))));
+        builder = builder.texts(props::NAME, texts);
     }
 
     builder
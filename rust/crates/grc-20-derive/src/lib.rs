@@ -0,0 +1,159 @@
+//! Derive macros for the `grc-20` crate.
+//!
+//! Use these through `grc_20::GrcEntity` (feature `derive`) rather than
+//! depending on this crate directly; the generated code refers to `::grc_20`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, ExprLit, Fields, Ident, Lit, LitStr,
+};
+
+/// Derives `grc_20::mapping::GrcEntity` for a struct with named fields.
+///
+/// Exactly one field is marked `#[grc(id)]` and holds the entity ID. Every
+/// other field is either `#[grc(property = ...)]` or `#[grc(skip)]`. The
+/// property (and optional `language`) is a UUID string literal, checked at
+/// compile time, or any constant expression of type `Id`.
+#[proc_macro_derive(GrcEntity, attributes(grc))]
+pub fn derive_grc_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+enum FieldKind {
+    Id,
+    Skip,
+    Property { property: TokenStream2, language: TokenStream2 },
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(Error::new_spanned(&input, "GrcEntity requires named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input, "GrcEntity can only be derived for structs")),
+    };
+
+    let mut id_field: Option<&Ident> = None;
+    let mut to_values = Vec::new();
+    let mut from_fields = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        match field_kind(field)? {
+            FieldKind::Id => {
+                if id_field.is_some() {
+                    return Err(Error::new_spanned(field, "duplicate #[grc(id)] field"));
+                }
+                id_field = Some(ident);
+                from_fields.push(quote! { #ident: view.id });
+            }
+            FieldKind::Skip => {
+                from_fields.push(quote! { #ident: ::core::default::Default::default() });
+            }
+            FieldKind::Property { property, language } => {
+                let field_name = ident.to_string();
+                to_values.push(quote! {
+                    if let ::core::option::Option::Some(value) =
+                        ::grc_20::mapping::FieldValue::to_value(&self.#ident, #language)
+                    {
+                        values.push(::grc_20::PropertyValue { property: #property, value });
+                    }
+                });
+                from_fields.push(quote! {
+                    #ident: <#ty as ::grc_20::mapping::FieldValue>::from_value(
+                        view.value_in(&#property, #language),
+                        #field_name,
+                        #property,
+                    )?
+                });
+            }
+        }
+    }
+    let Some(id_field) = id_field else {
+        return Err(Error::new_spanned(&input.ident, "GrcEntity requires a #[grc(id)] field"));
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::grc_20::mapping::GrcEntity for #name #ty_generics #where_clause {
+            fn entity_id(&self) -> ::grc_20::Id {
+                self.#id_field
+            }
+
+            fn to_values(&self) -> ::grc_20::mapping::__private::Vec<::grc_20::PropertyValue<'static>> {
+                let mut values = ::grc_20::mapping::__private::Vec::new();
+                #(#to_values)*
+                values
+            }
+
+            fn from_entity_view(
+                view: &::grc_20::EntityView<'_, '_>,
+            ) -> ::core::result::Result<Self, ::grc_20::MappingError> {
+                ::core::result::Result::Ok(Self { #(#from_fields,)* })
+            }
+        }
+    })
+}
+
+fn field_kind(field: &syn::Field) -> syn::Result<FieldKind> {
+    let mut kind = None;
+    let mut property = None;
+    let mut language = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("grc")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                kind = Some(FieldKind::Id);
+            } else if meta.path.is_ident("skip") {
+                kind = Some(FieldKind::Skip);
+            } else if meta.path.is_ident("property") {
+                property = Some(id_expr(meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("language") {
+                language = Some(id_expr(meta.value()?.parse()?)?);
+            } else {
+                return Err(meta.error("expected `id`, `skip`, `property`, or `language`"));
+            }
+            Ok(())
+        })?;
+    }
+    match (kind, property) {
+        (Some(_), Some(_)) => Err(Error::new_spanned(field, "`property` cannot be combined with `id` or `skip`")),
+        (Some(kind), None) => {
+            if language.is_some() {
+                return Err(Error::new_spanned(field, "`language` requires `property`"));
+            }
+            Ok(kind)
+        }
+        (None, Some(property)) => {
+            let language = match language {
+                Some(l) => quote! { ::core::option::Option::Some(#l) },
+                None => quote! { ::core::option::Option::None },
+            };
+            Ok(FieldKind::Property { property, language })
+        }
+        (None, None) => Err(Error::new_spanned(
+            field,
+            "field needs #[grc(id)], #[grc(property = ...)], or #[grc(skip)]",
+        )),
+    }
+}
+
+/// A UUID string literal becomes a compile-time checked constant; anything
+/// else is used as an `Id` expression as written.
+fn id_expr(expr: Expr) -> syn::Result<TokenStream2> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) => Ok(hex_id(&s)),
+        other => Ok(quote! { (#other) }),
+    }
+}
+
+fn hex_id(s: &LitStr) -> TokenStream2 {
+    quote! {{
+        const ID: ::grc_20::Id = ::grc_20::id_from_hex(#s);
+        ID
+    }}
+}
@@ -45,57 +45,57 @@ const fn hex_digit(c: u8) -> u8 {
 mod props {
     use super::hex;
 
-    pub const NAME: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const CODE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const NATIVE_NAME: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d6");
-    pub const POPULATION: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d7");
-    pub const LOCATION: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d8");
-    pub const TIMEZONE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3d9");
-    pub const WIKIDATA_ID: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3da");
-    pub const CITY_TYPE: [u8; 16] = hex("a1b2c3d4e5f6071829304050a1b2c3db");
+    pub const NAME: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3d4");
+    pub const CODE: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3d5");
+    pub const NATIVE_NAME: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3d6");
+    pub const POPULATION: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3d7");
+    pub const LOCATION: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3d8");
+    pub const TIMEZONE: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3d9");
+    pub const WIKIDATA_ID: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3da");
+    pub const CITY_TYPE: [u8; 16] = hex("a1b2c3d4e5f68718a9304050a1b2c3db");
 }
 
 /// Type IDs (same as grc-20-bench)
 mod types {
     use super::hex;
 
-    pub const CITY: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const STATE: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const COUNTRY: [u8; 16] = hex("b1b2c3d4e5f6071829304050a1b2c3d6");
+    pub const CITY: [u8; 16] = hex("b1b2c3d4e5f68718a9304050a1b2c3d4");
+    pub const STATE: [u8; 16] = hex("b1b2c3d4e5f68718a9304050a1b2c3d5");
+    pub const COUNTRY: [u8; 16] = hex("b1b2c3d4e5f68718a9304050a1b2c3d6");
 }
 
 /// Relation type IDs (same as grc-20-bench)
 mod rel_types {
     use super::hex;
 
-    pub const TYPES: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const IN_STATE: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const IN_COUNTRY: [u8; 16] = hex("c1b2c3d4e5f6071829304050a1b2c3d6");
+    pub const TYPES: [u8; 16] = hex("c1b2c3d4e5f68718a9304050a1b2c3d4");
+    pub const IN_STATE: [u8; 16] = hex("c1b2c3d4e5f68718a9304050a1b2c3d5");
+    pub const IN_COUNTRY: [u8; 16] = hex("c1b2c3d4e5f68718a9304050a1b2c3d6");
 }
 
 /// Language IDs (same as grc-20-bench)
 mod langs {
     use super::hex;
 
-    pub const BRETON: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d0");
-    pub const KOREAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d1");
-    pub const PORTUGUESE_BR: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d2");
-    pub const PORTUGUESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d3");
-    pub const DUTCH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d4");
-    pub const CROATIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d5");
-    pub const PERSIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d6");
-    pub const GERMAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d7");
-    pub const SPANISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d8");
-    pub const FRENCH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3d9");
-    pub const JAPANESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3da");
-    pub const ITALIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3db");
-    pub const CHINESE: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3dc");
-    pub const TURKISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3dd");
-    pub const RUSSIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3de");
-    pub const UKRAINIAN: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3df");
-    pub const POLISH: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e0");
-    pub const ARABIC: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e1");
-    pub const HINDI: [u8; 16] = hex("d1b2c3d4e5f6071829304050a1b2c3e2");
+    pub const BRETON: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d0");
+    pub const KOREAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d1");
+    pub const PORTUGUESE_BR: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d2");
+    pub const PORTUGUESE: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d3");
+    pub const DUTCH: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d4");
+    pub const CROATIAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d5");
+    pub const PERSIAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d6");
+    pub const GERMAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d7");
+    pub const SPANISH: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d8");
+    pub const FRENCH: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3d9");
+    pub const JAPANESE: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3da");
+    pub const ITALIAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3db");
+    pub const CHINESE: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3dc");
+    pub const TURKISH: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3dd");
+    pub const RUSSIAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3de");
+    pub const UKRAINIAN: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3df");
+    pub const POLISH: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3e0");
+    pub const ARABIC: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3e1");
+    pub const HINDI: [u8; 16] = hex("d1b2c3d4e5f68718a9304050a1b2c3e2");
 
     pub fn from_code(code: &str) -> Option<[u8; 16]> {
         match code {
@@ -0,0 +1,155 @@
+//! Codec benchmarks.
+//!
+//! ```text
+//! cargo bench -p grc-20 --bench codec -- --save-baseline main
+//! cargo bench -p grc-20 --bench codec -- --baseline main
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use grc_20::codec::{decode_value, encode_value, Reader, Writer};
+use grc_20::{
+    decode_edit, decompress, encode_edit_compressed, encode_edit_with_context, encode_edit_with_options,
+    DecimalMantissa, DictionaryBuilder, Edit, EditBuilder, EmbeddingSubType, EncodeContext, EncodeOptions, Id,
+    Value,
+};
+
+const NAME: Id = [10u8; 16];
+const POPULATION: Id = [11u8; 16];
+const LOCATION: Id = [12u8; 16];
+const FOUNDED: Id = [13u8; 16];
+const IN_COUNTRY: Id = [20u8; 16];
+const ENGLISH: Id = [30u8; 16];
+const GERMAN: Id = [31u8; 16];
+
+fn entity_id(prefix: u8, i: u32) -> Id {
+    let mut id = [prefix; 16];
+    id[12..].copy_from_slice(&i.to_be_bytes());
+    id
+}
+
+/// A city-like edit: `entities` entities with a few values each and one
+/// relation to one of 50 countries.
+fn make_edit(entities: u32) -> Edit<'static> {
+    let mut builder = EditBuilder::new([1u8; 16]).name("bench").author([2u8; 16]).created_at(1_700_000_000_000_000);
+    for i in 0..entities {
+        let id = entity_id(3, i);
+        builder = builder
+            .create_entity(id, |e| {
+                e.text(NAME, format!("City {i}"), Some(ENGLISH))
+                    .text(NAME, format!("Stadt {i}"), Some(GERMAN))
+                    .int64(POPULATION, i64::from(i) * 1_000, None)
+                    .point(LOCATION, f64::from(i % 360) - 180.0, f64::from(i % 180) - 90.0, None)
+                    .date(FOUNDED, "1850-06-01")
+            })
+            .create_relation(|r| r.id(entity_id(4, i)).relation_type(IN_COUNTRY).from(id).to(entity_id(5, i % 50)));
+    }
+    builder.build()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for entities in [100, 10_000] {
+        let edit = make_edit(entities);
+        let size = encode_edit_with_options(&edit, EncodeOptions::new()).unwrap().len();
+        group.throughput(Throughput::Bytes(size as u64));
+        let modes = [
+            ("fast", EncodeOptions::new()),
+            ("canonical", EncodeOptions::canonical()),
+            ("columnar", EncodeOptions::columnar()),
+        ];
+        for (name, options) in modes {
+            group.bench_with_input(BenchmarkId::new(name, entities), &edit, |b, edit| {
+                b.iter(|| encode_edit_with_options(black_box(edit), options).unwrap())
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("fast_context", entities), &edit, |b, edit| {
+            let mut context = EncodeContext::new();
+            b.iter(|| encode_edit_with_context(black_box(edit), &mut context).unwrap().len())
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for entities in [100, 10_000] {
+        let edit = make_edit(entities);
+        let bytes = encode_edit_with_options(&edit, EncodeOptions::new()).unwrap();
+        let columnar = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("borrowed", entities), &bytes, |b, bytes| {
+            b.iter(|| decode_edit(black_box(bytes)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("columnar", entities), &columnar, |b, bytes| {
+            b.iter(|| decode_edit(black_box(bytes)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_compressed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compressed");
+    let edit = make_edit(10_000);
+    let uncompressed = encode_edit_with_options(&edit, EncodeOptions::new()).unwrap();
+    let compressed = encode_edit_compressed(&edit, 3).unwrap();
+    group.throughput(Throughput::Bytes(uncompressed.len() as u64));
+    group.bench_function("encode_zstd3", |b| b.iter(|| encode_edit_compressed(black_box(&edit), 3).unwrap()));
+    group.bench_function("decompress", |b| b.iter(|| decompress(black_box(&compressed)).unwrap()));
+    group.bench_function("decompress_then_decode", |b| {
+        b.iter(|| {
+            let bytes = decompress(black_box(&compressed)).unwrap();
+            decode_edit(&bytes).unwrap().ops.len()
+        })
+    });
+    // Decoding compressed input directly yields an owned edit
+    group.bench_function("decode_owned", |b| b.iter(|| decode_edit(black_box(&compressed)).unwrap()));
+    group.finish();
+}
+
+fn bench_values(c: &mut Criterion) {
+    let values: Vec<(&str, Value<'static>)> = vec![
+        ("bool", Value::Bool(true)),
+        ("int64", Value::Int64 { value: -123_456_789, unit: None }),
+        ("float64", Value::Float64 { value: 1.5, unit: None }),
+        ("decimal", Value::Decimal { exponent: -2, mantissa: DecimalMantissa::I64(12345), unit: None }),
+        ("text", Value::Text { value: "The quick brown fox jumps over the lazy dog".into(), language: None }),
+        ("bytes", Value::Bytes(vec![7u8; 64].into())),
+        ("date", Value::Date("2024-01-02".into())),
+        ("time", Value::Time("10:00:00Z".into())),
+        ("datetime", Value::Datetime("2024-01-02T10:00:00Z".into())),
+        ("schedule", Value::Schedule("BEGIN:VEVENT\r\nDTSTART:20240102T100000Z\r\nEND:VEVENT".into())),
+        ("point", Value::Point { lon: 13.4, lat: 52.5, alt: None }),
+        ("rect", Value::Rect { min_lat: 52.3, min_lon: 13.0, max_lat: 52.7, max_lon: 13.8 }),
+        (
+            "embedding",
+            Value::Embedding { sub_type: EmbeddingSubType::Float32, dims: 384, data: vec![0u8; 384 * 4].into() },
+        ),
+    ];
+
+    let mut group = c.benchmark_group("value");
+    for (name, value) in &values {
+        group.bench_with_input(BenchmarkId::new("encode", name), value, |b, value| {
+            let mut dicts = DictionaryBuilder::new();
+            b.iter(|| {
+                let mut writer = Writer::new();
+                encode_value(&mut writer, black_box(value), &mut dicts).unwrap();
+                writer
+            })
+        });
+
+        let mut dicts = DictionaryBuilder::new();
+        let mut writer = Writer::new();
+        encode_value(&mut writer, value, &mut dicts).unwrap();
+        let bytes = writer.into_bytes();
+        let wire = dicts.build();
+        group.bench_with_input(BenchmarkId::new("decode", name), &bytes, |b, bytes| {
+            b.iter(|| decode_value(&mut Reader::new(black_box(bytes)), value.data_type(), &wire).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode, bench_compressed, bench_values);
+criterion_main!(benches);
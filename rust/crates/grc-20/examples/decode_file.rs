@@ -81,6 +81,7 @@ fn main() {
     let mut restore_relation_count = 0;
 
     let mut create_value_ref_count = 0;
+    let mut unknown_count = 0;
     for op in &edit.ops {
         match op {
             Op::CreateEntity(_) => create_entity_count += 1,
@@ -92,6 +93,7 @@ fn main() {
             Op::DeleteRelation(_) => delete_relation_count += 1,
             Op::RestoreRelation(_) => restore_relation_count += 1,
             Op::CreateValueRef(_) => create_value_ref_count += 1,
+            Op::Unknown(_) => unknown_count += 1,
         }
     }
     println!("  CreateEntity: {}", create_entity_count);
@@ -103,6 +105,9 @@ fn main() {
     println!("  DeleteRelation: {}", delete_relation_count);
     println!("  RestoreRelation: {}", restore_relation_count);
     println!("  CreateValueRef: {}", create_value_ref_count);
+    if unknown_count > 0 {
+        println!("  Unknown (extension): {}", unknown_count);
+    }
 
     // Show first few operations in detail
     println!("\n=== First 20 Operations (detail) ===");
@@ -0,0 +1,104 @@
+//! Writes the seed corpus for the `fuzz/` targets (committed in `fuzz/seeds/`).
+//!
+//! The seeds are encodings of one edit covering every op and value type, in
+//! each wire format the decoder accepts. Run after changing the codec:
+//!
+//! ```text
+//! cargo run --example fuzz_corpus --features lz4,brotli [-- <seed dir>]
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use grc_20::codec::{encode_value, Writer};
+use grc_20::{
+    encode_edit_compressed_with_codec, encode_edit_with_options, Codec, DecimalMantissa,
+    DictionaryBuilder, Edit, EditBuilder, EmbeddingSubType, EncodeOptions, Op, PropertyValue, UnknownOp,
+};
+
+fn seed_edit() -> Edit<'static> {
+    let language = [20u8; 16];
+    let unit = [21u8; 16];
+    let mut edit = EditBuilder::new([1u8; 16])
+        .name("seed")
+        .author([2u8; 16])
+        .parent([9u8; 16])
+        .created_at(1_700_000_000_000_000)
+        .create_entity([3u8; 16], |e| {
+            e.text([10u8; 16], "hello", None)
+                .text([10u8; 16], "hallo", Some(language))
+                .int64([11u8; 16], -5, Some(unit))
+                .float64([12u8; 16], 1.5, Some(unit))
+                .bool([13u8; 16], true)
+                .bytes([14u8; 16], vec![1u8, 2, 3])
+                .point([15u8; 16], 13.4, 52.5, Some(34.0))
+                .date([16u8; 16], "2024-01-02")
+                .time([17u8; 16], "10:00:00Z")
+                .datetime([18u8; 16], "2024-01-02T10:00:00+01:00")
+                .schedule([19u8; 16], "BEGIN:VEVENT\r\nDTSTART:20240102T100000Z\r\nEND:VEVENT")
+                .decimal([22u8; 16], -2, DecimalMantissa::I64(12345), Some(unit))
+                .decimal([23u8; 16], 0, DecimalMantissa::Big(vec![1u8, 0, 0, 0, 0, 0, 0, 0, 0].into()), None)
+                .embedding([24u8; 16], EmbeddingSubType::Float32, 2, vec![0u8; 8])
+                .embedding([25u8; 16], EmbeddingSubType::Binary, 3, vec![0b101u8])
+        })
+        .update_entity([3u8; 16], |u| u.set_int64([11u8; 16], 6, None).unset_language([10u8; 16], language))
+        .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]).position("a"))
+        .update_relation([7u8; 16], |r| r.set_position("b"))
+        .delete_relation([7u8; 16])
+        .restore_relation([7u8; 16])
+        .delete_entity([4u8; 16])
+        .restore_entity([4u8; 16])
+        .build();
+    edit.ops.push(Op::Unknown(UnknownOp { op_type: 0x80, bytes: vec![1u8, 2, 3].into() }));
+    edit
+}
+
+fn write(dir: &Path, name: &str, bytes: &[u8]) {
+    fs::create_dir_all(dir).expect("create corpus dir");
+    fs::write(dir.join(name), bytes).expect("write seed");
+}
+
+fn main() {
+    let root = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../fuzz/seeds"));
+    let edit = seed_edit();
+
+    let layouts = [
+        ("row", EncodeOptions::new()),
+        ("canonical", EncodeOptions::canonical()),
+        ("columnar", EncodeOptions::columnar()),
+        ("interned", EncodeOptions::intern_values(1)),
+    ];
+    for (name, options) in layouts {
+        let bytes = encode_edit_with_options(&edit, options).expect("encode seed edit");
+        write(&root.join("decode_edit"), name, &bytes);
+        write(&root.join("lazy_edit"), name, &bytes);
+        write(&root.join("peek_header"), name, &bytes);
+    }
+    for codec in [Codec::Zstd, Codec::Lz4, Codec::Brotli] {
+        if !codec.is_enabled() {
+            continue;
+        }
+        let bytes = encode_edit_compressed_with_codec(&edit, codec, 3).expect("compress seed edit");
+        write(&root.join("decode_edit"), codec.name(), &bytes);
+        write(&root.join("decompress"), codec.name(), &bytes);
+        write(&root.join("peek_header"), codec.name(), &bytes);
+    }
+
+    // decode_value input: data type byte, then the encoded value
+    let values = edit.ops.iter().filter_map(|op| match op {
+        Op::CreateEntity(ce) => Some(&ce.values),
+        _ => None,
+    });
+    for (i, PropertyValue { value, .. }) in values.flatten().enumerate() {
+        let mut dicts = DictionaryBuilder::new();
+        let mut writer = Writer::new();
+        writer.write_byte(value.data_type() as u8);
+        encode_value(&mut writer, value, &mut dicts).expect("encode seed value");
+        write(&root.join("decode_value"), &format!("{i:02}-{:?}", value.data_type()), writer.as_bytes());
+    }
+
+    println!("wrote seeds to {}", root.display());
+}
@@ -0,0 +1,261 @@
+//! Interactive shell for inspecting and correcting GRC-20 data.
+//!
+//! Loads edits and snapshots into a materialized graph, answers queries,
+//! and stages corrections written in the patch format (see `grc_20::patch`)
+//! until they are written out as an edit:
+//!
+//! ```text
+//! cargo run --example shell [-- <edit.g20 | space.snap>...]
+//!
+//! grc20> find type place text name "Berlin"
+//! grc20> show 8f3a9c2e4b1d4e6f9a0b1c2d3e4f5a6b
+//! grc20> patch entity 8f3a9c2e4b1d4e6f9a0b1c2d3e4f5a6b { set name = "Berlin"; }
+//! grc20> write fix.g20
+//! ```
+//!
+//! To complete a name or command, end the line with Tab and press Enter.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use grc_20::genesis::{properties, GenesisNames};
+use grc_20::names::Named;
+use grc_20::patch::PatchParser;
+use grc_20::query::Query;
+use grc_20::snapshot::{read_snapshot, write_snapshot};
+use grc_20::state::{Graph, ValueKey};
+use grc_20::{decode_edit, derived_uuid, encode_edit, DisplayId, EditBuilder, Id, Op};
+
+const COMMANDS: &[&str] =
+    &["help", "load", "show", "find", "stats", "names", "alias", "patch", "staged", "write", "save", "quit"];
+
+const HELP: &str = "\
+load <file>...          replay edits or load a GRC2SNAP snapshot
+show <id>               values and relations of an object
+find <filter>...        live entities matching all filters:
+                          type <id>, text <property> <text>, int <property> <n>,
+                          has <property>, rel <relation type>, limit <n>
+stats                   object counts
+names [prefix]          known names
+alias <name> <id>       name an ID
+patch <statements>      stage and apply patch statements
+staged                  list staged ops
+write <file>            write staged ops as an edit and clear them
+save <file>             write a snapshot of the graph
+quit";
+
+struct Shell {
+    graph: Graph,
+    names: PatchParser,
+    staged: Vec<Op<'static>>,
+}
+
+impl Shell {
+    /// Runs one command line, returning false to quit.
+    fn run(&mut self, line: &str) -> Result<bool, String> {
+        if let Some(line) = line.strip_suffix('\t') {
+            self.complete(line);
+            return Ok(true);
+        }
+        let words = split(line);
+        let Some((command, args)) = words.split_first() else {
+            return Ok(true);
+        };
+        match command.as_str() {
+            "help" => println!("{HELP}"),
+            "load" => {
+                for path in args {
+                    self.load(path)?;
+                }
+            }
+            "show" => self.show(self.id(arg(args, 0)?)?),
+            "find" => self.find(args)?,
+            "stats" => {
+                println!("{} entities, {} relations", self.graph.entities().count(), self.graph.relations().count());
+            }
+            "names" => {
+                let prefix = args.first().map_or("", String::as_str);
+                for (name, id) in self.names.names().into_iter().filter(|(name, _)| name.starts_with(prefix)) {
+                    println!("{name:<20} {}", DisplayId(id));
+                }
+            }
+            "alias" => {
+                let id = self.id(arg(args, 1)?)?;
+                self.names = std::mem::take(&mut self.names).name(arg(args, 0)?, id);
+            }
+            "patch" => {
+                let src = line.trim_start().strip_prefix("patch").unwrap_or_default();
+                let ops = self.names.parse(src).map_err(|e| e.to_string())?;
+                self.graph.apply_edit(&EditBuilder::new([0u8; 16]).ops(ops.clone()).build());
+                println!("staged {} ops", ops.len());
+                self.staged.extend(ops);
+            }
+            "staged" => {
+                let names = (&self.graph, GenesisNames);
+                for op in &self.staged {
+                    println!("{}", Named::new(op, &names));
+                }
+            }
+            "write" => {
+                let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros());
+                let id = derived_uuid(format!("grc20:shell:{created_at}").as_bytes());
+                let edit = EditBuilder::new(id).name("Corrections").created_now().ops(self.staged.clone()).build();
+                let bytes = encode_edit(&edit).map_err(|e| e.to_string())?;
+                fs::write(arg(args, 0)?, &bytes).map_err(|e| e.to_string())?;
+                println!("wrote edit {} ({} ops, {} bytes)", DisplayId(id), edit.ops.len(), bytes.len());
+                self.staged.clear();
+            }
+            "save" => {
+                let bytes = write_snapshot(&self.graph).map_err(|e| e.to_string())?;
+                fs::write(arg(args, 0)?, bytes).map_err(|e| e.to_string())?;
+            }
+            "quit" | "exit" => return Ok(false),
+            other => return Err(format!("unknown command {other:?}, try `help`")),
+        }
+        Ok(true)
+    }
+
+    fn load(&mut self, path: &str) -> Result<(), String> {
+        let bytes = fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+        if bytes.starts_with(grc_20::limits::MAGIC_SNAPSHOT) {
+            self.graph = read_snapshot(&bytes).map_err(|e| format!("{path}: {e}"))?;
+            println!("{path}: snapshot");
+        } else {
+            let edit = decode_edit(&bytes).map_err(|e| format!("{path}: {e}"))?;
+            self.graph.apply_edit(&edit);
+            println!("{path}: edit {:?} ({} ops)", edit.name, edit.ops.len());
+        }
+        Ok(())
+    }
+
+    fn show(&self, id: Id) {
+        println!("{}", self.label(&id));
+        if let Some(entity) = self.graph.entity(&id) {
+            for (key, value) in &entity.values {
+                let language = key.language.map(|l| format!(" @{}", DisplayId(l))).unwrap_or_default();
+                println!("  {}{language} = {value}", self.label(&key.property));
+            }
+        }
+        if let Some(relation) = self.graph.relation(&id) {
+            let (from, to) = (self.label(&relation.from), self.label(&relation.to));
+            println!("  {from} -[{}]-> {to}", self.label(&relation.relation_type));
+        }
+        for (_, relation) in self.graph.outgoing(&id, None) {
+            println!("  -[{}]-> {}", self.label(&relation.relation_type), self.label(&relation.to));
+        }
+        for (_, relation) in self.graph.incoming(&id, None) {
+            println!("  <-[{}]- {}", self.label(&relation.relation_type), self.label(&relation.from));
+        }
+    }
+
+    fn find(&self, args: &[String]) -> Result<(), String> {
+        let mut query = Query::new(&self.graph);
+        let mut args = args.iter();
+        while let Some(filter) = args.next() {
+            let mut next = || args.next().ok_or_else(|| format!("`{filter}` needs an argument"));
+            query = match filter.as_str() {
+                "type" => query.of_type(self.id(next()?)?),
+                "text" => {
+                    let property = self.id(next()?)?;
+                    query.where_text(property, next()?)
+                }
+                "int" => {
+                    let property = self.id(next()?)?;
+                    query.where_int64(property, next()?.parse().map_err(|e| format!("{e}"))?)
+                }
+                "has" => query.has(self.id(next()?)?),
+                "rel" => query.with_relation(self.id(next()?)?),
+                "limit" => query.limit(next()?.parse().map_err(|e| format!("{e}"))?),
+                other => return Err(format!("unknown filter {other:?}")),
+            };
+        }
+        let matches = query.run();
+        for m in &matches {
+            println!("{}", self.label(&m.id));
+        }
+        println!("{} matches", matches.len());
+        Ok(())
+    }
+
+    fn complete(&self, line: &str) {
+        let prefix = line.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let names = self.names.names();
+        let candidates: Vec<&str> = COMMANDS
+            .iter()
+            .copied()
+            .filter(|_| !line.trim_start().contains(' '))
+            .chain(names.iter().map(|(name, _)| *name))
+            .filter(|c| c.starts_with(prefix))
+            .collect();
+        println!("{}", candidates.join(" "));
+    }
+
+    fn id(&self, word: &str) -> Result<Id, String> {
+        self.names.resolve(word).ok_or_else(|| format!("unknown name {word:?}"))
+    }
+
+    /// The ID followed by its registered name or `name` value, if any.
+    fn label(&self, id: &Id) -> String {
+        let names = self.names.names();
+        match names.iter().find(|(_, named)| named == id) {
+            Some((name, _)) => format!("{} ({name})", DisplayId(*id)),
+            None => match self.graph.value(id, &ValueKey::new(properties::name())) {
+                Some(name) => format!("{} ({name})", DisplayId(*id)),
+                None => DisplayId(*id).to_string(),
+            },
+        }
+    }
+}
+
+fn arg(args: &[String], i: usize) -> Result<&str, String> {
+    args.get(i).map(String::as_str).ok_or_else(|| "missing argument, try `help`".to_string())
+}
+
+/// Splits a line into words, keeping double-quoted strings together.
+fn split(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let word: String = chars.by_ref().take_while(|c| *c != '"').collect();
+            words.push(word);
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn main() {
+    let mut shell = Shell { graph: Graph::new(), names: PatchParser::new(), staged: Vec::new() };
+    for path in std::env::args().skip(1) {
+        if let Err(e) = shell.load(&path) {
+            eprintln!("error: {e}");
+        }
+    }
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("grc20> ");
+        let _ = io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        match shell.run(&line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
+    if !shell.staged.is_empty() {
+        eprintln!("warning: {} staged ops were not written", shell.staged.len());
+    }
+}
@@ -0,0 +1,580 @@
+//! Heuristic checks and statistics over edits.
+//!
+//! [`id_report`] flags IDs that are legal on the wire but undermine the
+//! uniqueness assumptions of other clients: the nil UUID, IDs that are not
+//! RFC 4122 UUIDs, entities created twice, and runs of packed IDs that
+//! differ only in a counter (such as `prefix || 0... || u32`).
+//!
+//! ```ignore
+//! let report = analysis::id_report(&edit);
+//! for issue in &report.issues {
+//!     eprintln!("{:?}", issue);
+//! }
+//! ```
+//!
+//! [`PropertyStats`] accumulates per-property usage across many edits, for
+//! schema reviews of large spaces:
+//!
+//! ```ignore
+//! let mut stats = PropertyStats::new();
+//! for edit in &edits {
+//!     stats.add_edit(edit);
+//! }
+//! std::fs::write("properties.json", stats.to_json())?;
+//! ```
+//!
+//! [`find_duplicates`] proposes entity pairs to merge, for spaces built
+//! from several imports:
+//!
+//! ```ignore
+//! let config = DedupConfig::new().key(properties::name()).match_types();
+//! for pair in analysis::find_duplicates(&graph, &config) {
+//!     review.push((pair.a, pair.b));
+//! }
+//! ```
+
+use crate::collections::{FxHashMap, FxHashSet};
+use crate::model::{format_id, DataType, Edit, Id, Op, PropertyValue, Value, NIL_ID};
+use crate::prelude::*;
+use crate::state::{Entity, Graph};
+
+// =============================================================================
+// ID REPORT
+// =============================================================================
+
+/// IDs closer than this (as 128-bit numbers) are considered packed. Random
+/// or derived IDs in an edit of a million objects are typically 2^80 or more
+/// apart.
+const PACKED_GAP: u128 = 1 << 48;
+
+/// A suspicious ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdIssue {
+    /// The nil UUID is used where an object ID is expected.
+    Nil { op_index: usize },
+    /// The ID does not have the RFC 4122 variant and a version from 1 to 8.
+    NotUuid { id: Id, op_index: usize },
+    /// More than one CreateEntity op creates the same entity.
+    DuplicateEntity { id: Id, first_op: usize, op_index: usize },
+    /// A run of IDs that are close together as numbers, suggesting a
+    /// counter packed into the ID rather than random or derived IDs.
+    Packed { first: Id, last: Id, count: usize },
+}
+
+/// The result of [`id_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdReport {
+    /// Number of distinct IDs checked.
+    pub ids: usize,
+    /// Issues in op order, followed by packed runs in ID order.
+    pub issues: Vec<IdIssue>,
+}
+
+impl IdReport {
+    /// Returns true if no issues were found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Checks the object, endpoint, relation type, and property IDs of an edit.
+///
+/// Each distinct ID is reported at most once per issue kind, at the first op
+/// using it.
+pub fn id_report(edit: &Edit) -> IdReport {
+    let mut report = IdReport::default();
+    let mut seen: FxHashMap<Id, usize> = FxHashMap::default();
+    let mut created: FxHashMap<Id, usize> = FxHashMap::default();
+
+    for (op_index, op) in edit.ops.iter().enumerate() {
+        if let Op::CreateEntity(ce) = op {
+            if let Some(&first_op) = created.get(&ce.id) {
+                report.issues.push(IdIssue::DuplicateEntity { id: ce.id, first_op, op_index });
+            } else {
+                created.insert(ce.id, op_index);
+            }
+        }
+
+        for id in op_ids(op) {
+            if seen.contains_key(&id) {
+                continue;
+            }
+            seen.insert(id, op_index);
+            if id == NIL_ID {
+                report.issues.push(IdIssue::Nil { op_index });
+            } else if !is_uuid(&id) {
+                report.issues.push(IdIssue::NotUuid { id, op_index });
+            }
+        }
+    }
+
+    report.ids = seen.len();
+    let mut ids: Vec<u128> = seen.keys().filter(|&&id| id != NIL_ID).map(|id| u128::from_be_bytes(*id)).collect();
+    ids.sort_unstable();
+    let mut run_start = 0;
+    for i in 1..=ids.len() {
+        if i < ids.len() && ids[i] - ids[i - 1] < PACKED_GAP {
+            continue;
+        }
+        if i - run_start > 1 {
+            report.issues.push(IdIssue::Packed {
+                first: ids[run_start].to_be_bytes(),
+                last: ids[i - 1].to_be_bytes(),
+                count: i - run_start,
+            });
+        }
+        run_start = i;
+    }
+    report
+}
+
+/// The IDs an op refers to, other than languages, units, spaces, versions,
+/// and contexts.
+fn op_ids<'a>(op: &'a Op<'_>) -> Box<dyn Iterator<Item = Id> + 'a> {
+    match op {
+        Op::CreateEntity(ce) => Box::new(core::iter::once(ce.id).chain(ce.values.iter().map(|pv| pv.property))),
+        Op::UpdateEntity(ue) => Box::new(
+            core::iter::once(ue.id)
+                .chain(ue.set_properties.iter().map(|pv| pv.property))
+                .chain(ue.unset_values.iter().map(|u| u.property)),
+        ),
+        Op::CreateRelation(cr) => {
+            Box::new([cr.id, cr.from, cr.to, cr.relation_type].into_iter().chain(cr.entity))
+        }
+        Op::CreateValueRef(cvr) => Box::new([cvr.id, cvr.entity, cvr.property].into_iter()),
+        Op::Unknown(_) => Box::new(core::iter::empty()),
+        _ => Box::new(core::iter::once(op.id())),
+    }
+}
+
+/// Returns true if `id` has the RFC 4122 variant and a version from 1 to 8.
+fn is_uuid(id: &Id) -> bool {
+    matches!(id[6] >> 4, 1..=8) && id[8] & 0xC0 == 0x80
+}
+
+// =============================================================================
+// PROPERTY STATISTICS
+// =============================================================================
+
+/// How one property is used, see [`PropertyStats`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertyUsage {
+    /// Values set, by CreateEntity or UpdateEntity.
+    pub values: u64,
+    /// UpdateEntity unsets.
+    pub unsets: u64,
+    /// Values set, per data type.
+    pub data_types: FxHashMap<DataType, u64>,
+    /// Distinct languages of TEXT values (not counting untagged values).
+    pub languages: FxHashSet<Id>,
+    /// Number of TEXT values.
+    pub text_values: u64,
+    /// Total UTF-8 length of TEXT values.
+    pub text_bytes: u64,
+}
+
+impl PropertyUsage {
+    /// Returns the mean UTF-8 length of the TEXT values, if there are any.
+    pub fn average_text_len(&self) -> Option<f64> {
+        (self.text_values > 0).then(|| self.text_bytes as f64 / self.text_values as f64)
+    }
+
+    fn add_value(&mut self, value: &Value) {
+        self.values += 1;
+        *self.data_types.entry(value.data_type()).or_default() += 1;
+        if let Value::Text { value, language } = value {
+            self.text_values += 1;
+            self.text_bytes += value.len() as u64;
+            if let Some(language) = language {
+                self.languages.insert(*language);
+            }
+        }
+    }
+}
+
+/// Per-property usage accumulated over many edits.
+///
+/// A property with values of more than one data type, or unexpected
+/// languages, is a candidate for cleanup in a schema review.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyStats {
+    edits: u64,
+    properties: FxHashMap<Id, PropertyUsage>,
+}
+
+impl PropertyStats {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the values set and unset by an edit.
+    pub fn add_edit(&mut self, edit: &Edit) {
+        self.edits += 1;
+        for op in &edit.ops {
+            match op {
+                Op::CreateEntity(ce) => self.add_values(&ce.values),
+                Op::UpdateEntity(ue) => {
+                    self.add_values(&ue.set_properties);
+                    for unset in &ue.unset_values {
+                        self.properties.entry(unset.property).or_default().unsets += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_values(&mut self, values: &[PropertyValue]) {
+        for pv in values {
+            self.properties.entry(pv.property).or_default().add_value(&pv.value);
+        }
+    }
+
+    /// Returns the number of edits added.
+    pub fn edits(&self) -> u64 {
+        self.edits
+    }
+
+    /// Returns the usage of a property, if it was seen.
+    pub fn get(&self, property: &Id) -> Option<&PropertyUsage> {
+        self.properties.get(property)
+    }
+
+    /// Iterates over the properties seen, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Id, &PropertyUsage)> {
+        self.properties.iter()
+    }
+
+    /// Returns the number of properties seen.
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    /// Returns true if no property was seen.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Exports the statistics as JSON, with properties sorted by ID:
+    ///
+    /// ```text
+    /// {"edits":2,"properties":[{"id":"...","values":3,"unsets":0,
+    ///   "data_types":{"Text":3},"languages":1,"average_text_len":4.5}]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut properties: Vec<_> = self.properties.iter().collect();
+        properties.sort_unstable_by_key(|(id, _)| **id);
+        let entries: Vec<String> = properties
+            .into_iter()
+            .map(|(id, usage)| {
+                let data_types: Vec<String> = DataType::ALL
+                    .iter()
+                    .filter_map(|t| usage.data_types.get(t).map(|n| format!("\"{}\":{}", t.name(), n)))
+                    .collect();
+                let average = match usage.average_text_len() {
+                    Some(average) => format!("{}", average),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "{{\"id\":\"{}\",\"values\":{},\"unsets\":{},\"data_types\":{{{}}},\"languages\":{},\"average_text_len\":{}}}",
+                    format_id(id),
+                    usage.values,
+                    usage.unsets,
+                    data_types.join(","),
+                    usage.languages.len(),
+                    average
+                )
+            })
+            .collect();
+        format!("{{\"edits\":{},\"properties\":[{}]}}", self.edits, entries.join(","))
+    }
+}
+
+// =============================================================================
+// DUPLICATE DETECTION
+// =============================================================================
+
+/// Configuration for [`find_duplicates`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DedupConfig {
+    /// TEXT properties whose normalized values must all be equal. Entities
+    /// missing one of them are not compared.
+    pub key_properties: Vec<Id>,
+    /// Also require the same set of types.
+    pub match_types: bool,
+    /// EMBEDDING property and minimum cosine similarity a candidate pair
+    /// must reach when both entities have an embedding.
+    #[cfg(feature = "std")]
+    pub embedding: Option<(Id, f32)>,
+}
+
+impl DedupConfig {
+    /// Creates an empty configuration; add at least one key property.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key property.
+    pub fn key(mut self, property: Id) -> Self {
+        self.key_properties.push(property);
+        self
+    }
+
+    /// Requires candidates to have the same types.
+    pub fn match_types(mut self) -> Self {
+        self.match_types = true;
+        self
+    }
+
+    /// Requires candidates that both have an embedding of `property` to
+    /// reach `min_similarity` (cosine).
+    #[cfg(feature = "std")]
+    pub fn embedding(mut self, property: Id, min_similarity: f32) -> Self {
+        self.embedding = Some((property, min_similarity));
+        self
+    }
+}
+
+/// Two live entities that are likely the same, with `a < b`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DuplicatePair {
+    pub a: Id,
+    pub b: Id,
+    /// Cosine similarity of their embeddings, when configured and both
+    /// have one.
+    pub similarity: Option<f32>,
+}
+
+/// Finds candidate duplicate entities in a graph.
+///
+/// Entities are bucketed by the normalized values of the key properties
+/// (lowercased, with punctuation and whitespace runs collapsed), and by
+/// their types with [`DedupConfig::match_types`]. Every pair in a bucket is
+/// a candidate, unless an embedding threshold rules it out. Pairs are
+/// sorted by `(a, b)`.
+pub fn find_duplicates(graph: &Graph, config: &DedupConfig) -> Vec<DuplicatePair> {
+    if config.key_properties.is_empty() {
+        return Vec::new();
+    }
+    let types_relation = graph.types_relation();
+    let mut buckets: FxHashMap<Vec<String>, Vec<Id>> = FxHashMap::default();
+    for (id, entity) in graph.entities() {
+        let Some(mut key) = dedup_key(entity, &config.key_properties) else {
+            continue;
+        };
+        if config.match_types {
+            let mut types: Vec<Id> = graph.outgoing(id, Some(types_relation)).iter().map(|(_, r)| r.to).collect();
+            types.sort_unstable();
+            types.dedup();
+            key.extend(types.iter().map(format_id));
+        }
+        buckets.entry(key).or_default().push(*id);
+    }
+
+    let mut pairs = Vec::new();
+    for ids in buckets.values_mut() {
+        ids.sort_unstable();
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                if let Some(similarity) = pair_similarity(graph, config, a, b) {
+                    pairs.push(DuplicatePair { a: *a, b: *b, similarity });
+                }
+            }
+        }
+    }
+    pairs.sort_unstable_by_key(|pair| (pair.a, pair.b));
+    pairs
+}
+
+/// Returns the normalized key values, or `None` if one is missing.
+fn dedup_key(entity: &Entity, properties: &[Id]) -> Option<Vec<String>> {
+    properties
+        .iter()
+        .map(|property| {
+            entity.values_of(*property).find_map(|(_, value)| match value {
+                Value::Text { value, .. } => Some(normalize_key(value)),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+fn normalize_key(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    out
+}
+
+/// Returns `Some(similarity)` if the pair passes the embedding check.
+#[cfg(feature = "std")]
+fn pair_similarity(graph: &Graph, config: &DedupConfig, a: &Id, b: &Id) -> Option<Option<f32>> {
+    let Some((property, min_similarity)) = config.embedding else {
+        return Some(None);
+    };
+    let embedding = |id: &Id| match graph.value(id, &crate::state::ValueKey::new(property)) {
+        Some(Value::Embedding { sub_type, dims, data }) => {
+            Some(crate::state::embedding_to_f32(*sub_type, *dims, data))
+        }
+        _ => None,
+    };
+    match (embedding(a), embedding(b)) {
+        (Some(x), Some(y)) if x.len() == y.len() => {
+            let similarity = crate::state::cosine(&x, &y);
+            (similarity >= min_similarity).then_some(Some(similarity))
+        }
+        _ => Some(None),
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn pair_similarity(_: &Graph, _: &DedupConfig, _: &Id, _: &Id) -> Option<Option<f32>> {
+    Some(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{derived_uuid, EditBuilder};
+
+    #[test]
+    fn test_id_report() {
+        let name = derived_uuid(b"name");
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity(derived_uuid(b"a"), |e| e.text(name, "A", None))
+            .create_entity(derived_uuid(b"a"), |e| e.text(name, "A again", None))
+            .create_entity(NIL_ID, |e| e)
+            .create_entity([7u8; 16], |e| e)
+            .build();
+        let report = id_report(&edit);
+        assert_eq!(report.ids, 4);
+        assert_eq!(
+            report.issues,
+            vec![
+                IdIssue::DuplicateEntity { id: derived_uuid(b"a"), first_op: 0, op_index: 1 },
+                IdIssue::Nil { op_index: 2 },
+                IdIssue::NotUuid { id: [7u8; 16], op_index: 3 },
+            ]
+        );
+
+        let clean = EditBuilder::new([1u8; 16])
+            .create_entity(derived_uuid(b"a"), |e| e.text(name, "A", None))
+            .create_relation(|r| r.id(derived_uuid(b"r")).from(derived_uuid(b"a")).to(derived_uuid(b"b")).relation_type(name))
+            .build();
+        assert!(id_report(&clean).is_clean());
+    }
+
+    #[test]
+    fn test_property_stats() {
+        let (name, size) = ([1u8; 16], [2u8; 16]);
+        let en = derived_uuid(b"en");
+        let edits = [
+            EditBuilder::new([10u8; 16])
+                .create_entity([3u8; 16], |e| e.text(name, "abcd", Some(en)).int64(size, 1, None))
+                .build(),
+            EditBuilder::new([11u8; 16])
+                .create_entity([4u8; 16], |e| e.text(name, "abcdef", None))
+                .update_entity([3u8; 16], |u| u.set_text(size, "big", None).unset_all(name))
+                .build(),
+        ];
+        let mut stats = PropertyStats::new();
+        for edit in &edits {
+            stats.add_edit(edit);
+        }
+        assert_eq!((stats.edits(), stats.len()), (2, 2));
+
+        let usage = stats.get(&name).unwrap();
+        assert_eq!((usage.values, usage.unsets, usage.languages.len()), (2, 1, 1));
+        assert_eq!(usage.average_text_len(), Some(5.0));
+        let usage = stats.get(&size).unwrap();
+        assert_eq!(usage.data_types.get(&DataType::Int64), Some(&1));
+        assert_eq!(usage.data_types.get(&DataType::Text), Some(&1));
+
+        assert_eq!(
+            stats.to_json(),
+            format!(
+                concat!(
+                    r#"{{"edits":2,"properties":["#,
+                    r#"{{"id":"{}","values":2,"unsets":1,"data_types":{{"Text":2}},"languages":1,"average_text_len":5}},"#,
+                    r#"{{"id":"{}","values":2,"unsets":0,"data_types":{{"Int64":1,"Text":1}},"languages":0,"average_text_len":3}}]}}"#,
+                ),
+                format_id(&name),
+                format_id(&size)
+            )
+        );
+        assert_eq!(PropertyStats::new().to_json(), r#"{"edits":0,"properties":[]}"#);
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let (name, types, city) = ([1u8; 16], crate::model::id::types_relation_type(), [2u8; 16]);
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([9u8; 16])
+                .create_entity([10u8; 16], |e| e.text(name, "New York", None))
+                .create_entity([11u8; 16], |e| e.text(name, "new  york!", None))
+                .create_entity([12u8; 16], |e| e.text(name, "NEW-YORK", None))
+                .create_entity([13u8; 16], |e| e.text(name, "Newark", None))
+                .create_empty_entity([14u8; 16])
+                .create_relation_simple([20u8; 16], [10u8; 16], city, types)
+                .create_relation_simple([21u8; 16], [11u8; 16], city, types)
+                .build(),
+        );
+        let ids = |pairs: Vec<DuplicatePair>| pairs.iter().map(|p| (p.a[0], p.b[0])).collect::<Vec<_>>();
+
+        let config = DedupConfig::new().key(name);
+        assert_eq!(ids(find_duplicates(&graph, &config)), vec![(10, 11), (10, 12), (11, 12)]);
+        assert_eq!(ids(find_duplicates(&graph, &config.clone().match_types())), vec![(10, 11)]);
+        assert!(find_duplicates(&graph, &DedupConfig::new()).is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_find_duplicates_embedding() {
+        use crate::model::EmbeddingSubType;
+
+        let (name, vector) = ([1u8; 16], [3u8; 16]);
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([9u8; 16])
+                .create_entity([10u8; 16], |e| e.text(name, "Paris", None).embedding(vector, EmbeddingSubType::Int8, 2, vec![10, 0]))
+                .create_entity([11u8; 16], |e| e.text(name, "paris", None).embedding(vector, EmbeddingSubType::Int8, 2, vec![9, 1]))
+                .create_entity([12u8; 16], |e| e.text(name, "Paris", None).embedding(vector, EmbeddingSubType::Int8, 2, vec![0, 10]))
+                .build(),
+        );
+        let pairs = find_duplicates(&graph, &DedupConfig::new().key(name).embedding(vector, 0.9));
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].a, pairs[0].b), ([10u8; 16], [11u8; 16]));
+        assert!(pairs[0].similarity.unwrap() > 0.99);
+    }
+
+    #[test]
+    fn test_id_report_packed() {
+        // The bench scheme: prefix byte, counter in the last four bytes
+        let packed = |prefix: u8, n: u32| {
+            let mut id = [0u8; 16];
+            id[0] = prefix;
+            id[12..].copy_from_slice(&n.to_be_bytes());
+            id[6] = 0x80;
+            id[8] = 0x80;
+            id
+        };
+        let mut builder = EditBuilder::new([1u8; 16]);
+        for n in 0..5 {
+            builder = builder.create_entity(packed(1, n), |e| e).create_entity(packed(2, n * 1000), |e| e);
+        }
+        let report = id_report(&builder.build());
+        assert_eq!(
+            report.issues,
+            vec![
+                IdIssue::Packed { first: packed(1, 0), last: packed(1, 4), count: 5 },
+                IdIssue::Packed { first: packed(2, 0), last: packed(2, 4000), count: 5 },
+            ]
+        );
+    }
+}
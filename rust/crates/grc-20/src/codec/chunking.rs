@@ -0,0 +1,331 @@
+//! Content-defined chunking of encoded edits.
+//!
+//! Large edits (bulk imports) are split into chunks with FastCDC, so that
+//! chunk boundaries follow the content rather than fixed offsets. Two
+//! encodings that share a long run of bytes, e.g. successive snapshots of
+//! the same import, share most chunks and a content-addressed store keeps
+//! them once.
+//!
+//! A [`Manifest`] lists the SHA-256 and length of every chunk plus the
+//! SHA-256 of the whole input; [`reassemble`] fetches the chunks by hash and
+//! verifies all three.
+//!
+//! Boundaries depend only on the bytes and [`ChunkingOptions`], so any
+//! implementation using the same gear table (SplitMix64 from seed 0, see
+//! `GEAR`) and sizes finds the same chunks.
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{ChunkError, DecodeError};
+use crate::limits::MAGIC_MANIFEST;
+use crate::prelude::*;
+
+/// A SHA-256 digest.
+pub type Hash = [u8; 32];
+
+/// Chunk size bounds for [`split`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingOptions {
+    /// No chunk except the last is smaller than this.
+    pub min_size: usize,
+    /// Target average chunk size; rounded down to a power of two.
+    pub avg_size: usize,
+    /// No chunk is larger than this.
+    pub max_size: usize,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self { min_size: 16 * 1024, avg_size: 64 * 1024, max_size: 256 * 1024 }
+    }
+}
+
+impl ChunkingOptions {
+    /// Creates the default options (16 KB min, 64 KB average, 256 KB max).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets all three sizes.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < min_size <= avg_size <= max_size` and
+    /// `avg_size >= 64`.
+    pub fn sizes(mut self, min_size: usize, avg_size: usize, max_size: usize) -> Self {
+        assert!(
+            0 < min_size && min_size <= avg_size && avg_size <= max_size && avg_size >= 64,
+            "invalid chunk sizes {min_size}/{avg_size}/{max_size}"
+        );
+        self.min_size = min_size;
+        self.avg_size = avg_size;
+        self.max_size = max_size;
+        self
+    }
+}
+
+/// Gear hash table: 256 outputs of SplitMix64 seeded with 0.
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut state = 0u64;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// A mask of the `bits` most significant bits.
+fn top_mask(bits: u32) -> u64 {
+    !(u64::MAX >> bits)
+}
+
+/// Returns the length of the next chunk of `data` (normalized chunking,
+/// level 1: a stricter mask before the average size, a looser one after).
+fn cut_point(data: &[u8], options: &ChunkingOptions) -> usize {
+    let n = data.len().min(options.max_size);
+    if n <= options.min_size {
+        return n;
+    }
+    let bits = usize::BITS - 1 - options.avg_size.leading_zeros();
+    let (mask_s, mask_l) = (top_mask(bits + 1), top_mask(bits - 1));
+    let normal = options.avg_size.min(n);
+
+    let mut fp = 0u64;
+    let mut i = options.min_size;
+    while i < normal {
+        fp = (fp << 1).wrapping_add(GEAR[data[i] as usize]);
+        if fp & mask_s == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    while i < n {
+        fp = (fp << 1).wrapping_add(GEAR[data[i] as usize]);
+        if fp & mask_l == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    n
+}
+
+/// A chunk's position in the manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkInfo {
+    pub hash: Hash,
+    pub len: usize,
+}
+
+/// The chunk list of a split input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// SHA-256 of the whole input.
+    pub content_hash: Hash,
+    /// Total input length.
+    pub total_len: usize,
+    /// Chunks in order.
+    pub chunks: Vec<ChunkInfo>,
+}
+
+/// Splits `input` into content-defined chunks.
+///
+/// Returns the manifest and the chunks, in order and borrowed from `input`.
+pub fn split<'a>(input: &'a [u8], options: &ChunkingOptions) -> (Manifest, Vec<&'a [u8]>) {
+    let mut chunks = Vec::new();
+    let mut infos = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let (chunk, tail) = rest.split_at(cut_point(rest, options));
+        infos.push(ChunkInfo { hash: Sha256::digest(chunk).into(), len: chunk.len() });
+        chunks.push(chunk);
+        rest = tail;
+    }
+    let manifest = Manifest { content_hash: Sha256::digest(input).into(), total_len: input.len(), chunks: infos };
+    (manifest, chunks)
+}
+
+/// Rebuilds the input from its chunks, fetched by hash with `fetch`.
+///
+/// Every chunk is checked against its hash and length, and the result
+/// against the content hash.
+pub fn reassemble<F, B>(manifest: &Manifest, mut fetch: F) -> Result<Vec<u8>, ChunkError>
+where
+    F: FnMut(&Hash) -> Option<B>,
+    B: AsRef<[u8]>,
+{
+    let mut out = Vec::with_capacity(manifest.total_len);
+    for (index, info) in manifest.chunks.iter().enumerate() {
+        let chunk = fetch(&info.hash).ok_or(ChunkError::MissingChunk { index, hash: info.hash })?;
+        let chunk = chunk.as_ref();
+        if chunk.len() != info.len {
+            return Err(ChunkError::LengthMismatch { index, expected: info.len, actual: chunk.len() });
+        }
+        if <Hash>::from(Sha256::digest(chunk)) != info.hash {
+            return Err(ChunkError::HashMismatch { index });
+        }
+        out.extend_from_slice(chunk);
+    }
+    if out.len() != manifest.total_len || <Hash>::from(Sha256::digest(&out)) != manifest.content_hash {
+        return Err(ChunkError::ContentHashMismatch);
+    }
+    Ok(out)
+}
+
+impl Manifest {
+    /// Encodes the manifest: `GRC2CM` magic, content hash, varint total
+    /// length, varint chunk count, then `hash (32 bytes) + varint length`
+    /// per chunk.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::with_capacity(MAGIC_MANIFEST.len() + 48 + self.chunks.len() * 36);
+        writer.write_bytes(MAGIC_MANIFEST);
+        writer.write_bytes(&self.content_hash);
+        writer.write_varint(self.total_len as u64);
+        writer.write_varint(self.chunks.len() as u64);
+        for chunk in &self.chunks {
+            writer.write_bytes(&chunk.hash);
+            writer.write_varint(chunk.len as u64);
+        }
+        writer.into_bytes()
+    }
+
+    /// Decodes a manifest written by [`Manifest::encode`].
+    ///
+    /// Chunk lengths must be non-zero and add up to the total length.
+    pub fn decode(input: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(input);
+        let magic = reader.read_bytes(MAGIC_MANIFEST.len(), "magic")?;
+        if magic != MAGIC_MANIFEST {
+            let mut found = [0u8; 4];
+            found.copy_from_slice(&magic[..4]);
+            return Err(DecodeError::InvalidMagic { found });
+        }
+        let mut content_hash = [0u8; 32];
+        content_hash.copy_from_slice(reader.read_bytes(32, "content_hash")?);
+        let total_len = reader.read_varint("total_len")?;
+        // Each chunk takes at least 33 bytes
+        let count = reader.read_varint("chunk_count")?;
+        let max = reader.remaining_len() / 33;
+        if count > max as u64 {
+            return Err(DecodeError::LengthExceedsLimit { field: "chunks", len: count as usize, max });
+        }
+        let mut chunks = Vec::with_capacity(count as usize);
+        let mut sum = 0u64;
+        for _ in 0..count {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(reader.read_bytes(32, "chunk_hash")?);
+            let len = reader.read_varint("chunk_len")?;
+            if len == 0 {
+                return Err(DecodeError::MalformedEncoding { context: "empty chunk in manifest" });
+            }
+            sum = sum.saturating_add(len);
+            chunks.push(ChunkInfo { hash, len: len as usize });
+        }
+        if sum != total_len || usize::try_from(total_len).is_err() {
+            return Err(DecodeError::MalformedEncoding { context: "chunk lengths do not add up to total length" });
+        }
+        if !reader.is_empty() {
+            return Err(DecodeError::MalformedEncoding { context: "trailing bytes after manifest" });
+        }
+        Ok(Manifest { content_hash, total_len: total_len as usize, chunks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::FxHashMap;
+
+    /// Deterministic pseudo-random bytes.
+    fn data(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 33) as u8
+            })
+            .collect()
+    }
+
+    fn small() -> ChunkingOptions {
+        ChunkingOptions::new().sizes(256, 1024, 4096)
+    }
+
+    #[test]
+    fn test_split_respects_sizes() {
+        let input = data(200_000, 1);
+        let (manifest, chunks) = split(&input, &small());
+        assert_eq!(chunks.concat(), input);
+        assert_eq!(manifest.total_len, input.len());
+        let (last, rest) = chunks.split_last().unwrap();
+        assert!(rest.iter().all(|c| c.len() >= 256 && c.len() <= 4096));
+        assert!(!last.is_empty() && last.len() <= 4096);
+        // Averages near the target
+        let avg = input.len() / chunks.len();
+        assert!((512..=2048).contains(&avg), "{avg}");
+
+        // Zeros never match the mask, so chunks are cut at max_size
+        let (_, chunks) = split(&[0u8; 10_000], &small());
+        assert_eq!(chunks.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![4096, 4096, 1808]);
+        assert!(split(&[], &small()).1.is_empty());
+    }
+
+    #[test]
+    fn test_insert_keeps_most_chunks() {
+        let input = data(100_000, 2);
+        let mut edited = input.clone();
+        edited.splice(5_000..5_000, *b"inserted bytes");
+
+        let (a, _) = split(&input, &small());
+        let (b, _) = split(&edited, &small());
+        let known: Vec<Hash> = a.chunks.iter().map(|c| c.hash).collect();
+        let shared = b.chunks.iter().filter(|c| known.contains(&c.hash)).count();
+        assert!(shared + 3 >= b.chunks.len(), "{shared} of {}", b.chunks.len());
+    }
+
+    #[test]
+    fn test_reassemble_verifies() {
+        let input = data(50_000, 3);
+        let (manifest, chunks) = split(&input, &small());
+        let store: FxHashMap<Hash, Vec<u8>> = chunks.iter().map(|c| (Sha256::digest(c).into(), c.to_vec())).collect();
+
+        let decoded = Manifest::decode(&manifest.encode()).unwrap();
+        assert_eq!(decoded, manifest);
+        assert_eq!(reassemble(&decoded, |h| store.get(h)).unwrap(), input);
+
+        let first = manifest.chunks[0].hash;
+        assert_eq!(
+            reassemble(&manifest, |h| if *h == first { None } else { store.get(h) }),
+            Err(ChunkError::MissingChunk { index: 0, hash: first })
+        );
+        let mut corrupt = store.clone();
+        corrupt.get_mut(&first).unwrap()[0] ^= 1;
+        assert_eq!(reassemble(&manifest, |h| corrupt.get(h)), Err(ChunkError::HashMismatch { index: 0 }));
+
+        let mut wrong = manifest.clone();
+        wrong.content_hash[0] ^= 1;
+        assert_eq!(reassemble(&wrong, |h| store.get(h)), Err(ChunkError::ContentHashMismatch));
+    }
+
+    #[test]
+    fn test_manifest_decode_rejects_malformed() {
+        let (manifest, _) = split(&data(10_000, 4), &small());
+        let bytes = manifest.encode();
+        assert!(Manifest::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(matches!(Manifest::decode(b"GRC2XX"), Err(DecodeError::InvalidMagic { .. })));
+
+        let mut wrong_total = manifest.clone();
+        wrong_total.total_len += 1;
+        assert!(matches!(Manifest::decode(&wrong_total.encode()), Err(DecodeError::MalformedEncoding { .. })));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(Manifest::decode(&trailing), Err(DecodeError::MalformedEncoding { .. })));
+    }
+}
@@ -0,0 +1,449 @@
+//! Compression codecs and level selection.
+//!
+//! Compressed edits use a 5-byte magic that names the codec, followed by the
+//! uncompressed size as a varint and the compressed frame (spec Section 6.6):
+//!
+//! | Magic   | Codec  | Feature  | Use case                         |
+//! |---------|--------|----------|----------------------------------|
+//! | `GRC2Z` | zstd   | `zstd`   | default                          |
+//! | `GRC2L` | LZ4    | `lz4`    | low-latency sync paths           |
+//! | `GRC2B` | Brotli | `brotli` | static archival (smallest output)|
+//!
+//! The decoder detects the codec from the magic. Decoding an envelope whose
+//! codec feature is disabled fails with [`DecodeError::UnsupportedCodec`].
+//!
+//! With the `ruzstd` feature, and always on wasm32 where libzstd doesn't
+//! build, `GRC2Z` envelopes are decoded by the pure-Rust `ruzstd` crate.
+//! That works without `std` too, but can't encode: on wasm32, compressing
+//! with zstd fails with [`EncodeError::CompressionFailed`].
+//!
+//! [`CompressionLevel::Auto`] picks a zstd level from the encoded payload
+//! size and an optional time budget, so services don't have to guess:
+//!
+//! - small payloads compress at high levels since the CPU cost is negligible
+//! - large payloads drop to fast levels where high levels stop paying off
+//! - with a time budget, the level is also capped so that the estimated
+//!   compression time stays within the budget
+
+#[cfg(any(all(feature = "zstd", not(any(feature = "ruzstd", target_arch = "wasm32"))), feature = "brotli"))]
+use std::io::Read;
+use core::time::Duration;
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, EncodeError};
+use crate::limits::{MAGIC_COMPRESSED, MAGIC_COMPRESSED_BROTLI, MAGIC_COMPRESSED_LZ4, MAX_EDIT_SIZE};
+use crate::prelude::*;
+
+/// zstd level used by [`CompressionLevel::Default`].
+pub const DEFAULT_LEVEL: i32 = 3;
+
+/// Highest level [`CompressionLevel::Auto`] picks.
+///
+/// Levels 20+ ("ultra") need large windows on the decoder side as well.
+pub const MAX_AUTO_LEVEL: i32 = 19;
+
+/// Size thresholds for [`CompressionLevel::Auto`] without a budget:
+/// (max payload bytes, level).
+const SIZE_LEVELS: &[(usize, i32)] = &[
+    (16 * 1024, 19),
+    (256 * 1024, 9),
+    (4 * 1024 * 1024, 3),
+];
+
+/// Conservative single-core zstd throughput estimates in bytes per second,
+/// from slowest to fastest level.
+const LEVEL_THROUGHPUT: &[(i32, u64)] = &[
+    (19, 4_000_000),
+    (15, 15_000_000),
+    (9, 60_000_000),
+    (6, 100_000_000),
+    (3, 250_000_000),
+    (1, 400_000_000),
+];
+
+/// Compression codec of a compressed edit envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Codec {
+    /// zstd (`GRC2Z`). Levels 1–22.
+    #[default]
+    Zstd,
+    /// LZ4 block format (`GRC2L`). The level is ignored.
+    Lz4,
+    /// Brotli (`GRC2B`). Levels are Brotli qualities 0–11.
+    Brotli,
+}
+
+impl Codec {
+    /// Returns the envelope magic for this codec.
+    pub fn magic(self) -> &'static [u8; 5] {
+        match self {
+            Codec::Zstd => MAGIC_COMPRESSED,
+            Codec::Lz4 => MAGIC_COMPRESSED_LZ4,
+            Codec::Brotli => MAGIC_COMPRESSED_BROTLI,
+        }
+    }
+
+    /// Detects the codec from the first bytes of an envelope.
+    pub fn from_magic(input: &[u8]) -> Option<Self> {
+        let magic = input.get(..5)?;
+        [Codec::Zstd, Codec::Lz4, Codec::Brotli]
+            .into_iter()
+            .find(|codec| magic == codec.magic())
+    }
+
+    /// Returns the codec name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Zstd => "zstd",
+            Codec::Lz4 => "lz4",
+            Codec::Brotli => "brotli",
+        }
+    }
+
+    /// Returns true if support for this codec is compiled in.
+    ///
+    /// zstd decoding alone may be available through `ruzstd`, which this
+    /// doesn't count.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Codec::Zstd => cfg!(all(feature = "zstd", not(target_arch = "wasm32"))),
+            Codec::Lz4 => cfg!(feature = "lz4"),
+            Codec::Brotli => cfg!(feature = "brotli"),
+        }
+    }
+}
+
+/// Wraps encoded edit bytes in a compressed envelope.
+pub(crate) fn compress_envelope(uncompressed: &[u8], codec: Codec, level: i32) -> Result<Vec<u8>, EncodeError> {
+    let compressed = compress_frame(uncompressed, codec, level)?;
+
+    let mut writer = Writer::with_capacity(5 + 10 + compressed.len());
+    writer.write_bytes(codec.magic());
+    writer.write_varint(uncompressed.len() as u64);
+    writer.write_bytes(&compressed);
+
+    Ok(writer.into_bytes())
+}
+
+// Without libzstd no codec may be compiled in and only the fallback arm remains
+#[cfg_attr(not(all(feature = "zstd", not(target_arch = "wasm32"))), allow(unused_variables))]
+fn compress_frame(data: &[u8], codec: Codec, level: i32) -> Result<Vec<u8>, EncodeError> {
+    let failed = |e: &dyn core::fmt::Display| EncodeError::CompressionFailed(format!("{}: {}", codec.name(), e));
+    match codec {
+        #[cfg(all(feature = "zstd", not(target_arch = "wasm32")))]
+        Codec::Zstd => zstd::encode_all(data, level).map_err(|e| failed(&e)),
+        #[cfg(feature = "lz4")]
+        Codec::Lz4 => Ok(lz4_flex::block::compress(data)),
+        #[cfg(feature = "brotli")]
+        Codec::Brotli => {
+            use std::io::Write;
+            let quality = level.clamp(0, 11) as u32;
+            let mut out = Vec::new();
+            {
+                let mut writer = brotli::CompressorWriter::new(&mut out, 4096, quality, 22);
+                writer.write_all(data).map_err(|e| failed(&e))?;
+            }
+            Ok(out)
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(failed(&"codec not enabled (see cargo features)")),
+    }
+}
+
+/// Decompresses the payload that follows a compressed envelope's magic.
+pub(crate) fn decompress_envelope(codec: Codec, payload: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut reader = Reader::new(payload);
+    let declared_size = reader.read_varint("uncompressed_size")? as usize;
+
+    if declared_size > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "uncompressed_size",
+            len: declared_size,
+            max: MAX_EDIT_SIZE,
+        });
+    }
+
+    let decompressed = decompress_frame(codec, reader.remaining(), declared_size)?;
+
+    if decompressed.len() != declared_size {
+        return Err(DecodeError::UncompressedSizeMismatch {
+            declared: declared_size,
+            actual: decompressed.len(),
+        });
+    }
+
+    Ok(decompressed)
+}
+
+/// Decompresses at most `limit` bytes from the start of a compressed
+/// envelope's payload, returning them with the declared uncompressed size.
+///
+/// zstd and Brotli stop once `limit` bytes are out; LZ4 blocks cannot be
+/// cut short and are decompressed in full.
+pub(crate) fn decompress_envelope_prefix(
+    codec: Codec,
+    payload: &[u8],
+    limit: usize,
+) -> Result<(Vec<u8>, usize), DecodeError> {
+    let mut reader = Reader::new(payload);
+    let declared_size = reader.read_varint("uncompressed_size")? as usize;
+
+    if declared_size > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "uncompressed_size",
+            len: declared_size,
+            max: MAX_EDIT_SIZE,
+        });
+    }
+
+    let limit = limit.min(declared_size);
+    // libzstd and Brotli can stop early
+    #[cfg(any(all(feature = "zstd", not(any(feature = "ruzstd", target_arch = "wasm32"))), feature = "brotli"))]
+    {
+        let failed = |e: &dyn core::fmt::Display| DecodeError::DecompressionFailed(format!("{}: {}", codec.name(), e));
+        let data = reader.remaining();
+        match codec {
+            #[cfg(all(feature = "zstd", not(any(feature = "ruzstd", target_arch = "wasm32"))))]
+            Codec::Zstd => {
+                let decoder = zstd::Decoder::new(data).map_err(|e| failed(&e))?;
+                return read_prefix(decoder, limit).map(|p| (p, declared_size)).map_err(|e| failed(&e));
+            }
+            #[cfg(feature = "brotli")]
+            Codec::Brotli => {
+                return read_prefix(brotli::Decompressor::new(data, 4096), limit)
+                    .map(|p| (p, declared_size))
+                    .map_err(|e| failed(&e));
+            }
+            _ => {}
+        }
+    }
+    let mut decompressed = decompress_frame(codec, reader.remaining(), declared_size)?;
+    decompressed.truncate(limit);
+    Ok((decompressed, declared_size))
+}
+
+#[cfg(any(all(feature = "zstd", not(any(feature = "ruzstd", target_arch = "wasm32"))), feature = "brotli"))]
+fn read_prefix(reader: impl Read, limit: usize) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(limit);
+    reader.take(limit as u64).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+fn decompress_frame(codec: Codec, data: &[u8], declared_size: usize) -> Result<Vec<u8>, DecodeError> {
+    let failed = |e: &dyn core::fmt::Display| DecodeError::DecompressionFailed(format!("{}: {}", codec.name(), e));
+    match codec {
+        #[cfg(any(feature = "ruzstd", target_arch = "wasm32"))]
+        Codec::Zstd => decode_ruzstd(data, declared_size).map_err(|e| failed(&e)),
+        #[cfg(all(feature = "zstd", not(any(feature = "ruzstd", target_arch = "wasm32"))))]
+        Codec::Zstd => {
+            let decoder = zstd::Decoder::new(data).map_err(|e| failed(&e))?;
+            read_bounded(decoder, declared_size).map_err(|e| failed(&e))
+        }
+        #[cfg(feature = "lz4")]
+        Codec::Lz4 => lz4_flex::block::decompress(data, declared_size).map_err(|e| failed(&e)),
+        #[cfg(feature = "brotli")]
+        Codec::Brotli => {
+            read_bounded(brotli::Decompressor::new(data, 4096), declared_size).map_err(|e| failed(&e))
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(DecodeError::UnsupportedCodec { codec: codec.name() }),
+    }
+}
+
+/// Decodes a zstd frame with ruzstd into a buffer one byte larger than the
+/// declared size, like [`read_bounded`].
+#[cfg(any(feature = "ruzstd", target_arch = "wasm32"))]
+fn decode_ruzstd(data: &[u8], declared_size: usize) -> Result<Vec<u8>, ruzstd::decoding::errors::FrameDecoderError> {
+    use ruzstd::decoding::errors::FrameDecoderError;
+
+    let mut out = vec![0u8; declared_size + 1];
+    let len = match ruzstd::decoding::FrameDecoder::new().decode_all(data, &mut out) {
+        Ok(len) => len,
+        // More than `declared_size + 1` bytes: reported as a size mismatch
+        Err(FrameDecoderError::TargetTooSmall) => out.len(),
+        Err(e) => return Err(e),
+    };
+    out.truncate(len);
+    Ok(out)
+}
+
+/// Reads at most one byte past the declared size, so a lying size header
+/// is detected without decompressing an unbounded stream.
+#[cfg(any(all(feature = "zstd", not(any(feature = "ruzstd", target_arch = "wasm32"))), feature = "brotli"))]
+fn read_bounded(reader: impl Read, declared_size: usize) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(declared_size);
+    reader.take(declared_size as u64 + 1).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// How hard to compress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionLevel {
+    /// zstd level 1.
+    Fastest,
+    /// zstd level 3 (zstd's own default).
+    #[default]
+    Default,
+    /// zstd level 19.
+    Best,
+    /// An explicit zstd level.
+    Level(i32),
+    /// Picks a level from the payload size and an optional time budget.
+    Auto { time_budget: Option<Duration> },
+}
+
+impl CompressionLevel {
+    /// Auto mode without a time budget.
+    pub fn auto() -> Self {
+        CompressionLevel::Auto { time_budget: None }
+    }
+
+    /// Auto mode that aims to compress within `budget`.
+    pub fn auto_with_budget(budget: Duration) -> Self {
+        CompressionLevel::Auto { time_budget: Some(budget) }
+    }
+
+    /// Returns the zstd level to use for a payload of `payload_len` bytes.
+    pub fn resolve(&self, payload_len: usize) -> i32 {
+        match *self {
+            CompressionLevel::Fastest => 1,
+            CompressionLevel::Default => DEFAULT_LEVEL,
+            CompressionLevel::Best => MAX_AUTO_LEVEL,
+            CompressionLevel::Level(level) => level,
+            CompressionLevel::Auto { time_budget } => {
+                let by_size = SIZE_LEVELS
+                    .iter()
+                    .find(|(max_len, _)| payload_len <= *max_len)
+                    .map_or(1, |(_, level)| *level);
+                match time_budget {
+                    Some(budget) => by_size.min(level_for_budget(payload_len, budget)),
+                    None => by_size,
+                }
+            }
+        }
+    }
+}
+
+impl From<i32> for CompressionLevel {
+    fn from(level: i32) -> Self {
+        CompressionLevel::Level(level)
+    }
+}
+
+/// Returns the highest level whose estimated time fits the budget, or 1 if
+/// none does.
+fn level_for_budget(payload_len: usize, budget: Duration) -> i32 {
+    LEVEL_THROUGHPUT
+        .iter()
+        .find(|(_, bytes_per_sec)| {
+            let estimate = Duration::from_secs_f64(payload_len as f64 / *bytes_per_sec as f64);
+            estimate <= budget
+        })
+        .map_or(1, |(level, _)| *level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "zstd", feature = "lz4", feature = "brotli"))]
+    fn roundtrip(codec: Codec, level: i32) {
+        let data = b"GRC2 hello hello hello hello hello hello".repeat(50);
+        let envelope = compress_envelope(&data, codec, level).unwrap();
+        assert_eq!(Codec::from_magic(&envelope), Some(codec));
+        assert!(envelope.len() < data.len());
+        assert_eq!(decompress_envelope(codec, &envelope[5..]).unwrap(), data);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_envelope() {
+        roundtrip(Codec::Zstd, 3);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_envelope() {
+        roundtrip(Codec::Lz4, 0);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_envelope() {
+        roundtrip(Codec::Brotli, 11);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_size_mismatch_detected() {
+        let envelope = compress_envelope(b"abcdef", Codec::Zstd, 3).unwrap();
+        // Declared size 6 -> 5
+        let mut tampered = envelope.clone();
+        tampered[5] = 5;
+        assert!(matches!(
+            decompress_envelope(Codec::Zstd, &tampered[5..]),
+            Err(DecodeError::UncompressedSizeMismatch { declared: 5, actual: 6 })
+        ));
+    }
+
+    #[cfg(feature = "ruzstd")]
+    #[test]
+    fn test_ruzstd_envelope() {
+        // `zstd -3 --no-check` output, so this runs without libzstd
+        let data = b"GRC2 hello hello hello hello";
+        let mut payload = vec![data.len() as u8];
+        payload.extend_from_slice(&[
+            0x28, 0xb5, 0x2f, 0xfd, 0x00, 0x58, 0x85, 0x00, 0x00, 0x50, 0x47, 0x52, 0x43, 0x32, 0x20, 0x68, 0x65,
+            0x6c, 0x6c, 0x6f, 0x01, 0x00, 0xf1, 0x0a, 0x12,
+        ]);
+        assert_eq!(decompress_envelope(Codec::Zstd, &payload).unwrap(), data);
+
+        payload[0] = 27;
+        assert!(matches!(
+            decompress_envelope(Codec::Zstd, &payload),
+            Err(DecodeError::UncompressedSizeMismatch { declared: 27, actual: 28 })
+        ));
+    }
+
+    #[test]
+    fn test_codec_from_magic() {
+        assert_eq!(Codec::from_magic(b"GRC2Z..."), Some(Codec::Zstd));
+        assert_eq!(Codec::from_magic(b"GRC2L"), Some(Codec::Lz4));
+        assert_eq!(Codec::from_magic(b"GRC2B"), Some(Codec::Brotli));
+        assert_eq!(Codec::from_magic(b"GRC2X"), None);
+        assert_eq!(Codec::from_magic(b"GRC2"), None);
+    }
+
+    #[test]
+    fn test_fixed_levels() {
+        assert_eq!(CompressionLevel::Fastest.resolve(1 << 30), 1);
+        assert_eq!(CompressionLevel::Default.resolve(0), DEFAULT_LEVEL);
+        assert_eq!(CompressionLevel::Best.resolve(0), 19);
+        assert_eq!(CompressionLevel::from(7).resolve(0), 7);
+    }
+
+    #[test]
+    fn test_auto_by_size() {
+        let auto = CompressionLevel::auto();
+        assert_eq!(auto.resolve(1024), 19);
+        assert_eq!(auto.resolve(100 * 1024), 9);
+        assert_eq!(auto.resolve(1024 * 1024), 3);
+        assert_eq!(auto.resolve(64 * 1024 * 1024), 1);
+    }
+
+    #[test]
+    fn test_auto_with_budget() {
+        // 10 KB in 1 ms: 19 would take ~2.5 ms, 15 ~0.7 ms
+        let auto = CompressionLevel::auto_with_budget(Duration::from_millis(1));
+        assert_eq!(auto.resolve(10_000), 15);
+
+        // A generous budget never raises the level above the size heuristic
+        let auto = CompressionLevel::auto_with_budget(Duration::from_secs(60));
+        assert_eq!(auto.resolve(1024 * 1024), 3);
+
+        // An impossible budget falls back to the fastest level
+        let auto = CompressionLevel::auto_with_budget(Duration::ZERO);
+        assert_eq!(auto.resolve(1024), 1);
+    }
+}
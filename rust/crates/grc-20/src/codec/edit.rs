@@ -2,28 +2,38 @@
 //!
 //! Implements the wire format for edits (spec Section 6.3).
 
-use std::borrow::Cow;
-use std::io::Read;
-
-use rustc_hash::{FxHashMap, FxHashSet};
-
-use crate::codec::op::{decode_op, encode_op};
+use alloc::borrow::Cow;
+use core::time::Duration;
+
+use crate::collections::{FxHashMap, FxHashSet};
+use crate::prelude::*;
+
+use crate::codec::compression::{compress_envelope, decompress_envelope, Codec, CompressionLevel};
+use crate::codec::filter::OpFilter;
+use crate::codec::metering::{record_header, record_op, DecodeMeter, DecodeUsage};
+use crate::codec::layout::{
+    collect_text_table, decode_extended, encode_extended, LAYOUT_COLUMNAR, LAYOUT_EMBEDDING_BLOB,
+    LAYOUT_INTERNED_TEXT,
+};
+use crate::codec::op::{decode_op_filtered, encode_op};
 use crate::codec::primitives::{Reader, Writer};
+use crate::codec::versions::FormatVersion;
 use crate::error::{DecodeError, EncodeError};
 use crate::limits::{
-    FORMAT_VERSION, MAGIC_COMPRESSED, MAGIC_UNCOMPRESSED, MAX_AUTHORS, MAX_DICT_SIZE,
-    MAX_EDIT_SIZE, MAX_OPS_PER_EDIT, MAX_STRING_LEN, MIN_FORMAT_VERSION,
+    default_alloc_budget, MAGIC_EXTENDED, MAGIC_UNCOMPRESSED, MAX_AUTHORS, MAX_DICT_SIZE, MAX_EDIT_SIZE,
+    MAX_OPS_PER_EDIT, MAX_PARENTS, MAX_STRING_LEN,
 };
 use crate::model::{
     Context, ContextEdge, DataType, DictionaryBuilder, Edit, Id, Op, UnsetLanguage,
-    UnsetRelationField, WireDictionaries,
+    UnsetRelationField, Value, WireDictionaries,
 };
 
 // =============================================================================
 // DECODING
 // =============================================================================
 
-/// Decompresses a GRC2Z compressed edit, returning the uncompressed bytes.
+/// Decompresses a compressed edit (GRC2Z, or GRC2L/GRC2B with the `lz4` or
+/// `brotli` feature), returning the uncompressed bytes.
 ///
 /// Use this with [`decode_edit`] for zero-copy decoding of compressed data:
 ///
@@ -36,17 +46,18 @@ pub fn decompress(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
     if input.len() < 5 {
         return Err(DecodeError::UnexpectedEof { context: "magic" });
     }
-    if &input[0..5] != MAGIC_COMPRESSED {
+    let Some(codec) = Codec::from_magic(input) else {
         let mut found = [0u8; 4];
         found.copy_from_slice(&input[0..4]);
         return Err(DecodeError::InvalidMagic { found });
-    }
-    decompress_zstd(&input[5..])
+    };
+    decompress_envelope(codec, &input[5..])
 }
 
 /// Decodes an Edit from binary data with zero-copy borrowing.
 ///
-/// Handles both compressed (GRC2Z) and uncompressed (GRC2) formats.
+/// Handles compressed (GRC2Z), uncompressed (GRC2), and extended layout
+/// (GRC2X, see [`EncodeOptions::columnar`]) formats.
 /// For true zero-copy with compressed data, use [`decompress`] first:
 ///
 /// ```ignore
@@ -60,24 +71,115 @@ pub fn decompress(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
 ///
 /// If you pass compressed data directly, it will decompress internally
 /// and allocate owned strings (no zero-copy benefit).
+///
+/// Total allocations are bounded by [`default_alloc_budget`] of the
+/// (decompressed) input size; use [`decode_edit_with_alloc_budget`] to set a
+/// different bound.
 pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
+    decode_edit_impl(input, DecodeOptions::default(), &mut ())
+}
+
+/// Decodes an Edit like [`decode_edit`], failing with
+/// [`DecodeError::AllocationBudgetExceeded`] if decoding would allocate more
+/// than `alloc_budget` bytes in total.
+pub fn decode_edit_with_alloc_budget(input: &[u8], alloc_budget: usize) -> Result<Edit<'_>, DecodeError> {
+    decode_edit_impl(input, DecodeOptions::alloc_budget(alloc_budget), &mut ())
+}
+
+/// Options for decoding edits.
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// Keep ops with undefined op types instead of failing.
+    ///
+    /// Op types added after this crate was built are written with a length
+    /// prefix (like [`EXTENSION_OP_TYPES`](crate::codec::op::EXTENSION_OP_TYPES)),
+    /// so a decoder can skip over them. When enabled, such ops decode as
+    /// [`Op::Unknown`] and re-encode unchanged; otherwise they fail with
+    /// [`DecodeError::InvalidOpType`]. Indexers that must not stall on edits
+    /// from newer clients should enable this and ignore `Op::Unknown`.
+    ///
+    /// Extension op types are always preserved.
+    pub preserve_unknown: bool,
+
+    /// Total allocation budget in bytes (see
+    /// [`decode_edit_with_alloc_budget`]). Defaults to
+    /// [`default_alloc_budget`] of the input size.
+    pub alloc_budget: Option<usize>,
+
+    /// Keep only the ops this filter matches (see [`OpFilter`]).
+    ///
+    /// Ops rejected by type, and entity ops rejected by ID, are skipped
+    /// without decoding their values; their contents are not validated.
+    pub op_filter: Option<OpFilter>,
+
+    /// Keep only values of these properties.
+    ///
+    /// Other values are skipped without being decoded or allocated, which
+    /// suits indexers that read names and types out of edits carrying
+    /// large embeddings. Entity ops keep their other fields, including
+    /// unsets, and are kept even if no values remain.
+    pub keep_properties: Option<FxHashSet<Id>>,
+}
+
+impl DecodeOptions {
+    /// Creates default (strict) decoding options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates options that keep ops with undefined op types.
+    pub fn preserve_unknown() -> Self {
+        Self { preserve_unknown: true, ..Self::default() }
+    }
+
+    /// Creates options with an explicit allocation budget.
+    pub fn alloc_budget(alloc_budget: usize) -> Self {
+        Self { alloc_budget: Some(alloc_budget), ..Self::default() }
+    }
+
+    /// Creates options that keep only the ops `filter` matches.
+    pub fn op_filter(filter: OpFilter) -> Self {
+        Self { op_filter: Some(filter), ..Self::default() }
+    }
+
+    /// Creates options that keep only values of the given properties.
+    pub fn keep_properties(properties: impl IntoIterator<Item = Id>) -> Self {
+        Self { keep_properties: Some(properties.into_iter().collect()), ..Self::default() }
+    }
+}
+
+/// Decodes an Edit like [`decode_edit`] with the given options.
+pub fn decode_edit_with_options(input: &[u8], options: DecodeOptions) -> Result<Edit<'_>, DecodeError> {
+    decode_edit_impl(input, options, &mut ())
+}
+
+pub(crate) fn decode_edit_impl<'a>(
+    input: &'a [u8],
+    options: DecodeOptions,
+    meter: &mut dyn DecodeMeter,
+) -> Result<Edit<'a>, DecodeError> {
+    let budget_for = |data: &[u8]| options.alloc_budget.unwrap_or_else(|| default_alloc_budget(data.len()));
+
     if input.len() < 4 {
         return Err(DecodeError::UnexpectedEof { context: "magic" });
     }
 
     // Detect compression
-    if input.len() >= 5 && &input[0..5] == MAGIC_COMPRESSED {
+    if let Some(codec) = Codec::from_magic(input) {
         // Compressed: decompress and decode with allocations
         // (for zero-copy, caller should use decompress() first)
-        let decompressed = decompress_zstd(&input[5..])?;
-        if decompressed.len() > MAX_EDIT_SIZE {
+        let decompressed = decompress_envelope(codec, &input[5..])?;
+        decode_decompressed(&decompressed, options, meter)
+    } else if input.len() >= 5 && &input[0..5] == MAGIC_EXTENDED {
+        // Extended layout: decode with zero-copy borrowing
+        if input.len() > MAX_EDIT_SIZE {
             return Err(DecodeError::LengthExceedsLimit {
                 field: "edit",
-                len: decompressed.len(),
+                len: input.len(),
                 max: MAX_EDIT_SIZE,
             });
         }
-        decode_edit_owned(&decompressed)
+        decode_extended(input, budget_for(input), &options, meter)
     } else if &input[0..4] == MAGIC_UNCOMPRESSED {
         // Uncompressed: decode with zero-copy borrowing
         if input.len() > MAX_EDIT_SIZE {
@@ -87,7 +189,7 @@ pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
                 max: MAX_EDIT_SIZE,
             });
         }
-        decode_edit_borrowed(input)
+        decode_edit_borrowed(input, budget_for(input), &options, meter)
     } else {
         let mut found = [0u8; 4];
         found.copy_from_slice(&input[0..4]);
@@ -95,117 +197,161 @@ pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
     }
 }
 
+/// Decodes the payload of a compressed envelope into an owned Edit.
+pub(crate) fn decode_decompressed(
+    decompressed: &[u8],
+    options: DecodeOptions,
+    meter: &mut dyn DecodeMeter,
+) -> Result<Edit<'static>, DecodeError> {
+    if decompressed.len() > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "edit",
+            len: decompressed.len(),
+            max: MAX_EDIT_SIZE,
+        });
+    }
+    let alloc_budget = options.alloc_budget.unwrap_or_else(|| default_alloc_budget(decompressed.len()));
+    decode_edit_owned(decompressed, alloc_budget, &options, meter)
+}
+
 /// Decodes an Edit with zero-copy borrowing from the input.
-fn decode_edit_borrowed(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
-    let mut reader = Reader::new(input);
+fn decode_edit_borrowed<'a>(
+    input: &'a [u8],
+    alloc_budget: usize,
+    options: &DecodeOptions,
+    meter: &mut dyn DecodeMeter,
+) -> Result<Edit<'a>, DecodeError> {
+    let mut reader = Reader::with_alloc_budget(input, alloc_budget);
 
     // Skip magic (already validated)
     reader.read_bytes(4, "magic")?;
+    let version = read_version(&mut reader)?;
 
-    // Version
-    let version = reader.read_byte("version")?;
-    if version < MIN_FORMAT_VERSION || version > FORMAT_VERSION {
-        return Err(DecodeError::UnsupportedVersion { version });
+    let preamble = decode_preamble(&mut reader, version)?;
+    let mut usage = DecodeUsage::default();
+    record_header(meter, &mut usage, reader.position())?;
+
+    // Operations
+    let op_count = read_op_count(&mut reader)?;
+    let mut ops = Vec::with_capacity(op_count);
+    for _ in 0..op_count {
+        let op = decode_op_filtered(&mut reader, &preamble.dicts, options)?;
+        record_op(meter, &mut usage, reader.position(), op.as_ref())?;
+        ops.extend(op);
     }
 
-    // Header
-    let edit_id = reader.read_id("edit_id")?;
-    let name = Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "name")?);
-    let authors = reader.read_id_vec(MAX_AUTHORS, "authors")?;
-    let created_at = reader.read_signed_varint("created_at")?;
+    Ok(Edit {
+        id: preamble.id,
+        name: Cow::Borrowed(preamble.name),
+        authors: preamble.authors,
+        parents: preamble.parents,
+        created_at: preamble.created_at,
+        ops,
+    })
+}
 
-    // Schema dictionaries (with duplicate detection)
-    let property_count = reader.read_varint("property_count")? as usize;
-    if property_count > MAX_DICT_SIZE {
-        return Err(DecodeError::LengthExceedsLimit {
-            field: "properties",
-            len: property_count,
-            max: MAX_DICT_SIZE,
-        });
-    }
-    let mut properties = Vec::with_capacity(property_count);
-    let mut seen_props = FxHashSet::with_capacity_and_hasher(property_count, Default::default());
-    for _ in 0..property_count {
-        let id = reader.read_id("property_id")?;
-        if !seen_props.insert(id) {
-            return Err(DecodeError::DuplicateDictionaryEntry { dict: "properties", id });
+/// Decodes an Edit with allocations (for decompressed data).
+fn decode_edit_owned(
+    data: &[u8],
+    alloc_budget: usize,
+    options: &DecodeOptions,
+    meter: &mut dyn DecodeMeter,
+) -> Result<Edit<'static>, DecodeError> {
+    // Owned copies of borrowed strings and bytes never exceed the input size
+    let alloc_budget = alloc_budget.checked_sub(data.len()).ok_or(DecodeError::AllocationBudgetExceeded {
+        field: "edit",
+        requested: data.len(),
+        remaining: alloc_budget,
+    })?;
+
+    if data.len() >= 5 && &data[0..5] == MAGIC_EXTENDED {
+        let edit = decode_extended(data, alloc_budget, options, meter)?;
+        // Interned TEXT values borrow the same table entry once per
+        // reference, so their copies can exceed the input size
+        let text_len: usize = edit
+            .ops
+            .iter()
+            .flat_map(|op| match op {
+                Op::CreateEntity(ce) => ce.values.as_slice(),
+                Op::UpdateEntity(ue) => ue.set_properties.as_slice(),
+                _ => &[],
+            })
+            .map(|pv| match &pv.value {
+                Value::Text { value, .. } => value.len(),
+                _ => 0,
+            })
+            .sum();
+        let requested = text_len.saturating_sub(data.len());
+        if requested > alloc_budget {
+            return Err(DecodeError::AllocationBudgetExceeded { field: "text", requested, remaining: alloc_budget });
         }
-        let dt_byte = reader.read_byte("data_type")?;
-        let data_type = DataType::from_u8(dt_byte)
-            .ok_or(DecodeError::InvalidDataType { data_type: dt_byte })?;
-        properties.push((id, data_type));
+        return Ok(edit_to_owned(edit));
     }
 
-    let relation_types = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "relation_types")?;
-    let languages = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "languages")?;
-    let units = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "units")?;
-    let objects = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "objects")?;
-    let context_ids = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "context_ids")?;
+    let mut reader = Reader::with_alloc_budget(data, alloc_budget);
 
-    let mut dicts = WireDictionaries {
-        properties,
-        relation_types,
-        languages,
-        units,
-        objects,
-        context_ids,
-        contexts: Vec::new(),
-    };
-
-    // Contexts - decode and store in dicts for op decoding to resolve
-    let context_count = reader.read_varint("context_count")? as usize;
-    if context_count > MAX_DICT_SIZE {
-        return Err(DecodeError::LengthExceedsLimit {
-            field: "contexts",
-            len: context_count,
-            max: MAX_DICT_SIZE,
-        });
-    }
-    for _ in 0..context_count {
-        dicts.contexts.push(decode_context(&mut reader, &dicts)?);
-    }
+    // Skip magic (already validated in decompress)
+    reader.read_bytes(4, "magic")?;
+    let version = read_version(&mut reader)?;
 
-    // Operations
-    let op_count = reader.read_varint("op_count")? as usize;
-    if op_count > MAX_OPS_PER_EDIT {
-        return Err(DecodeError::LengthExceedsLimit {
-            field: "ops",
-            len: op_count,
-            max: MAX_OPS_PER_EDIT,
-        });
-    }
+    let preamble = decode_preamble(&mut reader, version)?;
+    let mut usage = DecodeUsage::default();
+    record_header(meter, &mut usage, reader.position())?;
 
+    // Operations - use allocating decode
+    let op_count = read_op_count(&mut reader)?;
     let mut ops = Vec::with_capacity(op_count);
     for _ in 0..op_count {
-        ops.push(decode_op(&mut reader, &dicts)?);
+        let op = decode_op_owned(&mut reader, &preamble.dicts, options)?;
+        record_op(meter, &mut usage, reader.position(), op.as_ref())?;
+        ops.extend(op);
     }
 
     Ok(Edit {
-        id: edit_id,
-        name,
-        authors,
-        created_at,
+        id: preamble.id,
+        name: Cow::Owned(preamble.name.to_string()),
+        authors: preamble.authors,
+        parents: preamble.parents,
+        created_at: preamble.created_at,
         ops,
     })
 }
 
-/// Decodes an Edit with allocations (for decompressed data).
-fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
-    let mut reader = Reader::new(data);
-
-    // Skip magic (already validated in decompress)
-    reader.read_bytes(4, "magic")?;
+/// Header fields, dictionaries, and contexts that precede the ops.
+///
+/// This section is identical in the row layout and the extended layouts.
+pub(crate) struct Preamble<'a> {
+    pub id: Id,
+    pub name: &'a str,
+    pub authors: Vec<Id>,
+    pub parents: Vec<Id>,
+    pub created_at: i64,
+    pub dicts: WireDictionaries<'a>,
+}
 
-    // Version
+/// Reads the format version byte and checks that it is supported.
+pub(crate) fn read_version(reader: &mut Reader<'_>) -> Result<FormatVersion, DecodeError> {
     let version = reader.read_byte("version")?;
-    if version < MIN_FORMAT_VERSION || version > FORMAT_VERSION {
-        return Err(DecodeError::UnsupportedVersion { version });
-    }
+    FormatVersion::from_byte(version).ok_or(DecodeError::UnsupportedVersion { version })
+}
 
-    // Header - use allocating reads
-    let edit_id = reader.read_id("edit_id")?;
-    let name = Cow::Owned(reader.read_string(MAX_STRING_LEN, "name")?);
+/// Decodes the header, dictionaries, and contexts that follow the version byte.
+pub(crate) fn decode_preamble<'a>(
+    reader: &mut Reader<'a>,
+    version: FormatVersion,
+) -> Result<Preamble<'a>, DecodeError> {
+    // Header
+    let id = reader.read_id("edit_id")?;
+    let name = reader.read_str(MAX_STRING_LEN, "name")?;
     let authors = reader.read_id_vec(MAX_AUTHORS, "authors")?;
+    let parents = if version.has_parents() {
+        read_id_vec_no_duplicates(reader, MAX_PARENTS, "parents")?
+    } else {
+        Vec::new()
+    };
+    if parents.contains(&id) {
+        return Err(DecodeError::MalformedEncoding { context: "edit lists itself as a parent" });
+    }
     let created_at = reader.read_signed_varint("created_at")?;
 
     // Schema dictionaries (with duplicate detection)
@@ -217,6 +363,8 @@ fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
             max: MAX_DICT_SIZE,
         });
     }
+    reader.charge_items::<(Id, DataType)>(property_count, "properties")?;
+    reader.charge_items::<Id>(property_count, "properties")?;
     let mut properties = Vec::with_capacity(property_count);
     let mut seen_props = FxHashSet::with_capacity_and_hasher(property_count, Default::default());
     for _ in 0..property_count {
@@ -230,11 +378,11 @@ fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
         properties.push((id, data_type));
     }
 
-    let relation_types = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "relation_types")?;
-    let languages = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "languages")?;
-    let units = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "units")?;
-    let objects = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "objects")?;
-    let context_ids = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "context_ids")?;
+    let relation_types = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "relation_types")?;
+    let languages = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "languages")?;
+    let units = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "units")?;
+    let objects = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "objects")?;
+    let context_ids = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "context_ids")?;
 
     let mut dicts = WireDictionaries {
         properties,
@@ -244,6 +392,7 @@ fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
         objects,
         context_ids,
         contexts: Vec::new(),
+        texts: None,
     };
 
     // Contexts - decode and store in dicts for op decoding to resolve
@@ -255,11 +404,25 @@ fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
             max: MAX_DICT_SIZE,
         });
     }
+    reader.charge_items::<Context>(context_count, "contexts")?;
+    dicts.contexts.reserve(context_count);
     for _ in 0..context_count {
-        dicts.contexts.push(decode_context(&mut reader, &dicts)?);
+        dicts.contexts.push(decode_context(reader, &dicts)?);
     }
 
-    // Operations - use allocating decode
+    Ok(Preamble {
+        id,
+        name,
+        authors,
+        parents,
+        created_at,
+        dicts,
+    })
+}
+
+/// Reads the op count, checks it against [`MAX_OPS_PER_EDIT`], and charges
+/// the op vector against the allocation budget.
+pub(crate) fn read_op_count(reader: &mut Reader<'_>) -> Result<usize, DecodeError> {
     let op_count = reader.read_varint("op_count")? as usize;
     if op_count > MAX_OPS_PER_EDIT {
         return Err(DecodeError::LengthExceedsLimit {
@@ -268,26 +431,31 @@ fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
             max: MAX_OPS_PER_EDIT,
         });
     }
+    reader.charge_items::<Op>(op_count, "ops")?;
+    Ok(op_count)
+}
 
-    let mut ops = Vec::with_capacity(op_count);
-    for _ in 0..op_count {
-        ops.push(decode_op_owned(&mut reader, &dicts)?);
+/// Converts an Edit with borrowed data to owned data.
+pub(crate) fn edit_to_owned(edit: Edit<'_>) -> Edit<'static> {
+    Edit {
+        id: edit.id,
+        name: Cow::Owned(edit.name.into_owned()),
+        authors: edit.authors,
+        parents: edit.parents,
+        created_at: edit.created_at,
+        ops: edit.ops.into_iter().map(op_to_owned).collect(),
     }
-
-    Ok(Edit {
-        id: edit_id,
-        name,
-        authors,
-        created_at,
-        ops,
-    })
 }
 
 /// Decodes an Op with allocations (for decompressed data).
-fn decode_op_owned(reader: &mut Reader<'_>, dicts: &WireDictionaries) -> Result<Op<'static>, DecodeError> {
+fn decode_op_owned<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    options: &DecodeOptions,
+) -> Result<Option<Op<'static>>, DecodeError> {
     // Decode normally, then convert to owned
-    let op = decode_op(reader, dicts)?;
-    Ok(op_to_owned(op))
+    let op = decode_op_filtered(reader, dicts, options)?;
+    Ok(op.map(op_to_owned))
 }
 
 /// Decodes a Context from the reader.
@@ -311,6 +479,7 @@ fn decode_context(reader: &mut Reader<'_>, dicts: &WireDictionaries) -> Result<C
         });
     }
 
+    reader.charge_items::<ContextEdge>(edge_count, "context_edges")?;
     let mut edges = Vec::with_capacity(edge_count);
     for _ in 0..edge_count {
         let type_id_index = reader.read_varint("edge_type_id")? as usize;
@@ -383,6 +552,10 @@ fn op_to_owned(op: Op<'_>) -> Op<'static> {
         Op::DeleteRelation(dr) => Op::DeleteRelation(dr),
         Op::RestoreRelation(rr) => Op::RestoreRelation(rr),
         Op::CreateValueRef(cvr) => Op::CreateValueRef(cvr),
+        Op::Unknown(unknown) => Op::Unknown(crate::model::UnknownOp {
+            op_type: unknown.op_type,
+            bytes: Cow::Owned(unknown.bytes.into_owned()),
+        }),
     }
 }
 
@@ -390,41 +563,7 @@ fn op_to_owned(op: Op<'_>) -> Op<'static> {
 fn pv_to_owned(pv: crate::model::PropertyValue<'_>) -> crate::model::PropertyValue<'static> {
     crate::model::PropertyValue {
         property: pv.property,
-        value: value_to_owned(pv.value),
-    }
-}
-
-/// Converts a Value with borrowed data to owned data.
-fn value_to_owned(v: crate::model::Value<'_>) -> crate::model::Value<'static> {
-    use crate::model::{DecimalMantissa, Value};
-    match v {
-        Value::Bool(b) => Value::Bool(b),
-        Value::Int64 { value, unit } => Value::Int64 { value, unit },
-        Value::Float64 { value, unit } => Value::Float64 { value, unit },
-        Value::Decimal { exponent, mantissa, unit } => Value::Decimal {
-            exponent,
-            mantissa: match mantissa {
-                DecimalMantissa::I64(i) => DecimalMantissa::I64(i),
-                DecimalMantissa::Big(b) => DecimalMantissa::Big(Cow::Owned(b.into_owned())),
-            },
-            unit,
-        },
-        Value::Text { value, language } => Value::Text {
-            value: Cow::Owned(value.into_owned()),
-            language,
-        },
-        Value::Bytes(b) => Value::Bytes(Cow::Owned(b.into_owned())),
-        Value::Date(s) => Value::Date(Cow::Owned(s.into_owned())),
-        Value::Time(s) => Value::Time(Cow::Owned(s.into_owned())),
-        Value::Datetime(s) => Value::Datetime(Cow::Owned(s.into_owned())),
-        Value::Schedule(s) => Value::Schedule(Cow::Owned(s.into_owned())),
-        Value::Point { lat, lon, alt } => Value::Point { lat, lon, alt },
-        Value::Rect { min_lat, min_lon, max_lat, max_lon } => Value::Rect { min_lat, min_lon, max_lat, max_lon },
-        Value::Embedding { sub_type, dims, data } => Value::Embedding {
-            sub_type,
-            dims,
-            data: Cow::Owned(data.into_owned()),
-        },
+        value: pv.value.into_owned(),
     }
 }
 
@@ -434,60 +573,18 @@ fn read_id_vec_no_duplicates(
     max_len: usize,
     field: &'static str,
 ) -> Result<Vec<Id>, DecodeError> {
-    let count = reader.read_varint(field)? as usize;
-    if count > max_len {
-        return Err(DecodeError::LengthExceedsLimit {
-            field,
-            len: count,
-            max: max_len,
-        });
-    }
-
-    let mut ids = Vec::with_capacity(count);
-    let mut seen = FxHashSet::with_capacity_and_hasher(count, Default::default());
-
-    for _ in 0..count {
-        let id = reader.read_id(field)?;
-        if !seen.insert(id) {
-            return Err(DecodeError::DuplicateDictionaryEntry { dict: field, id });
+    let slice = reader.read_id_slice(max_len, field)?;
+
+    // The vector plus the duplicate-detection set
+    reader.charge_items::<Id>(slice.len().saturating_mul(2), field)?;
+    let mut seen = FxHashSet::with_capacity_and_hasher(slice.len(), Default::default());
+    for id in slice {
+        if !seen.insert(*id) {
+            return Err(DecodeError::DuplicateDictionaryEntry { dict: field, id: *id });
         }
-        ids.push(id);
     }
 
-    Ok(ids)
-}
-
-fn decompress_zstd(compressed: &[u8]) -> Result<Vec<u8>, DecodeError> {
-    // Read uncompressed size
-    let mut reader = Reader::new(compressed);
-    let declared_size = reader.read_varint("uncompressed_size")? as usize;
-
-    if declared_size > MAX_EDIT_SIZE {
-        return Err(DecodeError::LengthExceedsLimit {
-            field: "uncompressed_size",
-            len: declared_size,
-            max: MAX_EDIT_SIZE,
-        });
-    }
-
-    let compressed_data = reader.remaining();
-
-    let mut decoder = zstd::Decoder::new(compressed_data)
-        .map_err(|e| DecodeError::DecompressionFailed(e.to_string()))?;
-
-    let mut decompressed = Vec::with_capacity(declared_size);
-    decoder
-        .read_to_end(&mut decompressed)
-        .map_err(|e| DecodeError::DecompressionFailed(e.to_string()))?;
-
-    if decompressed.len() != declared_size {
-        return Err(DecodeError::UncompressedSizeMismatch {
-            declared: declared_size,
-            actual: decompressed.len(),
-        });
-    }
-
-    Ok(decompressed)
+    Ok(slice.to_vec())
 }
 
 // =============================================================================
@@ -511,6 +608,37 @@ pub struct EncodeOptions {
     /// Note: Canonical mode requires two passes over the ops and is slower
     /// than non-canonical encoding.
     pub canonical: bool,
+
+    /// Enable the columnar op layout.
+    ///
+    /// When enabled, the output uses the GRC2X extended layout: all
+    /// CreateEntity IDs are written together, followed by the property
+    /// values grouped by property. Homogeneous bulk data (e.g. imports of
+    /// many entities with the same schema) compresses noticeably better.
+    ///
+    /// [`decode_edit`] reconstructs the original op order transparently.
+    /// Content hashes and signatures should still be computed over the
+    /// row layout; combine with `canonical` to keep dictionary order stable.
+    pub columnar: bool,
+
+    /// Intern repeated TEXT values into a table.
+    ///
+    /// When set, TEXT strings that occur at least this many times in the
+    /// edit are stored once in a table (GRC2X extended layout) and values
+    /// reference them by index. Thresholds below 2 are treated as 2.
+    ///
+    /// Interned values decode borrowed from the table, so every reference
+    /// to a string shares the same input bytes.
+    pub intern_values: Option<usize>,
+
+    /// Move EMBEDDING payloads to a blob at the end of the edit.
+    ///
+    /// Values keep only an offset into the blob (GRC2X extended layout), so
+    /// decoders that skip embeddings, e.g. with
+    /// [`DecodeOptions::keep_properties`], never read or copy them, and the
+    /// blob can be memory-mapped separately. Embeddings still decode
+    /// borrowed from the input.
+    pub embedding_blob: bool,
 }
 
 impl EncodeOptions {
@@ -521,7 +649,67 @@ impl EncodeOptions {
 
     /// Creates canonical encoding options.
     pub fn canonical() -> Self {
-        Self { canonical: true }
+        Self { canonical: true, ..Self::default() }
+    }
+
+    /// Creates columnar encoding options.
+    pub fn columnar() -> Self {
+        Self { columnar: true, ..Self::default() }
+    }
+
+    /// Creates options that intern TEXT values occurring at least
+    /// `threshold` times.
+    pub fn intern_values(threshold: usize) -> Self {
+        Self { intern_values: Some(threshold), ..Self::default() }
+    }
+
+    /// Creates options that store EMBEDDING payloads in a trailing blob.
+    pub fn embedding_blob() -> Self {
+        Self { embedding_blob: true, ..Self::default() }
+    }
+
+    /// Layout flags that are applied by rewriting the row encoding.
+    fn transcode_flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.columnar {
+            flags |= LAYOUT_COLUMNAR;
+        }
+        if self.embedding_blob {
+            flags |= LAYOUT_EMBEDDING_BLOB;
+        }
+        flags
+    }
+}
+
+/// Reusable buffers for [`encode_edit_with_context`].
+///
+/// Encoding an edit allocates an op buffer, an output buffer, and the
+/// dictionary vectors and hash maps. A context keeps these between calls,
+/// so after the first few edits encoding a stream of similar edits stops
+/// allocating (apart from growth for an unusually large edit).
+#[derive(Debug, Clone, Default)]
+pub struct EncodeContext {
+    options: EncodeOptions,
+    dicts: DictionaryBuilder,
+    ops: Writer,
+    out: Writer,
+    authors: Vec<Id>,
+}
+
+impl EncodeContext {
+    /// Creates a context for default (fast) encoding.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a context that encodes with `options`.
+    pub fn with_options(options: EncodeOptions) -> Self {
+        Self { options, ..Self::default() }
+    }
+
+    /// Returns the options edits are encoded with.
+    pub fn options(&self) -> EncodeOptions {
+        self.options
     }
 }
 
@@ -539,6 +727,7 @@ fn validate_context_limits(context: &Context) -> Result<(), EncodeError> {
 /// Encode-time structural validation aligned with spec.md:
 /// - Section 4.3: size limits and dictionary constraints
 /// - Section 4.4: canonical duplicate rules (enforced elsewhere) and author checks
+/// - Section 6.3: distinct parents, none of them the edit itself
 /// - Section 4.5 / 6.3: context structure and ContextRef requirements
 /// - Section 6.4: op type constraints
 /// - Section 3.2 / 3.6: update set/unset overlap and TEXT-only language slots
@@ -558,6 +747,23 @@ fn validate_edit_inputs(edit: &Edit) -> Result<(), EncodeError> {
             max: MAX_AUTHORS,
         });
     }
+    if edit.parents.len() > MAX_PARENTS {
+        return Err(EncodeError::LengthExceedsLimit {
+            field: "parents",
+            len: edit.parents.len(),
+            max: MAX_PARENTS,
+        });
+    }
+    if edit.parents.contains(&edit.id) {
+        return Err(EncodeError::SelfParent { id: edit.id });
+    }
+    if edit.parents.len() > 1 {
+        let mut parents = edit.parents.clone();
+        parents.sort_unstable();
+        if let Some(pair) = parents.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(EncodeError::DuplicateParent { id: pair[0] });
+        }
+    }
     if edit.ops.len() > MAX_OPS_PER_EDIT {
         return Err(EncodeError::LengthExceedsLimit {
             field: "ops",
@@ -744,6 +950,15 @@ fn validate_edit_inputs(edit: &Edit) -> Result<(), EncodeError> {
                     }
                 }
             }
+            Op::Unknown(unknown) => {
+                if unknown.bytes.len() > crate::limits::MAX_BYTES_LEN {
+                    return Err(EncodeError::LengthExceedsLimit {
+                        field: "extension_op",
+                        len: unknown.bytes.len(),
+                        max: crate::limits::MAX_BYTES_LEN,
+                    });
+                }
+            }
         }
     }
 
@@ -760,55 +975,140 @@ pub fn encode_edit(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
 
 /// Encodes an Edit to binary format with the given options.
 pub fn encode_edit_with_options(edit: &Edit, options: EncodeOptions) -> Result<Vec<u8>, EncodeError> {
+    encode_edit_versioned(edit, options, FormatVersion::for_edit(edit))
+}
+
+/// Encodes an Edit at `version` (see [`crate::codec::versions`]).
+pub(crate) fn encode_edit_versioned(
+    edit: &Edit,
+    options: EncodeOptions,
+    version: FormatVersion,
+) -> Result<Vec<u8>, EncodeError> {
     validate_edit_inputs(edit)?;
+    if !edit.parents.is_empty() && !version.has_parents() {
+        return Err(EncodeError::InvalidInput { context: "parents require format version 2" });
+    }
+    let texts = options
+        .intern_values
+        .map(|threshold| collect_text_table(edit, threshold))
+        .filter(|texts| !texts.is_empty());
+    let encoded = if options.canonical {
+        encode_edit_canonical(edit, texts, version)?
+    } else {
+        encode_edit_fast(edit, texts, version)?
+    };
+    match options.transcode_flags() {
+        0 => Ok(encoded),
+        flags => encode_extended(&encoded, flags),
+    }
+}
+
+/// Encodes an Edit using the buffers in `context`.
+///
+/// Produces the same bytes as [`encode_edit_with_options`] with the
+/// context's options. The result borrows the context's output buffer and
+/// is overwritten by the next call; copy it out if it must outlive that.
+pub fn encode_edit_with_context<'c>(edit: &Edit, context: &'c mut EncodeContext) -> Result<&'c [u8], EncodeError> {
+    validate_edit_inputs(edit)?;
+    let EncodeContext { options, dicts, ops, out, authors } = context;
+    dicts.clear();
+    ops.clear();
+    out.clear();
+    if let Some(texts) = options
+        .intern_values
+        .map(|threshold| collect_text_table(edit, threshold))
+        .filter(|texts| !texts.is_empty())
+    {
+        dicts.set_text_table(texts);
+    }
     if options.canonical {
-        encode_edit_canonical(edit)
+        write_edit_canonical(edit, dicts, ops, out, authors, FormatVersion::for_edit(edit))?;
+    } else {
+        write_edit_fast(edit, dicts, ops, out, FormatVersion::for_edit(edit))?;
+    }
+    if options.transcode_flags() != 0 {
+        *out = Writer::from(encode_extended(out.as_bytes(), options.transcode_flags())?);
+    }
+    Ok(out.as_bytes())
+}
+
+/// Writes the magic and version, plus layout flags for extended layouts.
+fn write_magic(writer: &mut Writer, dict_builder: &DictionaryBuilder, version: FormatVersion) {
+    if dict_builder.interns_text() {
+        writer.write_bytes(MAGIC_EXTENDED);
+        writer.write_byte(version.as_byte());
+        writer.write_byte(LAYOUT_INTERNED_TEXT);
     } else {
-        encode_edit_fast(edit)
+        writer.write_bytes(MAGIC_UNCOMPRESSED);
+        writer.write_byte(version.as_byte());
     }
 }
 
-/// Fast single-pass encoding (non-canonical).
-fn encode_edit_fast(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
-    // Property types are determined from values themselves (per-edit typing)
-    let property_types = rustc_hash::FxHashMap::default();
+/// Writes the edit header in the layout of `version`.
+fn write_header(writer: &mut Writer, edit: &Edit, authors: &[Id], parents: &[Id], version: FormatVersion) {
+    writer.write_id(&edit.id);
+    writer.write_string(&edit.name);
+    writer.write_id_vec(authors);
+    if version.has_parents() {
+        writer.write_id_vec(parents);
+    }
+    writer.write_signed_varint(edit.created_at);
+}
 
+/// Fast single-pass encoding (non-canonical).
+fn encode_edit_fast(edit: &Edit, texts: Option<Vec<String>>, version: FormatVersion) -> Result<Vec<u8>, EncodeError> {
     // Create dictionary builder - contexts will be collected from ops
     let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
-
-    // Single pass: encode ops while building dictionaries (including contexts)
+    if let Some(texts) = texts {
+        dict_builder.set_text_table(texts);
+    }
     let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
+    let mut writer = Writer::new();
+    write_edit_fast(edit, &mut dict_builder, &mut ops_writer, &mut writer, version)?;
+    Ok(writer.into_bytes())
+}
 
+/// Single pass into caller-provided buffers: ops are encoded to `ops_writer`
+/// while building `dict_builder`, then the edit is assembled in `writer`.
+fn write_edit_fast(
+    edit: &Edit,
+    dict_builder: &mut DictionaryBuilder,
+    ops_writer: &mut Writer,
+    writer: &mut Writer,
+    version: FormatVersion,
+) -> Result<(), EncodeError> {
+    // Property types are determined from values themselves (per-edit typing)
+    let property_types = crate::collections::FxHashMap::default();
+
+    // Single pass: encode ops while building dictionaries (including contexts)
     for op in &edit.ops {
-        encode_op(&mut ops_writer, op, &mut dict_builder, &property_types)?;
+        encode_op(ops_writer, op, dict_builder, &property_types)?;
     }
     dict_builder.validate_limits()?;
 
     // Now assemble final output: header + dictionaries + contexts + ops
-    let ops_bytes = ops_writer.into_bytes();
-    let mut writer = Writer::with_capacity(256 + ops_bytes.len());
+    writer.reserve(256 + ops_writer.len());
 
     // Magic and version
-    writer.write_bytes(MAGIC_UNCOMPRESSED);
-    writer.write_byte(FORMAT_VERSION);
+    write_magic(writer, dict_builder, version);
 
     // Header
-    writer.write_id(&edit.id);
-    writer.write_string(&edit.name);
-    writer.write_id_vec(&edit.authors);
-    writer.write_signed_varint(edit.created_at);
+    write_header(writer, edit, &edit.authors, &edit.parents, version);
 
     // Dictionaries
-    dict_builder.write_dictionaries(&mut writer);
+    dict_builder.write_dictionaries(writer);
 
     // Contexts (collected from ops during encoding)
-    dict_builder.write_contexts(&mut writer);
+    dict_builder.write_contexts(writer);
+
+    // Interned TEXT table (extended layout only)
+    dict_builder.write_text_table(writer);
 
     // Operations (already encoded)
     writer.write_varint(edit.ops.len() as u64);
-    writer.write_bytes(&ops_bytes);
+    writer.write_bytes(ops_writer.as_bytes());
 
-    Ok(writer.into_bytes())
+    Ok(())
 }
 
 /// Canonical two-pass encoding with sorted dictionaries, authors, values, and unsets.
@@ -821,25 +1121,49 @@ fn encode_edit_fast(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
 /// - Authors sorted by ID bytes, no duplicates
 /// - Values sorted by (propertyRef, languageRef), no duplicate (property, language)
 /// - Unset values sorted by (propertyRef, language), no duplicates
-fn encode_edit_canonical(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
-    // Property types are determined from values themselves (per-edit typing)
-    let property_types = rustc_hash::FxHashMap::default();
-
+fn encode_edit_canonical(
+    edit: &Edit,
+    texts: Option<Vec<String>>,
+    version: FormatVersion,
+) -> Result<Vec<u8>, EncodeError> {
     // Create dictionary builder - contexts will be collected from ops
     let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
+    if let Some(texts) = texts {
+        dict_builder.set_text_table(texts);
+    }
+    let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
+    let mut writer = Writer::new();
+    let mut sorted_authors = Vec::new();
+    write_edit_canonical(edit, &mut dict_builder, &mut ops_writer, &mut writer, &mut sorted_authors, version)?;
+    Ok(writer.into_bytes())
+}
+
+/// Canonical encoding into caller-provided buffers. On return
+/// `dict_builder` holds the sorted dictionaries.
+fn write_edit_canonical(
+    edit: &Edit,
+    dict_builder: &mut DictionaryBuilder,
+    ops_writer: &mut Writer,
+    writer: &mut Writer,
+    sorted_authors: &mut Vec<Id>,
+    version: FormatVersion,
+) -> Result<(), EncodeError> {
+    // Property types are determined from values themselves (per-edit typing)
+    let property_types = crate::collections::FxHashMap::default();
 
     // Pass 1: Collect all dictionary entries (including contexts) by doing a dry run
-    let mut temp_writer = Writer::with_capacity(edit.ops.len() * 50);
     for op in &edit.ops {
-        encode_op(&mut temp_writer, op, &mut dict_builder, &property_types)?;
+        encode_op(ops_writer, op, dict_builder, &property_types)?;
     }
     dict_builder.validate_limits()?;
 
     // Sort dictionaries and get sorted builder
-    let sorted_builder = dict_builder.into_sorted();
+    *dict_builder = core::mem::take(dict_builder).into_sorted();
+    let sorted_builder = &*dict_builder;
 
     // Sort authors by ID bytes and check for duplicates
-    let mut sorted_authors = edit.authors.clone();
+    sorted_authors.clear();
+    sorted_authors.extend_from_slice(&edit.authors);
     sorted_authors.sort();
     // Check for duplicate authors
     for i in 1..sorted_authors.len() {
@@ -848,37 +1172,58 @@ fn encode_edit_canonical(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
         }
     }
 
+    // Parents are sorted too (duplicates were rejected up front)
+    let mut sorted_parents = edit.parents.clone();
+    sorted_parents.sort();
+
     // Pass 2: Encode ops with sorted dictionary indices and sorted values
-    let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
+    ops_writer.clear();
     let mut canonical_builder = sorted_builder.clone();
     for op in &edit.ops {
-        encode_op_canonical(&mut ops_writer, op, &mut canonical_builder, &property_types)?;
+        encode_op_canonical(ops_writer, op, &mut canonical_builder, &property_types)?;
     }
 
     // Assemble final output: header + dictionaries + contexts + ops
-    let ops_bytes = ops_writer.into_bytes();
-    let mut writer = Writer::with_capacity(256 + ops_bytes.len());
+    writer.reserve(256 + ops_writer.len());
 
     // Magic and version
-    writer.write_bytes(MAGIC_UNCOMPRESSED);
-    writer.write_byte(FORMAT_VERSION);
+    write_magic(writer, sorted_builder, version);
 
     // Header
-    writer.write_id(&edit.id);
-    writer.write_string(&edit.name);
-    writer.write_id_vec(&sorted_authors);
-    writer.write_signed_varint(edit.created_at);
+    write_header(writer, edit, sorted_authors, &sorted_parents, version);
 
     // Dictionaries (sorted)
-    sorted_builder.write_dictionaries(&mut writer);
+    sorted_builder.write_dictionaries(writer);
 
     // Contexts (collected from ops during pass 1, sorted)
-    sorted_builder.write_contexts(&mut writer);
+    sorted_builder.write_contexts(writer);
+
+    // Interned TEXT table (extended layout only)
+    sorted_builder.write_text_table(writer);
 
     // Operations
     writer.write_varint(edit.ops.len() as u64);
-    writer.write_bytes(&ops_bytes);
+    writer.write_bytes(ops_writer.as_bytes());
 
+    Ok(())
+}
+
+/// Encodes a single op canonically together with the dictionaries and
+/// contexts it references, so the bytes do not depend on the rest of the
+/// edit (see [`crate::codec::merkle`]).
+pub(crate) fn encode_op_standalone(op: &Op<'_>) -> Result<Vec<u8>, EncodeError> {
+    let property_types = FxHashMap::default();
+    let mut dict_builder = DictionaryBuilder::new();
+    let mut op_writer = Writer::new();
+    encode_op(&mut op_writer, op, &mut dict_builder, &property_types)?;
+    let mut sorted_builder = dict_builder.into_sorted();
+
+    let mut writer = Writer::with_capacity(64 + op_writer.len());
+    sorted_builder.write_dictionaries(&mut writer);
+    sorted_builder.write_contexts(&mut writer);
+    op_writer.clear();
+    encode_op_canonical(&mut op_writer, op, &mut sorted_builder, &property_types)?;
+    writer.write_bytes(op_writer.as_bytes());
     Ok(writer.into_bytes())
 }
 
@@ -1080,6 +1425,7 @@ fn encode_property_value_canonical(
 }
 
 /// Encodes an Edit with profiling output (two-pass for comparison).
+#[cfg(feature = "std")]
 pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, EncodeError> {
     if !profile {
         return encode_edit(edit);
@@ -1090,7 +1436,7 @@ pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, Encod
     let t0 = Instant::now();
 
     // Property types are determined from values themselves (per-edit typing)
-    let property_types = rustc_hash::FxHashMap::default();
+    let property_types = crate::collections::FxHashMap::default();
     let t1 = Instant::now();
 
     // Create dictionary builder - contexts will be collected from ops
@@ -1109,11 +1455,9 @@ pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, Encod
     let mut writer = Writer::with_capacity(256 + ops_bytes.len());
 
     writer.write_bytes(MAGIC_UNCOMPRESSED);
-    writer.write_byte(FORMAT_VERSION);
-    writer.write_id(&edit.id);
-    writer.write_string(&edit.name);
-    writer.write_id_vec(&edit.authors);
-    writer.write_signed_varint(edit.created_at);
+    let version = FormatVersion::for_edit(edit);
+    writer.write_byte(version.as_byte());
+    write_header(&mut writer, edit, &edit.authors, &edit.parents, version);
     dict_builder.write_dictionaries(&mut writer);
     dict_builder.write_contexts(&mut writer);
     writer.write_varint(edit.ops.len() as u64);
@@ -1144,24 +1488,48 @@ pub fn encode_edit_compressed_with_options(
     options: EncodeOptions,
 ) -> Result<Vec<u8>, EncodeError> {
     let uncompressed = encode_edit_with_options(edit, options)?;
+    compress_envelope(&uncompressed, Codec::Zstd, level)
+}
 
-    let compressed = zstd::encode_all(uncompressed.as_slice(), level)
-        .map_err(|e| EncodeError::CompressionFailed(e.to_string()))?;
-
-    let mut writer = Writer::with_capacity(5 + 10 + compressed.len());
-    writer.write_bytes(MAGIC_COMPRESSED);
-    writer.write_varint(uncompressed.len() as u64);
-    writer.write_bytes(&compressed);
+/// Encodes an Edit with the given compression codec.
+///
+/// `level` is codec-specific: zstd levels 1–22, Brotli qualities 0–11;
+/// LZ4 ignores it. Returns [`EncodeError::CompressionFailed`] if the codec's
+/// cargo feature is disabled.
+pub fn encode_edit_compressed_with_codec(
+    edit: &Edit,
+    codec: Codec,
+    level: i32,
+) -> Result<Vec<u8>, EncodeError> {
+    let uncompressed = encode_edit(edit)?;
+    compress_envelope(&uncompressed, codec, level)
+}
 
-    Ok(writer.into_bytes())
+/// Encodes an Edit with zstd compression at a level chosen by
+/// [`CompressionLevel::Auto`] for the encoded size and optional time budget.
+///
+/// ```ignore
+/// // Keep compression within ~5ms on the request path
+/// let bytes = encode_edit_compressed_auto(&edit, Some(Duration::from_millis(5)))?;
+/// ```
+pub fn encode_edit_compressed_auto(
+    edit: &Edit,
+    time_budget: Option<Duration>,
+) -> Result<Vec<u8>, EncodeError> {
+    let uncompressed = encode_edit(edit)?;
+    let level = CompressionLevel::Auto { time_budget }.resolve(uncompressed.len());
+    compress_envelope(&uncompressed, Codec::Zstd, level)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::op::decode_op_impl;
+    use crate::limits::FORMAT_VERSION;
     use crate::model::{
-        CreateEntity, CreateRelation, CreateValueRef, DeleteEntity, DeleteRelation, PropertyValue,
-        UpdateEntity, UpdateRelation, UnsetLanguage, UnsetRelationField, UnsetValue, Value,
+        CreateEntity, CreateRelation, CreateValueRef, DecimalMantissa, DeleteEntity, DeleteRelation,
+        EmbeddingSubType, PropertyValue, UpdateEntity, UpdateRelation, UnsetLanguage,
+        UnsetRelationField, UnsetValue, Value,
     };
 
     fn make_test_edit() -> Edit<'static> {
@@ -1169,6 +1537,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Owned("Test Edit".to_string()),
             authors: vec![[2u8; 16]],
+            parents: vec![],
             created_at: 1234567890,
                         ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1200,6 +1569,7 @@ mod tests {
         assert_eq!(edit.ops.len(), decoded.ops.len());
     }
 
+    #[cfg(feature = "zstd")]
     #[test]
     fn test_edit_compressed_roundtrip() {
         let edit = make_test_edit();
@@ -1214,12 +1584,60 @@ mod tests {
         assert_eq!(edit.ops.len(), decoded.ops.len());
     }
 
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_edit_compressed_auto_roundtrip() {
+        let edit = make_test_edit();
+
+        for budget in [None, Some(Duration::ZERO), Some(Duration::from_millis(10))] {
+            let encoded = encode_edit_compressed_auto(&edit, budget).unwrap();
+            assert_eq!(&encoded[0..5], Codec::Zstd.magic());
+            let decoded = decode_edit(&encoded).unwrap();
+            assert_eq!(edit.ops, decoded.ops);
+        }
+    }
+
+    #[test]
+    fn test_edit_compressed_with_codec_roundtrip() {
+        let edit = make_test_edit();
+
+        for codec in [Codec::Zstd, Codec::Lz4, Codec::Brotli] {
+            let result = encode_edit_compressed_with_codec(&edit, codec, 5);
+            if !codec.is_enabled() {
+                assert!(matches!(result, Err(EncodeError::CompressionFailed(_))));
+                continue;
+            }
+            let encoded = result.unwrap();
+            assert_eq!(&encoded[0..5], codec.magic());
+            assert_eq!(decode_edit(&encoded).unwrap().ops, edit.ops);
+
+            let uncompressed = decompress(&encoded).unwrap();
+            assert_eq!(decode_edit(&uncompressed).unwrap().ops, edit.ops);
+        }
+    }
+
+    #[cfg(not(feature = "lz4"))]
+    #[test]
+    fn test_disabled_codec_rejected() {
+        let input = b"GRC2L\x05abcde";
+        assert_eq!(decode_edit(input).unwrap_err(), DecodeError::UnsupportedCodec { codec: "lz4" });
+    }
+
+    // A build without compression features, e.g. for light clients
+    #[cfg(not(any(feature = "zstd", feature = "ruzstd", target_arch = "wasm32")))]
+    #[test]
+    fn test_zstd_rejected_without_compression() {
+        let input = b"GRC2Z\x05abcde";
+        assert_eq!(decode_edit(input).unwrap_err(), DecodeError::UnsupportedCodec { codec: "zstd" });
+    }
+
     #[test]
     fn test_update_entity_set_unset_overlap_rejected() {
         let edit = Edit {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![Op::UpdateEntity(UpdateEntity {
                 id: [2u8; 16],
@@ -1248,6 +1666,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![Op::UpdateEntity(UpdateEntity {
                 id: [2u8; 16],
@@ -1273,6 +1692,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![Op::UpdateRelation(UpdateRelation {
                 id: [4u8; 16],
@@ -1296,6 +1716,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1331,6 +1752,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![
                 Op::DeleteEntity(DeleteEntity {
@@ -1355,6 +1777,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![
                 Op::DeleteRelation(DeleteRelation {
@@ -1389,6 +1812,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![Op::CreateRelation(CreateRelation {
                 id: [4u8; 16],
@@ -1417,6 +1841,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1447,6 +1872,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![
                 Op::CreateValueRef(CreateValueRef {
@@ -1477,7 +1903,7 @@ mod tests {
         let encoded = encode_edit(&edit).unwrap();
         let mut reader = Reader::new(&encoded);
         reader.read_bytes(4, "magic").unwrap();
-        reader.read_byte("version").unwrap();
+        assert_eq!(reader.read_byte("version").unwrap(), 1);
         reader.read_id("edit_id").unwrap();
         reader.read_string(MAX_STRING_LEN, "name").unwrap();
         reader.read_id_vec(MAX_AUTHORS, "authors").unwrap();
@@ -1503,6 +1929,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![Op::UpdateEntity(UpdateEntity {
                 id: [2u8; 16],
@@ -1531,6 +1958,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
             ops: vec![Op::UpdateRelation(UpdateRelation {
                 id: [4u8; 16],
@@ -1548,6 +1976,7 @@ mod tests {
         assert!(matches!(err, EncodeError::InvalidInput { .. }));
     }
 
+    #[cfg(feature = "zstd")]
     #[test]
     fn test_compression_magic() {
         let edit = make_test_edit();
@@ -1584,6 +2013,7 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![],
         };
@@ -1610,6 +2040,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1637,6 +2068,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1704,6 +2136,7 @@ mod tests {
         assert_eq!(edit.ops.len(), decoded.ops.len());
     }
 
+    #[cfg(feature = "zstd")]
     #[test]
     fn test_canonical_encoding_compressed() {
         let edit = make_test_edit();
@@ -1723,8 +2156,9 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Owned("Test".to_string()),
             authors: vec![author1, author1], // Duplicate!
+            parents: vec![],
             created_at: 0,
-                        ops: vec![],
+            ops: vec![],
         };
 
         // Fast mode doesn't check duplicates
@@ -1736,6 +2170,70 @@ mod tests {
         assert!(matches!(result, Err(EncodeError::DuplicateAuthor { .. })));
     }
 
+    #[test]
+    fn test_parents_roundtrip() {
+        let mut edit = make_test_edit();
+        edit.parents = vec![[9u8; 16], [8u8; 16]];
+
+        for options in [EncodeOptions::new(), EncodeOptions::canonical(), EncodeOptions::columnar()] {
+            let encoded = encode_edit_with_options(&edit, options).unwrap();
+            let decoded = decode_edit(&encoded).unwrap();
+            if options.canonical {
+                // Canonical mode sorts parents like authors
+                assert_eq!(decoded.parents, vec![[8u8; 16], [9u8; 16]]);
+            } else {
+                assert_eq!(decoded, edit);
+            }
+        }
+
+        // Both paths reject duplicate parents and an edit listing itself
+        for options in [EncodeOptions::new(), EncodeOptions::canonical()] {
+            edit.parents = vec![[9u8; 16], [9u8; 16]];
+            let result = encode_edit_with_options(&edit, options);
+            assert!(matches!(result, Err(EncodeError::DuplicateParent { id }) if id == [9u8; 16]));
+
+            edit.parents = vec![[9u8; 16], edit.id];
+            let result = encode_edit_with_options(&edit, options);
+            assert!(matches!(result, Err(EncodeError::SelfParent { id }) if id == edit.id));
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_parents() {
+        let mut edit = make_test_edit();
+        edit.parents = vec![[9u8; 16], [8u8; 16]];
+        let encoded = encode_edit(&edit).unwrap();
+        let first = encoded.windows(16).position(|w| w == [9u8; 16]).unwrap();
+        let second = encoded.windows(16).position(|w| w == [8u8; 16]).unwrap();
+
+        let mut duplicate = encoded.clone();
+        duplicate[second..second + 16].copy_from_slice(&[9u8; 16]);
+        let result = decode_edit(&duplicate);
+        assert!(matches!(result, Err(DecodeError::DuplicateDictionaryEntry { dict: "parents", .. })));
+
+        let mut self_parent = encoded;
+        self_parent[first..first + 16].copy_from_slice(&edit.id);
+        let result = decode_edit(&self_parent);
+        assert!(matches!(result, Err(DecodeError::MalformedEncoding { .. })));
+    }
+
+    #[test]
+    fn test_parents_need_version_2() {
+        let mut edit = make_test_edit();
+
+        // Version 1 edits have no parents field and decode with none
+        let v1 = encode_edit_versioned(&edit, EncodeOptions::new(), FormatVersion::V1).unwrap();
+        assert_eq!(v1[4], 1);
+        assert_eq!(decode_edit(&v1).unwrap(), edit);
+        // Edits without parents are encoded at version 1 by default
+        assert_eq!(encode_edit(&edit).unwrap(), v1);
+
+        edit.parents.push([9u8; 16]);
+        let result = encode_edit_versioned(&edit, EncodeOptions::new(), FormatVersion::V1);
+        assert!(matches!(result, Err(EncodeError::InvalidInput { .. })));
+        assert_eq!(encode_edit(&edit).unwrap()[4], FormatVersion::V2.as_byte());
+    }
+
     #[test]
     fn test_canonical_rejects_duplicate_values() {
         let prop = [10u8; 16];
@@ -1744,6 +2242,7 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1784,6 +2283,7 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1824,6 +2324,7 @@ mod tests {
             id: [1u8; 16],
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![
                 Op::CreateEntity(CreateEntity {
@@ -1855,4 +2356,259 @@ mod tests {
         let decoded = decode_edit(&encoded1).unwrap();
         assert_eq!(decoded.ops.len(), 1);
     }
+
+    /// Writes a GRC2 header with empty dictionaries, ready for the op count.
+    fn empty_preamble() -> Writer {
+        let mut writer = Writer::new();
+        writer.write_bytes(MAGIC_UNCOMPRESSED);
+        writer.write_byte(FORMAT_VERSION);
+        writer.write_id(&[1u8; 16]);
+        writer.write_string("");
+        writer.write_varint(0); // authors
+        writer.write_varint(0); // parents
+        writer.write_signed_varint(0); // created_at
+        for _ in 0..7 {
+            writer.write_varint(0); // dictionaries and contexts
+        }
+        writer
+    }
+
+    #[test]
+    fn test_alloc_budget_rejects_op_count_bomb() {
+        let mut writer = empty_preamble();
+        writer.write_varint(MAX_OPS_PER_EDIT as u64);
+        let result = decode_edit(writer.as_bytes());
+        assert!(matches!(
+            result,
+            Err(DecodeError::AllocationBudgetExceeded { field: "ops", .. })
+        ));
+    }
+
+    #[test]
+    fn test_alloc_budget_rejects_repeated_context_copies() {
+        // One context with many edges, referenced by many ops: each op copies the edges
+        let edges = 1_000;
+        let ops = 1_000;
+        let mut writer = Writer::new();
+        writer.write_bytes(MAGIC_UNCOMPRESSED);
+        writer.write_byte(FORMAT_VERSION);
+        writer.write_id(&[1u8; 16]);
+        writer.write_string("");
+        writer.write_varint(0); // authors
+        writer.write_varint(0); // parents
+        writer.write_signed_varint(0); // created_at
+        writer.write_varint(0); // properties
+        writer.write_varint(1); // relation_types
+        writer.write_id(&[2u8; 16]);
+        writer.write_varint(0); // languages
+        writer.write_varint(0); // units
+        writer.write_varint(0); // objects
+        writer.write_varint(1); // context_ids
+        writer.write_id(&[3u8; 16]);
+        writer.write_varint(1); // contexts
+        writer.write_varint(0); // root_id
+        writer.write_varint(edges);
+        for _ in 0..edges {
+            writer.write_varint(0);
+            writer.write_varint(0);
+        }
+        writer.write_varint(ops);
+        for i in 0..ops {
+            writer.write_byte(1); // CreateEntity
+            let mut id = [0u8; 16];
+            id[..8].copy_from_slice(&i.to_be_bytes());
+            writer.write_id(&id);
+            writer.write_varint(0); // values
+            writer.write_varint(0); // context_ref
+        }
+
+        let bytes = writer.into_bytes();
+        assert!(matches!(
+            decode_edit(&bytes),
+            Err(DecodeError::AllocationBudgetExceeded { field: "context_edges", .. })
+        ));
+        // The same edit decodes when the caller grants a larger budget
+        let decoded = decode_edit_with_alloc_budget(&bytes, 64 * 1024 * 1024).unwrap();
+        assert_eq!(decoded.ops.len(), ops as usize);
+    }
+
+    #[test]
+    fn test_alloc_budget_explicit_limit() {
+        let edit = make_test_edit();
+        let encoded = encode_edit(&edit).unwrap();
+        assert!(matches!(
+            decode_edit_with_alloc_budget(&encoded, 16),
+            Err(DecodeError::AllocationBudgetExceeded { .. })
+        ));
+        assert_eq!(decode_edit_with_alloc_budget(&encoded, 4096).unwrap(), edit);
+    }
+
+    #[test]
+    fn test_alloc_budget_allows_dense_edits() {
+        // Many small values have the highest decoded-to-encoded size ratio
+        let props: Vec<Id> = (0..100u8).map(|i| [i; 16]).collect();
+        let mut builder = crate::model::EditBuilder::new([1u8; 16]);
+        for i in 0..2_000u32 {
+            let mut id = [0u8; 16];
+            id[..4].copy_from_slice(&i.to_be_bytes());
+            builder = builder.create_entity(id, |mut e| {
+                for prop in &props {
+                    e = e.bool(*prop, i % 2 == 0);
+                }
+                e
+            });
+        }
+        let edit = builder.build();
+
+        let encoded = encode_edit(&edit).unwrap();
+        assert_eq!(decode_edit(&encoded).unwrap().ops, edit.ops);
+        #[cfg(feature = "zstd")]
+        {
+            let compressed = encode_edit_compressed(&edit, 3).unwrap();
+            assert_eq!(decode_edit(&compressed).unwrap().ops, edit.ops);
+        }
+    }
+
+    #[test]
+    fn test_preserve_unknown_op_types() {
+        let mut edit = make_test_edit();
+        edit.ops.insert(1, Op::Unknown(crate::model::UnknownOp { op_type: 0x20, bytes: Cow::Owned(vec![1, 2, 3]) }));
+
+        for options in [EncodeOptions::new(), EncodeOptions::columnar(), EncodeOptions::intern_values(2)] {
+            let bytes = encode_edit_with_options(&edit, options).unwrap();
+            assert!(matches!(decode_edit(&bytes), Err(DecodeError::InvalidOpType { op_type: 0x20 })));
+
+            let decoded = decode_edit_with_options(&bytes, DecodeOptions::preserve_unknown()).unwrap();
+            assert_eq!(decoded.ops, edit.ops);
+            assert_eq!(encode_edit_with_options(&decoded, options).unwrap(), bytes);
+        }
+
+        #[cfg(feature = "zstd")]
+        {
+            let compressed = encode_edit_compressed(&edit, 3).unwrap();
+            let decoded = decode_edit_with_options(&compressed, DecodeOptions::preserve_unknown()).unwrap();
+            assert_eq!(decoded.ops, edit.ops);
+        }
+
+        // Op type 0 is never valid
+        let mut writer = Writer::new();
+        writer.write_byte(0);
+        let mut reader = Reader::new(writer.as_bytes());
+        let dicts = WireDictionaries::default();
+        assert!(matches!(
+            decode_op_impl(&mut reader, &dicts, true),
+            Err(DecodeError::InvalidOpType { op_type: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_keep_properties() {
+        let name = [10u8; 16];
+        let edit = crate::model::EditBuilder::new([9u8; 16])
+            .create_entity([4u8; 16], |e| {
+                e.text(name, "Alice", None).embedding([11u8; 16], EmbeddingSubType::Float32, 2, vec![0; 8])
+            })
+            .create_entity([5u8; 16], |e| e.embedding([11u8; 16], EmbeddingSubType::Float32, 2, vec![0; 8]))
+            .update_entity([4u8; 16], |u| u.set_text(name, "Alicia", None).unset_all([11u8; 16]))
+            .build();
+        let mut expected = edit.clone();
+        for op in &mut expected.ops {
+            match op {
+                Op::CreateEntity(ce) => ce.values.retain(|pv| pv.property == name),
+                Op::UpdateEntity(ue) => ue.set_properties.retain(|pv| pv.property == name),
+                _ => {}
+            }
+        }
+
+        let encodings = [
+            encode_edit(&edit).unwrap(),
+            #[cfg(feature = "zstd")]
+            encode_edit_compressed(&edit, 3).unwrap(),
+            encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap(),
+            encode_edit_with_options(&edit, EncodeOptions::intern_values(2)).unwrap(),
+        ];
+        for bytes in &encodings {
+            let decoded = decode_edit_with_options(bytes, DecodeOptions::keep_properties([name])).unwrap();
+            assert_eq!(decoded.ops, expected.ops);
+        }
+    }
+
+    #[test]
+    fn test_encode_with_context_matches_options() {
+        let small = make_test_edit();
+        let large = crate::model::EditBuilder::new([9u8; 16])
+            .authors([[3u8; 16], [2u8; 16]])
+            .create_entity([4u8; 16], |e| e.text([10u8; 16], "x", None).text([11u8; 16], "x", None))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([4u8; 16]).to([3u8; 16]))
+            .build();
+        let mut duplicate_authors = small.clone();
+        duplicate_authors.authors = vec![[2u8; 16], [2u8; 16]];
+
+        let all_options = [
+            EncodeOptions::new(),
+            EncodeOptions::canonical(),
+            EncodeOptions::columnar(),
+            EncodeOptions::intern_values(2),
+        ];
+        for options in all_options {
+            let mut context = EncodeContext::with_options(options);
+            // Alternate sizes and a failure so stale state would show up
+            for edit in [&large, &small, &large] {
+                let expected = encode_edit_with_options(edit, options).unwrap();
+                assert_eq!(encode_edit_with_context(edit, &mut context).unwrap(), expected.as_slice());
+                if options.canonical {
+                    assert!(encode_edit_with_context(&duplicate_authors, &mut context).is_err());
+                }
+            }
+        }
+    }
+
+    /// Cheap in-tree counterpart of the `fuzz/` targets: every truncation
+    /// and a sweep of single-byte mutations must decode (and peek and parse
+    /// lazily) to `Ok` or `Err`, never panic or hang.
+    #[test]
+    fn test_mutated_input_never_panics() {
+        let edit = crate::model::EditBuilder::new([1u8; 16])
+            .name("seed")
+            .author([2u8; 16])
+            .create_entity([3u8; 16], |e| {
+                e.text([10u8; 16], "hello", Some([20u8; 16]))
+                    .int64([11u8; 16], -5, Some([21u8; 16]))
+                    .decimal([12u8; 16], 0, DecimalMantissa::Big(vec![1u8, 0, 0, 0, 0, 0, 0, 0, 0].into()), None)
+                    .embedding([13u8; 16], EmbeddingSubType::Binary, 3, vec![0b101u8])
+            })
+            .update_entity([3u8; 16], |u| u.set_bool([14u8; 16], true).unset_all([11u8; 16]))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]).position("a"))
+            .delete_entity([4u8; 16])
+            .build();
+        let seeds = [
+            encode_edit(&edit).unwrap(),
+            encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap(),
+            encode_edit_with_options(&edit, EncodeOptions::intern_values(1)).unwrap(),
+            #[cfg(feature = "zstd")]
+            encode_edit_compressed(&edit, 3).unwrap(),
+        ];
+
+        let probe = |input: &[u8]| {
+            let _ = decode_edit(input);
+            let _ = crate::codec::peek_header(input);
+            if let Ok(lazy) = crate::codec::LazyEdit::parse(input) {
+                let _ = lazy.ops().count();
+            }
+        };
+        for seed in &seeds {
+            for len in 0..seed.len() {
+                probe(&seed[..len]);
+            }
+            let mut mutated = seed.clone();
+            for i in 0..mutated.len() {
+                for byte in [0x00, 0x01, 0x7f, 0x80, 0xff] {
+                    let original = mutated[i];
+                    mutated[i] = byte;
+                    probe(&mutated);
+                    mutated[i] = original;
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,120 @@
+//! Op filtering during decode.
+//!
+//! An [`OpFilter`] in [`DecodeOptions::op_filter`](crate::codec::DecodeOptions::op_filter)
+//! drops ops while decoding. Ops rejected by type, and entity ops rejected
+//! by ID, are skipped over without decoding their values:
+//!
+//! ```ignore
+//! // Only the relations of a large import
+//! let filter = OpFilter::new().op_types([5]);
+//! let edit = decode_edit_with_options(&bytes, DecodeOptions::op_filter(filter))?;
+//! ```
+
+use crate::collections::FxHashSet;
+use crate::model::{Id, Op};
+use crate::prelude::*;
+
+/// Selects ops by wire op type (see [`Op::op_type`]) and target ID (see
+/// [`Op::id`]).
+///
+/// An op is kept if it matches every criterion that is set; the default
+/// filter keeps everything.
+#[derive(Debug, Clone, Default)]
+pub struct OpFilter {
+    op_types: Option<Vec<u8>>,
+    ids: Option<FxHashSet<Id>>,
+}
+
+impl OpFilter {
+    /// Creates a filter that keeps every op.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps only ops of the given wire types.
+    pub fn op_types(mut self, op_types: impl IntoIterator<Item = u8>) -> Self {
+        self.op_types = Some(op_types.into_iter().collect());
+        self
+    }
+
+    /// Keeps only ops targeting one of the given IDs.
+    ///
+    /// Unknown ops have no visible target and never match.
+    pub fn ids(mut self, ids: impl IntoIterator<Item = Id>) -> Self {
+        self.ids = Some(ids.into_iter().collect());
+        self
+    }
+
+    /// Returns true if ops of this wire type can match.
+    pub fn matches_type(&self, op_type: u8) -> bool {
+        self.op_types.as_ref().is_none_or(|types| types.contains(&op_type))
+    }
+
+    /// Returns true if ops targeting `id` can match.
+    pub fn matches_id(&self, id: &Id) -> bool {
+        self.ids.as_ref().is_none_or(|ids| ids.contains(id))
+    }
+
+    /// Returns true if the filter keeps `op`.
+    pub fn matches(&self, op: &Op<'_>) -> bool {
+        let id_matches = match op {
+            Op::Unknown(_) => self.ids.is_none(),
+            _ => self.matches_id(&op.id()),
+        };
+        self.matches_type(op.op_type()) && id_matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit, decode_edit_with_options, encode_edit, encode_edit_with_options, DecodeOptions, EncodeOptions};
+    use crate::model::{Edit, EditBuilder};
+
+    fn edit() -> Edit<'static> {
+        let mut builder = EditBuilder::new([1u8; 16]).name("Filter");
+        for i in 0..10u8 {
+            builder = builder
+                .create_entity([i; 16], |e| e.text([100u8; 16], "name", None).int64([101u8; 16], i as i64, None))
+                .create_relation(|r| r.id([i + 50; 16]).relation_type([102u8; 16]).from([i; 16]).to([0u8; 16]))
+                .update_entity([i; 16], |u| u.set_text([100u8; 16], "renamed", None));
+        }
+        builder.delete_entity([3u8; 16]).build()
+    }
+
+    #[test]
+    fn test_op_filter_decode() {
+        let edit = edit();
+        let filters = [
+            OpFilter::new().op_types([5]),
+            OpFilter::new().ids([[3u8; 16], [55u8; 16]]),
+            OpFilter::new().op_types([1, 3]).ids([[3u8; 16]]),
+            OpFilter::new(),
+        ];
+        let encodings = [
+            encode_edit(&edit).unwrap(),
+            #[cfg(feature = "zstd")]
+            crate::codec::encode_edit_compressed(&edit, 3).unwrap(),
+            encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap(),
+            encode_edit_with_options(&edit, EncodeOptions { intern_values: Some(2), ..EncodeOptions::new() }).unwrap(),
+        ];
+        for filter in filters {
+            let expected: Vec<_> = edit.ops.iter().filter(|op| filter.matches(op)).cloned().collect();
+            for bytes in &encodings {
+                let decoded = decode_edit_with_options(bytes, DecodeOptions::op_filter(filter.clone())).unwrap();
+                assert_eq!(decoded.ops, expected);
+            }
+        }
+        assert_eq!(decode_edit(&encodings[0]).unwrap(), edit);
+    }
+
+    #[test]
+    fn test_op_filter_matches() {
+        let edit = edit();
+        let filter = OpFilter::new().op_types([1, 2]).ids([[4u8; 16]]);
+        let kept: Vec<_> = edit.ops.iter().filter(|op| filter.matches(op)).map(|op| op.op_type()).collect();
+        assert_eq!(kept, [1, 2]);
+        assert!(OpFilter::new().matches_type(200));
+        assert!(!OpFilter::new().ids([[4u8; 16]]).matches_id(&[5u8; 16]));
+    }
+}
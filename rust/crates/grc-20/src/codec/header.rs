@@ -0,0 +1,179 @@
+//! Header-only inspection of encoded edits.
+//!
+//! [`peek_header`] reads the envelope, header, and op count without decoding
+//! any ops, so services can route or filter edits by author, age, or size
+//! cheaply. For compressed edits only a prefix is decompressed (except for
+//! LZ4, whose blocks must be decompressed in full).
+
+use crate::codec::compression::{decompress_envelope_prefix, Codec};
+use crate::codec::edit::{decode_preamble, read_version};
+use crate::codec::layout::{read_text_table, LAYOUT_EMBEDDING_BLOB, LAYOUT_INTERNED_TEXT};
+use crate::codec::primitives::Reader;
+use crate::codec::versions::FormatVersion;
+use crate::error::DecodeError;
+use crate::limits::{default_alloc_budget, MAGIC_EXTENDED, MAGIC_UNCOMPRESSED, MAX_OPS_PER_EDIT};
+use crate::model::Id;
+use crate::prelude::*;
+
+/// Bytes of a compressed edit decompressed on the first attempt. Headers
+/// with large dictionaries take more attempts, each four times larger.
+const PEEK_PREFIX: usize = 4096;
+
+/// The envelope and header of an encoded edit, from [`peek_header`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditHeader {
+    pub id: Id,
+    pub name: String,
+    pub authors: Vec<Id>,
+    /// Empty before [`FormatVersion::V2`].
+    pub parents: Vec<Id>,
+    pub created_at: i64,
+    /// Number of ops, as declared by the header (the ops are not checked).
+    pub op_count: usize,
+    /// The compression codec, or `None` for GRC2 and GRC2X.
+    pub compressed: Option<Codec>,
+    pub version: FormatVersion,
+    /// Uncompressed size in bytes (as declared by a compressed envelope).
+    pub size: usize,
+}
+
+/// Reads an edit's header without decoding its ops.
+///
+/// Accepts the same formats as [`decode_edit`](crate::codec::decode_edit).
+/// A successful peek does not mean the ops decode.
+pub fn peek_header(input: &[u8]) -> Result<EditHeader, DecodeError> {
+    let Some(codec) = Codec::from_magic(input) else {
+        return parse_header(input, input.len(), None);
+    };
+    let mut limit = PEEK_PREFIX;
+    loop {
+        let (prefix, size) = decompress_envelope_prefix(codec, &input[5..], limit)?;
+        // A short prefix means the stream ended before the declared size
+        if prefix.len() < limit.min(size) {
+            return Err(DecodeError::UncompressedSizeMismatch { declared: size, actual: prefix.len() });
+        }
+        match parse_header(&prefix, size, Some(codec)) {
+            Err(DecodeError::UnexpectedEof { .. }) if prefix.len() < size => limit = limit.saturating_mul(4),
+            result => return result,
+        }
+    }
+}
+
+fn parse_header(input: &[u8], size: usize, compressed: Option<Codec>) -> Result<EditHeader, DecodeError> {
+    let mut reader = Reader::with_alloc_budget(input, default_alloc_budget(size));
+    let extended = if input.starts_with(MAGIC_EXTENDED) {
+        reader.read_bytes(MAGIC_EXTENDED.len(), "magic")?;
+        true
+    } else if input.starts_with(MAGIC_UNCOMPRESSED) {
+        reader.read_bytes(MAGIC_UNCOMPRESSED.len(), "magic")?;
+        false
+    } else {
+        let mut found = [0u8; 4];
+        let n = input.len().min(4);
+        found[..n].copy_from_slice(&input[..n]);
+        return Err(DecodeError::InvalidMagic { found });
+    };
+    let version = read_version(&mut reader)?;
+    let flags = if extended { reader.read_byte("layout_flags")? } else { 0 };
+
+    let preamble = decode_preamble(&mut reader, version)?;
+    if flags & LAYOUT_INTERNED_TEXT != 0 {
+        read_text_table(&mut reader)?;
+    }
+    if flags & LAYOUT_EMBEDDING_BLOB != 0 {
+        reader.read_varint("blob_len")?;
+    }
+    let op_count = reader.read_varint("op_count")? as usize;
+    if op_count > MAX_OPS_PER_EDIT {
+        return Err(DecodeError::LengthExceedsLimit { field: "ops", len: op_count, max: MAX_OPS_PER_EDIT });
+    }
+
+    Ok(EditHeader {
+        id: preamble.id,
+        name: preamble.name.to_string(),
+        authors: preamble.authors,
+        parents: preamble.parents,
+        created_at: preamble.created_at,
+        op_count,
+        compressed,
+        version,
+        size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{encode_edit, encode_edit_with_options, EncodeOptions};
+    use crate::model::EditBuilder;
+
+    fn edit() -> crate::model::Edit<'static> {
+        let mut builder = EditBuilder::new([1u8; 16]).name("Import").author([2u8; 16]).parent([3u8; 16]).created_at(42);
+        for i in 0..250u8 {
+            builder = builder.create_entity([i; 16], |e| e.text([255u8; 16], "some repeated text", None).int64([i; 16], i as i64, None));
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_peek_header() {
+        let edit = edit();
+        let expected = |size, compressed| EditHeader {
+            id: [1u8; 16],
+            name: "Import".into(),
+            authors: vec![[2u8; 16]],
+            parents: vec![[3u8; 16]],
+            created_at: 42,
+            op_count: 250,
+            compressed,
+            version: FormatVersion::CURRENT,
+            size,
+        };
+
+        let plain = encode_edit(&edit).unwrap();
+        assert_eq!(peek_header(&plain).unwrap(), expected(plain.len(), None));
+
+        // The property dictionary alone outgrows the first prefix
+        #[cfg(feature = "zstd")]
+        {
+            let compressed = crate::codec::encode_edit_compressed(&edit, 3).unwrap();
+            assert_eq!(peek_header(&compressed).unwrap(), expected(plain.len(), Some(Codec::Zstd)));
+        }
+
+        let extended = encode_edit_with_options(&edit, EncodeOptions { columnar: true, intern_values: Some(2), ..EncodeOptions::new() }).unwrap();
+        assert_eq!(peek_header(&extended).unwrap(), expected(extended.len(), None));
+    }
+
+    #[test]
+    fn test_peek_header_errors() {
+        let plain = encode_edit(&edit()).unwrap();
+        assert!(matches!(peek_header(&plain[..40]), Err(DecodeError::UnexpectedEof { .. })));
+        assert!(matches!(peek_header(b"NOPE"), Err(DecodeError::InvalidMagic { .. })));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_peek_header_short_stream() {
+        use crate::codec::compression::compress_envelope;
+        use crate::codec::primitives::Writer;
+
+        // A frame that decodes cleanly to fewer bytes than declared
+        let plain = encode_edit(&edit()).unwrap();
+        let envelope = compress_envelope(&plain[..100], Codec::Zstd, 3).unwrap();
+        let mut writer = Writer::new();
+        writer.write_bytes(&envelope[..5]);
+        writer.write_varint(plain.len() as u64);
+        writer.write_bytes(&envelope[6..]);
+        let tampered = writer.into_bytes();
+        let expected = DecodeError::UncompressedSizeMismatch { declared: plain.len(), actual: 100 };
+        assert_eq!(peek_header(&tampered), Err(expected));
+        assert!(crate::codec::decode_edit(&tampered).is_err());
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_peek_header_short_lz4_block() {
+        let input = [0x47, 0x52, 0x43, 0x32, 0x4c, 0xe0, 0x07, 0x6c, 0x47, 0x52, 0x43, 0x32, 0x02, 0x01];
+        assert!(matches!(peek_header(&input), Err(DecodeError::UncompressedSizeMismatch { declared: 992, .. })));
+    }
+}
@@ -0,0 +1,829 @@
+//! Extended op layouts (GRC2X, spec Section 6.7).
+//!
+//! The extended container carries the same header, dictionaries, and
+//! contexts as the row layout (spec Section 6.3). A layout flags byte after
+//! the version selects how the ops that follow are arranged:
+//!
+//! ```text
+//! "GRC2X" version:u8 layout_flags:u8 header dictionaries contexts [texts] [blob_len] op_count ops [blob]
+//! ```
+//!
+//! With [`LAYOUT_INTERNED_TEXT`] set, a table of repeated TEXT strings
+//! follows the contexts (`count:varint string[count]`), and every TEXT value
+//! starts with a `text_ref:varint` (0 = inline string follows, 1+ = table
+//! entry) before the usual language reference.
+//!
+//! With [`LAYOUT_EMBEDDING_BLOB`] set, EMBEDDING payloads are moved to a
+//! blob at the end of the edit. A `blob_len:varint` precedes the op count,
+//! the last `blob_len` bytes of the edit are the blob, and every EMBEDDING
+//! value is `sub_type:u8 dims:varint offset:varint` with its data at
+//! `blob[offset..]`. Decoders that skip embeddings never touch the blob, and
+//! it can be memory-mapped separately.
+//!
+//! With [`LAYOUT_COLUMNAR`] set, ops are split into columns so that similar
+//! bytes end up next to each other, which helps zstd on bulk imports:
+//!
+//! ```text
+//! op_types:         u8[op_count]
+//! entity_ids:       ID[n]                 -- one per CreateEntity, in op order
+//! value_counts:     varint[n]
+//! context_refs:     varint[n]
+//! value_properties: varint[sum(value_counts)]
+//! property_streams: (len:varint bytes)[property_count]  -- values grouped by property
+//! other_ops:        len:varint bytes      -- remaining ops, row-encoded, in op order
+//! ```
+//!
+//! Extended layouts are a transport optimization. The decoder reconstructs
+//! the exact op sequence, so content hashes and signatures are computed over
+//! the row layout as usual.
+
+use alloc::borrow::Cow;
+
+use crate::collections::FxHashMap;
+use crate::prelude::*;
+
+use crate::codec::edit::{decode_preamble, read_op_count, read_version, DecodeOptions};
+use crate::codec::metering::{record_header, record_op, DecodeMeter, DecodeUsage};
+use crate::codec::op::{decode_op_filtered, decode_op_impl, resolve_context_ref, rewrite_op_values, OP_CREATE_ENTITY};
+use crate::codec::primitives::{Reader, Writer};
+use crate::codec::value::{decode_value, skip_value};
+use crate::error::{DecodeError, EncodeError};
+use crate::limits::{
+    MAGIC_EXTENDED, MAGIC_UNCOMPRESSED, MAX_DICT_SIZE, MAX_STRING_LEN, MAX_VALUES_PER_ENTITY,
+};
+use crate::model::{CreateEntity, DataType, Edit, Id, Op, PropertyValue, Value, WireDictionaries};
+
+/// Layout flag: CreateEntity ops are stored column-wise.
+pub const LAYOUT_COLUMNAR: u8 = 0x01;
+
+/// Layout flag: repeated TEXT values are stored once in a table.
+pub const LAYOUT_INTERNED_TEXT: u8 = 0x02;
+
+/// Layout flag: EMBEDDING payloads are stored in a trailing blob.
+pub const LAYOUT_EMBEDDING_BLOB: u8 = 0x04;
+
+/// Layout flag bits that are not yet assigned.
+const LAYOUT_RESERVED_MASK: u8 = !(LAYOUT_COLUMNAR | LAYOUT_INTERNED_TEXT | LAYOUT_EMBEDDING_BLOB);
+
+// =============================================================================
+// DECODING
+// =============================================================================
+
+/// Decodes a GRC2X edit, reconstructing the ops in their original order.
+pub(crate) fn decode_extended<'a>(
+    input: &'a [u8],
+    alloc_budget: usize,
+    options: &DecodeOptions,
+    meter: &mut dyn DecodeMeter,
+) -> Result<Edit<'a>, DecodeError> {
+    let mut reader = Reader::with_alloc_budget(input, alloc_budget);
+
+    // Skip magic (already validated)
+    reader.read_bytes(MAGIC_EXTENDED.len(), "magic")?;
+    let version = read_version(&mut reader)?;
+
+    let flags = reader.read_byte("layout_flags")?;
+    if flags & LAYOUT_RESERVED_MASK != 0 {
+        return Err(DecodeError::ReservedBitsSet { context: "layout_flags" });
+    }
+
+    let mut preamble = decode_preamble(&mut reader, version)?;
+    if flags & LAYOUT_INTERNED_TEXT != 0 {
+        preamble.dicts.texts = Some(read_text_table(&mut reader)?);
+    }
+    if flags & LAYOUT_EMBEDDING_BLOB != 0 {
+        read_embedding_blob(&mut reader)?;
+    }
+    let mut usage = DecodeUsage::default();
+    record_header(meter, &mut usage, reader.position())?;
+    let op_count = read_op_count(&mut reader)?;
+
+    let ops = if flags & LAYOUT_COLUMNAR != 0 {
+        let ops = decode_columnar_ops(&mut reader, op_count, &preamble.dicts, options)?;
+        for op in &ops {
+            record_op(meter, &mut usage, reader.position(), Some(op))?;
+        }
+        ops
+    } else {
+        let mut ops = Vec::with_capacity(op_count);
+        for _ in 0..op_count {
+            let op = decode_op_filtered(&mut reader, &preamble.dicts, options)?;
+            record_op(meter, &mut usage, reader.position(), op.as_ref())?;
+            ops.extend(op);
+        }
+        ops
+    };
+
+    Ok(Edit {
+        id: preamble.id,
+        name: Cow::Borrowed(preamble.name),
+        authors: preamble.authors,
+        parents: preamble.parents,
+        created_at: preamble.created_at,
+        ops,
+    })
+}
+
+fn decode_columnar_ops<'a>(
+    reader: &mut Reader<'a>,
+    op_count: usize,
+    dicts: &WireDictionaries<'a>,
+    options: &DecodeOptions,
+) -> Result<Vec<Op<'a>>, DecodeError> {
+    let filter = options.op_filter.as_ref();
+    let op_types = reader.read_bytes(op_count, "op_types")?;
+    let entity_count = op_types.iter().filter(|&&t| t == OP_CREATE_ENTITY).count();
+
+    reader.charge_items::<Id>(entity_count, "entity_ids")?;
+    reader.charge_items::<usize>(entity_count, "value_counts")?;
+    reader.charge_items::<u32>(entity_count, "context_refs")?;
+    let mut entity_ids = Vec::with_capacity(entity_count);
+    for _ in 0..entity_count {
+        entity_ids.push(reader.read_id("entity_ids")?);
+    }
+
+    let mut value_counts = Vec::with_capacity(entity_count);
+    let mut total_values = 0usize;
+    for _ in 0..entity_count {
+        let count = reader.read_varint("value_counts")? as usize;
+        if count > MAX_VALUES_PER_ENTITY {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "values",
+                len: count,
+                max: MAX_VALUES_PER_ENTITY,
+            });
+        }
+        total_values += count;
+        value_counts.push(count);
+    }
+
+    let mut context_refs = Vec::with_capacity(entity_count);
+    for _ in 0..entity_count {
+        context_refs.push(reader.read_varint("context_refs")? as u32);
+    }
+
+    // Each value property takes at least one byte, which bounds the allocation
+    if total_values > reader.remaining_len() {
+        return Err(DecodeError::UnexpectedEof { context: "value_properties" });
+    }
+    reader.charge_items::<usize>(total_values, "value_properties")?;
+    let mut value_properties = Vec::with_capacity(total_values);
+    for _ in 0..total_values {
+        let index = reader.read_varint("value_properties")? as usize;
+        if index >= dicts.properties.len() {
+            return Err(DecodeError::IndexOutOfBounds {
+                dict: "properties",
+                index,
+                size: dicts.properties.len(),
+            });
+        }
+        value_properties.push(index);
+    }
+
+    reader.charge_items::<Reader<'_>>(dicts.properties.len(), "property_stream")?;
+    let mut streams = Vec::with_capacity(dicts.properties.len());
+    for _ in 0..dicts.properties.len() {
+        streams.push(read_stream(reader, "property_stream")?);
+    }
+    let mut other_ops = read_stream(reader, "other_ops")?;
+
+    // Reassemble ops in their original order. The columns were sized from
+    // op_types above, but take from them with checked access so a decoder
+    // bug surfaces as an error rather than a panic.
+    let mut ops = Vec::with_capacity(op_count);
+    let mut entities = entity_ids.into_iter().zip(value_counts).zip(context_refs);
+    let mut value_properties = value_properties.as_slice();
+    for &op_type in op_types {
+        if op_type != OP_CREATE_ENTITY {
+            if other_ops.peek_byte("other_ops")? != op_type {
+                return Err(DecodeError::MalformedEncoding { context: "op_types column does not match other_ops" });
+            }
+            reader.lend_budget(&mut other_ops);
+            let op = decode_op_filtered(&mut other_ops, dicts, options);
+            other_ops.lend_budget(reader);
+            ops.extend(op?);
+            continue;
+        }
+
+        let Some(((id, count), context_ref)) = entities.next() else {
+            return Err(DecodeError::MalformedEncoding { context: "entity columns shorter than op_types" });
+        };
+        if count > value_properties.len() {
+            return Err(DecodeError::MalformedEncoding { context: "value_properties shorter than value_counts" });
+        }
+        let (properties, rest) = value_properties.split_at(count);
+        value_properties = rest;
+
+        reader.charge_items::<PropertyValue>(count, "values")?;
+        let mut values = Vec::with_capacity(count);
+        for &prop_index in properties {
+            let (Some(&(property, data_type)), Some(stream)) =
+                (dicts.properties.get(prop_index), streams.get_mut(prop_index))
+            else {
+                return Err(DecodeError::IndexOutOfBounds {
+                    dict: "properties",
+                    index: prop_index,
+                    size: dicts.properties.len(),
+                });
+            };
+            if options.keep_properties.as_ref().is_some_and(|keep| !keep.contains(&property)) {
+                skip_value(stream, data_type, dicts)?;
+                continue;
+            }
+            reader.lend_budget(stream);
+            let value = decode_value(stream, data_type, dicts);
+            stream.lend_budget(reader);
+            values.push(PropertyValue { property, value: value? });
+        }
+
+        let context = resolve_context_ref(reader, dicts, context_ref)?;
+
+        // Values share the property streams, so filtered entities are
+        // decoded anyway and dropped here
+        let op = Op::CreateEntity(CreateEntity { id, values, context });
+        if filter.is_none_or(|f| f.matches(&op)) {
+            ops.push(op);
+        }
+    }
+
+    if !other_ops.is_empty() || streams.iter().any(|s| !s.is_empty()) {
+        return Err(DecodeError::MalformedEncoding { context: "trailing bytes in columnar stream" });
+    }
+
+    Ok(ops)
+}
+
+/// Reads the interned TEXT table, borrowing the strings from the input.
+pub(crate) fn read_text_table<'a>(reader: &mut Reader<'a>) -> Result<Vec<Cow<'a, str>>, DecodeError> {
+    let count = reader.read_varint("text_count")? as usize;
+    if count > MAX_DICT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "texts",
+            len: count,
+            max: MAX_DICT_SIZE,
+        });
+    }
+    // Each entry takes at least one byte, which bounds the allocation
+    if count > reader.remaining_len() {
+        return Err(DecodeError::UnexpectedEof { context: "texts" });
+    }
+    reader.charge_items::<Cow<'a, str>>(count, "texts")?;
+    let mut texts = Vec::with_capacity(count);
+    for _ in 0..count {
+        texts.push(Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "texts")?));
+    }
+    Ok(texts)
+}
+
+/// Reads the blob length and splits the blob off the end of the reader's
+/// data, so embedding values resolve their offsets against it.
+pub(crate) fn read_embedding_blob(reader: &mut Reader<'_>) -> Result<(), DecodeError> {
+    let len = reader.read_varint("blob_len")? as usize;
+    reader.split_embedding_blob(len)
+}
+
+/// Reads a length-prefixed stream and returns a reader over it.
+fn read_stream<'a>(reader: &mut Reader<'a>, field: &'static str) -> Result<Reader<'a>, DecodeError> {
+    let len = reader.read_varint(field)? as usize;
+    reader.take(len, field)
+}
+
+// =============================================================================
+// ENCODING
+// =============================================================================
+
+/// Collects the TEXT strings that occur at least `threshold` times.
+///
+/// The most frequent strings come first so they get the shortest references;
+/// ties are broken by byte order to keep the table deterministic.
+pub(crate) fn collect_text_table(edit: &Edit, threshold: usize) -> Vec<String> {
+    let mut counts: FxHashMap<&str, usize> = FxHashMap::default();
+    for op in &edit.ops {
+        let values = match op {
+            Op::CreateEntity(ce) => &ce.values,
+            Op::UpdateEntity(ue) => &ue.set_properties,
+            _ => continue,
+        };
+        for pv in values {
+            if let Value::Text { value, .. } = &pv.value {
+                *counts.entry(value.as_ref()).or_default() += 1;
+            }
+        }
+    }
+
+    let threshold = threshold.max(2);
+    let mut texts: Vec<(&str, usize)> = counts.into_iter().filter(|&(_, n)| n >= threshold).collect();
+    texts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    texts.into_iter().map(|(text, _)| text.to_string()).collect()
+}
+
+/// Rewrites an encoded edit into the GRC2X container, adding `flags`.
+///
+/// The input may be a row-layout edit or an extended edit without the
+/// requested flags. Value and op bytes are copied verbatim, so this is a
+/// pure reshuffle of an already validated encoding.
+pub(crate) fn encode_extended(row: &[u8], flags: u8) -> Result<Vec<u8>, EncodeError> {
+    transcode(row, flags).map_err(|_| EncodeError::InvalidInput {
+        context: "row encoding could not be rewritten into extended layout",
+    })
+}
+
+fn transcode(row: &[u8], flags: u8) -> Result<Vec<u8>, DecodeError> {
+    // The input is our own encoding of an in-memory edit, so decoding it
+    // again is not bounded by the allocation budget
+    let mut reader = Reader::with_alloc_budget(row, usize::MAX);
+    let input_flags = if row.starts_with(MAGIC_EXTENDED) {
+        reader.read_bytes(MAGIC_EXTENDED.len(), "magic")?;
+        let version = read_version(&mut reader)?;
+        (version, reader.read_byte("layout_flags")?)
+    } else {
+        reader.read_bytes(MAGIC_UNCOMPRESSED.len(), "magic")?;
+        (read_version(&mut reader)?, 0)
+    };
+    let (version, input_flags) = input_flags;
+    if input_flags & (LAYOUT_COLUMNAR | LAYOUT_RESERVED_MASK) != 0 {
+        return Err(DecodeError::MalformedEncoding { context: "edit is already columnar" });
+    }
+    if input_flags & LAYOUT_EMBEDDING_BLOB != 0 {
+        return Err(DecodeError::MalformedEncoding { context: "edit already has an embedding blob" });
+    }
+    let flags = flags | input_flags;
+
+    let preamble_start = reader.position();
+    let mut preamble = decode_preamble(&mut reader, version)?;
+    if input_flags & LAYOUT_INTERNED_TEXT != 0 {
+        preamble.dicts.texts = Some(read_text_table(&mut reader)?);
+    }
+    let preamble_end = reader.position();
+    let op_count = read_op_count(&mut reader)?;
+
+    let mut writer = Writer::with_capacity(row.len() + 16);
+    writer.write_bytes(MAGIC_EXTENDED);
+    writer.write_byte(version.as_byte());
+    writer.write_byte(flags);
+    writer.write_bytes(&row[preamble_start..preamble_end]);
+
+    if flags & LAYOUT_EMBEDDING_BLOB == 0 {
+        writer.write_varint(op_count as u64);
+        if flags & LAYOUT_COLUMNAR != 0 {
+            write_columnar_ops(&mut writer, row, &mut reader, op_count, &preamble.dicts)?;
+        } else {
+            writer.write_bytes(reader.remaining());
+        }
+        return Ok(writer.into_bytes());
+    }
+
+    let (ops, blob) = extract_embeddings(&mut reader, op_count, &preamble.dicts)?;
+    writer.write_varint(blob.len() as u64);
+    writer.write_varint(op_count as u64);
+    if flags & LAYOUT_COLUMNAR != 0 {
+        let mut reader = Reader::with_alloc_budget(ops.as_bytes(), usize::MAX);
+        reader.set_embedding_blob(Some(blob.as_bytes()));
+        write_columnar_ops(&mut writer, ops.as_bytes(), &mut reader, op_count, &preamble.dicts)?;
+    } else {
+        writer.write_bytes(ops.as_bytes());
+    }
+    writer.write_bytes(blob.as_bytes());
+
+    Ok(writer.into_bytes())
+}
+
+/// Rewrites row-encoded ops with their EMBEDDING payloads moved to a blob,
+/// returning the ops and the blob.
+fn extract_embeddings<'a>(
+    reader: &mut Reader<'a>,
+    op_count: usize,
+    dicts: &WireDictionaries<'a>,
+) -> Result<(Writer, Writer), DecodeError> {
+    let mut ops = Writer::with_capacity(reader.remaining_len());
+    let mut blob = Writer::new();
+    let mut rewrite = |reader: &mut Reader<'a>, data_type: DataType, out: &mut Writer| {
+        let start = reader.remaining();
+        if data_type != DataType::Embedding {
+            skip_value(reader, data_type, dicts)?;
+            out.write_bytes(&start[..start.len() - reader.remaining_len()]);
+            return Ok(());
+        }
+        let Value::Embedding { sub_type, dims, data } = decode_value(reader, data_type, dicts)? else {
+            return Err(DecodeError::MalformedEncoding { context: "EMBEDDING value decoded to another type" });
+        };
+        out.write_byte(sub_type as u8);
+        out.write_varint(dims as u64);
+        out.write_varint(blob.len() as u64);
+        blob.write_bytes(&data);
+        Ok(())
+    };
+    for _ in 0..op_count {
+        rewrite_op_values(reader, dicts, &mut ops, &mut rewrite)?;
+    }
+    Ok((ops, blob))
+}
+
+fn write_columnar_ops<'a>(
+    writer: &mut Writer,
+    row: &[u8],
+    reader: &mut Reader<'a>,
+    op_count: usize,
+    dicts: &WireDictionaries<'a>,
+) -> Result<(), DecodeError> {
+    let mut op_types = Vec::with_capacity(op_count);
+    let mut entity_ids = Writer::new();
+    let mut value_counts = Writer::new();
+    let mut context_refs = Writer::new();
+    let mut value_properties = Writer::new();
+    let mut streams = vec![Writer::new(); dicts.properties.len()];
+    let mut other_ops = Writer::new();
+
+    for _ in 0..op_count {
+        let op_start = reader.position();
+        let op_type = reader.clone().read_byte("op_type")?;
+        op_types.push(op_type);
+
+        if op_type != OP_CREATE_ENTITY {
+            // The row encoder writes unknown ops length-prefixed, so they
+            // can be skipped like extension ops
+            decode_op_impl(reader, dicts, true)?;
+            other_ops.write_bytes(&row[op_start..reader.position()]);
+            continue;
+        }
+
+        reader.read_byte("op_type")?;
+        entity_ids.write_id(&reader.read_id("entity_id")?);
+        let value_count = reader.read_varint("value_count")?;
+        value_counts.write_varint(value_count);
+        for _ in 0..value_count {
+            let prop_index = reader.read_varint("property")? as usize;
+            let (_, data_type) = *dicts.properties.get(prop_index).ok_or(DecodeError::IndexOutOfBounds {
+                dict: "properties",
+                index: prop_index,
+                size: dicts.properties.len(),
+            })?;
+            value_properties.write_varint(prop_index as u64);
+
+            let value_start = reader.position();
+            decode_value(reader, data_type, dicts)?;
+            streams[prop_index].write_bytes(&row[value_start..reader.position()]);
+        }
+        context_refs.write_varint(reader.read_varint("context_ref")?);
+    }
+
+    writer.write_bytes(&op_types);
+    writer.write_bytes(entity_ids.as_bytes());
+    writer.write_bytes(value_counts.as_bytes());
+    writer.write_bytes(context_refs.as_bytes());
+    writer.write_bytes(value_properties.as_bytes());
+    for stream in &streams {
+        writer.write_bytes_prefixed(stream.as_bytes());
+    }
+    writer.write_bytes_prefixed(other_ops.as_bytes());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::{decode_edit, encode_edit, encode_edit_with_options, EncodeOptions};
+    use crate::model::{
+        Context, ContextEdge, CreateRelation, DeleteEntity, EditBuilder, UpdateEntity, Value,
+    };
+
+    fn make_bulk_edit(count: u8) -> Edit<'static> {
+        let name_prop = [10u8; 16];
+        let pop_prop = [11u8; 16];
+        let region_prop = [13u8; 16];
+        let mut builder = EditBuilder::new([1u8; 16])
+            .name("Bulk")
+            .author([2u8; 16])
+            .created_at(1_700_000_000_000_000);
+        for i in 0..count {
+            builder = builder.create_entity([i; 16], |e| {
+                e.text(name_prop, format!("City {}", i), None)
+                    .int64(pop_prop, 1000 + i as i64, None)
+                    .text(region_prop, if i % 2 == 0 { "Europe" } else { "Asia" }, None)
+            });
+        }
+        builder.build()
+    }
+
+    fn make_mixed_edit() -> Edit<'static> {
+        let context = Context {
+            root_id: [50u8; 16],
+            edges: vec![ContextEdge { type_id: [51u8; 16], to_entity_id: [52u8; 16] }],
+        };
+        let mut edit = make_bulk_edit(3);
+        edit.ops.insert(1, Op::DeleteEntity(DeleteEntity { id: [9u8; 16], context: None }));
+        edit.ops.push(Op::CreateEntity(CreateEntity {
+            id: [20u8; 16],
+            values: vec![
+                PropertyValue {
+                    property: [10u8; 16],
+                    value: Value::Text { value: Cow::Borrowed("Hola"), language: Some([30u8; 16]) },
+                },
+                PropertyValue {
+                    property: [12u8; 16],
+                    value: Value::Float64 { value: 1.5, unit: Some([31u8; 16]) },
+                },
+            ],
+            context: Some(context.clone()),
+        }));
+        edit.ops.push(Op::CreateRelation(CreateRelation {
+            id: [21u8; 16],
+            relation_type: [40u8; 16],
+            from: [0u8; 16],
+            from_is_value_ref: false,
+            from_space: None,
+            from_version: None,
+            to: [20u8; 16],
+            to_is_value_ref: false,
+            to_space: None,
+            to_version: None,
+            entity: None,
+            position: Some(Cow::Borrowed("a")),
+            context: None,
+        }));
+        edit.ops.push(Op::UpdateEntity(UpdateEntity {
+            id: [20u8; 16],
+            set_properties: vec![
+                PropertyValue { property: [11u8; 16], value: Value::Int64 { value: 7, unit: None } },
+                PropertyValue {
+                    property: [13u8; 16],
+                    value: Value::Text { value: Cow::Borrowed("Europe"), language: None },
+                },
+            ],
+            unset_values: vec![],
+            context: Some(context),
+        }));
+        edit
+    }
+
+    #[test]
+    fn test_columnar_roundtrip() {
+        let edit = make_mixed_edit();
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        assert_eq!(&encoded[0..5], MAGIC_EXTENDED);
+
+        let decoded = decode_edit(&encoded).unwrap();
+        assert_eq!(decoded.id, edit.id);
+        assert_eq!(decoded.name, edit.name);
+        assert_eq!(decoded.authors, edit.authors);
+        assert_eq!(decoded.created_at, edit.created_at);
+        assert_eq!(decoded.ops, edit.ops);
+    }
+
+    #[test]
+    fn test_columnar_canonical_matches_row() {
+        let edit = make_mixed_edit();
+        let options = EncodeOptions { canonical: true, columnar: true, ..Default::default() };
+        let columnar = encode_edit_with_options(&edit, options).unwrap();
+        let decoded = decode_edit(&columnar).unwrap();
+
+        // Re-encoding the decoded ops canonically gives the same row bytes
+        let row = encode_edit_with_options(&edit, EncodeOptions::canonical()).unwrap();
+        assert_eq!(encode_edit_with_options(&decoded, EncodeOptions::canonical()).unwrap(), row);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_columnar_compressed_roundtrip() {
+        use crate::codec::encode_edit_compressed_with_options;
+
+        let edit = make_bulk_edit(200);
+        let row = encode_edit_compressed_with_options(&edit, 3, EncodeOptions::new()).unwrap();
+        let columnar = encode_edit_compressed_with_options(&edit, 3, EncodeOptions::columnar()).unwrap();
+        assert!(columnar.len() <= row.len());
+
+        let decoded = decode_edit(&columnar).unwrap();
+        assert_eq!(decoded.ops, edit.ops);
+    }
+
+    #[test]
+    fn test_columnar_empty_edit() {
+        let edit = EditBuilder::new([1u8; 16]).build();
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        assert!(decode_edit(&encoded).unwrap().ops.is_empty());
+    }
+
+    #[test]
+    fn test_reserved_layout_flags_rejected() {
+        let edit = make_bulk_edit(2);
+        let mut encoded = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        encoded[6] |= 0x80;
+        assert!(matches!(
+            decode_edit(&encoded),
+            Err(DecodeError::ReservedBitsSet { context: "layout_flags" })
+        ));
+    }
+
+    #[test]
+    fn test_extended_row_passthrough() {
+        let edit = make_mixed_edit();
+        let row = encode_edit(&edit).unwrap();
+        let extended = encode_extended(&row, 0).unwrap();
+        assert_eq!(decode_edit(&extended).unwrap().ops, edit.ops);
+    }
+
+    #[test]
+    fn test_columnar_truncated() {
+        let edit = make_bulk_edit(5);
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        for len in [encoded.len() - 1, encoded.len() / 2] {
+            assert!(decode_edit(&encoded[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_interned_roundtrip() {
+        let edit = make_mixed_edit();
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::intern_values(2)).unwrap();
+        assert_eq!(&encoded[0..5], MAGIC_EXTENDED);
+        assert_eq!(encoded[6], LAYOUT_INTERNED_TEXT);
+        assert_eq!(decode_edit(&encoded).unwrap().ops, edit.ops);
+    }
+
+    #[test]
+    fn test_interned_smaller_than_row() {
+        let edit = make_bulk_edit(100);
+        let row = encode_edit(&edit).unwrap();
+        let interned = encode_edit_with_options(&edit, EncodeOptions::intern_values(2)).unwrap();
+        assert!(interned.len() < row.len());
+        assert_eq!(decode_edit(&interned).unwrap().ops, edit.ops);
+    }
+
+    #[test]
+    fn test_interned_text_borrowed() {
+        // A large interned string referenced by every op decodes without a copy per reference
+        let text = "x".repeat(4096);
+        let mut builder = EditBuilder::new([1u8; 16]);
+        for i in 0..2_000u32 {
+            let mut id = [0u8; 16];
+            id[..4].copy_from_slice(&i.to_be_bytes());
+            builder = builder.create_entity(id, |e| e.text([10u8; 16], text.clone(), None));
+        }
+        let edit = builder.build();
+        let options = EncodeOptions { columnar: true, ..EncodeOptions::intern_values(2) };
+        let encoded = encode_edit_with_options(&edit, options).unwrap();
+
+        let decoded = decode_edit(&encoded).unwrap();
+        assert_eq!(decoded.ops, edit.ops);
+        for op in &decoded.ops {
+            let Op::CreateEntity(ce) = op else { panic!("unexpected op {:?}", op) };
+            assert!(matches!(&ce.values[0].value, Value::Text { value: Cow::Borrowed(_), .. }));
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_interned_text_copies_charged_when_owned() {
+        use crate::codec::{decode_edit_with_alloc_budget, encode_edit_compressed_with_options};
+
+        // Decompressed edits are decoded into owned values, so every
+        // reference to an interned string is a copy
+        let text = "x".repeat(4096);
+        let mut builder = EditBuilder::new([1u8; 16]);
+        for i in 0..2_000u32 {
+            let mut id = [0u8; 16];
+            id[..4].copy_from_slice(&i.to_be_bytes());
+            builder = builder.create_entity(id, |e| e.text([10u8; 16], text.clone(), None));
+        }
+        let edit = builder.build();
+        let compressed = encode_edit_compressed_with_options(&edit, 3, EncodeOptions::intern_values(2)).unwrap();
+        assert!(matches!(
+            decode_edit(&compressed),
+            Err(DecodeError::AllocationBudgetExceeded { field: "text", .. })
+        ));
+        let decoded = decode_edit_with_alloc_budget(&compressed, 16 * 1024 * 1024).unwrap();
+        assert_eq!(decoded.ops, edit.ops);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_interned_columnar_canonical() {
+        use crate::codec::encode_edit_compressed_with_options;
+
+        let edit = make_mixed_edit();
+        let options = EncodeOptions {
+            canonical: true,
+            columnar: true,
+            intern_values: Some(2),
+            ..EncodeOptions::new()
+        };
+        let encoded = encode_edit_with_options(&edit, options).unwrap();
+        assert_eq!(encoded[6], LAYOUT_COLUMNAR | LAYOUT_INTERNED_TEXT);
+
+        let decoded = decode_edit(&encoded).unwrap();
+        let row = encode_edit_with_options(&edit, EncodeOptions::canonical()).unwrap();
+        assert_eq!(encode_edit_with_options(&decoded, EncodeOptions::canonical()).unwrap(), row);
+
+        let compressed = encode_edit_compressed_with_options(&edit, 3, options).unwrap();
+        assert_eq!(decode_edit(&compressed).unwrap().ops, edit.ops);
+    }
+
+    #[test]
+    fn test_interning_below_threshold_uses_row_layout() {
+        let edit = make_bulk_edit(4);
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::intern_values(2)).unwrap();
+        assert_eq!(&encoded[0..5], MAGIC_EXTENDED);
+
+        // "Europe" and "Asia" occur twice each, so nothing reaches a threshold of 3
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::intern_values(3)).unwrap();
+        assert_eq!(&encoded[0..4], MAGIC_UNCOMPRESSED);
+        assert_ne!(&encoded[0..5], MAGIC_EXTENDED);
+    }
+
+    #[test]
+    fn test_collect_text_table_order() {
+        let mut edit = make_bulk_edit(5);
+        assert_eq!(collect_text_table(&edit, 2), vec!["Europe".to_string(), "Asia".to_string()]);
+        assert_eq!(collect_text_table(&edit, 3), vec!["Europe".to_string()]);
+
+        // Ties are broken by byte order
+        edit.ops.truncate(4);
+        assert_eq!(collect_text_table(&edit, 0), vec!["Asia".to_string(), "Europe".to_string()]);
+    }
+
+    #[test]
+    fn test_interned_text_ref_out_of_bounds() {
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text([10u8; 16], "x", None))
+            .create_entity([4u8; 16], |e| e.text([10u8; 16], "x", None))
+            .build();
+        let mut encoded = encode_edit_with_options(&edit, EncodeOptions::intern_values(2)).unwrap();
+
+        // The last entity ends with: text_ref(1), language(0), context_ref (5 bytes)
+        let ref_pos = encoded.len() - 7;
+        assert_eq!(encoded[ref_pos], 1);
+        encoded[ref_pos] = 2;
+        assert!(matches!(
+            decode_edit(&encoded),
+            Err(DecodeError::IndexOutOfBounds { dict: "texts", .. })
+        ));
+    }
+
+    fn make_embedding_edit() -> Edit<'static> {
+        use crate::model::EmbeddingSubType;
+
+        let mut builder = EditBuilder::new([1u8; 16]);
+        for i in 0..4u8 {
+            builder = builder.create_entity([i + 2; 16], |e| {
+                e.text([10u8; 16], "Europe", None)
+                    .embedding([11u8; 16], EmbeddingSubType::Int8, 8, vec![i; 8])
+                    .int64([12u8; 16], i as i64, None)
+            });
+        }
+        builder
+            .update_entity([2u8; 16], |u| {
+                u.set_text([10u8; 16], "Asia", None)
+                    .set_embedding([11u8; 16], EmbeddingSubType::Binary, 16, vec![0xAB, 0xCD])
+            })
+            .delete_entity([3u8; 16])
+            .build()
+    }
+
+    #[test]
+    fn test_embedding_blob_roundtrip() {
+        use crate::codec::{decode_edit_with_options, DecodeOptions};
+
+        let edit = make_embedding_edit();
+        let blob = EncodeOptions::embedding_blob();
+        for options in [
+            blob,
+            EncodeOptions { columnar: true, ..blob },
+            EncodeOptions { canonical: true, intern_values: Some(2), ..blob },
+            EncodeOptions { canonical: true, columnar: true, intern_values: Some(2), embedding_blob: true },
+        ] {
+            let encoded = encode_edit_with_options(&edit, options).unwrap();
+            assert_ne!(encoded[6] & LAYOUT_EMBEDDING_BLOB, 0);
+            // Embedding payloads trail the edit in op order
+            assert!(encoded.ends_with(&[3, 3, 3, 3, 3, 3, 3, 3, 0xAB, 0xCD]));
+            assert_eq!(decode_edit(&encoded).unwrap(), edit);
+
+            let names = decode_edit_with_options(&encoded, DecodeOptions::keep_properties([[10u8; 16]])).unwrap();
+            assert_eq!(names.ops.len(), edit.ops.len());
+
+            #[cfg(feature = "zstd")]
+            {
+                let compressed = crate::codec::encode_edit_compressed_with_options(&edit, 3, options).unwrap();
+                assert_eq!(decode_edit(&compressed).unwrap(), edit);
+            }
+        }
+
+        let row = encode_edit(&edit).unwrap();
+        let encoded = encode_edit_with_options(&edit, blob).unwrap();
+        let lazy = crate::codec::LazyEdit::parse(&encoded).unwrap();
+        assert_eq!(lazy.to_edit().unwrap(), edit);
+        assert!(lazy.op_bytes(0).len() < 40);
+        assert_eq!(crate::codec::peek_header(&encoded).unwrap().op_count, edit.ops.len());
+        assert_eq!(encode_edit(&decode_edit(&encoded).unwrap()).unwrap(), row);
+    }
+
+    #[test]
+    fn test_embedding_blob_truncated() {
+        let edit = make_embedding_edit();
+        let encoded = encode_edit_with_options(&edit, EncodeOptions::embedding_blob()).unwrap();
+        assert!(matches!(
+            decode_edit(&encoded[..encoded.len() - 1]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+    }
+}
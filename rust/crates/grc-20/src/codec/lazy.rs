@@ -0,0 +1,235 @@
+//! On-demand op decoding.
+//!
+//! [`LazyEdit::parse`] decodes the header and dictionaries, then records
+//! where each op starts in one scan that skips over values instead of
+//! decoding them. Ops are decoded individually with [`LazyEdit::op`], so a
+//! consumer that needs only the relations of a large import never decodes
+//! its values.
+//!
+//! ```ignore
+//! let lazy = LazyEdit::parse(&bytes)?;
+//! for i in 0..lazy.len() {
+//!     if lazy.op_type(i) == 5 {
+//!         let Op::CreateRelation(rel) = lazy.op(i)? else { unreachable!() };
+//!         index.insert(rel.from, rel.to);
+//!     }
+//! }
+//! ```
+
+use crate::codec::edit::{decode_preamble, read_version};
+use crate::codec::layout::{
+    read_embedding_blob, read_text_table, LAYOUT_COLUMNAR, LAYOUT_EMBEDDING_BLOB, LAYOUT_INTERNED_TEXT,
+};
+use crate::codec::op::{decode_op_impl, skip_op};
+use crate::codec::primitives::Reader;
+use crate::codec::versions::FormatVersion;
+use crate::codec::Codec;
+use crate::error::DecodeError;
+use crate::limits::{default_alloc_budget, MAGIC_EXTENDED, MAGIC_UNCOMPRESSED, MAX_EDIT_SIZE, MAX_OPS_PER_EDIT};
+use crate::model::{Edit, Id, Op, WireDictionaries};
+use crate::prelude::*;
+
+use alloc::borrow::Cow;
+
+/// An encoded edit whose ops are decoded on demand.
+///
+/// Borrows from uncompressed GRC2 or row-layout GRC2X input; decompress
+/// compressed edits first with [`decompress`](crate::codec::decompress).
+/// The columnar layout has no per-op boundaries and is rejected.
+#[derive(Debug, Clone)]
+pub struct LazyEdit<'a> {
+    pub id: Id,
+    pub name: &'a str,
+    pub authors: Vec<Id>,
+    pub parents: Vec<Id>,
+    pub created_at: i64,
+    pub version: FormatVersion,
+    data: &'a [u8],
+    embedding_blob: Option<&'a [u8]>,
+    dicts: WireDictionaries<'a>,
+    /// Start offset of each op, followed by the end of the last op.
+    offsets: Vec<usize>,
+}
+
+impl<'a> LazyEdit<'a> {
+    /// Decodes the header and indexes the op boundaries.
+    ///
+    /// Values are skipped, not validated; an op that scans cleanly can
+    /// still fail in [`LazyEdit::op`].
+    pub fn parse(input: &'a [u8]) -> Result<Self, DecodeError> {
+        if Codec::from_magic(input).is_some() {
+            return Err(DecodeError::MalformedEncoding { context: "LazyEdit needs uncompressed input" });
+        }
+        if input.len() > MAX_EDIT_SIZE {
+            return Err(DecodeError::LengthExceedsLimit { field: "edit", len: input.len(), max: MAX_EDIT_SIZE });
+        }
+        let mut reader = Reader::with_alloc_budget(input, default_alloc_budget(input.len()));
+        let extended = if input.starts_with(MAGIC_EXTENDED) {
+            reader.read_bytes(MAGIC_EXTENDED.len(), "magic")?;
+            true
+        } else if input.starts_with(MAGIC_UNCOMPRESSED) {
+            reader.read_bytes(MAGIC_UNCOMPRESSED.len(), "magic")?;
+            false
+        } else {
+            let mut found = [0u8; 4];
+            let n = input.len().min(4);
+            found[..n].copy_from_slice(&input[..n]);
+            return Err(DecodeError::InvalidMagic { found });
+        };
+        let version = read_version(&mut reader)?;
+        let flags = if extended { reader.read_byte("layout_flags")? } else { 0 };
+        if flags & !(LAYOUT_COLUMNAR | LAYOUT_INTERNED_TEXT | LAYOUT_EMBEDDING_BLOB) != 0 {
+            return Err(DecodeError::ReservedBitsSet { context: "layout_flags" });
+        }
+        if flags & LAYOUT_COLUMNAR != 0 {
+            return Err(DecodeError::MalformedEncoding { context: "LazyEdit does not support the columnar layout" });
+        }
+
+        let mut preamble = decode_preamble(&mut reader, version)?;
+        if flags & LAYOUT_INTERNED_TEXT != 0 {
+            preamble.dicts.texts = Some(read_text_table(&mut reader)?);
+        }
+        if flags & LAYOUT_EMBEDDING_BLOB != 0 {
+            read_embedding_blob(&mut reader)?;
+        }
+        let op_count = reader.read_varint("op_count")? as usize;
+        if op_count > MAX_OPS_PER_EDIT {
+            return Err(DecodeError::LengthExceedsLimit { field: "ops", len: op_count, max: MAX_OPS_PER_EDIT });
+        }
+        reader.charge_items::<usize>(op_count + 1, "op_offsets")?;
+
+        let mut offsets = Vec::with_capacity(op_count + 1);
+        for _ in 0..op_count {
+            offsets.push(reader.position());
+            skip_op(&mut reader, &preamble.dicts, false)?;
+        }
+        offsets.push(reader.position());
+
+        Ok(Self {
+            id: preamble.id,
+            name: preamble.name,
+            authors: preamble.authors,
+            parents: preamble.parents,
+            created_at: preamble.created_at,
+            version,
+            data: input,
+            embedding_blob: reader.embedding_blob(),
+            dicts: preamble.dicts,
+            offsets,
+        })
+    }
+
+    /// Returns the number of ops.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns true if the edit has no ops.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the wire op type of op `i` (see [`Op::op_type`]) without
+    /// decoding it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()`.
+    pub fn op_type(&self, i: usize) -> u8 {
+        assert!(i < self.len(), "op index {} out of range for {} ops", i, self.len());
+        self.data[self.offsets[i]]
+    }
+
+    /// Returns the encoded bytes of op `i`. With out-of-line embeddings
+    /// (see [`EncodeOptions::embedding_blob`](crate::codec::EncodeOptions::embedding_blob))
+    /// these hold offsets in place of the embedding data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()`.
+    pub fn op_bytes(&self, i: usize) -> &'a [u8] {
+        &self.data[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Decodes op `i`, borrowing from the input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.len()`.
+    pub fn op(&self, i: usize) -> Result<Op<'a>, DecodeError> {
+        let mut reader = Reader::new(self.op_bytes(i));
+        reader.set_embedding_blob(self.embedding_blob);
+        decode_op_impl(&mut reader, &self.dicts, false)
+    }
+
+    /// Decodes every op in order.
+    pub fn ops(&self) -> impl Iterator<Item = Result<Op<'a>, DecodeError>> + '_ {
+        (0..self.len()).map(|i| self.op(i))
+    }
+
+    /// Decodes the whole edit.
+    pub fn to_edit(&self) -> Result<Edit<'a>, DecodeError> {
+        Ok(Edit {
+            id: self.id,
+            name: Cow::Borrowed(self.name),
+            authors: self.authors.clone(),
+            parents: self.parents.clone(),
+            created_at: self.created_at,
+            ops: self.ops().collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit, encode_edit, encode_edit_with_options, EncodeOptions};
+    use crate::model::{DecimalMantissa, EditBuilder, EmbeddingSubType};
+
+    fn edit() -> Edit<'static> {
+        let mut builder = EditBuilder::new([1u8; 16]).name("Lazy").author([2u8; 16]).created_at(7);
+        for i in 0..20u8 {
+            builder = builder
+                .create_entity([i; 16], |e| {
+                    e.text([100u8; 16], "repeated", None)
+                        .decimal([101u8; 16], -2, DecimalMantissa::I64(1234), None)
+                        .embedding([102u8; 16], EmbeddingSubType::Int8, 4, vec![1, 2, 3, 4])
+                        .point([103u8; 16], 1.0, 2.0, Some(3.0))
+                })
+                .create_relation(|r| r.id([i + 50; 16]).relation_type([104u8; 16]).from([i; 16]).to([0u8; 16]).position("a0"))
+                .update_entity([i; 16], |u| u.set_text([100u8; 16], "changed", None).unset_all([103u8; 16]));
+        }
+        builder.delete_entity([3u8; 16]).build()
+    }
+
+    #[test]
+    fn test_lazy_edit_matches_decode() {
+        let edit = edit();
+        let interned = EncodeOptions { intern_values: Some(2), ..EncodeOptions::new() };
+        for bytes in [encode_edit(&edit).unwrap(), encode_edit_with_options(&edit, interned).unwrap()] {
+            let lazy = LazyEdit::parse(&bytes).unwrap();
+            assert_eq!(lazy.len(), edit.ops.len());
+            assert_eq!((lazy.id, lazy.name, lazy.created_at), (edit.id, "Lazy", 7));
+            assert_eq!(lazy.to_edit().unwrap(), decode_edit(&bytes).unwrap());
+            for (i, op) in edit.ops.iter().enumerate() {
+                assert_eq!(lazy.op_type(i), op.op_type());
+            }
+            assert_eq!(lazy.op(1).unwrap(), edit.ops[1]);
+        }
+    }
+
+    #[test]
+    fn test_lazy_edit_rejects() {
+        let edit = edit();
+        #[cfg(feature = "zstd")]
+        {
+            let compressed = crate::codec::encode_edit_compressed(&edit, 3).unwrap();
+            assert!(matches!(LazyEdit::parse(&compressed), Err(DecodeError::MalformedEncoding { .. })));
+        }
+        let columnar = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        assert!(matches!(LazyEdit::parse(&columnar), Err(DecodeError::MalformedEncoding { .. })));
+
+        let bytes = encode_edit(&edit).unwrap();
+        assert!(matches!(LazyEdit::parse(&bytes[..bytes.len() - 1]), Err(DecodeError::UnexpectedEof { .. })));
+    }
+}
@@ -0,0 +1,279 @@
+//! Merkle proofs of op inclusion.
+//!
+//! The ops of an edit form the leaves of a Merkle tree with the shape and
+//! hashing of RFC 6962 (Certificate Transparency):
+//!
+//! ```text
+//! leaf = SHA-256(0x00 || op_bytes)
+//! node = SHA-256(0x01 || left || right)
+//! ```
+//!
+//! where `op_bytes` is the op encoded canonically together with the
+//! dictionaries and contexts it references, so a leaf does not depend on
+//! the other ops. For `n > 1` leaves the left subtree holds the largest
+//! power of two below `n`; the root of an edit without ops is SHA-256 of
+//! the empty string.
+//!
+//! A publisher announces [`op_root`] next to the edit (or signs it); a
+//! light client holding the root checks a single op with its [`OpProof`]
+//! without downloading the rest:
+//!
+//! ```
+//! use grc_20::codec::merkle::{op_root, prove_op, verify_proof};
+//! use grc_20::EditBuilder;
+//!
+//! let edit = EditBuilder::new([1u8; 16])
+//!     .create_entity([2u8; 16], |e| e.text([3u8; 16], "Alice", None))
+//!     .delete_entity([4u8; 16])
+//!     .build();
+//! let root = op_root(&edit).unwrap();
+//! let proof = prove_op(&edit, 1).unwrap();
+//! assert!(verify_proof(&root, &edit.ops[1], &proof));
+//! assert!(!verify_proof(&root, &edit.ops[0], &proof));
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::edit::encode_op_standalone;
+use crate::error::EncodeError;
+use crate::model::{Edit, Op};
+use crate::prelude::*;
+
+/// A SHA-256 digest.
+pub type Hash = [u8; 32];
+
+/// Proof that an op is the `index`-th of `op_count` ops under a root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpProof {
+    pub index: usize,
+    pub op_count: usize,
+    /// Sibling hashes from the leaf up to the root.
+    pub path: Vec<Hash>,
+}
+
+/// Returns the leaf hash of an op.
+pub fn leaf_hash(op: &Op<'_>) -> Result<Hash, EncodeError> {
+    let bytes = encode_op_standalone(op)?;
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(&bytes);
+    Ok(hasher.finalize().into())
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Largest power of two strictly less than `n` (for `n > 1`).
+fn split(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaves[0],
+        n => {
+            let k = split(n);
+            node_hash(&subtree_root(&leaves[..k]), &subtree_root(&leaves[k..]))
+        }
+    }
+}
+
+fn audit_path(index: usize, leaves: &[Hash], path: &mut Vec<Hash>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split(n);
+    if index < k {
+        audit_path(index, &leaves[..k], path);
+        path.push(subtree_root(&leaves[k..]));
+    } else {
+        audit_path(index - k, &leaves[k..], path);
+        path.push(subtree_root(&leaves[..k]));
+    }
+}
+
+/// The leaf hashes of an edit, for computing the root and several proofs
+/// without re-encoding the ops each time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpTree {
+    leaves: Vec<Hash>,
+}
+
+impl OpTree {
+    /// Hashes every op of `edit`.
+    pub fn new(edit: &Edit<'_>) -> Result<Self, EncodeError> {
+        let leaves = edit.ops.iter().map(leaf_hash).collect::<Result<_, _>>()?;
+        Ok(Self { leaves })
+    }
+
+    /// Returns the root hash.
+    pub fn root(&self) -> Hash {
+        subtree_root(&self.leaves)
+    }
+
+    /// Returns the number of leaves (ops).
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns true if the edit has no ops.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Returns the inclusion proof of the op at `index`, or `None` if out
+    /// of range.
+    pub fn prove(&self, index: usize) -> Option<OpProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        audit_path(index, &self.leaves, &mut path);
+        Some(OpProof { index, op_count: self.leaves.len(), path })
+    }
+}
+
+/// Returns the Merkle root over the ops of `edit`.
+pub fn op_root(edit: &Edit<'_>) -> Result<Hash, EncodeError> {
+    Ok(OpTree::new(edit)?.root())
+}
+
+/// Returns the inclusion proof of `edit.ops[index]`.
+///
+/// Use [`OpTree`] to prove several ops of the same edit.
+pub fn prove_op(edit: &Edit<'_>, index: usize) -> Result<OpProof, EncodeError> {
+    OpTree::new(edit)?.prove(index).ok_or(EncodeError::InvalidInput { context: "op index out of range" })
+}
+
+/// Checks that `op` is included under `root` at the proof's index.
+///
+/// Returns false for a wrong op, root, or path, and for ops that cannot be
+/// encoded.
+pub fn verify_proof(root: &Hash, op: &Op<'_>, proof: &OpProof) -> bool {
+    match leaf_hash(op) {
+        Ok(leaf) => verify_leaf(root, &leaf, proof),
+        Err(_) => false,
+    }
+}
+
+/// Checks a leaf hash against a root (RFC 9162, Section 2.1.3.2).
+pub fn verify_leaf(root: &Hash, leaf: &Hash, proof: &OpProof) -> bool {
+    if proof.index >= proof.op_count {
+        return false;
+    }
+    let mut f = proof.index;
+    let mut s = proof.op_count - 1;
+    let mut r = *leaf;
+    for p in &proof.path {
+        if s == 0 {
+            return false;
+        }
+        if f & 1 == 1 || f == s {
+            r = node_hash(p, &r);
+            while f & 1 == 0 && f != 0 {
+                f >>= 1;
+                s >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        f >>= 1;
+        s >>= 1;
+    }
+    s == 0 && r == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    fn edit(n: u8) -> Edit<'static> {
+        let mut builder = EditBuilder::new([1u8; 16]);
+        for i in 0..n {
+            builder = builder.create_entity([i + 10; 16], |e| e.text([3u8; 16], format!("entity {i}"), None));
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_every_op_proves_for_all_sizes() {
+        for n in 0..=17 {
+            let edit = edit(n);
+            let tree = OpTree::new(&edit).unwrap();
+            let root = tree.root();
+            assert_eq!(root, op_root(&edit).unwrap());
+            for (i, op) in edit.ops.iter().enumerate() {
+                let proof = tree.prove(i).unwrap();
+                assert!(proof.path.len() <= 5);
+                assert!(verify_proof(&root, op, &proof), "n={n} i={i}");
+                if n > 1 {
+                    let other = &edit.ops[(i + 1) % n as usize];
+                    assert!(!verify_proof(&root, other, &proof));
+                }
+            }
+            assert!(tree.prove(n as usize).is_none());
+        }
+        assert_eq!(op_root(&edit(0)).unwrap(), <Hash>::from(Sha256::digest([])));
+    }
+
+    #[test]
+    fn test_tampered_proofs_rejected() {
+        let edit = edit(5);
+        let root = op_root(&edit).unwrap();
+        let proof = prove_op(&edit, 4).unwrap();
+        assert!(verify_proof(&root, &edit.ops[4], &proof));
+
+        let mut bad = proof.clone();
+        bad.index = 3;
+        assert!(!verify_proof(&root, &edit.ops[4], &bad));
+        bad = proof.clone();
+        bad.op_count = 6;
+        assert!(!verify_proof(&root, &edit.ops[4], &bad));
+        bad = proof.clone();
+        bad.path.push([0u8; 32]);
+        assert!(!verify_proof(&root, &edit.ops[4], &bad));
+        bad = proof.clone();
+        bad.path[0][0] ^= 1;
+        assert!(!verify_proof(&root, &edit.ops[4], &bad));
+        assert!(!verify_proof(&[0u8; 32], &edit.ops[4], &proof));
+        assert!(matches!(prove_op(&edit, 5), Err(EncodeError::InvalidInput { .. })));
+    }
+
+    #[test]
+    fn test_leaf_independent_of_other_ops() {
+        // The same op hashes the same wherever it appears
+        let a = edit(3);
+        let mut b = edit(3);
+        b.ops.swap(0, 2);
+        b.ops.push(edit(1).ops.remove(0));
+        assert_eq!(leaf_hash(&a.ops[2]).unwrap(), leaf_hash(&b.ops[0]).unwrap());
+        assert_ne!(op_root(&a).unwrap(), op_root(&b).unwrap());
+
+        // Canonical: value order within an op does not matter
+        let x = EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.text([3u8; 16], "a", None).int64([4u8; 16], 1, None))
+            .build();
+        let y = EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.int64([4u8; 16], 1, None).text([3u8; 16], "a", None))
+            .build();
+        assert_eq!(op_root(&x).unwrap(), op_root(&y).unwrap());
+    }
+
+    #[test]
+    fn test_rfc6962_shape() {
+        // Three leaves: root = H(H(l0, l1), l2)
+        let edit = edit(3);
+        let leaves: Vec<Hash> = edit.ops.iter().map(|op| leaf_hash(op).unwrap()).collect();
+        let expected = node_hash(&node_hash(&leaves[0], &leaves[1]), &leaves[2]);
+        assert_eq!(op_root(&edit).unwrap(), expected);
+        assert_eq!(prove_op(&edit, 2).unwrap().path, vec![node_hash(&leaves[0], &leaves[1])]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Decode accounting for gateways.
+//!
+//! A [`DecodeMeter`] sees running totals while an edit decodes, so an API
+//! gateway can enforce per-tenant quotas and emit metrics from the same
+//! pass that produces the edit:
+//!
+//! ```rust
+//! use grc_20::codec::metering::{decode_edit_metered, Quota};
+//! use grc_20::{encode_edit, DecodeError, DecodeOptions, EditBuilder};
+//!
+//! let bytes = encode_edit(&EditBuilder::new([1u8; 16]).create_empty_entity([2u8; 16]).build()).unwrap();
+//!
+//! let mut quota = Quota::new().max_ops(1_000).max_values(10_000);
+//! let edit = decode_edit_metered(&bytes, DecodeOptions::new(), &mut quota).unwrap();
+//! assert_eq!(quota.usage().ops, 1);
+//!
+//! let mut quota = Quota::new().max_bytes(16);
+//! let err = decode_edit_metered(&bytes, DecodeOptions::new(), &mut quota).unwrap_err();
+//! assert_eq!(err, DecodeError::QuotaExceeded { quota: "bytes" });
+//! ```
+
+use crate::codec::edit::{decode_edit_impl, DecodeOptions};
+use crate::error::DecodeError;
+use crate::model::{Edit, Op};
+
+/// Running totals of one decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DecodeUsage {
+    /// Bytes of the edit read so far, after decompression.
+    pub bytes: usize,
+    /// Ops read so far, including ops dropped by an op filter.
+    pub ops: usize,
+    /// Property values decoded so far. Values skipped by
+    /// [`DecodeOptions::keep_properties`] are not counted.
+    pub values: usize,
+}
+
+/// Receives decode progress, e.g. to enforce quotas or record metrics.
+pub trait DecodeMeter {
+    /// Called with the running totals after the edit header and after each
+    /// op, so the last call carries the totals of the whole edit. Returning
+    /// `Err(quota)` stops decoding with [`DecodeError::QuotaExceeded`].
+    ///
+    /// In the columnar layout all ops are read at once, so every op is
+    /// reported after the last byte is read, and ops dropped by an op
+    /// filter are not counted.
+    fn record(&mut self, usage: DecodeUsage) -> Result<(), &'static str>;
+}
+
+/// Meters nothing.
+impl DecodeMeter for () {
+    fn record(&mut self, _usage: DecodeUsage) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+impl<F> DecodeMeter for F
+where
+    F: FnMut(DecodeUsage) -> Result<(), &'static str>,
+{
+    fn record(&mut self, usage: DecodeUsage) -> Result<(), &'static str> {
+        self(usage)
+    }
+}
+
+/// A meter with fixed limits, which fails with the quota names `"bytes"`,
+/// `"ops"`, and `"values"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quota {
+    pub max_bytes: Option<usize>,
+    pub max_ops: Option<usize>,
+    pub max_values: Option<usize>,
+    usage: DecodeUsage,
+}
+
+impl Quota {
+    /// Creates a quota without limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the bytes of the (decompressed) edit.
+    pub fn max_bytes(mut self, max: usize) -> Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    /// Limits the number of ops.
+    pub fn max_ops(mut self, max: usize) -> Self {
+        self.max_ops = Some(max);
+        self
+    }
+
+    /// Limits the number of values.
+    pub fn max_values(mut self, max: usize) -> Self {
+        self.max_values = Some(max);
+        self
+    }
+
+    /// Returns the totals of the last decode, up to where it stopped.
+    pub fn usage(&self) -> DecodeUsage {
+        self.usage
+    }
+}
+
+impl DecodeMeter for Quota {
+    fn record(&mut self, usage: DecodeUsage) -> Result<(), &'static str> {
+        self.usage = usage;
+        let over = |max: Option<usize>, n: usize| max.is_some_and(|max| n > max);
+        if over(self.max_bytes, usage.bytes) {
+            Err("bytes")
+        } else if over(self.max_ops, usage.ops) {
+            Err("ops")
+        } else if over(self.max_values, usage.values) {
+            Err("values")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Decodes an Edit like [`decode_edit_with_options`](crate::codec::decode_edit_with_options),
+/// reporting progress to `meter`.
+pub fn decode_edit_metered<'a>(
+    input: &'a [u8],
+    options: DecodeOptions,
+    meter: &mut dyn DecodeMeter,
+) -> Result<Edit<'a>, DecodeError> {
+    decode_edit_impl(input, options, meter)
+}
+
+/// Reports the edit header, read up to `position`.
+pub(crate) fn record_header(meter: &mut dyn DecodeMeter, usage: &mut DecodeUsage, position: usize) -> Result<(), DecodeError> {
+    usage.bytes = position;
+    meter.record(*usage).map_err(|quota| DecodeError::QuotaExceeded { quota })
+}
+
+/// Reports one op read up to `position`; `None` if a filter dropped it.
+pub(crate) fn record_op(
+    meter: &mut dyn DecodeMeter,
+    usage: &mut DecodeUsage,
+    position: usize,
+    op: Option<&Op<'_>>,
+) -> Result<(), DecodeError> {
+    usage.bytes = position;
+    usage.ops += 1;
+    usage.values += match op {
+        Some(Op::CreateEntity(ce)) => ce.values.len(),
+        Some(Op::UpdateEntity(ue)) => ue.set_properties.len(),
+        _ => 0,
+    };
+    meter.record(*usage).map_err(|quota| DecodeError::QuotaExceeded { quota })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{encode_edit, encode_edit_with_options, EncodeOptions, OpFilter};
+    use crate::model::EditBuilder;
+    use crate::prelude::*;
+
+    fn edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.text([10u8; 16], "a", None).int64([11u8; 16], 1, None))
+            .update_entity([2u8; 16], |u| u.set_text([10u8; 16], "b", None).unset_all([11u8; 16]))
+            .delete_entity([3u8; 16])
+            .build()
+    }
+
+    #[test]
+    fn test_decode_edit_metered() {
+        for options in [EncodeOptions::new(), EncodeOptions::columnar()] {
+            let bytes = encode_edit_with_options(&edit(), options).unwrap();
+            let mut calls = Vec::new();
+            let decoded = decode_edit_metered(&bytes, DecodeOptions::new(), &mut |usage| {
+                calls.push(usage);
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(decoded, edit());
+            assert_eq!(calls.len(), 4);
+            assert_eq!(calls[0].ops, 0);
+            assert_eq!(calls[3], DecodeUsage { bytes: bytes.len(), ops: 3, values: 3 });
+        }
+
+        // Dropped ops count, their values don't
+        let bytes = encode_edit(&edit()).unwrap();
+        let mut quota = Quota::new();
+        let options = DecodeOptions::op_filter(OpFilter::new().op_types([3]));
+        assert_eq!(decode_edit_metered(&bytes, options, &mut quota).unwrap().ops.len(), 1);
+        assert_eq!((quota.usage().ops, quota.usage().values), (3, 0));
+    }
+
+    #[test]
+    fn test_quota() {
+        let bytes = encode_edit(&edit()).unwrap();
+        let mut quota = Quota::new().max_values(2);
+        assert_eq!(
+            decode_edit_metered(&bytes, DecodeOptions::new(), &mut quota).unwrap_err(),
+            DecodeError::QuotaExceeded { quota: "values" }
+        );
+        // Stopped at the second op
+        assert_eq!(quota.usage().ops, 2);
+
+        let mut quota = Quota::new().max_ops(3).max_values(3).max_bytes(bytes.len());
+        assert!(decode_edit_metered(&bytes, DecodeOptions::new(), &mut quota).is_ok());
+        assert!(decode_edit_metered(&bytes, DecodeOptions::new(), &mut Quota::new().max_ops(2)).is_err());
+    }
+}
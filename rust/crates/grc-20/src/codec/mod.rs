@@ -2,15 +2,38 @@
 //!
 //! This module implements the GRC-20 v2 binary format (spec Section 6).
 
+pub mod chunking;
+pub mod compression;
 pub mod edit;
+pub mod filter;
+pub mod header;
+pub mod layout;
+pub mod lazy;
+pub mod merkle;
+pub mod metering;
 pub mod op;
 pub mod primitives;
+pub mod registry;
+pub mod roundtrip;
 pub mod value;
+pub mod versions;
 
+pub use compression::{Codec, CompressionLevel};
 pub use edit::{
-    decode_edit, decompress, encode_edit, encode_edit_compressed,
-    encode_edit_compressed_with_options, encode_edit_profiled, encode_edit_with_options,
-    EncodeOptions,
+    decode_edit, decode_edit_with_alloc_budget, decode_edit_with_options, decompress, encode_edit,
+    encode_edit_compressed, encode_edit_compressed_auto, encode_edit_compressed_with_codec,
+    encode_edit_compressed_with_options, encode_edit_with_context, encode_edit_with_options,
+    DecodeOptions, EncodeContext, EncodeOptions,
 };
+#[cfg(feature = "std")]
+pub use edit::encode_edit_profiled;
+pub use filter::OpFilter;
+pub use header::{peek_header, EditHeader};
+pub use lazy::LazyEdit;
+pub use metering::{decode_edit_metered, DecodeMeter, DecodeUsage, Quota};
+pub use op::EXTENSION_OP_TYPES;
 pub use primitives::{Reader, Writer, zigzag_decode, zigzag_encode};
+pub use registry::{CodecRegistry, ExtensionOp};
+pub use roundtrip::{verify_roundtrip, verify_roundtrip_with_options};
 pub use value::{decode_value, encode_value};
+pub use versions::{decode_any_version, upgrade, FormatVersion};
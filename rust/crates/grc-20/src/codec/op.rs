@@ -2,18 +2,25 @@
 //!
 //! Implements the wire format for operations (spec Section 6.4).
 
+use alloc::borrow::Cow;
+
+use crate::codec::edit::DecodeOptions;
 use crate::codec::primitives::{Reader, Writer};
-use crate::codec::value::{decode_position, decode_property_value, validate_position};
+use crate::codec::value::{
+    decode_position, decode_projected_value, skip_property_value, validate_position,
+};
+use crate::collections::FxHashSet;
 use crate::error::{DecodeError, EncodeError};
-use crate::limits::MAX_VALUES_PER_ENTITY;
+use crate::limits::{MAX_BYTES_LEN, MAX_VALUES_PER_ENTITY};
 use crate::model::{
-    CreateEntity, CreateRelation, CreateValueRef, DataType, DeleteEntity, DeleteRelation,
-    DictionaryBuilder, Op, PropertyValue, RestoreEntity, RestoreRelation,
+    Context, ContextEdge, CreateEntity, CreateRelation, CreateValueRef, DataType, DeleteEntity, DeleteRelation,
+    DictionaryBuilder, Id, Op, PropertyValue, RestoreEntity, RestoreRelation, UnknownOp,
     UnsetLanguage, UnsetValue, UnsetRelationField, UpdateEntity, UpdateRelation, WireDictionaries,
 };
+use crate::prelude::*;
 
 // Op type constants (grouped by lifecycle: Create, Update, Delete, Restore)
-const OP_CREATE_ENTITY: u8 = 1;
+pub(crate) const OP_CREATE_ENTITY: u8 = 1;
 const OP_UPDATE_ENTITY: u8 = 2;
 const OP_DELETE_ENTITY: u8 = 3;
 const OP_RESTORE_ENTITY: u8 = 4;
@@ -23,6 +30,13 @@ const OP_DELETE_RELATION: u8 = 7;
 const OP_RESTORE_RELATION: u8 = 8;
 const OP_CREATE_VALUE_REF: u8 = 9;
 
+/// Op types reserved for extensions (see [`crate::codec::registry`]).
+///
+/// Extension ops are encoded as `op_type, payload_len: varint, payload`, so a
+/// decoder that does not know the type can still keep the op as
+/// [`Op::Unknown`] and re-encode it unchanged.
+pub const EXTENSION_OP_TYPES: core::ops::RangeInclusive<u8> = 0x80..=0xFF;
+
 // UpdateEntity flags
 const FLAG_HAS_SET_PROPERTIES: u8 = 0x01;
 const FLAG_HAS_UNSET_VALUES: u8 = 0x02;
@@ -44,7 +58,7 @@ const FLAG_HAS_SPACE: u8 = 0x02;
 const CREATE_VALUE_REF_RESERVED_MASK: u8 = 0xFC;
 
 // Context reference sentinel value (no context)
-const NO_CONTEXT_REF: u32 = 0xFFFFFFFF;
+pub(crate) const NO_CONTEXT_REF: u32 = 0xFFFFFFFF;
 
 // UpdateRelation set flags (bit order matches field order in spec Section 6.4)
 const UPDATE_SET_FROM_SPACE: u8 = 0x01;
@@ -67,12 +81,34 @@ const UPDATE_UNSET_RESERVED_MASK: u8 = 0xE0;
 // =============================================================================
 
 /// Decodes an Op from the reader (zero-copy).
-pub fn decode_op<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<Op<'a>, DecodeError> {
+pub fn decode_op<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries<'a>) -> Result<Op<'a>, DecodeError> {
+    decode_op_impl(reader, dicts, false)
+}
+
+/// Decodes an Op, reading undefined core op types as length-prefixed
+/// [`Op::Unknown`] payloads if `preserve_unknown` is set (see
+/// [`DecodeOptions::preserve_unknown`](crate::codec::DecodeOptions::preserve_unknown)).
+pub(crate) fn decode_op_impl<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    preserve_unknown: bool,
+) -> Result<Op<'a>, DecodeError> {
+    decode_op_projected(reader, dicts, preserve_unknown, None)
+}
+
+/// Decodes an Op like [`decode_op_impl`], dropping entity values whose
+/// property is not in `keep`.
+fn decode_op_projected<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    preserve_unknown: bool,
+    keep: Option<&FxHashSet<Id>>,
+) -> Result<Op<'a>, DecodeError> {
     let op_type = reader.read_byte("op_type")?;
 
     match op_type {
-        OP_CREATE_ENTITY => decode_create_entity(reader, dicts),
-        OP_UPDATE_ENTITY => decode_update_entity(reader, dicts),
+        OP_CREATE_ENTITY => decode_create_entity(reader, dicts, keep),
+        OP_UPDATE_ENTITY => decode_update_entity(reader, dicts, keep),
         OP_DELETE_ENTITY => decode_delete_entity(reader, dicts),
         OP_RESTORE_ENTITY => decode_restore_entity(reader, dicts),
         OP_CREATE_RELATION => decode_create_relation(reader, dicts),
@@ -80,13 +116,206 @@ pub fn decode_op<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Resul
         OP_DELETE_RELATION => decode_delete_relation(reader, dicts),
         OP_RESTORE_RELATION => decode_restore_relation(reader, dicts),
         OP_CREATE_VALUE_REF => decode_create_value_ref(reader, dicts),
+        _ if EXTENSION_OP_TYPES.contains(&op_type) => decode_unknown_op(reader, op_type),
+        _ if preserve_unknown && op_type > OP_CREATE_VALUE_REF => decode_unknown_op(reader, op_type),
         _ => Err(DecodeError::InvalidOpType { op_type }),
     }
 }
 
-fn decode_create_entity<'a>(
+/// Decodes the next op with the op filter and property projection of
+/// `options`, returning `None` if the filter drops it.
+///
+/// Ops rejected by type, and entity ops rejected by ID, are skipped without
+/// decoding their values.
+pub(crate) fn decode_op_filtered<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    options: &DecodeOptions,
+) -> Result<Option<Op<'a>>, DecodeError> {
+    let keep = options.keep_properties.as_ref();
+    let Some(filter) = &options.op_filter else {
+        return decode_op_projected(reader, dicts, options.preserve_unknown, keep).map(Some);
+    };
+    let op_type = reader.peek_byte("op_type")?;
+    let rejected = !filter.matches_type(op_type)
+        || peek_entity_id(reader, dicts, op_type)?.is_some_and(|id| !filter.matches_id(&id));
+    if rejected {
+        skip_op(reader, dicts, options.preserve_unknown)?;
+        return Ok(None);
+    }
+    let op = decode_op_projected(reader, dicts, options.preserve_unknown, keep)?;
+    Ok(filter.matches(&op).then_some(op))
+}
+
+/// Returns the target of an entity op without consuming it. Out-of-range
+/// indices return `None` and fail when the op is decoded.
+fn peek_entity_id(reader: &Reader<'_>, dicts: &WireDictionaries, op_type: u8) -> Result<Option<Id>, DecodeError> {
+    let mut peek = reader.clone();
+    match op_type {
+        OP_CREATE_ENTITY => {
+            peek.skip(1, "op_type")?;
+            Ok(Some(peek.read_id("entity_id")?))
+        }
+        OP_UPDATE_ENTITY => {
+            peek.skip(1, "op_type")?;
+            let index = peek.read_varint("entity_id")? as usize;
+            Ok(dicts.objects.get(index).copied())
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Moves the reader past one op, returning its type.
+///
+/// Entity ops skip their values without decoding them; the other ops are
+/// small and are decoded and dropped.
+pub(crate) fn skip_op<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    preserve_unknown: bool,
+) -> Result<u8, DecodeError> {
+    let op_type = reader.peek_byte("op_type")?;
+    match op_type {
+        OP_CREATE_ENTITY => {
+            reader.skip(1, "op_type")?;
+            reader.read_id("entity_id")?;
+            skip_values(reader, dicts, "values")?;
+        }
+        OP_UPDATE_ENTITY => {
+            reader.skip(1, "op_type")?;
+            reader.read_varint("entity_id")?;
+            let flags = reader.read_byte("update_flags")?;
+            if flags & UPDATE_ENTITY_RESERVED_MASK != 0 {
+                return Err(DecodeError::ReservedBitsSet { context: "UpdateEntity flags" });
+            }
+            if flags & FLAG_HAS_SET_PROPERTIES != 0 {
+                skip_values(reader, dicts, "set_properties")?;
+            }
+            if flags & FLAG_HAS_UNSET_VALUES != 0 {
+                skip_unset_values(reader)?;
+            }
+        }
+        _ => {
+            decode_op_impl(reader, dicts, preserve_unknown)?;
+            return Ok(op_type);
+        }
+    }
+    reader.read_varint("context_ref")?;
+    Ok(op_type)
+}
+
+fn skip_values(reader: &mut Reader<'_>, dicts: &WireDictionaries, field: &'static str) -> Result<(), DecodeError> {
+    let count = reader.read_varint(field)? as usize;
+    if count > MAX_VALUES_PER_ENTITY {
+        return Err(DecodeError::LengthExceedsLimit { field, len: count, max: MAX_VALUES_PER_ENTITY });
+    }
+    (0..count).try_for_each(|_| skip_property_value(reader, dicts))
+}
+
+fn skip_unset_values(reader: &mut Reader<'_>) -> Result<(), DecodeError> {
+    let count = reader.read_varint("unset_values_count")? as usize;
+    for _ in 0..count {
+        reader.read_varint("property")?;
+        reader.read_varint("unset.language")?;
+    }
+    Ok(())
+}
+
+/// Copies one op from `reader` to `out`, handing each entity value to
+/// `rewrite`, which reads the value and writes its replacement. Returns the
+/// op type.
+///
+/// Used to move values around when transcoding; everything but the values
+/// is copied verbatim.
+pub(crate) fn rewrite_op_values<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    out: &mut Writer,
+    rewrite: &mut dyn FnMut(&mut Reader<'a>, DataType, &mut Writer) -> Result<(), DecodeError>,
+) -> Result<u8, DecodeError> {
+    let mut start = reader.remaining();
+    let op_type = reader.peek_byte("op_type")?;
+    match op_type {
+        OP_CREATE_ENTITY => {
+            reader.skip(1, "op_type")?;
+            reader.read_id("entity_id")?;
+            rewrite_values(reader, dicts, out, &mut start, "values", rewrite)?;
+        }
+        OP_UPDATE_ENTITY => {
+            reader.skip(1, "op_type")?;
+            reader.read_varint("entity_id")?;
+            let flags = reader.read_byte("update_flags")?;
+            if flags & UPDATE_ENTITY_RESERVED_MASK != 0 {
+                return Err(DecodeError::ReservedBitsSet { context: "UpdateEntity flags" });
+            }
+            if flags & FLAG_HAS_SET_PROPERTIES != 0 {
+                rewrite_values(reader, dicts, out, &mut start, "set_properties", rewrite)?;
+            }
+            if flags & FLAG_HAS_UNSET_VALUES != 0 {
+                skip_unset_values(reader)?;
+            }
+        }
+        _ => {
+            decode_op_impl(reader, dicts, true)?;
+            copy_consumed(out, &mut start, reader);
+            return Ok(op_type);
+        }
+    }
+    reader.read_varint("context_ref")?;
+    copy_consumed(out, &mut start, reader);
+    Ok(op_type)
+}
+
+fn rewrite_values<'a>(
     reader: &mut Reader<'a>,
     dicts: &WireDictionaries,
+    out: &mut Writer,
+    start: &mut &'a [u8],
+    field: &'static str,
+    rewrite: &mut dyn FnMut(&mut Reader<'a>, DataType, &mut Writer) -> Result<(), DecodeError>,
+) -> Result<(), DecodeError> {
+    let count = reader.read_varint(field)? as usize;
+    if count > MAX_VALUES_PER_ENTITY {
+        return Err(DecodeError::LengthExceedsLimit { field, len: count, max: MAX_VALUES_PER_ENTITY });
+    }
+    for _ in 0..count {
+        let prop_index = reader.read_varint("property")? as usize;
+        let (_, data_type) = *dicts.properties.get(prop_index).ok_or(DecodeError::IndexOutOfBounds {
+            dict: "properties",
+            index: prop_index,
+            size: dicts.properties.len(),
+        })?;
+        copy_consumed(out, start, reader);
+        rewrite(reader, data_type, out)?;
+        *start = reader.remaining();
+    }
+    Ok(())
+}
+
+/// Writes the bytes consumed since `start` and moves `start` up to the
+/// reader's position.
+fn copy_consumed<'a>(out: &mut Writer, start: &mut &'a [u8], reader: &Reader<'a>) {
+    out.write_bytes(&start[..start.len() - reader.remaining_len()]);
+    *start = reader.remaining();
+}
+
+fn decode_unknown_op<'a>(reader: &mut Reader<'a>, op_type: u8) -> Result<Op<'a>, DecodeError> {
+    let len = reader.read_varint("unknown_op")? as usize;
+    if len > MAX_BYTES_LEN {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "unknown_op",
+            len,
+            max: MAX_BYTES_LEN,
+        });
+    }
+    let bytes = reader.read_bytes(len, "unknown_op")?;
+    Ok(Op::Unknown(UnknownOp { op_type, bytes: Cow::Borrowed(bytes) }))
+}
+
+fn decode_create_entity<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    keep: Option<&FxHashSet<Id>>,
 ) -> Result<Op<'a>, DecodeError> {
     let id = reader.read_id("entity_id")?;
     let value_count = reader.read_varint("value_count")? as usize;
@@ -99,30 +328,21 @@ fn decode_create_entity<'a>(
         });
     }
 
+    reader.charge_items::<PropertyValue>(value_count, "values")?;
     let mut values = Vec::with_capacity(value_count);
     for _ in 0..value_count {
-        values.push(decode_property_value(reader, dicts)?);
+        values.extend(decode_projected_value(reader, dicts, keep)?);
     }
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::CreateEntity(CreateEntity { id, values, context }))
 }
 
 fn decode_update_entity<'a>(
     reader: &mut Reader<'a>,
-    dicts: &WireDictionaries,
+    dicts: &WireDictionaries<'a>,
+    keep: Option<&FxHashSet<Id>>,
 ) -> Result<Op<'a>, DecodeError> {
     let id_index = reader.read_varint("entity_id")? as usize;
     if id_index >= dicts.objects.len() {
@@ -154,8 +374,9 @@ fn decode_update_entity<'a>(
                 max: MAX_VALUES_PER_ENTITY,
             });
         }
+        reader.charge_items::<PropertyValue>(count, "set_properties")?;
         for _ in 0..count {
-            update.set_properties.push(decode_property_value(reader, dicts)?);
+            update.set_properties.extend(decode_projected_value(reader, dicts, keep)?);
         }
     }
 
@@ -168,6 +389,7 @@ fn decode_update_entity<'a>(
                 max: MAX_VALUES_PER_ENTITY,
             });
         }
+        reader.charge_items::<UnsetValue>(count, "unset_values")?;
         for _ in 0..count {
             let prop_index = reader.read_varint("property")? as usize;
             if prop_index >= dicts.properties.len() {
@@ -201,18 +423,7 @@ fn decode_update_entity<'a>(
         }
     }
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    update.context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    update.context = read_context_ref(reader, dicts)?;
 
     Ok(Op::UpdateEntity(update))
 }
@@ -231,18 +442,7 @@ fn decode_delete_entity<'a>(
     }
     let id = dicts.objects[id_index];
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::DeleteEntity(DeleteEntity { id, context }))
 }
@@ -261,18 +461,7 @@ fn decode_restore_entity<'a>(
     }
     let id = dicts.objects[id_index];
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::RestoreEntity(RestoreEntity { id, context }))
 }
@@ -364,18 +553,7 @@ fn decode_create_relation<'a>(
         None
     };
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::CreateRelation(CreateRelation {
         id,
@@ -472,18 +650,7 @@ fn decode_update_relation<'a>(
         unset.push(UnsetRelationField::Position);
     }
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::UpdateRelation(UpdateRelation {
         id,
@@ -511,18 +678,7 @@ fn decode_delete_relation<'a>(
     }
     let id = dicts.objects[id_index];
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::DeleteRelation(DeleteRelation { id, context }))
 }
@@ -541,18 +697,7 @@ fn decode_restore_relation<'a>(
     }
     let id = dicts.objects[id_index];
 
-    // Read context_ref: 0xFFFFFFFF = no context, else index into contexts[]
-    let context_ref_raw = reader.read_varint("context_ref")? as u32;
-    let context = if context_ref_raw == NO_CONTEXT_REF {
-        None
-    } else {
-        let idx = context_ref_raw as usize;
-        Some(dicts.get_context(idx).ok_or_else(|| DecodeError::IndexOutOfBounds {
-            dict: "contexts",
-            index: idx,
-            size: dicts.contexts.len(),
-        })?.clone())
-    };
+    let context = read_context_ref(reader, dicts)?;
 
     Ok(Op::RestoreRelation(RestoreRelation { id, context }))
 }
@@ -634,6 +779,34 @@ fn decode_create_value_ref<'a>(
     }))
 }
 
+/// Reads a context_ref (0xFFFFFFFF = no context) and resolves it.
+fn read_context_ref(reader: &mut Reader<'_>, dicts: &WireDictionaries) -> Result<Option<Context>, DecodeError> {
+    let context_ref = reader.read_varint("context_ref")? as u32;
+    resolve_context_ref(reader, dicts, context_ref)
+}
+
+/// Resolves a context_ref into an owned copy of the context.
+///
+/// Every op gets its own copy of the edges, so the copy is charged against
+/// the reader's allocation budget.
+pub(crate) fn resolve_context_ref(
+    reader: &mut Reader<'_>,
+    dicts: &WireDictionaries,
+    context_ref: u32,
+) -> Result<Option<Context>, DecodeError> {
+    if context_ref == NO_CONTEXT_REF {
+        return Ok(None);
+    }
+    let idx = context_ref as usize;
+    let context = dicts.get_context(idx).ok_or(DecodeError::IndexOutOfBounds {
+        dict: "contexts",
+        index: idx,
+        size: dicts.contexts.len(),
+    })?;
+    reader.charge_items::<ContextEdge>(context.edges.len(), "context_edges")?;
+    Ok(Some(context.clone()))
+}
+
 // =============================================================================
 // ENCODING
 // =============================================================================
@@ -646,7 +819,7 @@ pub fn encode_op(
     writer: &mut Writer,
     op: &Op<'_>,
     dict_builder: &mut DictionaryBuilder,
-    property_types: &rustc_hash::FxHashMap<crate::model::Id, DataType>,
+    property_types: &crate::collections::FxHashMap<crate::model::Id, DataType>,
 ) -> Result<(), EncodeError> {
     match op {
         Op::CreateEntity(ce) => encode_create_entity(writer, ce, dict_builder, property_types),
@@ -658,6 +831,14 @@ pub fn encode_op(
         Op::DeleteRelation(dr) => encode_delete_relation(writer, dr, dict_builder),
         Op::RestoreRelation(rr) => encode_restore_relation(writer, rr, dict_builder),
         Op::CreateValueRef(cvr) => encode_create_value_ref(writer, cvr, dict_builder),
+        Op::Unknown(unknown) => {
+            if unknown.op_type <= OP_CREATE_VALUE_REF {
+                return Err(EncodeError::InvalidInput { context: "unknown op reuses a core op type" });
+            }
+            writer.write_byte(unknown.op_type);
+            writer.write_bytes_prefixed(&unknown.bytes);
+            Ok(())
+        }
     }
 }
 
@@ -665,7 +846,7 @@ fn encode_create_entity(
     writer: &mut Writer,
     ce: &CreateEntity<'_>,
     dict_builder: &mut DictionaryBuilder,
-    property_types: &rustc_hash::FxHashMap<crate::model::Id, DataType>,
+    property_types: &crate::collections::FxHashMap<crate::model::Id, DataType>,
 ) -> Result<(), EncodeError> {
     writer.write_byte(OP_CREATE_ENTITY);
     writer.write_id(&ce.id);
@@ -692,7 +873,7 @@ fn encode_update_entity(
     writer: &mut Writer,
     ue: &UpdateEntity<'_>,
     dict_builder: &mut DictionaryBuilder,
-    property_types: &rustc_hash::FxHashMap<crate::model::Id, DataType>,
+    property_types: &crate::collections::FxHashMap<crate::model::Id, DataType>,
 ) -> Result<(), EncodeError> {
     writer.write_byte(OP_UPDATE_ENTITY);
 
@@ -1037,7 +1218,7 @@ fn encode_property_value(
 
 #[cfg(test)]
 mod tests {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use super::*;
     use crate::model::Value;
@@ -1057,7 +1238,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let mut property_types = rustc_hash::FxHashMap::default();
+        let mut property_types = crate::collections::FxHashMap::default();
         property_types.insert([2u8; 16], DataType::Text);
 
         let mut writer = Writer::new();
@@ -1107,7 +1288,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1156,7 +1337,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1200,7 +1381,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1247,7 +1428,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1281,7 +1462,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1313,7 +1494,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1350,7 +1531,7 @@ mod tests {
 
         let mut dict_builder = DictionaryBuilder::new();
         dict_builder.add_object([1u8; 16]); // Pre-add the relation ID
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1397,7 +1578,7 @@ mod tests {
 
         let mut dict_builder = DictionaryBuilder::new();
         dict_builder.add_object([1u8; 16]); // Pre-add the relation ID
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::collections::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1,10 +1,36 @@
 //! Primitive encoding/decoding for GRC-20 binary format.
 //!
 //! Implements varint, signed varint (zigzag), and basic types.
+//!
+//! [`Reader`] and [`Writer`] are the same primitives the edit codec is built
+//! on, and they are public so extensions (signature envelopes, custom
+//! containers) can reuse them instead of reimplementing the wire encoding.
+//! Their methods follow semver: behavior of the existing methods only changes
+//! in a major release. Every read is bounds-checked and fails with
+//! [`DecodeError::UnexpectedEof`] rather than panicking.
+//!
+//! ```
+//! use grc_20::codec::{Reader, Writer};
+//!
+//! // A custom container: tag byte, then a length-prefixed payload
+//! let mut writer = Writer::new();
+//! writer.write_byte(0x01);
+//! writer.write_bytes_prefixed(b"payload");
+//!
+//! let mut reader = Reader::new(writer.as_bytes());
+//! assert_eq!(reader.peek_byte("tag").unwrap(), 0x01);
+//! reader.skip(1, "tag").unwrap();
+//! let len = reader.read_varint("len").unwrap() as usize;
+//! let mut payload = reader.take(len, "payload").unwrap();
+//! assert_eq!(payload.remaining(), b"payload");
+//! assert_eq!(payload.read_bytes(3, "payload").unwrap(), b"pay");
+//! assert!(reader.is_empty());
+//! ```
 
 use crate::error::DecodeError;
-use crate::limits::MAX_VARINT_BYTES;
+use crate::limits::{default_alloc_budget, MAX_VARINT_BYTES};
 use crate::model::Id;
+use crate::prelude::*;
 
 // =============================================================================
 // DECODING
@@ -14,16 +40,65 @@ use crate::model::Id;
 ///
 /// Wraps a byte slice and provides methods for reading primitives
 /// with bounds checking and error handling.
+///
+/// The reader also tracks an allocation budget shared by everything decoded
+/// through it. Per-field limits bound each allocation individually; the budget
+/// bounds their sum, so many maximal-size fields cannot multiply memory use.
 #[derive(Debug, Clone)]
 pub struct Reader<'a> {
     data: &'a [u8],
     pos: usize,
+    alloc_budget: usize,
+    embedding_blob: Option<&'a [u8]>,
 }
 
 impl<'a> Reader<'a> {
-    /// Creates a new reader from a byte slice.
+    /// Creates a new reader from a byte slice, with the default allocation
+    /// budget for its length (see [`default_alloc_budget`]).
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data, pos: 0 }
+        Self::with_alloc_budget(data, default_alloc_budget(data.len()))
+    }
+
+    /// Creates a new reader with an explicit allocation budget in bytes.
+    pub fn with_alloc_budget(data: &'a [u8], alloc_budget: usize) -> Self {
+        Self { data, pos: 0, alloc_budget, embedding_blob: None }
+    }
+
+    /// Returns the remaining allocation budget in bytes.
+    pub fn alloc_budget(&self) -> usize {
+        self.alloc_budget
+    }
+
+    /// Charges `bytes` against the allocation budget.
+    ///
+    /// Call this before allocating, so an oversized request fails without
+    /// reserving any memory.
+    #[inline]
+    pub fn charge(&mut self, bytes: usize, field: &'static str) -> Result<(), DecodeError> {
+        if bytes > self.alloc_budget {
+            return Err(DecodeError::AllocationBudgetExceeded {
+                field,
+                requested: bytes,
+                remaining: self.alloc_budget,
+            });
+        }
+        self.alloc_budget -= bytes;
+        Ok(())
+    }
+
+    /// Charges the allocation budget for `count` values of type `T`.
+    #[inline]
+    pub fn charge_items<T>(&mut self, count: usize, field: &'static str) -> Result<(), DecodeError> {
+        self.charge(count.saturating_mul(core::mem::size_of::<T>()), field)
+    }
+
+    /// Moves this reader's remaining budget to `other`.
+    ///
+    /// Used for sub-readers over length-prefixed streams: lend the budget
+    /// before decoding from the stream and take it back afterwards, so the
+    /// whole edit shares a single budget.
+    pub fn lend_budget(&mut self, other: &mut Reader<'_>) {
+        other.alloc_budget = core::mem::take(&mut self.alloc_budget);
     }
 
     /// Returns the current position in the data.
@@ -46,6 +121,62 @@ impl<'a> Reader<'a> {
         self.pos >= self.data.len()
     }
 
+    /// Returns the next byte without consuming it.
+    #[inline]
+    pub fn peek_byte(&self, context: &'static str) -> Result<u8, DecodeError> {
+        self.data.get(self.pos).copied().ok_or(DecodeError::UnexpectedEof { context })
+    }
+
+    /// Returns the next `n` bytes without consuming them.
+    #[inline]
+    pub fn peek(&self, n: usize, context: &'static str) -> Result<&'a [u8], DecodeError> {
+        if n > self.data.len() - self.pos {
+            return Err(DecodeError::UnexpectedEof { context });
+        }
+        Ok(&self.data[self.pos..self.pos + n])
+    }
+
+    /// Advances past `n` bytes.
+    #[inline]
+    pub fn skip(&mut self, n: usize, context: &'static str) -> Result<(), DecodeError> {
+        self.read_bytes(n, context).map(|_| ())
+    }
+
+    /// Splits off a sub-reader over the next `len` bytes and advances past
+    /// them.
+    ///
+    /// The sub-reader starts with no allocation budget; use
+    /// [`lend_budget`](Self::lend_budget) to share this reader's budget while
+    /// decoding from it. Its positions are relative to the sub-slice.
+    pub fn take(&mut self, len: usize, context: &'static str) -> Result<Reader<'a>, DecodeError> {
+        let data = self.read_bytes(len, context)?;
+        let mut reader = Reader::with_alloc_budget(data, 0);
+        reader.embedding_blob = self.embedding_blob;
+        Ok(reader)
+    }
+
+    /// Returns the blob that out-of-line embedding values point into, if
+    /// the edit has one (see [`crate::codec::layout`]).
+    pub(crate) fn embedding_blob(&self) -> Option<&'a [u8]> {
+        self.embedding_blob
+    }
+
+    /// Sets the blob that embedding values are read from.
+    pub(crate) fn set_embedding_blob(&mut self, blob: Option<&'a [u8]>) {
+        self.embedding_blob = blob;
+    }
+
+    /// Splits the last `len` bytes off the data as the embedding blob.
+    pub(crate) fn split_embedding_blob(&mut self, len: usize) -> Result<(), DecodeError> {
+        if len > self.remaining_len() {
+            return Err(DecodeError::UnexpectedEof { context: "embedding_blob" });
+        }
+        let (data, blob) = self.data.split_at(self.data.len() - len);
+        self.data = data;
+        self.embedding_blob = Some(blob);
+        Ok(())
+    }
+
     /// Reads a single byte.
     #[inline]
     pub fn read_byte(&mut self, context: &'static str) -> Result<u8, DecodeError> {
@@ -60,7 +191,8 @@ impl<'a> Reader<'a> {
     /// Reads exactly n bytes.
     #[inline]
     pub fn read_bytes(&mut self, n: usize, context: &'static str) -> Result<&'a [u8], DecodeError> {
-        if self.pos + n > self.data.len() {
+        // Compare against the remainder so huge lengths cannot overflow
+        if n > self.data.len() - self.pos {
             return Err(DecodeError::UnexpectedEof { context });
         }
         let bytes = &self.data[self.pos..self.pos + n];
@@ -77,8 +209,29 @@ impl<'a> Reader<'a> {
     }
 
     /// Reads an unsigned varint (LEB128).
+    ///
+    /// One- and two-byte values (below 16384) take an unrolled fast path;
+    /// they cover nearly all counts, lengths, and dictionary indices.
     #[inline]
     pub fn read_varint(&mut self, context: &'static str) -> Result<u64, DecodeError> {
+        if let Some(&b0) = self.data.get(self.pos) {
+            if b0 & 0x80 == 0 {
+                self.pos += 1;
+                return Ok(b0 as u64);
+            }
+            if let Some(&b1) = self.data.get(self.pos + 1) {
+                if b1 & 0x80 == 0 {
+                    self.pos += 2;
+                    return Ok((b0 & 0x7F) as u64 | (b1 as u64) << 7);
+                }
+            }
+        }
+        self.read_varint_slow(context)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn read_varint_slow(&mut self, context: &'static str) -> Result<u64, DecodeError> {
         let mut result: u64 = 0;
         let mut shift = 0;
 
@@ -107,6 +260,7 @@ impl<'a> Reader<'a> {
     }
 
     /// Reads a signed varint (zigzag encoded).
+    #[inline]
     pub fn read_signed_varint(&mut self, context: &'static str) -> Result<i64, DecodeError> {
         let unsigned = self.read_varint(context)?;
         Ok(zigzag_decode(unsigned))
@@ -128,8 +282,9 @@ impl<'a> Reader<'a> {
             });
         }
         let bytes = self.read_bytes(len, field)?;
+        self.charge(len, field)?;
         // Validate UTF-8 on borrowed slice, then allocate once (avoids intermediate Vec)
-        std::str::from_utf8(bytes)
+        core::str::from_utf8(bytes)
             .map(|s| s.to_string())
             .map_err(|_| DecodeError::InvalidUtf8 { field })
     }
@@ -150,7 +305,7 @@ impl<'a> Reader<'a> {
             });
         }
         let bytes = self.read_bytes(len, field)?;
-        std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8 { field })
+        core::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8 { field })
     }
 
     /// Reads a length-prefixed byte array.
@@ -168,6 +323,7 @@ impl<'a> Reader<'a> {
             });
         }
         let bytes = self.read_bytes(len, field)?;
+        self.charge(len, field)?;
         Ok(bytes.to_vec())
     }
 
@@ -191,12 +347,15 @@ impl<'a> Reader<'a> {
         Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
     }
 
-    /// Reads a vector of IDs with length prefix.
-    pub fn read_id_vec(
+    /// Reads a length-prefixed run of IDs as a borrowed slice (zero-copy).
+    ///
+    /// Validates the count and bounds once, then reinterprets the bytes in
+    /// place instead of copying each ID out.
+    pub fn read_id_slice(
         &mut self,
         max_len: usize,
         field: &'static str,
-    ) -> Result<Vec<Id>, DecodeError> {
+    ) -> Result<&'a [Id], DecodeError> {
         let count = self.read_varint(field)? as usize;
         if count > max_len {
             return Err(DecodeError::LengthExceedsLimit {
@@ -205,11 +364,22 @@ impl<'a> Reader<'a> {
                 max: max_len,
             });
         }
-        let mut ids = Vec::with_capacity(count);
-        for _ in 0..count {
-            ids.push(self.read_id(field)?);
-        }
-        Ok(ids)
+        let len = count.checked_mul(16).ok_or(DecodeError::UnexpectedEof { context: field })?;
+        let bytes = self.read_bytes(len, field)?;
+        // SAFETY: `[u8; 16]` has the size of 16 `u8`s and alignment 1, and
+        // `bytes` holds exactly `count * 16` initialized bytes borrowed for 'a
+        Ok(unsafe { core::slice::from_raw_parts(bytes.as_ptr().cast::<Id>(), count) })
+    }
+
+    /// Reads a vector of IDs with length prefix.
+    pub fn read_id_vec(
+        &mut self,
+        max_len: usize,
+        field: &'static str,
+    ) -> Result<Vec<Id>, DecodeError> {
+        let ids = self.read_id_slice(max_len, field)?;
+        self.charge_items::<Id>(ids.len(), field)?;
+        Ok(ids.to_vec())
     }
 }
 
@@ -223,6 +393,13 @@ pub struct Writer {
     buf: Vec<u8>,
 }
 
+impl From<Vec<u8>> for Writer {
+    /// Wraps a buffer; writes append to its contents.
+    fn from(buf: Vec<u8>) -> Self {
+        Self { buf }
+    }
+}
+
 impl Writer {
     /// Creates a new writer.
     pub fn new() -> Self {
@@ -236,6 +413,16 @@ impl Writer {
         }
     }
 
+    /// Clears the written bytes, keeping the allocation for reuse.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.reserve(additional);
+    }
+
     /// Returns the written bytes.
     pub fn into_bytes(self) -> Vec<u8> {
         self.buf
@@ -473,4 +660,99 @@ mod tests {
         let result = reader.read_bytes(10, "test");
         assert!(matches!(result, Err(DecodeError::UnexpectedEof { .. })));
     }
+
+    #[test]
+    fn test_read_bytes_huge_len() {
+        let data = [0u8; 5];
+        let mut reader = Reader::new(&data);
+        reader.read_byte("test").unwrap();
+        assert!(matches!(reader.read_bytes(usize::MAX, "test"), Err(DecodeError::UnexpectedEof { .. })));
+        assert_eq!(reader.position(), 1);
+    }
+
+    #[test]
+    fn test_varint_fast_path_boundaries() {
+        for value in [0u64, 1, 127, 128, 255, 16383, 16384, 1 << 21, u64::MAX] {
+            let mut writer = Writer::new();
+            writer.write_varint(value);
+            let mut reader = Reader::new(writer.as_bytes());
+            assert_eq!(reader.read_varint("test").unwrap(), value);
+            assert!(reader.is_empty());
+        }
+
+        // Non-minimal two-byte encoding decodes like the slow path did
+        let mut reader = Reader::new(&[0x80, 0x00]);
+        assert_eq!(reader.read_varint("test").unwrap(), 0);
+        assert_eq!(reader.position(), 2);
+
+        // Truncated after a continuation byte
+        let mut reader = Reader::new(&[0x80]);
+        assert!(matches!(reader.read_varint("test"), Err(DecodeError::UnexpectedEof { .. })));
+    }
+
+    #[test]
+    fn test_read_id_slice() {
+        let ids = [[1u8; 16], [2u8; 16], [3u8; 16]];
+        let mut writer = Writer::new();
+        writer.write_id_vec(&ids);
+        writer.write_byte(0xAA);
+
+        let mut reader = Reader::with_alloc_budget(writer.as_bytes(), 0);
+        assert_eq!(reader.read_id_slice(3, "ids").unwrap(), &ids);
+        assert_eq!(reader.read_byte("tail").unwrap(), 0xAA);
+
+        let mut reader = Reader::new(writer.as_bytes());
+        assert!(matches!(
+            reader.read_id_slice(2, "ids"),
+            Err(DecodeError::LengthExceedsLimit { field: "ids", len: 3, max: 2 })
+        ));
+
+        let truncated = &writer.as_bytes()[..40];
+        let mut reader = Reader::new(truncated);
+        assert!(matches!(reader.read_id_slice(3, "ids"), Err(DecodeError::UnexpectedEof { .. })));
+    }
+
+    #[test]
+    fn test_peek_skip_take() {
+        let data = [1u8, 2, 3, 4, 5];
+        let mut reader = Reader::new(&data);
+        assert_eq!(reader.peek_byte("test").unwrap(), 1);
+        assert_eq!(reader.peek(2, "test").unwrap(), &[1, 2]);
+        assert_eq!(reader.position(), 0);
+        assert!(matches!(reader.peek(6, "test"), Err(DecodeError::UnexpectedEof { .. })));
+
+        reader.skip(1, "test").unwrap();
+        let mut sub = reader.take(2, "sub").unwrap();
+        assert_eq!(reader.position(), 3);
+        assert_eq!(sub.alloc_budget(), 0);
+        assert_eq!(sub.read_byte("sub").unwrap(), 2);
+        assert_eq!(sub.position(), 1);
+        assert_eq!(sub.read_byte("sub").unwrap(), 3);
+        assert!(matches!(sub.read_byte("sub"), Err(DecodeError::UnexpectedEof { .. })));
+
+        assert!(matches!(reader.take(3, "test"), Err(DecodeError::UnexpectedEof { .. })));
+        assert!(matches!(reader.skip(usize::MAX, "test"), Err(DecodeError::UnexpectedEof { .. })));
+        assert_eq!(reader.position(), 3);
+        reader.skip(2, "test").unwrap();
+        assert!(matches!(reader.peek_byte("test"), Err(DecodeError::UnexpectedEof { .. })));
+    }
+
+    #[test]
+    fn test_alloc_budget_shared_across_fields() {
+        let mut writer = Writer::new();
+        writer.write_string("hello");
+        writer.write_string("world");
+
+        let mut reader = Reader::with_alloc_budget(writer.as_bytes(), 8);
+        assert_eq!(reader.read_string(100, "a").unwrap(), "hello");
+        assert_eq!(reader.alloc_budget(), 3);
+        assert!(matches!(
+            reader.read_string(100, "b"),
+            Err(DecodeError::AllocationBudgetExceeded { field: "b", requested: 5, remaining: 3 })
+        ));
+
+        // Zero-copy reads are not charged
+        let mut reader = Reader::with_alloc_budget(writer.as_bytes(), 0);
+        assert_eq!(reader.read_str(100, "a").unwrap(), "hello");
+    }
 }
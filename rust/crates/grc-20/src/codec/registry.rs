@@ -0,0 +1,285 @@
+//! Extension op registry.
+//!
+//! Op types in [`EXTENSION_OP_TYPES`] (`0x80..=0xFF`) are reserved for
+//! experimental and application-specific ops, e.g. space governance. They are
+//! length-prefixed on the wire, so every decoder keeps them as
+//! [`Op::Unknown`] and re-encodes them byte for byte, whether or not it
+//! understands them.
+//!
+//! Tooling that does understand an extension implements [`ExtensionOp`] for
+//! its payload type and registers it in a [`CodecRegistry`], which then
+//! checks those payloads on decode:
+//!
+//! ```
+//! use grc_20::codec::{CodecRegistry, ExtensionOp, Reader, Writer};
+//! use grc_20::{DecodeError, EditBuilder, EncodeError, Id, encode_edit};
+//!
+//! struct GrantRole { member: Id, role: u8 }
+//!
+//! impl ExtensionOp for GrantRole {
+//!     const OP_TYPE: u8 = 0x80;
+//!     const NAME: &'static str = "GrantRole";
+//!
+//!     fn decode_payload(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+//!         Ok(GrantRole { member: reader.read_id("member")?, role: reader.read_byte("role")? })
+//!     }
+//!
+//!     fn encode_payload(&self, writer: &mut Writer) -> Result<(), EncodeError> {
+//!         writer.write_id(&self.member);
+//!         writer.write_byte(self.role);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let op = GrantRole { member: [7u8; 16], role: 1 }.to_op().unwrap();
+//! let mut edit = EditBuilder::new([1u8; 16]).build();
+//! edit.ops.push(op);
+//! let bytes = encode_edit(&edit).unwrap();
+//!
+//! let registry = CodecRegistry::new().register::<GrantRole>();
+//! let decoded = registry.decode_edit(&bytes).unwrap();
+//! let grant = GrantRole::from_op(&decoded.ops[0]).unwrap().unwrap();
+//! assert_eq!(grant.member, [7u8; 16]);
+//! assert_eq!(registry.name(0x80), Some("GrantRole"));
+//! ```
+
+use alloc::borrow::Cow;
+
+use crate::codec::edit::decode_edit;
+use crate::codec::op::EXTENSION_OP_TYPES;
+use crate::codec::primitives::{Reader, Writer};
+use crate::collections::FxHashMap;
+use crate::error::{DecodeError, EncodeError};
+use crate::model::{Edit, Op, UnknownOp};
+
+/// A typed payload for an extension op type.
+pub trait ExtensionOp: Sized {
+    /// The wire op type, in [`EXTENSION_OP_TYPES`].
+    const OP_TYPE: u8;
+    /// A human-readable name for diagnostics.
+    const NAME: &'static str;
+
+    /// Decodes the payload. The reader covers exactly the payload bytes.
+    fn decode_payload(reader: &mut Reader<'_>) -> Result<Self, DecodeError>;
+
+    /// Encodes the payload (without op type or length prefix).
+    fn encode_payload(&self, writer: &mut Writer) -> Result<(), EncodeError>;
+
+    /// Wraps the payload in an [`Op::Unknown`] for inclusion in an edit.
+    fn to_op(&self) -> Result<Op<'static>, EncodeError> {
+        if !EXTENSION_OP_TYPES.contains(&Self::OP_TYPE) {
+            return Err(EncodeError::InvalidInput { context: "extension op type outside the extension range" });
+        }
+        let mut writer = Writer::new();
+        self.encode_payload(&mut writer)?;
+        Ok(Op::Unknown(UnknownOp { op_type: Self::OP_TYPE, bytes: Cow::Owned(writer.into_bytes()) }))
+    }
+
+    /// Decodes `op` if it is an extension op of this type.
+    ///
+    /// Returns `None` for any other op. Trailing payload bytes are an error.
+    fn from_op(op: &Op<'_>) -> Option<Result<Self, DecodeError>> {
+        match op {
+            Op::Unknown(unknown) if unknown.op_type == Self::OP_TYPE => Some(decode_exact(&unknown.bytes)),
+            _ => None,
+        }
+    }
+}
+
+fn decode_exact<T: ExtensionOp>(bytes: &[u8]) -> Result<T, DecodeError> {
+    let mut reader = Reader::new(bytes);
+    let payload = T::decode_payload(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(DecodeError::MalformedEncoding { context: "trailing bytes in extension op payload" });
+    }
+    Ok(payload)
+}
+
+fn check_payload<T: ExtensionOp>(bytes: &[u8]) -> Result<(), DecodeError> {
+    decode_exact::<T>(bytes).map(|_| ())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Registration {
+    name: &'static str,
+    check: fn(&[u8]) -> Result<(), DecodeError>,
+}
+
+/// The extension op types a tool understands.
+///
+/// Unregistered extension ops are passed through untouched; registered ones
+/// must decode cleanly with their [`ExtensionOp`] implementation.
+#[derive(Debug, Clone, Default)]
+pub struct CodecRegistry {
+    entries: FxHashMap<u8, Registration>,
+}
+
+impl CodecRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` for its op type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T::OP_TYPE` is outside [`EXTENSION_OP_TYPES`] or already
+    /// registered.
+    pub fn register<T: ExtensionOp>(mut self) -> Self {
+        assert!(
+            EXTENSION_OP_TYPES.contains(&T::OP_TYPE),
+            "extension op {} uses core op type {}",
+            T::NAME,
+            T::OP_TYPE
+        );
+        let registration = Registration { name: T::NAME, check: check_payload::<T> };
+        if let Some(existing) = self.entries.insert(T::OP_TYPE, registration) {
+            panic!("op type {} registered for both {} and {}", T::OP_TYPE, existing.name, T::NAME);
+        }
+        self
+    }
+
+    /// Returns true if `op_type` has a registered codec.
+    pub fn is_registered(&self, op_type: u8) -> bool {
+        self.entries.contains_key(&op_type)
+    }
+
+    /// Returns the registered name for `op_type`.
+    pub fn name(&self, op_type: u8) -> Option<&'static str> {
+        self.entries.get(&op_type).map(|r| r.name)
+    }
+
+    /// Checks the payload of a registered extension op.
+    ///
+    /// Core ops and unregistered extension ops are accepted as they are.
+    pub fn check_op(&self, op: &Op<'_>) -> Result<(), DecodeError> {
+        match op {
+            Op::Unknown(unknown) => match self.entries.get(&unknown.op_type) {
+                Some(registration) => (registration.check)(&unknown.bytes),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Decodes an edit (see [`decode_edit`]) and checks every registered
+    /// extension op in it.
+    pub fn decode_edit<'a>(&self, input: &'a [u8]) -> Result<Edit<'a>, DecodeError> {
+        let edit = decode_edit(input)?;
+        for op in &edit.ops {
+            self.check_op(op)?;
+        }
+        Ok(edit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::{encode_edit, encode_edit_with_options, EncodeOptions};
+    use crate::model::{EditBuilder, Id};
+
+    #[derive(Debug, PartialEq)]
+    struct GrantRole {
+        member: Id,
+        role: u8,
+    }
+
+    impl ExtensionOp for GrantRole {
+        const OP_TYPE: u8 = 0x80;
+        const NAME: &'static str = "GrantRole";
+
+        fn decode_payload(reader: &mut Reader<'_>) -> Result<Self, DecodeError> {
+            Ok(GrantRole { member: reader.read_id("member")?, role: reader.read_byte("role")? })
+        }
+
+        fn encode_payload(&self, writer: &mut Writer) -> Result<(), EncodeError> {
+            writer.write_id(&self.member);
+            writer.write_byte(self.role);
+            Ok(())
+        }
+    }
+
+    struct Core;
+
+    impl ExtensionOp for Core {
+        const OP_TYPE: u8 = 5;
+        const NAME: &'static str = "Core";
+
+        fn decode_payload(_: &mut Reader<'_>) -> Result<Self, DecodeError> {
+            Ok(Core)
+        }
+
+        fn encode_payload(&self, _: &mut Writer) -> Result<(), EncodeError> {
+            Ok(())
+        }
+    }
+
+    fn edit_with(ops: Vec<Op<'static>>) -> Edit<'static> {
+        let mut edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text([10u8; 16], "Alice", None))
+            .delete_entity([4u8; 16])
+            .build();
+        edit.ops.extend(ops);
+        edit
+    }
+
+    #[test]
+    fn test_unknown_op_roundtrip_all_layouts() {
+        let grant = GrantRole { member: [7u8; 16], role: 2 }.to_op().unwrap();
+        let opaque = Op::Unknown(UnknownOp { op_type: 0xFF, bytes: Cow::Owned(vec![1, 2, 3]) });
+        let edit = edit_with(vec![grant, opaque]);
+
+        for options in [EncodeOptions::new(), EncodeOptions::canonical(), EncodeOptions::columnar()] {
+            let bytes = encode_edit_with_options(&edit, options).unwrap();
+            let decoded = decode_edit(&bytes).unwrap();
+            assert_eq!(decoded.ops, edit.ops);
+            assert!(matches!(&decoded.ops[2], Op::Unknown(u) if matches!(u.bytes, Cow::Borrowed(_))));
+        }
+    }
+
+    #[test]
+    fn test_registry_checks_registered_payloads() {
+        let registry = CodecRegistry::new().register::<GrantRole>();
+        assert!(registry.is_registered(0x80));
+        assert_eq!(registry.name(0x81), None);
+
+        let good = edit_with(vec![GrantRole { member: [7u8; 16], role: 2 }.to_op().unwrap()]);
+        let bytes = encode_edit(&good).unwrap();
+        let decoded = registry.decode_edit(&bytes).unwrap();
+        assert_eq!(GrantRole::from_op(&decoded.ops[2]).unwrap().unwrap(), GrantRole { member: [7u8; 16], role: 2 });
+        assert!(GrantRole::from_op(&decoded.ops[0]).is_none());
+
+        // Truncated and overlong payloads
+        for bytes in [vec![0u8; 16], vec![0u8; 18]] {
+            let bad = edit_with(vec![Op::Unknown(UnknownOp { op_type: 0x80, bytes: Cow::Owned(bytes) })]);
+            let encoded = encode_edit(&bad).unwrap();
+            assert!(decode_edit(&encoded).is_ok());
+            assert!(registry.decode_edit(&encoded).is_err());
+        }
+
+        // Unregistered extension ops pass through
+        let other = edit_with(vec![Op::Unknown(UnknownOp { op_type: 0x90, bytes: Cow::Owned(vec![9]) })]);
+        assert!(registry.decode_edit(&encode_edit(&other).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_core_op_types_rejected() {
+        assert!(matches!(Core.to_op(), Err(EncodeError::InvalidInput { .. })));
+        let edit = edit_with(vec![Op::Unknown(UnknownOp { op_type: 5, bytes: Cow::Owned(vec![]) })]);
+        assert!(matches!(encode_edit(&edit), Err(EncodeError::InvalidInput { .. })));
+    }
+
+    #[test]
+    #[should_panic(expected = "core op type")]
+    fn test_register_core_op_type_panics() {
+        let _ = CodecRegistry::new().register::<Core>();
+    }
+
+    #[test]
+    #[should_panic(expected = "registered for both")]
+    fn test_register_twice_panics() {
+        let _ = CodecRegistry::new().register::<GrantRole>().register::<GrantRole>();
+    }
+}
@@ -0,0 +1,170 @@
+//! Round-trip checks for publishing pipelines.
+//!
+//! [`verify_roundtrip`] encodes an edit, decodes the bytes, and compares the
+//! result with the original, so a pipeline can refuse to publish an edit
+//! that would not read back as written:
+//!
+//! ```rust
+//! use grc_20::codec::roundtrip::verify_roundtrip;
+//! use grc_20::EditBuilder;
+//!
+//! let edit = EditBuilder::new([1u8; 16])
+//!     .create_entity([2u8; 16], |e| e.text([3u8; 16], "Berlin", None))
+//!     .build();
+//! verify_roundtrip(&edit).unwrap();
+//! ```
+
+use crate::codec::edit::{decode_edit, encode_edit_with_options, EncodeOptions};
+use crate::error::RoundtripDivergence;
+use crate::model::{Edit, Id, Op, PropertyValue, UnsetLanguage, UnsetValue, Value};
+use crate::prelude::*;
+
+/// Checks that `edit` decodes to itself after default encoding.
+pub fn verify_roundtrip(edit: &Edit) -> Result<(), RoundtripDivergence> {
+    verify_roundtrip_with_options(edit, EncodeOptions::new())
+}
+
+/// Checks that `edit` decodes to itself after encoding with `options`.
+///
+/// Borrowed and owned strings compare by content. Canonical encoding sorts
+/// authors, parents, and the values and unsets of each op, so with
+/// `options.canonical` those are compared without regard to order.
+pub fn verify_roundtrip_with_options(edit: &Edit, options: EncodeOptions) -> Result<(), RoundtripDivergence> {
+    let bytes = encode_edit_with_options(edit, options)?;
+    let decoded = decode_edit(&bytes)?;
+    compare(edit, &decoded, options.canonical)
+}
+
+/// Compares an edit with its decoded form, ignoring the order canonical
+/// encoding does not preserve.
+fn compare(edit: &Edit, decoded: &Edit, canonical: bool) -> Result<(), RoundtripDivergence> {
+    let sorted = |ids: &[Id]| {
+        let mut ids = ids.to_vec();
+        if canonical {
+            ids.sort_unstable();
+        }
+        ids
+    };
+    let header = [
+        ("id", edit.id == decoded.id),
+        ("name", edit.name == decoded.name),
+        ("authors", sorted(&edit.authors) == sorted(&decoded.authors)),
+        ("parents", sorted(&edit.parents) == sorted(&decoded.parents)),
+        ("created_at", edit.created_at == decoded.created_at),
+    ];
+    if let Some((field, _)) = header.iter().find(|(_, same)| !same) {
+        return Err(RoundtripDivergence::Header { field });
+    }
+
+    if edit.ops.len() != decoded.ops.len() {
+        return Err(RoundtripDivergence::OpCount { expected: edit.ops.len(), actual: decoded.ops.len() });
+    }
+    for (index, (expected, actual)) in edit.ops.iter().zip(&decoded.ops).enumerate() {
+        let same = if canonical {
+            normalize(expected.clone()) == normalize(actual.clone())
+        } else {
+            expected == actual
+        };
+        if !same {
+            // Fall back to Debug when Display hides the difference, e.g. contexts
+            let (mut expected, mut actual) = (expected.to_string(), actual.to_string());
+            if expected == actual {
+                (expected, actual) = (format!("{:?}", edit.ops[index]), format!("{:?}", decoded.ops[index]));
+            }
+            return Err(RoundtripDivergence::Op { index, expected, actual });
+        }
+    }
+    Ok(())
+}
+
+/// Sorts the values and unsets of an op into a fixed order.
+fn normalize(mut op: Op<'_>) -> Op<'_> {
+    fn value_key(pv: &PropertyValue<'_>) -> (Id, Option<Id>) {
+        match &pv.value {
+            Value::Text { language, .. } => (pv.property, *language),
+            _ => (pv.property, None),
+        }
+    }
+    fn unset_key(unset: &UnsetValue) -> (Id, u8, Id) {
+        match unset.language {
+            UnsetLanguage::English => (unset.property, 0, [0u8; 16]),
+            UnsetLanguage::Specific(language) => (unset.property, 1, language),
+            UnsetLanguage::All => (unset.property, 2, [0u8; 16]),
+        }
+    }
+    match &mut op {
+        Op::CreateEntity(ce) => ce.values.sort_by_key(value_key),
+        Op::UpdateEntity(ue) => {
+            ue.set_properties.sort_by_key(value_key);
+            ue.unset_values.sort_by_key(unset_key);
+        }
+        _ => {}
+    }
+    op
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    fn edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16])
+            .name("Round trip")
+            .author([9u8; 16])
+            .author([8u8; 16])
+            .create_entity([2u8; 16], |e| e.int64([11u8; 16], 1, None).text([10u8; 16], "a", None))
+            .update_entity([2u8; 16], |u| u.unset_all([11u8; 16]).unset_all([10u8; 16]))
+            .create_relation_simple([4u8; 16], [2u8; 16], [3u8; 16], [12u8; 16])
+            .build()
+    }
+
+    #[test]
+    fn test_verify_roundtrip() {
+        verify_roundtrip(&edit()).unwrap();
+        verify_roundtrip_with_options(&edit(), EncodeOptions::canonical()).unwrap();
+        verify_roundtrip_with_options(&edit(), EncodeOptions::columnar()).unwrap();
+    }
+
+    #[test]
+    fn test_compare() {
+        let edit = edit();
+
+        // Canonical order
+        let mut decoded = edit.clone();
+        decoded.authors.reverse();
+        if let Op::CreateEntity(ce) = &mut decoded.ops[0] {
+            ce.values.reverse();
+        }
+        if let Op::UpdateEntity(ue) = &mut decoded.ops[1] {
+            ue.unset_values.reverse();
+        }
+        assert!(compare(&edit, &decoded, true).is_ok());
+        assert_eq!(compare(&edit, &decoded, false), Err(RoundtripDivergence::Header { field: "authors" }));
+        decoded.authors.reverse();
+        assert!(matches!(compare(&edit, &decoded, false), Err(RoundtripDivergence::Op { index: 0, .. })));
+
+        // A lost context only shows in Debug
+        let mut decoded = edit.clone();
+        let context = crate::model::Context { root_id: [7u8; 16], edges: Vec::new() };
+        if let Op::UpdateEntity(ue) = &mut decoded.ops[1] {
+            ue.context = Some(context);
+        }
+        match compare(&edit, &decoded, true) {
+            Err(RoundtripDivergence::Op { index: 1, expected, actual }) => {
+                assert!(actual.contains("root_id") && !expected.contains("root_id"));
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+
+        let mut decoded = edit.clone();
+        decoded.ops.pop();
+        assert_eq!(compare(&edit, &decoded, true), Err(RoundtripDivergence::OpCount { expected: 3, actual: 2 }));
+    }
+
+    #[test]
+    fn test_verify_roundtrip_encode_error() {
+        let nan = EditBuilder::new([1u8; 16]).create_entity([2u8; 16], |e| e.float64([10u8; 16], f64::NAN, None)).build();
+        assert!(matches!(verify_roundtrip(&nan), Err(RoundtripDivergence::Encode(_))));
+    }
+}
@@ -2,13 +2,14 @@
 //!
 //! Implements the wire format for property values (spec Section 6.5).
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use crate::codec::primitives::{Reader, Writer};
+use crate::collections::FxHashSet;
 use crate::error::{DecodeError, EncodeError};
 use crate::limits::{MAX_BYTES_LEN, MAX_EMBEDDING_BYTES, MAX_EMBEDDING_DIMS, MAX_POSITION_LEN, MAX_STRING_LEN};
 use crate::model::{
-    DataType, DecimalMantissa, DictionaryBuilder, EmbeddingSubType, PropertyValue, Value,
+    DataType, DecimalMantissa, DictionaryBuilder, EmbeddingSubType, Id, PropertyValue, Value,
     WireDictionaries,
 };
 use crate::util::{
@@ -24,7 +25,7 @@ use crate::util::{
 pub fn decode_value<'a>(
     reader: &mut Reader<'a>,
     data_type: DataType,
-    dicts: &WireDictionaries,
+    dicts: &WireDictionaries<'a>,
 ) -> Result<Value<'a>, DecodeError> {
     match data_type {
         DataType::Bool => decode_bool(reader),
@@ -224,8 +225,31 @@ fn twos_complement_abs_mod_10(bytes: &[u8]) -> u32 {
     (remainder + 1) % 10
 }
 
-fn decode_text<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<Value<'a>, DecodeError> {
-    let value = reader.read_str(MAX_STRING_LEN, "text")?;
+fn decode_text<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries<'a>) -> Result<Value<'a>, DecodeError> {
+    let value = match &dicts.texts {
+        None => Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "text")?),
+        Some(texts) => {
+            let text_ref = reader.read_varint("text.ref")? as usize;
+            if text_ref == 0 {
+                Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "text")?)
+            } else {
+                let text = texts.get(text_ref - 1).ok_or(DecodeError::IndexOutOfBounds {
+                    dict: "texts",
+                    index: text_ref,
+                    size: texts.len() + 1, // +1 for index 0
+                })?;
+                match text {
+                    Cow::Borrowed(text) => Cow::Borrowed(*text),
+                    // Tables built for encoding own their strings, so each
+                    // reference gets its own copy
+                    Cow::Owned(text) => {
+                        reader.charge(text.len(), "text")?;
+                        Cow::Owned(text.clone())
+                    }
+                }
+            }
+        }
+    };
     let lang_index = reader.read_varint("text.language")? as usize;
 
     let language = if lang_index == 0 {
@@ -242,7 +266,7 @@ fn decode_text<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<
         Some(dicts.languages[idx])
     };
 
-    Ok(Value::Text { value: Cow::Borrowed(value), language })
+    Ok(Value::Text { value, language })
 }
 
 fn decode_bytes<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeError> {
@@ -422,7 +446,16 @@ fn decode_embedding<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeErro
         });
     }
 
-    let data = reader.read_bytes(expected_bytes, "embedding.data")?;
+    // With out-of-line embeddings the data sits in the trailing blob
+    let data = match reader.embedding_blob() {
+        Some(blob) => {
+            let offset = reader.read_varint("embedding.offset")? as usize;
+            blob.get(offset..)
+                .and_then(|rest| rest.get(..expected_bytes))
+                .ok_or(DecodeError::UnexpectedEof { context: "embedding_blob" })?
+        }
+        None => reader.read_bytes(expected_bytes, "embedding.data")?,
+    };
 
     // Validate no NaN in float32 embeddings
     if sub_type == EmbeddingSubType::Float32 {
@@ -452,7 +485,7 @@ fn decode_embedding<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeErro
 /// Decodes a PropertyValue (property index + value + optional language).
 pub fn decode_property_value<'a>(
     reader: &mut Reader<'a>,
-    dicts: &WireDictionaries,
+    dicts: &WireDictionaries<'a>,
 ) -> Result<PropertyValue<'a>, DecodeError> {
     let prop_index = reader.read_varint("property")? as usize;
     if prop_index >= dicts.properties.len() {
@@ -469,6 +502,111 @@ pub fn decode_property_value<'a>(
     Ok(PropertyValue { property, value })
 }
 
+/// Skips over an encoded property value without decoding or validating it.
+///
+/// Lengths are still checked against the decode limits and the property
+/// index against the dictionary, since the data type depends on it.
+pub(crate) fn skip_property_value(reader: &mut Reader<'_>, dicts: &WireDictionaries) -> Result<(), DecodeError> {
+    let prop_index = reader.read_varint("property")? as usize;
+    let (_, data_type) = *dicts.properties.get(prop_index).ok_or(DecodeError::IndexOutOfBounds {
+        dict: "properties",
+        index: prop_index,
+        size: dicts.properties.len(),
+    })?;
+    skip_value(reader, data_type, dicts)
+}
+
+/// Decodes a property value, or skips it and returns `None` if `keep` is set
+/// and does not contain its property.
+pub(crate) fn decode_projected_value<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries<'a>,
+    keep: Option<&FxHashSet<Id>>,
+) -> Result<Option<PropertyValue<'a>>, DecodeError> {
+    let Some(keep) = keep else {
+        return decode_property_value(reader, dicts).map(Some);
+    };
+    let prop_index = reader.read_varint("property")? as usize;
+    let (property, data_type) = *dicts.properties.get(prop_index).ok_or(DecodeError::IndexOutOfBounds {
+        dict: "properties",
+        index: prop_index,
+        size: dicts.properties.len(),
+    })?;
+    if !keep.contains(&property) {
+        skip_value(reader, data_type, dicts)?;
+        return Ok(None);
+    }
+    let value = decode_value(reader, data_type, dicts)?;
+    Ok(Some(PropertyValue { property, value }))
+}
+
+/// Skips over an encoded value of the given type (see [`skip_property_value`]).
+pub(crate) fn skip_value(reader: &mut Reader<'_>, data_type: DataType, dicts: &WireDictionaries) -> Result<(), DecodeError> {
+    match data_type {
+        DataType::Bool => reader.skip(1, "bool"),
+        DataType::Int64 => {
+            reader.read_varint("int64")?;
+            reader.read_varint("int64.unit").map(drop)
+        }
+        DataType::Float64 => {
+            reader.skip(8, "float64")?;
+            reader.read_varint("float64.unit").map(drop)
+        }
+        DataType::Decimal => {
+            reader.read_varint("decimal.exponent")?;
+            match reader.read_byte("decimal.mantissa_type")? {
+                0x00 => drop(reader.read_varint("decimal.mantissa")?),
+                0x01 => {
+                    let len = reader.read_varint("decimal.mantissa_len")? as usize;
+                    reader.skip(len, "decimal.mantissa_bytes")?;
+                }
+                _ => return Err(DecodeError::MalformedEncoding { context: "invalid decimal mantissa type" }),
+            }
+            reader.read_varint("decimal.unit").map(drop)
+        }
+        DataType::Text => {
+            if dicts.texts.is_none() || reader.read_varint("text.ref")? == 0 {
+                skip_prefixed(reader, MAX_STRING_LEN, "text")?;
+            }
+            reader.read_varint("text.language").map(drop)
+        }
+        DataType::Bytes => skip_prefixed(reader, MAX_BYTES_LEN, "bytes"),
+        DataType::Date => reader.skip(6, "date"),
+        DataType::Time => reader.skip(8, "time"),
+        DataType::Datetime => reader.skip(10, "datetime"),
+        DataType::Schedule => skip_prefixed(reader, MAX_STRING_LEN, "schedule"),
+        DataType::Point => {
+            let ordinates = reader.read_byte("point.ordinate_count")?;
+            if ordinates != 2 && ordinates != 3 {
+                return Err(DecodeError::MalformedEncoding { context: "POINT ordinate_count must be 2 or 3" });
+            }
+            reader.skip(8 * ordinates as usize, "point")
+        }
+        DataType::Rect => reader.skip(32, "rect"),
+        DataType::Embedding => {
+            let sub_type_byte = reader.read_byte("embedding.sub_type")?;
+            let sub_type = EmbeddingSubType::from_u8(sub_type_byte)
+                .ok_or(DecodeError::InvalidEmbeddingSubType { sub_type: sub_type_byte })?;
+            let dims = reader.read_varint("embedding.dims")? as usize;
+            if dims > MAX_EMBEDDING_DIMS {
+                return Err(DecodeError::LengthExceedsLimit { field: "embedding.dims", len: dims, max: MAX_EMBEDDING_DIMS });
+            }
+            if reader.embedding_blob().is_some() {
+                return reader.read_varint("embedding.offset").map(drop);
+            }
+            reader.skip(sub_type.bytes_for_dims(dims), "embedding.data")
+        }
+    }
+}
+
+fn skip_prefixed(reader: &mut Reader<'_>, max: usize, field: &'static str) -> Result<(), DecodeError> {
+    let len = reader.read_varint(field)? as usize;
+    if len > max {
+        return Err(DecodeError::LengthExceedsLimit { field, len, max });
+    }
+    reader.skip(len, field)
+}
+
 // =============================================================================
 // ENCODING
 // =============================================================================
@@ -502,7 +640,15 @@ pub fn encode_value(
             writer.write_varint(unit_index as u64);
         }
         Value::Text { value, language } => {
-            writer.write_string(value);
+            if dict_builder.interns_text() {
+                let text_ref = dict_builder.get_text_ref(value);
+                writer.write_varint(text_ref as u64);
+                if text_ref == 0 {
+                    writer.write_string(value);
+                }
+            } else {
+                writer.write_string(value);
+            }
             let lang_index = dict_builder.add_language(*language);
             writer.write_varint(lang_index as u64);
         }
@@ -962,6 +1108,20 @@ mod tests {
         assert!(encode_value(&mut writer, &invalid, &mut dict_builder).is_err());
     }
 
+    #[test]
+    fn test_decimal_huge_mantissa_len_rejected() {
+        let mut writer = Writer::new();
+        writer.write_signed_varint(0);
+        writer.write_byte(0x01);
+        writer.write_varint(u64::MAX);
+        let dicts = WireDictionaries::default();
+        let mut reader = Reader::new(writer.as_bytes());
+        assert!(matches!(
+            decode_value(&mut reader, DataType::Decimal, &dicts),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+    }
+
     #[test]
     fn test_date_roundtrip() {
         let dicts = WireDictionaries::default();
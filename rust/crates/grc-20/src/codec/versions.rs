@@ -0,0 +1,211 @@
+//! Format version negotiation and multi-version decoding.
+//!
+//! Each format version this crate reads has a [`FormatVersion`] variant.
+//! Header differences between versions are handled while decoding the
+//! preamble, so [`decode_any_version`] decodes every version the same way.
+//! When [`FORMAT_VERSION`] is bumped the previous version stays decodable,
+//! so stored edits keep decoding and [`upgrade`] can re-encode them at the
+//! current version.
+//!
+//! Versions 0 and 1 share a wire layout; version 0 predates the stable
+//! release and is accepted for old archives. Version 2 adds the `parents`
+//! list to the edit header. Edits are encoded at the oldest stable version
+//! that can represent them (see [`FormatVersion::for_edit`]), so decoders
+//! that predate version 2 keep reading edits without parents.
+
+use crate::codec::compression::{decompress_envelope, Codec};
+use crate::codec::edit::{
+    decode_decompressed, decode_edit_with_options, encode_edit_versioned, DecodeOptions, EncodeOptions,
+};
+use crate::error::{DecodeError, EncodeError, UpgradeError};
+use crate::limits::{FORMAT_VERSION, MAGIC_EXTENDED, MAGIC_UNCOMPRESSED, MIN_FORMAT_VERSION};
+use crate::model::Edit;
+use crate::prelude::*;
+
+/// A binary format version this crate can decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum FormatVersion {
+    /// Pre-release layout, identical to V1 on the wire.
+    V0 = 0,
+    /// The first stable layout.
+    V1 = 1,
+    /// Adds `parents` after `authors` in the header.
+    V2 = 2,
+}
+
+const _: () = assert!(FormatVersion::CURRENT as u8 == FORMAT_VERSION);
+const _: () = assert!(FormatVersion::V0 as u8 == MIN_FORMAT_VERSION);
+
+impl FormatVersion {
+    /// The newest version this crate encodes.
+    pub const CURRENT: FormatVersion = FormatVersion::V2;
+
+    /// All decodable versions, oldest first.
+    pub const ALL: &'static [FormatVersion] = &[FormatVersion::V0, FormatVersion::V1, FormatVersion::V2];
+
+    /// Returns the version for a wire version byte, if supported.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(FormatVersion::V0),
+            1 => Some(FormatVersion::V1),
+            2 => Some(FormatVersion::V2),
+            _ => None,
+        }
+    }
+
+    /// Returns the wire version byte.
+    pub fn as_byte(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the version an edit is encoded at by default: V1, or V2 if
+    /// the edit has parents.
+    pub fn for_edit(edit: &Edit) -> Self {
+        if edit.parents.is_empty() { FormatVersion::V1 } else { FormatVersion::V2 }
+    }
+
+    /// Returns true if the header carries `parents`.
+    pub fn has_parents(self) -> bool {
+        self >= FormatVersion::V2
+    }
+}
+
+/// Picks the newest version both sides can read, given the version bytes
+/// a peer advertises.
+pub fn negotiate(peer_versions: &[u8]) -> Option<FormatVersion> {
+    peer_versions.iter().filter_map(|&v| FormatVersion::from_byte(v)).max()
+}
+
+/// Returns the format version of an encoded edit (GRC2 or GRC2X, possibly
+/// compressed) without decoding it.
+pub fn detect_version(input: &[u8]) -> Result<FormatVersion, DecodeError> {
+    match decompress_if_compressed(input)? {
+        Some(decompressed) => header_version(&decompressed),
+        None => header_version(input),
+    }
+}
+
+/// Returns the payload of a compressed envelope, or `None` if `input` is
+/// not compressed.
+fn decompress_if_compressed(input: &[u8]) -> Result<Option<Vec<u8>>, DecodeError> {
+    Codec::from_magic(input).map(|codec| decompress_envelope(codec, &input[5..])).transpose()
+}
+
+/// Reads the version byte of an uncompressed (GRC2 or GRC2X) edit.
+fn header_version(input: &[u8]) -> Result<FormatVersion, DecodeError> {
+    let offset = if input.starts_with(MAGIC_EXTENDED) {
+        MAGIC_EXTENDED.len()
+    } else if input.starts_with(MAGIC_UNCOMPRESSED) {
+        MAGIC_UNCOMPRESSED.len()
+    } else {
+        let mut found = [0u8; 4];
+        let n = input.len().min(4);
+        found[..n].copy_from_slice(&input[..n]);
+        return Err(DecodeError::InvalidMagic { found });
+    };
+    let version = *input.get(offset).ok_or(DecodeError::UnexpectedEof { context: "version" })?;
+    FormatVersion::from_byte(version).ok_or(DecodeError::UnsupportedVersion { version })
+}
+
+/// Decodes an edit of any supported version, returning the version it was
+/// encoded at.
+pub fn decode_any_version(input: &[u8]) -> Result<(FormatVersion, Edit<'_>), DecodeError> {
+    decode_any_version_with_options(input, DecodeOptions::default())
+}
+
+/// Decodes an edit of any supported version with the given options.
+pub fn decode_any_version_with_options(
+    input: &[u8],
+    options: DecodeOptions,
+) -> Result<(FormatVersion, Edit<'_>), DecodeError> {
+    // Compressed input is decompressed once and decoded from that buffer
+    match decompress_if_compressed(input)? {
+        Some(decompressed) => {
+            let version = header_version(&decompressed)?;
+            Ok((version, decode_decompressed(&decompressed, options, &mut ())?))
+        }
+        None => Ok((header_version(input)?, decode_edit_with_options(input, options)?)),
+    }
+}
+
+/// Encodes an edit at a specific format version, e.g. one chosen by
+/// [`negotiate`] for an older peer.
+///
+/// Fails with [`EncodeError::InvalidInput`] if the edit uses a field the
+/// version cannot represent, such as `parents` before V2.
+pub fn encode_edit_at_version(
+    edit: &Edit,
+    version: FormatVersion,
+    options: EncodeOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    encode_edit_versioned(edit, options, version)
+}
+
+/// Re-encodes an edit of any supported version at the version new edits
+/// are encoded at (see [`FormatVersion::for_edit`]).
+///
+/// The edit is always decoded and re-encoded with `options`, so this also
+/// normalizes the layout of edits that are already current.
+pub fn upgrade(input: &[u8], options: EncodeOptions) -> Result<Vec<u8>, UpgradeError> {
+    let (_, edit) = decode_any_version(input)?;
+    Ok(encode_edit_at_version(&edit, FormatVersion::for_edit(&edit), options)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::{decode_edit, encode_edit, encode_edit_with_options};
+    use crate::model::EditBuilder;
+
+    fn sample_edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16])
+            .name("versions")
+            .create_entity([3u8; 16], |e| e.text([10u8; 16], "Alice", None))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .build()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate(&[0]), Some(FormatVersion::V0));
+        assert_eq!(negotiate(&[0, 1, 7]), Some(FormatVersion::V1));
+        assert_eq!(negotiate(&[7, 8]), None);
+        assert_eq!(negotiate(&[]), None);
+    }
+
+    #[test]
+    fn test_decode_and_upgrade_old_version() {
+        let edit = sample_edit();
+        for options in [EncodeOptions::new(), EncodeOptions::columnar()] {
+            let v0 = encode_edit_at_version(&edit, FormatVersion::V0, options).unwrap();
+            assert_eq!(detect_version(&v0).unwrap(), FormatVersion::V0);
+            let (version, decoded) = decode_any_version(&v0).unwrap();
+            assert_eq!(version, FormatVersion::V0);
+            assert_eq!(decoded, edit);
+
+            let upgraded = upgrade(&v0, options).unwrap();
+            assert_eq!(detect_version(&upgraded).unwrap(), FormatVersion::V1);
+            assert_eq!(upgraded, encode_edit_with_options(&edit, options).unwrap());
+            assert_eq!(decode_edit(&upgraded).unwrap(), edit);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_detect_version_compressed() {
+        let compressed = crate::codec::edit::encode_edit_compressed(&sample_edit(), 3).unwrap();
+        assert_eq!(detect_version(&compressed).unwrap(), FormatVersion::V1);
+        assert_eq!(decode_any_version(&compressed).unwrap().1, sample_edit());
+    }
+
+    #[test]
+    fn test_unsupported_versions_rejected() {
+        let mut bytes = encode_edit(&sample_edit()).unwrap();
+        bytes[4] = 99;
+        assert!(matches!(detect_version(&bytes), Err(DecodeError::UnsupportedVersion { version: 99 })));
+        assert!(matches!(upgrade(&bytes, EncodeOptions::new()), Err(UpgradeError::Decode(_))));
+        assert!(matches!(detect_version(b"GRC2"), Err(DecodeError::UnexpectedEof { .. })));
+        assert!(matches!(detect_version(b"XY"), Err(DecodeError::InvalidMagic { found: [b'X', b'Y', 0, 0] })));
+    }
+}
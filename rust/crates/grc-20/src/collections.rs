@@ -0,0 +1,10 @@
+//! Fx hash maps that work with and without `std`.
+
+#[cfg(feature = "std")]
+pub(crate) use rustc_hash::{FxHashMap, FxHashSet};
+
+#[cfg(not(feature = "std"))]
+pub(crate) type FxHashMap<K, V> = hashbrown::HashMap<K, V, rustc_hash::FxBuildHasher>;
+
+#[cfg(not(feature = "std"))]
+pub(crate) type FxHashSet<T> = hashbrown::HashSet<T, rustc_hash::FxBuildHasher>;
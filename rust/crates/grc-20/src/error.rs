@@ -3,6 +3,7 @@
 use thiserror::Error;
 
 use crate::model::{DataType, Id};
+use crate::prelude::*;
 
 /// Error codes as defined in spec Section 8.3.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -114,13 +115,26 @@ pub enum DecodeError {
     #[error("[E005] float value is NaN")]
     FloatIsNan,
 
+    #[error("[E005] allocation budget exceeded while decoding {field}: {requested} bytes requested, {remaining} remaining")]
+    AllocationBudgetExceeded {
+        field: &'static str,
+        requested: usize,
+        remaining: usize,
+    },
+
     #[error("[E005] malformed encoding: {context}")]
     MalformedEncoding { context: &'static str },
 
     // === Compression errors ===
-    #[error("[E005] zstd decompression failed: {0}")]
+    #[error("[E005] decompression failed: {0}")]
     DecompressionFailed(String),
 
+    #[error("[E005] decode quota exceeded: {quota}")]
+    QuotaExceeded { quota: &'static str },
+
+    #[error("[E005] {codec} compression is not enabled (see cargo features)")]
+    UnsupportedCodec { codec: &'static str },
+
     #[error("[E005] decompressed size {actual} doesn't match declared {declared}")]
     UncompressedSizeMismatch { declared: usize, actual: usize },
 
@@ -140,6 +154,45 @@ impl DecodeError {
             _ => ErrorCode::MalformedEncoding,
         }
     }
+
+    /// Returns the stable number of this variant, 1001 and up.
+    ///
+    /// Unlike [`code`](Self::code), which groups errors by spec class, the
+    /// number identifies the variant, so ports and HTTP APIs can report the
+    /// same error the same way. Numbers are never reused; new variants take
+    /// the next free one.
+    pub fn number(&self) -> u16 {
+        match self {
+            DecodeError::InvalidMagic { .. } => 1001,
+            DecodeError::UnsupportedVersion { .. } => 1002,
+            DecodeError::IndexOutOfBounds { .. } => 1003,
+            DecodeError::InvalidUtf8 { .. } => 1004,
+            DecodeError::UnexpectedEof { .. } => 1005,
+            DecodeError::VarintTooLong => 1006,
+            DecodeError::VarintOverflow => 1007,
+            DecodeError::LengthExceedsLimit { .. } => 1008,
+            DecodeError::InvalidOpType { .. } => 1009,
+            DecodeError::InvalidDataType { .. } => 1010,
+            DecodeError::InvalidEmbeddingSubType { .. } => 1011,
+            DecodeError::InvalidBool { .. } => 1012,
+            DecodeError::ReservedBitsSet { .. } => 1013,
+            DecodeError::LatitudeOutOfRange { .. } => 1014,
+            DecodeError::LongitudeOutOfRange { .. } => 1015,
+            DecodeError::InvalidPositionChar { .. } => 1016,
+            DecodeError::PositionTooLong { .. } => 1017,
+            DecodeError::EmbeddingDataMismatch { .. } => 1018,
+            DecodeError::DecimalNotNormalized => 1019,
+            DecodeError::DecimalMantissaNotMinimal => 1020,
+            DecodeError::FloatIsNan => 1021,
+            DecodeError::AllocationBudgetExceeded { .. } => 1022,
+            DecodeError::MalformedEncoding { .. } => 1023,
+            DecodeError::DecompressionFailed(_) => 1024,
+            DecodeError::QuotaExceeded { .. } => 1025,
+            DecodeError::UnsupportedCodec { .. } => 1026,
+            DecodeError::UncompressedSizeMismatch { .. } => 1027,
+            DecodeError::DuplicateDictionaryEntry { .. } => 1028,
+        }
+    }
 }
 
 /// Error during binary encoding.
@@ -159,7 +212,7 @@ pub enum EncodeError {
         data_len: usize,
     },
 
-    #[error("zstd compression failed: {0}")]
+    #[error("compression failed: {0}")]
     CompressionFailed(String),
 
     #[error("DECIMAL value is not normalized (has trailing zeros)")]
@@ -192,11 +245,43 @@ pub enum EncodeError {
     #[error("duplicate author ID in canonical mode: {id:?}")]
     DuplicateAuthor { id: Id },
 
+    #[error("duplicate parent ID: {id:?}")]
+    DuplicateParent { id: Id },
+
     #[error("duplicate value (property={property:?}, language={language:?}) in canonical mode")]
     DuplicateValue { property: Id, language: Option<Id> },
 
     #[error("duplicate unset property (property={property:?}, language={language:?}) in canonical mode")]
     DuplicateUnset { property: Id, language: Option<Id> },
+
+    #[error("edit {id:?} lists itself as a parent")]
+    SelfParent { id: Id },
+}
+
+impl EncodeError {
+    /// Returns the stable number of this variant, 2001 and up (see
+    /// [`DecodeError::number`]).
+    pub fn number(&self) -> u16 {
+        match self {
+            EncodeError::LengthExceedsLimit { .. } => 2001,
+            EncodeError::EmbeddingDimensionMismatch { .. } => 2002,
+            EncodeError::CompressionFailed(_) => 2003,
+            EncodeError::DecimalNotNormalized => 2004,
+            EncodeError::FloatIsNan => 2005,
+            EncodeError::LatitudeOutOfRange { .. } => 2006,
+            EncodeError::LongitudeOutOfRange { .. } => 2007,
+            EncodeError::InvalidPositionChar => 2008,
+            EncodeError::PositionTooLong => 2009,
+            EncodeError::InvalidDate { .. } => 2010,
+            EncodeError::BatchEntityValueCountMismatch { .. } => 2011,
+            EncodeError::InvalidInput { .. } => 2012,
+            EncodeError::DuplicateAuthor { .. } => 2013,
+            EncodeError::DuplicateParent { .. } => 2014,
+            EncodeError::DuplicateValue { .. } => 2015,
+            EncodeError::DuplicateUnset { .. } => 2016,
+            EncodeError::SelfParent { .. } => 2017,
+        }
+    }
 }
 
 /// Error during semantic validation.
@@ -220,4 +305,344 @@ pub enum ValidationError {
         schema: DataType,
         declared: DataType,
     },
+
+    #[error("properties not in schema: {properties:?}")]
+    UnknownProperties { properties: Vec<Id> },
+
+    #[error("text value for property {property:?} is {len} bytes, max {max}")]
+    TextTooLong { property: Id, len: usize, max: usize },
+
+    #[error("text value for property {property:?} does not match the schema pattern")]
+    PatternMismatch { property: Id },
+
+    #[error("parent {parent:?} is listed more than once")]
+    DuplicateParent { parent: Id },
+
+    #[error("edit lists itself as a parent")]
+    SelfParent,
+
+    #[error("invalid value for property {property:?}: {reason}")]
+    InvalidValue { property: Id, reason: &'static str },
+}
+
+impl ValidationError {
+    /// Returns the stable number of this variant, 3001 and up (see
+    /// [`DecodeError::number`]).
+    pub fn number(&self) -> u16 {
+        match self {
+            ValidationError::TypeMismatch { .. } => 3001,
+            ValidationError::EntityIsDead { .. } => 3002,
+            ValidationError::RelationIsDead { .. } => 3003,
+            ValidationError::PropertyNotFound { .. } => 3004,
+            ValidationError::DataTypeInconsistent { .. } => 3005,
+            ValidationError::UnknownProperties { .. } => 3006,
+            ValidationError::TextTooLong { .. } => 3007,
+            ValidationError::PatternMismatch { .. } => 3008,
+            ValidationError::DuplicateParent { .. } => 3009,
+            ValidationError::SelfParent => 3010,
+            ValidationError::InvalidValue { .. } => 3011,
+        }
+    }
+}
+
+/// Error reassembling chunked content (see [`crate::codec::chunking`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ChunkError {
+    #[error("chunk {index} ({hash:?}) not found")]
+    MissingChunk { index: usize, hash: [u8; 32] },
+
+    #[error("chunk {index} is {actual} bytes, manifest says {expected}")]
+    LengthMismatch { index: usize, expected: usize, actual: usize },
+
+    #[error("chunk {index} does not match its hash")]
+    HashMismatch { index: usize },
+
+    #[error("reassembled content does not match the manifest content hash")]
+    ContentHashMismatch,
+}
+
+/// Error upgrading an edit to the current format version (see
+/// [`crate::codec::versions::upgrade`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum UpgradeError {
+    #[error("decoding failed: {0}")]
+    Decode(#[from] DecodeError),
+
+    #[error("re-encoding failed: {0}")]
+    Encode(#[from] EncodeError),
+}
+
+/// Where an edit stops matching itself after encoding and decoding (see
+/// [`crate::codec::roundtrip`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RoundtripDivergence {
+    #[error("encoding failed: {0}")]
+    Encode(#[from] EncodeError),
+
+    #[error("decoding failed: {0}")]
+    Decode(#[from] DecodeError),
+
+    #[error("header field {field} differs after decoding")]
+    Header { field: &'static str },
+
+    #[error("edit has {expected} ops but decoded to {actual}")]
+    OpCount { expected: usize, actual: usize },
+
+    #[error("op {index} differs after decoding: {expected} became {actual}")]
+    Op { index: usize, expected: String, actual: String },
+}
+
+/// Error signing or verifying author signatures (see [`crate::signature`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SignatureError {
+    #[error("[E003] invalid signature by author {author:?}")]
+    InvalidSignature { author: Id },
+
+    #[error("[E003] author {author:?} has not signed the edit")]
+    MissingSignature { author: Id },
+
+    #[error("[E003] author {author:?} signed more than once")]
+    DuplicateSignature { author: Id },
+
+    #[error("[E003] signer {author:?} is not an author of the edit")]
+    NotAnAuthor { author: Id },
+
+    #[error("[E003] no key registered for author {author:?}")]
+    UnknownKey { author: Id },
+
+    #[error("key resolution failed: {0}")]
+    Resolver(String),
+
+    #[error("hashing the edit failed: {0}")]
+    Encode(#[from] EncodeError),
+}
+
+impl SignatureError {
+    /// Returns the spec error code, if the error has one.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            SignatureError::Encode(_) | SignatureError::Resolver(_) => None,
+            _ => Some(ErrorCode::InvalidSignature),
+        }
+    }
+}
+
+/// Error fetching or publishing an edit over HTTP (see `crate::net`).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum NetError {
+    #[error("HTTP {status} from {url}")]
+    Status { url: String, status: u16 },
+
+    #[error("transport error: {0}")]
+    Transport(String),
+
+    #[error("body of {len} bytes exceeds limit {max}")]
+    TooLarge { len: usize, max: usize },
+
+    #[error("IPFS error: {0}")]
+    Ipfs(String),
+
+    #[error("decoding failed: {0}")]
+    Decode(#[from] DecodeError),
+
+    #[error("encoding failed: {0}")]
+    Encode(#[from] EncodeError),
+}
+
+/// Error replaying edits into a [`crate::state::Graph`].
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ReplayError {
+    #[error("edit {index} ({edit:?}) has created_at {created_at}, expected {expected} {previous}")]
+    OutOfOrder {
+        index: usize,
+        edit: Id,
+        created_at: i64,
+        previous: i64,
+        expected: &'static str,
+    },
+}
+
+/// Error reading or writing persisted state (see `crate::state::store`).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum StoreError {
+    #[error("database error: {0}")]
+    Database(String),
+
+    #[error("unsupported store schema version {version}")]
+    UnsupportedSchema { version: i64 },
+
+    #[error("corrupt store: {0}")]
+    Corrupt(&'static str),
+
+    #[error("decoding failed: {0}")]
+    Decode(#[from] DecodeError),
+
+    #[error("encoding failed: {0}")]
+    Encode(#[from] EncodeError),
+}
+
+/// Error mapping an entity onto a Rust type (see [`crate::mapping`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MappingError {
+    #[error("field {field}: property {property:?} has no value")]
+    Missing { field: &'static str, property: Id },
+
+    #[error("field {field}: expected {expected:?}, found {found:?}")]
+    WrongType {
+        field: &'static str,
+        expected: DataType,
+        found: DataType,
+    },
+}
+
+/// Error converting to or from an external format (see [`crate::interop`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum InteropError {
+    #[error("arrow error: {0}")]
+    Arrow(String),
+
+    #[error("parquet error: {0}")]
+    Parquet(String),
+
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    #[error("invalid DID {did:?}: {reason}")]
+    InvalidDid { did: String, reason: &'static str },
+
+    #[error("invalid CID {cid:?}: {reason}")]
+    InvalidCid { cid: String, reason: &'static str },
+}
+
+/// Error importing an external dataset (see [`crate::import`]).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ImportError {
+    #[error("CSV error: {0}")]
+    Csv(String),
+
+    #[error("JSON error: {0}")]
+    Json(String),
+
+    #[error("column {column:?} not found in header")]
+    MissingColumn { column: String },
+
+    #[error("row {row}, column {column:?}: {reason}")]
+    InvalidValue {
+        row: usize,
+        column: String,
+        reason: String,
+    },
+
+    #[error("claim for {property}: {reason}")]
+    InvalidClaim { property: String, reason: String },
+
+    #[error("{data_type:?} values cannot be imported from {format}")]
+    UnsupportedDataType {
+        data_type: DataType,
+        format: &'static str,
+    },
+}
+
+/// Error parsing a patch (see [`crate::patch`]).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PatchError {
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+
+    #[error("line {line}: unknown name {name:?}")]
+    UnknownName { line: usize, name: String },
+}
+
+/// Error reconciling sets of edits (see [`crate::sync`]).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SyncError {
+    #[error("digests have {ours} and {theirs} cells")]
+    SizeMismatch { ours: usize, theirs: usize },
+
+    #[error("difference does not fit a digest of {cells} cells")]
+    Undecodable { cells: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_numbers() {
+        let id = [0u8; 16];
+        let decode = [
+            DecodeError::InvalidMagic { found: *b"GRC1" },
+            DecodeError::UnsupportedVersion { version: 9 },
+            DecodeError::IndexOutOfBounds { dict: "properties", index: 1, size: 0 },
+            DecodeError::InvalidUtf8 { field: "name" },
+            DecodeError::UnexpectedEof { context: "op" },
+            DecodeError::VarintTooLong,
+            DecodeError::VarintOverflow,
+            DecodeError::LengthExceedsLimit { field: "name", len: 2, max: 1 },
+            DecodeError::InvalidOpType { op_type: 0 },
+            DecodeError::InvalidDataType { data_type: 0 },
+            DecodeError::InvalidEmbeddingSubType { sub_type: 9 },
+            DecodeError::InvalidBool { value: 2 },
+            DecodeError::ReservedBitsSet { context: "flags" },
+            DecodeError::LatitudeOutOfRange { lat: 91.0 },
+            DecodeError::LongitudeOutOfRange { lon: 181.0 },
+            DecodeError::InvalidPositionChar { char: '!' },
+            DecodeError::PositionTooLong { len: 65 },
+            DecodeError::EmbeddingDataMismatch { dims: 1, expected: 4, actual: 3 },
+            DecodeError::DecimalNotNormalized,
+            DecodeError::DecimalMantissaNotMinimal,
+            DecodeError::FloatIsNan,
+            DecodeError::AllocationBudgetExceeded { field: "ops", requested: 2, remaining: 1 },
+            DecodeError::MalformedEncoding { context: "value" },
+            DecodeError::DecompressionFailed("bad frame".to_string()),
+            DecodeError::QuotaExceeded { quota: "ops" },
+            DecodeError::UnsupportedCodec { codec: "zstd" },
+            DecodeError::UncompressedSizeMismatch { declared: 2, actual: 1 },
+            DecodeError::DuplicateDictionaryEntry { dict: "properties", id },
+        ];
+        let numbers: Vec<u16> = decode.iter().map(DecodeError::number).collect();
+        assert_eq!(numbers, (1001..=1028).collect::<Vec<_>>());
+
+        let encode = [
+            EncodeError::LengthExceedsLimit { field: "name", len: 2, max: 1 },
+            EncodeError::EmbeddingDimensionMismatch { sub_type: 0, dims: 1, data_len: 3 },
+            EncodeError::CompressionFailed("io".to_string()),
+            EncodeError::DecimalNotNormalized,
+            EncodeError::FloatIsNan,
+            EncodeError::LatitudeOutOfRange { lat: 91.0 },
+            EncodeError::LongitudeOutOfRange { lon: 181.0 },
+            EncodeError::InvalidPositionChar,
+            EncodeError::PositionTooLong,
+            EncodeError::InvalidDate { reason: "month" },
+            EncodeError::BatchEntityValueCountMismatch { expected: 2, actual: 1 },
+            EncodeError::InvalidInput { context: "ops" },
+            EncodeError::DuplicateAuthor { id },
+            EncodeError::DuplicateParent { id },
+            EncodeError::DuplicateValue { property: id, language: None },
+            EncodeError::DuplicateUnset { property: id, language: None },
+            EncodeError::SelfParent { id },
+        ];
+        let numbers: Vec<u16> = encode.iter().map(EncodeError::number).collect();
+        assert_eq!(numbers, (2001..=2017).collect::<Vec<_>>());
+
+        let validation = [
+            ValidationError::TypeMismatch { property: id, expected: DataType::Text },
+            ValidationError::EntityIsDead { entity: id },
+            ValidationError::RelationIsDead { relation: id },
+            ValidationError::PropertyNotFound { property: id },
+            ValidationError::DataTypeInconsistent { property: id, schema: DataType::Text, declared: DataType::Int64 },
+            ValidationError::UnknownProperties { properties: vec![id] },
+            ValidationError::TextTooLong { property: id, len: 2, max: 1 },
+            ValidationError::PatternMismatch { property: id },
+            ValidationError::DuplicateParent { parent: id },
+            ValidationError::SelfParent,
+            ValidationError::InvalidValue { property: id, reason: "range" },
+        ];
+        let numbers: Vec<u16> = validation.iter().map(ValidationError::number).collect();
+        assert_eq!(numbers, (3001..=3011).collect::<Vec<_>>());
+
+        // Spec codes stay coarse
+        assert_eq!(decode[5].code(), ErrorCode::MalformedEncoding);
+        assert_eq!(decode[1].code().code(), "E001");
+    }
 }
@@ -1,9 +1,14 @@
 //! Genesis Space well-known IDs.
 //!
 //! The Genesis Space provides well-known IDs for core properties, types,
-//! and relation types (spec Section 7).
+//! and relation types (spec Section 7), plus library-defined unit IDs.
+//! [`genesis_edit`] builds the Genesis Space edit itself, so indexers can
+//! bootstrap from the crate.
 
-use crate::model::{derived_uuid, Id};
+use crate::collections::FxHashMap;
+use crate::model::id::data_type_entity_id;
+use crate::model::{derived_uuid, DataType, Edit, EditBuilder, Id, Op, Value};
+use crate::names::NameResolver;
 
 // =============================================================================
 // ID DERIVATION
@@ -19,16 +24,33 @@ pub fn genesis_id(name: &str) -> Id {
     derived_uuid(input.as_bytes())
 }
 
-/// Derives a language ID from an ISO language code.
+/// Derives a language ID from a BCP 47 language tag.
 ///
 /// ```text
-/// id = derived_uuid("grc20:genesis:language:" + code)
+/// id = derived_uuid("grc20:genesis:language:" + lowercase(code))
 /// ```
+///
+/// See [`crate::lang::from_bcp47`] for tags that need more normalization
+/// (`en_US`, `zh-CN`).
 pub fn language_id(code: &str) -> Id {
-    let input = format!("grc20:genesis:language:{}", code);
+    let input = format!("grc20:genesis:language:{}", code.to_ascii_lowercase());
     derived_uuid(input.as_bytes())
 }
 
+/// Derives the ID of a data type entity (spec Section 7.5).
+///
+/// ```text
+/// id = derived_uuid("grc20:genesis:datatype:" + type_name)
+/// ```
+pub fn data_type_id(data_type: DataType) -> Id {
+    data_type_entity_id(data_type)
+}
+
+/// Returns the data type whose entity ID is `id`, if any.
+pub fn data_type_for_id(id: &Id) -> Option<DataType> {
+    DataType::ALL.into_iter().find(|&data_type| data_type_entity_id(data_type) == *id)
+}
+
 // =============================================================================
 // CORE PROPERTIES (Section 7.1)
 // =============================================================================
@@ -148,6 +170,9 @@ pub mod relation_types {
 
         /// RelatedTo relation - generic association
         pub static ref RELATED_TO: Id = genesis_id("RelatedTo");
+
+        /// DataType relation - property's expected data type
+        pub static ref DATA_TYPE: Id = genesis_id("DataType");
     }
 
     /// Returns the Types relation type ID.
@@ -164,6 +189,11 @@ pub mod relation_types {
     pub fn related_to() -> Id {
         *RELATED_TO
     }
+
+    /// Returns the DataType relation type ID.
+    pub fn data_type() -> Id {
+        *DATA_TYPE
+    }
 }
 
 // =============================================================================
@@ -187,6 +217,16 @@ pub mod languages {
         pub static ref RUSSIAN: Id = language_id("ru");
         pub static ref ARABIC: Id = language_id("ar");
         pub static ref HINDI: Id = language_id("hi");
+        pub static ref CHINESE_SIMPLIFIED: Id = language_id("zh-hans");
+        pub static ref CHINESE_TRADITIONAL: Id = language_id("zh-hant");
+        pub static ref DUTCH: Id = language_id("nl");
+        pub static ref POLISH: Id = language_id("pl");
+        pub static ref TURKISH: Id = language_id("tr");
+        pub static ref UKRAINIAN: Id = language_id("uk");
+        pub static ref PERSIAN: Id = language_id("fa");
+        pub static ref HEBREW: Id = language_id("he");
+        pub static ref INDONESIAN: Id = language_id("id");
+        pub static ref VIETNAMESE: Id = language_id("vi");
     }
 
     /// Returns the language ID for the given ISO code.
@@ -244,6 +284,266 @@ pub mod languages {
     pub fn hindi() -> Id {
         *HINDI
     }
+
+    pub fn chinese_simplified() -> Id {
+        *CHINESE_SIMPLIFIED
+    }
+
+    pub fn chinese_traditional() -> Id {
+        *CHINESE_TRADITIONAL
+    }
+
+    pub fn dutch() -> Id {
+        *DUTCH
+    }
+
+    pub fn polish() -> Id {
+        *POLISH
+    }
+
+    pub fn turkish() -> Id {
+        *TURKISH
+    }
+
+    pub fn ukrainian() -> Id {
+        *UKRAINIAN
+    }
+
+    pub fn persian() -> Id {
+        *PERSIAN
+    }
+
+    pub fn hebrew() -> Id {
+        *HEBREW
+    }
+
+    pub fn indonesian() -> Id {
+        *INDONESIAN
+    }
+
+    pub fn vietnamese() -> Id {
+        *VIETNAMESE
+    }
+}
+
+// =============================================================================
+// UNITS
+// =============================================================================
+
+/// Well-known unit IDs (see [`crate::units`] for derivation and conversion).
+pub mod units {
+    use super::*;
+    use crate::units::unit_id;
+
+    lazy_static::lazy_static! {
+        pub static ref METER: Id = unit_id("m");
+        pub static ref KILOMETER: Id = unit_id("km");
+        pub static ref SQUARE_METER: Id = unit_id("m2");
+        pub static ref SQUARE_KILOMETER: Id = unit_id("km2");
+        pub static ref HECTARE: Id = unit_id("ha");
+        pub static ref LITER: Id = unit_id("l");
+        pub static ref KILOGRAM: Id = unit_id("kg");
+        pub static ref GRAM: Id = unit_id("g");
+        pub static ref SECOND: Id = unit_id("s");
+        pub static ref HOUR: Id = unit_id("h");
+        pub static ref DAY: Id = unit_id("d");
+        pub static ref CELSIUS: Id = unit_id("degC");
+        pub static ref KELVIN: Id = unit_id("K");
+        pub static ref PERCENT: Id = unit_id("percent");
+        pub static ref USD: Id = unit_id("USD");
+        pub static ref EUR: Id = unit_id("EUR");
+        pub static ref GBP: Id = unit_id("GBP");
+    }
+
+    /// Returns the Meter unit ID (`m`).
+    pub fn meter() -> Id {
+        *METER
+    }
+
+    /// Returns the Kilometer unit ID (`km`).
+    pub fn kilometer() -> Id {
+        *KILOMETER
+    }
+
+    /// Returns the Square meter unit ID (`m2`).
+    pub fn square_meter() -> Id {
+        *SQUARE_METER
+    }
+
+    /// Returns the Square kilometer unit ID (`km2`).
+    pub fn square_kilometer() -> Id {
+        *SQUARE_KILOMETER
+    }
+
+    /// Returns the Hectare unit ID (`ha`).
+    pub fn hectare() -> Id {
+        *HECTARE
+    }
+
+    /// Returns the Liter unit ID (`l`).
+    pub fn liter() -> Id {
+        *LITER
+    }
+
+    /// Returns the Kilogram unit ID (`kg`).
+    pub fn kilogram() -> Id {
+        *KILOGRAM
+    }
+
+    /// Returns the Gram unit ID (`g`).
+    pub fn gram() -> Id {
+        *GRAM
+    }
+
+    /// Returns the Second unit ID (`s`).
+    pub fn second() -> Id {
+        *SECOND
+    }
+
+    /// Returns the Hour unit ID (`h`).
+    pub fn hour() -> Id {
+        *HOUR
+    }
+
+    /// Returns the Day unit ID (`d`).
+    pub fn day() -> Id {
+        *DAY
+    }
+
+    /// Returns the Degree Celsius unit ID (`degC`).
+    pub fn celsius() -> Id {
+        *CELSIUS
+    }
+
+    /// Returns the Kelvin unit ID (`K`).
+    pub fn kelvin() -> Id {
+        *KELVIN
+    }
+
+    /// Returns the Percent unit ID (`percent`).
+    pub fn percent() -> Id {
+        *PERCENT
+    }
+
+    /// Returns the US dollar unit ID (`USD`).
+    pub fn usd() -> Id {
+        *USD
+    }
+
+    /// Returns the Euro unit ID (`EUR`).
+    pub fn eur() -> Id {
+        *EUR
+    }
+
+    /// Returns the Pound sterling unit ID (`GBP`).
+    pub fn gbp() -> Id {
+        *GBP
+    }
+}
+
+// =============================================================================
+// GENESIS EDIT
+// =============================================================================
+
+const LANGUAGES: [(&str, &str); 22] = [
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("zh", "Chinese"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("pt", "Portuguese"),
+    ("it", "Italian"),
+    ("ru", "Russian"),
+    ("ar", "Arabic"),
+    ("hi", "Hindi"),
+    ("zh-hans", "Chinese (Simplified)"),
+    ("zh-hant", "Chinese (Traditional)"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("fa", "Persian"),
+    ("he", "Hebrew"),
+    ("id", "Indonesian"),
+    ("vi", "Vietnamese"),
+];
+
+/// Builds the Genesis Space edit from the IDs in this module.
+///
+/// Creates a named entity for every data type, core property, core type,
+/// core relation type, and language. Properties are declared with
+/// [`EditBuilder::create_property`], whose relation IDs are derived, so the
+/// edit (and its encoding) is the same on every run. Units are
+/// library-defined and not included.
+pub fn genesis_edit() -> Edit<'static> {
+    let properties = [
+        ("Name", properties::name(), DataType::Text),
+        ("Description", properties::description(), DataType::Text),
+        ("Avatar", properties::avatar(), DataType::Text),
+        ("URL", properties::url(), DataType::Text),
+        ("Created", properties::created(), DataType::Datetime),
+        ("Modified", properties::modified(), DataType::Datetime),
+    ];
+    let types = [
+        ("Person", types::person()),
+        ("Organization", types::organization()),
+        ("Place", types::place()),
+        ("Topic", types::topic()),
+    ];
+    let relation_types = [
+        ("Types", relation_types::types()),
+        ("PartOf", relation_types::part_of()),
+        ("RelatedTo", relation_types::related_to()),
+        ("DataType", relation_types::data_type()),
+    ];
+
+    let name = properties::name();
+    let mut builder = EditBuilder::new(genesis_id("GenesisEdit")).name("Genesis");
+    for data_type in DataType::ALL {
+        builder = builder.create_entity(data_type_id(data_type), |e| e.text(name, data_type.name(), None));
+    }
+    for (label, id, data_type) in properties {
+        builder = builder.create_property(id, data_type, |e| e.text(name, label, None));
+    }
+    for (label, id) in types.into_iter().chain(relation_types) {
+        builder = builder.create_entity(id, |e| e.text(name, label, None));
+    }
+    for (code, label) in LANGUAGES {
+        builder = builder.create_entity(language_id(code), |e| e.text(name, label, None));
+    }
+    builder.build()
+}
+
+// =============================================================================
+// NAMES
+// =============================================================================
+
+lazy_static::lazy_static! {
+    static ref NAMES: FxHashMap<Id, String> = genesis_edit()
+        .ops
+        .iter()
+        .filter_map(|op| match op {
+            Op::CreateEntity(ce) => ce.values.iter().find_map(|pv| match &pv.value {
+                Value::Text { value, .. } if pv.property == properties::name() => Some((ce.id, value.to_string())),
+                _ => None,
+            }),
+            _ => None,
+        })
+        .collect();
+}
+
+/// Names the entities of [`genesis_edit`] (data types, core properties,
+/// types, relation types, and languages) for diagnostics; see
+/// [`crate::names`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GenesisNames;
+
+impl NameResolver for GenesisNames {
+    fn name(&self, id: &Id) -> Option<String> {
+        NAMES.get(id).cloned()
+    }
 }
 
 #[cfg(test)]
@@ -251,6 +551,15 @@ mod tests {
     use super::*;
     use crate::model::format_id;
 
+    #[test]
+    fn test_genesis_names() {
+        assert_eq!(GenesisNames.name(&properties::name()).as_deref(), Some("Name"));
+        assert_eq!(GenesisNames.name(&relation_types::types()).as_deref(), Some("Types"));
+        assert_eq!(GenesisNames.name(&languages::german()).as_deref(), Some("German"));
+        assert_eq!(GenesisNames.name(&data_type_id(DataType::Int64)).as_deref(), Some(DataType::Int64.name()));
+        assert_eq!(GenesisNames.name(&[1u8; 16]), None);
+    }
+
     #[test]
     fn test_genesis_id_deterministic() {
         let id1 = genesis_id("Name");
@@ -313,6 +622,42 @@ mod tests {
         assert_eq!(languages::english(), language_id("en"));
         assert_eq!(languages::spanish(), language_id("es"));
         assert_eq!(languages::from_code("en"), languages::english());
+        assert_eq!(languages::from_code("EN"), languages::english());
+        assert_eq!(languages::chinese_simplified(), language_id("zh-hans"));
+    }
+
+    #[test]
+    fn test_static_units() {
+        assert_eq!(units::kilometer(), crate::units::unit_id("km"));
+        assert_eq!(crate::units::convert(2.0, &units::kilometer(), &units::meter()), Some(2000.0));
+    }
+
+    #[test]
+    fn test_genesis_edit() {
+        use crate::codec::{decode_edit, encode_edit};
+        use crate::model::Op;
+
+        let edit = genesis_edit();
+        assert_eq!(edit, genesis_edit());
+        assert_eq!(decode_edit(&encode_edit(&edit).unwrap()).unwrap(), edit);
+        assert!(crate::analysis::id_report(&edit).is_clean());
+
+        assert_eq!(data_type_id(DataType::Int64), derived_uuid(b"grc20:genesis:datatype:int64"));
+        assert_eq!(data_type_for_id(&data_type_id(DataType::Rect)), Some(DataType::Rect));
+        assert_eq!(data_type_for_id(&properties::name()), None);
+        let relations: Vec<_> = edit
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::CreateRelation(r) => Some((r.from, r.to)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(relations.len(), 6);
+        assert_eq!(relation_types::data_type(), crate::model::id::data_type_relation_type());
+        assert_eq!(relation_types::types(), crate::model::id::types_relation_type());
+        assert!(relations.contains(&(properties::created(), data_type_id(DataType::Datetime))));
+        assert!(edit.ops.iter().any(|op| op.id() == languages::vietnamese()));
     }
 
     #[test]
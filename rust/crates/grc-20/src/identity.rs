@@ -0,0 +1,126 @@
+//! Author identity resolution (requires the `signature` feature).
+//!
+//! Signature verification asks a [`KeyResolver`] for the keys an author
+//! could sign with when the edit was made. [`KeyRegistry`] keeps them in
+//! memory; directories backed by on-chain registries or DID documents
+//! implement the trait themselves.
+//!
+//! Key validity is checked against the edit's `created_at`, which the
+//! authors chose themselves. A revoked key can still sign an edit that
+//! claims an earlier time, so resolvers that need hard revocation should
+//! also compare against when the edit was published.
+
+use ed25519_dalek::VerifyingKey;
+
+use crate::collections::FxHashMap;
+use crate::error::SignatureError;
+use crate::model::Id;
+use crate::prelude::*;
+
+/// Looks up the public keys of authors.
+pub trait KeyResolver {
+    /// Returns the keys `author` could sign with at `at` (microseconds since
+    /// the Unix epoch, as in `Edit::created_at`).
+    ///
+    /// An empty list means the author is unknown or had no valid key then.
+    /// Errors are for failed lookups, e.g. an unreachable directory.
+    fn resolve(&self, author: &Id, at: i64) -> Result<Vec<VerifyingKey>, SignatureError>;
+}
+
+impl<R: KeyResolver + ?Sized> KeyResolver for &R {
+    fn resolve(&self, author: &Id, at: i64) -> Result<Vec<VerifyingKey>, SignatureError> {
+        (**self).resolve(author, at)
+    }
+}
+
+/// A key and the time range it is valid in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: VerifyingKey,
+    /// First valid timestamp (inclusive), or `None` for no lower bound.
+    pub valid_from: Option<i64>,
+    /// End of validity (exclusive), or `None` if the key is not revoked.
+    pub valid_until: Option<i64>,
+}
+
+impl KeyBinding {
+    /// Returns true if the key is valid at `at`.
+    pub fn is_valid_at(&self, at: i64) -> bool {
+        self.valid_from.is_none_or(|from| at >= from) && self.valid_until.is_none_or(|until| at < until)
+    }
+}
+
+/// An in-memory [`KeyResolver`].
+#[derive(Debug, Clone, Default)]
+pub struct KeyRegistry {
+    keys: FxHashMap<Id, Vec<KeyBinding>>,
+}
+
+impl KeyRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a key that is valid at all times.
+    pub fn insert(&mut self, author: Id, key: VerifyingKey) {
+        self.insert_bounded(author, key, None, None);
+    }
+
+    /// Registers a key valid from `valid_from` (inclusive) until
+    /// `valid_until` (exclusive), e.g. for rotated keys.
+    pub fn insert_bounded(
+        &mut self,
+        author: Id,
+        key: VerifyingKey,
+        valid_from: Option<i64>,
+        valid_until: Option<i64>,
+    ) {
+        self.keys.entry(author).or_default().push(KeyBinding { key, valid_from, valid_until });
+    }
+
+    /// Returns all keys registered for `author`, in insertion order.
+    pub fn bindings(&self, author: &Id) -> &[KeyBinding] {
+        self.keys.get(author).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the number of registered authors.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns true if no keys are registered.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl KeyResolver for KeyRegistry {
+    fn resolve(&self, author: &Id, at: i64) -> Result<Vec<VerifyingKey>, SignatureError> {
+        Ok(self.bindings(author).iter().filter(|b| b.is_valid_at(at)).map(|b| b.key).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    #[test]
+    fn test_registry_time_bounds() {
+        let old = SigningKey::from_bytes(&[1u8; 32]).verifying_key();
+        let new = SigningKey::from_bytes(&[2u8; 32]).verifying_key();
+        let author = [5u8; 16];
+
+        let mut registry = KeyRegistry::new();
+        registry.insert_bounded(author, old, None, Some(100));
+        registry.insert_bounded(author, new, Some(100), None);
+
+        assert_eq!(registry.resolve(&author, 99).unwrap(), vec![old]);
+        assert_eq!(registry.resolve(&author, 100).unwrap(), vec![new]);
+        assert_eq!(registry.resolve(&author, i64::MIN).unwrap(), vec![old]);
+        assert!(registry.resolve(&[6u8; 16], 0).unwrap().is_empty());
+        assert_eq!(registry.bindings(&author).len(), 2);
+        assert_eq!(registry.len(), 1);
+    }
+}
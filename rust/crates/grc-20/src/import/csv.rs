@@ -0,0 +1,623 @@
+//! CSV/TSV bulk import driven by a [`MappingSpec`].
+//!
+//! Each data row becomes one CreateEntity op. The mapping spec declares:
+//!
+//! - how the entity ID is obtained (a hex ID column, an ID derived from a
+//!   key column, or an ID derived from the row number)
+//! - column → property bindings with their data types, optional units, and
+//!   a fixed language or a per-row language column (BCP 47 codes)
+//! - relation columns whose cells name one or more target entities
+//! - type IDs that every imported entity is linked to via `Types`
+//!
+//! ```ignore
+//! use grc_20::import::csv::{import, IdFormat, MappingSpec, RelationMapping};
+//! use grc_20::genesis::{properties, types};
+//!
+//! let spec = MappingSpec::new(edit_id)
+//!     .id_column("qid", IdFormat::derived("wikidata:"))
+//!     .entity_type(types::place())
+//!     .text("name", properties::name(), None)
+//!     .property("population", population, DataType::Int64)
+//!     .relation(RelationMapping::new("country", country_rel, IdFormat::derived("wikidata:")));
+//!
+//! let edit = import(std::fs::File::open("cities.csv")?, &spec)?;
+//! ```
+//!
+//! Empty cells are skipped. Relation IDs use unique mode
+//! (`derived_uuid(from || to || type)`), so importing the same rows twice
+//! produces the same relations.
+
+use std::borrow::Cow;
+use std::io::Read;
+
+use crate::error::ImportError;
+use crate::import::parse_decimal;
+use crate::genesis::{language_id, relation_types};
+use crate::lang::from_bcp47;
+use crate::model::id::unique_relation_id;
+use crate::model::{
+    derived_uuid, parse_id, CreateEntity, DataType, Edit, EditBuilder, Id, Op,
+    PropertyValue, Value,
+};
+use crate::util::{parse_date_rfc3339, parse_datetime_rfc3339, parse_time_rfc3339};
+
+/// How a cell is turned into an ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdFormat {
+    /// The cell holds a GRC-20 ID as hex (hyphens allowed).
+    Hex,
+    /// The ID is `derived_uuid(namespace || cell)`, e.g. namespace
+    /// `"wikidata:"` and cell `"Q64"`.
+    Derived { namespace: String },
+}
+
+impl IdFormat {
+    /// Creates a derived ID format with the given namespace prefix.
+    pub fn derived(namespace: impl Into<String>) -> Self {
+        IdFormat::Derived { namespace: namespace.into() }
+    }
+
+    fn resolve(&self, cell: &str) -> Result<Id, String> {
+        match self {
+            IdFormat::Hex => parse_id(cell).ok_or_else(|| format!("invalid ID {:?}", cell)),
+            IdFormat::Derived { namespace } => {
+                let mut input = Vec::with_capacity(namespace.len() + cell.len());
+                input.extend_from_slice(namespace.as_bytes());
+                input.extend_from_slice(cell.as_bytes());
+                Ok(derived_uuid(&input))
+            }
+        }
+    }
+}
+
+/// Where the entity ID of a row comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityIdSource {
+    /// `derived_uuid(edit_id || row_number)` with a big-endian u64 row number.
+    RowNumber,
+    /// A key column.
+    Column { column: String, format: IdFormat },
+}
+
+/// Language of a TEXT column.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LanguageSource {
+    /// No language (default).
+    #[default]
+    None,
+    /// Every value has the same language.
+    Fixed(Id),
+    /// A column holding a BCP 47 code per row; empty cells mean no language.
+    Column(String),
+}
+
+/// Binds a column to a property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyMapping {
+    pub column: String,
+    pub property: Id,
+    pub data_type: DataType,
+    pub language: LanguageSource,
+    pub unit: Option<Id>,
+}
+
+impl PropertyMapping {
+    /// Creates a mapping without language or unit.
+    pub fn new(column: impl Into<String>, property: Id, data_type: DataType) -> Self {
+        Self {
+            column: column.into(),
+            property,
+            data_type,
+            language: LanguageSource::None,
+            unit: None,
+        }
+    }
+
+    /// Sets a fixed language (TEXT only).
+    pub fn language(mut self, language: Id) -> Self {
+        self.language = LanguageSource::Fixed(language);
+        self
+    }
+
+    /// Reads the language from a BCP 47 code column (TEXT only).
+    pub fn language_column(mut self, column: impl Into<String>) -> Self {
+        self.language = LanguageSource::Column(column.into());
+        self
+    }
+
+    /// Sets the unit (INT64, FLOAT64, DECIMAL only).
+    pub fn unit(mut self, unit: Id) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+}
+
+/// Binds a pair of latitude/longitude columns to a POINT property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointMapping {
+    pub lat_column: String,
+    pub lon_column: String,
+    pub property: Id,
+}
+
+/// Turns a column into relations from the row's entity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationMapping {
+    pub column: String,
+    pub relation_type: Id,
+    pub format: IdFormat,
+    /// Splits a cell into several targets (e.g. `';'`).
+    pub separator: Option<char>,
+}
+
+impl RelationMapping {
+    /// Creates a mapping with one target per cell.
+    pub fn new(column: impl Into<String>, relation_type: Id, format: IdFormat) -> Self {
+        Self {
+            column: column.into(),
+            relation_type,
+            format,
+            separator: None,
+        }
+    }
+
+    /// Splits cells on `separator` into several targets.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+}
+
+/// Declares how a CSV/TSV file maps onto an edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappingSpec {
+    pub edit_id: Id,
+    pub name: String,
+    pub authors: Vec<Id>,
+    pub created_at: i64,
+    pub delimiter: u8,
+    pub entity_id: EntityIdSource,
+    pub types: Vec<Id>,
+    pub properties: Vec<PropertyMapping>,
+    pub points: Vec<PointMapping>,
+    pub relations: Vec<RelationMapping>,
+}
+
+impl MappingSpec {
+    /// Creates an empty comma-separated mapping for the given edit ID.
+    pub fn new(edit_id: Id) -> Self {
+        Self {
+            edit_id,
+            name: String::new(),
+            authors: Vec::new(),
+            created_at: 0,
+            delimiter: b',',
+            entity_id: EntityIdSource::RowNumber,
+            types: Vec::new(),
+            properties: Vec::new(),
+            points: Vec::new(),
+            relations: Vec::new(),
+        }
+    }
+
+    /// Sets the edit name.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Adds an author.
+    pub fn author(mut self, author: Id) -> Self {
+        self.authors.push(author);
+        self
+    }
+
+    /// Sets the edit timestamp (microseconds since epoch).
+    pub fn created_at(mut self, created_at: i64) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Sets the field delimiter.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Uses tab-separated input.
+    pub fn tsv(self) -> Self {
+        self.delimiter(b'\t')
+    }
+
+    /// Takes entity IDs from a key column.
+    pub fn id_column(mut self, column: impl Into<String>, format: IdFormat) -> Self {
+        self.entity_id = EntityIdSource::Column { column: column.into(), format };
+        self
+    }
+
+    /// Links every imported entity to `type_id` via the `Types` relation.
+    pub fn entity_type(mut self, type_id: Id) -> Self {
+        self.types.push(type_id);
+        self
+    }
+
+    /// Binds a column to a property.
+    pub fn property(mut self, column: impl Into<String>, property: Id, data_type: DataType) -> Self {
+        self.properties.push(PropertyMapping::new(column, property, data_type));
+        self
+    }
+
+    /// Binds a column to a TEXT property with an optional fixed language.
+    pub fn text(self, column: impl Into<String>, property: Id, language: Option<Id>) -> Self {
+        let mapping = PropertyMapping::new(column, property, DataType::Text);
+        match language {
+            Some(language) => self.map(mapping.language(language)),
+            None => self.map(mapping),
+        }
+    }
+
+    /// Adds a fully configured property mapping.
+    pub fn map(mut self, mapping: PropertyMapping) -> Self {
+        self.properties.push(mapping);
+        self
+    }
+
+    /// Binds latitude/longitude columns to a POINT property.
+    pub fn point(mut self, lat_column: impl Into<String>, lon_column: impl Into<String>, property: Id) -> Self {
+        self.points.push(PointMapping {
+            lat_column: lat_column.into(),
+            lon_column: lon_column.into(),
+            property,
+        });
+        self
+    }
+
+    /// Adds a relation column.
+    pub fn relation(mut self, mapping: RelationMapping) -> Self {
+        self.relations.push(mapping);
+        self
+    }
+}
+
+/// Reads CSV/TSV data with a header row and converts it into an edit.
+pub fn import<R: Read>(reader: R, spec: &MappingSpec) -> Result<Edit<'static>, ImportError> {
+    for mapping in &spec.properties {
+        if matches!(mapping.data_type, DataType::Rect | DataType::Embedding) {
+            return Err(ImportError::UnsupportedDataType {
+                data_type: mapping.data_type,
+                format: "CSV",
+            });
+        }
+    }
+
+    let mut csv = ::csv::ReaderBuilder::new()
+        .delimiter(spec.delimiter)
+        .has_headers(true)
+        .from_reader(reader);
+    let headers = csv.headers().map_err(|e| ImportError::Csv(e.to_string()))?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| ImportError::MissingColumn { column: name.to_string() })
+    };
+
+    // Resolve column names to indices once
+    let id_column = match &spec.entity_id {
+        EntityIdSource::RowNumber => None,
+        EntityIdSource::Column { column: name, format } => Some((column(name)?, name.as_str(), format)),
+    };
+    let mut properties = Vec::with_capacity(spec.properties.len());
+    for mapping in &spec.properties {
+        let language_column = match &mapping.language {
+            LanguageSource::Column(name) => Some(column(name)?),
+            _ => None,
+        };
+        properties.push((column(&mapping.column)?, language_column, mapping));
+    }
+    let mut points = Vec::with_capacity(spec.points.len());
+    for mapping in &spec.points {
+        points.push((column(&mapping.lat_column)?, column(&mapping.lon_column)?, mapping));
+    }
+    let mut relations = Vec::with_capacity(spec.relations.len());
+    for mapping in &spec.relations {
+        relations.push((column(&mapping.column)?, mapping));
+    }
+
+    let mut builder = EditBuilder::new(spec.edit_id)
+        .name(spec.name.clone())
+        .authors(spec.authors.iter().copied())
+        .created_at(spec.created_at);
+    let types_rel = relation_types::types();
+
+    for (index, record) in csv.records().enumerate() {
+        let record = record.map_err(|e| ImportError::Csv(e.to_string()))?;
+        let row = index + 1;
+        let cell = |i: usize| record.get(i).unwrap_or("").trim();
+        let invalid = |column: &str, reason: String| ImportError::InvalidValue {
+            row,
+            column: column.to_string(),
+            reason,
+        };
+
+        let entity = match id_column {
+            None => {
+                let mut input = [0u8; 24];
+                input[..16].copy_from_slice(&spec.edit_id);
+                input[16..].copy_from_slice(&(index as u64).to_be_bytes());
+                derived_uuid(&input)
+            }
+            Some((i, name, format)) => {
+                if cell(i).is_empty() {
+                    return Err(invalid(name, "empty ID".to_string()));
+                }
+                format.resolve(cell(i)).map_err(|reason| invalid(name, reason))?
+            }
+        };
+
+        let mut values = Vec::with_capacity(properties.len() + points.len());
+        for &(i, language_column, mapping) in &properties {
+            let text = cell(i);
+            if text.is_empty() {
+                continue;
+            }
+            let language = match (&mapping.language, language_column) {
+                (LanguageSource::Fixed(id), _) => Some(*id),
+                (LanguageSource::Column(_), Some(j)) if !cell(j).is_empty() => {
+                    Some(from_bcp47(cell(j)).unwrap_or_else(|| language_id(cell(j))))
+                }
+                _ => None,
+            };
+            let value = parse_cell(mapping.data_type, text, language, mapping.unit)
+                .map_err(|reason| invalid(&mapping.column, reason))?;
+            values.push(PropertyValue { property: mapping.property, value });
+        }
+        for &(lat_i, lon_i, mapping) in &points {
+            if cell(lat_i).is_empty() && cell(lon_i).is_empty() {
+                continue;
+            }
+            let lat = parse_float(cell(lat_i)).map_err(|reason| invalid(&mapping.lat_column, reason))?;
+            let lon = parse_float(cell(lon_i)).map_err(|reason| invalid(&mapping.lon_column, reason))?;
+            values.push(PropertyValue {
+                property: mapping.property,
+                value: Value::Point { lat, lon, alt: None },
+            });
+        }
+        builder = builder.op(Op::CreateEntity(CreateEntity { id: entity, values, context: None }));
+
+        for type_id in &spec.types {
+            let id = unique_relation_id(&entity, type_id, &types_rel);
+            builder = builder.create_relation_simple(id, entity, *type_id, types_rel);
+        }
+
+        for &(i, mapping) in &relations {
+            let text = cell(i);
+            let targets: Vec<&str> = match mapping.separator {
+                Some(sep) => text.split(sep).map(str::trim).filter(|t| !t.is_empty()).collect(),
+                None if text.is_empty() => Vec::new(),
+                None => vec![text],
+            };
+            for target in targets {
+                let to = mapping.format.resolve(target).map_err(|reason| invalid(&mapping.column, reason))?;
+                let id = unique_relation_id(&entity, &to, &mapping.relation_type);
+                builder = builder.create_relation_simple(id, entity, to, mapping.relation_type);
+            }
+        }
+    }
+
+    Ok(builder.build())
+}
+
+/// Parses a cell into a value of the given type.
+fn parse_cell(
+    data_type: DataType,
+    text: &str,
+    language: Option<Id>,
+    unit: Option<Id>,
+) -> Result<Value<'static>, String> {
+    let owned = || Cow::Owned(text.to_string());
+    let value = match data_type {
+        DataType::Bool => match text.to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Value::Bool(true),
+            "false" | "f" | "no" | "n" | "0" => Value::Bool(false),
+            _ => return Err(format!("invalid BOOL {:?}", text)),
+        },
+        DataType::Int64 => Value::Int64 {
+            value: text.parse().map_err(|_| format!("invalid INT64 {:?}", text))?,
+            unit,
+        },
+        DataType::Float64 => Value::Float64 { value: parse_float(text)?, unit },
+        DataType::Decimal => {
+            let (exponent, mantissa) = parse_decimal(text).ok_or_else(|| format!("invalid DECIMAL {:?}", text))?;
+            Value::Decimal { exponent, mantissa, unit }
+        }
+        DataType::Text => Value::Text { value: owned(), language },
+        DataType::Bytes => Value::Bytes(Cow::Owned(parse_hex(text)?)),
+        DataType::Date => {
+            parse_date_rfc3339(text).map_err(|e| e.to_string())?;
+            Value::Date(owned())
+        }
+        DataType::Time => {
+            parse_time_rfc3339(text).map_err(|e| e.to_string())?;
+            Value::Time(owned())
+        }
+        DataType::Datetime => {
+            parse_datetime_rfc3339(text).map_err(|e| e.to_string())?;
+            Value::Datetime(owned())
+        }
+        DataType::Schedule => Value::Schedule(owned()),
+        DataType::Point => {
+            let parts: Vec<&str> = text.split(',').map(str::trim).collect();
+            match parts.as_slice() {
+                [lat, lon] => Value::Point { lat: parse_float(lat)?, lon: parse_float(lon)?, alt: None },
+                [lat, lon, alt] => Value::Point {
+                    lat: parse_float(lat)?,
+                    lon: parse_float(lon)?,
+                    alt: Some(parse_float(alt)?),
+                },
+                _ => return Err(format!("invalid POINT {:?} (expected \"lat,lon[,alt]\")", text)),
+            }
+        }
+        DataType::Rect | DataType::Embedding => {
+            return Err(format!("{:?} cannot be imported from a single cell", data_type));
+        }
+    };
+    if let Some(reason) = value.validate() {
+        return Err(reason.to_string());
+    }
+    Ok(value)
+}
+
+fn parse_float(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(v) if !v.is_nan() => Ok(v),
+        _ => Err(format!("invalid FLOAT64 {:?}", text)),
+    }
+}
+
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let hex = text.strip_prefix("0x").unwrap_or(text);
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(format!("invalid hex BYTES {:?}", text));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("invalid hex BYTES {:?}", text)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit, encode_edit};
+    use crate::genesis::{languages, properties, types};
+
+    const CITIES: &str = "\
+qid,name,name_lang,population,area,lat,lon,country,tags
+Q64,Berlin,de,3600000,891.8,52.52,13.405,Q183,capital;city
+Q90,Paris,fr,2100000,,48.8566,2.3522,Q142,
+";
+
+    fn cities_spec() -> MappingSpec {
+        MappingSpec::new([1u8; 16])
+            .name("Cities")
+            .author([2u8; 16])
+            .id_column("qid", IdFormat::derived("wikidata:"))
+            .entity_type(types::place())
+            .map(PropertyMapping::new("name", properties::name(), DataType::Text).language_column("name_lang"))
+            .property("population", [10u8; 16], DataType::Int64)
+            .map(PropertyMapping::new("area", [11u8; 16], DataType::Float64).unit([12u8; 16]))
+            .point("lat", "lon", [13u8; 16])
+            .relation(RelationMapping::new("country", [20u8; 16], IdFormat::derived("wikidata:")))
+            .relation(RelationMapping::new("tags", [21u8; 16], IdFormat::derived("tag:")).separator(';'))
+    }
+
+    fn entities<'a>(edit: &'a Edit<'_>) -> Vec<&'a CreateEntity<'a>> {
+        edit.ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::CreateEntity(ce) => Some(ce),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_import_cities() {
+        let edit = import(CITIES.as_bytes(), &cities_spec()).unwrap();
+        assert_eq!(edit.id, [1u8; 16]);
+        assert_eq!(edit.name, "Cities");
+
+        let berlin_id = derived_uuid(b"wikidata:Q64");
+        let entities = entities(&edit);
+        assert_eq!(entities[0].id, berlin_id);
+
+        let values = &entities[0].values;
+        assert_eq!(values.len(), 4);
+        assert_eq!(
+            values[0].value,
+            Value::Text { value: Cow::Borrowed("Berlin"), language: Some(languages::german()) }
+        );
+        assert_eq!(values[1].value, Value::Int64 { value: 3_600_000, unit: None });
+        assert_eq!(values[2].value, Value::Float64 { value: 891.8, unit: Some([12u8; 16]) });
+        assert_eq!(values[3].value, Value::Point { lat: 52.52, lon: 13.405, alt: None });
+
+        // Paris has no area, so only three values
+        assert_eq!(entities[1].values.len(), 3);
+
+        // Berlin: types + country + 2 tags; Paris: types + country
+        let relations: Vec<_> = edit
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::CreateRelation(cr) => Some(cr),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(relations.len(), 6);
+        assert_eq!(relations[0].relation_type, relation_types::types());
+        assert_eq!(relations[0].to, types::place());
+        assert_eq!(relations[1].to, derived_uuid(b"wikidata:Q183"));
+        assert_eq!(relations[1].id, unique_relation_id(&berlin_id, &relations[1].to, &[20u8; 16]));
+        assert_eq!(relations[2].to, derived_uuid(b"tag:capital"));
+        assert_eq!(relations[3].to, derived_uuid(b"tag:city"));
+
+        // The result encodes and decodes
+        let encoded = encode_edit(&edit).unwrap();
+        assert_eq!(decode_edit(&encoded).unwrap().ops.len(), edit.ops.len());
+    }
+
+    #[test]
+    fn test_import_tsv_row_number_ids() {
+        let data = "name\tborn\nAda\t1815-12-10\nAlan\t1912-06-23\n";
+        let spec = MappingSpec::new([1u8; 16])
+            .tsv()
+            .text("name", properties::name(), Some(languages::english()))
+            .property("born", [10u8; 16], DataType::Date);
+        let edit = import(data.as_bytes(), &spec).unwrap();
+        assert_eq!(edit.ops.len(), 2);
+
+        let mut input = [0u8; 24];
+        input[..16].copy_from_slice(&[1u8; 16]);
+        input[16..].copy_from_slice(&1u64.to_be_bytes());
+        let alan = entities(&edit)[1];
+        assert_eq!(alan.id, derived_uuid(&input));
+        assert_eq!(alan.values[1].value, Value::Date(Cow::Borrowed("1912-06-23")));
+    }
+
+    #[test]
+    fn test_import_missing_column() {
+        let spec = MappingSpec::new([1u8; 16]).property("nope", [10u8; 16], DataType::Int64);
+        let err = import("a,b\n1,2\n".as_bytes(), &spec).unwrap_err();
+        assert_eq!(err, ImportError::MissingColumn { column: "nope".to_string() });
+    }
+
+    #[test]
+    fn test_import_invalid_value_reports_row() {
+        let spec = MappingSpec::new([1u8; 16]).property("n", [10u8; 16], DataType::Int64);
+        let err = import("n\n1\nabc\n".as_bytes(), &spec).unwrap_err();
+        assert!(matches!(err, ImportError::InvalidValue { row: 2, ref column, .. } if column == "n"));
+    }
+
+    #[test]
+    fn test_import_unsupported_type() {
+        let spec = MappingSpec::new([1u8; 16]).property("v", [10u8; 16], DataType::Embedding);
+        assert!(matches!(
+            import("v\n1\n".as_bytes(), &spec),
+            Err(ImportError::UnsupportedDataType { data_type: DataType::Embedding, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_cell_types() {
+        assert_eq!(parse_cell(DataType::Bool, "Yes", None, None), Ok(Value::Bool(true)));
+        assert_eq!(parse_cell(DataType::Bytes, "0x0aff", None, None), Ok(Value::Bytes(Cow::Owned(vec![0x0a, 0xff]))));
+        assert_eq!(
+            parse_cell(DataType::Point, "10.5, 20.25, 100", None, None),
+            Ok(Value::Point { lat: 10.5, lon: 20.25, alt: Some(100.0) })
+        );
+        assert!(parse_cell(DataType::Point, "91,0", None, None).is_err());
+        assert!(parse_cell(DataType::Float64, "NaN", None, None).is_err());
+        assert!(parse_cell(DataType::Datetime, "yesterday", None, None).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Importers that turn external datasets into GRC-20 edits.
+//!
+//! Importers are behind cargo features so the core crate stays lean:
+//!
+//! - [`csv`] (feature `csv`): CSV/TSV files with a column mapping
+//! - [`wikidata`] (feature `wikidata`): Wikidata JSON entities
+
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "wikidata")]
+pub mod wikidata;
+
+#[cfg(any(feature = "csv", feature = "wikidata"))]
+use std::borrow::Cow;
+
+#[cfg(any(feature = "csv", feature = "wikidata"))]
+use crate::model::DecimalMantissa;
+
+/// Parses a decimal string (e.g. `"-12.50"`, `"1.5e3"`) into a normalized
+/// (exponent, mantissa) pair with no trailing zeros in the mantissa.
+#[cfg(any(feature = "csv", feature = "wikidata"))]
+pub(crate) fn parse_decimal(text: &str) -> Option<(i32, DecimalMantissa<'static>)> {
+    let (number, exp) = match text.find(['e', 'E']) {
+        Some(pos) => (&text[..pos], text[pos + 1..].parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (negative, number) = match number.as_bytes().first()? {
+        b'-' => (true, &number[1..]),
+        b'+' => (false, &number[1..]),
+        _ => (false, number),
+    };
+    let (int_part, frac_part) = number.split_once('.').unwrap_or((number, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    if !int_part.bytes().chain(frac_part.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = format!("{}{}", int_part, frac_part);
+    let digits = digits.trim_start_matches('0');
+    let trimmed = digits.trim_end_matches('0');
+    if trimmed.is_empty() {
+        return Some((0, DecimalMantissa::I64(0)));
+    }
+    let exponent = exp
+        .checked_sub(i32::try_from(frac_part.len()).ok()?)?
+        .checked_add(i32::try_from(digits.len() - trimmed.len()).ok()?)?;
+
+    let magnitude: i128 = trimmed.parse().ok()?;
+    let mantissa = if negative { -magnitude } else { magnitude };
+    match i64::try_from(mantissa) {
+        Ok(v) => Some((exponent, DecimalMantissa::I64(v))),
+        Err(_) => {
+            // Minimal-length big-endian two's complement
+            let bytes = mantissa.to_be_bytes();
+            let mut start = 0;
+            while start < bytes.len() - 1 {
+                let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+                    || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+                if !redundant {
+                    break;
+                }
+                start += 1;
+            }
+            Some((exponent, DecimalMantissa::Big(Cow::Owned(bytes[start..].to_vec()))))
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "csv", feature = "wikidata")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("123.45"), Some((-2, DecimalMantissa::I64(12345))));
+        assert_eq!(parse_decimal("-12.50"), Some((-1, DecimalMantissa::I64(-125))));
+        assert_eq!(parse_decimal("1500"), Some((2, DecimalMantissa::I64(15))));
+        assert_eq!(parse_decimal("1.5e3"), Some((2, DecimalMantissa::I64(15))));
+        assert_eq!(parse_decimal("0.000"), Some((0, DecimalMantissa::I64(0))));
+        assert_eq!(parse_decimal(".5"), Some((-1, DecimalMantissa::I64(5))));
+        assert_eq!(parse_decimal("abc"), None);
+        assert_eq!(parse_decimal("-"), None);
+
+        // 2^64 needs a big mantissa: 0x01 followed by eight zero bytes
+        assert_eq!(
+            parse_decimal("18446744073709551617"),
+            Some((0, DecimalMantissa::Big(Cow::Owned(vec![0x01, 0, 0, 0, 0, 0, 0, 0, 0x01]))))
+        );
+    }
+}
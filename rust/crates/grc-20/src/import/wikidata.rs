@@ -0,0 +1,624 @@
+//! Wikidata entity import.
+//!
+//! Converts entities from the Wikidata JSON format (the `Special:EntityData`
+//! API and the JSON dumps use the same shape) into GRC-20 ops. IDs are
+//! derived deterministically so that every space importing the same item
+//! gets the same entity:
+//!
+//! ```text
+//! entity_id(Q64)       = derived_uuid("wikidata:Q64")
+//! relation ID          = derived_uuid(from || to || relation_type)  (unique mode)
+//! ```
+//!
+//! A [`PropertyMap`] decides what is imported:
+//!
+//! - labels → Name, descriptions → Description (one value per language)
+//! - sitelinks → URL (Wikipedia article URLs, tagged with the site language)
+//! - claims on mapped value properties → one value, from the best-ranked
+//!   statement (preferred if any, else the first normal one)
+//! - claims on mapped relation properties → one relation per best-ranked
+//!   statement; `P31` (instance of) maps to `Types` by default
+//!
+//! Unmapped properties, deprecated statements, and `novalue`/`somevalue`
+//! snaks are skipped.
+//!
+//! ```ignore
+//! use grc_20::import::wikidata::{entity_to_ops, PropertyMap, WikidataJson};
+//!
+//! let map = PropertyMap::new()
+//!     .value("P1082", population, DataType::Int64)
+//!     .value("P625", location, DataType::Point)
+//!     .relation("P17", country);
+//! let entity = WikidataJson::from_json(&json)?;
+//! let ops = entity_to_ops(&entity, &map)?;
+//! ```
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::collections::FxHashMap;
+use crate::error::ImportError;
+use crate::genesis::{language_id, properties, relation_types};
+use crate::import::parse_decimal;
+use crate::lang::from_bcp47;
+use crate::model::id::unique_relation_id;
+use crate::model::{derived_uuid, CreateEntity, CreateRelation, DataType, Id, Op, PropertyValue, Value};
+
+/// Returns the GRC-20 entity ID for a Wikidata ID (e.g. `"Q64"`).
+pub fn entity_id(wikidata_id: &str) -> Id {
+    let input = format!("wikidata:{}", wikidata_id);
+    derived_uuid(input.as_bytes())
+}
+
+// =============================================================================
+// JSON MODEL
+// =============================================================================
+
+/// One entity in Wikidata JSON format.
+///
+/// Only the parts used by the importer are modeled; other fields are ignored.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WikidataJson {
+    pub id: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, LanguageValue>,
+    #[serde(default)]
+    pub descriptions: BTreeMap<String, LanguageValue>,
+    #[serde(default)]
+    pub claims: BTreeMap<String, Vec<Statement>>,
+    #[serde(default)]
+    pub sitelinks: BTreeMap<String, Sitelink>,
+}
+
+impl WikidataJson {
+    /// Parses a single entity object.
+    pub fn from_json(json: &str) -> Result<Self, ImportError> {
+        serde_json::from_str(json).map_err(|e| ImportError::Json(e.to_string()))
+    }
+
+    /// Parses one line of a Wikidata JSON dump.
+    ///
+    /// Dumps are one large array with one entity per line, so lines carry a
+    /// trailing comma and the first/last lines are `[` and `]`. Returns
+    /// `Ok(None)` for those bracket lines.
+    pub fn from_dump_line(line: &str) -> Result<Option<Self>, ImportError> {
+        let line = line.trim().trim_end_matches(',');
+        if line.is_empty() || line == "[" || line == "]" {
+            return Ok(None);
+        }
+        Self::from_json(line).map(Some)
+    }
+}
+
+/// A label or description in one language.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LanguageValue {
+    pub language: String,
+    pub value: String,
+}
+
+/// A sitelink (e.g. the English Wikipedia article).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Sitelink {
+    pub site: String,
+    pub title: String,
+}
+
+/// A claim statement.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Statement {
+    pub mainsnak: Snak,
+    #[serde(default)]
+    pub rank: Rank,
+}
+
+/// Statement rank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rank {
+    Preferred,
+    #[default]
+    Normal,
+    Deprecated,
+}
+
+/// The main snak of a statement.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Snak {
+    /// `value`, `novalue`, or `somevalue`.
+    pub snaktype: String,
+    pub property: String,
+    #[serde(default)]
+    pub datavalue: Option<DataValue>,
+}
+
+/// A typed snak value.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum DataValue {
+    #[serde(rename = "string")]
+    String(String),
+    #[serde(rename = "monolingualtext")]
+    MonolingualText { text: String, language: String },
+    #[serde(rename = "wikibase-entityid")]
+    EntityId {
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(default, rename = "numeric-id")]
+        numeric_id: Option<u64>,
+    },
+    #[serde(rename = "quantity")]
+    Quantity { amount: String, unit: String },
+    #[serde(rename = "time")]
+    Time { time: String, precision: u8 },
+    #[serde(rename = "globecoordinate")]
+    GlobeCoordinate {
+        latitude: f64,
+        longitude: f64,
+        #[serde(default)]
+        altitude: Option<f64>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+// =============================================================================
+// PROPERTY MAP
+// =============================================================================
+
+/// Decides which parts of a Wikidata entity are imported and how.
+#[derive(Debug, Clone)]
+pub struct PropertyMap {
+    values: FxHashMap<String, (Id, DataType)>,
+    relations: FxHashMap<String, Id>,
+    labels: Option<Id>,
+    descriptions: Option<Id>,
+    sitelinks: Option<Id>,
+    languages: Option<Vec<String>>,
+}
+
+impl Default for PropertyMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PropertyMap {
+    /// Creates a map that imports labels, descriptions, Wikipedia sitelinks,
+    /// and `P31` (instance of) as `Types` relations.
+    pub fn new() -> Self {
+        let mut relations = FxHashMap::default();
+        relations.insert("P31".to_string(), relation_types::types());
+        Self {
+            values: FxHashMap::default(),
+            relations,
+            labels: Some(properties::name()),
+            descriptions: Some(properties::description()),
+            sitelinks: Some(properties::url()),
+            languages: None,
+        }
+    }
+
+    /// Imports claims of `wikidata_property` as values of `property`.
+    ///
+    /// Supported combinations: string → TEXT, monolingual text → TEXT,
+    /// quantity → INT64/FLOAT64/DECIMAL, time → DATE/DATETIME,
+    /// globe coordinate → POINT.
+    pub fn value(mut self, wikidata_property: impl Into<String>, property: Id, data_type: DataType) -> Self {
+        self.values.insert(wikidata_property.into(), (property, data_type));
+        self
+    }
+
+    /// Imports item-valued claims of `wikidata_property` as relations.
+    pub fn relation(mut self, wikidata_property: impl Into<String>, relation_type: Id) -> Self {
+        self.relations.insert(wikidata_property.into(), relation_type);
+        self
+    }
+
+    /// Sets the property for labels (`None` skips labels).
+    pub fn labels(mut self, property: Option<Id>) -> Self {
+        self.labels = property;
+        self
+    }
+
+    /// Sets the property for descriptions (`None` skips descriptions).
+    pub fn descriptions(mut self, property: Option<Id>) -> Self {
+        self.descriptions = property;
+        self
+    }
+
+    /// Sets the property for Wikipedia URLs (`None` skips sitelinks).
+    pub fn sitelinks(mut self, property: Option<Id>) -> Self {
+        self.sitelinks = property;
+        self
+    }
+
+    /// Restricts labels, descriptions, and sitelinks to these language codes.
+    pub fn languages<I, S>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.languages = Some(languages.into_iter().map(Into::into).collect());
+        self
+    }
+
+    fn wants_language(&self, code: &str) -> bool {
+        self.languages.as_ref().is_none_or(|l| l.iter().any(|c| c == code))
+    }
+}
+
+// =============================================================================
+// CONVERSION
+// =============================================================================
+
+/// Converts one Wikidata entity into a CreateEntity op followed by its
+/// relation ops.
+pub fn entity_to_ops(entity: &WikidataJson, map: &PropertyMap) -> Result<Vec<Op<'static>>, ImportError> {
+    let id = entity_id(&entity.id);
+    let mut values = Vec::new();
+    let mut relations = Vec::new();
+
+    for (property, texts) in [(map.labels, &entity.labels), (map.descriptions, &entity.descriptions)] {
+        let Some(property) = property else { continue };
+        for text in texts.values().filter(|t| map.wants_language(&t.language)) {
+            values.push(text_value(property, &text.value, &text.language));
+        }
+    }
+
+    if let Some(property) = map.sitelinks {
+        for link in entity.sitelinks.values() {
+            let Some(language) = wikipedia_language(&link.site) else { continue };
+            if !map.wants_language(language) {
+                continue;
+            }
+            let url = format!("https://{}.wikipedia.org/wiki/{}", language, link.title.replace(' ', "_"));
+            values.push(text_value(property, &url, language));
+        }
+    }
+
+    for (wikidata_property, statements) in &entity.claims {
+        let mapped_value = map.values.get(wikidata_property);
+        let mapped_relation = map.relations.get(wikidata_property);
+        if mapped_value.is_none() && mapped_relation.is_none() {
+            continue;
+        }
+        let invalid = |reason: String| ImportError::InvalidClaim {
+            property: wikidata_property.clone(),
+            reason,
+        };
+
+        for snak in best_ranked(statements) {
+            let Some(datavalue) = &snak.datavalue else { continue };
+            if snak.snaktype != "value" {
+                continue;
+            }
+            if let Some(&(property, data_type)) = mapped_value {
+                let value = convert_value(datavalue, data_type).map_err(invalid)?;
+                values.push(PropertyValue { property, value });
+                // GRC-20 holds one value per property (and language)
+                break;
+            }
+            if let Some(&relation_type) = mapped_relation {
+                let DataValue::EntityId { id: target, numeric_id } = datavalue else {
+                    return Err(invalid("expected an item value for a relation".to_string()));
+                };
+                let target = match (target, numeric_id) {
+                    (Some(target), _) => target.clone(),
+                    (None, Some(n)) => format!("Q{}", n),
+                    (None, None) => return Err(invalid("entity value without ID".to_string())),
+                };
+                let to = entity_id(&target);
+                relations.push(Op::CreateRelation(CreateRelation {
+                    id: unique_relation_id(&id, &to, &relation_type),
+                    relation_type,
+                    from: id,
+                    from_is_value_ref: false,
+                    to,
+                    to_is_value_ref: false,
+                    entity: None,
+                    position: None,
+                    from_space: None,
+                    from_version: None,
+                    to_space: None,
+                    to_version: None,
+                    context: None,
+                }));
+            }
+        }
+    }
+
+    let mut ops = Vec::with_capacity(1 + relations.len());
+    ops.push(Op::CreateEntity(CreateEntity { id, values, context: None }));
+    ops.extend(relations);
+    Ok(ops)
+}
+
+fn text_value(property: Id, text: &str, language: &str) -> PropertyValue<'static> {
+    PropertyValue {
+        property,
+        value: Value::Text {
+            value: Cow::Owned(text.to_string()),
+            language: Some(language_entity(language)),
+        },
+    }
+}
+
+/// Returns the language ID for a Wikidata language code, which is a BCP 47
+/// tag apart from a few legacy codes; those fall back to [`language_id`].
+fn language_entity(code: &str) -> Id {
+    from_bcp47(code).unwrap_or_else(|| language_id(code))
+}
+
+/// Returns the language of a Wikipedia site key (`"enwiki"` → `"en"`).
+fn wikipedia_language(site: &str) -> Option<&str> {
+    let language = site.strip_suffix("wiki")?;
+    // Non-Wikipedia projects that share the suffix
+    const PROJECTS: &[&str] = &["commons", "species", "meta", "wikidata", "mediawiki", "sources", "incubator"];
+    if language.is_empty() || PROJECTS.contains(&language) {
+        return None;
+    }
+    Some(language)
+}
+
+/// Returns the snaks of the truthy statements: preferred ones if any exist,
+/// otherwise the normal ones.
+fn best_ranked(statements: &[Statement]) -> impl Iterator<Item = &Snak> {
+    let rank = if statements.iter().any(|s| s.rank == Rank::Preferred) {
+        Rank::Preferred
+    } else {
+        Rank::Normal
+    };
+    statements.iter().filter(move |s| s.rank == rank).map(|s| &s.mainsnak)
+}
+
+fn convert_value(datavalue: &DataValue, data_type: DataType) -> Result<Value<'static>, String> {
+    let value = match (datavalue, data_type) {
+        (DataValue::String(s), DataType::Text) => Value::Text { value: Cow::Owned(s.clone()), language: None },
+        (DataValue::MonolingualText { text, language }, DataType::Text) => Value::Text {
+            value: Cow::Owned(text.clone()),
+            language: Some(language_entity(language)),
+        },
+        (DataValue::Quantity { amount, unit }, DataType::Int64 | DataType::Float64 | DataType::Decimal) => {
+            let amount = amount.strip_prefix('+').unwrap_or(amount);
+            // Units are entity URLs, "1" means dimensionless
+            let unit = unit.rsplit_once('/').map(|(_, qid)| entity_id(qid));
+            match data_type {
+                DataType::Int64 => Value::Int64 {
+                    value: amount.parse().map_err(|_| format!("{:?} is not an integer", amount))?,
+                    unit,
+                },
+                DataType::Float64 => Value::Float64 {
+                    value: amount.parse().map_err(|_| format!("{:?} is not a number", amount))?,
+                    unit,
+                },
+                _ => {
+                    let (exponent, mantissa) =
+                        parse_decimal(amount).ok_or_else(|| format!("{:?} is not a decimal", amount))?;
+                    Value::Decimal { exponent, mantissa, unit }
+                }
+            }
+        }
+        (DataValue::Time { time, precision }, DataType::Date | DataType::Datetime) => {
+            // "+2001-12-31T00:00:00Z"; unknown month/day are written as 00
+            let time = time.strip_prefix('+').unwrap_or(time);
+            let (date, rest) = time.split_once('T').ok_or_else(|| format!("invalid time {:?}", time))?;
+            let (sign, ymd) = match date.strip_prefix('-') {
+                Some(ymd) => ("-", ymd),
+                None => ("", date),
+            };
+            let [year, month, day] = ymd.split('-').collect::<Vec<_>>()[..] else {
+                return Err(format!("invalid time {:?}", time));
+            };
+            let fix = |part: &str| if part == "00" { "01".to_string() } else { part.to_string() };
+            let date = format!("{}{}-{}-{}", sign, year, fix(month), fix(day));
+            // Precision 11 is day; finer precisions carry a time of day
+            match data_type {
+                DataType::Date => Value::Date(Cow::Owned(date)),
+                _ if *precision <= 11 => Value::Datetime(Cow::Owned(format!("{}T00:00:00Z", date))),
+                _ => Value::Datetime(Cow::Owned(format!("{}T{}", date, rest))),
+            }
+        }
+        (DataValue::GlobeCoordinate { latitude, longitude, altitude }, DataType::Point) => Value::Point {
+            lat: *latitude,
+            lon: *longitude,
+            alt: *altitude,
+        },
+        (datavalue, data_type) => {
+            return Err(format!("cannot convert {:?} to {:?}", datavalue, data_type));
+        }
+    };
+    if let Some(reason) = value.validate() {
+        return Err(reason.to_string());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::languages;
+    use crate::model::DecimalMantissa;
+
+    const BERLIN: &str = r#"{
+        "id": "Q64",
+        "type": "item",
+        "labels": {
+            "en": {"language": "en", "value": "Berlin"},
+            "de": {"language": "de", "value": "Berlin"}
+        },
+        "descriptions": {
+            "en": {"language": "en", "value": "capital and largest city of Germany"}
+        },
+        "sitelinks": {
+            "enwiki": {"site": "enwiki", "title": "Berlin", "badges": []},
+            "commonswiki": {"site": "commonswiki", "title": "Category:Berlin", "badges": []}
+        },
+        "claims": {
+            "P31": [
+                {"mainsnak": {"snaktype": "value", "property": "P31", "datavalue": {"value": {"entity-type": "item", "numeric-id": 515, "id": "Q515"}, "type": "wikibase-entityid"}}, "rank": "normal"},
+                {"mainsnak": {"snaktype": "value", "property": "P31", "datavalue": {"value": {"entity-type": "item", "numeric-id": 5119, "id": "Q5119"}, "type": "wikibase-entityid"}}, "rank": "normal"}
+            ],
+            "P1082": [
+                {"mainsnak": {"snaktype": "value", "property": "P1082", "datavalue": {"value": {"amount": "+3400000", "unit": "1"}, "type": "quantity"}}, "rank": "normal"},
+                {"mainsnak": {"snaktype": "value", "property": "P1082", "datavalue": {"value": {"amount": "+3755251", "unit": "1"}, "type": "quantity"}}, "rank": "preferred"}
+            ],
+            "P2046": [
+                {"mainsnak": {"snaktype": "value", "property": "P2046", "datavalue": {"value": {"amount": "+891.12", "unit": "http://www.wikidata.org/entity/Q712226"}, "type": "quantity"}}, "rank": "normal"}
+            ],
+            "P625": [
+                {"mainsnak": {"snaktype": "value", "property": "P625", "datavalue": {"value": {"latitude": 52.516666666667, "longitude": 13.383333333333, "altitude": null, "precision": 0.00027777777777778, "globe": "http://www.wikidata.org/entity/Q2"}, "type": "globecoordinate"}}, "rank": "normal"}
+            ],
+            "P571": [
+                {"mainsnak": {"snaktype": "value", "property": "P571", "datavalue": {"value": {"time": "+1237-00-00T00:00:00Z", "timezone": 0, "before": 0, "after": 0, "precision": 9, "calendarmodel": "http://www.wikidata.org/entity/Q1985727"}, "type": "time"}}, "rank": "normal"}
+            ],
+            "P1448": [
+                {"mainsnak": {"snaktype": "value", "property": "P1448", "datavalue": {"value": {"text": "Berlin", "language": "de"}, "type": "monolingualtext"}}, "rank": "normal"}
+            ],
+            "P6": [
+                {"mainsnak": {"snaktype": "somevalue", "property": "P6"}, "rank": "normal"}
+            ],
+            "P17": [
+                {"mainsnak": {"snaktype": "value", "property": "P17", "datavalue": {"value": {"entity-type": "item", "numeric-id": 183}, "type": "wikibase-entityid"}}, "rank": "normal"},
+                {"mainsnak": {"snaktype": "value", "property": "P17", "datavalue": {"value": {"entity-type": "item", "numeric-id": 1}, "type": "wikibase-entityid"}}, "rank": "deprecated"}
+            ]
+        }
+    }"#;
+
+    fn berlin_map() -> PropertyMap {
+        PropertyMap::new()
+            .value("P1082", [10u8; 16], DataType::Int64)
+            .value("P2046", [11u8; 16], DataType::Decimal)
+            .value("P625", [12u8; 16], DataType::Point)
+            .value("P571", [13u8; 16], DataType::Date)
+            .value("P1448", [14u8; 16], DataType::Text)
+            .relation("P6", [20u8; 16])
+            .relation("P17", [21u8; 16])
+    }
+
+    fn value_of<'a>(ce: &'a CreateEntity<'static>, property: Id) -> &'a Value<'static> {
+        &ce.values.iter().find(|pv| pv.property == property).expect("value").value
+    }
+
+    #[test]
+    fn test_entity_to_ops() {
+        let entity = WikidataJson::from_json(BERLIN).unwrap();
+        let ops = entity_to_ops(&entity, &berlin_map()).unwrap();
+
+        let Op::CreateEntity(ce) = &ops[0] else { panic!("expected CreateEntity") };
+        assert_eq!(ce.id, entity_id("Q64"));
+        assert_eq!(ce.id, derived_uuid(b"wikidata:Q64"));
+
+        // 2 labels + 1 description + 1 sitelink + 5 claims
+        assert_eq!(ce.values.len(), 9);
+        assert_eq!(
+            value_of(ce, properties::url()),
+            &Value::Text {
+                value: Cow::Borrowed("https://en.wikipedia.org/wiki/Berlin"),
+                language: Some(languages::english())
+            }
+        );
+        // Preferred rank wins
+        assert_eq!(value_of(ce, [10u8; 16]), &Value::Int64 { value: 3_755_251, unit: None });
+        assert_eq!(
+            value_of(ce, [11u8; 16]),
+            &Value::Decimal {
+                exponent: -2,
+                mantissa: DecimalMantissa::I64(89112),
+                unit: Some(entity_id("Q712226"))
+            }
+        );
+        assert!(matches!(value_of(ce, [12u8; 16]), Value::Point { alt: None, .. }));
+        assert_eq!(value_of(ce, [13u8; 16]), &Value::Date(Cow::Borrowed("1237-01-01")));
+        assert_eq!(
+            value_of(ce, [14u8; 16]),
+            &Value::Text { value: Cow::Borrowed("Berlin"), language: Some(languages::german()) }
+        );
+
+        // 2 Types relations + 1 country (deprecated and somevalue skipped)
+        let relations: Vec<_> = ops[1..]
+            .iter()
+            .map(|op| match op {
+                Op::CreateRelation(cr) => cr,
+                _ => panic!("expected CreateRelation"),
+            })
+            .collect();
+        assert_eq!(relations.len(), 3);
+        let country = relations.iter().find(|r| r.relation_type == [21u8; 16]).unwrap();
+        assert_eq!(country.to, entity_id("Q183"));
+        assert_eq!(country.id, unique_relation_id(&ce.id, &country.to, &[21u8; 16]));
+        assert_eq!(
+            relations.iter().filter(|r| r.relation_type == relation_types::types()).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_languages_filter() {
+        let entity = WikidataJson::from_json(BERLIN).unwrap();
+        let map = PropertyMap::new().languages(["de"]).sitelinks(None).relation("P31", [0u8; 16]);
+        let ops = entity_to_ops(&entity, &map).unwrap();
+        let Op::CreateEntity(ce) = &ops[0] else { panic!("expected CreateEntity") };
+        assert_eq!(ce.values.len(), 1);
+        assert_eq!(ops.len(), 3);
+    }
+
+    #[test]
+    fn test_language_codes_normalized() {
+        let json = r#"{"id": "Q1", "labels": {
+            "zh-cn": {"language": "zh-cn", "value": "柏林"},
+            "be-tarask": {"language": "be-tarask", "value": "Бэрлін"}
+        }}"#;
+        let entity = WikidataJson::from_json(json).unwrap();
+        let ops = entity_to_ops(&entity, &PropertyMap::new().sitelinks(None)).unwrap();
+        let Op::CreateEntity(ce) = &ops[0] else { panic!("expected CreateEntity") };
+        let tags: Vec<_> = ce
+            .values
+            .iter()
+            .map(|pv| match pv.value {
+                Value::Text { language, .. } => language,
+                _ => None,
+            })
+            .collect();
+        assert!(tags.contains(&Some(languages::chinese_simplified())));
+        assert!(tags.contains(&from_bcp47("be-tarask")));
+    }
+
+    #[test]
+    fn test_incompatible_mapping() {
+        let entity = WikidataJson::from_json(BERLIN).unwrap();
+        let map = PropertyMap::new().value("P625", [12u8; 16], DataType::Int64);
+        assert!(matches!(
+            entity_to_ops(&entity, &map),
+            Err(ImportError::InvalidClaim { ref property, .. }) if property == "P625"
+        ));
+    }
+
+    #[test]
+    fn test_convert_time() {
+        let time = |t: &str, precision| DataValue::Time { time: t.to_string(), precision };
+        assert_eq!(
+            convert_value(&time("+2001-12-31T10:20:30Z", 14), DataType::Datetime),
+            Ok(Value::Datetime(Cow::Borrowed("2001-12-31T10:20:30Z")))
+        );
+        assert_eq!(
+            convert_value(&time("+2001-12-00T00:00:00Z", 10), DataType::Datetime),
+            Ok(Value::Datetime(Cow::Borrowed("2001-12-01T00:00:00Z")))
+        );
+        assert_eq!(
+            convert_value(&time("+2001-12-31T10:20:30Z", 14), DataType::Date),
+            Ok(Value::Date(Cow::Borrowed("2001-12-31")))
+        );
+    }
+
+    #[test]
+    fn test_from_dump_line() {
+        assert_eq!(WikidataJson::from_dump_line("[").unwrap(), None);
+        assert_eq!(WikidataJson::from_dump_line("]").unwrap(), None);
+        let line = r#"{"id": "Q1", "labels": {}},"#;
+        assert_eq!(WikidataJson::from_dump_line(line).unwrap().unwrap().id, "Q1");
+        assert!(matches!(WikidataJson::from_dump_line("{oops"), Err(ImportError::Json(_))));
+    }
+}
@@ -0,0 +1,492 @@
+//! Arrow export of decoded edits.
+//!
+//! Flattens an [`Edit`] into three Arrow tables so that edits can be queried
+//! with DuckDB, Polars, DataFusion, etc. without a custom ETL step:
+//!
+//! - **entities**: one row per entity op (create, update, delete, restore)
+//! - **values**: one row per property value set by CreateEntity/UpdateEntity
+//! - **relations**: one row per relation op
+//!
+//! IDs are written as 32-character lowercase hex strings (see [`format_id`])
+//! so they can be joined and filtered directly in SQL. Every table starts
+//! with `edit_id` and `op_index` columns, which makes it easy to append the
+//! batches of many edits into one dataset.
+//!
+//! Value columns are typed where Arrow has a natural counterpart:
+//! `value_bool`, `value_int64`, `value_float64`, `value_bytes` (BYTES and
+//! EMBEDDING data), and `value_text` for everything else (TEXT, DECIMAL as a
+//! decimal string, in scientific notation for very large or small
+//! exponents, temporal types as RFC 3339, SCHEDULE, and POINT/RECT as WKT).
+//!
+//! With the `parquet` feature, [`write_parquet`] and
+//! [`EditRecordBatches::write_parquet_dir`] write the tables as Parquet files.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder, UInt32Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType as ArrowType, Field, Schema, SchemaRef};
+
+use crate::error::InteropError;
+use crate::interop::{data_type_name, format_decimal, format_wkt};
+use crate::model::{format_id, Edit, Id, Op, PropertyValue, Value};
+
+/// The three tables produced from one edit.
+#[derive(Debug, Clone)]
+pub struct EditRecordBatches {
+    /// Entity ops (see [`entities_schema`]).
+    pub entities: RecordBatch,
+    /// Property values (see [`values_schema`]).
+    pub values: RecordBatch,
+    /// Relation ops (see [`relations_schema`]).
+    pub relations: RecordBatch,
+}
+
+/// Returns the schema of the entities table.
+pub fn entities_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("edit_id", ArrowType::Utf8, false),
+        Field::new("op_index", ArrowType::UInt32, false),
+        Field::new("op_type", ArrowType::Utf8, false),
+        Field::new("entity_id", ArrowType::Utf8, false),
+    ]))
+}
+
+/// Returns the schema of the values table.
+pub fn values_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("edit_id", ArrowType::Utf8, false),
+        Field::new("op_index", ArrowType::UInt32, false),
+        Field::new("entity_id", ArrowType::Utf8, false),
+        Field::new("property_id", ArrowType::Utf8, false),
+        Field::new("data_type", ArrowType::Utf8, false),
+        Field::new("language", ArrowType::Utf8, true),
+        Field::new("unit", ArrowType::Utf8, true),
+        Field::new("value_bool", ArrowType::Boolean, true),
+        Field::new("value_int64", ArrowType::Int64, true),
+        Field::new("value_float64", ArrowType::Float64, true),
+        Field::new("value_text", ArrowType::Utf8, true),
+        Field::new("value_bytes", ArrowType::Binary, true),
+    ]))
+}
+
+/// Returns the schema of the relations table.
+pub fn relations_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("edit_id", ArrowType::Utf8, false),
+        Field::new("op_index", ArrowType::UInt32, false),
+        Field::new("op_type", ArrowType::Utf8, false),
+        Field::new("relation_id", ArrowType::Utf8, false),
+        Field::new("relation_type", ArrowType::Utf8, true),
+        Field::new("from_id", ArrowType::Utf8, true),
+        Field::new("from_is_value_ref", ArrowType::Boolean, true),
+        Field::new("from_space", ArrowType::Utf8, true),
+        Field::new("from_version", ArrowType::Utf8, true),
+        Field::new("to_id", ArrowType::Utf8, true),
+        Field::new("to_is_value_ref", ArrowType::Boolean, true),
+        Field::new("to_space", ArrowType::Utf8, true),
+        Field::new("to_version", ArrowType::Utf8, true),
+        Field::new("entity_id", ArrowType::Utf8, true),
+        Field::new("position", ArrowType::Utf8, true),
+    ]))
+}
+
+/// Converts an edit into entities, values, and relations record batches.
+///
+/// CreateValueRef ops are not exported.
+pub fn edit_to_record_batches(edit: &Edit) -> Result<EditRecordBatches, InteropError> {
+    let edit_id = format_id(&edit.id);
+    let mut entities = EntityColumns::default();
+    let mut values = ValueColumns::default();
+    let mut relations = RelationColumns::default();
+
+    for (index, op) in edit.ops.iter().enumerate() {
+        let op_index = index as u32;
+        match op {
+            Op::CreateEntity(ce) => {
+                entities.push(&edit_id, op_index, "create", &ce.id);
+                for pv in &ce.values {
+                    values.push(&edit_id, op_index, &ce.id, pv);
+                }
+            }
+            Op::UpdateEntity(ue) => {
+                entities.push(&edit_id, op_index, "update", &ue.id);
+                for pv in &ue.set_properties {
+                    values.push(&edit_id, op_index, &ue.id, pv);
+                }
+            }
+            Op::DeleteEntity(de) => entities.push(&edit_id, op_index, "delete", &de.id),
+            Op::RestoreEntity(re) => entities.push(&edit_id, op_index, "restore", &re.id),
+            Op::CreateRelation(cr) => {
+                let row = relations.push(&edit_id, op_index, "create", &cr.id);
+                row.relation_type.append_value(format_id(&cr.relation_type));
+                row.from_id.append_value(format_id(&cr.from));
+                row.from_is_value_ref.append_value(cr.from_is_value_ref);
+                row.from_space.append_option(cr.from_space.as_ref().map(format_id));
+                row.from_version.append_option(cr.from_version.as_ref().map(format_id));
+                row.to_id.append_value(format_id(&cr.to));
+                row.to_is_value_ref.append_value(cr.to_is_value_ref);
+                row.to_space.append_option(cr.to_space.as_ref().map(format_id));
+                row.to_version.append_option(cr.to_version.as_ref().map(format_id));
+                row.entity_id.append_value(format_id(&cr.entity_id()));
+                row.position.append_option(cr.position.as_deref());
+            }
+            Op::UpdateRelation(ur) => {
+                let row = relations.push(&edit_id, op_index, "update", &ur.id);
+                row.relation_type.append_null();
+                row.from_id.append_null();
+                row.from_is_value_ref.append_null();
+                row.from_space.append_option(ur.from_space.as_ref().map(format_id));
+                row.from_version.append_option(ur.from_version.as_ref().map(format_id));
+                row.to_id.append_null();
+                row.to_is_value_ref.append_null();
+                row.to_space.append_option(ur.to_space.as_ref().map(format_id));
+                row.to_version.append_option(ur.to_version.as_ref().map(format_id));
+                row.entity_id.append_null();
+                row.position.append_option(ur.position.as_deref());
+            }
+            Op::DeleteRelation(dr) => relations.push(&edit_id, op_index, "delete", &dr.id).append_nulls(),
+            Op::RestoreRelation(rr) => relations.push(&edit_id, op_index, "restore", &rr.id).append_nulls(),
+            Op::CreateValueRef(_) | Op::Unknown(_) => {}
+        }
+    }
+
+    Ok(EditRecordBatches {
+        entities: entities.finish()?,
+        values: values.finish()?,
+        relations: relations.finish()?,
+    })
+}
+
+fn finish_batch(schema: SchemaRef, columns: Vec<ArrayRef>) -> Result<RecordBatch, InteropError> {
+    RecordBatch::try_new(schema, columns).map_err(|e| InteropError::Arrow(e.to_string()))
+}
+
+#[derive(Default)]
+struct EntityColumns {
+    edit_id: StringBuilder,
+    op_index: UInt32Builder,
+    op_type: StringBuilder,
+    entity_id: StringBuilder,
+}
+
+impl EntityColumns {
+    fn push(&mut self, edit_id: &str, op_index: u32, op_type: &str, entity_id: &Id) {
+        self.edit_id.append_value(edit_id);
+        self.op_index.append_value(op_index);
+        self.op_type.append_value(op_type);
+        self.entity_id.append_value(format_id(entity_id));
+    }
+
+    fn finish(mut self) -> Result<RecordBatch, InteropError> {
+        finish_batch(entities_schema(), vec![
+            Arc::new(self.edit_id.finish()),
+            Arc::new(self.op_index.finish()),
+            Arc::new(self.op_type.finish()),
+            Arc::new(self.entity_id.finish()),
+        ])
+    }
+}
+
+#[derive(Default)]
+struct ValueColumns {
+    edit_id: StringBuilder,
+    op_index: UInt32Builder,
+    entity_id: StringBuilder,
+    property_id: StringBuilder,
+    data_type: StringBuilder,
+    language: StringBuilder,
+    unit: StringBuilder,
+    value_bool: BooleanBuilder,
+    value_int64: Int64Builder,
+    value_float64: Float64Builder,
+    value_text: StringBuilder,
+    value_bytes: BinaryBuilder,
+}
+
+impl ValueColumns {
+    fn push(&mut self, edit_id: &str, op_index: u32, entity_id: &Id, pv: &PropertyValue<'_>) {
+        self.edit_id.append_value(edit_id);
+        self.op_index.append_value(op_index);
+        self.entity_id.append_value(format_id(entity_id));
+        self.property_id.append_value(format_id(&pv.property));
+        self.data_type.append_value(data_type_name(pv.value.data_type()));
+
+        let (language, unit) = match &pv.value {
+            Value::Text { language, .. } => (*language, None),
+            Value::Int64 { unit, .. } | Value::Float64 { unit, .. } | Value::Decimal { unit, .. } => (None, *unit),
+            _ => (None, None),
+        };
+        self.language.append_option(language.as_ref().map(format_id));
+        self.unit.append_option(unit.as_ref().map(format_id));
+
+        let mut bool_value = None;
+        let mut int_value = None;
+        let mut float_value = None;
+        let mut text_value = None;
+        let mut bytes_value = None;
+        match &pv.value {
+            Value::Bool(b) => bool_value = Some(*b),
+            Value::Int64 { value, .. } => int_value = Some(*value),
+            Value::Float64 { value, .. } => float_value = Some(*value),
+            Value::Decimal { exponent, mantissa, .. } => text_value = Some(format_decimal(*exponent, mantissa)),
+            Value::Text { value, .. } => text_value = Some(value.to_string()),
+            Value::Bytes(b) => bytes_value = Some(b.as_ref()),
+            Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => {
+                text_value = Some(s.to_string())
+            }
+            Value::Point { .. } | Value::Rect { .. } => text_value = format_wkt(&pv.value),
+            Value::Embedding { data, .. } => bytes_value = Some(data.as_ref()),
+        }
+        self.value_bool.append_option(bool_value);
+        self.value_int64.append_option(int_value);
+        self.value_float64.append_option(float_value);
+        self.value_text.append_option(text_value);
+        self.value_bytes.append_option(bytes_value);
+    }
+
+    fn finish(mut self) -> Result<RecordBatch, InteropError> {
+        finish_batch(values_schema(), vec![
+            Arc::new(self.edit_id.finish()),
+            Arc::new(self.op_index.finish()),
+            Arc::new(self.entity_id.finish()),
+            Arc::new(self.property_id.finish()),
+            Arc::new(self.data_type.finish()),
+            Arc::new(self.language.finish()),
+            Arc::new(self.unit.finish()),
+            Arc::new(self.value_bool.finish()),
+            Arc::new(self.value_int64.finish()),
+            Arc::new(self.value_float64.finish()),
+            Arc::new(self.value_text.finish()),
+            Arc::new(self.value_bytes.finish()),
+        ])
+    }
+}
+
+#[derive(Default)]
+struct RelationColumns {
+    edit_id: StringBuilder,
+    op_index: UInt32Builder,
+    op_type: StringBuilder,
+    relation_id: StringBuilder,
+    relation_type: StringBuilder,
+    from_id: StringBuilder,
+    from_is_value_ref: BooleanBuilder,
+    from_space: StringBuilder,
+    from_version: StringBuilder,
+    to_id: StringBuilder,
+    to_is_value_ref: BooleanBuilder,
+    to_space: StringBuilder,
+    to_version: StringBuilder,
+    entity_id: StringBuilder,
+    position: StringBuilder,
+}
+
+impl RelationColumns {
+    /// Appends the key columns; the caller fills in the remaining columns.
+    fn push(&mut self, edit_id: &str, op_index: u32, op_type: &str, relation_id: &Id) -> &mut Self {
+        self.edit_id.append_value(edit_id);
+        self.op_index.append_value(op_index);
+        self.op_type.append_value(op_type);
+        self.relation_id.append_value(format_id(relation_id));
+        self
+    }
+
+    /// Appends nulls to all non-key columns.
+    fn append_nulls(&mut self) {
+        self.relation_type.append_null();
+        self.from_id.append_null();
+        self.from_is_value_ref.append_null();
+        self.from_space.append_null();
+        self.from_version.append_null();
+        self.to_id.append_null();
+        self.to_is_value_ref.append_null();
+        self.to_space.append_null();
+        self.to_version.append_null();
+        self.entity_id.append_null();
+        self.position.append_null();
+    }
+
+    fn finish(mut self) -> Result<RecordBatch, InteropError> {
+        finish_batch(relations_schema(), vec![
+            Arc::new(self.edit_id.finish()),
+            Arc::new(self.op_index.finish()),
+            Arc::new(self.op_type.finish()),
+            Arc::new(self.relation_id.finish()),
+            Arc::new(self.relation_type.finish()),
+            Arc::new(self.from_id.finish()),
+            Arc::new(self.from_is_value_ref.finish()),
+            Arc::new(self.from_space.finish()),
+            Arc::new(self.from_version.finish()),
+            Arc::new(self.to_id.finish()),
+            Arc::new(self.to_is_value_ref.finish()),
+            Arc::new(self.to_space.finish()),
+            Arc::new(self.to_version.finish()),
+            Arc::new(self.entity_id.finish()),
+            Arc::new(self.position.finish()),
+        ])
+    }
+}
+
+// =============================================================================
+// PARQUET
+// =============================================================================
+
+/// Writes a record batch as a Parquet file.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(batch: &RecordBatch, writer: W) -> Result<(), InteropError> {
+    use parquet::arrow::ArrowWriter;
+
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)
+        .map_err(|e| InteropError::Parquet(e.to_string()))?;
+    writer.write(batch).map_err(|e| InteropError::Parquet(e.to_string()))?;
+    writer.close().map_err(|e| InteropError::Parquet(e.to_string()))?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+impl EditRecordBatches {
+    /// Writes `entities.parquet`, `values.parquet`, and `relations.parquet`
+    /// into `dir` (which must exist).
+    pub fn write_parquet_dir(&self, dir: &std::path::Path) -> Result<(), InteropError> {
+        for (name, batch) in [
+            ("entities.parquet", &self.entities),
+            ("values.parquet", &self.values),
+            ("relations.parquet", &self.relations),
+        ] {
+            let file = std::fs::File::create(dir.join(name)).map_err(|e| InteropError::Io(e.to_string()))?;
+            write_parquet(batch, file)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, BooleanArray, Float64Array, Int64Array, StringArray, UInt32Array};
+
+    use crate::model::{DecimalMantissa, EditBuilder};
+
+    fn make_test_edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16])
+            .name("Arrow")
+            .create_entity([3u8; 16], |e| {
+                e.text([10u8; 16], "Berlin", Some([30u8; 16]))
+                    .int64([11u8; 16], 3_600_000, None)
+                    .float64([12u8; 16], 891.8, Some([31u8; 16]))
+                    .bool([13u8; 16], true)
+                    .point([14u8; 16], 13.4, 52.5, None)
+                    .decimal([15u8; 16], -2, DecimalMantissa::I64(12345), None)
+            })
+            .update_entity([3u8; 16], |u| u.set_int64([11u8; 16], 3_700_000, None))
+            .create_relation_simple([4u8; 16], [3u8; 16], [5u8; 16], [40u8; 16])
+            .delete_relation([4u8; 16])
+            .delete_entity([6u8; 16])
+            .build()
+    }
+
+    fn strings(batch: &RecordBatch, column: &str) -> Vec<Option<String>> {
+        let array = batch.column_by_name(column).unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        array.iter().map(|s| s.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn test_entities_table() {
+        let batches = edit_to_record_batches(&make_test_edit()).unwrap();
+        let entities = &batches.entities;
+        assert_eq!(entities.num_rows(), 3);
+        assert_eq!(
+            strings(entities, "op_type"),
+            vec![Some("create".into()), Some("update".into()), Some("delete".into())]
+        );
+        assert_eq!(strings(entities, "entity_id")[0], Some(format_id(&[3u8; 16])));
+        assert_eq!(strings(entities, "edit_id")[0], Some(format_id(&[1u8; 16])));
+
+        let op_index = entities.column_by_name("op_index").unwrap().as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!(op_index.values().to_vec(), vec![0, 1, 4]);
+    }
+
+    #[test]
+    fn test_values_table() {
+        let batches = edit_to_record_batches(&make_test_edit()).unwrap();
+        let values = &batches.values;
+        assert_eq!(values.num_rows(), 7);
+
+        assert_eq!(strings(values, "data_type")[0], Some("TEXT".into()));
+        assert_eq!(strings(values, "value_text")[0], Some("Berlin".into()));
+        assert_eq!(strings(values, "language")[0], Some(format_id(&[30u8; 16])));
+
+        let ints = values.column_by_name("value_int64").unwrap().as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(ints.value(1), 3_600_000);
+        assert!(ints.is_null(0));
+        assert_eq!(ints.value(6), 3_700_000);
+
+        let floats = values.column_by_name("value_float64").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(floats.value(2), 891.8);
+        assert_eq!(strings(values, "unit")[2], Some(format_id(&[31u8; 16])));
+
+        let bools = values.column_by_name("value_bool").unwrap().as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert!(bools.value(3));
+
+        assert_eq!(strings(values, "value_text")[4], Some("POINT (13.4 52.5)".into()));
+        assert_eq!(strings(values, "value_text")[5], Some("123.45".into()));
+    }
+
+    #[test]
+    fn test_relations_table() {
+        let batches = edit_to_record_batches(&make_test_edit()).unwrap();
+        let relations = &batches.relations;
+        assert_eq!(relations.num_rows(), 2);
+        assert_eq!(strings(relations, "op_type"), vec![Some("create".into()), Some("delete".into())]);
+        assert_eq!(strings(relations, "from_id")[0], Some(format_id(&[3u8; 16])));
+        assert_eq!(strings(relations, "to_id")[0], Some(format_id(&[5u8; 16])));
+        assert_eq!(strings(relations, "relation_type")[1], None);
+        assert!(strings(relations, "entity_id")[0].is_some());
+    }
+
+    #[test]
+    fn test_extreme_decimal_exponents() {
+        // Exponents come from the wire unbounded and must not be padded out
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| {
+                e.decimal([15u8; 16], i32::MAX, DecimalMantissa::I64(5), None)
+                    .decimal([16u8; 16], i32::MIN + 1, DecimalMantissa::I64(-5), None)
+            })
+            .build();
+        let batches = edit_to_record_batches(&edit).unwrap();
+        assert_eq!(
+            strings(&batches.values, "value_text"),
+            vec![Some("5E2147483647".into()), Some("-5E-2147483647".into())]
+        );
+    }
+
+    #[test]
+    fn test_empty_edit() {
+        let edit = EditBuilder::new([1u8; 16]).build();
+        let batches = edit_to_record_batches(&edit).unwrap();
+        assert_eq!(batches.entities.num_rows(), 0);
+        assert_eq!(batches.values.num_rows(), 0);
+        assert_eq!(batches.relations.num_rows(), 0);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_write_parquet_dir() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = std::env::temp_dir().join(format!("grc20-arrow-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let batches = edit_to_record_batches(&make_test_edit()).unwrap();
+        batches.write_parquet_dir(&dir).unwrap();
+
+        let file = std::fs::File::open(dir.join("values.parquet")).unwrap();
+        let reader = SerializedFileReader::new(file).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 7);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+//! Author IDs for `did:key` and `did:pkh` identities.
+//!
+//! Edits authored with wallet or key identities need the same author ID in
+//! every implementation. The derivation is:
+//!
+//! ```text
+//! author_id = derived_uuid("grc20:author:" || normalize(did))
+//! ```
+//!
+//! where `normalize` makes equivalent spellings of a DID identical:
+//!
+//! - `did:key:z<base58btc>`: the method-specific ID must be a base58btc
+//!   multibase string of a multicodec public key (varint codec prefix
+//!   followed by at least one key byte). It is kept as is, since base58
+//!   has a single spelling per key.
+//! - `did:pkh:<namespace>:<reference>:<address>` (a CAIP-10 account):
+//!   `eip155` addresses are hex and case-insensitive (EIP-55 only adds a
+//!   checksum through case), so they are lowercased. Other namespaces are
+//!   kept as is.
+//!
+//! The `did` scheme and method name are matched case-sensitively, as the
+//! DID syntax requires them to be lowercase.
+
+use crate::error::InteropError;
+use crate::model::id::{base58_decode, base58_encode};
+use crate::model::{derived_uuid, Id};
+
+/// Multicodec code of Ed25519 public keys.
+pub const MULTICODEC_ED25519_PUB: u64 = 0xed;
+
+/// Returns the normalized form of a `did:key` or `did:pkh` identifier.
+pub fn normalize_did(did: &str) -> Result<String, InteropError> {
+    let invalid = |reason| InteropError::InvalidDid { did: did.to_string(), reason };
+    if let Some(key) = did.strip_prefix("did:key:") {
+        decode_did_key(key).map_err(invalid)?;
+        Ok(did.to_string())
+    } else if let Some(account) = did.strip_prefix("did:pkh:") {
+        let mut parts = account.splitn(3, ':');
+        let (Some(namespace), Some(reference), Some(address)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid("expected did:pkh:<namespace>:<reference>:<address>"));
+        };
+        if namespace.is_empty() || reference.is_empty() || address.is_empty() || address.contains(':') {
+            return Err(invalid("expected did:pkh:<namespace>:<reference>:<address>"));
+        }
+        if namespace == "eip155" {
+            let hex = address.strip_prefix("0x").ok_or_else(|| invalid("eip155 address must start with 0x"))?;
+            if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid("eip155 address must be 20 hex bytes"));
+            }
+            return Ok(format!("did:pkh:eip155:{}:0x{}", reference, hex.to_ascii_lowercase()));
+        }
+        Ok(did.to_string())
+    } else {
+        Err(invalid("unsupported DID method (expected did:key or did:pkh)"))
+    }
+}
+
+/// Derives the author ID of a DID (see the module docs).
+pub fn author_id_from_did(did: &str) -> Result<Id, InteropError> {
+    let normalized = normalize_did(did)?;
+    let mut input = Vec::with_capacity(13 + normalized.len());
+    input.extend_from_slice(b"grc20:author:");
+    input.extend_from_slice(normalized.as_bytes());
+    Ok(derived_uuid(&input))
+}
+
+/// Returns the `did:key` of an Ed25519 public key.
+pub fn did_key_from_ed25519(public_key: &[u8; 32]) -> String {
+    let mut bytes = Vec::with_capacity(34);
+    bytes.extend_from_slice(&[0xed, 0x01]); // varint(0xed)
+    bytes.extend_from_slice(public_key);
+    format!("did:key:z{}", base58_encode(&bytes))
+}
+
+/// Returns the Ed25519 public key of a `did:key`, e.g. to register it for
+/// signature checks.
+pub fn ed25519_from_did_key(did: &str) -> Result<[u8; 32], InteropError> {
+    let invalid = |reason| InteropError::InvalidDid { did: did.to_string(), reason };
+    let key = did.strip_prefix("did:key:").ok_or_else(|| invalid("not a did:key"))?;
+    let (codec, public_key) = decode_did_key(key).map_err(invalid)?;
+    if codec != MULTICODEC_ED25519_PUB {
+        return Err(invalid("not an Ed25519 key"));
+    }
+    public_key.try_into().map_err(|_| invalid("Ed25519 key must be 32 bytes"))
+}
+
+/// Decodes the method-specific ID of a `did:key` into (multicodec, key bytes).
+fn decode_did_key(key: &str) -> Result<(u64, Vec<u8>), &'static str> {
+    let encoded = key.strip_prefix('z').ok_or("did:key must use base58btc multibase ('z')")?;
+    let bytes = base58_decode(encoded).ok_or("invalid base58btc")?;
+
+    // Unsigned varint multicodec prefix
+    let mut codec = 0u64;
+    for (i, &b) in bytes.iter().enumerate().take(9) {
+        codec |= u64::from(b & 0x7F) << (7 * i);
+        if b & 0x80 == 0 {
+            let public_key = bytes[i + 1..].to_vec();
+            if public_key.is_empty() {
+                return Err("did:key has no key bytes");
+            }
+            return Ok((codec, public_key));
+        }
+    }
+    Err("invalid multicodec prefix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from the did:key spec (Ed25519)
+    const DID_KEY: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+    #[test]
+    fn test_did_key() {
+        let public_key = ed25519_from_did_key(DID_KEY).unwrap();
+        assert_eq!(did_key_from_ed25519(&public_key), DID_KEY);
+        assert_eq!(normalize_did(DID_KEY).unwrap(), DID_KEY);
+
+        assert!(normalize_did("did:key:6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK").is_err());
+        assert!(normalize_did("did:key:z0").is_err());
+        assert!(normalize_did("did:key:z").is_err());
+        // secp256k1 keys are valid DIDs but not Ed25519 keys
+        let secp = format!("did:key:z{}", base58_encode(&[[0xe7, 0x01].as_slice(), &[2u8; 33]].concat()));
+        assert!(normalize_did(&secp).is_ok());
+        assert!(matches!(ed25519_from_did_key(&secp), Err(InteropError::InvalidDid { .. })));
+    }
+
+    #[test]
+    fn test_did_pkh_normalization() {
+        let checksummed = "did:pkh:eip155:1:0xb9c5714089478a327F09197987f16f9E5d936E8a";
+        let lower = "did:pkh:eip155:1:0xb9c5714089478a327f09197987f16f9e5d936e8a";
+        assert_eq!(normalize_did(checksummed).unwrap(), lower);
+        assert_eq!(author_id_from_did(checksummed).unwrap(), author_id_from_did(lower).unwrap());
+
+        // Other chains keep their address spelling
+        let solana = "did:pkh:solana:4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZ:CKg5d12Jhpej1JqtmxLJgaFqqeYjxgPqToJ4LBdvG9Ev";
+        assert_eq!(normalize_did(solana).unwrap(), solana);
+        assert_ne!(author_id_from_did(solana).unwrap(), author_id_from_did(lower).unwrap());
+
+        for bad in ["did:pkh:eip155:1", "did:pkh:eip155:1:b9c5", "did:pkh:eip155:1:0xZZ", "did:web:example.com", "DID:key:z6Mk"] {
+            assert!(author_id_from_did(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_author_id_derivation() {
+        let id = author_id_from_did(DID_KEY).unwrap();
+        assert_eq!(id, derived_uuid(format!("grc20:author:{DID_KEY}").as_bytes()));
+        assert_eq!(id[6] >> 4, 8);
+    }
+}
@@ -0,0 +1,317 @@
+//! IPFS storage through the Kubo HTTP API (requires the `ipfs` feature).
+//!
+//! Edits are stored in their canonical, uncompressed encoding as CIDv1 with
+//! raw leaves and SHA-256, so the CID of an edit up to [`MAX_RAW_BLOCK`]
+//! bytes is a `raw` CID whose digest is the SHA-256 of the canonical
+//! encoding, the same hash signatures cover. [`Cid::for_edit`] computes it
+//! without a node. Larger edits are split by Kubo and get a `dag-pb` root
+//! CID instead.
+//!
+//! ```no_run
+//! use grc_20::interop::ipfs::{get_edit, put_edit, IpfsClient};
+//! # let edit = grc_20::EditBuilder::new([1u8; 16]).build();
+//!
+//! let client = IpfsClient::local();
+//! let cid = put_edit(&client, &edit).unwrap();
+//! assert_eq!(get_edit(&client, &cid).unwrap(), edit);
+//! println!("{cid}"); // bafkrei...
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::edit::{decode_edit, edit_to_owned, encode_edit_with_options, EncodeOptions};
+use crate::error::{EncodeError, InteropError, NetError};
+use crate::model::Edit;
+use crate::net::{read_body, with_retries, NetOptions};
+
+/// Multicodec code of raw binary blocks.
+pub const CODEC_RAW: u64 = 0x55;
+
+/// Multicodec code of UnixFS (`dag-pb`) nodes.
+pub const CODEC_DAG_PB: u64 = 0x70;
+
+/// Multihash code of SHA-256.
+const MULTIHASH_SHA2_256: u64 = 0x12;
+
+/// Largest edit stored as a single raw block (Kubo chunk size).
+pub const MAX_RAW_BLOCK: usize = 1024 * 1024;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A CIDv1 with a SHA-256 multihash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cid {
+    /// Content multicodec, e.g. [`CODEC_RAW`].
+    pub codec: u64,
+    pub digest: [u8; 32],
+}
+
+impl Cid {
+    /// Returns the `raw` CID of `bytes`.
+    pub fn raw(bytes: &[u8]) -> Self {
+        Cid { codec: CODEC_RAW, digest: Sha256::digest(bytes).into() }
+    }
+
+    /// Returns the CID [`put_edit`] stores `edit` under, if it fits in a
+    /// single raw block.
+    pub fn for_edit(edit: &Edit<'_>) -> Result<Option<Self>, EncodeError> {
+        let bytes = encode_edit_with_options(edit, EncodeOptions::canonical())?;
+        Ok((bytes.len() <= MAX_RAW_BLOCK).then(|| Cid::raw(&bytes)))
+    }
+
+    /// Returns the binary CID.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(38);
+        for v in [1, self.codec, MULTIHASH_SHA2_256, 32] {
+            write_uvarint(&mut bytes, v);
+        }
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+
+    /// Parses a binary CID.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        let mut rest = bytes;
+        if read_uvarint(&mut rest)? != 1 {
+            return Err("only CIDv1 is supported");
+        }
+        let codec = read_uvarint(&mut rest)?;
+        if read_uvarint(&mut rest)? != MULTIHASH_SHA2_256 || read_uvarint(&mut rest)? != 32 {
+            return Err("only sha2-256 multihashes are supported");
+        }
+        let digest = rest.try_into().map_err(|_| "digest must be 32 bytes")?;
+        Ok(Cid { codec, digest })
+    }
+}
+
+/// Formats as multibase base32 (`b...`), as Kubo does for CIDv1.
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("b")?;
+        f.write_str(&base32_encode(&self.to_bytes()))
+    }
+}
+
+impl FromStr for Cid {
+    type Err = InteropError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| InteropError::InvalidCid { cid: s.to_string(), reason };
+        let encoded = s.strip_prefix('b').ok_or_else(|| invalid("expected base32 multibase ('b')"))?;
+        let bytes = base32_decode(encoded).ok_or_else(|| invalid("invalid base32"))?;
+        Cid::from_bytes(&bytes).map_err(invalid)
+    }
+}
+
+fn write_uvarint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_uvarint(input: &mut &[u8]) -> Result<u64, &'static str> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let (&b, rest) = input.split_first().ok_or("truncated CID")?;
+        *input = rest;
+        value |= u64::from(b & 0x7F) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("varint too long")
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &b in bytes {
+        buffer = (buffer << 8) | u32::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in s.bytes() {
+        let v = BASE32_ALPHABET.iter().position(|&a| a == c)? as u32;
+        buffer = (buffer << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// A Kubo RPC endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpfsClient {
+    /// Base URL of the RPC API, e.g. `http://127.0.0.1:5001`.
+    pub api: String,
+    pub options: NetOptions,
+}
+
+impl IpfsClient {
+    /// Creates a client for the RPC API at `api`.
+    pub fn new(api: impl Into<String>) -> Self {
+        Self { api: api.into(), options: NetOptions::default() }
+    }
+
+    /// Creates a client for a local node (`http://127.0.0.1:5001`).
+    pub fn local() -> Self {
+        Self::new("http://127.0.0.1:5001")
+    }
+
+    /// Sets the size limits, retries, and timeout.
+    pub fn options(mut self, options: NetOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn call(&self, path: &str, query: &[(&str, &str)], body: Option<(&str, &[u8])>) -> Result<Vec<u8>, NetError> {
+        let url = format!("{}/api/v0/{}", self.api.trim_end_matches('/'), path);
+        let agent = ureq::AgentBuilder::new().timeout(self.options.timeout).build();
+        let response = with_retries(&url, &self.options, || {
+            let mut request = agent.post(&url);
+            for (name, value) in query {
+                request = request.query(name, value);
+            }
+            match body {
+                Some((content_type, body)) => request.set("Content-Type", content_type).send_bytes(body),
+                None => request.call(),
+            }
+            .map_err(Box::new)
+        })?;
+        read_body(response, self.options.max_size)
+    }
+}
+
+/// Adds and pins `edit` (canonical encoding, CIDv1, raw leaves), returning
+/// its CID.
+///
+/// For edits up to [`MAX_RAW_BLOCK`] bytes the node's CID is checked
+/// against [`Cid::for_edit`].
+pub fn put_edit(client: &IpfsClient, edit: &Edit) -> Result<Cid, NetError> {
+    let bytes = encode_edit_with_options(edit, EncodeOptions::canonical())?;
+    if bytes.len() > client.options.max_size {
+        return Err(NetError::TooLarge { len: bytes.len(), max: client.options.max_size });
+    }
+
+    const BOUNDARY: &str = "grc20-edit-boundary";
+    let mut body = Vec::with_capacity(bytes.len() + 256);
+    body.extend_from_slice(format!("--{BOUNDARY}\r\n").as_bytes());
+    body.extend_from_slice(b"Content-Disposition: form-data; name=\"file\"; filename=\"edit\"\r\n");
+    body.extend_from_slice(b"Content-Type: application/octet-stream\r\n\r\n");
+    body.extend_from_slice(&bytes);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    let chunker = format!("size-{MAX_RAW_BLOCK}");
+    let query = [
+        ("cid-version", "1"),
+        ("raw-leaves", "true"),
+        ("hash", "sha2-256"),
+        ("chunker", chunker.as_str()),
+        ("pin", "true"),
+    ];
+    let content_type = format!("multipart/form-data; boundary={BOUNDARY}");
+    let response = client.call("add", &query, Some((&content_type, &body)))?;
+
+    // {"Name":"edit","Hash":"bafkrei...","Size":"123"}
+    let response = String::from_utf8_lossy(&response);
+    let hash = response
+        .split("\"Hash\":\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .ok_or_else(|| NetError::Ipfs(format!("unexpected add response: {response}")))?;
+    let cid: Cid = hash.parse().map_err(|e: InteropError| NetError::Ipfs(e.to_string()))?;
+    if bytes.len() <= MAX_RAW_BLOCK && cid != Cid::raw(&bytes) {
+        return Err(NetError::Ipfs(format!("node returned {cid}, expected {}", Cid::raw(&bytes))));
+    }
+    Ok(cid)
+}
+
+/// Fetches and decodes the edit stored under `cid`.
+///
+/// The bytes of `raw` CIDs are checked against the digest.
+pub fn get_edit(client: &IpfsClient, cid: &Cid) -> Result<Edit<'static>, NetError> {
+    let cid_str = cid.to_string();
+    let bytes = client.call("cat", &[("arg", cid_str.as_str())], None)?;
+    if cid.codec == CODEC_RAW && Cid::raw(&bytes) != *cid {
+        return Err(NetError::Ipfs(format!("content of {cid} does not match its digest")));
+    }
+    Ok(edit_to_owned(decode_edit(&bytes)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use crate::net::test_server::serve;
+
+    fn edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16]).name("ipfs").create_entity([2u8; 16], |e| e.text([3u8; 16], "Alice", None)).build()
+    }
+
+    #[test]
+    fn test_cid_format() {
+        // `echo -n hello | ipfs add --cid-version 1 --raw-leaves`
+        let cid = Cid::raw(b"hello");
+        assert_eq!(cid.to_string(), "bafkreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq");
+        assert_eq!(cid.to_string().parse::<Cid>().unwrap(), cid);
+        assert_eq!(Cid::from_bytes(&cid.to_bytes()).unwrap(), cid);
+
+        assert!(matches!("Qmfoo".parse::<Cid>(), Err(InteropError::InvalidCid { .. })));
+        assert!("b1".parse::<Cid>().is_err());
+        assert!("bafkrei".parse::<Cid>().is_err());
+
+        let canonical = encode_edit_with_options(&edit(), EncodeOptions::canonical()).unwrap();
+        assert_eq!(Cid::for_edit(&edit()).unwrap(), Some(Cid::raw(&canonical)));
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let cid = Cid::for_edit(&edit()).unwrap().unwrap();
+        let canonical = encode_edit_with_options(&edit(), EncodeOptions::canonical()).unwrap();
+        let added = format!("{{\"Name\":\"edit\",\"Hash\":\"{cid}\",\"Size\":\"{}\"}}\n", canonical.len());
+        let (api, requests) = serve(vec![(200, vec![], added.into_bytes()), (200, vec![], canonical.clone())]);
+        let client = IpfsClient::new(api);
+
+        assert_eq!(put_edit(&client, &edit()).unwrap(), cid);
+        let add = requests.recv().unwrap();
+        assert_eq!(add.method, "POST");
+        assert!(add.path.starts_with("/api/v0/add?cid-version=1&raw-leaves=true"));
+        assert!(add.header("Content-Type").unwrap().starts_with("multipart/form-data; boundary="));
+        assert!(add.body.windows(canonical.len()).any(|w| w == canonical));
+
+        assert_eq!(get_edit(&client, &cid).unwrap(), edit());
+        assert_eq!(requests.recv().unwrap().path, format!("/api/v0/cat?arg={cid}"));
+    }
+
+    #[test]
+    fn test_mismatches_rejected() {
+        let cid = Cid::for_edit(&edit()).unwrap().unwrap();
+        let other = Cid::raw(b"other");
+        let added = format!("{{\"Hash\":\"{other}\"}}");
+        let (api, _requests) = serve(vec![(200, vec![], added.into_bytes()), (200, vec![], b"tampered".to_vec())]);
+        let client = IpfsClient::new(api);
+
+        assert!(matches!(put_edit(&client, &edit()), Err(NetError::Ipfs(_))));
+        assert!(matches!(get_edit(&client, &cid), Err(NetError::Ipfs(_))));
+    }
+}
@@ -0,0 +1,182 @@
+//! Conversions between GRC-20 data and other data ecosystems.
+//!
+//! Each submodule targets one external format. Modules that need extra
+//! dependencies are behind cargo features:
+//!
+//! - [`arrow`] (feature `arrow`, Parquet writing with feature `parquet`):
+//!   Arrow record batches for analytics tooling
+//! - [`rdf`]: N-Triples and Turtle for SPARQL tooling
+//! - [`did`]: author IDs for `did:key` and `did:pkh` identities
+//! - `ipfs` (feature `ipfs`): storing edits through the Kubo HTTP API
+//! - [`postgres`]: PostgreSQL `COPY` data for bulk loading
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod did;
+#[cfg(feature = "ipfs")]
+pub mod ipfs;
+pub mod postgres;
+pub mod rdf;
+
+use crate::model::{DataType, DecimalMantissa, Value};
+
+/// Returns the spec name of a data type (e.g. `"TEXT"`).
+pub(crate) fn data_type_name(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Bool => "BOOL",
+        DataType::Int64 => "INT64",
+        DataType::Float64 => "FLOAT64",
+        DataType::Decimal => "DECIMAL",
+        DataType::Text => "TEXT",
+        DataType::Bytes => "BYTES",
+        DataType::Date => "DATE",
+        DataType::Time => "TIME",
+        DataType::Datetime => "DATETIME",
+        DataType::Schedule => "SCHEDULE",
+        DataType::Point => "POINT",
+        DataType::Rect => "RECT",
+        DataType::Embedding => "EMBEDDING",
+    }
+}
+
+/// Largest exponent magnitude [`format_decimal`] writes out in full.
+pub(crate) const MAX_PLAIN_EXPONENT: u32 = 64;
+
+/// Formats a DECIMAL value (`mantissa * 10^exponent`) as a decimal string.
+///
+/// E.g. `12345` with exponent `-2` becomes `"123.45"` and `5` with exponent
+/// `3` becomes `"5000"`. Exponents beyond [`MAX_PLAIN_EXPONENT`] in
+/// magnitude come from the wire unbounded, so they are written in
+/// scientific notation (`"5E100"`) instead of padding with zeros.
+pub(crate) fn format_decimal(exponent: i32, mantissa: &DecimalMantissa<'_>) -> String {
+    let (negative, digits) = match mantissa {
+        DecimalMantissa::I64(v) => (*v < 0, v.unsigned_abs().to_string()),
+        DecimalMantissa::Big(bytes) => big_to_decimal_digits(bytes),
+    };
+
+    let mut out = String::with_capacity(digits.len() + 4);
+    if negative {
+        out.push('-');
+    }
+    if exponent.unsigned_abs() > MAX_PLAIN_EXPONENT {
+        out.push_str(&digits);
+        out.push('E');
+        out.push_str(&exponent.to_string());
+    } else if exponent >= 0 {
+        out.push_str(&digits);
+        if digits != "0" {
+            out.extend(std::iter::repeat_n('0', exponent as usize));
+        }
+    } else {
+        let scale = exponent.unsigned_abs() as usize;
+        if digits.len() > scale {
+            let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+            out.push_str(int_part);
+            out.push('.');
+            out.push_str(frac_part);
+        } else {
+            out.push_str("0.");
+            out.extend(std::iter::repeat_n('0', scale - digits.len()));
+            out.push_str(&digits);
+        }
+    }
+    out
+}
+
+/// Formats a POINT or RECT value as WKT (`x` is longitude, `y` is latitude).
+///
+/// Returns `None` for other value types.
+pub(crate) fn format_wkt(value: &Value<'_>) -> Option<String> {
+    match value {
+        Value::Point { lat, lon, alt: Some(alt) } => Some(format!("POINT Z ({} {} {})", lon, lat, alt)),
+        Value::Point { lat, lon, alt: None } => Some(format!("POINT ({} {})", lon, lat)),
+        Value::Rect { min_lat, min_lon, max_lat, max_lon } => Some(format!(
+            "POLYGON (({0} {1}, {2} {1}, {2} {3}, {0} {3}, {0} {1}))",
+            min_lon, min_lat, max_lon, max_lat
+        )),
+        _ => None,
+    }
+}
+
+/// Converts big-endian two's complement bytes to (is_negative, decimal digits).
+fn big_to_decimal_digits(bytes: &[u8]) -> (bool, String) {
+    let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+
+    // Magnitude as big-endian unsigned bytes
+    let mut magnitude: Vec<u8> = bytes.to_vec();
+    if negative {
+        for b in magnitude.iter_mut() {
+            *b = !*b;
+        }
+        for b in magnitude.iter_mut().rev() {
+            let (sum, carry) = b.overflowing_add(1);
+            *b = sum;
+            if !carry {
+                break;
+            }
+        }
+    }
+
+    // Repeated division by 10
+    let mut digits = Vec::new();
+    while magnitude.iter().any(|&b| b != 0) {
+        let mut remainder = 0u32;
+        for b in magnitude.iter_mut() {
+            let acc = (remainder << 8) | *b as u32;
+            *b = (acc / 10) as u8;
+            remainder = acc % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+
+    (negative, String::from_utf8(digits).expect("ASCII digits"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_format_decimal_i64() {
+        assert_eq!(format_decimal(-2, &DecimalMantissa::I64(12345)), "123.45");
+        assert_eq!(format_decimal(-2, &DecimalMantissa::I64(-12345)), "-123.45");
+        assert_eq!(format_decimal(-4, &DecimalMantissa::I64(5)), "0.0005");
+        assert_eq!(format_decimal(3, &DecimalMantissa::I64(5)), "5000");
+        assert_eq!(format_decimal(0, &DecimalMantissa::I64(0)), "0");
+        assert_eq!(format_decimal(0, &DecimalMantissa::I64(i64::MIN)), "-9223372036854775808");
+        assert_eq!(format_decimal(64, &DecimalMantissa::I64(1)).len(), 65);
+        assert_eq!(format_decimal(65, &DecimalMantissa::I64(1)), "1E65");
+        assert_eq!(format_decimal(i32::MAX, &DecimalMantissa::I64(-5)), "-5E2147483647");
+    }
+
+    #[test]
+    fn test_format_decimal_big() {
+        // 2^64 = 18446744073709551616
+        let big = DecimalMantissa::Big(Cow::Owned(vec![0x01, 0, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(format_decimal(-1, &big), "1844674407370955161.6");
+        assert_eq!(format_decimal(i32::MIN, &big), "18446744073709551616E-2147483648");
+
+        // -(2^64) in two's complement
+        let neg = DecimalMantissa::Big(Cow::Owned(vec![0xFF, 0, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(format_decimal(0, &neg), "-18446744073709551616");
+    }
+
+    #[test]
+    fn test_format_wkt() {
+        assert_eq!(format_wkt(&Value::Point { lat: 52.5, lon: 13.4, alt: None }).unwrap(), "POINT (13.4 52.5)");
+        assert_eq!(
+            format_wkt(&Value::Point { lat: 1.0, lon: 2.0, alt: Some(3.5) }).unwrap(),
+            "POINT Z (2 1 3.5)"
+        );
+        assert_eq!(
+            format_wkt(&Value::Rect { min_lat: 0.0, min_lon: 1.0, max_lat: 2.0, max_lon: 3.0 }).unwrap(),
+            "POLYGON ((1 0, 3 0, 3 2, 1 2, 1 0))"
+        );
+        assert_eq!(format_wkt(&Value::Bool(true)), None);
+    }
+}
@@ -0,0 +1,467 @@
+//! RDF export as N-Triples or Turtle.
+//!
+//! Both single edits and a materialized [`Graph`] can be exported. Every
+//! property value becomes one triple `<entity> <property> literal` and
+//! every relation becomes `<from> <relation_type> <to>`. IDs are turned into
+//! IRIs by an [`IriScheme`]; the default is `urn:uuid:<uuid>`, which lets
+//! other datasets link to GRC-20 entities without a resolver.
+//!
+//! Literals use XSD datatypes where one exists:
+//!
+//! | GRC-20   | RDF                                 |
+//! |----------|-------------------------------------|
+//! | BOOL     | `xsd:boolean`                       |
+//! | INT64    | `xsd:long`                          |
+//! | FLOAT64  | `xsd:double`                        |
+//! | DECIMAL  | `xsd:decimal`                       |
+//! | TEXT     | plain or language-tagged string     |
+//! | BYTES    | `xsd:hexBinary`                     |
+//! | DATE     | `xsd:date`                          |
+//! | TIME     | `xsd:time`                          |
+//! | DATETIME | `xsd:dateTime`                      |
+//! | SCHEDULE | plain string (iCalendar)            |
+//! | POINT    | `geo:wktLiteral`                    |
+//! | RECT     | `geo:wktLiteral` (polygon)          |
+//!
+//! TEXT languages are written as BCP 47 tags when the language ID is known
+//! (see [`RdfOptions::language`]); otherwise the string is untagged. Units,
+//! EMBEDDING values, relation metadata (position, entity, space pins), and
+//! delete/restore/unset ops have no triple representation and are skipped.
+//! An edit's triples therefore only describe what it adds; to describe the
+//! current state of a space, export its graph with [`graph_to_ntriples`] or
+//! [`graph_to_turtle`], which skip deleted entities and relations.
+
+use std::fmt::Write;
+
+use crate::collections::FxHashMap;
+use crate::genesis::language_id;
+use crate::interop::{format_decimal, format_wkt};
+use crate::model::{Edit, Id, Op, PropertyValue, Value};
+use crate::state::Graph;
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const GEO: &str = "http://www.opengis.net/ont/geosparql#";
+
+/// Language codes recognized by [`RdfOptions::new`].
+const COMMON_LANGUAGES: &[&str] = &[
+    "en", "es", "fr", "de", "zh", "ja", "ko", "pt", "it", "ru", "ar", "hi", "nl", "sv", "pl", "tr",
+    "uk", "vi", "id", "he", "el", "cs", "da", "fi", "no", "hu", "ro", "th", "fa", "bn",
+];
+
+/// Maps GRC-20 IDs to IRIs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IriScheme {
+    prefix: String,
+    hyphenated: bool,
+}
+
+impl IriScheme {
+    /// `urn:uuid:` followed by the hyphenated UUID (the default).
+    pub fn urn_uuid() -> Self {
+        Self { prefix: "urn:uuid:".to_string(), hyphenated: true }
+    }
+
+    /// `prefix` followed by the 32-character hex ID,
+    /// e.g. `https://example.com/entity/` .
+    pub fn hex(prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into(), hyphenated: false }
+    }
+
+    /// `prefix` followed by the hyphenated UUID.
+    pub fn uuid(prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into(), hyphenated: true }
+    }
+
+    /// Returns the IRI for an ID (without angle brackets).
+    pub fn iri(&self, id: &Id) -> String {
+        let mut out = String::with_capacity(self.prefix.len() + 36);
+        out.push_str(&self.prefix);
+        for (i, b) in id.iter().enumerate() {
+            if self.hyphenated && matches!(i, 4 | 6 | 8 | 10) {
+                out.push('-');
+            }
+            let _ = write!(out, "{:02x}", b);
+        }
+        out
+    }
+}
+
+impl Default for IriScheme {
+    fn default() -> Self {
+        Self::urn_uuid()
+    }
+}
+
+/// Options for RDF export.
+#[derive(Debug, Clone)]
+pub struct RdfOptions {
+    iri: IriScheme,
+    languages: FxHashMap<Id, String>,
+}
+
+impl Default for RdfOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RdfOptions {
+    /// Creates options with the default IRI scheme and common language codes.
+    pub fn new() -> Self {
+        let languages = COMMON_LANGUAGES
+            .iter()
+            .map(|code| (language_id(code), code.to_string()))
+            .collect();
+        Self { iri: IriScheme::default(), languages }
+    }
+
+    /// Sets the IRI scheme.
+    pub fn iri_scheme(mut self, iri: IriScheme) -> Self {
+        self.iri = iri;
+        self
+    }
+
+    /// Registers a BCP 47 code so TEXT values in that language are tagged.
+    pub fn language(mut self, code: impl Into<String>) -> Self {
+        let code = code.into();
+        self.languages.insert(language_id(&code), code);
+        self
+    }
+}
+
+// =============================================================================
+// TRIPLES
+// =============================================================================
+
+/// Object of a triple.
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Iri(Id),
+    Literal {
+        lexical: String,
+        datatype: Option<(&'static str, &'static str)>,
+        language: Option<String>,
+    },
+}
+
+/// Collects the triples of an edit: value triples in op order, then
+/// relation triples in op order.
+fn edit_triples(edit: &Edit, options: &RdfOptions) -> Vec<(Id, Id, Object)> {
+    let mut triples = Vec::new();
+    let mut push_values = |entity: Id, values: &[PropertyValue<'_>]| {
+        for pv in values {
+            if let Some(object) = literal(&pv.value, options) {
+                triples.push((entity, pv.property, object));
+            }
+        }
+    };
+    for op in &edit.ops {
+        match op {
+            Op::CreateEntity(ce) => push_values(ce.id, &ce.values),
+            Op::UpdateEntity(ue) => push_values(ue.id, &ue.set_properties),
+            _ => {}
+        }
+    }
+    for op in &edit.ops {
+        if let Op::CreateRelation(cr) = op {
+            triples.push((cr.from, cr.relation_type, Object::Iri(cr.to)));
+        }
+    }
+    triples
+}
+
+/// Collects the triples of a graph's live entities and relations, ordered by
+/// entity and relation ID.
+fn graph_triples(graph: &Graph, options: &RdfOptions) -> Vec<(Id, Id, Object)> {
+    let mut triples = Vec::new();
+    let mut entities: Vec<_> = graph.entities().collect();
+    entities.sort_unstable_by_key(|(id, _)| **id);
+    for (id, entity) in entities {
+        for (key, value) in &entity.values {
+            if let Some(object) = literal(value, options) {
+                triples.push((*id, key.property, object));
+            }
+        }
+    }
+    let mut relations: Vec<_> = graph.relations().collect();
+    relations.sort_unstable_by_key(|(id, _)| **id);
+    for (_, relation) in relations {
+        triples.push((relation.from, relation.relation_type, Object::Iri(relation.to)));
+    }
+    triples
+}
+
+/// Converts a value to a literal, or `None` if it has no RDF form.
+fn literal(value: &Value<'_>, options: &RdfOptions) -> Option<Object> {
+    let typed = |lexical: String, local: &'static str| Object::Literal {
+        lexical,
+        datatype: Some((XSD, local)),
+        language: None,
+    };
+    Some(match value {
+        Value::Bool(b) => typed(b.to_string(), "boolean"),
+        Value::Int64 { value, .. } => typed(value.to_string(), "long"),
+        Value::Float64 { value, .. } => {
+            let lexical = match *value {
+                v if v == f64::INFINITY => "INF".to_string(),
+                v if v == f64::NEG_INFINITY => "-INF".to_string(),
+                v => v.to_string(),
+            };
+            typed(lexical, "double")
+        }
+        Value::Decimal { exponent, mantissa, .. } => {
+            // xsd:decimal has no exponent form; large exponents become doubles
+            let lexical = format_decimal(*exponent, mantissa);
+            let datatype = if lexical.contains('E') { "double" } else { "decimal" };
+            typed(lexical, datatype)
+        }
+        Value::Text { value, language } => Object::Literal {
+            lexical: value.to_string(),
+            datatype: None,
+            language: language.and_then(|id| options.languages.get(&id).cloned()),
+        },
+        Value::Bytes(bytes) => {
+            let mut hex = String::with_capacity(bytes.len() * 2);
+            for b in bytes.iter() {
+                let _ = write!(hex, "{:02X}", b);
+            }
+            typed(hex, "hexBinary")
+        }
+        Value::Date(s) => typed(s.to_string(), "date"),
+        Value::Time(s) => typed(s.to_string(), "time"),
+        Value::Datetime(s) => typed(s.to_string(), "dateTime"),
+        Value::Schedule(s) => Object::Literal { lexical: s.to_string(), datatype: None, language: None },
+        Value::Point { .. } | Value::Rect { .. } => Object::Literal {
+            lexical: format_wkt(value)?,
+            datatype: Some((GEO, "wktLiteral")),
+            language: None,
+        },
+        Value::Embedding { .. } => return None,
+    })
+}
+
+/// Appends a quoted, escaped string literal.
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// =============================================================================
+// SERIALIZATION
+// =============================================================================
+
+/// Serializes the triples of an edit as N-Triples.
+pub fn edit_to_ntriples(edit: &Edit, options: &RdfOptions) -> String {
+    to_ntriples(edit_triples(edit, options), options)
+}
+
+/// Serializes the current state of a graph as N-Triples.
+pub fn graph_to_ntriples(graph: &Graph, options: &RdfOptions) -> String {
+    to_ntriples(graph_triples(graph, options), options)
+}
+
+/// Serializes the triples of an edit as Turtle, grouped by subject.
+pub fn edit_to_turtle(edit: &Edit, options: &RdfOptions) -> String {
+    to_turtle(edit_triples(edit, options), options)
+}
+
+/// Serializes the current state of a graph as Turtle, grouped by subject.
+pub fn graph_to_turtle(graph: &Graph, options: &RdfOptions) -> String {
+    to_turtle(graph_triples(graph, options), options)
+}
+
+fn to_ntriples(triples: Vec<(Id, Id, Object)>, options: &RdfOptions) -> String {
+    let mut out = String::new();
+    for (subject, predicate, object) in triples {
+        let _ = write!(out, "<{}> <{}> ", options.iri.iri(&subject), options.iri.iri(&predicate));
+        write_object(&mut out, &object, options, false);
+        out.push_str(" .\n");
+    }
+    out
+}
+
+fn to_turtle(triples: Vec<(Id, Id, Object)>, options: &RdfOptions) -> String {
+    // Group by subject in first-seen order
+    let mut order: Vec<Id> = Vec::new();
+    let mut groups: FxHashMap<Id, Vec<(Id, Object)>> = FxHashMap::default();
+    for (subject, predicate, object) in triples {
+        groups
+            .entry(subject)
+            .or_insert_with(|| {
+                order.push(subject);
+                Vec::new()
+            })
+            .push((predicate, object));
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "@prefix xsd: <{}> .", XSD);
+    let _ = writeln!(out, "@prefix geo: <{}> .", GEO);
+    for subject in order {
+        let _ = write!(out, "\n<{}>", options.iri.iri(&subject));
+        let group = &groups[&subject];
+        for (i, (predicate, object)) in group.iter().enumerate() {
+            let _ = write!(out, "\n    <{}> ", options.iri.iri(predicate));
+            write_object(&mut out, object, options, true);
+            out.push_str(if i + 1 == group.len() { " ." } else { " ;" });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn write_object(out: &mut String, object: &Object, options: &RdfOptions, prefixed: bool) {
+    match object {
+        Object::Iri(id) => {
+            let _ = write!(out, "<{}>", options.iri.iri(id));
+        }
+        Object::Literal { lexical, datatype, language } => {
+            write_string(out, lexical);
+            if let Some(language) = language {
+                let _ = write!(out, "@{}", language);
+            } else if let Some((namespace, local)) = datatype {
+                if prefixed {
+                    let prefix = if *namespace == XSD { "xsd" } else { "geo" };
+                    let _ = write!(out, "^^{}:{}", prefix, local);
+                } else {
+                    let _ = write!(out, "^^<{}{}>", namespace, local);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::{languages, properties, relation_types};
+    use crate::model::EditBuilder;
+
+    fn make_edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| {
+                e.text(properties::name(), "Berlin", Some(languages::german()))
+                    .text(properties::description(), "Say \"hi\"\n", None)
+                    .int64([10u8; 16], 3_600_000, None)
+                    .point([11u8; 16], 13.4, 52.5, None)
+            })
+            .create_relation_simple([5u8; 16], [3u8; 16], [4u8; 16], relation_types::types())
+            .build()
+    }
+
+    #[test]
+    fn test_iri_scheme() {
+        let id = [0xabu8; 16];
+        assert_eq!(IriScheme::urn_uuid().iri(&id), "urn:uuid:abababab-abab-abab-abab-abababababab");
+        assert_eq!(
+            IriScheme::hex("https://example.com/e/").iri(&id),
+            "https://example.com/e/abababababababababababababababab"
+        );
+    }
+
+    #[test]
+    fn test_ntriples() {
+        let options = RdfOptions::new().iri_scheme(IriScheme::hex("x:"));
+        let nt = edit_to_ntriples(&make_edit(), &options);
+        let lines: Vec<&str> = nt.lines().collect();
+        assert_eq!(lines.len(), 5);
+
+        let entity = format!("<x:{}>", "03".repeat(16));
+        assert!(lines[0].starts_with(&entity));
+        assert!(lines[0].ends_with("\"Berlin\"@de ."));
+        assert!(lines[1].ends_with("\"Say \\\"hi\\\"\\n\" ."));
+        assert!(lines[2].ends_with("\"3600000\"^^<http://www.w3.org/2001/XMLSchema#long> ."));
+        assert!(lines[3].ends_with("\"POINT (13.4 52.5)\"^^<http://www.opengis.net/ont/geosparql#wktLiteral> ."));
+        assert_eq!(
+            lines[4],
+            format!(
+                "{} <x:{}> <x:{}> .",
+                entity,
+                crate::model::format_id(&relation_types::types()),
+                "04".repeat(16)
+            )
+        );
+    }
+
+    #[test]
+    fn test_turtle_groups_by_subject() {
+        let ttl = edit_to_turtle(&make_edit(), &RdfOptions::new());
+        assert!(ttl.starts_with("@prefix xsd:"));
+        // One subject block: name ; description ; int ; point ; type .
+        assert_eq!(ttl.matches("<urn:uuid:03030303-").count(), 1);
+        assert_eq!(ttl.matches(" ;").count(), 4);
+        assert!(ttl.contains("\"3600000\"^^xsd:long ;"));
+        assert!(ttl.trim_end().ends_with("<urn:uuid:04040404-0404-0404-0404-040404040404> ."));
+    }
+
+    #[test]
+    fn test_graph_export_skips_deleted() {
+        let mut graph = Graph::new();
+        graph.apply_edit(&make_edit());
+        graph.apply_edit(
+            &EditBuilder::new([2u8; 16])
+                .update_entity([3u8; 16], |u| u.unset_all([10u8; 16]))
+                .create_entity([6u8; 16], |e| e.int64([10u8; 16], 1, None))
+                .delete_entity([6u8; 16])
+                .create_relation_simple([7u8; 16], [3u8; 16], [8u8; 16], [9u8; 16])
+                .delete_relation([7u8; 16])
+                .build(),
+        );
+
+        let options = RdfOptions::new().iri_scheme(IriScheme::hex("x:"));
+        let nt = graph_to_ntriples(&graph, &options);
+        let lines: Vec<&str> = nt.lines().collect();
+        // name, description, point, and the type relation; the unset int is gone
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|line| line.starts_with(&format!("<x:{}>", "03".repeat(16)))));
+        assert!(!nt.contains("3600000"));
+        assert!(!nt.contains(&"06".repeat(16)) && !nt.contains(&"08".repeat(16)));
+        assert!(lines[3].ends_with(&format!("<x:{}> .", "04".repeat(16))));
+
+        let ttl = graph_to_turtle(&graph, &options);
+        assert_eq!(ttl.matches(" ;").count(), 3);
+        assert_eq!(graph_to_ntriples(&Graph::new(), &options), "");
+    }
+
+    #[test]
+    fn test_literals() {
+        let options = RdfOptions::new();
+        let lexical = |v: Value<'_>| match literal(&v, &options) {
+            Some(Object::Literal { lexical, .. }) => lexical,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(lexical(Value::Float64 { value: f64::NEG_INFINITY, unit: None }), "-INF");
+        assert_eq!(lexical(Value::Bytes(vec![0x0a, 0xff].into())), "0AFF");
+        assert_eq!(
+            lexical(Value::Decimal { exponent: -2, mantissa: crate::model::DecimalMantissa::I64(12345), unit: None }),
+            "123.45"
+        );
+
+        // Exponents come from the wire unbounded and must not be padded out
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| {
+                e.decimal([10u8; 16], i32::MAX, crate::model::DecimalMantissa::I64(5), None)
+                    .decimal([11u8; 16], i32::MIN + 1, crate::model::DecimalMantissa::I64(-5), None)
+            })
+            .build();
+        let nt = edit_to_ntriples(&edit, &options);
+        assert!(nt.contains("\"5E2147483647\"^^<http://www.w3.org/2001/XMLSchema#double>"));
+        assert!(nt.contains("\"-5E-2147483647\"^^<http://www.w3.org/2001/XMLSchema#double>"));
+
+        // Unknown languages are untagged until registered
+        let text = Value::Text { value: "kia ora".into(), language: Some(language_id("mi")) };
+        assert!(matches!(literal(&text, &options), Some(Object::Literal { language: None, .. })));
+        let options = options.language("mi");
+        assert!(matches!(literal(&text, &options), Some(Object::Literal { language: Some(ref l), .. }) if l == "mi"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Language tags for TEXT values.
+//!
+//! Language entity IDs are derived from lowercase BCP 47 tags (spec Section
+//! 7.4). [`from_bcp47`] normalizes tags as they appear in the wild
+//! (`pt-BR`, `en_US`, `zh-CN`) before deriving, so importers get the same
+//! IDs without keeping their own tables; [`to_bcp47`] maps well-known IDs
+//! back to tags.
+
+use crate::model::{derived_uuid, Id};
+use crate::prelude::*;
+
+/// Tags [`to_bcp47`] can recover from an ID, in normalized form.
+pub const WELL_KNOWN_TAGS: &[&str] = &[
+    "en", "es", "zh", "zh-hans", "zh-hant", "ar", "hi", "pt", "pt-br", "ru", "ja", "fr", "de", "ko", "it", "nl",
+    "pl", "tr", "uk", "sv", "no", "da", "fi", "cs", "el", "he", "fa", "id", "ms", "vi", "th", "bn", "ur", "ta",
+    "sw", "ro", "hu", "hr", "sr", "ca", "br", "en-gb", "en-us", "es-419", "fr-ca",
+];
+
+/// Chinese regions written in simplified and traditional script.
+const HANS_REGIONS: &[&str] = &["cn", "sg", "my"];
+const HANT_REGIONS: &[&str] = &["tw", "hk", "mo"];
+
+/// Normalizes a BCP 47 tag, or returns `None` if it is malformed.
+///
+/// Lowercases, accepts `_` as a separator, replaces deprecated language
+/// codes (`iw` → `he`, `in` → `id`, `ji` → `yi`), and maps Chinese region
+/// tags to script tags (`zh-CN` → `zh-hans`, `zh-TW` → `zh-hant`), matching
+/// the Genesis language table.
+pub fn normalize_bcp47(tag: &str) -> Option<String> {
+    let mut subtags = Vec::new();
+    for subtag in tag.split(['-', '_']) {
+        if subtag.is_empty() || subtag.len() > 8 || !subtag.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return None;
+        }
+        subtags.push(subtag.to_ascii_lowercase());
+    }
+    let primary = subtags.first_mut()?;
+    if !(2..=8).contains(&primary.len()) || !primary.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    match primary.as_str() {
+        "iw" => *primary = "he".to_string(),
+        "in" => *primary = "id".to_string(),
+        "ji" => *primary = "yi".to_string(),
+        _ => {}
+    }
+    if subtags[0] == "zh" && subtags.len() == 2 {
+        if HANS_REGIONS.contains(&subtags[1].as_str()) {
+            subtags[1] = "hans".to_string();
+        } else if HANT_REGIONS.contains(&subtags[1].as_str()) {
+            subtags[1] = "hant".to_string();
+        }
+    }
+    Some(subtags.join("-"))
+}
+
+/// Derives the language entity ID for a BCP 47 tag.
+///
+/// ```text
+/// id = derived_uuid("grc20:genesis:language:" + normalize_bcp47(tag))
+/// ```
+///
+/// Returns `None` if the tag is malformed.
+pub fn from_bcp47(tag: &str) -> Option<Id> {
+    normalize_bcp47(tag).map(|tag| derive(&tag))
+}
+
+/// Returns the normalized tag of a well-known language ID.
+///
+/// IDs are one-way hashes, so only the tags in [`WELL_KNOWN_TAGS`] can be
+/// recovered; each call derives them all, so cache the result in hot loops.
+pub fn to_bcp47(id: &Id) -> Option<&'static str> {
+    WELL_KNOWN_TAGS.iter().copied().find(|tag| derive(tag) == *id)
+}
+
+fn derive(tag: &str) -> Id {
+    let mut input = Vec::with_capacity(23 + tag.len());
+    input.extend_from_slice(b"grc20:genesis:language:");
+    input.extend_from_slice(tag.as_bytes());
+    derived_uuid(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_bcp47() {
+        assert_eq!(normalize_bcp47("pt-BR").as_deref(), Some("pt-br"));
+        assert_eq!(normalize_bcp47("en_US").as_deref(), Some("en-us"));
+        assert_eq!(normalize_bcp47("zh-CN").as_deref(), Some("zh-hans"));
+        assert_eq!(normalize_bcp47("zh-Hant").as_deref(), Some("zh-hant"));
+        assert_eq!(normalize_bcp47("zh-HK").as_deref(), Some("zh-hant"));
+        assert_eq!(normalize_bcp47("iw").as_deref(), Some("he"));
+        assert_eq!(normalize_bcp47(""), None);
+        assert_eq!(normalize_bcp47("en--us"), None);
+        assert_eq!(normalize_bcp47("1a"), None);
+        assert_eq!(normalize_bcp47("en us"), None);
+    }
+
+    #[test]
+    fn test_from_and_to_bcp47() {
+        assert_eq!(from_bcp47("EN"), from_bcp47("en"));
+        assert_eq!(from_bcp47("en"), Some(derived_uuid(b"grc20:genesis:language:en")));
+        for tag in WELL_KNOWN_TAGS {
+            assert_eq!(normalize_bcp47(tag).as_deref(), Some(*tag));
+            assert_eq!(to_bcp47(&from_bcp47(tag).unwrap()), Some(*tag));
+        }
+        assert_eq!(to_bcp47(&from_bcp47("pt-BR").unwrap()), Some("pt-br"));
+        assert_eq!(to_bcp47(&from_bcp47("tlh").unwrap()), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_matches_genesis() {
+        use crate::genesis::languages;
+        assert_eq!(from_bcp47("de"), Some(languages::german()));
+        assert_eq!(from_bcp47("zh-TW"), Some(languages::chinese_traditional()));
+    }
+}
@@ -13,6 +13,8 @@
 //! # Quick Start
 //!
 //! ```rust
+//! # // Genesis IDs need `std`
+//! # #[cfg(feature = "std")] {
 //! use std::borrow::Cow;
 //! use grc_20::{Edit, Op, CreateEntity, PropertyValue, Value, DataType};
 //! use grc_20::codec::{encode_edit, decode_edit};
@@ -23,6 +25,7 @@
 //!     id: [1u8; 16],
 //!     name: Cow::Owned("My Edit".to_string()),
 //!     authors: vec![[2u8; 16]],
+//!     parents: vec![],
 //!     created_at: 1234567890,
 //!     ops: vec![
 //!         Op::CreateEntity(CreateEntity {
@@ -45,6 +48,7 @@
 //! // Decode back (zero-copy for uncompressed data)
 //! let decoded = decode_edit(&bytes).unwrap();
 //! assert_eq!(edit.id, decoded.id);
+//! # }
 //! ```
 //!
 //! # Modules
@@ -52,7 +56,26 @@
 //! - [`model`]: Core data types (Entity, Relation, Value, Op, Edit)
 //! - [`codec`]: Binary encoding/decoding with compression support
 //! - [`validate`]: Semantic validation
+//! - [`lint`]: Style checks for edits, with custom rules
+//! - [`schema`]: Schema extraction from published edits
 //! - [`genesis`]: Well-known IDs from the Genesis Space
+//! - [`lang`]: BCP 47 language tag mapping
+//! - [`names`]: Names instead of UUIDs in printed ops, values, and errors
+//! - [`units`]: Measurement unit IDs and conversion
+//! - [`import`]: Importers for external datasets (CSV, ...)
+//! - [`interop`]: Conversions to external formats (Arrow, RDF, PostgreSQL)
+//! - [`state`]: Materialized space state from replaying edits
+//! - [`mapping`]: Struct <-> entity mapping (`derive(GrcEntity)` with feature `derive`)
+//! - [`merge`]: Reference merge semantics for concurrent edits
+//! - [`analysis`]: Heuristic checks and statistics over edits (suspicious IDs, property usage, duplicates)
+//! - [`patch`]: A text format for writing ops by hand
+//! - [`query`]: Fluent queries over materialized state
+//! - [`transform`]: Edit rewriting (retyping properties, ...)
+//! - [`snapshot`]: `GRC2SNAP` snapshots of materialized state
+//! - [`sync`]: Digests of edit sets for peer-to-peer sync
+//! - `signature`: Detached Ed25519 author signatures (feature `signature`)
+//! - `identity`: Author key resolution for signature checks (feature `signature`)
+//! - `net`: HTTP fetch and publish of edits (feature `net`)
 //! - [`error`]: Error types
 //! - [`limits`]: Security limits for decoding
 //!
@@ -65,40 +88,108 @@
 //!
 //! # Wire Format
 //!
-//! Edits use a binary format with optional zstd compression:
+//! Edits use a binary format with optional compression:
 //! - Uncompressed: `GRC2` magic + version + data
 //! - Compressed: `GRC2Z` magic + uncompressed size + zstd data
+//!   (`GRC2L` for LZ4 and `GRC2B` for Brotli, see [`codec::compression`])
+//! - Extended layout: `GRC2X` magic + version + layout flags + data
+//! - State snapshots: `GRC2SNAP` magic + version + dictionaries + objects
+//!   (see [`snapshot`])
 //!
-//! The decoder automatically detects and handles both formats.
+//! The decoder automatically detects and handles all formats.
+//!
+//! # `no_std`
+//!
+//! With `default-features = false` the crate is `no_std + alloc`: the model,
+//! uncompressed encoding/decoding (GRC2 and GRC2X), ID derivation, and
+//! validation are available, e.g. for verifying edit hashes in WASM smart
+//! contracts. Compression, [`genesis`], [`schema`], importers, and interop need `std`,
+//! except zstd decoding through `ruzstd` (feature `ruzstd`, always on for wasm32).
+//! zstd itself is the default `zstd` feature; without it (and the other codec
+//! features) libzstd is not linked and compressed input is rejected.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+// Lets `derive(GrcEntity)` output (which names `::grc_20`) compile in this crate's tests.
+extern crate self as grc_20;
 
+pub mod analysis;
 pub mod codec;
 pub mod error;
+#[cfg(feature = "std")]
 pub mod genesis;
+#[cfg(feature = "signature")]
+pub mod identity;
+#[cfg(feature = "std")]
+pub mod import;
+#[cfg(feature = "std")]
+pub mod interop;
+pub mod lang;
 pub mod limits;
+#[cfg(feature = "std")]
+pub mod lint;
+pub mod mapping;
+pub mod merge;
 pub mod model;
+pub mod names;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "std")]
+pub mod patch;
+pub mod query;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "signature")]
+pub mod signature;
+pub mod snapshot;
+pub mod state;
+pub mod sync;
+pub mod transform;
+pub mod units;
 pub mod util;
 pub mod validate;
 
+mod collections;
+mod prelude;
+
 // Re-export commonly used types at crate root
 pub use codec::{
-    decode_edit, decompress, encode_edit, encode_edit_compressed,
-    encode_edit_compressed_with_options, encode_edit_profiled, encode_edit_with_options,
-    EncodeOptions,
+    decode_edit, decode_edit_with_alloc_budget, decode_edit_with_options, decompress, encode_edit,
+    encode_edit_compressed, encode_edit_compressed_auto, encode_edit_compressed_with_codec,
+    encode_edit_compressed_with_options, encode_edit_with_context, encode_edit_with_options,
+    Codec, CompressionLevel, DecodeOptions, EncodeContext, EncodeOptions,
+};
+#[cfg(feature = "std")]
+pub use codec::encode_edit_profiled;
+pub use error::{
+    ChunkError, DecodeError, EncodeError, ImportError, InteropError, MappingError, NetError, PatchError, ReplayError,
+    RoundtripDivergence, SignatureError, StoreError, SyncError, UpgradeError, ValidationError,
 };
-pub use error::{DecodeError, EncodeError, ValidationError};
 pub use model::{
-    CreateEntity, CreateRelation, DataType, DecimalMantissa, DeleteEntity,
-    DeleteRelation, DictionaryBuilder, Edit, EditBuilder, EmbeddingSubType, EntityBuilder, Id,
-    Op, Property, PropertyValue, RelationBuilder, UnsetLanguage, UnsetRelationField, UnsetValue,
-    UpdateEntity, UpdateEntityBuilder, UpdateRelation, Value, WireDictionaries,
+    CreateEntity, CreateRelation, DataType, DecimalMantissa, DeleteEntity, DeleteRelation,
+    DictionaryBuilder, Edit, EditBuilder, EmbeddingSubType, EntityBuilder, EntityView, Id, Op,
+    OpKind, Property, PropertyValue, RelationBuilder, UnknownOp, UnsetLanguage, UnsetRelationField,
+    UnsetValue, UpdateEntity, UpdateEntityBuilder, UpdateRelation, Value, WireDictionaries,
 };
+pub use mapping::GrcEntity;
 pub use model::builder::UpdateRelationBuilder;
-pub use model::id::{derived_uuid, format_id, parse_id, text_value_id, value_id, NIL_ID};
+pub use model::op::position_between;
+pub use model::id::{
+    derive_in_namespace, derived_uuid, format_id, format_id_base58, format_id_base64url, id_from_hex, DisplayId,
+    parse_id, parse_id_base58, parse_id_base64url, text_value_id, value_id, NIL_ID,
+};
 pub use util::{
     format_date_rfc3339, format_datetime_rfc3339, format_time_rfc3339,
     parse_date_rfc3339, parse_datetime_rfc3339, parse_time_rfc3339, DateTimeParseError,
 };
-pub use validate::{validate_edit, validate_position, validate_value, SchemaContext};
+pub use validate::{
+    validate_edit, validate_edit_report, validate_edit_with_options, validate_position,
+    validate_value, SchemaContext, Severity, ValidationOptions, ValidationReport,
+    ValidationWarning,
+};
+#[cfg(feature = "rayon")]
+pub use validate::{validate_edits_parallel, validate_edits_parallel_with_options};
 
 /// Crate version.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -27,12 +27,33 @@ pub const MAX_VALUES_PER_ENTITY: usize = 10_000;
 /// Maximum authors per edit.
 pub const MAX_AUTHORS: usize = 1_000;
 
+/// Maximum parent edits per edit.
+pub const MAX_PARENTS: usize = 1_000;
+
 /// Maximum entries in any dictionary.
 pub const MAX_DICT_SIZE: usize = 1_000_000;
 
 /// Maximum total edit size after decompression (256 MB).
 pub const MAX_EDIT_SIZE: usize = 256 * 1024 * 1024;
 
+/// Allocation budget per input byte when decoding.
+///
+/// The budget covers everything the decoder allocates for an edit: op and
+/// value vectors, dictionaries, copied strings, and per-op context copies.
+/// Decoded structs are much wider than their wire encoding (a two-byte BOOL
+/// value becomes a 64-byte `PropertyValue`), so valid edits use up to ~32x
+/// their input size; the factor leaves headroom above that.
+pub const ALLOC_BUDGET_FACTOR: usize = 64;
+
+/// Allocation budget granted on top of [`ALLOC_BUDGET_FACTOR`], so small
+/// edits are not rejected for fixed per-edit overhead (64 KB).
+pub const ALLOC_BUDGET_BASE: usize = 64 * 1024;
+
+/// Returns the default allocation budget for decoding `input_len` bytes.
+pub const fn default_alloc_budget(input_len: usize) -> usize {
+    input_len.saturating_mul(ALLOC_BUDGET_FACTOR).saturating_add(ALLOC_BUDGET_BASE)
+}
+
 /// Maximum position string length (spec Section 2.6).
 pub const MAX_POSITION_LEN: usize = 64;
 
@@ -42,8 +63,29 @@ pub const MAGIC_UNCOMPRESSED: &[u8; 4] = b"GRC2";
 /// Magic bytes for zstd-compressed edits.
 pub const MAGIC_COMPRESSED: &[u8; 5] = b"GRC2Z";
 
-/// Current binary format version (for encoding).
-pub const FORMAT_VERSION: u8 = 1;
+/// Magic bytes for LZ4-compressed edits (feature `lz4`).
+pub const MAGIC_COMPRESSED_LZ4: &[u8; 5] = b"GRC2L";
+
+/// Magic bytes for Brotli-compressed edits (feature `brotli`).
+pub const MAGIC_COMPRESSED_BROTLI: &[u8; 5] = b"GRC2B";
+
+/// Magic bytes for extended layouts (e.g. columnar ops).
+pub const MAGIC_EXTENDED: &[u8; 5] = b"GRC2X";
+
+/// Magic bytes for materialized state snapshots.
+pub const MAGIC_SNAPSHOT: &[u8; 8] = b"GRC2SNAP";
+
+/// Magic bytes for chunk manifests (see `codec::chunking`).
+pub const MAGIC_MANIFEST: &[u8; 6] = b"GRC2CM";
+
+/// Magic bytes for detached signature lists (feature `signature`).
+pub const MAGIC_SIGNATURES: &[u8; 6] = b"GRC2SG";
+
+/// Magic bytes for sync messages (see `sync::Message`).
+pub const MAGIC_SYNC: &[u8; 6] = b"GRC2SY";
+
+/// Newest binary format version (for encoding edits with parents).
+pub const FORMAT_VERSION: u8 = 2;
 
 /// Minimum supported format version (for decoding).
 pub const MIN_FORMAT_VERSION: u8 = 0;
@@ -0,0 +1,360 @@
+//! Style checks for edits.
+//!
+//! Validation (see [`crate::validate`]) rejects edits that are wrong; lints
+//! flag edits that are legal but probably not what a space wants, such as
+//! entities without a name or type. [`lint_edit`] runs the built-in rules
+//! and any rules registered in the [`LintConfig`]:
+//!
+//! ```rust
+//! use grc_20::genesis::properties;
+//! use grc_20::lint::{lint_edit, LintConfig, LintRule, LintFinding, MISSING_TYPES};
+//! use grc_20::{Edit, EditBuilder};
+//!
+//! /// Edits should say what they do.
+//! struct EditName;
+//!
+//! impl LintRule for EditName {
+//!     fn name(&self) -> &'static str {
+//!         "edit-name"
+//!     }
+//!
+//!     fn check(&self, edit: &Edit, findings: &mut Vec<LintFinding>) {
+//!         if edit.name.is_empty() {
+//!             findings.push(LintFinding::new(self.name(), None, edit.id, "edit has no name"));
+//!         }
+//!     }
+//! }
+//!
+//! let edit = EditBuilder::new([1u8; 16])
+//!     .create_entity([2u8; 16], |e| e.text(properties::name(), "Berlin", None))
+//!     .build();
+//!
+//! let config = LintConfig::new().rule(EditName).deny(MISSING_TYPES);
+//! let findings = lint_edit(&edit, &config);
+//! assert_eq!(findings.len(), 2);
+//! assert!(findings.iter().any(|f| f.rule == "edit-name"));
+//! ```
+
+use core::fmt;
+
+use crate::collections::FxHashSet;
+use crate::genesis::{properties, relation_types};
+use crate::model::{format_id, Edit, Id, Op, PropertyValue, Value};
+use crate::names::NameResolver;
+use crate::prelude::*;
+use crate::validate::Severity;
+
+/// An entity's name is set to an empty or blank string.
+pub const EMPTY_NAME: &str = "empty-name";
+/// A TEXT value has no language (only with [`LintConfig::multilingual`]).
+pub const UNTRANSLATED_TEXT: &str = "untranslated-text";
+/// A relation of an ordered type (see [`LintConfig::ordered_type`]) has no
+/// position.
+pub const UNPOSITIONED_RELATION: &str = "unpositioned-relation";
+/// A created entity gets no Types relation in the edit.
+pub const MISSING_TYPES: &str = "missing-types";
+
+/// Something a lint rule flagged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// Name of the rule that produced the finding.
+    pub rule: &'static str,
+    /// [`Severity::Warning`], unless the rule is denied in the config.
+    pub severity: Severity,
+    /// Index of the op in the edit, or `None` for edit-wide findings.
+    pub op_index: Option<usize>,
+    /// The object the finding is about.
+    pub id: Id,
+    pub message: String,
+}
+
+impl LintFinding {
+    /// Creates a warning.
+    pub fn new(rule: &'static str, op_index: Option<usize>, id: Id, message: impl Into<String>) -> Self {
+        Self { rule, severity: Severity::Warning, op_index, id, message: message.into() }
+    }
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.op_index {
+            Some(i) => write!(f, "{level}[{}] op {i}: {}", self.rule, self.message),
+            None => write!(f, "{level}[{}]: {}", self.rule, self.message),
+        }
+    }
+}
+
+/// A custom lint rule.
+pub trait LintRule: Send + Sync {
+    /// The rule name used in findings and in [`LintConfig::allow`].
+    fn name(&self) -> &'static str;
+
+    /// Adds findings for `edit`, created with [`LintFinding::new`].
+    fn check(&self, edit: &Edit, findings: &mut Vec<LintFinding>);
+}
+
+/// Which rules [`lint_edit`] runs and how it reports them.
+///
+/// [`EMPTY_NAME`] and [`MISSING_TYPES`] run by default;
+/// [`UNTRANSLATED_TEXT`] and [`UNPOSITIONED_RELATION`] run once configured.
+pub struct LintConfig {
+    /// The property holding entity names.
+    pub name_property: Id,
+    /// Whether the space is multilingual, so TEXT values need a language.
+    pub multilingual: bool,
+    /// TEXT properties exempt from [`UNTRANSLATED_TEXT`], e.g. URLs.
+    pub untranslated_properties: FxHashSet<Id>,
+    /// Relation types whose relations are ordered and need a position.
+    pub ordered_types: FxHashSet<Id>,
+    allowed: FxHashSet<&'static str>,
+    denied: FxHashSet<&'static str>,
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            name_property: properties::name(),
+            multilingual: false,
+            untranslated_properties: FxHashSet::default(),
+            ordered_types: FxHashSet::default(),
+            allowed: FxHashSet::default(),
+            denied: FxHashSet::default(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for LintConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LintConfig")
+            .field("name_property", &format_id(&self.name_property))
+            .field("multilingual", &self.multilingual)
+            .field("untranslated_properties", &self.untranslated_properties.len())
+            .field("ordered_types", &self.ordered_types.len())
+            .field("allowed", &self.allowed)
+            .field("denied", &self.denied)
+            .field("rules", &self.rules.iter().map(|r| r.name()).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl LintConfig {
+    /// Creates the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `property` instead of the Genesis Name property.
+    pub fn name_property(mut self, property: Id) -> Self {
+        self.name_property = property;
+        self
+    }
+
+    /// Requires TEXT values to have a language.
+    pub fn multilingual(mut self) -> Self {
+        self.multilingual = true;
+        self
+    }
+
+    /// Exempts `property` from [`UNTRANSLATED_TEXT`].
+    pub fn untranslated(mut self, property: Id) -> Self {
+        self.untranslated_properties.insert(property);
+        self
+    }
+
+    /// Requires relations of `relation_type` to have a position.
+    pub fn ordered_type(mut self, relation_type: Id) -> Self {
+        self.ordered_types.insert(relation_type);
+        self
+    }
+
+    /// Turns off a rule, built-in or custom.
+    pub fn allow(mut self, rule: &'static str) -> Self {
+        self.allowed.insert(rule);
+        self
+    }
+
+    /// Reports a rule's findings as [`Severity::Error`].
+    pub fn deny(mut self, rule: &'static str) -> Self {
+        self.denied.insert(rule);
+        self
+    }
+
+    /// Registers a custom rule.
+    pub fn rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    fn enabled(&self, rule: &str) -> bool {
+        !self.allowed.contains(rule)
+    }
+}
+
+/// Lints an edit, returning findings in op order (edit-wide findings
+/// first), built-in rules before custom rules for the same op.
+pub fn lint_edit(edit: &Edit, config: &LintConfig) -> Vec<LintFinding> {
+    lint_edit_with_names(edit, config, &())
+}
+
+/// Lints an edit like [`lint_edit`], naming IDs in built-in findings with
+/// `names` (e.g. `(&graph, GenesisNames)`).
+pub fn lint_edit_with_names(edit: &Edit, config: &LintConfig, names: &dyn NameResolver) -> Vec<LintFinding> {
+    let label = |id: &Id| names.name(id).unwrap_or_else(|| format_id(id));
+    let mut findings = Vec::new();
+    let types = relation_types::types();
+    let typed: FxHashSet<Id> = edit
+        .ops
+        .iter()
+        .filter_map(|op| match op {
+            Op::CreateRelation(cr) if cr.relation_type == types => Some(cr.from),
+            _ => None,
+        })
+        .collect();
+
+    for (i, op) in edit.ops.iter().enumerate() {
+        let (id, values) = match op {
+            Op::CreateEntity(ce) => {
+                if config.enabled(MISSING_TYPES) && !typed.contains(&ce.id) {
+                    let message = format!("entity {} has no type", label(&ce.id));
+                    findings.push(LintFinding::new(MISSING_TYPES, Some(i), ce.id, message));
+                }
+                (ce.id, &ce.values)
+            }
+            Op::UpdateEntity(ue) => (ue.id, &ue.set_properties),
+            Op::CreateRelation(cr) => {
+                if config.enabled(UNPOSITIONED_RELATION)
+                    && cr.position.is_none()
+                    && config.ordered_types.contains(&cr.relation_type)
+                {
+                    let message = format!("relation {} of an ordered type has no position", label(&cr.id));
+                    findings.push(LintFinding::new(UNPOSITIONED_RELATION, Some(i), cr.id, message));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        lint_values(&mut findings, i, id, values, config, &label);
+    }
+
+    for rule in config.rules.iter().filter(|r| config.enabled(r.name())) {
+        rule.check(edit, &mut findings);
+    }
+    // Stable, so each op keeps built-in findings first
+    findings.sort_by_key(|f| f.op_index.map_or(0, |i| i + 1));
+    for finding in &mut findings {
+        if config.denied.contains(finding.rule) {
+            finding.severity = Severity::Error;
+        }
+    }
+    findings
+}
+
+fn lint_values(
+    findings: &mut Vec<LintFinding>,
+    i: usize,
+    id: Id,
+    values: &[PropertyValue],
+    config: &LintConfig,
+    label: &dyn Fn(&Id) -> String,
+) {
+    for pv in values {
+        let Value::Text { value, language } = &pv.value else {
+            continue;
+        };
+        if pv.property == config.name_property && value.trim().is_empty() && config.enabled(EMPTY_NAME) {
+            let message = format!("entity {} has an empty name", label(&id));
+            findings.push(LintFinding::new(EMPTY_NAME, Some(i), id, message));
+        }
+        if config.multilingual
+            && language.is_none()
+            && !config.untranslated_properties.contains(&pv.property)
+            && config.enabled(UNTRANSLATED_TEXT)
+        {
+            let message = format!("TEXT value of {} on {} has no language", label(&pv.property), label(&id));
+            findings.push(LintFinding::new(UNTRANSLATED_TEXT, Some(i), id, message));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::languages;
+    use crate::model::EditBuilder;
+
+    fn edit() -> Edit<'static> {
+        let (name, url, blocks) = (properties::name(), properties::url(), [20u8; 16]);
+        EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.text(name, " ", None).text(url, "https://example.com", None))
+            .create_entity([3u8; 16], |e| e.text(name, "Berlin", Some(languages::english())))
+            .create_relation_simple([4u8; 16], [3u8; 16], [9u8; 16], relation_types::types())
+            .create_relation_simple([5u8; 16], [3u8; 16], [2u8; 16], blocks)
+            .update_entity([2u8; 16], |u| u.set_text(name, "Mitte", None))
+            .build()
+    }
+
+    fn rules(findings: &[LintFinding]) -> Vec<(&'static str, Option<usize>)> {
+        findings.iter().map(|f| (f.rule, f.op_index)).collect()
+    }
+
+    #[test]
+    fn test_lint_edit() {
+        let findings = lint_edit(&edit(), &LintConfig::new());
+        assert_eq!(rules(&findings), vec![(MISSING_TYPES, Some(0)), (EMPTY_NAME, Some(0))]);
+        assert_eq!(findings[0].id, [2u8; 16]);
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+
+        let config = LintConfig::new()
+            .multilingual()
+            .untranslated(properties::url())
+            .ordered_type([20u8; 16])
+            .allow(MISSING_TYPES)
+            .deny(EMPTY_NAME);
+        let findings = lint_edit(&edit(), &config);
+        assert_eq!(
+            rules(&findings),
+            vec![
+                (EMPTY_NAME, Some(0)),
+                (UNTRANSLATED_TEXT, Some(0)),
+                (UNPOSITIONED_RELATION, Some(3)),
+                (UNTRANSLATED_TEXT, Some(4)),
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(findings[0].to_string().starts_with("error[empty-name] op 0: "));
+
+        let findings = lint_edit_with_names(&edit(), &config, &crate::genesis::GenesisNames);
+        assert!(findings[1].message.starts_with("TEXT value of Name on "));
+    }
+
+    struct NoDeletes;
+
+    impl LintRule for NoDeletes {
+        fn name(&self) -> &'static str {
+            "no-deletes"
+        }
+
+        fn check(&self, edit: &Edit, findings: &mut Vec<LintFinding>) {
+            for (i, op) in edit.ops.iter().enumerate() {
+                if let Op::DeleteEntity(de) = op {
+                    findings.push(LintFinding::new(self.name(), Some(i), de.id, "entity deleted"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let mut edit = edit();
+        edit.ops.insert(1, Op::DeleteEntity(crate::model::DeleteEntity { id: [7u8; 16], context: None }));
+        let config = LintConfig::new().rule(NoDeletes).allow(EMPTY_NAME);
+        assert_eq!(rules(&lint_edit(&edit, &config)), vec![(MISSING_TYPES, Some(0)), ("no-deletes", Some(1))]);
+        assert_eq!(lint_edit(&edit, &config.allow("no-deletes")).len(), 1);
+    }
+}
@@ -0,0 +1,214 @@
+//! Mapping between Rust structs and entities.
+//!
+//! [`GrcEntity`] converts a struct to the ops that create it and back from an
+//! [`EntityView`]. With the `derive` feature it can be derived, mapping each
+//! field to a property:
+//!
+//! ```ignore
+//! use grc_20::GrcEntity;
+//!
+//! #[derive(GrcEntity)]
+//! struct Person {
+//!     #[grc(id)]
+//!     id: grc_20::Id,
+//!     #[grc(property = "a126ca530c8e48d5b88882c734c38935")]
+//!     name: String,
+//!     #[grc(property = "a126ca530c8e48d5b88882c734c38935", language = GERMAN)]
+//!     name_de: Option<String>,
+//!     #[grc(property = AGE)]
+//!     age: Option<i64>,
+//!     #[grc(skip)]
+//!     cached: u32,
+//! }
+//! ```
+//!
+//! Properties and languages are UUID string literals, checked at compile time
+//! (see [`crate::id_from_hex`]), or any `Id` expression.
+//! Field types implement [`FieldValue`]; `Option<T>` fields may be absent,
+//! all others are required.
+
+use crate::error::MappingError;
+use crate::model::{CreateEntity, DataType, EntityView, Id, Op, PropertyValue, Value};
+use crate::prelude::*;
+
+#[cfg(feature = "derive")]
+pub use grc_20_derive::GrcEntity;
+
+/// A Rust type stored as a single entity.
+pub trait GrcEntity: Sized {
+    /// The entity ID.
+    fn entity_id(&self) -> Id;
+
+    /// The entity's values, skipping absent optional fields.
+    fn to_values(&self) -> Vec<PropertyValue<'static>>;
+
+    /// Reads the type back from an entity view.
+    fn from_entity_view(view: &EntityView<'_, '_>) -> Result<Self, MappingError>;
+
+    /// Returns a single CreateEntity op carrying [`GrcEntity::to_values`].
+    fn to_ops(&self) -> Vec<Op<'static>> {
+        vec![Op::CreateEntity(CreateEntity { id: self.entity_id(), values: self.to_values(), context: None })]
+    }
+}
+
+/// A field type that maps to one property value.
+pub trait FieldValue: Sized {
+    /// Converts the field to a value, or `None` to write nothing.
+    /// `language` applies to TEXT values only.
+    fn to_value(&self, language: Option<Id>) -> Option<Value<'static>>;
+
+    /// Reads the field from the property's value, if it has one.
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError>;
+}
+
+fn required<'v, 'a>(value: Option<&'v Value<'a>>, field: &'static str, property: Id) -> Result<&'v Value<'a>, MappingError> {
+    value.ok_or(MappingError::Missing { field, property })
+}
+
+fn wrong_type(field: &'static str, expected: DataType, found: &Value<'_>) -> MappingError {
+    MappingError::WrongType { field, expected, found: found.data_type() }
+}
+
+impl FieldValue for String {
+    fn to_value(&self, language: Option<Id>) -> Option<Value<'static>> {
+        Some(Value::Text { value: self.clone().into(), language })
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        match required(value, field, property)? {
+            Value::Text { value, .. } => Ok(value.to_string()),
+            other => Err(wrong_type(field, DataType::Text, other)),
+        }
+    }
+}
+
+impl FieldValue for i64 {
+    fn to_value(&self, _language: Option<Id>) -> Option<Value<'static>> {
+        Some(Value::Int64 { value: *self, unit: None })
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        match required(value, field, property)? {
+            Value::Int64 { value, .. } => Ok(*value),
+            other => Err(wrong_type(field, DataType::Int64, other)),
+        }
+    }
+}
+
+impl FieldValue for f64 {
+    fn to_value(&self, _language: Option<Id>) -> Option<Value<'static>> {
+        Some(Value::Float64 { value: *self, unit: None })
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        match required(value, field, property)? {
+            Value::Float64 { value, .. } => Ok(*value),
+            other => Err(wrong_type(field, DataType::Float64, other)),
+        }
+    }
+}
+
+impl FieldValue for bool {
+    fn to_value(&self, _language: Option<Id>) -> Option<Value<'static>> {
+        Some(Value::Bool(*self))
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        match required(value, field, property)? {
+            Value::Bool(b) => Ok(*b),
+            other => Err(wrong_type(field, DataType::Bool, other)),
+        }
+    }
+}
+
+impl FieldValue for Vec<u8> {
+    fn to_value(&self, _language: Option<Id>) -> Option<Value<'static>> {
+        Some(Value::Bytes(self.clone().into()))
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        match required(value, field, property)? {
+            Value::Bytes(b) => Ok(b.to_vec()),
+            other => Err(wrong_type(field, DataType::Bytes, other)),
+        }
+    }
+}
+
+/// Any value, unchecked. The field's `language` is ignored.
+impl FieldValue for Value<'static> {
+    fn to_value(&self, _language: Option<Id>) -> Option<Value<'static>> {
+        Some(self.clone())
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        Ok(required(value, field, property)?.clone().into_owned())
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn to_value(&self, language: Option<Id>) -> Option<Value<'static>> {
+        self.as_ref().and_then(|v| v.to_value(language))
+    }
+
+    fn from_value(value: Option<&Value<'_>>, field: &'static str, property: Id) -> Result<Self, MappingError> {
+        value.map(|v| T::from_value(Some(v), field, property)).transpose()
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec::Vec;
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const AGE: Id = [11u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    #[derive(Debug, PartialEq, GrcEntity)]
+    struct Person {
+        #[grc(id)]
+        id: Id,
+        #[grc(property = "a126ca530c8e48d5b88882c734c38935")]
+        name: String,
+        #[grc(property = "a126ca530c8e48d5b88882c734c38935", language = GERMAN)]
+        name_de: Option<String>,
+        #[grc(property = AGE)]
+        age: Option<i64>,
+        #[grc(skip)]
+        cached: u32,
+    }
+
+    #[test]
+    fn test_derive_roundtrip() {
+        let alice = Person { id: [3u8; 16], name: "Alice".into(), name_de: Some("Alicia".into()), age: None, cached: 7 };
+        let ops = alice.to_ops();
+        assert!(matches!(&ops[..], [Op::CreateEntity(ce)] if ce.values.len() == 2));
+
+        let mut edit = EditBuilder::new([1u8; 16]).build();
+        edit.ops = ops;
+        let bytes = crate::encode_edit(&edit).unwrap();
+        let decoded = crate::decode_edit(&bytes).unwrap();
+        let view = &decoded.entity_views()[0];
+        assert_eq!(Person::from_entity_view(view).unwrap(), Person { cached: 0, ..alice });
+    }
+
+    #[test]
+    fn test_derive_errors() {
+        let edit = EditBuilder::new([1u8; 16]).create_entity([3u8; 16], |e| e.int64(AGE, 30, None)).build();
+        let view = &edit.entity_views()[0];
+        assert_eq!(
+            Person::from_entity_view(view),
+            Err(MappingError::Missing { field: "name", property: crate::id_from_hex("a126ca530c8e48d5b88882c734c38935") })
+        );
+
+        let edit = EditBuilder::new([1u8; 16]).create_entity([3u8; 16], |e| e.text(AGE, "thirty", None)).build();
+        assert_eq!(
+            Option::<i64>::from_value(edit.entity_views()[0].value(&AGE), "age", AGE),
+            Err(MappingError::WrongType { field: "age", expected: DataType::Int64, found: DataType::Text })
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Reference merge semantics for concurrent edits.
+//!
+//! Within a space, the governance log totally orders edits and replaying it
+//! is enough (see [`Graph::apply_edit`]). Pluralistic setups also need to
+//! combine edits that were made concurrently, e.g. two forks of a space or
+//! proposals accepted in different spaces. This module defines that merge:
+//!
+//! - Edits are ordered by [`EditOrder`]: `(created_at, edit ID)`. Within an
+//!   edit, ops keep their order.
+//! - [`MergeStrategy::LastWriterWins`] replays the edits in that order; the
+//!   last write to each (entity, property, language) slot wins.
+//! - [`MergeStrategy::MultiValue`] treats the edits as concurrent and keeps
+//!   every distinct value written to a slot as a [`Conflict`], so an
+//!   application can present or resolve them. The graph holds the LWW winner
+//!   in the meantime.
+//!
+//! A [`Merge`] can be turned into a resolution [`Edit`] that, appended after
+//! the merged edits in any order, sets each conflicting slot to its chosen
+//! value, so replicas that applied the edits in different orders converge.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+
+use crate::collections::FxHashMap;
+use crate::model::{
+    Edit, Id, Op, PropertyValue, UnsetLanguage, UnsetValue, UpdateEntity, Value,
+};
+use crate::prelude::*;
+use crate::state::{Graph, ValueKey};
+
+/// Merge order of an edit: by `created_at`, then by edit ID bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EditOrder {
+    pub created_at: i64,
+    pub edit_id: Id,
+}
+
+impl EditOrder {
+    /// Returns the merge order of an edit.
+    pub fn of(edit: &Edit<'_>) -> Self {
+        Self { created_at: edit.created_at, edit_id: edit.id }
+    }
+}
+
+/// How concurrent writes to the same value slot are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// The write from the edit with the highest [`EditOrder`] wins.
+    #[default]
+    LastWriterWins,
+    /// Every distinct concurrent write is kept as a [`Conflict`].
+    MultiValue,
+}
+
+/// One edit's final write to a slot. `value` is `None` for an unset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub order: EditOrder,
+    pub value: Option<Value<'static>>,
+}
+
+/// Concurrent writes of different values to the same slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub entity: Id,
+    pub key: ValueKey,
+    /// Distinct candidates in ascending [`EditOrder`].
+    pub candidates: Vec<Candidate>,
+}
+
+impl Conflict {
+    /// Returns the last-writer-wins candidate.
+    pub fn winner(&self) -> Option<&Candidate> {
+        self.candidates.last()
+    }
+}
+
+/// Result of merging edits.
+#[derive(Debug, Clone, Default)]
+pub struct Merge {
+    /// Merged state, with the LWW winner in every slot.
+    pub graph: Graph,
+    /// Slots with concurrent writes (empty for [`MergeStrategy::LastWriterWins`]).
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Builds an edit that sets every conflicting slot to the value returned
+    /// by `choose` (`None` unsets the slot).
+    pub fn resolution_edit<F>(&self, id: Id, created_at: i64, mut choose: F) -> Edit<'static>
+    where
+        F: FnMut(&Conflict) -> Option<Value<'static>>,
+    {
+        let mut updates: BTreeMap<Id, UpdateEntity<'static>> = BTreeMap::new();
+        for conflict in &self.conflicts {
+            let update = updates.entry(conflict.entity).or_insert_with(|| UpdateEntity::new(conflict.entity));
+            match choose(conflict) {
+                Some(value) => update.set_properties.push(PropertyValue { property: conflict.key.property, value }),
+                None => update.unset_values.push(UnsetValue {
+                    property: conflict.key.property,
+                    language: unset_language(conflict),
+                }),
+            }
+        }
+        Edit {
+            id,
+            name: Cow::Borrowed(""),
+            authors: Vec::new(),
+            parents: Vec::new(),
+            created_at,
+            ops: updates.into_values().map(Op::UpdateEntity).collect(),
+        }
+    }
+
+    /// Builds a resolution edit that pins every conflicting slot to its
+    /// last-writer-wins value.
+    pub fn lww_resolution_edit(&self, id: Id, created_at: i64) -> Edit<'static> {
+        self.resolution_edit(id, created_at, |conflict| conflict.winner().and_then(|c| c.value.clone()))
+    }
+}
+
+/// TEXT slots unset one language; other properties have a single slot.
+fn unset_language(conflict: &Conflict) -> UnsetLanguage {
+    let is_text = conflict
+        .candidates
+        .iter()
+        .any(|c| matches!(c.value, Some(Value::Text { .. })));
+    match conflict.key.language {
+        Some(language) => UnsetLanguage::Specific(language),
+        None if is_text => UnsetLanguage::English,
+        None => UnsetLanguage::All,
+    }
+}
+
+/// Merges edits into a graph under the given strategy.
+///
+/// The edits are sorted by [`EditOrder`], so the result does not depend on
+/// the order they are passed in.
+pub fn merge(edits: &[Edit<'_>], strategy: MergeStrategy) -> Merge {
+    let mut sorted: Vec<&Edit<'_>> = edits.iter().collect();
+    sorted.sort_by_key(|edit| EditOrder::of(edit));
+
+    let mut graph = Graph::new();
+    let mut writes: BTreeMap<(Id, ValueKey), Vec<Candidate>> = BTreeMap::new();
+    for edit in sorted {
+        let order = EditOrder::of(edit);
+        let mut edit_writes: FxHashMap<(Id, ValueKey), Option<Value<'static>>> = FxHashMap::default();
+        for op in &edit.ops {
+            if strategy == MergeStrategy::MultiValue {
+                record_writes(&graph, op, &mut edit_writes);
+            }
+            graph.apply_op(op);
+        }
+        for (slot, value) in edit_writes {
+            // Writes to entities the op could not touch are not candidates
+            if graph.entity(&slot.0).is_none() {
+                continue;
+            }
+            let candidates = writes.entry(slot).or_default();
+            candidates.retain(|c| c.value != value);
+            candidates.push(Candidate { order, value });
+        }
+    }
+
+    let conflicts = writes
+        .into_iter()
+        .filter(|((entity, _), candidates)| candidates.len() > 1 && graph.entity(entity).is_some())
+        .map(|((entity, key), candidates)| Conflict { entity, key, candidates })
+        .collect();
+    Merge { graph, conflicts }
+}
+
+/// Records the slot writes an op makes, before it is applied to `graph`.
+fn record_writes(
+    graph: &Graph,
+    op: &Op<'_>,
+    writes: &mut FxHashMap<(Id, ValueKey), Option<Value<'static>>>,
+) {
+    let (id, unset, set) = match op {
+        Op::CreateEntity(ce) => (ce.id, &[][..], &ce.values[..]),
+        Op::UpdateEntity(ue) => (ue.id, &ue.unset_values[..], &ue.set_properties[..]),
+        _ => return,
+    };
+    for uv in unset {
+        match uv.language {
+            UnsetLanguage::All => {
+                let keys = graph.entity(&id).into_iter().flat_map(|e| e.values_of(uv.property).map(|(k, _)| *k));
+                for key in keys {
+                    writes.insert((id, key), None);
+                }
+            }
+            UnsetLanguage::English => {
+                writes.insert((id, ValueKey::new(uv.property)), None);
+            }
+            UnsetLanguage::Specific(language) => {
+                writes.insert((id, ValueKey::with_language(uv.property, language)), None);
+            }
+        }
+    }
+    for pv in set {
+        writes.insert((id, ValueKey::of(pv.property, &pv.value)), Some(pv.value.clone().into_owned()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const NAME: Id = [10u8; 16];
+    const AGE: Id = [11u8; 16];
+    const ALICE: Id = [3u8; 16];
+
+    fn edit(id: u8, created_at: i64, age: i64) -> Edit<'static> {
+        EditBuilder::new([id; 16])
+            .created_at(created_at)
+            .create_entity(ALICE, |e| e.int64(AGE, age, None))
+            .build()
+    }
+
+    fn age(graph: &Graph) -> Option<&Value<'static>> {
+        graph.value(&ALICE, &ValueKey::new(AGE))
+    }
+
+    #[test]
+    fn test_lww_order_independent() {
+        let edits = [edit(1, 200, 31), edit(2, 100, 30), edit(3, 200, 32)];
+        let merged = merge(&edits, MergeStrategy::LastWriterWins);
+        // Same created_at: the higher edit ID wins
+        assert_eq!(age(&merged.graph), Some(&Value::Int64 { value: 32, unit: None }));
+        assert!(merged.conflicts.is_empty());
+
+        let reversed = [edits[2].clone(), edits[1].clone(), edits[0].clone()];
+        assert_eq!(merge(&reversed, MergeStrategy::LastWriterWins).graph, merged.graph);
+    }
+
+    #[test]
+    fn test_multi_value_conflicts() {
+        let name = EditBuilder::new([4u8; 16])
+            .created_at(50)
+            .create_entity(ALICE, |e| e.text(NAME, "Alice", None))
+            .build();
+        let edits = [edit(1, 200, 31), edit(2, 100, 30), edit(3, 300, 31), name];
+        let merged = merge(&edits, MergeStrategy::MultiValue);
+
+        assert_eq!(merged.conflicts.len(), 1);
+        let conflict = &merged.conflicts[0];
+        assert_eq!(conflict.key, ValueKey::new(AGE));
+        // Equal writes collapse to the latest one
+        let values: Vec<_> = conflict.candidates.iter().map(|c| c.value.clone()).collect();
+        assert_eq!(values, vec![
+            Some(Value::Int64 { value: 30, unit: None }),
+            Some(Value::Int64 { value: 31, unit: None }),
+        ]);
+        assert_eq!(conflict.winner().unwrap().order.edit_id, [3u8; 16]);
+        assert_eq!(age(&merged.graph), Some(&Value::Int64 { value: 31, unit: None }));
+    }
+
+    #[test]
+    fn test_unset_is_a_candidate() {
+        let unset = EditBuilder::new([5u8; 16])
+            .created_at(300)
+            .update_entity(ALICE, |u| u.unset_all(AGE))
+            .build();
+        let edits = [edit(1, 100, 30), unset];
+        let merged = merge(&edits, MergeStrategy::MultiValue);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].winner().unwrap().value, None);
+        assert_eq!(age(&merged.graph), None);
+    }
+
+    #[test]
+    fn test_resolution_edit_converges() {
+        let edits = [edit(1, 100, 30), edit(2, 200, 31)];
+        let merged = merge(&edits, MergeStrategy::MultiValue);
+
+        // A replica that applied the edits in the opposite order
+        let mut replica = Graph::new();
+        replica.apply_edit(&edits[1]);
+        replica.apply_edit(&edits[0]);
+        assert_ne!(replica, merged.graph);
+
+        let resolution = merged.lww_resolution_edit([9u8; 16], 300);
+        assert_eq!(resolution.ops.len(), 1);
+        replica.apply_edit(&resolution);
+        assert_eq!(age(&replica), age(&merged.graph));
+
+        // A custom choice unsets the slot
+        let resolution = merged.resolution_edit([9u8; 16], 300, |_| None);
+        replica.apply_edit(&resolution);
+        assert_eq!(age(&replica), None);
+    }
+}
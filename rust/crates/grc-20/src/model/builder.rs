@@ -5,6 +5,8 @@
 //! # Example
 //!
 //! ```rust
+//! # // Genesis IDs need `std`
+//! # #[cfg(feature = "std")] {
 //! use grc_20::model::builder::EditBuilder;
 //! use grc_20::genesis::{properties, relation_types};
 //! use grc_20::Value;
@@ -18,15 +20,19 @@
 //!         .text(properties::description(), "A person", None)
 //!     )
 //!     .build();
+//! # }
 //! ```
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
+use crate::model::id::{data_type_entity_id, data_type_relation_type, types_relation_type, unique_relation_id};
+use crate::model::op::position_between;
 use crate::model::{
-    CreateEntity, CreateRelation, DeleteEntity, DeleteRelation,
+    CreateEntity, CreateRelation, DataType, DeleteEntity, DeleteRelation,
     Edit, Id, Op, PropertyValue, RestoreEntity, RestoreRelation, UnsetRelationField,
     UnsetLanguage, UnsetValue, UpdateEntity, UpdateRelation, Value,
 };
+use crate::prelude::*;
 
 /// Builder for constructing an Edit with operations.
 #[derive(Debug, Clone)]
@@ -34,6 +40,7 @@ pub struct EditBuilder<'a> {
     id: Id,
     name: Cow<'a, str>,
     authors: Vec<Id>,
+    parents: Vec<Id>,
     created_at: i64,
     ops: Vec<Op<'a>>,
 }
@@ -45,6 +52,7 @@ impl<'a> EditBuilder<'a> {
             id,
             name: Cow::Borrowed(""),
             authors: Vec::new(),
+            parents: Vec::new(),
             created_at: 0,
             ops: Vec::new(),
         }
@@ -68,6 +76,18 @@ impl<'a> EditBuilder<'a> {
         self
     }
 
+    /// Adds a parent edit.
+    pub fn parent(mut self, edit_id: Id) -> Self {
+        self.parents.push(edit_id);
+        self
+    }
+
+    /// Adds multiple parent edits at once.
+    pub fn parents(mut self, edit_ids: impl IntoIterator<Item = Id>) -> Self {
+        self.parents.extend(edit_ids);
+        self
+    }
+
     /// Sets the creation timestamp (microseconds since Unix epoch).
     pub fn created_at(mut self, timestamp: i64) -> Self {
         self.created_at = timestamp;
@@ -75,6 +95,7 @@ impl<'a> EditBuilder<'a> {
     }
 
     /// Sets the creation timestamp to now.
+    #[cfg(feature = "std")]
     pub fn created_now(mut self) -> Self {
         use std::time::{SystemTime, UNIX_EPOCH};
         let micros = SystemTime::now()
@@ -113,6 +134,38 @@ impl<'a> EditBuilder<'a> {
         self
     }
 
+    /// Declares a property: a CreateEntity for the property (built like
+    /// [`create_entity`](Self::create_entity)) followed by a `DataType`
+    /// relation to its data type entity (spec Section 7.3).
+    ///
+    /// Validation type-checks the property for the rest of the edit, and
+    /// [`schema::extract`](crate::schema::extract) registers it.
+    pub fn create_property<F>(self, id: Id, data_type: DataType, f: F) -> Self
+    where
+        F: FnOnce(EntityBuilder<'a>) -> EntityBuilder<'a>,
+    {
+        let to = data_type_entity_id(data_type);
+        let relation_type = data_type_relation_type();
+        let relation = unique_relation_id(&id, &to, &relation_type);
+        self.create_entity(id, f).create_relation_simple(relation, id, to, relation_type)
+    }
+
+    /// Creates an entity of type `type_id`: a CreateEntity (built like
+    /// [`create_entity`](Self::create_entity)) followed by its `Types`
+    /// relation, whose ID is [`unique_relation_id`], so the same entity and
+    /// type always get the same relation.
+    ///
+    /// Call [`create_relation_simple`](Self::create_relation_simple) for
+    /// further types.
+    pub fn create_typed_entity<F>(self, id: Id, type_id: Id, f: F) -> Self
+    where
+        F: FnOnce(EntityBuilder<'a>) -> EntityBuilder<'a>,
+    {
+        let relation_type = types_relation_type();
+        let relation = unique_relation_id(&id, &type_id, &relation_type);
+        self.create_entity(id, f).create_relation_simple(relation, id, type_id, relation_type)
+    }
+
     /// Adds an UpdateEntity operation using a builder function.
     pub fn update_entity<F>(mut self, id: Id, f: F) -> Self
     where
@@ -188,16 +241,7 @@ impl<'a> EditBuilder<'a> {
         F: FnOnce(UpdateRelationBuilder<'a>) -> UpdateRelationBuilder<'a>,
     {
         let builder = f(UpdateRelationBuilder::new(id));
-        self.ops.push(Op::UpdateRelation(UpdateRelation {
-            id: builder.id,
-            from_space: builder.from_space,
-            from_version: builder.from_version,
-            to_space: builder.to_space,
-            to_version: builder.to_version,
-            position: builder.position,
-            unset: builder.unset,
-            context: None,
-        }));
+        self.ops.push(Op::UpdateRelation(builder.build()));
         self
     }
 
@@ -254,8 +298,9 @@ impl<'a> EditBuilder<'a> {
             id: self.id,
             name: self.name,
             authors: self.authors,
+            parents: self.parents,
             created_at: self.created_at,
-                        ops: self.ops,
+            ops: self.ops,
         }
     }
 
@@ -283,6 +328,37 @@ impl<'a> EntityBuilder<'a> {
         self
     }
 
+    /// Adds property values, replacing earlier values in the same slot
+    /// (property, and language for TEXT) rather than duplicating them.
+    pub fn values_from(mut self, values: impl IntoIterator<Item = PropertyValue<'a>>) -> Self {
+        for pv in values {
+            let language = |v: &Value<'a>| match v {
+                Value::Text { language, .. } => *language,
+                _ => None,
+            };
+            let slot = |v: &PropertyValue<'a>| v.property == pv.property && language(&v.value) == language(&pv.value);
+            match self.values.iter_mut().find(|v| slot(v)) {
+                Some(existing) => *existing = pv,
+                None => self.values.push(pv),
+            }
+        }
+        self
+    }
+
+    /// Adds TEXT values of one property, e.g. the translations of a name.
+    /// Like [`values_from`](Self::values_from), a language seen twice keeps
+    /// the last text.
+    pub fn texts<S: Into<Cow<'a, str>>>(
+        self,
+        property: Id,
+        texts: impl IntoIterator<Item = (S, Option<Id>)>,
+    ) -> Self {
+        self.values_from(texts.into_iter().map(|(value, language)| PropertyValue {
+            property,
+            value: Value::Text { value: value.into(), language },
+        }))
+    }
+
     /// Adds a TEXT value.
     pub fn text(
         mut self,
@@ -742,6 +818,9 @@ impl<'a> RelationBuilder<'a> {
 }
 
 /// Builder for UpdateRelation operations.
+///
+/// Setting a field cancels an earlier unset of the same field and vice
+/// versa, so the built op never both sets and unsets a field.
 #[derive(Debug, Clone)]
 pub struct UpdateRelationBuilder<'a> {
     id: Id,
@@ -770,60 +849,115 @@ impl<'a> UpdateRelationBuilder<'a> {
     /// Sets the from_space pin.
     pub fn set_from_space(mut self, space_id: Id) -> Self {
         self.from_space = Some(space_id);
-        self
+        self.cancel_unset(UnsetRelationField::FromSpace)
     }
 
     /// Sets the from_version pin.
     pub fn set_from_version(mut self, version_id: Id) -> Self {
         self.from_version = Some(version_id);
-        self
+        self.cancel_unset(UnsetRelationField::FromVersion)
     }
 
     /// Sets the to_space pin.
     pub fn set_to_space(mut self, space_id: Id) -> Self {
         self.to_space = Some(space_id);
-        self
+        self.cancel_unset(UnsetRelationField::ToSpace)
     }
 
     /// Sets the to_version pin.
     pub fn set_to_version(mut self, version_id: Id) -> Self {
         self.to_version = Some(version_id);
-        self
+        self.cancel_unset(UnsetRelationField::ToVersion)
     }
 
     /// Sets the position for ordering.
     pub fn set_position(mut self, pos: impl Into<Cow<'a, str>>) -> Self {
         self.position = Some(pos.into());
-        self
+        self.cancel_unset(UnsetRelationField::Position)
+    }
+
+    /// Moves the relation directly before the sibling at position `next`.
+    ///
+    /// Leaves the position unchanged if `next` is invalid or nothing sorts
+    /// before it; use [`position_between`] to handle that case explicitly.
+    pub fn move_before(self, next: &str) -> Self {
+        self.move_to(position_between(None, Some(next)))
+    }
+
+    /// Moves the relation directly after the sibling at position `prev`.
+    ///
+    /// Leaves the position unchanged if `prev` is invalid.
+    pub fn move_after(self, prev: &str) -> Self {
+        self.move_to(position_between(Some(prev), None))
+    }
+
+    /// Moves the relation between the siblings at positions `prev` and `next`.
+    ///
+    /// Leaves the position unchanged if no position fits between them.
+    pub fn move_between(self, prev: &str, next: &str) -> Self {
+        self.move_to(position_between(Some(prev), Some(next)))
+    }
+
+    fn move_to(self, position: Result<String, &'static str>) -> Self {
+        match position {
+            Ok(pos) => self.set_position(pos),
+            Err(_) => self,
+        }
     }
 
     /// Unsets the from_space pin.
     pub fn unset_from_space(mut self) -> Self {
-        self.unset.push(UnsetRelationField::FromSpace);
-        self
+        self.from_space = None;
+        self.push_unset(UnsetRelationField::FromSpace)
     }
 
     /// Unsets the from_version pin.
     pub fn unset_from_version(mut self) -> Self {
-        self.unset.push(UnsetRelationField::FromVersion);
-        self
+        self.from_version = None;
+        self.push_unset(UnsetRelationField::FromVersion)
     }
 
     /// Unsets the to_space pin.
     pub fn unset_to_space(mut self) -> Self {
-        self.unset.push(UnsetRelationField::ToSpace);
-        self
+        self.to_space = None;
+        self.push_unset(UnsetRelationField::ToSpace)
     }
 
     /// Unsets the to_version pin.
     pub fn unset_to_version(mut self) -> Self {
-        self.unset.push(UnsetRelationField::ToVersion);
-        self
+        self.to_version = None;
+        self.push_unset(UnsetRelationField::ToVersion)
     }
 
     /// Unsets the position.
     pub fn unset_position(mut self) -> Self {
-        self.unset.push(UnsetRelationField::Position);
+        self.position = None;
+        self.push_unset(UnsetRelationField::Position)
+    }
+
+    /// Builds the UpdateRelation.
+    pub fn build(self) -> UpdateRelation<'a> {
+        UpdateRelation {
+            id: self.id,
+            from_space: self.from_space,
+            from_version: self.from_version,
+            to_space: self.to_space,
+            to_version: self.to_version,
+            position: self.position,
+            unset: self.unset,
+            context: None,
+        }
+    }
+
+    fn push_unset(mut self, field: UnsetRelationField) -> Self {
+        if !self.unset.contains(&field) {
+            self.unset.push(field);
+        }
+        self
+    }
+
+    fn cancel_unset(mut self, field: UnsetRelationField) -> Self {
+        self.unset.retain(|&f| f != field);
         self
     }
 }
@@ -831,6 +965,7 @@ impl<'a> UpdateRelationBuilder<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::DisplayId;
 
     #[test]
     fn test_edit_builder_basic() {
@@ -963,4 +1098,95 @@ mod tests {
             _ => panic!("Expected CreateEntity"),
         }
     }
+
+    #[test]
+    fn test_update_relation_builder() {
+        let edit = EditBuilder::new([1u8; 16])
+            .update_relation([2u8; 16], |r| r.set_to_version([3u8; 16]).unset_from_space())
+            .build();
+
+        match &edit.ops[0] {
+            Op::UpdateRelation(ur) => {
+                assert_eq!(ur.to_version, Some([3u8; 16]));
+                assert_eq!(ur.unset, vec![UnsetRelationField::FromSpace]);
+                assert!(ur.position.is_none());
+            }
+            _ => panic!("Expected UpdateRelation"),
+        }
+    }
+
+    #[test]
+    fn test_update_relation_builder_set_unset_consistent() {
+        // The last call wins, and no field ends up both set and unset
+        let ur = UpdateRelationBuilder::new([2u8; 16])
+            .set_position("a")
+            .unset_position()
+            .unset_to_version()
+            .unset_to_version()
+            .set_to_version([3u8; 16])
+            .build();
+        assert!(ur.position.is_none());
+        assert_eq!(ur.to_version, Some([3u8; 16]));
+        assert_eq!(ur.unset, vec![UnsetRelationField::Position]);
+    }
+
+    #[test]
+    fn test_update_relation_builder_move() {
+        let ur = UpdateRelationBuilder::new([2u8; 16]).unset_position().move_before("a").build();
+        let pos = ur.position.unwrap();
+        assert!(pos.as_ref() < "a");
+        assert!(ur.unset.is_empty());
+
+        let ur = UpdateRelationBuilder::new([2u8; 16]).move_between("a", "b").build();
+        assert_eq!(ur.position.as_deref(), Some("aV"));
+
+        let ur = UpdateRelationBuilder::new([2u8; 16]).move_after("z").build();
+        assert_eq!(ur.position.as_deref(), Some("zV"));
+
+        // Nothing sorts before "0", so the position is left unchanged
+        let ur = UpdateRelationBuilder::new([2u8; 16]).set_position("b").move_before("0").build();
+        assert_eq!(ur.position.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_create_typed_entity() {
+        let (city, place, name) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+        let edit = EditBuilder::new([0u8; 16]).create_typed_entity(city, place, |e| e.text(name, "Berlin", None)).build();
+        assert_eq!(edit.ops.len(), 2);
+        assert!(matches!(&edit.ops[0], Op::CreateEntity(ce) if ce.id == city && ce.values.len() == 1));
+        let Op::CreateRelation(cr) = &edit.ops[1] else {
+            panic!("Expected CreateRelation");
+        };
+        assert_eq!((cr.from, cr.to, cr.relation_type), (city, place, types_relation_type()));
+        assert_eq!(cr.id, unique_relation_id(&city, &place, &types_relation_type()));
+    }
+
+    #[test]
+    fn test_entity_builder_values_from() {
+        let (name, population, de, fr) = ([2u8; 16], [3u8; 16], [4u8; 16], [5u8; 16]);
+        let edit = EditBuilder::new([0u8; 16])
+            .create_entity([1u8; 16], |e| {
+                e.text(name, "Munich", None)
+                    .texts(name, [("München", Some(de)), ("Munich", Some(fr)), ("Muenchen", Some(de))])
+                    .values_from([
+                        PropertyValue { property: population, value: Value::Int64 { value: 1, unit: None } },
+                        PropertyValue { property: population, value: Value::Int64 { value: 2, unit: None } },
+                    ])
+                    .texts(name, [("Munich (city)", None)])
+            })
+            .build();
+        let Op::CreateEntity(ce) = &edit.ops[0] else {
+            panic!("Expected CreateEntity");
+        };
+        let texts: Vec<_> = ce.values.iter().map(|pv| (pv.property, pv.value.to_string())).collect();
+        assert_eq!(
+            texts,
+            vec![
+                (name, "\"Munich (city)\"".to_string()),
+                (name, format!("\"Muenchen\"@{}", DisplayId(de))),
+                (name, format!("\"Munich\"@{}", DisplayId(fr))),
+                (population, "2".to_string()),
+            ]
+        );
+    }
 }
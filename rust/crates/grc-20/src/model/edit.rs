@@ -2,14 +2,15 @@
 //!
 //! Edits are standalone patches containing a batch of ops with metadata.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
-use rustc_hash::FxHashMap;
+use crate::collections::FxHashMap;
+use crate::prelude::*;
 
 use crate::codec::primitives::Writer;
 use crate::error::EncodeError;
 use crate::limits::MAX_DICT_SIZE;
-use crate::model::{DataType, Id, Op};
+use crate::model::{CreateEntity, CreateRelation, DataType, Id, Op, OpKind};
 
 /// An edge in a context path (spec Section 4.5).
 ///
@@ -37,8 +38,9 @@ pub struct Context {
 
 /// A batch of operations with metadata (spec Section 4.1).
 ///
-/// Edits are standalone patches. They contain no parent references;
-/// ordering is provided by on-chain governance.
+/// Edits are standalone patches. They may list the edits they causally
+/// follow in `parents`, forming a DAG that sync protocols can use for
+/// ordering; the canonical order is still provided by on-chain governance.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Edit<'a> {
     /// The edit's unique identifier.
@@ -47,6 +49,8 @@ pub struct Edit<'a> {
     pub name: Cow<'a, str>,
     /// Author entity IDs.
     pub authors: Vec<Id>,
+    /// IDs of the edits this edit was made on top of (format version 2+).
+    pub parents: Vec<Id>,
     /// Creation timestamp (metadata only, not used for conflict resolution).
     pub created_at: i64,
     /// Operations in this edit.
@@ -60,6 +64,7 @@ impl<'a> Edit<'a> {
             id,
             name: Cow::Borrowed(""),
             authors: Vec::new(),
+            parents: Vec::new(),
             created_at: 0,
             ops: Vec::new(),
         }
@@ -71,10 +76,76 @@ impl<'a> Edit<'a> {
             id,
             name: name.into(),
             authors: Vec::new(),
+            parents: Vec::new(),
             created_at: 0,
             ops: Vec::new(),
         }
     }
+
+    /// Iterates over the ops of one type, e.g.
+    /// `edit.ops_of_type::<DeleteEntity>()`.
+    pub fn ops_of_type<T: OpKind<'a> + 'a>(&self) -> impl Iterator<Item = &T> + '_ {
+        self.ops.iter().filter_map(T::from_op)
+    }
+
+    /// Iterates over the CreateEntity ops.
+    pub fn entities(&self) -> impl Iterator<Item = &CreateEntity<'a>> + '_ {
+        self.ops_of_type()
+    }
+
+    /// Iterates over the CreateRelation ops.
+    pub fn relations(&self) -> impl Iterator<Item = &CreateRelation<'a>> + '_ {
+        self.ops_of_type()
+    }
+
+    /// Iterates over the IDs of the objects the ops target, in op order.
+    ///
+    /// A CreateRelation yields the relation ID followed by its reified
+    /// entity ID. IDs repeat if several ops target the same object.
+    pub fn touched_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.ops.iter().filter(|op| !matches!(op, Op::Unknown(_))).flat_map(|op| {
+            let reified = match op {
+                Op::CreateRelation(cr) => Some(cr.entity_id()),
+                _ => None,
+            };
+            core::iter::once(op.id()).chain(reified)
+        })
+    }
+
+    /// Groups the entity ops by entity ID, keeping op order within each
+    /// group.
+    ///
+    /// Covers CreateEntity, UpdateEntity, DeleteEntity, RestoreEntity, and
+    /// CreateValueRef (grouped under the entity holding the value).
+    /// Relation ops are grouped by [`Edit::group_by_relation`].
+    pub fn group_by_entity(&self) -> FxHashMap<Id, Vec<&Op<'a>>> {
+        self.group_by(|op| match op {
+            Op::CreateEntity(_) | Op::UpdateEntity(_) | Op::DeleteEntity(_) | Op::RestoreEntity(_) => Some(op.id()),
+            Op::CreateValueRef(cvr) => Some(cvr.entity),
+            _ => None,
+        })
+    }
+
+    /// Groups the relation ops by relation ID, keeping op order within each
+    /// group.
+    pub fn group_by_relation(&self) -> FxHashMap<Id, Vec<&Op<'a>>> {
+        self.group_by(|op| match op {
+            Op::CreateRelation(_) | Op::UpdateRelation(_) | Op::DeleteRelation(_) | Op::RestoreRelation(_) => {
+                Some(op.id())
+            }
+            _ => None,
+        })
+    }
+
+    fn group_by(&self, key: impl Fn(&Op<'a>) -> Option<Id>) -> FxHashMap<Id, Vec<&Op<'a>>> {
+        let mut groups: FxHashMap<Id, Vec<&Op<'a>>> = FxHashMap::default();
+        for op in &self.ops {
+            if let Some(id) = key(op) {
+                groups.entry(id).or_default().push(op);
+            }
+        }
+        groups
+    }
 }
 
 /// Wire-format dictionaries for encoding/decoding.
@@ -82,7 +153,7 @@ impl<'a> Edit<'a> {
 /// These dictionaries map between full IDs and compact indices
 /// within an edit.
 #[derive(Debug, Clone, Default)]
-pub struct WireDictionaries {
+pub struct WireDictionaries<'a> {
     /// Properties dictionary: (ID, DataType) pairs.
     pub properties: Vec<(Id, DataType)>,
     /// Relation type IDs.
@@ -97,9 +168,15 @@ pub struct WireDictionaries {
     pub context_ids: Vec<Id>,
     /// Decoded contexts array - used by op decoders to resolve context_ref to Context.
     pub contexts: Vec<Context>,
+    /// Interned TEXT values (extended layout only).
+    ///
+    /// When present, every TEXT value starts with a reference into this table
+    /// (0 = inline string follows, 1+ = texts[index-1]). Decoded tables
+    /// borrow from the input, so every reference decodes without a copy.
+    pub texts: Option<Vec<Cow<'a, str>>>,
 }
 
-impl WireDictionaries {
+impl WireDictionaries<'_> {
     /// Creates empty dictionaries.
     pub fn new() -> Self {
         Self::default()
@@ -153,6 +230,18 @@ impl WireDictionaries {
     pub fn get_context(&self, index: usize) -> Option<&Context> {
         self.contexts.get(index)
     }
+
+    /// Looks up an interned TEXT value by index.
+    ///
+    /// Index 0 means the string is stored inline, returns None.
+    /// Index 1+ maps to texts[index-1].
+    pub fn get_text(&self, index: usize) -> Option<&str> {
+        if index == 0 {
+            None
+        } else {
+            self.texts.as_ref()?.get(index - 1).map(Cow::as_ref)
+        }
+    }
 }
 
 /// Builder for constructing wire dictionaries during encoding.
@@ -174,6 +263,8 @@ pub struct DictionaryBuilder {
     context_id_indices: FxHashMap<Id, usize>,
     contexts: Vec<Context>,
     context_indices: FxHashMap<Context, usize>,
+    texts: Option<Vec<String>>,
+    text_indices: FxHashMap<String, usize>,
 }
 
 impl DictionaryBuilder {
@@ -216,9 +307,31 @@ impl DictionaryBuilder {
             context_id_indices: FxHashMap::with_capacity_and_hasher(ctx_id_cap, Default::default()),
             contexts: Vec::with_capacity(ctx_cap),
             context_indices: FxHashMap::with_capacity_and_hasher(ctx_cap, Default::default()),
+            texts: None,
+            text_indices: FxHashMap::default(),
         }
     }
 
+    /// Removes all entries, keeping the allocations for reuse.
+    pub fn clear(&mut self) {
+        self.properties.clear();
+        self.property_indices.clear();
+        self.relation_types.clear();
+        self.relation_type_indices.clear();
+        self.languages.clear();
+        self.language_indices.clear();
+        self.units.clear();
+        self.unit_indices.clear();
+        self.objects.clear();
+        self.object_indices.clear();
+        self.context_ids.clear();
+        self.context_id_indices.clear();
+        self.contexts.clear();
+        self.context_indices.clear();
+        self.texts = None;
+        self.text_indices.clear();
+    }
+
     /// Adds or gets the index for a property.
     pub fn add_property(&mut self, id: Id, data_type: DataType) -> usize {
         if let Some(&idx) = self.property_indices.get(&id) {
@@ -336,8 +449,33 @@ impl DictionaryBuilder {
         self.context_indices.get(context).copied()
     }
 
+    /// Enables TEXT interning with a fixed table of strings.
+    ///
+    /// The table must be set before any values are encoded, since it changes
+    /// the encoding of every TEXT value.
+    pub fn set_text_table(&mut self, texts: Vec<String>) {
+        self.text_indices = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| (text.clone(), i))
+            .collect();
+        self.texts = Some(texts);
+    }
+
+    /// Returns true if TEXT values are encoded as table references.
+    pub fn interns_text(&self) -> bool {
+        self.texts.is_some()
+    }
+
+    /// Gets the table reference for a TEXT value (for encoding).
+    ///
+    /// Returns 0 if the string is not interned, 1+ for table entries.
+    pub fn get_text_ref(&self, text: &str) -> usize {
+        self.text_indices.get(text).map_or(0, |idx| idx + 1)
+    }
+
     /// Builds the final wire dictionaries (consumes the builder).
-    pub fn build(self) -> WireDictionaries {
+    pub fn build(self) -> WireDictionaries<'static> {
         WireDictionaries {
             properties: self.properties,
             relation_types: self.relation_types,
@@ -346,12 +484,13 @@ impl DictionaryBuilder {
             objects: self.objects,
             context_ids: self.context_ids,
             contexts: self.contexts,
+            texts: self.texts.map(|texts| texts.into_iter().map(Cow::Owned).collect()),
         }
     }
 
     /// Returns a reference to wire dictionaries without consuming the builder.
     /// This allows continued use of the builder for encoding while having the dictionaries.
-    pub fn as_wire_dicts(&self) -> WireDictionaries {
+    pub fn as_wire_dicts(&self) -> WireDictionaries<'_> {
         WireDictionaries {
             properties: self.properties.clone(),
             relation_types: self.relation_types.clone(),
@@ -360,6 +499,7 @@ impl DictionaryBuilder {
             objects: self.objects.clone(),
             context_ids: self.context_ids.clone(),
             contexts: self.contexts.clone(),
+            texts: self.texts.as_ref().map(|texts| texts.iter().map(|text| Cow::Borrowed(text.as_str())).collect()),
         }
     }
 
@@ -449,6 +589,16 @@ impl DictionaryBuilder {
         }
     }
 
+    /// Writes the interned TEXT table to the writer, if interning is enabled.
+    pub fn write_text_table(&self, writer: &mut Writer) {
+        if let Some(texts) = &self.texts {
+            writer.write_varint(texts.len() as u64);
+            for text in texts {
+                writer.write_string(text);
+            }
+        }
+    }
+
     /// Validates dictionary and context sizes against codec limits.
     pub fn validate_limits(&self) -> Result<(), EncodeError> {
         let max = MAX_DICT_SIZE;
@@ -501,6 +651,14 @@ impl DictionaryBuilder {
                 max,
             });
         }
+        let text_count = self.texts.as_ref().map_or(0, Vec::len);
+        if text_count > max {
+            return Err(EncodeError::LengthExceedsLimit {
+                field: "texts",
+                len: text_count,
+                max,
+            });
+        }
         for ctx in &self.contexts {
             if ctx.edges.len() > max {
                 return Err(EncodeError::LengthExceedsLimit {
@@ -579,7 +737,7 @@ impl DictionaryBuilder {
         contexts.sort_by(|a, b| {
             // First compare by root_id
             match a.root_id.cmp(&b.root_id) {
-                std::cmp::Ordering::Equal => {
+                core::cmp::Ordering::Equal => {
                     // Then compare edges lexicographically
                     let a_edges: Vec<_> = a.edges.iter().map(|e| (e.type_id, e.to_entity_id)).collect();
                     let b_edges: Vec<_> = b.edges.iter().map(|e| (e.type_id, e.to_entity_id)).collect();
@@ -609,6 +767,8 @@ impl DictionaryBuilder {
             context_id_indices,
             contexts,
             context_indices,
+            texts: self.texts,
+            text_indices: self.text_indices,
         }
     }
 }
@@ -616,6 +776,7 @@ impl DictionaryBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::EditBuilder;
 
     #[test]
     fn test_edit_new() {
@@ -627,6 +788,49 @@ mod tests {
         assert!(edit.ops.is_empty());
     }
 
+    #[test]
+    fn test_op_iterators() {
+        use crate::model::{DeleteEntity, UpdateEntity};
+
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text([10u8; 16], "Alice", None))
+            .update_entity([3u8; 16], |u| u.set_int64([11u8; 16], 30, None))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .delete_entity([4u8; 16])
+            .build();
+
+        assert_eq!(edit.entities().map(|e| e.id).collect::<Vec<_>>(), vec![[3u8; 16]]);
+        assert_eq!(edit.relations().count(), 1);
+        assert_eq!(edit.ops_of_type::<UpdateEntity>().count(), 1);
+        assert_eq!(edit.ops_of_type::<DeleteEntity>().next().map(|d| d.id), Some([4u8; 16]));
+
+        let touched: Vec<Id> = edit.touched_ids().collect();
+        let reified = crate::model::relation_entity_id(&[7u8; 16]);
+        assert_eq!(touched, vec![[3u8; 16], [3u8; 16], [7u8; 16], reified, [4u8; 16]]);
+    }
+
+    #[test]
+    fn test_group_by_entity() {
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text([10u8; 16], "Alice", None))
+            .create_entity([4u8; 16], |e| e)
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .update_entity([3u8; 16], |u| u.set_int64([11u8; 16], 30, None))
+            .update_relation([7u8; 16], |r| r.set_position("a"))
+            .delete_entity([4u8; 16])
+            .build();
+
+        let entities = edit.group_by_entity();
+        assert_eq!(entities.len(), 2);
+        let alice: Vec<u8> = entities[&[3u8; 16]].iter().map(|op| op.op_type()).collect();
+        assert_eq!(alice, vec![1, 2]);
+        assert_eq!(entities[&[4u8; 16]].len(), 2);
+
+        let relations = edit.group_by_relation();
+        let ops: Vec<u8> = relations[&[7u8; 16]].iter().map(|op| op.op_type()).collect();
+        assert_eq!(ops, vec![5, 6]);
+    }
+
     #[test]
     fn test_dictionary_builder() {
         let mut builder = DictionaryBuilder::new();
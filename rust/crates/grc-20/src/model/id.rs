@@ -2,8 +2,13 @@
 //!
 //! All identifiers in GRC-20 are RFC 4122 UUIDs stored as 16 raw bytes.
 
+use core::fmt;
+
 use sha2::{Digest, Sha256};
 
+use crate::model::DataType;
+use crate::prelude::*;
+
 /// A 16-byte UUID identifier.
 ///
 /// This is the universal identifier type for entities, relations, properties,
@@ -34,6 +39,23 @@ pub fn derived_uuid(input: &[u8]) -> Id {
     id
 }
 
+/// Derives a name-based ID within a namespace, like a UUIDv5 but with
+/// SHA-256 and version 8:
+///
+/// ```text
+/// id = derived_uuid(namespace || name)
+/// ```
+///
+/// Importers can map source keys to stable IDs, e.g.
+/// `derive_in_namespace(COUNTRIES, "ISO3:DEU")`; the same key in another
+/// namespace gets a different ID.
+pub fn derive_in_namespace(namespace: Id, name: &str) -> Id {
+    let mut input = Vec::with_capacity(16 + name.len());
+    input.extend_from_slice(&namespace);
+    input.extend_from_slice(name.as_bytes());
+    derived_uuid(&input)
+}
+
 /// Computes the value identity hash for a non-TEXT value.
 ///
 /// ```text
@@ -100,6 +122,40 @@ pub fn relation_entity_id(relation_id: &Id) -> Id {
     derived_uuid(&input)
 }
 
+/// Domain separator prefix for data type entity derivation.
+const DATA_TYPE_PREFIX: &[u8] = b"grc20:genesis:datatype:";
+
+/// Derives the ID of a data type entity (spec Section 7.5).
+///
+/// ```text
+/// id = derived_uuid("grc20:genesis:datatype:" + lowercase(name))
+/// ```
+///
+/// Public as [`crate::genesis::data_type_id`]; defined here so that
+/// builders and validation work without `std`.
+pub(crate) fn data_type_entity_id(data_type: DataType) -> Id {
+    let name = data_type.name();
+    let mut input = Vec::with_capacity(DATA_TYPE_PREFIX.len() + name.len());
+    input.extend_from_slice(DATA_TYPE_PREFIX);
+    input.extend(name.bytes().map(|b| b.to_ascii_lowercase()));
+    derived_uuid(&input)
+}
+
+/// The Genesis `DataType` relation type, `genesis_id("DataType")`.
+pub(crate) fn data_type_relation_type() -> Id {
+    derived_uuid(b"grc20:genesis:DataType")
+}
+
+/// The Genesis `Types` relation type, `genesis_id("Types")`.
+pub(crate) fn types_relation_type() -> Id {
+    derived_uuid(b"grc20:genesis:Types")
+}
+
+/// The Genesis `Name` property, `genesis_id("Name")`.
+pub(crate) fn name_property() -> Id {
+    derived_uuid(b"grc20:genesis:Name")
+}
+
 /// Formats a UUID as non-hyphenated lowercase hex (recommended display format).
 pub fn format_id(id: &Id) -> String {
     let mut s = String::with_capacity(32);
@@ -109,6 +165,31 @@ pub fn format_id(id: &Id) -> String {
     s
 }
 
+/// Displays an ID as a hyphenated lowercase UUID (8-4-4-4-12).
+///
+/// `Id` is a plain byte array, so it cannot implement `Display` itself:
+/// `println!("{}", DisplayId(id))`. `Debug` prints the same form.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisplayId(pub Id);
+
+impl fmt::Display for DisplayId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DisplayId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 /// Parses a UUID from hex string (with or without hyphens).
 pub fn parse_id(s: &str) -> Option<Id> {
     // Remove hyphens if present
@@ -119,16 +200,326 @@ pub fn parse_id(s: &str) -> Option<Id> {
 
     let mut id = [0u8; 16];
     for (i, chunk) in hex.as_bytes().chunks(2).enumerate() {
-        let byte_str = std::str::from_utf8(chunk).ok()?;
+        let byte_str = core::str::from_utf8(chunk).ok()?;
         id[i] = u8::from_str_radix(byte_str, 16).ok()?;
     }
     Some(id)
 }
 
+/// The Bitcoin base58 alphabet, as used by the Geo web app.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The URL-safe base64 alphabet (RFC 4648 Section 5).
+const BASE64URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Formats an ID in base58 with the Bitcoin alphabet.
+///
+/// The 16 bytes are read as a big-endian number; each leading zero byte
+/// becomes a leading `1`, as in Bitcoin addresses. IDs are 22 characters
+/// at most.
+pub fn format_id_base58(id: &Id) -> String {
+    base58_encode(id)
+}
+
+/// Parses an ID formatted by [`format_id_base58`].
+///
+/// Returns `None` for characters outside the alphabet, values that do not
+/// fit in 16 bytes, or a number of leading `1`s that does not match the
+/// leading zero bytes.
+pub fn parse_id_base58(s: &str) -> Option<Id> {
+    // Longer input can't be an ID; reject it before the quadratic decode
+    if s.len() > 22 {
+        return None;
+    }
+    base58_decode(s)?.try_into().ok()
+}
+
+/// Encodes bytes in base58 with the Bitcoin alphabet.
+///
+/// Each leading zero byte becomes a leading `1`.
+pub(crate) fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // Base-58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &b in &bytes[zeros..] {
+        let mut carry = u32::from(b);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = String::with_capacity(zeros + digits.len());
+    out.extend(core::iter::repeat_n('1', zeros));
+    out.extend(digits.iter().rev().map(|&d| BASE58_ALPHABET[d as usize] as char));
+    out
+}
+
+/// Decodes a string written by [`base58_encode`], or `None` for characters
+/// outside the alphabet.
+pub(crate) fn base58_decode(s: &str) -> Option<Vec<u8>> {
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    // Bytes, least significant first
+    let mut bytes: Vec<u8> = Vec::with_capacity(s.len() * 733 / 1000 + 1);
+    for c in s.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for b in bytes.iter_mut() {
+            carry += u32::from(*b) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Some(out)
+}
+
+/// Formats an ID as unpadded URL-safe base64 (22 characters).
+pub fn format_id_base64url(id: &Id) -> String {
+    let mut s = String::with_capacity(22);
+    for chunk in id.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            s.push(BASE64URL_ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3F] as char);
+        }
+    }
+    s
+}
+
+/// Parses an ID formatted by [`format_id_base64url`].
+///
+/// Returns `None` unless the input is exactly 22 alphabet characters with
+/// zero padding bits.
+pub fn parse_id_base64url(s: &str) -> Option<Id> {
+    if s.len() != 22 {
+        return None;
+    }
+    let mut id = [0u8; 16];
+    let mut bytes = id.iter_mut();
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let sextet = BASE64URL_ALPHABET.iter().position(|&a| a == c)?;
+            n |= (sextet as u32) << (18 - 6 * i);
+        }
+        let len = chunk.len() - 1;
+        if len < 3 && n & (0xFFFFFF >> (8 * len)) != 0 {
+            return None;
+        }
+        for (i, byte) in bytes.by_ref().take(len).enumerate() {
+            *byte = (n >> (16 - 8 * i)) as u8;
+        }
+    }
+    Some(id)
+}
+
+/// Parses a UUID in a const context, for well-known ID constants.
+///
+/// Accepts 32 hex digits, optionally hyphenated as 8-4-4-4-12. Apart from
+/// the nil UUID, the ID must have the RFC 4122 variant and a version from 1
+/// to 8. Invalid input panics, which is a compile error in a `const` item;
+/// see [`grc20_ids!`](crate::grc20_ids).
+pub const fn id_from_hex(s: &str) -> Id {
+    let bytes = s.as_bytes();
+    let hyphenated = match bytes.len() {
+        32 => false,
+        36 => true,
+        _ => panic!("ID must be 32 hex digits or a hyphenated UUID"),
+    };
+
+    let mut id = [0u8; 16];
+    let mut i = 0;
+    let mut pos = 0;
+    while i < 16 {
+        if hyphenated && (pos == 8 || pos == 13 || pos == 18 || pos == 23) {
+            if bytes[pos] != b'-' {
+                panic!("UUID hyphens must be at 8-4-4-4-12");
+            }
+            pos += 1;
+        }
+        id[i] = (hex_digit(bytes[pos]) << 4) | hex_digit(bytes[pos + 1]);
+        pos += 2;
+        i += 1;
+    }
+
+    let mut nil = true;
+    i = 0;
+    while i < 16 {
+        nil &= id[i] == 0;
+        i += 1;
+    }
+    if !nil {
+        let version = id[6] >> 4;
+        if version < 1 || version > 8 {
+            panic!("UUID version must be 1 to 8");
+        }
+        if id[8] & 0xC0 != 0x80 {
+            panic!("UUID variant must be RFC 4122");
+        }
+    }
+    id
+}
+
+const fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => panic!("invalid hex digit in ID"),
+    }
+}
+
+/// Declares well-known ID constants, checked at compile time.
+///
+/// Each `NAME = "..."` becomes a `const NAME: Id` parsed with
+/// [`id_from_hex`](crate::model::id_from_hex), so a typo, a wrong length, or
+/// a non-UUID value fails the build:
+///
+/// ```
+/// grc_20::grc20_ids! {
+///     /// The Name property.
+///     pub NAME = "a126ca53-0c8e-48d5-b888-82c734c38935";
+///     pub(crate) CITY = "7f9562d4034d4385bf5cf02cdebba47a";
+///     LOCAL = "00000000000000000000000000000000";
+/// }
+/// assert_eq!(NAME[0], 0xa1);
+/// ```
+///
+/// ```compile_fail
+/// grc_20::grc20_ids! {
+///     // Version nibble 0: not a UUID
+///     BAD = "a1b2c3d4e5f6071829304050a1b2c3d4";
+/// }
+/// ```
+#[macro_export]
+macro_rules! grc20_ids {
+    ($($(#[$meta:meta])* $vis:vis $name:ident = $hex:literal;)*) => {
+        $(
+            $(#[$meta])*
+            $vis const $name: $crate::model::Id = $crate::model::id_from_hex($hex);
+        )*
+    };
+}
+
+// =============================================================================
+// TEST VECTORS
+// =============================================================================
+
+/// One ID derivation with fixed inputs and its output (see
+/// [`derivation_test_vectors`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationVector {
+    /// The derivation: `derived_uuid`, `derive_in_namespace`,
+    /// `unique_relation_id`, `relation_entity_id`, `text_value_id`, or
+    /// `value_id`.
+    pub function: &'static str,
+    /// The arguments in order, as `(name, bytes)`. An absent language is
+    /// empty.
+    pub inputs: Vec<(&'static str, Vec<u8>)>,
+    /// The derived ID.
+    pub output: Id,
+}
+
+/// Returns input/output pairs for every ID derivation in this module.
+///
+/// Implementations in other languages can check their derivations against
+/// these byte for byte; [`derivation_test_vectors_json`] writes them in a
+/// portable form.
+pub fn derivation_test_vectors() -> Vec<DerivationVector> {
+    const FROM: Id = id_from_hex("7f9562d4034d4385bf5cf02cdebba47a");
+    const TO: Id = id_from_hex("a126ca530c8e48d5b88882c734c38935");
+    const TYPE: Id = id_from_hex("550e8400e29b41d4a716446655440000");
+    const LANGUAGE: Id = id_from_hex("a1b2c3d4e5f64718a9304050a1b2c3d4");
+
+    let mut vectors = Vec::new();
+    for input in [&b""[..], b"hello world", "Zürich".as_bytes()] {
+        vectors.push(DerivationVector {
+            function: "derived_uuid",
+            inputs: vec![("input", input.to_vec())],
+            output: derived_uuid(input),
+        });
+    }
+    for (from, to) in [(FROM, TO), (TO, FROM)] {
+        vectors.push(DerivationVector {
+            function: "unique_relation_id",
+            inputs: vec![("from_id", from.to_vec()), ("to_id", to.to_vec()), ("type_id", TYPE.to_vec())],
+            output: unique_relation_id(&from, &to, &TYPE),
+        });
+    }
+    vectors.push(DerivationVector {
+        function: "derive_in_namespace",
+        inputs: vec![("namespace", TYPE.to_vec()), ("name", b"ISO3:DEU".to_vec())],
+        output: derive_in_namespace(TYPE, "ISO3:DEU"),
+    });
+    vectors.push(DerivationVector {
+        function: "relation_entity_id",
+        inputs: vec![("relation_id", FROM.to_vec())],
+        output: relation_entity_id(&FROM),
+    });
+    for language in [None, Some(&LANGUAGE)] {
+        vectors.push(DerivationVector {
+            function: "text_value_id",
+            inputs: vec![
+                ("property_id", TO.to_vec()),
+                ("text", "Zürich".as_bytes().to_vec()),
+                ("language_id", language.map_or_else(Vec::new, |l| l.to_vec())),
+            ],
+            output: text_value_id(&TO, "Zürich".as_bytes(), language),
+        });
+    }
+    let payload = 42i64.to_le_bytes();
+    vectors.push(DerivationVector {
+        function: "value_id",
+        inputs: vec![("property_id", TO.to_vec()), ("canonical_payload", payload.to_vec())],
+        output: value_id(&TO, &payload),
+    });
+    vectors
+}
+
+/// Formats [`derivation_test_vectors`] as a JSON array, with byte strings
+/// and IDs as lowercase hex:
+///
+/// ```text
+/// [{"function":"derived_uuid","inputs":{"input":""},"output":"e3b0c44298fc8c149afbf4c8996fb924"},...]
+/// ```
+pub fn derivation_test_vectors_json() -> String {
+    let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    let entries: Vec<String> = derivation_test_vectors()
+        .iter()
+        .map(|v| {
+            let inputs: Vec<String> =
+                v.inputs.iter().map(|(name, bytes)| format!("\"{}\":\"{}\"", name, hex(bytes))).collect();
+            format!(
+                "{{\"function\":\"{}\",\"inputs\":{{{}}},\"output\":\"{}\"}}",
+                v.function,
+                inputs.join(","),
+                format_id(&v.output)
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_display_id() {
+        let id = id_from_hex("7f9562d4-034d-4385-bf5c-f02cdebba47a");
+        assert_eq!(DisplayId(id).to_string(), "7f9562d4-034d-4385-bf5c-f02cdebba47a");
+        assert_eq!(format!("{:?}", DisplayId(id)), "7f9562d4-034d-4385-bf5c-f02cdebba47a");
+        assert_eq!(parse_id(&DisplayId(id).to_string()), Some(id));
+    }
+
     #[test]
     fn test_derived_uuid_version_and_variant() {
         let id = derived_uuid(b"test");
@@ -202,4 +593,91 @@ mod tests {
         assert_eq!(entity1[6] & 0xF0, 0x80);
         assert_eq!(entity1[8] & 0xC0, 0x80);
     }
+
+    #[test]
+    fn test_derive_in_namespace() {
+        let countries = derived_uuid(b"countries");
+        let deu = derive_in_namespace(countries, "ISO3:DEU");
+        assert_eq!(deu, derive_in_namespace(countries, "ISO3:DEU"));
+        assert_ne!(deu, derive_in_namespace(countries, "ISO3:FRA"));
+        assert_ne!(deu, derive_in_namespace(derived_uuid(b"regions"), "ISO3:DEU"));
+        assert_eq!((deu[6] & 0xF0, deu[8] & 0xC0), (0x80, 0x80));
+    }
+
+    crate::grc20_ids! {
+        GERMAN = "A1B2C3D4-E5F6-4718-A930-4050A1B2C3D4";
+    }
+
+    #[test]
+    fn test_id_from_hex() {
+        const ID: Id = id_from_hex("a1b2c3d4e5f647189930405060708090");
+        assert_eq!(Some(ID), parse_id("a1b2c3d4e5f647189930405060708090"));
+        assert_eq!(GERMAN, parse_id("a1b2c3d4e5f64718a9304050a1b2c3d4").unwrap());
+        assert_eq!(id_from_hex("00000000-0000-0000-0000-000000000000"), NIL_ID);
+        assert_eq!(id_from_hex(&format_id(&derived_uuid(b"x"))), derived_uuid(b"x"));
+    }
+
+    #[test]
+    fn test_id_from_hex_rejects_invalid() {
+        for bad in [
+            "a1b2c3d4e5f64718993040506070809",    // too short
+            "a1b2c3d4e5f6471899304050607080zz",   // not hex
+            "a1b2c3d4e5f6071899304050607080aa",   // version 0
+            "a1b2c3d4e5f6471829304050607080aa",   // NCS variant
+            "a1b2c3d4e-5f6-4718-9930-4050607080", // misplaced hyphens
+        ] {
+            assert!(std::panic::catch_unwind(|| id_from_hex(bad)).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_derivation_test_vectors() {
+        let vectors = derivation_test_vectors();
+        for v in &vectors {
+            let arg = |i: usize| -> Id { v.inputs[i].1.as_slice().try_into().unwrap() };
+            let output = match v.function {
+                "derived_uuid" => derived_uuid(&v.inputs[0].1),
+                "unique_relation_id" => unique_relation_id(&arg(0), &arg(1), &arg(2)),
+                "derive_in_namespace" => derive_in_namespace(arg(0), core::str::from_utf8(&v.inputs[1].1).unwrap()),
+                "relation_entity_id" => relation_entity_id(&arg(0)),
+                "text_value_id" => {
+                    let language = (!v.inputs[2].1.is_empty()).then(|| arg(2));
+                    text_value_id(&arg(0), &v.inputs[1].1, language.as_ref())
+                }
+                "value_id" => value_id(&arg(0), &v.inputs[1].1),
+                other => panic!("unknown function {other}"),
+            };
+            assert_eq!(output, v.output, "{}", v.function);
+        }
+
+        // Pinned, so a change to a derivation cannot go unnoticed
+        assert_eq!(format_id(&vectors[0].output), "e3b0c44298fc8c149afbf4c8996fb924");
+        let json = derivation_test_vectors_json();
+        assert!(json.starts_with(r#"[{"function":"derived_uuid","inputs":{"input":""},"output":"e3b0c44298fc8c149afbf4c8996fb924"}"#));
+        assert_eq!(json.matches("\"function\"").count(), vectors.len());
+    }
+
+    #[test]
+    fn test_base58_base64url() {
+        let id = id_from_hex("7f9562d4-034d-4385-bf5c-f02cdebba47a");
+        for id in [id, NIL_ID, [0xFF; 16], [0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]] {
+            assert_eq!(parse_id_base58(&format_id_base58(&id)), Some(id));
+            assert_eq!(format_id_base64url(&id).len(), 22);
+            assert_eq!(parse_id_base64url(&format_id_base64url(&id)), Some(id));
+        }
+        assert_eq!(format_id_base58(&NIL_ID), "1111111111111111");
+        assert_eq!(format_id_base58(&[0xFF; 16]), "YcVfxkQb6JRzqk5kF2tNLv");
+        assert_eq!(format_id_base64url(&[0xFF; 16]), "_____________________w");
+
+        assert_eq!(parse_id_base58("0OIl"), None);
+        assert_eq!(parse_id_base58("YcVfxkQb6JRzqk5kF2tNLw"), None); // 2^128
+        assert_eq!(parse_id_base58("11"), None);
+
+        for bytes in [vec![], vec![0], vec![0, 0, 1], vec![0xFF; 40], (0u8..=255).collect::<Vec<_>>()] {
+            assert_eq!(base58_decode(&base58_encode(&bytes)).unwrap(), bytes);
+        }
+        assert_eq!(base58_encode(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(parse_id_base64url("_____________________x"), None);
+        assert_eq!(parse_id_base64url("f5Vi1ANNQ4W_XPAs3ruke"), None);
+    }
 }
@@ -6,19 +6,27 @@
 //! - Operations (state changes)
 //! - Edits (batched operations)
 //! - Builders (ergonomic construction)
+//! - Entity views (denormalized records per entity)
 
 pub mod builder;
 pub mod edit;
 pub mod id;
 pub mod op;
 pub mod value;
+pub mod view;
 
-pub use builder::{EditBuilder, EntityBuilder, RelationBuilder, UpdateEntityBuilder};
+pub use builder::{
+    EditBuilder, EntityBuilder, RelationBuilder, UpdateEntityBuilder, UpdateRelationBuilder,
+};
 pub use edit::{Context, ContextEdge, DictionaryBuilder, Edit, WireDictionaries};
-pub use id::{derived_uuid, format_id, parse_id, relation_entity_id, text_value_id, value_id, Id, NIL_ID};
+pub use id::{
+    derive_in_namespace, derived_uuid, format_id, format_id_base58, format_id_base64url, id_from_hex, DisplayId,
+    parse_id, parse_id_base58, parse_id_base64url, relation_entity_id, text_value_id, value_id, Id, NIL_ID,
+};
 pub use op::{
-    validate_position, CreateEntity, CreateRelation, CreateValueRef, DeleteEntity, DeleteRelation,
-    Op, RestoreEntity, RestoreRelation, UnsetLanguage, UnsetRelationField, UnsetValue, UpdateEntity,
-    UpdateRelation,
+    position_between, validate_position, CreateEntity, CreateRelation, CreateValueRef,
+    DeleteEntity, DeleteRelation, Op, OpKind, RestoreEntity, RestoreRelation, UnknownOp, UnsetLanguage,
+    UnsetRelationField, UnsetValue, UpdateEntity, UpdateRelation,
 };
 pub use value::{DataType, DecimalMantissa, EmbeddingSubType, Property, PropertyValue, Value};
+pub use view::EntityView;
@@ -2,9 +2,12 @@
 //!
 //! All state changes in GRC-20 are expressed as operations (ops).
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use core::fmt;
 
 use crate::model::{Context, Id, PropertyValue};
+use crate::names::{NameResolver, Named};
+use crate::prelude::*;
 
 /// An atomic operation that modifies graph state (spec Section 3.1).
 #[derive(Debug, Clone, PartialEq)]
@@ -18,6 +21,9 @@ pub enum Op<'a> {
     DeleteRelation(DeleteRelation),
     RestoreRelation(RestoreRelation),
     CreateValueRef(CreateValueRef),
+    /// An op type this crate does not define, kept as raw payload bytes
+    /// (see [`crate::codec::registry`]).
+    Unknown(UnknownOp<'a>),
 }
 
 impl Op<'_> {
@@ -33,6 +39,167 @@ impl Op<'_> {
             Op::DeleteRelation(_) => 7,
             Op::RestoreRelation(_) => 8,
             Op::CreateValueRef(_) => 9,
+            Op::Unknown(op) => op.op_type,
+        }
+    }
+
+    /// Returns the ID of the object the op targets.
+    ///
+    /// Unknown ops have no target this crate can see and return
+    /// [`NIL_ID`](crate::model::id::NIL_ID).
+    pub fn id(&self) -> Id {
+        match self {
+            Op::CreateEntity(op) => op.id,
+            Op::UpdateEntity(op) => op.id,
+            Op::DeleteEntity(op) => op.id,
+            Op::RestoreEntity(op) => op.id,
+            Op::CreateRelation(op) => op.id,
+            Op::UpdateRelation(op) => op.id,
+            Op::DeleteRelation(op) => op.id,
+            Op::RestoreRelation(op) => op.id,
+            Op::CreateValueRef(op) => op.id,
+            Op::Unknown(_) => crate::model::id::NIL_ID,
+        }
+    }
+}
+
+/// One line per op with hyphenated UUIDs, e.g.
+/// `CreateRelation <id> <from> -[<type>]-> <to>`. Values use their
+/// `Display` form, so embeddings and long BYTES stay short.
+impl fmt::Display for Op<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_named(f, &())
+    }
+}
+
+impl Op<'_> {
+    /// Formats like `Display`, naming IDs with `names` (see
+    /// [`crate::names::Named`]).
+    pub(crate) fn fmt_named(&self, f: &mut fmt::Formatter<'_>, names: &dyn NameResolver) -> fmt::Result {
+        let id = |id: &Id| Named::new(*id, names);
+        match self {
+            Op::CreateEntity(op) => {
+                write!(f, "CreateEntity {}", id(&op.id))?;
+                write_values(f, &op.values, names)
+            }
+            Op::UpdateEntity(op) => {
+                write!(f, "UpdateEntity {}", id(&op.id))?;
+                if !op.set_properties.is_empty() {
+                    write_values(f, &op.set_properties, names)?;
+                }
+                if !op.unset_values.is_empty() {
+                    f.write_str(" unset {")?;
+                    for (i, uv) in op.unset_values.iter().enumerate() {
+                        let sep = if i == 0 { "" } else { ", " };
+                        write!(f, "{}{}", sep, id(&uv.property))?;
+                        match uv.language {
+                            UnsetLanguage::All => {}
+                            UnsetLanguage::English => f.write_str("@en")?,
+                            UnsetLanguage::Specific(l) => write!(f, "@{}", id(&l))?,
+                        }
+                    }
+                    f.write_str("}")?;
+                }
+                Ok(())
+            }
+            Op::DeleteEntity(op) => write!(f, "DeleteEntity {}", id(&op.id)),
+            Op::RestoreEntity(op) => write!(f, "RestoreEntity {}", id(&op.id)),
+            Op::CreateRelation(op) => {
+                write!(
+                    f,
+                    "CreateRelation {} {} -[{}]-> {}",
+                    id(&op.id),
+                    id(&op.from),
+                    id(&op.relation_type),
+                    id(&op.to)
+                )?;
+                match &op.position {
+                    Some(position) => write!(f, " position {}", position),
+                    None => Ok(()),
+                }
+            }
+            Op::UpdateRelation(op) => {
+                write!(f, "UpdateRelation {}", id(&op.id))?;
+                let pins = [
+                    ("from_space", op.from_space),
+                    ("from_version", op.from_version),
+                    ("to_space", op.to_space),
+                    ("to_version", op.to_version),
+                ];
+                for (name, pin) in pins {
+                    if let Some(pin) = pin {
+                        write!(f, " {} {}", name, id(&pin))?;
+                    }
+                }
+                if let Some(position) = &op.position {
+                    write!(f, " position {}", position)?;
+                }
+                if !op.unset.is_empty() {
+                    write!(f, " unset {:?}", op.unset)?;
+                }
+                Ok(())
+            }
+            Op::DeleteRelation(op) => write!(f, "DeleteRelation {}", id(&op.id)),
+            Op::RestoreRelation(op) => write!(f, "RestoreRelation {}", id(&op.id)),
+            Op::CreateValueRef(op) => {
+                write!(f, "CreateValueRef {} {}.{}", id(&op.id), id(&op.entity), id(&op.property))?;
+                match op.language {
+                    Some(l) => write!(f, "@{}", id(&l)),
+                    None => Ok(()),
+                }
+            }
+            Op::Unknown(op) => write!(f, "Unknown({}) {} bytes", op.op_type, op.bytes.len()),
+        }
+    }
+}
+
+fn write_values(f: &mut fmt::Formatter<'_>, values: &[PropertyValue<'_>], names: &dyn NameResolver) -> fmt::Result {
+    f.write_str(" {")?;
+    for (i, pv) in values.iter().enumerate() {
+        let sep = if i == 0 { "" } else { ", " };
+        write!(f, "{}{}: {}", sep, Named::new(pv.property, names), Named::new(&pv.value, names))?;
+    }
+    f.write_str("}")
+}
+
+/// An op payload type, for [`Edit::ops_of_type`](crate::model::Edit::ops_of_type).
+pub trait OpKind<'a> {
+    /// Returns the payload if `op` is of this type.
+    fn from_op<'o>(op: &'o Op<'a>) -> Option<&'o Self>;
+}
+
+macro_rules! impl_op_kind {
+    ($($variant:ident $(<$a:lifetime>)?),* $(,)?) => {
+        $(
+            impl<'a> OpKind<'a> for $variant $(<$a>)? {
+                fn from_op<'o>(op: &'o Op<'a>) -> Option<&'o Self> {
+                    match op {
+                        Op::$variant(inner) => Some(inner),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_op_kind!(
+    CreateEntity<'a>,
+    UpdateEntity<'a>,
+    DeleteEntity,
+    RestoreEntity,
+    CreateRelation<'a>,
+    UpdateRelation<'a>,
+    DeleteRelation,
+    RestoreRelation,
+    CreateValueRef,
+);
+
+impl<'a> OpKind<'a> for UnknownOp<'a> {
+    fn from_op<'o>(op: &'o Op<'a>) -> Option<&'o Self> {
+        match op {
+            Op::Unknown(inner) => Some(inner),
+            _ => None,
         }
     }
 }
@@ -313,6 +480,21 @@ pub struct CreateValueRef {
     pub space: Option<Id>,
 }
 
+/// An op outside the core op types, carried as opaque bytes.
+///
+/// Op types in [`EXTENSION_OP_TYPES`](crate::codec::op::EXTENSION_OP_TYPES)
+/// are length-prefixed on the wire, so the decoder can keep them without
+/// understanding the payload and the encoder writes them back unchanged.
+/// Core op types newer than this crate are kept the same way with
+/// [`DecodeOptions::preserve_unknown`](crate::codec::DecodeOptions::preserve_unknown).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownOp<'a> {
+    /// The wire op type.
+    pub op_type: u8,
+    /// The payload following the length prefix.
+    pub bytes: Cow<'a, [u8]>,
+}
+
 /// Validates a position string according to spec rules.
 ///
 /// Position strings must:
@@ -334,9 +516,80 @@ pub fn validate_position(pos: &str) -> Result<(), &'static str> {
     Ok(())
 }
 
+/// Position alphabet in ASCII order, so string order matches digit order.
+const POSITION_DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Returns a position string that sorts strictly between `before` and `after`.
+///
+/// `None` stands for the start or end of the list, so
+/// `position_between(None, Some(p))` places an item before `p` and
+/// `position_between(Some(p), None)` places it after. The result is the
+/// shortest such string and never ends in `'0'`, so there is always room to
+/// insert again on either side of it.
+///
+/// Fails if either input is not a valid position, if `before >= after`, or
+/// if no position of at most 64 characters fits (e.g. before `"0"`).
+pub fn position_between(before: Option<&str>, after: Option<&str>) -> Result<String, &'static str> {
+    fn digits(pos: &str) -> Result<Vec<u8>, &'static str> {
+        validate_position(pos)?;
+        // validate_position guarantees every byte is in the alphabet
+        Ok(pos.bytes().map(|b| POSITION_DIGITS.iter().position(|&d| d == b).unwrap() as u8).collect())
+    }
+
+    let a = before.map(digits).transpose()?.unwrap_or_default();
+    let b = after.map(digits).transpose()?;
+    if let (Some(before), Some(after)) = (before, after) {
+        if before >= after {
+            return Err("position bounds are not in ascending order");
+        }
+    }
+
+    let mid = midpoint(&a, b.as_deref()).ok_or("no position fits between the bounds")?;
+    if mid.len() > 64 {
+        return Err("position exceeds 64 characters");
+    }
+    Ok(mid.iter().map(|&d| POSITION_DIGITS[d as usize] as char).collect())
+}
+
+/// Midpoint of two digit strings read as base-62 fractions (`a` is padded
+/// with zeros; `b` of `None` is 1.0).
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> Option<Vec<u8>> {
+    let base = POSITION_DIGITS.len() as u8;
+    if let Some(b) = b {
+        let shared = b.iter().enumerate().take_while(|&(i, &d)| a.get(i).copied().unwrap_or(0) == d).count();
+        if shared == b.len() {
+            return None;
+        }
+        if shared > 0 {
+            let mut out = b[..shared].to_vec();
+            out.extend(midpoint(a.get(shared..).unwrap_or(&[]), Some(&b[shared..]))?);
+            return Some(out);
+        }
+    }
+
+    let digit_a = a.first().copied().unwrap_or(0);
+    let digit_b = b.map_or(base, |b| b[0]);
+    if digit_a >= digit_b {
+        return None;
+    }
+    if digit_b - digit_a > 1 {
+        return Some(vec![(digit_a + digit_b) / 2]);
+    }
+    // Adjacent digits: a prefix of `b` works if `b` continues past it
+    if let Some(b) = b {
+        if b.len() > 1 {
+            return Some(vec![b[0]]);
+        }
+    }
+    let mut out = vec![digit_a];
+    out.extend(midpoint(a.get(1..).unwrap_or(&[]), None)?);
+    Some(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::id::DisplayId;
 
     #[test]
     fn test_op_type_codes() {
@@ -353,6 +606,30 @@ mod tests {
         assert_eq!(Op::DeleteEntity(DeleteEntity { id: [0; 16], context: None }).op_type(), 3);
     }
 
+    #[test]
+    fn test_op_display() {
+        let id = |b: u8| [b; 16];
+        let uuid = |b: u8| DisplayId([b; 16]).to_string();
+        let op = Op::CreateEntity(CreateEntity {
+            id: id(1),
+            values: vec![PropertyValue::new(id(2), "Alice"), PropertyValue::new(id(3), 30i64)],
+            context: None,
+        });
+        assert_eq!(op.to_string(), format!("CreateEntity {} {{{}: \"Alice\", {}: 30}}", uuid(1), uuid(2), uuid(3)));
+
+        let mut update = UpdateEntity::new(id(1));
+        update.unset_values = vec![UnsetValue::all(id(2)), UnsetValue::english(id(3))];
+        assert_eq!(Op::UpdateEntity(update).to_string(), format!("UpdateEntity {} unset {{{}, {}@en}}", uuid(1), uuid(2), uuid(3)));
+
+        let mut update = UpdateRelation::new(id(4));
+        update.position = Some(Cow::Borrowed("a0"));
+        update.unset = vec![UnsetRelationField::ToSpace];
+        assert_eq!(Op::UpdateRelation(update).to_string(), format!("UpdateRelation {} position a0 unset [ToSpace]", uuid(4)));
+
+        let unknown = Op::Unknown(UnknownOp { op_type: 200, bytes: Cow::Borrowed(&[1, 2, 3]) });
+        assert_eq!(unknown.to_string(), "Unknown(200) 3 bytes");
+    }
+
     #[test]
     fn test_validate_position() {
         assert!(validate_position("abc123").is_ok());
@@ -451,4 +728,40 @@ mod tests {
         update3.unset.push(UnsetRelationField::Position);
         assert!(!update3.is_empty());
     }
+
+    #[test]
+    fn test_position_between() {
+        assert_eq!(position_between(None, None).unwrap(), "V");
+        assert_eq!(position_between(Some("a"), Some("c")).unwrap(), "b");
+        assert_eq!(position_between(Some("a"), Some("b")).unwrap(), "aV");
+        assert_eq!(position_between(Some("a"), Some("a05")).unwrap(), "a02");
+        assert_eq!(position_between(None, Some("a")).unwrap(), "I");
+        assert_eq!(position_between(Some("z"), None).unwrap(), "zV");
+        assert_eq!(position_between(None, Some("1")).unwrap(), "0V");
+
+        assert!(position_between(None, Some("0")).is_err());
+        assert!(position_between(Some("b"), Some("a")).is_err());
+        assert!(position_between(Some("a"), Some("a")).is_err());
+        assert!(position_between(Some("a"), Some("a0")).is_err());
+        assert!(position_between(Some("a-"), None).is_err());
+    }
+
+    #[test]
+    fn test_position_between_repeated_inserts() {
+        // Inserting repeatedly before the same item keeps the order strict
+        let mut positions = vec!["a".to_string()];
+        for _ in 0..50 {
+            let next = position_between(None, Some(&positions[0])).unwrap();
+            assert!(next < positions[0]);
+            positions.insert(0, next);
+        }
+        // ...and so does inserting between two neighbours
+        let (mut lo, hi) = ("a".to_string(), "b".to_string());
+        for _ in 0..50 {
+            let mid = position_between(Some(&lo), Some(&hi)).unwrap();
+            assert!(lo < mid && mid < hi);
+            assert!(validate_position(&mid).is_ok());
+            lo = mid;
+        }
+    }
 }
@@ -2,9 +2,14 @@
 //!
 //! Values are typed attribute instances on entities and relations.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use alloc::vec::Vec;
 
 use crate::model::Id;
+use crate::names::{write_id, NameResolver};
 use crate::util::{parse_date_rfc3339, parse_datetime_rfc3339, parse_time_rfc3339};
 
 /// Data types for property values (spec Section 2.4).
@@ -27,6 +32,43 @@ pub enum DataType {
 }
 
 impl DataType {
+    /// All data types, in wire order.
+    pub const ALL: [DataType; 13] = [
+        DataType::Bool,
+        DataType::Int64,
+        DataType::Float64,
+        DataType::Decimal,
+        DataType::Text,
+        DataType::Bytes,
+        DataType::Date,
+        DataType::Time,
+        DataType::Datetime,
+        DataType::Schedule,
+        DataType::Point,
+        DataType::Rect,
+        DataType::Embedding,
+    ];
+
+    /// Returns the name of the data type entity (spec Section 7.5), e.g.
+    /// `"Int64"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DataType::Bool => "Bool",
+            DataType::Int64 => "Int64",
+            DataType::Float64 => "Float64",
+            DataType::Decimal => "Decimal",
+            DataType::Text => "Text",
+            DataType::Bytes => "Bytes",
+            DataType::Date => "Date",
+            DataType::Time => "Time",
+            DataType::Datetime => "Datetime",
+            DataType::Schedule => "Schedule",
+            DataType::Point => "Point",
+            DataType::Rect => "Rect",
+            DataType::Embedding => "Embedding",
+        }
+    }
+
     /// Creates a DataType from its wire representation.
     pub fn from_u8(v: u8) -> Option<DataType> {
         match v {
@@ -115,7 +157,10 @@ impl DecimalMantissa<'_> {
 }
 
 /// A typed value that can be stored on an entity or relation.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// `Debug` matches the derived form but elides long BYTES and EMBEDDING
+/// payloads; `Display` is a short human-readable form for logs.
+#[derive(Clone, PartialEq)]
 pub enum Value<'a> {
     /// Boolean value.
     Bool(bool),
@@ -215,6 +260,39 @@ impl Value<'_> {
         }
     }
 
+    /// Converts this value into one that owns all of its data.
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Bool(b) => Value::Bool(b),
+            Value::Int64 { value, unit } => Value::Int64 { value, unit },
+            Value::Float64 { value, unit } => Value::Float64 { value, unit },
+            Value::Decimal { exponent, mantissa, unit } => Value::Decimal {
+                exponent,
+                mantissa: match mantissa {
+                    DecimalMantissa::I64(i) => DecimalMantissa::I64(i),
+                    DecimalMantissa::Big(b) => DecimalMantissa::Big(Cow::Owned(b.into_owned())),
+                },
+                unit,
+            },
+            Value::Text { value, language } => Value::Text {
+                value: Cow::Owned(value.into_owned()),
+                language,
+            },
+            Value::Bytes(b) => Value::Bytes(Cow::Owned(b.into_owned())),
+            Value::Date(s) => Value::Date(Cow::Owned(s.into_owned())),
+            Value::Time(s) => Value::Time(Cow::Owned(s.into_owned())),
+            Value::Datetime(s) => Value::Datetime(Cow::Owned(s.into_owned())),
+            Value::Schedule(s) => Value::Schedule(Cow::Owned(s.into_owned())),
+            Value::Point { lat, lon, alt } => Value::Point { lat, lon, alt },
+            Value::Rect { min_lat, min_lon, max_lat, max_lon } => Value::Rect { min_lat, min_lon, max_lat, max_lon },
+            Value::Embedding { sub_type, dims, data } => Value::Embedding {
+                sub_type,
+                dims,
+                data: Cow::Owned(data.into_owned()),
+            },
+        }
+    }
+
     /// Validates this value according to spec rules.
     ///
     /// Returns an error description if invalid, None if valid.
@@ -302,6 +380,234 @@ impl Value<'_> {
     }
 }
 
+/// Byte payloads longer than this are elided by `Value`'s `Debug` and
+/// `Display`.
+const SHOWN_BYTES: usize = 16;
+
+/// Debug-formats a byte slice like `[u8]`, eliding all but the first
+/// [`SHOWN_BYTES`] bytes.
+struct ElidedBytes<'b>(&'b [u8]);
+
+impl fmt::Debug for ElidedBytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.len() <= SHOWN_BYTES {
+            return self.0.fmt(f);
+        }
+        f.debug_list()
+            .entries(&self.0[..SHOWN_BYTES])
+            .entry(&format_args!("... {} more", self.0.len() - SHOWN_BYTES))
+            .finish()
+    }
+}
+
+impl fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            Value::Int64 { value, unit } => f.debug_struct("Int64").field("value", value).field("unit", unit).finish(),
+            Value::Float64 { value, unit } => {
+                f.debug_struct("Float64").field("value", value).field("unit", unit).finish()
+            }
+            Value::Decimal { exponent, mantissa, unit } => f
+                .debug_struct("Decimal")
+                .field("exponent", exponent)
+                .field("mantissa", mantissa)
+                .field("unit", unit)
+                .finish(),
+            Value::Text { value, language } => {
+                f.debug_struct("Text").field("value", value).field("language", language).finish()
+            }
+            Value::Bytes(b) => f.debug_tuple("Bytes").field(&ElidedBytes(b)).finish(),
+            Value::Date(s) => f.debug_tuple("Date").field(s).finish(),
+            Value::Time(s) => f.debug_tuple("Time").field(s).finish(),
+            Value::Datetime(s) => f.debug_tuple("Datetime").field(s).finish(),
+            Value::Schedule(s) => f.debug_tuple("Schedule").field(s).finish(),
+            Value::Point { lat, lon, alt } => {
+                f.debug_struct("Point").field("lat", lat).field("lon", lon).field("alt", alt).finish()
+            }
+            Value::Rect { min_lat, min_lon, max_lat, max_lon } => f
+                .debug_struct("Rect")
+                .field("min_lat", min_lat)
+                .field("min_lon", min_lon)
+                .field("max_lat", max_lat)
+                .field("max_lon", max_lon)
+                .finish(),
+            Value::Embedding { sub_type, dims, data } => f
+                .debug_struct("Embedding")
+                .field("sub_type", sub_type)
+                .field("dims", dims)
+                .field("data", &ElidedBytes(data))
+                .finish(),
+        }
+    }
+}
+
+/// Formats values for humans: `true`, `42`, `12.34`, `"Alice"@<language>`,
+/// `0x0102`, `(40.7, -74)`, `Float32[768]`. Units and languages print as
+/// hyphenated UUIDs; BYTES longer than 16 bytes are elided.
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_named(f, &())
+    }
+}
+
+impl Value<'_> {
+    /// Formats like `Display`, naming units and languages with `names`
+    /// (see [`crate::names::Named`]).
+    pub(crate) fn fmt_named(&self, f: &mut fmt::Formatter<'_>, names: &dyn NameResolver) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int64 { value, unit } => {
+                write!(f, "{}", value)?;
+                write_unit(f, unit, names)
+            }
+            Value::Float64 { value, unit } => {
+                write!(f, "{}", value)?;
+                write_unit(f, unit, names)
+            }
+            Value::Decimal { exponent, mantissa, unit } => {
+                match mantissa {
+                    DecimalMantissa::I64(m) => write_decimal(f, *m, *exponent)?,
+                    DecimalMantissa::Big(bytes) => {
+                        write_hex(f, bytes)?;
+                        write!(f, "e{}", exponent)?;
+                    }
+                }
+                write_unit(f, unit, names)
+            }
+            Value::Text { value, language } => {
+                write!(f, "{:?}", value)?;
+                match language {
+                    Some(l) => {
+                        f.write_str("@")?;
+                        write_id(f, l, names)
+                    }
+                    None => Ok(()),
+                }
+            }
+            Value::Bytes(b) => write_hex(f, b),
+            Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => f.write_str(s),
+            Value::Point { lat, lon, alt: None } => write!(f, "({}, {})", lat, lon),
+            Value::Point { lat, lon, alt: Some(alt) } => write!(f, "({}, {}, {})", lat, lon, alt),
+            Value::Rect { min_lat, min_lon, max_lat, max_lon } => {
+                write!(f, "[({}, {}), ({}, {})]", min_lat, min_lon, max_lat, max_lon)
+            }
+            Value::Embedding { sub_type, dims, .. } => write!(f, "{:?}[{}]", sub_type, dims),
+        }
+    }
+}
+
+fn write_unit(f: &mut fmt::Formatter<'_>, unit: &Option<Id>, names: &dyn NameResolver) -> fmt::Result {
+    match unit {
+        Some(u) => {
+            f.write_str(" ")?;
+            write_id(f, u, names)
+        }
+        None => Ok(()),
+    }
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    f.write_str("0x")?;
+    for b in bytes.iter().take(SHOWN_BYTES) {
+        write!(f, "{:02x}", b)?;
+    }
+    if bytes.len() > SHOWN_BYTES {
+        write!(f, "... ({} bytes)", bytes.len())?;
+    }
+    Ok(())
+}
+
+/// Writes `mantissa * 10^exponent` in plain decimal notation.
+fn write_decimal(f: &mut fmt::Formatter<'_>, mantissa: i64, exponent: i32) -> fmt::Result {
+    if exponent >= 0 {
+        write!(f, "{}", mantissa)?;
+        return (0..exponent).try_for_each(|_| f.write_str("0"));
+    }
+    let digits = format!("{}", mantissa.unsigned_abs());
+    let scale = exponent.unsigned_abs() as usize;
+    if mantissa < 0 {
+        f.write_str("-")?;
+    }
+    if digits.len() > scale {
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", int, frac)
+    } else {
+        f.write_str("0.")?;
+        (digits.len()..scale).try_for_each(|_| f.write_str("0"))?;
+        f.write_str(&digits)
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Text in a specific language (`"Alice".into()` has no language).
+    pub fn text_lang(value: impl Into<Cow<'a, str>>, language: Id) -> Self {
+        Value::Text { value: value.into(), language: Some(language) }
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Text { value: Cow::Borrowed(value), language: None }
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(value: String) -> Self {
+        Value::Text { value: Cow::Owned(value), language: None }
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Value<'a> {
+    fn from(value: Cow<'a, str>) -> Self {
+        Value::Text { value, language: None }
+    }
+}
+
+impl From<i64> for Value<'_> {
+    fn from(value: i64) -> Self {
+        Value::Int64 { value, unit: None }
+    }
+}
+
+impl From<f64> for Value<'_> {
+    fn from(value: f64) -> Self {
+        Value::Float64 { value, unit: None }
+    }
+}
+
+impl From<bool> for Value<'_> {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+/// A point from `(lat, lon)`.
+impl From<(f64, f64)> for Value<'_> {
+    fn from((lat, lon): (f64, f64)) -> Self {
+        Value::Point { lat, lon, alt: None }
+    }
+}
+
+/// A point from `(lat, lon, alt)`.
+impl From<(f64, f64, f64)> for Value<'_> {
+    fn from((lat, lon, alt): (f64, f64, f64)) -> Self {
+        Value::Point { lat, lon, alt: Some(alt) }
+    }
+}
+
+impl<'a> From<&'a [u8]> for Value<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        Value::Bytes(Cow::Borrowed(value))
+    }
+}
+
+impl From<Vec<u8>> for Value<'_> {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytes(Cow::Owned(value))
+    }
+}
+
 /// A property-value pair that can be attached to an object.
 #[derive(Debug, Clone, PartialEq)]
 pub struct PropertyValue<'a> {
@@ -311,6 +617,14 @@ pub struct PropertyValue<'a> {
     pub value: Value<'a>,
 }
 
+impl<'a> PropertyValue<'a> {
+    /// Creates a property value without checks (see `new_checked` for
+    /// schema-checked construction).
+    pub fn new(property: Id, value: impl Into<Value<'a>>) -> Self {
+        Self { property, value: value.into() }
+    }
+}
+
 /// A property definition in the schema.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Property {
@@ -394,4 +708,47 @@ mod tests {
         };
         assert!(valid.validate().is_none());
     }
+
+    #[test]
+    fn test_value_from() {
+        let name: Value = "Alice".into();
+        assert!(matches!(name, Value::Text { value: Cow::Borrowed("Alice"), language: None }));
+        assert_eq!(Value::from(String::from("Alice")), name);
+        assert_eq!(
+            Value::text_lang("Alicia", [20u8; 16]),
+            Value::Text { value: Cow::Borrowed("Alicia"), language: Some([20u8; 16]) }
+        );
+        assert_eq!(Value::from(42i64), Value::Int64 { value: 42, unit: None });
+        assert_eq!(Value::from(1.5), Value::Float64 { value: 1.5, unit: None });
+        assert_eq!(Value::from(true), Value::Bool(true));
+        assert_eq!(Value::from((40.7, -74.0)), Value::Point { lat: 40.7, lon: -74.0, alt: None });
+        assert_eq!(Value::from((40.7, -74.0, 10.0)), Value::Point { lat: 40.7, lon: -74.0, alt: Some(10.0) });
+        assert_eq!(Value::from(&[1u8, 2][..]), Value::Bytes(Cow::Owned(vec![1, 2])));
+    }
+
+    #[test]
+    fn test_value_display() {
+        let unit = [0x11u8; 16];
+        assert_eq!(Value::Int64 { value: 42, unit: None }.to_string(), "42");
+        assert_eq!(Value::Float64 { value: 1.5, unit: Some(unit) }.to_string(), "1.5 11111111-1111-1111-1111-111111111111");
+        let decimal = |mantissa, exponent| Value::Decimal { exponent, mantissa: DecimalMantissa::I64(mantissa), unit: None };
+        assert_eq!(decimal(1234, -2).to_string(), "12.34");
+        assert_eq!(decimal(-5, -3).to_string(), "-0.005");
+        assert_eq!(decimal(12, 2).to_string(), "1200");
+        assert_eq!(Value::text_lang("Alice", unit).to_string(), "\"Alice\"@11111111-1111-1111-1111-111111111111");
+        assert_eq!(Value::from((40.7, -74.0)).to_string(), "(40.7, -74)");
+        assert_eq!(Value::from(vec![0xab; 20]).to_string(), format!("0x{}... (20 bytes)", "ab".repeat(16)));
+        let embedding = Value::Embedding { sub_type: EmbeddingSubType::Float32, dims: 768, data: Cow::Owned(vec![0; 3072]) };
+        assert_eq!(embedding.to_string(), "Float32[768]");
+    }
+
+    #[test]
+    fn test_value_debug_elides_payloads() {
+        assert_eq!(format!("{:?}", Value::from(vec![1u8, 2])), "Bytes([1, 2])");
+        assert_eq!(format!("{:?}", Value::from(42i64)), "Int64 { value: 42, unit: None }");
+        let embedding = Value::Embedding { sub_type: EmbeddingSubType::Int8, dims: 768, data: Cow::Owned(vec![7; 768]) };
+        let debug = format!("{:?}", embedding);
+        assert!(debug.starts_with("Embedding { sub_type: Int8, dims: 768, data: [7, 7,"), "{}", debug);
+        assert!(debug.ends_with(", ... 752 more] }"), "{}", debug);
+    }
 }
@@ -0,0 +1,142 @@
+//! Denormalized per-entity views of an edit.
+
+use crate::collections::FxHashMap;
+use crate::model::{CreateRelation, Edit, Id, Op, PropertyValue, UnsetLanguage, Value};
+use crate::prelude::*;
+
+/// An entity's values and outgoing relations, as written by one edit.
+///
+/// Built by [`Edit::entity_views`]. Borrows from the edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityView<'e, 'a> {
+    pub id: Id,
+    /// Values after applying the edit's sets and unsets in op order, one
+    /// per (property, language).
+    pub values: Vec<&'e PropertyValue<'a>>,
+    /// CreateRelation ops whose `from` is this entity, in op order.
+    pub relations: Vec<&'e CreateRelation<'a>>,
+    /// True if the edit deletes the entity (and does not restore it).
+    pub deleted: bool,
+}
+
+impl<'e, 'a> EntityView<'e, 'a> {
+    fn new(id: Id) -> Self {
+        Self { id, values: Vec::new(), relations: Vec::new(), deleted: false }
+    }
+
+    /// Returns the value of a property (in any language for TEXT).
+    pub fn value(&self, property: &Id) -> Option<&'e Value<'a>> {
+        self.values.iter().find(|pv| pv.property == *property).map(|pv| &pv.value)
+    }
+
+    /// Returns the value of a property in exactly `language` (`None` is the
+    /// default language, and the only match for non-TEXT values).
+    pub fn value_in(&self, property: &Id, language: Option<Id>) -> Option<&'e Value<'a>> {
+        self.values
+            .iter()
+            .find(|pv| pv.property == *property && language_of(&pv.value) == language)
+            .map(|pv| &pv.value)
+    }
+
+    fn set(&mut self, pv: &'e PropertyValue<'a>) {
+        let language = language_of(&pv.value);
+        match self.values.iter_mut().find(|v| v.property == pv.property && language_of(&v.value) == language) {
+            Some(slot) => *slot = pv,
+            None => self.values.push(pv),
+        }
+    }
+
+    fn unset(&mut self, property: Id, language: UnsetLanguage) {
+        self.values.retain(|pv| {
+            pv.property != property
+                || match language {
+                    UnsetLanguage::All => false,
+                    UnsetLanguage::English => language_of(&pv.value).is_some(),
+                    UnsetLanguage::Specific(l) => language_of(&pv.value) != Some(l),
+                }
+        });
+    }
+}
+
+fn language_of(value: &Value<'_>) -> Option<Id> {
+    match value {
+        Value::Text { language, .. } => *language,
+        _ => None,
+    }
+}
+
+impl<'a> Edit<'a> {
+    /// Builds one [`EntityView`] per entity the edit creates, updates,
+    /// deletes, or relates from, in order of first appearance.
+    ///
+    /// Relations from value refs are not attached to any view.
+    pub fn entity_views(&self) -> Vec<EntityView<'_, 'a>> {
+        let mut views: Vec<EntityView<'_, 'a>> = Vec::new();
+        let mut index: FxHashMap<Id, usize> = FxHashMap::default();
+        for op in &self.ops {
+            let id = match op {
+                Op::CreateEntity(ce) => ce.id,
+                Op::UpdateEntity(ue) => ue.id,
+                Op::DeleteEntity(de) => de.id,
+                Op::RestoreEntity(re) => re.id,
+                Op::CreateRelation(cr) if !cr.from_is_value_ref => cr.from,
+                _ => continue,
+            };
+            let slot = *index.entry(id).or_insert_with(|| {
+                views.push(EntityView::new(id));
+                views.len() - 1
+            });
+            let view = &mut views[slot];
+            match op {
+                Op::CreateEntity(ce) => ce.values.iter().for_each(|pv| view.set(pv)),
+                Op::UpdateEntity(ue) => {
+                    for uv in &ue.unset_values {
+                        view.unset(uv.property, uv.language);
+                    }
+                    ue.set_properties.iter().for_each(|pv| view.set(pv));
+                }
+                Op::DeleteEntity(_) => view.deleted = true,
+                Op::RestoreEntity(_) => view.deleted = false,
+                Op::CreateRelation(cr) => view.relations.push(cr),
+                _ => {}
+            }
+        }
+        views
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const NAME: Id = [10u8; 16];
+    const AGE: Id = [11u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    #[test]
+    fn test_entity_views() {
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text(NAME, "Alice", None).text(NAME, "Alicia", Some(GERMAN)).int64(AGE, 30, None))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .update_entity([3u8; 16], |u| u.unset_language(NAME, GERMAN).set_int64(AGE, 31, None))
+            .create_relation(|r| r.id([8u8; 16]).relation_type([5u8; 16]).from([4u8; 16]).to([3u8; 16]))
+            .delete_entity([4u8; 16])
+            .build();
+
+        let views = edit.entity_views();
+        assert_eq!(views.iter().map(|v| v.id).collect::<Vec<_>>(), vec![[3u8; 16], [4u8; 16]]);
+
+        let alice = &views[0];
+        assert_eq!(alice.values.len(), 2);
+        assert_eq!(alice.value(&AGE), Some(&Value::Int64 { value: 31, unit: None }));
+        assert!(matches!(alice.value(&NAME), Some(Value::Text { language: None, .. })));
+        assert_eq!(alice.value_in(&NAME, Some(GERMAN)), None);
+        assert_eq!(alice.value_in(&AGE, None), alice.value(&AGE));
+        assert_eq!(alice.relations.iter().map(|r| r.id).collect::<Vec<_>>(), vec![[7u8; 16]]);
+        assert!(!alice.deleted);
+
+        assert_eq!(views[1].relations[0].id, [8u8; 16]);
+        assert!(views[1].deleted);
+    }
+}
@@ -0,0 +1,207 @@
+//! Names for IDs in diagnostics.
+//!
+//! Ops, values, validation errors, and lint findings print raw UUIDs by
+//! default. Wrapping them in [`Named`] with a [`NameResolver`] prints names
+//! instead, falling back to the UUID for IDs the resolver doesn't know:
+//!
+//! ```rust
+//! # // Genesis IDs need `std`
+//! # #[cfg(feature = "std")] {
+//! use grc_20::genesis::{properties, GenesisNames};
+//! use grc_20::names::Named;
+//! use grc_20::state::Graph;
+//! use grc_20::EditBuilder;
+//!
+//! let population = [7u8; 16];
+//! let edit = EditBuilder::new([1u8; 16])
+//!     .create_entity(population, |e| e.text(properties::name(), "Population", None))
+//!     .create_entity([2u8; 16], |e| e.text(properties::name(), "Berlin", None).int64(population, 3_645_000, None))
+//!     .build();
+//! let mut graph = Graph::new();
+//! graph.apply_edit(&edit);
+//!
+//! // The graph names its entities, Genesis names the Name property
+//! let names = (&graph, GenesisNames);
+//! assert_eq!(
+//!     Named::new(&edit.ops[1], &names).to_string(),
+//!     "CreateEntity Berlin {Name: \"Berlin\", Population: 3645000}"
+//! );
+//! # }
+//! ```
+
+use core::fmt;
+
+use crate::error::ValidationError;
+use crate::model::id::{name_property, DisplayId};
+use crate::model::{Id, Op, Value};
+use crate::prelude::*;
+use crate::state::Graph;
+
+/// Looks up human-readable names of IDs.
+pub trait NameResolver {
+    /// Returns the name of `id`, if known.
+    fn name(&self, id: &Id) -> Option<String>;
+}
+
+/// Knows no names.
+impl NameResolver for () {
+    fn name(&self, _id: &Id) -> Option<String> {
+        None
+    }
+}
+
+impl<F> NameResolver for F
+where
+    F: Fn(&Id) -> Option<String>,
+{
+    fn name(&self, id: &Id) -> Option<String> {
+        self(id)
+    }
+}
+
+/// Tries the first resolver, then the second.
+impl<A: NameResolver, B: NameResolver> NameResolver for (A, B) {
+    fn name(&self, id: &Id) -> Option<String> {
+        self.0.name(id).or_else(|| self.1.name(id))
+    }
+}
+
+/// Names entities by their Genesis Name value, preferring the default
+/// language.
+impl NameResolver for Graph {
+    fn name(&self, id: &Id) -> Option<String> {
+        self.entity(id)?.values_of(name_property()).find_map(|(_, value)| match value {
+            Value::Text { value, .. } => Some(value.to_string()),
+            _ => None,
+        })
+    }
+}
+
+impl NameResolver for &Graph {
+    fn name(&self, id: &Id) -> Option<String> {
+        (*self).name(id)
+    }
+}
+
+/// Displays an ID, [`Op`], [`Value`], or [`ValidationError`] with the IDs
+/// it mentions named by a resolver.
+pub struct Named<'n, T> {
+    item: T,
+    names: &'n dyn NameResolver,
+}
+
+impl<'n, T> Named<'n, T> {
+    /// Wraps `item` for display with `names`.
+    pub fn new(item: T, names: &'n dyn NameResolver) -> Self {
+        Self { item, names }
+    }
+}
+
+impl fmt::Display for Named<'_, Id> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.names.name(&self.item) {
+            Some(name) => f.write_str(&name),
+            None => write!(f, "{}", DisplayId(self.item)),
+        }
+    }
+}
+
+impl fmt::Display for Named<'_, &Op<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.item.fmt_named(f, self.names)
+    }
+}
+
+impl fmt::Display for Named<'_, &Value<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.item.fmt_named(f, self.names)
+    }
+}
+
+/// The error's `Display` message with names in place of IDs.
+impl fmt::Display for Named<'_, &ValidationError> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = |id: &Id| Named::new(*id, self.names);
+        match self.item {
+            ValidationError::TypeMismatch { property, expected } => {
+                write!(f, "value type mismatch for property {}: expected {expected:?}", id(property))
+            }
+            ValidationError::EntityIsDead { entity } => write!(f, "entity {} is dead (tombstoned)", id(entity)),
+            ValidationError::RelationIsDead { relation } => {
+                write!(f, "relation {} is dead (tombstoned)", id(relation))
+            }
+            ValidationError::PropertyNotFound { property } => {
+                write!(f, "property {} not found in schema", id(property))
+            }
+            ValidationError::DataTypeInconsistent { property, schema, declared } => write!(
+                f,
+                "data type mismatch for property {}: schema says {schema:?}, edit declares {declared:?}",
+                id(property)
+            ),
+            ValidationError::UnknownProperties { properties } => {
+                f.write_str("properties not in schema: ")?;
+                for (i, property) in properties.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{sep}{}", id(property))?;
+                }
+                Ok(())
+            }
+            ValidationError::TextTooLong { property, len, max } => {
+                write!(f, "text value for property {} is {len} bytes, max {max}", id(property))
+            }
+            ValidationError::PatternMismatch { property } => {
+                write!(f, "text value for property {} does not match the schema pattern", id(property))
+            }
+            ValidationError::DuplicateParent { parent } => write!(f, "parent {} is listed more than once", id(parent)),
+            ValidationError::SelfParent => f.write_str("edit lists itself as a parent"),
+            ValidationError::InvalidValue { property, reason } => {
+                write!(f, "invalid value for property {}: {reason}", id(property))
+            }
+        }
+    }
+}
+
+/// Writes `id` as its name, or as a UUID if `names` doesn't know it.
+pub(crate) fn write_id(f: &mut fmt::Formatter<'_>, id: &Id, names: &dyn NameResolver) -> fmt::Result {
+    fmt::Display::fmt(&Named::new(*id, names), f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DataType, EditBuilder};
+
+    #[test]
+    fn test_named() {
+        let (population, people, german) = ([7u8; 16], [8u8; 16], [9u8; 16]);
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_entity(population, |e| {
+                    e.text(name_property(), "Bevölkerung", Some(german)).text(name_property(), "Population", None)
+                })
+                .create_entity(people, |e| e.text(name_property(), "people", None))
+                .build(),
+        );
+        let names = |id: &Id| (*id == german).then(|| "German".to_string());
+        let names = (&graph, names);
+
+        let value = Value::Int64 { value: 3, unit: Some(people) };
+        assert_eq!(Named::new(&value, &names).to_string(), "3 people");
+        let value = Value::Text { value: "Berlin".into(), language: Some(german) };
+        assert_eq!(Named::new(&value, &names).to_string(), "\"Berlin\"@German");
+
+        let op = EditBuilder::new([1u8; 16]).update_entity([2u8; 16], |u| u.set_int64(population, 3, None)).build();
+        let uuid = DisplayId([2u8; 16]);
+        assert_eq!(Named::new(&op.ops[0], &names).to_string(), format!("UpdateEntity {uuid} {{Population: 3}}"));
+        assert_eq!(Named::new(&op.ops[0], &()).to_string(), op.ops[0].to_string());
+
+        let error = ValidationError::TypeMismatch { property: population, expected: DataType::Text };
+        assert_eq!(
+            Named::new(&error, &names).to_string(),
+            "value type mismatch for property Population: expected Text"
+        );
+        let error = ValidationError::UnknownProperties { properties: vec![population, people] };
+        assert_eq!(Named::new(&error, &names).to_string(), "properties not in schema: Population, people");
+    }
+}
@@ -0,0 +1,363 @@
+//! Blocking HTTP fetch and publish of edits (requires the `net` feature).
+//!
+//! Edits travel as their binary encoding with content type
+//! [`CONTENT_TYPE`]. Compression is part of the format (`GRC2Z` and
+//! friends), so responses decode whether or not the server compressed
+//! them; HTTP-level gzip is also accepted.
+//!
+//! Every response body is limited to [`NetOptions::max_size`] bytes before
+//! it is decoded, and decoding applies the usual limits on top. Transport
+//! errors, `429`, and `5xx` responses are retried with exponential backoff;
+//! other `4xx` responses fail immediately.
+//!
+//! ```no_run
+//! use grc_20::net::{fetch_edit, publish_edit};
+//!
+//! let edit = fetch_edit("https://example.com/edits/1").unwrap();
+//! publish_edit("https://example.com/edits", &edit).unwrap();
+//! ```
+
+use std::io::Read;
+use std::time::Duration;
+
+use crate::codec::edit::{decode_edit, edit_to_owned, encode_edit, encode_edit_compressed};
+use crate::error::NetError;
+use crate::limits::MAX_EDIT_SIZE;
+use crate::model::Edit;
+
+/// Content type of encoded edits.
+pub const CONTENT_TYPE: &str = "application/vnd.grc20.edit";
+
+/// Options for [`fetch_edit_with_options`] and [`publish_edit_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetOptions {
+    /// Maximum response (or request) body size in bytes.
+    pub max_size: usize,
+    /// Retries after the first attempt.
+    pub retries: u32,
+    /// Delay before the first retry; doubled for each further retry.
+    pub backoff: Duration,
+    /// Timeout for each attempt.
+    pub timeout: Duration,
+    /// zstd level for published edits, or `None` to publish uncompressed.
+    pub compression_level: Option<i32>,
+}
+
+impl Default for NetOptions {
+    fn default() -> Self {
+        Self {
+            max_size: MAX_EDIT_SIZE,
+            retries: 3,
+            backoff: Duration::from_millis(200),
+            timeout: Duration::from_secs(30),
+            compression_level: Some(3),
+        }
+    }
+}
+
+impl NetOptions {
+    /// Creates the default options (256 MB limit, 3 retries, zstd level 3).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the body size limit.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the number of retries and the initial backoff.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the per-attempt timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Publishes edits uncompressed.
+    pub fn uncompressed(mut self) -> Self {
+        self.compression_level = None;
+        self
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        ureq::AgentBuilder::new().timeout(self.timeout).build()
+    }
+}
+
+/// Result of a conditional fetch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fetched {
+    /// The edit and its `ETag`, if the server sent one.
+    Edit { edit: Edit<'static>, etag: Option<String> },
+    /// The server answered `304 Not Modified` to `If-None-Match`.
+    NotModified,
+}
+
+/// Server response to [`publish_edit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Published {
+    pub status: u16,
+    /// The `Location` header, e.g. the URL of the stored edit.
+    pub location: Option<String>,
+    pub etag: Option<String>,
+}
+
+/// Fetches and decodes the edit at `url`.
+pub fn fetch_edit(url: &str) -> Result<Edit<'static>, NetError> {
+    match fetch_edit_with_options(url, None, &NetOptions::default())? {
+        Fetched::Edit { edit, .. } => Ok(edit),
+        Fetched::NotModified => unreachable!("304 without If-None-Match is rejected"),
+    }
+}
+
+/// Fetches the edit at `url`, sending `If-None-Match: etag` if given.
+pub fn fetch_edit_with_options(
+    url: &str,
+    etag: Option<&str>,
+    options: &NetOptions,
+) -> Result<Fetched, NetError> {
+    let agent = options.agent();
+    let response = with_retries(url, options, || {
+        let mut request = agent.get(url).set("Accept", CONTENT_TYPE);
+        if let Some(etag) = etag {
+            request = request.set("If-None-Match", etag);
+        }
+        request.call().map_err(Box::new)
+    })?;
+
+    if response.status() == 304 {
+        if etag.is_none() {
+            return Err(NetError::Status { url: url.to_string(), status: 304 });
+        }
+        return Ok(Fetched::NotModified);
+    }
+    let etag = response.header("ETag").map(str::to_string);
+    let body = read_body(response, options.max_size)?;
+    let edit = edit_to_owned(decode_edit(&body)?);
+    Ok(Fetched::Edit { edit, etag })
+}
+
+/// Encodes `edit` (zstd level 3) and POSTs it to `endpoint`.
+pub fn publish_edit(endpoint: &str, edit: &Edit) -> Result<Published, NetError> {
+    publish_edit_with_options(endpoint, edit, &NetOptions::default())
+}
+
+/// Encodes and POSTs `edit` with the given options.
+///
+/// If the server rejects a compressed edit with `415 Unsupported Media
+/// Type`, the edit is sent again uncompressed.
+pub fn publish_edit_with_options(endpoint: &str, edit: &Edit, options: &NetOptions) -> Result<Published, NetError> {
+    let bytes = match options.compression_level {
+        Some(level) => encode_edit_compressed(edit, level)?,
+        None => encode_edit(edit)?,
+    };
+    if bytes.len() > options.max_size {
+        return Err(NetError::TooLarge { len: bytes.len(), max: options.max_size });
+    }
+    let agent = options.agent();
+    let post = |body: &[u8]| {
+        with_retries(endpoint, options, || {
+            agent.post(endpoint).set("Content-Type", CONTENT_TYPE).send_bytes(body).map_err(Box::new)
+        })
+    };
+    let response = match post(&bytes) {
+        Err(NetError::Status { status: 415, .. }) if options.compression_level.is_some() => post(&encode_edit(edit)?)?,
+        result => result?,
+    };
+    Ok(Published {
+        status: response.status(),
+        location: response.header("Location").map(str::to_string),
+        etag: response.header("ETag").map(str::to_string),
+    })
+}
+
+/// Runs `attempt`, retrying transport errors, 429, and 5xx.
+pub(crate) fn with_retries(
+    url: &str,
+    options: &NetOptions,
+    mut attempt: impl FnMut() -> Result<ureq::Response, Box<ureq::Error>>,
+) -> Result<ureq::Response, NetError> {
+    let mut backoff = options.backoff;
+    let mut retries_left = options.retries;
+    loop {
+        let error = match attempt().map_err(|e| *e) {
+            Ok(response) => return Ok(response),
+            Err(ureq::Error::Status(status, _)) => {
+                let error = NetError::Status { url: url.to_string(), status };
+                if status != 429 && status < 500 {
+                    return Err(error);
+                }
+                error
+            }
+            Err(ureq::Error::Transport(transport)) => NetError::Transport(transport.to_string()),
+        };
+        if retries_left == 0 {
+            return Err(error);
+        }
+        retries_left -= 1;
+        std::thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2);
+    }
+}
+
+/// Reads a response body, failing once it exceeds `max` bytes.
+pub(crate) fn read_body(response: ureq::Response, max: usize) -> Result<Vec<u8>, NetError> {
+    if let Some(len) = response.header("Content-Length").and_then(|v| v.parse::<usize>().ok()) {
+        if len > max {
+            return Err(NetError::TooLarge { len, max });
+        }
+    }
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(max as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| NetError::Transport(e.to_string()))?;
+    if body.len() > max {
+        return Err(NetError::TooLarge { len: body.len(), max });
+    }
+    Ok(body)
+}
+
+/// A minimal HTTP/1.1 server for client tests.
+#[cfg(test)]
+pub(crate) mod test_server {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// A request as seen by the test server.
+    pub(crate) struct Request {
+        pub method: String,
+        /// Path and query.
+        pub path: String,
+        pub headers: Vec<(String, String)>,
+        pub body: Vec<u8>,
+    }
+
+    impl Request {
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Status, extra headers, and body.
+    pub(crate) type Response = (u16, Vec<(&'static str, String)>, Vec<u8>);
+
+    /// Serves one canned response per request, in order, and reports what
+    /// it received. Returns the base URL.
+    pub(crate) fn serve(responses: Vec<Response>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (status, headers, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut parts = line.split(' ');
+                let method = parts.next().unwrap().to_string();
+                let path = parts.next().unwrap().to_string();
+                let mut request_headers = Vec::new();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(": ") else { break };
+                    request_headers.push((name.to_string(), value.to_string()));
+                }
+                let len = request_headers
+                    .iter()
+                    .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+                    .map_or(0, |(_, v)| v.parse().unwrap());
+                let mut request_body = vec![0u8; len];
+                reader.read_exact(&mut request_body).unwrap();
+                tx.send(Request { method, path, headers: request_headers, body: request_body }).unwrap();
+
+                let mut stream = stream;
+                write!(stream, "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len())
+                    .unwrap();
+                for (name, value) in headers {
+                    write!(stream, "{}: {}\r\n", name, value).unwrap();
+                }
+                stream.write_all(b"\r\n").unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        (url, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_server::serve;
+    use crate::model::EditBuilder;
+
+    fn edit() -> Edit<'static> {
+        EditBuilder::new([1u8; 16]).name("net").create_entity([2u8; 16], |e| e.text([3u8; 16], "Alice", None)).build()
+    }
+
+    fn fast() -> NetOptions {
+        NetOptions::new().retries(2, Duration::from_millis(1))
+    }
+
+    #[test]
+    fn test_fetch_with_etag_and_retries() {
+        let bytes = encode_edit_compressed(&edit(), 3).unwrap();
+        let (url, requests) = serve(vec![
+            (503, vec![], vec![]),
+            (200, vec![("ETag", "\"v1\"".into())], bytes),
+            (304, vec![], vec![]),
+        ]);
+
+        let fetched = fetch_edit_with_options(&url, None, &fast()).unwrap();
+        assert_eq!(fetched, Fetched::Edit { edit: edit(), etag: Some("\"v1\"".into()) });
+        let first = requests.recv().unwrap();
+        assert_eq!(first.header("Accept"), Some(CONTENT_TYPE));
+        assert_eq!(requests.recv().unwrap().method, "GET");
+
+        assert_eq!(fetch_edit_with_options(&url, Some("\"v1\""), &fast()).unwrap(), Fetched::NotModified);
+        assert_eq!(requests.recv().unwrap().header("If-None-Match"), Some("\"v1\""));
+    }
+
+    #[test]
+    fn test_fetch_errors() {
+        let bytes = encode_edit(&edit()).unwrap();
+        let len = bytes.len();
+        let (url, _requests) = serve(vec![(404, vec![], vec![]), (200, vec![], bytes), (200, vec![], b"nope".to_vec())]);
+
+        // 4xx is not retried
+        assert!(matches!(fetch_edit_with_options(&url, None, &fast()), Err(NetError::Status { status: 404, .. })));
+        let small = fast().max_size(len - 1);
+        assert_eq!(fetch_edit_with_options(&url, None, &small), Err(NetError::TooLarge { len, max: len - 1 }));
+        assert!(matches!(fetch_edit(&url), Err(NetError::Decode(_))));
+    }
+
+    #[test]
+    fn test_publish_falls_back_to_uncompressed() {
+        let (url, requests) = serve(vec![
+            (415, vec![], vec![]),
+            (201, vec![("Location", "/edits/1".into())], vec![]),
+        ]);
+        let published = publish_edit_with_options(&url, &edit(), &fast()).unwrap();
+        assert_eq!(published, Published { status: 201, location: Some("/edits/1".into()), etag: None });
+
+        let compressed = requests.recv().unwrap();
+        assert_eq!(compressed.method, "POST");
+        assert_eq!(compressed.header("Content-Type"), Some(CONTENT_TYPE));
+        assert!(compressed.body.starts_with(b"GRC2Z"));
+        let plain = requests.recv().unwrap();
+        assert_eq!(plain.body, encode_edit(&edit()).unwrap());
+
+        let tiny = fast().max_size(10);
+        assert!(matches!(publish_edit_with_options(&url, &edit(), &tiny), Err(NetError::TooLarge { .. })));
+    }
+}
@@ -0,0 +1,509 @@
+//! A small text format for writing ops by hand.
+//!
+//! Patches are meant for test fixtures and emergency corrections, where
+//! writing Rust is overkill:
+//!
+//! ```text
+//! # Comments run to the end of the line
+//! create 8f3a9c2e4b1d4e6f9a0b1c2d3e4f5a6b {
+//!     set name = "Berlin";
+//!     set name = "Berlin" @de;
+//! }
+//! entity 8f3a9c2e-4b1d-4e6f-9a0b-1c2d3e4f5a6b {
+//!     set description = "Capital of Germany";
+//!     set created = datetime "2024-01-15T14:30:00Z";
+//!     unset url;
+//!     unset name @de;
+//! }
+//! relation 1d2c... 8f3a... -[part_of]-> 5e6f... at "a0";
+//! delete 2b3c...;
+//! restore relation 4d5e...;
+//! ```
+//!
+//! | Statement | Op |
+//! |---|---|
+//! | `create <id> { set ...; }` | `CreateEntity` |
+//! | `entity <id> { set ...; unset ...; }` | `UpdateEntity` |
+//! | `relation <id> <from> -[<type>]-> <to> [at "<position>"];` | `CreateRelation` |
+//! | `delete [relation] <id>;` | `DeleteEntity` / `DeleteRelation` |
+//! | `restore [relation] <id>;` | `RestoreEntity` / `RestoreRelation` |
+//!
+//! IDs are UUIDs in hex, with or without hyphens, or names registered with
+//! [`PatchParser::name`]. The Genesis properties, types, and relation types
+//! are predefined under their function names (`name`, `description`,
+//! `person`, `part_of`, ...).
+//!
+//! Values are `"text"` (optionally followed by `@<bcp47 tag>`), `true` and
+//! `false`, integers (INT64), numbers with a fraction or exponent (FLOAT64),
+//! and `date`, `time`, `datetime`, or `schedule` followed by a string.
+//! `unset <property>` clears every language slot; `unset <property> @<tag>`
+//! clears one.
+
+use alloc::borrow::Cow;
+
+use crate::collections::FxHashMap;
+use crate::error::PatchError;
+use crate::genesis::{properties, relation_types, types};
+use crate::lang;
+use crate::model::{
+    parse_id, CreateEntity, CreateRelation, DeleteEntity, DeleteRelation, Id, Op, PropertyValue, RestoreEntity,
+    RestoreRelation, UnsetLanguage, UnsetValue, UpdateEntity, Value,
+};
+use crate::prelude::*;
+
+/// Parses a patch with the predefined Genesis names.
+pub fn parse(src: &str) -> Result<Vec<Op<'static>>, PatchError> {
+    PatchParser::new().parse(src)
+}
+
+/// Parses patches, resolving names to IDs.
+#[derive(Debug, Clone)]
+pub struct PatchParser {
+    names: FxHashMap<String, Id>,
+}
+
+impl Default for PatchParser {
+    fn default() -> Self {
+        let names = [
+            ("name", properties::name()),
+            ("description", properties::description()),
+            ("avatar", properties::avatar()),
+            ("url", properties::url()),
+            ("created", properties::created()),
+            ("modified", properties::modified()),
+            ("person", types::person()),
+            ("organization", types::organization()),
+            ("place", types::place()),
+            ("topic", types::topic()),
+            ("types", relation_types::types()),
+            ("part_of", relation_types::part_of()),
+            ("related_to", relation_types::related_to()),
+            ("data_type", relation_types::data_type()),
+        ];
+        Self { names: names.into_iter().map(|(name, id)| (name.to_string(), id)).collect() }
+    }
+}
+
+impl PatchParser {
+    /// Creates a parser with the predefined Genesis names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a name for an ID, replacing any previous one.
+    ///
+    /// Names may contain ASCII letters, digits, and `_ . - + :`.
+    pub fn name(mut self, name: impl Into<String>, id: Id) -> Self {
+        self.names.insert(name.into(), id);
+        self
+    }
+
+    /// Resolves a registered name, or parses a hex UUID.
+    pub fn resolve(&self, name: &str) -> Option<Id> {
+        self.names.get(name).copied().or_else(|| parse_id(name))
+    }
+
+    /// Returns the registered names, sorted.
+    pub fn names(&self) -> Vec<(&str, Id)> {
+        let mut names: Vec<(&str, Id)> = self.names.iter().map(|(name, id)| (name.as_str(), *id)).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Parses a patch into ops, in statement order.
+    pub fn parse(&self, src: &str) -> Result<Vec<Op<'static>>, PatchError> {
+        let mut parser = Parser { tokens: tokenize(src)?, pos: 0, resolver: self };
+        let mut ops = Vec::new();
+        while parser.pos < parser.tokens.len() {
+            ops.push(parser.statement()?);
+        }
+        Ok(ops)
+    }
+}
+
+// =============================================================================
+// Lexer
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Word(String),
+    Str(String),
+    /// `{ } ; = @ -[ ]->`
+    Punct(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    line: usize,
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"_.-+:".contains(&b)
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, PatchError> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let kind = match rest[0] {
+            b'\n' => {
+                line += 1;
+                i += 1;
+                continue;
+            }
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'#' => {
+                i += rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+                continue;
+            }
+            b'"' => {
+                let (value, len) = string(&src[i + 1..], line)?;
+                i += len + 1;
+                Kind::Str(value)
+            }
+            b'-' if rest.starts_with(b"-[") => {
+                i += 2;
+                Kind::Punct("-[")
+            }
+            b']' if rest.starts_with(b"]->") => {
+                i += 3;
+                Kind::Punct("]->")
+            }
+            b @ (b'{' | b'}' | b';' | b'=' | b'@') => {
+                i += 1;
+                Kind::Punct(match b {
+                    b'{' => "{",
+                    b'}' => "}",
+                    b';' => ";",
+                    b'=' => "=",
+                    _ => "@",
+                })
+            }
+            b if is_word_byte(b) => {
+                let start = i;
+                while i < bytes.len() && is_word_byte(bytes[i]) && !bytes[i..].starts_with(b"-[") {
+                    i += 1;
+                }
+                Kind::Word(src[start..i].to_string())
+            }
+            _ => {
+                let c = src[i..].chars().next().unwrap_or_default();
+                return Err(PatchError::Syntax { line, message: format!("unexpected character {c:?}") });
+            }
+        };
+        tokens.push(Token { kind, line });
+    }
+    Ok(tokens)
+}
+
+/// Reads a string body after the opening quote, returning the value and
+/// the number of bytes consumed including the closing quote.
+fn string(src: &str, line: usize) -> Result<(String, usize), PatchError> {
+    let mut value = String::new();
+    let mut chars = src.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, i + 1)),
+            '\n' => break,
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, c @ ('"' | '\\'))) => value.push(c),
+                other => {
+                    let message = format!("invalid escape {:?}", other.map(|(_, c)| c).unwrap_or_default());
+                    return Err(PatchError::Syntax { line, message });
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    Err(PatchError::Syntax { line, message: "unterminated string".to_string() })
+}
+
+// =============================================================================
+// Parser
+// =============================================================================
+
+struct Parser<'p> {
+    tokens: Vec<Token>,
+    pos: usize,
+    resolver: &'p PatchParser,
+}
+
+impl Parser<'_> {
+    fn statement(&mut self) -> Result<Op<'static>, PatchError> {
+        let keyword = self.word("a statement")?;
+        let op = match keyword.as_str() {
+            "create" => {
+                let id = self.id()?;
+                let (values, _) = self.block(false)?;
+                return Ok(Op::CreateEntity(CreateEntity { id, values, context: None }));
+            }
+            "entity" => {
+                let id = self.id()?;
+                let (set_properties, unset_values) = self.block(true)?;
+                return Ok(Op::UpdateEntity(UpdateEntity { id, set_properties, unset_values, context: None }));
+            }
+            "relation" => {
+                let id = self.id()?;
+                let from = self.id()?;
+                self.expect("-[")?;
+                let relation_type = self.id()?;
+                self.expect("]->")?;
+                let to = self.id()?;
+                let position = if self.eat_word("at") { Some(Cow::Owned(self.string()?)) } else { None };
+                Op::CreateRelation(CreateRelation {
+                    id,
+                    relation_type,
+                    from,
+                    from_is_value_ref: false,
+                    from_space: None,
+                    from_version: None,
+                    to,
+                    to_is_value_ref: false,
+                    to_space: None,
+                    to_version: None,
+                    entity: None,
+                    position,
+                    context: None,
+                })
+            }
+            "delete" => match self.eat_word("relation") {
+                true => Op::DeleteRelation(DeleteRelation { id: self.id()?, context: None }),
+                false => Op::DeleteEntity(DeleteEntity { id: self.id()?, context: None }),
+            },
+            "restore" => match self.eat_word("relation") {
+                true => Op::RestoreRelation(RestoreRelation { id: self.id()?, context: None }),
+                false => Op::RestoreEntity(RestoreEntity { id: self.id()?, context: None }),
+            },
+            _ => return Err(self.error_at(self.pos - 1, "a statement")),
+        };
+        self.expect(";")?;
+        Ok(op)
+    }
+
+    /// `{ (set <property> = <value>; | unset <property> [@<tag>];)* }`
+    fn block(&mut self, allow_unset: bool) -> Result<(Vec<PropertyValue<'static>>, Vec<UnsetValue>), PatchError> {
+        self.expect("{")?;
+        let (mut values, mut unsets) = (Vec::new(), Vec::new());
+        while !self.eat("}") {
+            let expected = if allow_unset { "`set`, `unset`, or `}`" } else { "`set` or `}`" };
+            match self.word(expected)?.as_str() {
+                "set" => {
+                    let property = self.id()?;
+                    self.expect("=")?;
+                    values.push(PropertyValue { property, value: self.value()? });
+                }
+                "unset" if allow_unset => {
+                    let property = self.id()?;
+                    let language =
+                        if self.eat("@") { UnsetLanguage::Specific(self.language()?) } else { UnsetLanguage::All };
+                    unsets.push(UnsetValue { property, language });
+                }
+                _ => return Err(self.error_at(self.pos - 1, expected)),
+            }
+            self.expect(";")?;
+        }
+        Ok((values, unsets))
+    }
+
+    fn value(&mut self) -> Result<Value<'static>, PatchError> {
+        let token = self.next("a value")?;
+        let value = match token.kind {
+            Kind::Str(value) => {
+                let language = if self.eat("@") { Some(self.language()?) } else { None };
+                return Ok(Value::Text { value: Cow::Owned(value), language });
+            }
+            Kind::Word(word) => match word.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "date" => Value::Date(Cow::Owned(self.string()?)),
+                "time" => Value::Time(Cow::Owned(self.string()?)),
+                "datetime" => Value::Datetime(Cow::Owned(self.string()?)),
+                "schedule" => Value::Schedule(Cow::Owned(self.string()?)),
+                _ => number(&word).ok_or_else(|| self.error_at(self.pos - 1, "a value"))?,
+            },
+            Kind::Punct(_) => return Err(self.error_at(self.pos - 1, "a value")),
+        };
+        if self.peek_is("@") {
+            return Err(PatchError::Syntax { line: self.line(), message: "only text values take a language".to_string() });
+        }
+        Ok(value)
+    }
+
+    fn id(&mut self) -> Result<Id, PatchError> {
+        let word = self.word("an ID or name")?;
+        match self.resolver.resolve(&word) {
+            Some(id) => Ok(id),
+            None => Err(PatchError::UnknownName { line: self.tokens[self.pos - 1].line, name: word }),
+        }
+    }
+
+    fn language(&mut self) -> Result<Id, PatchError> {
+        let tag = self.word("a language tag")?;
+        lang::from_bcp47(&tag).ok_or_else(|| PatchError::Syntax {
+            line: self.tokens[self.pos - 1].line,
+            message: format!("invalid language tag {tag:?}"),
+        })
+    }
+
+    fn string(&mut self) -> Result<String, PatchError> {
+        match self.next("a string")?.kind {
+            Kind::Str(value) => Ok(value),
+            _ => Err(self.error_at(self.pos - 1, "a string")),
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<String, PatchError> {
+        match self.next(expected)?.kind {
+            Kind::Word(word) => Ok(word),
+            _ => Err(self.error_at(self.pos - 1, expected)),
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), PatchError> {
+        match self.eat(punct) {
+            true => Ok(()),
+            false if self.pos < self.tokens.len() => Err(self.error_at(self.pos, &format!("`{punct}`"))),
+            false => Err(self.end(&format!("`{punct}`"))),
+        }
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, PatchError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| self.end(expected))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let found = self.peek_is(punct);
+        self.pos += found as usize;
+        found
+    }
+
+    fn eat_word(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.tokens.get(self.pos), Some(Token { kind: Kind::Word(w), .. }) if w == keyword);
+        self.pos += found as usize;
+        found
+    }
+
+    fn peek_is(&self, punct: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token { kind: Kind::Punct(p), .. }) if *p == punct)
+    }
+
+    /// The line of the next token, or of the last one at the end.
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |t| t.line)
+    }
+
+    fn error_at(&self, pos: usize, expected: &str) -> PatchError {
+        let token = &self.tokens[pos];
+        let found = match &token.kind {
+            Kind::Word(word) => format!("`{word}`"),
+            Kind::Str(value) => format!("{value:?}"),
+            Kind::Punct(punct) => format!("`{punct}`"),
+        };
+        PatchError::Syntax { line: token.line, message: format!("expected {expected}, found {found}") }
+    }
+
+    fn end(&self, expected: &str) -> PatchError {
+        PatchError::Syntax { line: self.line(), message: format!("expected {expected}, found end of patch") }
+    }
+}
+
+/// Parses an INT64 or a finite FLOAT64 literal.
+fn number(word: &str) -> Option<Value<'static>> {
+    let digits = word.strip_prefix(['-', '+']).unwrap_or(word);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    if let Ok(value) = word.parse::<i64>() {
+        return Some(Value::Int64 { value, unit: None });
+    }
+    let value = word.parse::<f64>().ok().filter(|v| v.is_finite())?;
+    Some(Value::Float64 { value, unit: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_parse() {
+        let (berlin, germany, population) = ([1u8; 16], [2u8; 16], [50u8; 16]);
+        let de = lang::from_bcp47("de").unwrap();
+        let src = r#"
+            # Berlin
+            create 01010101010101010101010101010101 {
+                set name = "Berlin";
+                set name = "Berlin \"DE\"" @de;
+                set population = 3645000;
+            }
+            entity berlin {
+                set area = 891.7;
+                set capital = true;
+                set founded = date "1237-10-28";
+                unset population;
+                unset name @de;
+            }
+            relation 03030303-0303-0303-0303-030303030303 berlin -[part_of]-> germany at "a0";
+            delete relation 03030303030303030303030303030303;
+            restore berlin;
+        "#;
+        let parser = PatchParser::new()
+            .name("berlin", berlin)
+            .name("germany", germany)
+            .name("population", population)
+            .name("area", [51u8; 16])
+            .name("capital", [52u8; 16])
+            .name("founded", [53u8; 16]);
+        let ops = parser.parse(src).unwrap();
+
+        let expected = EditBuilder::new([0u8; 16])
+            .create_entity(berlin, |e| {
+                e.text(properties::name(), "Berlin", None)
+                    .text(properties::name(), "Berlin \"DE\"", Some(de))
+                    .int64(population, 3_645_000, None)
+            })
+            .update_entity(berlin, |u| {
+                u.set_float64([51u8; 16], 891.7, None)
+                    .set_bool([52u8; 16], true)
+                    .set_date([53u8; 16], "1237-10-28")
+                    .unset_all(population)
+                    .unset_language(properties::name(), de)
+            })
+            .create_relation(|r| {
+                r.id([3u8; 16]).from(berlin).to(germany).relation_type(relation_types::part_of()).position("a0")
+            })
+            .delete_relation([3u8; 16])
+            .restore_entity(berlin)
+            .build();
+        assert_eq!(ops, expected.ops);
+        assert_eq!(parse("# nothing to do\n").unwrap(), vec![]);
+        assert_eq!(parser.resolve("berlin"), Some(berlin));
+        assert_eq!(parser.resolve("01010101-0101-0101-0101-010101010101"), Some(berlin));
+        assert_eq!(parser.resolve("paris"), None);
+        assert!(parser.names().windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |src: &str| parse(src).unwrap_err().to_string();
+        assert_eq!(error("delete nowhere;"), "line 1: unknown name \"nowhere\"");
+        assert_eq!(error("entity name {\n  set description = 1\n}"), "line 3: expected `;`, found `}`");
+        assert_eq!(error("create name { unset url; }"), "line 1: expected `set` or `}`, found `unset`");
+        assert_eq!(error("entity name { set url = 1 @en; }"), "line 1: only text values take a language");
+        assert_eq!(error("entity name {\n set url = \"x"), "line 2: unterminated string");
+        assert_eq!(error("delete name"), "line 1: expected `;`, found end of patch");
+        assert_eq!(error("drop name;"), "line 1: expected a statement, found `drop`");
+    }
+}
@@ -0,0 +1,12 @@
+//! `alloc` items that the `std` prelude provides implicitly.
+//!
+//! Modules that must build without `std` glob-import this.
+
+#![allow(unused_imports)]
+
+pub(crate) use alloc::borrow::ToOwned;
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::format;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec;
+pub(crate) use alloc::vec::Vec;
@@ -0,0 +1,254 @@
+//! Fluent queries over materialized state.
+//!
+//! ```rust
+//! use grc_20::state::Graph;
+//!
+//! # let graph = Graph::new();
+//! # let (country, iso2, in_region) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+//! let matches = graph.query().of_type(country).where_text(iso2, "DE").with_relation(in_region).run();
+//! for m in &matches {
+//!     println!("{:?}: {} values", m.id, m.entity.values.len());
+//! }
+//! ```
+//!
+//! A query starts from the most selective filter that an index can answer
+//! (see [`crate::state::IndexConfig`]) and checks the rest per entity, so
+//! it works without indexes, only slower.
+
+use crate::collections::FxHashSet;
+use crate::model::{Id, Value};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, ValueKey};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    OfType(Id),
+    Text(Id, String),
+    Int64(Id, i64),
+    Has(Id),
+    Relation { relation_type: Id, to: Option<Id> },
+}
+
+/// A live entity matched by a [`Query`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueryMatch<'g> {
+    pub id: Id,
+    pub entity: &'g Entity,
+}
+
+/// A query over the live entities of a [`Graph`].
+///
+/// Filters are combined with AND. Results are sorted by entity ID.
+#[derive(Debug, Clone)]
+pub struct Query<'g> {
+    graph: &'g Graph,
+    types_relation: Option<Id>,
+    filters: Vec<Filter>,
+    limit: Option<usize>,
+}
+
+impl Graph {
+    /// Starts a query over the live entities of this graph.
+    pub fn query(&self) -> Query<'_> {
+        Query::new(self)
+    }
+}
+
+impl<'g> Query<'g> {
+    /// Creates a query that matches every live entity.
+    ///
+    /// Types are resolved through the graph's indexed types relation, or
+    /// the Genesis `Types` relation (with `std`) if no type index is set.
+    pub fn new(graph: &'g Graph) -> Self {
+        let types_relation = graph.index_config().and_then(|c| c.types_relation);
+        #[cfg(feature = "std")]
+        let types_relation = types_relation.or_else(|| Some(crate::genesis::relation_types::types()));
+        Self { graph, types_relation, filters: Vec::new(), limit: None }
+    }
+
+    /// Sets the relation type that assigns types to entities.
+    pub fn types_relation(mut self, relation_type: Id) -> Self {
+        self.types_relation = Some(relation_type);
+        self
+    }
+
+    /// Keeps entities with a live types relation to `type_id`.
+    pub fn of_type(mut self, type_id: Id) -> Self {
+        self.filters.push(Filter::OfType(type_id));
+        self
+    }
+
+    /// Keeps entities with a TEXT value (in any language) equal to `text`.
+    pub fn where_text(mut self, property: Id, text: &str) -> Self {
+        self.filters.push(Filter::Text(property, text.to_string()));
+        self
+    }
+
+    /// Keeps entities with an INT64 value equal to `value` (any unit).
+    pub fn where_int64(mut self, property: Id, value: i64) -> Self {
+        self.filters.push(Filter::Int64(property, value));
+        self
+    }
+
+    /// Keeps entities with any value for `property`.
+    pub fn has(mut self, property: Id) -> Self {
+        self.filters.push(Filter::Has(property));
+        self
+    }
+
+    /// Keeps entities with a live outgoing relation of the given type.
+    pub fn with_relation(mut self, relation_type: Id) -> Self {
+        self.filters.push(Filter::Relation { relation_type, to: None });
+        self
+    }
+
+    /// Keeps entities with a live outgoing relation of the given type to
+    /// `to`.
+    pub fn with_relation_to(mut self, relation_type: Id, to: Id) -> Self {
+        self.filters.push(Filter::Relation { relation_type, to: Some(to) });
+        self
+    }
+
+    /// Returns at most `n` matches.
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Runs the query.
+    pub fn run(&self) -> Vec<QueryMatch<'g>> {
+        let graph = self.graph;
+        let limit = self.limit.unwrap_or(usize::MAX);
+        // Relation filters are resolved with one pass over the relations
+        let sources: Vec<Option<FxHashSet<Id>>> = self.filters.iter().map(|f| self.sources(f)).collect();
+        self.seed()
+            .into_iter()
+            .filter_map(|id| graph.entity(&id).map(|entity| QueryMatch { id, entity }))
+            .filter(|m| {
+                self.filters.iter().zip(&sources).all(|(f, sources)| match sources {
+                    Some(sources) => sources.contains(&m.id),
+                    None => matches(m.entity, f),
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Runs the query and returns only the matching IDs.
+    pub fn ids(&self) -> Vec<Id> {
+        self.run().into_iter().map(|m| m.id).collect()
+    }
+
+    /// Returns the sorted candidate IDs from the most selective filter.
+    fn seed(&self) -> Vec<Id> {
+        let graph = self.graph;
+        for filter in &self.filters {
+            if let Filter::OfType(type_id) = filter {
+                if let Some(ids) = graph.entities_of_type(type_id) {
+                    return ids;
+                }
+            }
+        }
+        for filter in &self.filters {
+            match filter {
+                Filter::Text(property, text) => return graph.entities_with_text(*property, text),
+                Filter::Int64(property, value) => return graph.entities_with_int64(*property, *value),
+                _ => {}
+            }
+        }
+        let mut ids: Vec<Id> = graph.entities().map(|(id, _)| *id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Returns the `from` entities of the live relations a relation
+    /// filter accepts, or `None` for value filters.
+    fn sources(&self, filter: &Filter) -> Option<FxHashSet<Id>> {
+        let (relation_type, to) = match filter {
+            Filter::OfType(type_id) => (self.types_relation, Some(*type_id)),
+            Filter::Relation { relation_type, to } => (Some(*relation_type), *to),
+            _ => return None,
+        };
+        let Some(relation_type) = relation_type else {
+            return Some(FxHashSet::default());
+        };
+        Some(
+            self.graph
+                .relations()
+                .filter(|(_, r)| r.relation_type == relation_type && to.is_none_or(|t| r.to == t))
+                .map(|(_, r)| r.from)
+                .collect(),
+        )
+    }
+}
+
+fn matches(entity: &Entity, filter: &Filter) -> bool {
+    match filter {
+        Filter::Text(property, text) => entity
+            .values_of(*property)
+            .any(|(_, v)| matches!(v, Value::Text { value, .. } if value == text)),
+        Filter::Int64(property, value) => {
+            matches!(entity.value(&ValueKey::new(*property)), Some(Value::Int64 { value: v, .. }) if v == value)
+        }
+        Filter::Has(property) => entity.values_of(*property).next().is_some(),
+        Filter::OfType(_) | Filter::Relation { .. } => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use crate::state::IndexConfig;
+
+    const TYPES: Id = [50u8; 16];
+    const COUNTRY: Id = [51u8; 16];
+    const CITY: Id = [52u8; 16];
+    const IN_REGION: Id = [53u8; 16];
+    const EUROPE: Id = [54u8; 16];
+    const ISO2: Id = [60u8; 16];
+    const POPULATION: Id = [61u8; 16];
+
+    fn make_graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.apply_edit(&EditBuilder::new([9u8; 16])
+            .create_entity([1u8; 16], |e| e.text(ISO2, "DE", None).int64(POPULATION, 83, None))
+            .create_entity([2u8; 16], |e| e.text(ISO2, "FR", None).int64(POPULATION, 68, None))
+            .create_entity([3u8; 16], |e| e.text(ISO2, "DE", None))
+            .create_relation(|r| r.id([10u8; 16]).relation_type(TYPES).from([1u8; 16]).to(COUNTRY))
+            .create_relation(|r| r.id([11u8; 16]).relation_type(TYPES).from([2u8; 16]).to(COUNTRY))
+            .create_relation(|r| r.id([12u8; 16]).relation_type(TYPES).from([3u8; 16]).to(CITY))
+            .create_relation(|r| r.id([13u8; 16]).relation_type(IN_REGION).from([1u8; 16]).to(EUROPE))
+            .build());
+        graph
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let plain = make_graph();
+        let indexed = make_graph().with_indexes(IndexConfig::new().types(TYPES).values(ISO2));
+        for graph in [&plain, &indexed] {
+            let query = || graph.query().types_relation(TYPES);
+            assert_eq!(query().of_type(COUNTRY).ids(), vec![[1u8; 16], [2u8; 16]]);
+            assert_eq!(query().where_text(ISO2, "DE").ids(), vec![[1u8; 16], [3u8; 16]]);
+            assert_eq!(
+                query().of_type(COUNTRY).where_text(ISO2, "DE").with_relation(IN_REGION).ids(),
+                vec![[1u8; 16]]
+            );
+            assert_eq!(query().with_relation_to(IN_REGION, [2u8; 16]).ids(), Vec::<Id>::new());
+            assert_eq!(query().where_int64(POPULATION, 68).ids(), vec![[2u8; 16]]);
+            assert_eq!(query().has(POPULATION).limit(1).ids(), vec![[1u8; 16]]);
+        }
+    }
+
+    #[test]
+    fn test_query_returns_values() {
+        let graph = make_graph();
+        let matches = graph.query().types_relation(TYPES).of_type(CITY).run();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            matches[0].entity.value(&ValueKey::new(ISO2)),
+            Some(&Value::Text { value: "DE".into(), language: None })
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Schema extraction from published edits.
+//!
+//! When a space publishes its schema as edits, [`extract`] recovers a
+//! [`SchemaContext`] from them instead of registering each property by
+//! hand. A property is declared by a relation from the property entity to
+//! a data type entity (see [`crate::genesis::data_type_id`]), using either
+//! the `DataType` or the `Types` relation type, as written by
+//! [`EditBuilder::create_property`](crate::model::EditBuilder::create_property):
+//!
+//! ```ignore
+//! let schema = schema::extract(&schema_edit);
+//! validate_edit(&data_edit, &schema)?;
+//! ```
+
+use crate::model::{DataType, Edit, Id, Op};
+use crate::prelude::*;
+use crate::validate::{PropertyDeclarations, SchemaContext};
+
+/// Builds a [`SchemaContext`] from the property declarations in an edit.
+///
+/// Relations deleted later in the same edit are ignored. If a property is
+/// declared with several data types, the last declaration wins.
+pub fn extract(edit: &Edit) -> SchemaContext {
+    let declarations = PropertyDeclarations::new();
+
+    // (relation, property, data type), in op order
+    let mut found: Vec<(Id, Id, DataType)> = Vec::new();
+    for op in &edit.ops {
+        match op {
+            Op::CreateRelation(cr) => {
+                if let Some((property, data_type)) = declarations.get(cr) {
+                    found.push((cr.id, property, data_type));
+                }
+            }
+            Op::DeleteRelation(dr) => found.retain(|(id, _, _)| *id != dr.id),
+            _ => {}
+        }
+    }
+
+    let mut schema = SchemaContext::new();
+    for (_, property, data_type) in found {
+        schema.add_property(property, data_type);
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::{data_type_id, genesis_edit, properties, relation_types};
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_extract_genesis() {
+        let schema = extract(&genesis_edit());
+        assert_eq!(schema.get_property_type(&properties::name()), Some(DataType::Text));
+        assert_eq!(schema.get_property_type(&properties::created()), Some(DataType::Datetime));
+        assert!(!schema.knows(&relation_types::types()));
+    }
+
+    #[test]
+    fn test_extract_types_and_deletes() {
+        let population = [1u8; 16];
+        let area = [2u8; 16];
+        let edit = EditBuilder::new([9u8; 16])
+            .create_relation_simple([10u8; 16], population, data_type_id(DataType::Int64), relation_types::types())
+            .create_relation_simple([11u8; 16], area, data_type_id(DataType::Float64), relation_types::data_type())
+            .create_relation_simple([12u8; 16], area, [3u8; 16], relation_types::data_type())
+            .delete_relation([11u8; 16])
+            .build();
+        let schema = extract(&edit);
+        assert_eq!(schema.get_property_type(&population), Some(DataType::Int64));
+        assert!(!schema.knows(&area));
+    }
+}
@@ -0,0 +1,320 @@
+//! Detached Ed25519 author signatures (requires the `signature` feature).
+//!
+//! Each author of an edit signs its [`canonical_hash`], the SHA-256 of the
+//! canonical encoding. Signatures are detached: they travel next to the
+//! encoded edit (see [`encode_signatures`]) rather than inside it, so the
+//! edit bytes and its hash are the same whether it has one signature or
+//! many. An edit with several `authors` is valid once every one of them has
+//! signed it:
+//!
+//! ```
+//! use ed25519_dalek::SigningKey;
+//! use grc_20::EditBuilder;
+//! use grc_20::signature::{verify_all, KeyRegistry, SignedEdit};
+//!
+//! let (alice, bob) = ([1u8; 16], [2u8; 16]);
+//! let (alice_key, bob_key) = (SigningKey::from_bytes(&[7u8; 32]), SigningKey::from_bytes(&[8u8; 32]));
+//! let edit = EditBuilder::new([9u8; 16]).author(alice).author(bob).build();
+//!
+//! let signed = SignedEdit::new(edit).sign(alice, &alice_key).unwrap().sign(bob, &bob_key).unwrap();
+//!
+//! let mut keys = KeyRegistry::new();
+//! keys.insert(alice, alice_key.verifying_key());
+//! keys.insert(bob, bob_key.verifying_key());
+//! assert!(verify_all(&signed, &keys).is_ok());
+//! ```
+
+use ed25519_dalek::{Signature, Signer, SigningKey};
+use sha2::{Digest, Sha256};
+
+use crate::codec::edit::{encode_edit_with_options, EncodeOptions};
+use crate::codec::primitives::{Reader, Writer};
+use crate::collections::FxHashSet;
+use crate::error::{DecodeError, EncodeError, SignatureError};
+use crate::limits::{MAGIC_SIGNATURES, MAX_AUTHORS};
+use crate::model::{Edit, Id};
+use crate::prelude::*;
+
+pub use crate::identity::{KeyRegistry, KeyResolver};
+
+/// Domain separator prepended to the hash before signing, so an edit
+/// signature cannot be replayed as a signature over other 32-byte data.
+const SIGNING_DOMAIN: &[u8] = b"grc20:edit-signature:";
+
+/// Returns the SHA-256 of the canonical encoding of `edit`.
+///
+/// This is the hash authors sign; it does not depend on the layout or
+/// compression the edit is published with.
+pub fn canonical_hash(edit: &Edit<'_>) -> Result<[u8; 32], EncodeError> {
+    let bytes = encode_edit_with_options(edit, EncodeOptions::canonical())?;
+    Ok(Sha256::digest(&bytes).into())
+}
+
+fn signing_message(hash: &[u8; 32]) -> [u8; 53] {
+    let mut message = [0u8; 53];
+    message[..SIGNING_DOMAIN.len()].copy_from_slice(SIGNING_DOMAIN);
+    message[SIGNING_DOMAIN.len()..].copy_from_slice(hash);
+    message
+}
+
+/// One author's signature over an edit's canonical hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthorSignature {
+    /// The signing author, one of the edit's `authors`.
+    pub author: Id,
+    /// The Ed25519 signature bytes.
+    pub signature: [u8; 64],
+}
+
+/// An edit together with its detached author signatures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedEdit<'a> {
+    pub edit: Edit<'a>,
+    pub signatures: Vec<AuthorSignature>,
+}
+
+impl<'a> SignedEdit<'a> {
+    /// Wraps an unsigned edit.
+    pub fn new(edit: Edit<'a>) -> Self {
+        Self { edit, signatures: Vec::new() }
+    }
+
+    /// Wraps an edit with signatures decoded from [`decode_signatures`].
+    pub fn with_signatures(edit: Edit<'a>, signatures: Vec<AuthorSignature>) -> Self {
+        Self { edit, signatures }
+    }
+
+    /// Adds `author`'s signature made with `key`.
+    ///
+    /// Fails if `author` is not in the edit's `authors` or has already signed.
+    pub fn sign(mut self, author: Id, key: &SigningKey) -> Result<Self, SignatureError> {
+        if !self.edit.authors.contains(&author) {
+            return Err(SignatureError::NotAnAuthor { author });
+        }
+        if self.signatures.iter().any(|s| s.author == author) {
+            return Err(SignatureError::DuplicateSignature { author });
+        }
+        let hash = canonical_hash(&self.edit)?;
+        let signature = key.sign(&signing_message(&hash)).to_bytes();
+        self.signatures.push(AuthorSignature { author, signature });
+        Ok(self)
+    }
+
+    /// Returns the authors that have not signed yet, in `authors` order.
+    pub fn missing_signers(&self) -> Vec<Id> {
+        let signed: FxHashSet<Id> = self.signatures.iter().map(|s| s.author).collect();
+        self.edit.authors.iter().copied().filter(|a| !signed.contains(a)).collect()
+    }
+}
+
+/// Verifies that every author of the edit signed it, and nobody else.
+///
+/// Each entry in `authors` needs exactly one signature by a key the
+/// resolver returns for that author at the edit's `created_at`. Signatures
+/// by non-authors and repeated signatures are rejected rather than ignored,
+/// so a signature list cannot be padded.
+pub fn verify_all<R: KeyResolver + ?Sized>(signed: &SignedEdit<'_>, resolver: &R) -> Result<(), SignatureError> {
+    let authors: FxHashSet<Id> = signed.edit.authors.iter().copied().collect();
+    let mut seen = FxHashSet::default();
+    for s in &signed.signatures {
+        if !authors.contains(&s.author) {
+            return Err(SignatureError::NotAnAuthor { author: s.author });
+        }
+        if !seen.insert(s.author) {
+            return Err(SignatureError::DuplicateSignature { author: s.author });
+        }
+    }
+    if let Some(author) = signed.edit.authors.iter().find(|a| !seen.contains(*a)) {
+        return Err(SignatureError::MissingSignature { author: *author });
+    }
+
+    let message = signing_message(&canonical_hash(&signed.edit)?);
+    for s in &signed.signatures {
+        let keys = resolver.resolve(&s.author, signed.edit.created_at)?;
+        if keys.is_empty() {
+            return Err(SignatureError::UnknownKey { author: s.author });
+        }
+        let signature = Signature::from_bytes(&s.signature);
+        if !keys.iter().any(|key| key.verify_strict(&message, &signature).is_ok()) {
+            return Err(SignatureError::InvalidSignature { author: s.author });
+        }
+    }
+    Ok(())
+}
+
+/// Encodes a signature list: `GRC2SG` magic, a varint count, then
+/// `author (16 bytes) + signature (64 bytes)` per entry.
+pub fn encode_signatures(signatures: &[AuthorSignature]) -> Vec<u8> {
+    let mut writer = Writer::with_capacity(MAGIC_SIGNATURES.len() + 1 + signatures.len() * 80);
+    writer.write_bytes(MAGIC_SIGNATURES);
+    writer.write_varint(signatures.len() as u64);
+    for s in signatures {
+        writer.write_id(&s.author);
+        writer.write_bytes(&s.signature);
+    }
+    writer.into_bytes()
+}
+
+/// Decodes a signature list written by [`encode_signatures`].
+pub fn decode_signatures(input: &[u8]) -> Result<Vec<AuthorSignature>, DecodeError> {
+    let mut reader = Reader::new(input);
+    let magic = reader.read_bytes(MAGIC_SIGNATURES.len(), "magic")?;
+    if magic != MAGIC_SIGNATURES {
+        let mut found = [0u8; 4];
+        found.copy_from_slice(&magic[..4]);
+        return Err(DecodeError::InvalidMagic { found });
+    }
+    let count = reader.read_varint("signature_count")?;
+    if count > MAX_AUTHORS as u64 {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "signatures",
+            len: count as usize,
+            max: MAX_AUTHORS,
+        });
+    }
+    let mut signatures = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let author = reader.read_id("signature_author")?;
+        let mut signature = [0u8; 64];
+        signature.copy_from_slice(reader.read_bytes(64, "signature")?);
+        signatures.push(AuthorSignature { author, signature });
+    }
+    if !reader.is_empty() {
+        return Err(DecodeError::MalformedEncoding { context: "trailing bytes after signatures" });
+    }
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::encode_edit_with_options;
+    use crate::model::EditBuilder;
+
+    const ALICE: Id = [1u8; 16];
+    const BOB: Id = [2u8; 16];
+
+    fn keys() -> (SigningKey, SigningKey, KeyRegistry) {
+        let alice = SigningKey::from_bytes(&[7u8; 32]);
+        let bob = SigningKey::from_bytes(&[8u8; 32]);
+        let mut registry = KeyRegistry::new();
+        registry.insert(ALICE, alice.verifying_key());
+        registry.insert(BOB, bob.verifying_key());
+        (alice, bob, registry)
+    }
+
+    fn edit() -> Edit<'static> {
+        EditBuilder::new([9u8; 16])
+            .author(ALICE)
+            .author(BOB)
+            .create_entity([3u8; 16], |e| e.text([10u8; 16], "Alice", None).text([11u8; 16], "x", None))
+            .build()
+    }
+
+    #[test]
+    fn test_canonical_hash_ignores_layout() {
+        let edit = edit();
+        let mut reordered = edit.clone();
+        reordered.authors.reverse();
+        assert_eq!(canonical_hash(&edit).unwrap(), canonical_hash(&reordered).unwrap());
+
+        let columnar = encode_edit_with_options(&edit, EncodeOptions::columnar()).unwrap();
+        let decoded = crate::codec::edit::decode_edit(&columnar).unwrap();
+        assert_eq!(canonical_hash(&decoded).unwrap(), canonical_hash(&edit).unwrap());
+    }
+
+    #[test]
+    fn test_verify_all_requires_every_author() {
+        let (alice, bob, registry) = keys();
+        let signed = SignedEdit::new(edit()).sign(ALICE, &alice).unwrap();
+        assert_eq!(signed.missing_signers(), vec![BOB]);
+        assert_eq!(verify_all(&signed, &registry), Err(SignatureError::MissingSignature { author: BOB }));
+
+        let signed = signed.sign(BOB, &bob).unwrap();
+        assert!(signed.missing_signers().is_empty());
+        assert_eq!(verify_all(&signed, &registry), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_all_rejects_bad_signatures() {
+        let (alice, bob, registry) = keys();
+        let signed = SignedEdit::new(edit()).sign(ALICE, &alice).unwrap().sign(BOB, &bob).unwrap();
+
+        // Signed with the wrong key
+        let mut forged = SignedEdit::new(edit()).sign(ALICE, &alice).unwrap().sign(BOB, &alice).unwrap();
+        assert_eq!(verify_all(&forged, &registry), Err(SignatureError::InvalidSignature { author: BOB }));
+
+        // Edit changed after signing
+        forged = signed.clone();
+        forged.edit.name = "changed".into();
+        assert_eq!(verify_all(&forged, &registry), Err(SignatureError::InvalidSignature { author: ALICE }));
+
+        // Padding with a repeated signature or a non-author
+        forged = signed.clone();
+        forged.signatures.push(signed.signatures[0]);
+        assert_eq!(verify_all(&forged, &registry), Err(SignatureError::DuplicateSignature { author: ALICE }));
+        forged.signatures.pop();
+        forged.signatures.push(AuthorSignature { author: [5u8; 16], signature: [0u8; 64] });
+        assert_eq!(verify_all(&forged, &registry), Err(SignatureError::NotAnAuthor { author: [5u8; 16] }));
+
+        let mut partial = KeyRegistry::new();
+        partial.insert(ALICE, alice.verifying_key());
+        assert_eq!(verify_all(&signed, &partial), Err(SignatureError::UnknownKey { author: BOB }));
+
+        assert_eq!(
+            SignedEdit::new(edit()).sign([5u8; 16], &alice).unwrap_err(),
+            SignatureError::NotAnAuthor { author: [5u8; 16] }
+        );
+        assert_eq!(signed.sign(ALICE, &alice).unwrap_err(), SignatureError::DuplicateSignature { author: ALICE });
+    }
+
+    #[test]
+    fn test_verify_all_with_resolver() {
+        struct Offline;
+        impl KeyResolver for Offline {
+            fn resolve(&self, _: &Id, _: i64) -> Result<Vec<ed25519_dalek::VerifyingKey>, SignatureError> {
+                Err(SignatureError::Resolver("directory unreachable".into()))
+            }
+        }
+
+        let (alice, _, _) = keys();
+        let rotated = SigningKey::from_bytes(&[9u8; 32]);
+        let mut edit = edit();
+        edit.authors = vec![ALICE];
+        edit.created_at = 1_000;
+        let signed = SignedEdit::new(edit).sign(ALICE, &alice).unwrap();
+
+        // Alice rotated to a new key at 500; the old key no longer counts
+        let mut registry = KeyRegistry::new();
+        registry.insert_bounded(ALICE, alice.verifying_key(), None, Some(500));
+        registry.insert_bounded(ALICE, rotated.verifying_key(), Some(500), None);
+        assert_eq!(verify_all(&signed, &registry), Err(SignatureError::InvalidSignature { author: ALICE }));
+
+        let mut early = signed.edit.clone();
+        early.created_at = 100;
+        let early = SignedEdit::new(early).sign(ALICE, &alice).unwrap();
+        assert_eq!(verify_all(&early, &registry), Ok(()));
+
+        let mut future = KeyRegistry::new();
+        future.insert_bounded(ALICE, alice.verifying_key(), Some(2_000), None);
+        assert_eq!(verify_all(&signed, &future), Err(SignatureError::UnknownKey { author: ALICE }));
+        assert!(matches!(verify_all(&signed, &Offline), Err(SignatureError::Resolver(_))));
+    }
+
+    #[test]
+    fn test_signatures_roundtrip() {
+        let (alice, bob, registry) = keys();
+        let signed = SignedEdit::new(edit()).sign(ALICE, &alice).unwrap().sign(BOB, &bob).unwrap();
+        let bytes = encode_signatures(&signed.signatures);
+        assert_eq!(bytes.len(), 6 + 1 + 2 * 80);
+        let decoded = decode_signatures(&bytes).unwrap();
+        assert_eq!(decoded, signed.signatures);
+        assert!(verify_all(&SignedEdit::with_signatures(edit(), decoded), &registry).is_ok());
+
+        assert!(matches!(decode_signatures(&bytes[..bytes.len() - 1]), Err(DecodeError::UnexpectedEof { .. })));
+        assert!(matches!(decode_signatures(b"GRC2SX\x00"), Err(DecodeError::InvalidMagic { .. })));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(decode_signatures(&trailing), Err(DecodeError::MalformedEncoding { .. })));
+    }
+}
@@ -0,0 +1,478 @@
+//! Space snapshots (`GRC2SNAP`).
+//!
+//! A snapshot serializes a materialized [`Graph`] so a new node can
+//! bootstrap from it instead of replaying the whole edit history. Objects
+//! are written in ID order, so equal graphs produce identical bytes.
+//!
+//! ```text
+//! Snapshot {
+//!   magic: "GRC2SNAP"
+//!   version: u8
+//!   ids: varint count + ID[]                 // every ID outside values
+//!   languages: varint count + ID[]           // value dictionaries, as in edits
+//!   units: varint count + ID[]
+//!   properties: varint count + (id_index, data_type)[]
+//!   entities: varint count + Entity[]
+//!   relations: varint count + Relation[]
+//!   value_ref_slots: varint count + (Slot, id_index)[]
+//!   value_refs: varint count + (id_index, slot_index)[]
+//! }
+//! ```
+//!
+//! Values use the edit value encoding (spec Section 6.5) against the
+//! snapshot's `languages` and `units` dictionaries. A property appears once
+//! per data type it has been written with.
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::codec::value::{decode_value, encode_value};
+use crate::collections::FxHashMap;
+use crate::error::{DecodeError, EncodeError};
+use crate::limits::{MAGIC_SNAPSHOT, MAX_DICT_SIZE, MAX_POSITION_LEN};
+use crate::model::{DataType, DictionaryBuilder, Id, WireDictionaries};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, Relation, ValueKey, ValueRefSlot};
+
+/// Current snapshot format version.
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+// Entity flags
+const ENTITY_DELETED: u8 = 0x01;
+const ENTITY_RESERVED_MASK: u8 = !ENTITY_DELETED;
+
+// Relation flags
+const REL_DELETED: u8 = 0x01;
+const REL_FROM_IS_VALUE_REF: u8 = 0x02;
+const REL_TO_IS_VALUE_REF: u8 = 0x04;
+const REL_HAS_POSITION: u8 = 0x08;
+const REL_HAS_FROM_SPACE: u8 = 0x10;
+const REL_HAS_FROM_VERSION: u8 = 0x20;
+const REL_HAS_TO_SPACE: u8 = 0x40;
+const REL_HAS_TO_VERSION: u8 = 0x80;
+
+// Value ref slot flags
+const SLOT_HAS_LANGUAGE: u8 = 0x01;
+const SLOT_HAS_SPACE: u8 = 0x02;
+const SLOT_RESERVED_MASK: u8 = !(SLOT_HAS_LANGUAGE | SLOT_HAS_SPACE);
+
+// =============================================================================
+// ENCODING
+// =============================================================================
+
+/// Interning table for IDs and property slots.
+#[derive(Default)]
+struct SnapshotDicts {
+    ids: Vec<Id>,
+    id_indices: FxHashMap<Id, usize>,
+    properties: Vec<(usize, DataType)>,
+    property_indices: FxHashMap<(Id, DataType), usize>,
+    values: DictionaryBuilder,
+}
+
+impl SnapshotDicts {
+    fn id(&mut self, id: Id) -> u64 {
+        let next = self.ids.len();
+        let index = *self.id_indices.entry(id).or_insert(next);
+        if index == next {
+            self.ids.push(id);
+        }
+        index as u64
+    }
+
+    fn property(&mut self, id: Id, data_type: DataType) -> u64 {
+        if let Some(&index) = self.property_indices.get(&(id, data_type)) {
+            return index as u64;
+        }
+        let id_index = self.id(id) as usize;
+        let index = self.properties.len();
+        self.properties.push((id_index, data_type));
+        self.property_indices.insert((id, data_type), index);
+        index as u64
+    }
+}
+
+/// Serializes a graph into a `GRC2SNAP` snapshot.
+pub fn write_snapshot(graph: &Graph) -> Result<Vec<u8>, EncodeError> {
+    let mut dicts = SnapshotDicts::default();
+    let mut body = Writer::new();
+
+    let mut entities: Vec<(&Id, &Entity)> = graph.entities.iter().collect();
+    entities.sort_unstable_by_key(|(id, _)| **id);
+    body.write_varint(entities.len() as u64);
+    for (id, entity) in entities {
+        body.write_varint(dicts.id(*id));
+        body.write_byte(if entity.deleted { ENTITY_DELETED } else { 0 });
+        body.write_varint(entity.values.len() as u64);
+        for (key, value) in &entity.values {
+            body.write_varint(dicts.property(key.property, value.data_type()));
+            encode_value(&mut body, value, &mut dicts.values)?;
+        }
+    }
+
+    let mut relations: Vec<(&Id, &Relation)> = graph.relations.iter().collect();
+    relations.sort_unstable_by_key(|(id, _)| **id);
+    body.write_varint(relations.len() as u64);
+    for (id, relation) in relations {
+        write_relation(&mut body, &mut dicts, id, relation);
+    }
+
+    let mut slots: Vec<(&ValueRefSlot, &Id)> = graph.value_ref_slots.iter().collect();
+    slots.sort_unstable_by_key(|(slot, _)| slot_sort_key(slot));
+    let mut slot_indices = FxHashMap::default();
+    body.write_varint(slots.len() as u64);
+    for (index, (slot, id)) in slots.iter().enumerate() {
+        write_slot(&mut body, &mut dicts, slot);
+        body.write_varint(dicts.id(**id));
+        slot_indices.insert(**slot, index);
+    }
+
+    let mut value_refs: Vec<(&Id, &ValueRefSlot)> = graph.value_refs.iter().collect();
+    value_refs.sort_unstable_by_key(|(id, _)| **id);
+    body.write_varint(value_refs.len() as u64);
+    for (id, slot) in value_refs {
+        let slot_index = slot_indices.get(slot).ok_or(EncodeError::InvalidInput {
+            context: "value ref bound to a slot without a mapping",
+        })?;
+        body.write_varint(dicts.id(*id));
+        body.write_varint(*slot_index as u64);
+    }
+
+    let values = dicts.values.build();
+    let mut writer = Writer::with_capacity(body.len() + dicts.ids.len() * 16 + 64);
+    writer.write_bytes(MAGIC_SNAPSHOT);
+    writer.write_byte(SNAPSHOT_VERSION);
+    writer.write_id_vec(&dicts.ids);
+    writer.write_id_vec(&values.languages);
+    writer.write_id_vec(&values.units);
+    writer.write_varint(dicts.properties.len() as u64);
+    for (id_index, data_type) in &dicts.properties {
+        writer.write_varint(*id_index as u64);
+        writer.write_byte(*data_type as u8);
+    }
+    writer.write_bytes(body.as_bytes());
+    Ok(writer.into_bytes())
+}
+
+fn write_relation(writer: &mut Writer, dicts: &mut SnapshotDicts, id: &Id, relation: &Relation) {
+    let optional = [
+        (relation.from_space, REL_HAS_FROM_SPACE),
+        (relation.from_version, REL_HAS_FROM_VERSION),
+        (relation.to_space, REL_HAS_TO_SPACE),
+        (relation.to_version, REL_HAS_TO_VERSION),
+    ];
+    let mut flags = 0;
+    if relation.deleted {
+        flags |= REL_DELETED;
+    }
+    if relation.from_is_value_ref {
+        flags |= REL_FROM_IS_VALUE_REF;
+    }
+    if relation.to_is_value_ref {
+        flags |= REL_TO_IS_VALUE_REF;
+    }
+    if relation.position.is_some() {
+        flags |= REL_HAS_POSITION;
+    }
+    for (pin, flag) in optional {
+        if pin.is_some() {
+            flags |= flag;
+        }
+    }
+
+    writer.write_varint(dicts.id(*id));
+    writer.write_byte(flags);
+    writer.write_varint(dicts.id(relation.relation_type));
+    writer.write_varint(dicts.id(relation.from));
+    writer.write_varint(dicts.id(relation.to));
+    writer.write_varint(dicts.id(relation.entity));
+    if let Some(position) = &relation.position {
+        writer.write_string(position);
+    }
+    for (pin, _) in optional {
+        if let Some(pin) = pin {
+            writer.write_varint(dicts.id(pin));
+        }
+    }
+}
+
+fn write_slot(writer: &mut Writer, dicts: &mut SnapshotDicts, slot: &ValueRefSlot) {
+    let mut flags = 0;
+    if slot.language.is_some() {
+        flags |= SLOT_HAS_LANGUAGE;
+    }
+    if slot.space.is_some() {
+        flags |= SLOT_HAS_SPACE;
+    }
+    writer.write_byte(flags);
+    writer.write_varint(dicts.id(slot.entity));
+    writer.write_varint(dicts.id(slot.property));
+    if let Some(language) = slot.language {
+        writer.write_varint(dicts.id(language));
+    }
+    if let Some(space) = slot.space {
+        writer.write_varint(dicts.id(space));
+    }
+}
+
+fn slot_sort_key(slot: &ValueRefSlot) -> (Id, Id, Option<Id>, Option<Id>) {
+    (slot.entity, slot.property, slot.language, slot.space)
+}
+
+// =============================================================================
+// DECODING
+// =============================================================================
+
+/// Reads a `GRC2SNAP` snapshot back into a graph.
+pub fn read_snapshot(input: &[u8]) -> Result<Graph, DecodeError> {
+    let mut reader = Reader::new(input);
+    let magic = reader.read_bytes(MAGIC_SNAPSHOT.len(), "magic")?;
+    if magic != MAGIC_SNAPSHOT {
+        return Err(DecodeError::MalformedEncoding { context: "invalid snapshot magic" });
+    }
+    let version = reader.read_byte("version")?;
+    if version != SNAPSHOT_VERSION {
+        return Err(DecodeError::UnsupportedVersion { version });
+    }
+
+    let ids = read_ids(&mut reader, "ids")?;
+    let values = WireDictionaries {
+        languages: read_ids(&mut reader, "languages")?,
+        units: read_ids(&mut reader, "units")?,
+        ..WireDictionaries::default()
+    };
+    let property_count = read_count(&mut reader, 2, "properties")?;
+    if property_count > MAX_DICT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "properties",
+            len: property_count,
+            max: MAX_DICT_SIZE,
+        });
+    }
+    reader.charge_items::<(Id, DataType)>(property_count, "properties")?;
+    let mut properties = Vec::with_capacity(property_count);
+    for _ in 0..property_count {
+        let id = lookup(&ids, &mut reader, "property")?;
+        let dt_byte = reader.read_byte("data_type")?;
+        let data_type = DataType::from_u8(dt_byte).ok_or(DecodeError::InvalidDataType { data_type: dt_byte })?;
+        properties.push((id, data_type));
+    }
+
+    let mut graph = Graph::new();
+
+    let entity_count = read_count(&mut reader, 3, "entities")?;
+    reader.charge_items::<(Id, Entity)>(entity_count, "entities")?;
+    graph.entities.reserve(entity_count);
+    for _ in 0..entity_count {
+        let id = lookup(&ids, &mut reader, "entity")?;
+        let flags = reader.read_byte("entity_flags")?;
+        if flags & ENTITY_RESERVED_MASK != 0 {
+            return Err(DecodeError::ReservedBitsSet { context: "entity_flags" });
+        }
+        let mut entity = Entity { deleted: flags & ENTITY_DELETED != 0, ..Entity::default() };
+        let value_count = read_count(&mut reader, 2, "values")?;
+        reader.charge_items::<(ValueKey, crate::model::Value<'static>)>(value_count, "values")?;
+        for _ in 0..value_count {
+            let index = reader.read_varint("property")? as usize;
+            let &(property, data_type) = properties.get(index).ok_or(DecodeError::IndexOutOfBounds {
+                dict: "properties",
+                index,
+                size: properties.len(),
+            })?;
+            let value = decode_value(&mut reader, data_type, &values)?;
+            reader.charge(owned_payload_len(&value), "values")?;
+            entity.values.insert(ValueKey::of(property, &value), value.into_owned());
+        }
+        if graph.entities.insert(id, entity).is_some() {
+            return Err(DecodeError::DuplicateDictionaryEntry { dict: "entities", id });
+        }
+    }
+
+    let relation_count = read_count(&mut reader, 6, "relations")?;
+    reader.charge_items::<(Id, Relation)>(relation_count, "relations")?;
+    graph.relations.reserve(relation_count);
+    for _ in 0..relation_count {
+        let (id, relation) = read_relation(&mut reader, &ids)?;
+        if graph.relations.insert(id, relation).is_some() {
+            return Err(DecodeError::DuplicateDictionaryEntry { dict: "relations", id });
+        }
+    }
+
+    let slot_count = read_count(&mut reader, 4, "value_ref_slots")?;
+    reader.charge_items::<(ValueRefSlot, Id)>(slot_count.saturating_mul(2), "value_ref_slots")?;
+    let mut slots = Vec::with_capacity(slot_count);
+    for _ in 0..slot_count {
+        let slot = read_slot(&mut reader, &ids)?;
+        let id = lookup(&ids, &mut reader, "value_ref")?;
+        graph.value_ref_slots.insert(slot, id);
+        slots.push(slot);
+    }
+
+    let value_ref_count = read_count(&mut reader, 2, "value_refs")?;
+    reader.charge_items::<(Id, ValueRefSlot)>(value_ref_count, "value_refs")?;
+    for _ in 0..value_ref_count {
+        let id = lookup(&ids, &mut reader, "value_ref")?;
+        let index = reader.read_varint("slot")? as usize;
+        let slot = *slots.get(index).ok_or(DecodeError::IndexOutOfBounds {
+            dict: "value_ref_slots",
+            index,
+            size: slots.len(),
+        })?;
+        graph.value_refs.insert(id, slot);
+    }
+
+    if !reader.is_empty() {
+        return Err(DecodeError::MalformedEncoding { context: "trailing bytes after snapshot" });
+    }
+    Ok(graph)
+}
+
+fn read_relation(reader: &mut Reader<'_>, ids: &[Id]) -> Result<(Id, Relation), DecodeError> {
+    let id = lookup(ids, reader, "relation")?;
+    let flags = reader.read_byte("relation_flags")?;
+    let relation_type = lookup(ids, reader, "relation_type")?;
+    let from = lookup(ids, reader, "from")?;
+    let to = lookup(ids, reader, "to")?;
+    let entity = lookup(ids, reader, "relation_entity")?;
+    let position = if flags & REL_HAS_POSITION != 0 {
+        Some(reader.read_string(MAX_POSITION_LEN, "position")?)
+    } else {
+        None
+    };
+    let mut pin = |flag: u8, field: &'static str| -> Result<Option<Id>, DecodeError> {
+        if flags & flag != 0 { lookup(ids, reader, field).map(Some) } else { Ok(None) }
+    };
+    let from_space = pin(REL_HAS_FROM_SPACE, "from_space")?;
+    let from_version = pin(REL_HAS_FROM_VERSION, "from_version")?;
+    let to_space = pin(REL_HAS_TO_SPACE, "to_space")?;
+    let to_version = pin(REL_HAS_TO_VERSION, "to_version")?;
+    Ok((id, Relation {
+        relation_type,
+        from,
+        from_is_value_ref: flags & REL_FROM_IS_VALUE_REF != 0,
+        to,
+        to_is_value_ref: flags & REL_TO_IS_VALUE_REF != 0,
+        entity,
+        position,
+        from_space,
+        from_version,
+        to_space,
+        to_version,
+        deleted: flags & REL_DELETED != 0,
+    }))
+}
+
+fn read_slot(reader: &mut Reader<'_>, ids: &[Id]) -> Result<ValueRefSlot, DecodeError> {
+    let flags = reader.read_byte("slot_flags")?;
+    if flags & SLOT_RESERVED_MASK != 0 {
+        return Err(DecodeError::ReservedBitsSet { context: "slot_flags" });
+    }
+    let entity = lookup(ids, reader, "slot_entity")?;
+    let property = lookup(ids, reader, "slot_property")?;
+    let language = if flags & SLOT_HAS_LANGUAGE != 0 { Some(lookup(ids, reader, "slot_language")?) } else { None };
+    let space = if flags & SLOT_HAS_SPACE != 0 { Some(lookup(ids, reader, "slot_space")?) } else { None };
+    Ok(ValueRefSlot { entity, property, language, space })
+}
+
+/// Reads a count and checks that the remaining input could hold that many
+/// entries of at least `min_size` bytes, which bounds the allocation.
+fn read_count(reader: &mut Reader<'_>, min_size: usize, field: &'static str) -> Result<usize, DecodeError> {
+    let count = reader.read_varint(field)? as usize;
+    if count.saturating_mul(min_size) > reader.remaining_len() {
+        return Err(DecodeError::UnexpectedEof { context: field });
+    }
+    Ok(count)
+}
+
+fn read_ids(reader: &mut Reader<'_>, field: &'static str) -> Result<Vec<Id>, DecodeError> {
+    let count = read_count(reader, 16, field)?;
+    reader.charge_items::<Id>(count, field)?;
+    let mut ids = Vec::with_capacity(count);
+    for _ in 0..count {
+        ids.push(reader.read_id(field)?);
+    }
+    Ok(ids)
+}
+
+fn lookup(ids: &[Id], reader: &mut Reader<'_>, field: &'static str) -> Result<Id, DecodeError> {
+    let index = reader.read_varint(field)? as usize;
+    ids.get(index).copied().ok_or(DecodeError::IndexOutOfBounds { dict: "ids", index, size: ids.len() })
+}
+
+/// Bytes a borrowed value copies when made owned.
+fn owned_payload_len(value: &crate::model::Value<'_>) -> usize {
+    use crate::model::{DecimalMantissa, Value};
+    match value {
+        Value::Text { value, .. } => value.len(),
+        Value::Bytes(b) => b.len(),
+        Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => s.len(),
+        Value::Decimal { mantissa: DecimalMantissa::Big(b), .. } => b.len(),
+        Value::Embedding { data, .. } => data.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder, Op};
+
+    fn make_graph() -> Graph {
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| {
+                e.text([10u8; 16], "Alice", None)
+                    .text([10u8; 16], "Alicia", Some([20u8; 16]))
+                    .int64([11u8; 16], 30, Some([21u8; 16]))
+                    .date([12u8; 16], "1990-05-15Z")
+                    .point([13u8; 16], 13.4, 52.5, None)
+            })
+            .create_entity([4u8; 16], |e| e.bool([11u8; 16], true))
+            .delete_entity([4u8; 16])
+            .create_relation(|r| {
+                r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([6u8; 16]).position("a").to_space([8u8; 16])
+            })
+            .create_relation(|r| r.id([9u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to_value_ref([30u8; 16]))
+            .delete_relation([9u8; 16])
+            .build();
+        let mut graph = Graph::new();
+        graph.apply_edit(&edit);
+        for id in [[30u8; 16], [31u8; 16]] {
+            graph.apply_op(&Op::CreateValueRef(CreateValueRef {
+                id,
+                entity: [3u8; 16],
+                property: [11u8; 16],
+                language: None,
+                space: Some([8u8; 16]),
+            }));
+        }
+        graph
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let graph = make_graph();
+        let bytes = write_snapshot(&graph).unwrap();
+        assert_eq!(&bytes[..8], MAGIC_SNAPSHOT);
+        assert_eq!(read_snapshot(&bytes).unwrap(), graph);
+    }
+
+    #[test]
+    fn test_snapshot_deterministic() {
+        let graph = make_graph();
+        let copy = read_snapshot(&write_snapshot(&graph).unwrap()).unwrap();
+        assert_eq!(write_snapshot(&copy).unwrap(), write_snapshot(&graph).unwrap());
+        assert_eq!(read_snapshot(&write_snapshot(&Graph::new()).unwrap()).unwrap(), Graph::new());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_malformed() {
+        let bytes = write_snapshot(&make_graph()).unwrap();
+        for len in [0, 8, bytes.len() / 2, bytes.len() - 1] {
+            assert!(read_snapshot(&bytes[..len]).is_err());
+        }
+        let mut bad = bytes.clone();
+        bad[8] = SNAPSHOT_VERSION + 1;
+        assert!(matches!(read_snapshot(&bad), Err(DecodeError::UnsupportedVersion { .. })));
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(read_snapshot(&trailing).is_err());
+    }
+}
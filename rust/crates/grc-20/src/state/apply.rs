@@ -0,0 +1,373 @@
+//! Op application (spec Sections 3 and 4.2.1).
+
+use crate::model::{
+    relation_entity_id, CreateEntity, CreateRelation, CreateValueRef, Edit, Id, Op, PropertyValue,
+    UnsetLanguage, UnsetRelationField, UnsetValue, UpdateEntity, UpdateRelation, Value,
+};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, Relation, ValueKey, ValueRefSlot};
+
+/// Callback for changes to visible value slots.
+///
+/// Called with the entity, the slot, the previously visible value, and the
+/// now visible value.
+pub(crate) type ValueObserver<'o> =
+    dyn FnMut(Id, &ValueKey, Option<&Value<'static>>, Option<&Value<'static>>) + 'o;
+
+impl Graph {
+    /// Applies every op of an edit in order.
+    ///
+    /// Edits must be applied in log order; within an edit, later ops win.
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) {
+        self.apply_edit_observed(edit, None);
+    }
+
+    /// Applies an edit, reporting value changes to `observer`, updating
+    /// views, recording history if enabled, and notifying subscribers.
+    pub(crate) fn apply_edit_observed(&mut self, edit: &Edit<'_>, mut observer: Option<&mut ValueObserver<'_>>) {
+        let mut touched = Vec::new();
+        #[cfg(feature = "std")]
+        let mut before = (!self.subscribers.is_empty()).then(super::subscribe::Before::default);
+        let track = self.history.is_some() || self.has_views();
+        for op in &edit.ops {
+            if track {
+                self.touched_ids(op, &mut touched);
+            }
+            #[cfg(feature = "std")]
+            if let Some(before) = before.as_mut() {
+                before.record(self, op);
+            }
+            self.apply_op_observed(op, observer.as_deref_mut());
+        }
+        if self.has_views() {
+            self.refresh_views(&touched);
+        }
+        if self.history.is_some() {
+            self.record_history(edit, touched);
+        }
+        #[cfg(feature = "std")]
+        if let Some(before) = before {
+            self.notify(edit, before);
+        }
+    }
+
+    /// Applies a single op.
+    ///
+    /// Ops that the spec resolves as ignored (an ID already used by another
+    /// kind of object, updates or deletes of missing objects, updates to
+    /// tombstoned objects, re-creating an existing relation) leave the graph
+    /// unchanged.
+    pub fn apply_op(&mut self, op: &Op<'_>) {
+        let mut touched = Vec::new();
+        if self.has_views() {
+            self.touched_ids(op, &mut touched);
+        }
+        self.apply_op_observed(op, None);
+        if !touched.is_empty() {
+            self.refresh_views(&touched);
+        }
+    }
+
+    /// Applies an op, reporting every change to a visible value slot to
+    /// `observer`.
+    pub(crate) fn apply_op_observed(&mut self, op: &Op<'_>, observer: Option<&mut ValueObserver<'_>>) {
+        match op {
+            Op::CreateEntity(ce) => self.create_entity(ce, observer),
+            Op::UpdateEntity(ue) => self.update_entity(ue, observer),
+            Op::DeleteEntity(de) => {
+                if let Some(entity) = self.entities.get_mut(&de.id) {
+                    if let (false, Some(observer)) = (entity.deleted, observer) {
+                        for (key, value) in &entity.values {
+                            observer(de.id, key, Some(value), None);
+                        }
+                    }
+                    entity.deleted = true;
+                }
+            }
+            Op::RestoreEntity(re) => {
+                if let Some(entity) = self.entities.get_mut(&re.id) {
+                    if let (true, Some(observer)) = (entity.deleted, observer) {
+                        for (key, value) in &entity.values {
+                            observer(re.id, key, None, Some(value));
+                        }
+                    }
+                    entity.deleted = false;
+                }
+            }
+            Op::CreateRelation(cr) => self.create_relation(cr),
+            Op::UpdateRelation(ur) => self.update_relation(ur),
+            Op::DeleteRelation(dr) => {
+                if let Some(relation) = self.relations.get_mut(&dr.id) {
+                    relation.deleted = true;
+                }
+            }
+            Op::RestoreRelation(rr) => {
+                if let Some(relation) = self.relations.get_mut(&rr.id) {
+                    relation.deleted = false;
+                }
+            }
+            Op::CreateValueRef(cvr) => self.create_value_ref(cvr),
+            // Extension ops carry no state this crate can interpret
+            Op::Unknown(_) => {}
+        }
+    }
+
+    fn is_relation_or_value_ref(&self, id: &Id) -> bool {
+        self.relations.contains_key(id) || self.value_refs.contains_key(id)
+    }
+
+    /// Runs `f` on the live entity for an upsert, creating it if needed,
+    /// and keeps the value index and observer in step.
+    fn upsert_entity(&mut self, id: Id, observer: Option<&mut ValueObserver<'_>>, f: impl FnOnce(&mut Entity)) {
+        if self.is_relation_or_value_ref(&id) {
+            return;
+        }
+        let entity = self.entities.entry(id).or_default();
+        if entity.deleted {
+            return;
+        }
+        let before = observer.as_ref().map(|_| entity.values.clone());
+        match self.indexes.as_deref_mut() {
+            Some(indexes) => {
+                indexes.unindex_values(id, entity);
+                f(entity);
+                indexes.index_values(id, entity);
+            }
+            None => f(entity),
+        }
+        if let (Some(before), Some(observer)) = (before, observer) {
+            for (key, old) in before.iter().filter(|(key, _)| !entity.values.contains_key(key)) {
+                observer(id, key, Some(old), None);
+            }
+            for (key, value) in &entity.values {
+                let old = before.get(key);
+                if old != Some(value) {
+                    observer(id, key, old, Some(value));
+                }
+            }
+        }
+    }
+
+    fn create_entity(&mut self, ce: &CreateEntity<'_>, observer: Option<&mut ValueObserver<'_>>) {
+        self.upsert_entity(ce.id, observer, |entity| set_values(entity, &ce.values));
+    }
+
+    /// UpdateEntity on a missing entity is ignored (no implicit create).
+    fn update_entity(&mut self, ue: &UpdateEntity<'_>, observer: Option<&mut ValueObserver<'_>>) {
+        if !self.entities.contains_key(&ue.id) {
+            return;
+        }
+        self.upsert_entity(ue.id, observer, |entity| {
+            unset_values(entity, &ue.unset_values);
+            set_values(entity, &ue.set_properties);
+        });
+    }
+
+    fn create_relation(&mut self, cr: &CreateRelation<'_>) {
+        if self.relations.contains_key(&cr.id)
+            || self.entities.contains_key(&cr.id)
+            || self.value_refs.contains_key(&cr.id)
+        {
+            return;
+        }
+        let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+        if entity == cr.id {
+            return;
+        }
+        // The reified entity is created if needed; an existing one (even a
+        // deleted one) is reused as-is
+        if !self.is_relation_or_value_ref(&entity) {
+            self.entities.entry(entity).or_default();
+        }
+        let relation = Relation {
+            relation_type: cr.relation_type,
+            from: cr.from,
+            from_is_value_ref: cr.from_is_value_ref,
+            to: cr.to,
+            to_is_value_ref: cr.to_is_value_ref,
+            entity,
+            position: cr.position.as_ref().map(|p| p.to_string()),
+            from_space: cr.from_space,
+            from_version: cr.from_version,
+            to_space: cr.to_space,
+            to_version: cr.to_version,
+            deleted: false,
+        };
+        if let Some(indexes) = self.indexes.as_deref_mut() {
+            indexes.index_relation(cr.id, &relation);
+        }
+        self.relations.insert(cr.id, relation);
+    }
+
+    fn update_relation(&mut self, ur: &UpdateRelation<'_>) {
+        let Some(relation) = self.relations.get_mut(&ur.id).filter(|r| !r.deleted) else {
+            return;
+        };
+        for field in &ur.unset {
+            match field {
+                UnsetRelationField::FromSpace => relation.from_space = None,
+                UnsetRelationField::FromVersion => relation.from_version = None,
+                UnsetRelationField::ToSpace => relation.to_space = None,
+                UnsetRelationField::ToVersion => relation.to_version = None,
+                UnsetRelationField::Position => relation.position = None,
+            }
+        }
+        if ur.from_space.is_some() {
+            relation.from_space = ur.from_space;
+        }
+        if ur.from_version.is_some() {
+            relation.from_version = ur.from_version;
+        }
+        if ur.to_space.is_some() {
+            relation.to_space = ur.to_space;
+        }
+        if ur.to_version.is_some() {
+            relation.to_version = ur.to_version;
+        }
+        if let Some(position) = &ur.position {
+            relation.position = Some(position.to_string());
+        }
+    }
+
+    fn create_value_ref(&mut self, cvr: &CreateValueRef) {
+        if self.entities.contains_key(&cvr.id) || self.relations.contains_key(&cvr.id) {
+            return;
+        }
+        let slot = ValueRefSlot {
+            entity: cvr.entity,
+            property: cvr.property,
+            language: cvr.language,
+            space: cvr.space,
+        };
+        // LWW keyed by slot: a newer ref for the slot replaces the older one
+        if let Some(previous) = self.value_ref_slots.insert(slot, cvr.id) {
+            if previous != cvr.id && self.value_refs.get(&previous) == Some(&slot) {
+                self.value_refs.remove(&previous);
+            }
+        }
+        self.value_refs.insert(cvr.id, slot);
+    }
+}
+
+fn set_values(entity: &mut Entity, values: &[PropertyValue<'_>]) {
+    for pv in values {
+        entity.values.insert(ValueKey::of(pv.property, &pv.value), pv.value.clone().into_owned());
+    }
+}
+
+fn unset_values(entity: &mut Entity, unset: &[UnsetValue]) {
+    for uv in unset {
+        match uv.language {
+            UnsetLanguage::All => entity.values.retain(|key, _| key.property != uv.property),
+            UnsetLanguage::English => {
+                entity.values.remove(&ValueKey::new(uv.property));
+            }
+            UnsetLanguage::Specific(language) => {
+                entity.values.remove(&ValueKey::with_language(uv.property, language));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use crate::state::ObjectState;
+
+    const NAME: Id = [10u8; 16];
+    const AGE: Id = [11u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    #[test]
+    fn test_upsert_and_unset() {
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text(NAME, "Alice", None).text(NAME, "Alicia", Some(GERMAN)))
+            .create_entity([3u8; 16], |e| e.int64(AGE, 30, None))
+            .update_entity([3u8; 16], |u| u.unset_english(NAME).set_int64(AGE, 31, None))
+            .build();
+        let mut graph = Graph::new();
+        graph.apply_edit(&edit);
+
+        let entity = graph.entity(&[3u8; 16]).unwrap();
+        assert_eq!(entity.value(&ValueKey::new(NAME)), None);
+        assert!(entity.value(&ValueKey::with_language(NAME, GERMAN)).is_some());
+        assert_eq!(entity.value(&ValueKey::new(AGE)), Some(&Value::Int64 { value: 31, unit: None }));
+
+        graph.apply_edit(&EditBuilder::new([2u8; 16]).update_entity([3u8; 16], |u| u.unset_all(NAME)).build());
+        assert_eq!(graph.entity(&[3u8; 16]).unwrap().values.len(), 1);
+    }
+
+    #[test]
+    fn test_tombstone_absorbs_updates() {
+        let mut graph = Graph::new();
+        graph.apply_edit(&EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.int64(AGE, 30, None))
+            .delete_entity([3u8; 16])
+            .create_entity([3u8; 16], |e| e.int64(AGE, 40, None))
+            .update_entity([3u8; 16], |u| u.set_int64(AGE, 50, None))
+            .build());
+        assert_eq!(graph.state(&[3u8; 16]), ObjectState::Deleted);
+
+        graph.apply_edit(&EditBuilder::new([2u8; 16]).restore_entity([3u8; 16]).build());
+        assert_eq!(graph.value(&[3u8; 16], &ValueKey::new(AGE)), Some(&Value::Int64 { value: 30, unit: None }));
+
+    }
+
+    #[test]
+    fn test_missing_entities_ignored() {
+        // Updating or deleting an unknown entity is a no-op (spec Section 8.2)
+        let mut graph = Graph::new();
+        graph.apply_edit(&EditBuilder::new([1u8; 16])
+            .update_entity([4u8; 16], |u| u.set_int64(AGE, 50, None))
+            .delete_entity([5u8; 16])
+            .build());
+        assert_eq!(graph.state(&[4u8; 16]), ObjectState::NotFound);
+        assert_eq!(graph.state(&[5u8; 16]), ObjectState::NotFound);
+
+        // So a later create goes through
+        graph.apply_edit(&EditBuilder::new([2u8; 16]).create_entity([5u8; 16], |e| e.int64(AGE, 1, None)).build());
+        assert!(matches!(graph.state(&[5u8; 16]), ObjectState::Entity(_)));
+    }
+
+    #[test]
+    fn test_relations() {
+        let mut graph = Graph::new();
+        graph.apply_edit(&EditBuilder::new([1u8; 16])
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([6u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .update_relation([7u8; 16], |r| r.set_position("a").set_to_version([8u8; 16]))
+            .build());
+
+        let relation = graph.relation(&[7u8; 16]).unwrap();
+        assert_eq!(relation.relation_type, [5u8; 16]);
+        assert_eq!(relation.position.as_deref(), Some("a"));
+        assert_eq!(relation.to_version, Some([8u8; 16]));
+        // The reified entity exists
+        assert!(graph.entity(&relation_entity_id(&[7u8; 16])).is_some());
+
+        graph.apply_edit(&EditBuilder::new([2u8; 16])
+            .delete_relation([7u8; 16])
+            .update_relation([7u8; 16], |r| r.unset_position())
+            .restore_relation([7u8; 16])
+            .build());
+        assert_eq!(graph.relation(&[7u8; 16]).unwrap().position.as_deref(), Some("a"));
+
+        // Shared namespace: an entity cannot take a relation's ID
+        graph.apply_edit(&EditBuilder::new([3u8; 16]).create_entity([7u8; 16], |e| e.int64(AGE, 1, None)).build());
+        assert!(matches!(graph.state(&[7u8; 16]), ObjectState::Relation(_)));
+    }
+
+    #[test]
+    fn test_value_ref_lww_by_slot() {
+        let slot_op = |id: Id| {
+            Op::CreateValueRef(CreateValueRef { id, entity: [3u8; 16], property: AGE, language: None, space: None })
+        };
+        let mut graph = Graph::new();
+        graph.apply_op(&slot_op([30u8; 16]));
+        graph.apply_op(&slot_op([31u8; 16]));
+
+        assert!(graph.value_ref(&[30u8; 16]).is_none());
+        assert_eq!(graph.value_ref(&[31u8; 16]).unwrap().property, AGE);
+    }
+}
@@ -0,0 +1,383 @@
+//! Compact in-memory storage.
+//!
+//! A [`Graph`] keeps every entity's values in their own `BTreeMap`, with a
+//! separate allocation for each TEXT and EMBEDDING value. For large spaces
+//! most of that is overhead: the same names, labels, and descriptions
+//! recur across entities, and a map node is allocated even for one value.
+//!
+//! [`CompactStore`] is a [`Store`] that instead keeps each entity's values
+//! in one exact-sized slice, TEXT values in a shared arena where equal
+//! strings are stored once, and embeddings in a contiguous pool. Use it
+//! with [`StoreGraph`](crate::state::store::StoreGraph); lookups rebuild
+//! owned [`Entity`] values on demand.
+//!
+//! ```
+//! use grc_20::state::compact::CompactStore;
+//! use grc_20::state::store::StoreGraph;
+//! use grc_20::state::ValueKey;
+//! use grc_20::EditBuilder;
+//!
+//! let mut graph = StoreGraph::new(CompactStore::new());
+//! let edit = EditBuilder::new([1u8; 16]).create_entity([2u8; 16], |e| e.text([3u8; 16], "Berlin", None)).build();
+//! graph.apply_edit(&edit).unwrap();
+//! assert!(graph.value(&[2u8; 16], &ValueKey::new([3u8; 16])).unwrap().is_some());
+//! ```
+
+use alloc::borrow::Cow;
+use core::hash::BuildHasher;
+
+use crate::collections::FxHashMap;
+use crate::error::StoreError;
+use crate::model::{EmbeddingSubType, Id, Value};
+use crate::prelude::*;
+use crate::state::store::{Store, StoreBatch};
+use crate::state::{Entity, Graph, Relation, ReplayProgress, ValueKey, ValueRefSlot};
+
+/// Pool garbage below this many bytes is never collected.
+const MIN_GARBAGE: usize = 64 * 1024;
+
+/// A [`Store`] in memory that shares TEXT values and pools embeddings.
+#[derive(Debug, Clone, Default)]
+pub struct CompactStore {
+    entities: FxHashMap<Id, CompactEntity>,
+    relations: FxHashMap<Id, Relation>,
+    value_refs: FxHashMap<Id, ValueRefSlot>,
+    value_ref_slots: FxHashMap<ValueRefSlot, Id>,
+    texts: Pool,
+    embeddings: Pool,
+    progress: ReplayProgress,
+}
+
+#[derive(Debug, Clone)]
+struct CompactEntity {
+    /// Sorted by slot key.
+    values: Box<[(ValueKey, CompactValue)]>,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone)]
+enum CompactValue {
+    /// The language is in the slot key.
+    Text(u32),
+    Embedding { sub_type: EmbeddingSubType, dims: usize, data: u32 },
+    Other(Value<'static>),
+}
+
+impl CompactStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies the state of `graph` into a new store.
+    pub fn from_graph(graph: &Graph) -> Self {
+        let mut store = Self::new();
+        for (id, entity) in &graph.entities {
+            let entity = store.compact(entity);
+            store.entities.insert(*id, entity);
+        }
+        store.relations = graph.relations.clone();
+        store.value_refs = graph.value_refs.clone();
+        store.value_ref_slots = graph.value_ref_slots.clone();
+        store
+    }
+
+    /// Returns the stored state as a graph.
+    pub fn to_graph(&self) -> Graph {
+        let mut graph = Graph::new();
+        for (id, entity) in &self.entities {
+            graph.entities.insert(*id, self.expand(entity));
+        }
+        graph.relations = self.relations.clone();
+        graph.value_refs = self.value_refs.clone();
+        graph.value_ref_slots = self.value_ref_slots.clone();
+        graph
+    }
+
+    /// Returns the number of distinct TEXT values and the bytes they take.
+    pub fn text_pool(&self) -> (usize, usize) {
+        (self.texts.live(), self.texts.bytes.len())
+    }
+
+    /// Returns the number of stored embeddings and the bytes they take.
+    pub fn embedding_pool(&self) -> (usize, usize) {
+        (self.embeddings.live(), self.embeddings.bytes.len())
+    }
+
+    fn compact(&mut self, entity: &Entity) -> CompactEntity {
+        let values = entity
+            .values
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::Text { value, .. } => CompactValue::Text(self.texts.intern(value.as_bytes())),
+                    Value::Embedding { sub_type, dims, data } => {
+                        CompactValue::Embedding { sub_type: *sub_type, dims: *dims, data: self.embeddings.intern(data) }
+                    }
+                    other => CompactValue::Other(other.clone()),
+                };
+                (*key, value)
+            })
+            .collect();
+        CompactEntity { values, deleted: entity.deleted }
+    }
+
+    fn expand(&self, entity: &CompactEntity) -> Entity {
+        let values = entity
+            .values
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    CompactValue::Text(text) => {
+                        // Only ever interned from a `str`
+                        let text = core::str::from_utf8(self.texts.get(*text)).expect("text pool holds UTF-8");
+                        Value::Text { value: Cow::Owned(text.to_string()), language: key.language }
+                    }
+                    CompactValue::Embedding { sub_type, dims, data } => Value::Embedding {
+                        sub_type: *sub_type,
+                        dims: *dims,
+                        data: Cow::Owned(self.embeddings.get(*data).to_vec()),
+                    },
+                    CompactValue::Other(value) => value.clone(),
+                };
+                (*key, value)
+            })
+            .collect();
+        Entity { values, deleted: entity.deleted }
+    }
+
+    fn release(&mut self, entity: &CompactEntity) {
+        for (_, value) in entity.values.iter() {
+            match value {
+                CompactValue::Text(text) => self.texts.release(*text),
+                CompactValue::Embedding { data, .. } => self.embeddings.release(*data),
+                CompactValue::Other(_) => {}
+            }
+        }
+    }
+}
+
+impl Store for CompactStore {
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError> {
+        Ok(self.entities.get(id).map(|e| self.expand(e)))
+    }
+
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError> {
+        Ok(self.relations.get(id).cloned())
+    }
+
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError> {
+        Ok(self.value_refs.get(id).copied())
+    }
+
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError> {
+        Ok(self.value_ref_slots.get(slot).copied())
+    }
+
+    fn progress(&self) -> Result<ReplayProgress, StoreError> {
+        Ok(self.progress)
+    }
+
+    fn write(&mut self, batch: StoreBatch) -> Result<(), StoreError> {
+        for (id, entity) in batch.entities {
+            // Intern the new values before releasing the old, so unchanged
+            // values keep their place in the pools
+            let old = match entity {
+                Some(entity) => {
+                    let entity = self.compact(&entity);
+                    self.entities.insert(id, entity)
+                }
+                None => self.entities.remove(&id),
+            };
+            if let Some(old) = old {
+                self.release(&old);
+            }
+        }
+        for (id, relation) in batch.relations {
+            match relation {
+                Some(relation) => self.relations.insert(id, relation),
+                None => self.relations.remove(&id),
+            };
+        }
+        for (id, slot) in batch.value_refs {
+            match slot {
+                Some(slot) => self.value_refs.insert(id, slot),
+                None => self.value_refs.remove(&id),
+            };
+        }
+        for (slot, id) in batch.slots {
+            match id {
+                Some(id) => self.value_ref_slots.insert(slot, id),
+                None => self.value_ref_slots.remove(&slot),
+            };
+        }
+        self.texts.collect_garbage();
+        self.embeddings.collect_garbage();
+        self.progress = batch.progress;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// POOLS
+// =============================================================================
+
+/// Reference-counted byte strings in one buffer, stored once per content.
+///
+/// Handles index `spans` and stay valid until released by every holder;
+/// freed spans are reused, and their bytes reclaimed once garbage
+/// outweighs live data.
+#[derive(Debug, Clone, Default)]
+struct Pool {
+    bytes: Vec<u8>,
+    spans: Vec<Span>,
+    free: Vec<u32>,
+    /// Content hash -> handle. A colliding content is stored unshared.
+    index: FxHashMap<u64, u32>,
+    garbage: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: usize,
+    len: u32,
+    refs: u32,
+}
+
+impl Pool {
+    fn hash(bytes: &[u8]) -> u64 {
+        rustc_hash::FxBuildHasher.hash_one(bytes)
+    }
+
+    fn get(&self, handle: u32) -> &[u8] {
+        let span = self.spans[handle as usize];
+        &self.bytes[span.start..span.start + span.len as usize]
+    }
+
+    fn live(&self) -> usize {
+        self.spans.len() - self.free.len()
+    }
+
+    fn intern(&mut self, bytes: &[u8]) -> u32 {
+        let hash = Self::hash(bytes);
+        if let Some(&handle) = self.index.get(&hash) {
+            if self.get(handle) == bytes {
+                self.spans[handle as usize].refs += 1;
+                return handle;
+            }
+        }
+        let len = u32::try_from(bytes.len()).expect("pooled value exceeds 4 GiB");
+        let span = Span { start: self.bytes.len(), len, refs: 1 };
+        self.bytes.extend_from_slice(bytes);
+        let handle = match self.free.pop() {
+            Some(handle) => {
+                self.spans[handle as usize] = span;
+                handle
+            }
+            None => {
+                self.spans.push(span);
+                (self.spans.len() - 1) as u32
+            }
+        };
+        self.index.entry(hash).or_insert(handle);
+        handle
+    }
+
+    fn release(&mut self, handle: u32) {
+        let span = &mut self.spans[handle as usize];
+        span.refs -= 1;
+        if span.refs > 0 {
+            return;
+        }
+        self.garbage += span.len as usize;
+        let hash = Self::hash(self.get(handle));
+        if self.index.get(&hash) == Some(&handle) {
+            self.index.remove(&hash);
+        }
+        self.free.push(handle);
+    }
+
+    /// Moves live bytes together if garbage outweighs them.
+    fn collect_garbage(&mut self) {
+        if self.garbage < MIN_GARBAGE || self.garbage * 2 < self.bytes.len() {
+            return;
+        }
+        let mut order: Vec<u32> = (0..self.spans.len() as u32).filter(|h| self.spans[*h as usize].refs > 0).collect();
+        order.sort_unstable_by_key(|h| self.spans[*h as usize].start);
+        let mut end = 0;
+        for handle in order {
+            let span = &mut self.spans[handle as usize];
+            let len = span.len as usize;
+            self.bytes.copy_within(span.start..span.start + len, end);
+            span.start = end;
+            end += len;
+        }
+        self.bytes.truncate(end);
+        self.bytes.shrink_to_fit();
+        self.garbage = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Edit, EditBuilder};
+    use crate::state::store::StoreGraph;
+
+    const NAME: Id = [10u8; 16];
+    const VECTOR: Id = [11u8; 16];
+
+    fn edits() -> Vec<Edit<'static>> {
+        let embedding = Value::Embedding { sub_type: EmbeddingSubType::Int8, dims: 4, data: vec![1, 2, 3, 4].into() };
+        vec![
+            EditBuilder::new([1u8; 16])
+                .create_entity([2u8; 16], |e| e.text(NAME, "Berlin", None).text(NAME, "Berlin", Some([9u8; 16])))
+                .create_entity([3u8; 16], |e| {
+                    e.text(NAME, "Berlin", None).value(VECTOR, embedding).int64([12u8; 16], 1, None)
+                })
+                .create_relation_simple([4u8; 16], [2u8; 16], [3u8; 16], [5u8; 16])
+                .build(),
+            EditBuilder::new([2u8; 16])
+                .update_entity([2u8; 16], |u| u.set_text(NAME, "Berlin, Germany", None))
+                .delete_entity([3u8; 16])
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_matches_in_memory_graph() {
+        let edits = edits();
+        let mut expected = Graph::new();
+        let mut graph = StoreGraph::new(CompactStore::new());
+        for edit in &edits {
+            expected.apply_edit(edit);
+            graph.apply_edit(edit).unwrap();
+        }
+        assert_eq!(graph.store().to_graph(), expected);
+        assert_eq!(CompactStore::from_graph(&expected).to_graph(), expected);
+
+        // "Berlin" is stored once for three slots, then held by two
+        let store = graph.into_store();
+        assert_eq!(store.text_pool().0, 2);
+        assert_eq!(store.embedding_pool(), (1, 4));
+    }
+
+    #[test]
+    fn test_pool_reclaims_garbage() {
+        let mut pool = Pool::default();
+        let big = vec![7u8; MIN_GARBAGE];
+        let kept = pool.intern(b"kept");
+        let dropped = pool.intern(&big);
+        assert_eq!(pool.intern(&big), dropped);
+        pool.release(dropped);
+        pool.collect_garbage();
+        assert_eq!(pool.bytes.len(), 4 + MIN_GARBAGE);
+        pool.release(dropped);
+        pool.collect_garbage();
+        assert_eq!(pool.bytes.len(), 4);
+        assert_eq!(pool.get(kept), b"kept");
+        // The freed handle is reused
+        assert_eq!(pool.intern(b"new"), dropped);
+        assert_eq!(pool.get(dropped), b"new");
+    }
+}
@@ -0,0 +1,290 @@
+//! Copy-on-write views of a graph for what-if application.
+//!
+//! [`Graph::fork`] returns a [`Fork`] that borrows the graph and copies an
+//! object only when an edit applied to the fork touches it, so an editor
+//! can preview a candidate edit against a large space without cloning it
+//! or mutating it:
+//!
+//! ```
+//! use grc_20::state::{Graph, ValueKey};
+//! use grc_20::EditBuilder;
+//!
+//! let (berlin, population) = ([2u8; 16], [3u8; 16]);
+//! let mut graph = Graph::new();
+//! graph.apply_edit(&EditBuilder::new([1u8; 16]).create_entity(berlin, |e| e.int64(population, 3, None)).build());
+//!
+//! let mut fork = graph.fork();
+//! fork.apply_edit(&EditBuilder::new([4u8; 16]).update_entity(berlin, |u| u.unset_all(population)).build());
+//! assert!(fork.value(&berlin, &ValueKey::new(population)).is_none());
+//! assert_eq!(fork.changes().len(), 1);
+//!
+//! // The base is unchanged
+//! assert!(graph.value(&berlin, &ValueKey::new(population)).is_some());
+//! ```
+
+use alloc::collections::BTreeSet;
+
+use crate::model::{Edit, Id, Value};
+use crate::prelude::*;
+use crate::state::store::Scratch;
+use crate::state::{relation_order, Entity, Graph, ObjectState, Relation, ValueKey, ValueRefSlot};
+
+/// A graph with edits applied on top of a borrowed base, from
+/// [`Graph::fork`].
+///
+/// Lookups see the forked state. Objects the fork's edits touched are
+/// answered from the fork's own copies, and everything else from the base.
+#[derive(Debug, Clone)]
+pub struct Fork<'g> {
+    base: &'g Graph,
+    overlay: Scratch,
+}
+
+/// An object whose state differs between the base and a [`Fork`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectChange<'f> {
+    pub id: Id,
+    pub before: ObjectState<'f>,
+    pub after: ObjectState<'f>,
+}
+
+/// A value slot whose value differs between the base and a [`Fork`], on an
+/// entity that is live in both.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueChange<'f> {
+    pub entity: Id,
+    pub key: ValueKey,
+    pub before: Option<&'f Value<'static>>,
+    pub after: Option<&'f Value<'static>>,
+}
+
+impl Graph {
+    /// Returns a copy-on-write view of the graph to apply candidate edits
+    /// to. Indexes, history, and the subtype relation are not carried over.
+    pub fn fork(&self) -> Fork<'_> {
+        Fork { base: self, overlay: Scratch::default() }
+    }
+}
+
+impl<'g> Fork<'g> {
+    /// Returns the graph the fork was made from.
+    pub fn base(&self) -> &'g Graph {
+        self.base
+    }
+
+    /// Applies an edit to the fork.
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) {
+        for op in &edit.ops {
+            // Loading from a graph cannot fail
+            let _ = self.overlay.load_op(self.base, op);
+        }
+        self.overlay.graph.apply_edit(edit);
+    }
+
+    /// Returns the resolved state of an object ID.
+    pub fn state(&self, id: &Id) -> ObjectState<'_> {
+        if self.overlay.ids.contains(id) {
+            self.overlay.graph.state(id)
+        } else {
+            self.base.state(id)
+        }
+    }
+
+    /// Returns a live (not deleted) entity.
+    pub fn entity(&self, id: &Id) -> Option<&Entity> {
+        match self.state(id) {
+            ObjectState::Entity(entity) => Some(entity),
+            _ => None,
+        }
+    }
+
+    /// Returns a live (not deleted) relation.
+    pub fn relation(&self, id: &Id) -> Option<&Relation> {
+        match self.state(id) {
+            ObjectState::Relation(relation) => Some(relation),
+            _ => None,
+        }
+    }
+
+    /// Returns the slot a value ref is bound to.
+    pub fn value_ref(&self, id: &Id) -> Option<&ValueRefSlot> {
+        match self.state(id) {
+            ObjectState::ValueRef(slot) => Some(slot),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a live entity in the given slot.
+    pub fn value(&self, entity: &Id, key: &ValueKey) -> Option<&Value<'static>> {
+        self.entity(entity)?.value(key)
+    }
+
+    /// Iterates over live entities, in no particular order.
+    pub fn entities(&self) -> impl Iterator<Item = (&Id, &Entity)> {
+        let base = self.base.entities().filter(|(id, _)| !self.overlay.ids.contains(*id));
+        base.chain(self.overlay.graph.entities())
+    }
+
+    /// Iterates over live relations, in no particular order.
+    pub fn relations(&self) -> impl Iterator<Item = (&Id, &Relation)> {
+        let base = self.base.relations().filter(|(id, _)| !self.overlay.ids.contains(*id));
+        base.chain(self.overlay.graph.relations())
+    }
+
+    /// Returns the live relations from `entity` (optionally of one type),
+    /// in spec order.
+    pub fn outgoing(&self, entity: &Id, relation_type: Option<Id>) -> Vec<(&Id, &Relation)> {
+        let mut out: Vec<_> = self
+            .relations()
+            .filter(|(_, r)| r.from == *entity && relation_type.is_none_or(|t| r.relation_type == t))
+            .collect();
+        out.sort_by(|(a_id, a), (b_id, b)| relation_order(a_id, a, b_id, b));
+        out
+    }
+
+    /// Returns the objects whose state the fork's edits changed, sorted by
+    /// ID.
+    pub fn changes(&self) -> Vec<ObjectChange<'_>> {
+        let mut changes: Vec<ObjectChange<'_>> = self
+            .overlay
+            .ids
+            .iter()
+            .map(|id| ObjectChange { id: *id, before: self.base.state(id), after: self.overlay.graph.state(id) })
+            .filter(|c| c.before != c.after)
+            .collect();
+        changes.sort_unstable_by_key(|c| c.id);
+        changes
+    }
+
+    /// Returns the values the fork's edits changed on entities live before
+    /// and after, sorted by entity and slot.
+    pub fn value_changes(&self) -> Vec<ValueChange<'_>> {
+        let mut changes = Vec::new();
+        for change in self.changes() {
+            let (ObjectState::Entity(before), ObjectState::Entity(after)) = (change.before, change.after) else {
+                continue;
+            };
+            let keys: BTreeSet<&ValueKey> = before.values.keys().chain(after.values.keys()).collect();
+            for key in keys {
+                let (old, new) = (before.value(key), after.value(key));
+                if old != new {
+                    changes.push(ValueChange { entity: change.id, key: *key, before: old, after: new });
+                }
+            }
+        }
+        changes
+    }
+
+    /// Returns the forked state as a full graph, e.g. to run
+    /// [`check_integrity`](crate::state::check_integrity) on it. This
+    /// clones the base.
+    pub fn to_graph(&self) -> Graph {
+        let mut graph = Graph::new();
+        graph.entities = self.base.entities.clone();
+        graph.relations = self.base.relations.clone();
+        graph.value_refs = self.base.value_refs.clone();
+        graph.value_ref_slots = self.base.value_ref_slots.clone();
+        let overlay = &self.overlay.graph;
+        for id in &self.overlay.ids {
+            match overlay.entities.get(id) {
+                Some(entity) => graph.entities.insert(*id, entity.clone()),
+                None => graph.entities.remove(id),
+            };
+            match overlay.relations.get(id) {
+                Some(relation) => graph.relations.insert(*id, relation.clone()),
+                None => graph.relations.remove(id),
+            };
+            match overlay.value_refs.get(id) {
+                Some(slot) => graph.value_refs.insert(*id, *slot),
+                None => graph.value_refs.remove(id),
+            };
+        }
+        for slot in &self.overlay.slots {
+            match overlay.value_ref_slots.get(slot) {
+                Some(id) => graph.value_ref_slots.insert(*slot, *id),
+                None => graph.value_ref_slots.remove(slot),
+            };
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder, Op};
+
+    const NAME: Id = [10u8; 16];
+    const PART_OF: Id = [11u8; 16];
+
+    fn base() -> Graph {
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_entity([2u8; 16], |e| e.text(NAME, "Berlin", None))
+                .create_entity([3u8; 16], |e| e.text(NAME, "Germany", None))
+                .create_relation_simple([4u8; 16], [2u8; 16], [3u8; 16], PART_OF)
+                .build(),
+        );
+        graph
+    }
+
+    fn candidate() -> Edit<'static> {
+        EditBuilder::new([5u8; 16])
+            .update_entity([2u8; 16], |u| u.set_text(NAME, "Berlin, Germany", None))
+            .delete_entity([3u8; 16])
+            .create_relation_simple([6u8; 16], [2u8; 16], [7u8; 16], PART_OF)
+            .op(Op::CreateValueRef(CreateValueRef {
+                id: [8u8; 16],
+                entity: [2u8; 16],
+                property: NAME,
+                language: None,
+                space: None,
+            }))
+            .build()
+    }
+
+    #[test]
+    fn test_fork_matches_applied_graph() {
+        let base = base();
+        let mut fork = base.fork();
+        fork.apply_edit(&candidate());
+        let mut expected = base.clone();
+        expected.apply_edit(&candidate());
+
+        assert_eq!(fork.to_graph(), expected);
+        assert_eq!(base, self::base());
+        assert_eq!(fork.entities().count(), expected.entity_count());
+        assert_eq!(fork.relations().count(), expected.relation_count());
+        assert!(fork.entity(&[3u8; 16]).is_none());
+        assert_eq!(fork.value_ref(&[8u8; 16]), expected.value_ref(&[8u8; 16]));
+        let outgoing: Vec<Id> = fork.outgoing(&[2u8; 16], Some(PART_OF)).iter().map(|(id, _)| **id).collect();
+        assert_eq!(outgoing, vec![[4u8; 16], [6u8; 16]]);
+    }
+
+    #[test]
+    fn test_fork_changes() {
+        let base = base();
+        let mut fork = base.fork();
+        fork.apply_edit(&candidate());
+
+        // Entity 2, entity 3, relation 6 and its reified entity, value ref 8
+        let changes = fork.changes();
+        let ids: Vec<Id> = changes.iter().map(|c| c.id).collect();
+        assert_eq!(ids.len(), 5);
+        assert!(ids.contains(&[6u8; 16]) && ids.contains(&[8u8; 16]));
+        let germany = changes.iter().find(|c| c.id == [3u8; 16]).unwrap();
+        assert!(matches!((germany.before, germany.after), (ObjectState::Entity(_), ObjectState::Deleted)));
+
+        let values = fork.value_changes();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].key, ValueKey::new(NAME));
+        assert_eq!(values[0].before, base.value(&[2u8; 16], &ValueKey::new(NAME)));
+
+        // Touching an object without changing it is not a change
+        let mut fork = base.fork();
+        let edit = EditBuilder::new([5u8; 16]).update_entity([2u8; 16], |u| u.set_text(NAME, "Berlin", None)).build();
+        fork.apply_edit(&edit);
+        assert!(fork.changes().is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! Per-object version history and time travel.
+//!
+//! History is opt-in (see [`Graph::enable_history`]). Once enabled, every
+//! edit applied through [`Graph::apply_edit`] (or the sink and replay
+//! variants) records a new version of each object whose state it changed.
+//! Each version stores the full object state, so memory grows with the
+//! number of changes. Ops applied one at a time with [`Graph::apply_op`]
+//! are not recorded.
+//!
+//! Time travel follows the log: [`Graph::at`] returns the state after the
+//! longest prefix of applied edits whose `created_at` is at most the given
+//! timestamp.
+
+use crate::collections::FxHashMap;
+use crate::model::{relation_entity_id, Edit, Id, Op};
+use crate::prelude::*;
+use crate::state::memory::{entity_bytes, relation_bytes, table_bytes};
+use crate::state::{Entity, Graph, Relation, ValueRefSlot};
+
+/// One recorded state of an object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version<T> {
+    /// The edit that produced this state; `None` for the state the history
+    /// started from.
+    pub edit: Option<Id>,
+    /// `created_at` of that edit (`i64::MIN` for the starting state).
+    pub created_at: i64,
+    /// Number of edits applied since history was enabled.
+    pub index: usize,
+    pub state: T,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct History {
+    edit_times: Vec<i64>,
+    /// IDs of the applied edits, in the same order.
+    edits: Vec<Id>,
+    entities: FxHashMap<Id, Vec<Version<Entity>>>,
+    relations: FxHashMap<Id, Vec<Version<Relation>>>,
+    /// `None` once a value ref has been replaced in its slot.
+    value_refs: FxHashMap<Id, Vec<Version<Option<ValueRefSlot>>>>,
+}
+
+impl History {
+    /// Approximate bytes held by the recorded versions.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        fn versions<T>(versions: &Vec<Version<T>>, heap: impl Fn(&T) -> usize) -> usize {
+            versions.capacity() * size_of::<Version<T>>() + versions.iter().map(|v| heap(&v.state)).sum::<usize>()
+        }
+        let entity = |e: &Entity| {
+            let (map, values) = entity_bytes(e);
+            map + values
+        };
+        self.edit_times.capacity() * size_of::<i64>()
+            + self.edits.capacity() * size_of::<Id>()
+            + table_bytes(&self.entities)
+            + self.entities.values().map(|v| versions(v, entity)).sum::<usize>()
+            + table_bytes(&self.relations)
+            + self.relations.values().map(|v| versions(v, relation_bytes)).sum::<usize>()
+            + table_bytes(&self.value_refs)
+            + self.value_refs.values().map(|v| versions(v, |_| 0)).sum::<usize>()
+    }
+}
+
+/// Appends a version unless the state is unchanged.
+fn push_version<T: PartialEq>(versions: &mut Vec<Version<T>>, edit: &Edit<'_>, index: usize, state: T) {
+    if versions.last().is_some_and(|v| v.state == state) {
+        return;
+    }
+    versions.push(Version { edit: Some(edit.id), created_at: edit.created_at, index, state });
+}
+
+fn baseline<T>(state: T) -> Vec<Version<T>> {
+    vec![Version { edit: None, created_at: i64::MIN, index: 0, state }]
+}
+
+/// Returns the last version at or before `index`.
+fn version_at<T>(versions: &[Version<T>], index: usize) -> Option<&T> {
+    versions.iter().rev().find(|v| v.index <= index).map(|v| &v.state)
+}
+
+impl Graph {
+    /// Starts recording history, with the current state as the baseline.
+    pub fn enable_history(&mut self) {
+        let history = History {
+            edit_times: Vec::new(),
+            edits: Vec::new(),
+            entities: self.entities.iter().map(|(id, e)| (*id, baseline(e.clone()))).collect(),
+            relations: self.relations.iter().map(|(id, r)| (*id, baseline(r.clone()))).collect(),
+            value_refs: self.value_refs.iter().map(|(id, s)| (*id, baseline(Some(*s)))).collect(),
+        };
+        self.history = Some(Box::new(history));
+    }
+
+    /// Returns the graph with history recording enabled.
+    pub fn with_history(mut self) -> Self {
+        self.enable_history();
+        self
+    }
+
+    /// Stops recording and drops the recorded history.
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Returns true if history is being recorded.
+    pub fn has_history(&self) -> bool {
+        self.history.is_some()
+    }
+
+    /// Returns the IDs of the edits applied since history was enabled, in
+    /// order; empty if history is not enabled.
+    pub fn applied_edits(&self) -> &[Id] {
+        self.history.as_ref().map_or(&[], |h| h.edits.as_slice())
+    }
+
+    /// Returns the recorded versions of an entity, oldest first.
+    pub fn entity_history(&self, id: &Id) -> &[Version<Entity>] {
+        self.history.as_ref().and_then(|h| h.entities.get(id)).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns the recorded versions of a relation, oldest first.
+    pub fn relation_history(&self, id: &Id) -> &[Version<Relation>] {
+        self.history.as_ref().and_then(|h| h.relations.get(id)).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Reconstructs the state as of `timestamp`.
+    ///
+    /// Returns `None` if history is not enabled. The result has no indexes
+    /// or history of its own.
+    pub fn at(&self, timestamp: i64) -> Option<Graph> {
+        let history = self.history.as_ref()?;
+        let index = history.edit_times.iter().take_while(|t| **t <= timestamp).count();
+
+        let mut graph = Graph::new();
+        for (id, versions) in &history.entities {
+            if let Some(entity) = version_at(versions, index) {
+                graph.entities.insert(*id, entity.clone());
+            }
+        }
+        for (id, versions) in &history.relations {
+            if let Some(relation) = version_at(versions, index) {
+                graph.relations.insert(*id, relation.clone());
+            }
+        }
+        for (id, versions) in &history.value_refs {
+            if let Some(Some(slot)) = version_at(versions, index) {
+                graph.value_refs.insert(*id, *slot);
+                graph.value_ref_slots.insert(*slot, *id);
+            }
+        }
+        Some(graph)
+    }
+
+    /// Returns the state of an entity at the end of an edit: `None` if
+    /// history is not enabled or the edit was not applied since, and
+    /// `Some(None)` if the entity did not exist then.
+    pub(crate) fn entity_at_edit(&self, id: &Id, edit: &Id) -> Option<Option<&Entity>> {
+        let history = self.history.as_ref()?;
+        let index = history.edits.iter().position(|e| e == edit)? + 1;
+        Some(history.entities.get(id).and_then(|versions| version_at(versions, index)))
+    }
+
+    /// Collects the IDs whose state `op` may change. Must be called before
+    /// the op is applied.
+    pub(crate) fn touched_ids(&self, op: &Op<'_>, touched: &mut Vec<Id>) {
+        match op {
+            Op::CreateEntity(ce) => touched.push(ce.id),
+            Op::UpdateEntity(ue) => touched.push(ue.id),
+            Op::DeleteEntity(de) => touched.push(de.id),
+            Op::RestoreEntity(re) => touched.push(re.id),
+            Op::CreateRelation(cr) => {
+                touched.push(cr.id);
+                touched.push(cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id)));
+            }
+            Op::UpdateRelation(ur) => touched.push(ur.id),
+            Op::DeleteRelation(dr) => touched.push(dr.id),
+            Op::RestoreRelation(rr) => touched.push(rr.id),
+            Op::CreateValueRef(cvr) => {
+                touched.push(cvr.id);
+                let slot = ValueRefSlot {
+                    entity: cvr.entity,
+                    property: cvr.property,
+                    language: cvr.language,
+                    space: cvr.space,
+                };
+                touched.extend(self.value_ref_slots.get(&slot));
+            }
+            Op::Unknown(_) => {}
+        }
+    }
+
+    /// Records new versions of the touched objects after an edit.
+    pub(crate) fn record_history(&mut self, edit: &Edit<'_>, mut touched: Vec<Id>) {
+        let Some(mut history) = self.history.take() else {
+            return;
+        };
+        history.edit_times.push(edit.created_at);
+        history.edits.push(edit.id);
+        let index = history.edit_times.len();
+        touched.sort_unstable();
+        touched.dedup();
+        for id in touched {
+            if let Some(entity) = self.entities.get(&id) {
+                push_version(history.entities.entry(id).or_default(), edit, index, entity.clone());
+            }
+            if let Some(relation) = self.relations.get(&id) {
+                push_version(history.relations.entry(id).or_default(), edit, index, relation.clone());
+            }
+            let slot = self.value_refs.get(&id).copied();
+            if slot.is_some() || history.value_refs.contains_key(&id) {
+                push_version(history.value_refs.entry(id).or_default(), edit, index, slot);
+            }
+        }
+        self.history = Some(history);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder, Value};
+    use crate::state::{ObjectState, ValueKey};
+
+    const AGE: Id = [11u8; 16];
+    const ALICE: Id = [3u8; 16];
+
+    fn age(graph: &Graph) -> Option<Value<'static>> {
+        graph.value(&ALICE, &ValueKey::new(AGE)).cloned()
+    }
+
+    #[test]
+    fn test_entity_history_and_time_travel() {
+        let mut graph = Graph::new().with_history();
+        graph.apply_edit(&EditBuilder::new([1u8; 16]).created_at(100).create_entity(ALICE, |e| e.int64(AGE, 30, None)).build());
+        graph.apply_edit(&EditBuilder::new([2u8; 16]).created_at(200).update_entity(ALICE, |u| u.set_int64(AGE, 31, None)).build());
+        // A no-op edit records no version
+        graph.apply_edit(&EditBuilder::new([3u8; 16]).created_at(250).update_entity(ALICE, |u| u.set_int64(AGE, 31, None)).build());
+        graph.apply_edit(&EditBuilder::new([4u8; 16]).created_at(300).delete_entity(ALICE).build());
+
+        let versions = graph.entity_history(&ALICE);
+        let edits: Vec<_> = versions.iter().map(|v| v.edit).collect();
+        assert_eq!(edits, vec![Some([1u8; 16]), Some([2u8; 16]), Some([4u8; 16])]);
+        assert!(versions[2].state.deleted);
+
+        assert_eq!(graph.at(50).unwrap().state(&ALICE), ObjectState::NotFound);
+        assert_eq!(age(&graph.at(150).unwrap()), Some(Value::Int64 { value: 30, unit: None }));
+        assert_eq!(age(&graph.at(299).unwrap()), Some(Value::Int64 { value: 31, unit: None }));
+        assert_eq!(graph.at(300).unwrap(), graph);
+        assert!(Graph::new().at(0).is_none());
+    }
+
+    #[test]
+    fn test_relation_and_value_ref_history() {
+        let mut graph = Graph::new();
+        graph.apply_edit(&EditBuilder::new([1u8; 16])
+            .created_at(100)
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from(ALICE).to([4u8; 16]))
+            .build());
+        graph.enable_history();
+        graph.apply_edit(&EditBuilder::new([2u8; 16])
+            .created_at(200)
+            .update_relation([7u8; 16], |r| r.set_position("a"))
+            .build());
+        let slot_edit = |edit: u8, created_at: i64, id: Id| Edit {
+            created_at,
+            ops: vec![Op::CreateValueRef(CreateValueRef { id, entity: ALICE, property: AGE, language: None, space: None })],
+            ..EditBuilder::new([edit; 16]).build()
+        };
+        graph.apply_edit(&slot_edit(3, 300, [30u8; 16]));
+        graph.apply_edit(&slot_edit(4, 400, [31u8; 16]));
+
+        let versions = graph.relation_history(&[7u8; 16]);
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].edit, None);
+        assert_eq!(graph.at(150).unwrap().relation(&[7u8; 16]).unwrap().position, None);
+
+        // The first value ref is bound until the second replaces it
+        let before = graph.at(350).unwrap();
+        assert!(before.value_ref(&[30u8; 16]).is_some());
+        assert!(before.value_ref(&[31u8; 16]).is_none());
+        assert_eq!(graph.at(400).unwrap(), graph);
+        assert!(graph.value_ref(&[30u8; 16]).is_none());
+    }
+}
@@ -0,0 +1,330 @@
+//! Optional secondary indexes on a [`Graph`].
+//!
+//! Indexes are kept up to date as ops are applied. They record tombstoned
+//! objects too; lookups filter on liveness, so deletes and restores need
+//! no index maintenance.
+
+use crate::collections::{FxHashMap, FxHashSet};
+use crate::model::{Id, Value};
+use crate::prelude::*;
+use crate::state::memory::table_bytes;
+use crate::state::{relation_order, Entity, Graph, Relation};
+
+/// Which secondary indexes a [`Graph`] maintains.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexConfig {
+    /// Index relations by their `to` endpoint, for [`Graph::incoming`].
+    pub incoming: bool,
+
+    /// Relation type that assigns types to entities (the Genesis `Types`
+    /// relation), for [`Graph::entities_of_type`].
+    pub types_relation: Option<Id>,
+
+    /// Properties whose TEXT and INT64 values are indexed for exact-match
+    /// lookups.
+    pub value_properties: Vec<Id>,
+}
+
+impl IndexConfig {
+    /// Creates a config with no indexes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables the reverse relation index.
+    pub fn incoming(mut self) -> Self {
+        self.incoming = true;
+        self
+    }
+
+    /// Enables the type index, using `relation_type` as the types relation.
+    pub fn types(mut self, relation_type: Id) -> Self {
+        self.types_relation = Some(relation_type);
+        self
+    }
+
+    /// Enables the value index for a property.
+    pub fn values(mut self, property: Id) -> Self {
+        if !self.value_properties.contains(&property) {
+            self.value_properties.push(property);
+        }
+        self
+    }
+}
+
+/// Indexed form of a value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum IndexKey {
+    Text(String),
+    Int64(i64),
+}
+
+impl IndexKey {
+    fn of(value: &Value<'_>) -> Option<Self> {
+        match value {
+            Value::Text { value, .. } => Some(IndexKey::Text(value.to_string())),
+            Value::Int64 { value, .. } => Some(IndexKey::Int64(*value)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Indexes {
+    config: IndexConfig,
+    value_properties: FxHashSet<Id>,
+    /// `to` -> relation IDs.
+    incoming: FxHashMap<Id, Vec<Id>>,
+    /// Type -> IDs of types relations pointing at it.
+    types: FxHashMap<Id, Vec<Id>>,
+    /// (property, value) -> entities holding it.
+    values: FxHashMap<(Id, IndexKey), FxHashSet<Id>>,
+}
+
+impl Indexes {
+    fn new(config: IndexConfig) -> Self {
+        let value_properties = config.value_properties.iter().copied().collect();
+        Self { config, value_properties, ..Self::default() }
+    }
+
+    pub(crate) fn index_relation(&mut self, id: Id, relation: &Relation) {
+        if self.config.incoming {
+            self.incoming.entry(relation.to).or_default().push(id);
+        }
+        if self.config.types_relation == Some(relation.relation_type) {
+            self.types.entry(relation.to).or_default().push(id);
+        }
+    }
+
+    fn value_keys<'e>(&'e self, entity: &'e Entity) -> impl Iterator<Item = (Id, IndexKey)> + 'e {
+        entity
+            .values
+            .iter()
+            .filter(|(key, _)| self.value_properties.contains(&key.property))
+            .filter_map(|(key, value)| Some((key.property, IndexKey::of(value)?)))
+    }
+
+    /// Approximate bytes held by the indexes.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let ids = |ids: &Vec<Id>| ids.capacity() * size_of::<Id>();
+        let mut bytes = table_bytes(&self.incoming) + self.incoming.values().map(ids).sum::<usize>();
+        bytes += table_bytes(&self.types) + self.types.values().map(ids).sum::<usize>();
+        bytes += table_bytes(&self.values);
+        for ((_, key), entities) in &self.values {
+            if let IndexKey::Text(text) = key {
+                bytes += text.capacity();
+            }
+            bytes += entities.capacity() * 8 / 7 * (size_of::<Id>() + 1);
+        }
+        bytes
+    }
+
+    /// Removes an entity's indexed values, before they change.
+    pub(crate) fn unindex_values(&mut self, id: Id, entity: &Entity) {
+        if self.value_properties.is_empty() {
+            return;
+        }
+        let keys: Vec<_> = self.value_keys(entity).collect();
+        for key in keys {
+            if let Some(ids) = self.values.get_mut(&key) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.values.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Adds an entity's indexed values, after they changed.
+    pub(crate) fn index_values(&mut self, id: Id, entity: &Entity) {
+        if self.value_properties.is_empty() {
+            return;
+        }
+        let keys: Vec<_> = self.value_keys(entity).collect();
+        for key in keys {
+            self.values.entry(key).or_default().insert(id);
+        }
+    }
+}
+
+impl Graph {
+    /// Returns the graph with the given indexes built.
+    pub fn with_indexes(mut self, config: IndexConfig) -> Self {
+        self.set_indexes(config);
+        self
+    }
+
+    /// Builds the given indexes from the current state, replacing any
+    /// previous ones. They are then maintained as ops are applied.
+    pub fn set_indexes(&mut self, config: IndexConfig) {
+        let mut indexes = Indexes::new(config);
+        for (id, relation) in &self.relations {
+            indexes.index_relation(*id, relation);
+        }
+        for (id, entity) in &self.entities {
+            indexes.index_values(*id, entity);
+        }
+        self.indexes = Some(Box::new(indexes));
+    }
+
+    /// Drops all indexes.
+    pub fn clear_indexes(&mut self) {
+        self.indexes = None;
+    }
+
+    /// Returns the index configuration, if indexes are enabled.
+    pub fn index_config(&self) -> Option<&IndexConfig> {
+        self.indexes.as_ref().map(|ix| &ix.config)
+    }
+
+    /// Returns the live relations to `entity` (optionally of one type), in
+    /// spec order.
+    ///
+    /// Uses the reverse index if enabled, and scans all relations otherwise.
+    pub fn incoming(&self, entity: &Id, relation_type: Option<Id>) -> Vec<(&Id, &Relation)> {
+        let matches = |r: &Relation| r.to == *entity && relation_type.is_none_or(|t| r.relation_type == t);
+        let mut out: Vec<_> = match self.indexes.as_ref().filter(|ix| ix.config.incoming) {
+            Some(ix) => ix
+                .incoming
+                .get(entity)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| self.relations.get_key_value(id))
+                .filter(|(_, r)| !r.deleted && matches(r))
+                .collect(),
+            None => self.relations().filter(|(_, r)| matches(r)).collect(),
+        };
+        out.sort_by(|(a_id, a), (b_id, b)| relation_order(a_id, a, b_id, b));
+        out
+    }
+
+    /// Returns the live entities with a live types relation to `type_id`,
+    /// sorted by ID.
+    ///
+    /// Returns `None` if the type index is not enabled (see
+    /// [`IndexConfig::types`]).
+    pub fn entities_of_type(&self, type_id: &Id) -> Option<Vec<Id>> {
+        let ix = self.indexes.as_ref()?;
+        ix.config.types_relation?;
+        let mut out: Vec<Id> = ix
+            .types
+            .get(type_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.relation(id))
+            .map(|r| r.from)
+            .filter(|from| self.entity(from).is_some())
+            .collect();
+        out.sort_unstable();
+        out.dedup();
+        Some(out)
+    }
+
+    /// Returns the live entities with a TEXT value (in any language) equal
+    /// to `text` for `property`, sorted by ID.
+    ///
+    /// Uses the value index if the property is indexed, and scans all
+    /// entities otherwise.
+    pub fn entities_with_text(&self, property: Id, text: &str) -> Vec<Id> {
+        self.entities_with(property, IndexKey::Text(text.to_string()))
+    }
+
+    /// Returns the live entities with an INT64 value equal to `value` for
+    /// `property` (ignoring the unit), sorted by ID.
+    pub fn entities_with_int64(&self, property: Id, value: i64) -> Vec<Id> {
+        self.entities_with(property, IndexKey::Int64(value))
+    }
+
+    fn entities_with(&self, property: Id, key: IndexKey) -> Vec<Id> {
+        let indexed = self.indexes.as_ref().filter(|ix| ix.value_properties.contains(&property));
+        let mut out: Vec<Id> = match indexed {
+            Some(ix) => ix
+                .values
+                .get(&(property, key))
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|id| self.entity(id).is_some())
+                .collect(),
+            None => self
+                .entities()
+                .filter(|(_, e)| e.values_of(property).any(|(_, v)| IndexKey::of(v).as_ref() == Some(&key)))
+                .map(|(id, _)| *id)
+                .collect(),
+        };
+        out.sort_unstable();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Edit, EditBuilder};
+
+    const TYPES: Id = [50u8; 16];
+    const COUNTRY: Id = [51u8; 16];
+    const NEIGHBOR: Id = [52u8; 16];
+    const ISO2: Id = [60u8; 16];
+    const POPULATION: Id = [61u8; 16];
+    const DE: Id = [1u8; 16];
+    const FR: Id = [2u8; 16];
+
+    fn make_graph_edit() -> Edit<'static> {
+        EditBuilder::new([9u8; 16])
+            .create_entity(DE, |e| e.text(ISO2, "DE", None).int64(POPULATION, 83, None))
+            .create_entity(FR, |e| e.text(ISO2, "FR", None).int64(POPULATION, 68, None))
+            .create_relation(|r| r.id([10u8; 16]).relation_type(TYPES).from(DE).to(COUNTRY))
+            .create_relation(|r| r.id([11u8; 16]).relation_type(TYPES).from(FR).to(COUNTRY))
+            .create_relation(|r| r.id([12u8; 16]).relation_type(NEIGHBOR).from(FR).to(DE))
+            .build()
+    }
+
+    fn make_graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.apply_edit(&make_graph_edit());
+        graph
+    }
+
+    fn config() -> IndexConfig {
+        IndexConfig::new().incoming().types(TYPES).values(ISO2).values(POPULATION)
+    }
+
+    #[test]
+    fn test_indexes_match_scans() {
+        let plain = make_graph();
+        let indexed = make_graph().with_indexes(config());
+        assert_eq!(plain.entities_of_type(&COUNTRY), None);
+        assert_eq!(indexed.entities_of_type(&COUNTRY), Some(vec![DE, FR]));
+
+        for graph in [&plain, &indexed] {
+            assert_eq!(graph.entities_with_text(ISO2, "DE"), vec![DE]);
+            assert_eq!(graph.entities_with_int64(POPULATION, 68), vec![FR]);
+            let incoming: Vec<Id> = graph.incoming(&DE, Some(NEIGHBOR)).into_iter().map(|(_, r)| r.from).collect();
+            assert_eq!(incoming, vec![FR]);
+            assert_eq!(graph.incoming(&COUNTRY, None).len(), 2);
+        }
+        // Indexes do not take part in equality
+        assert_eq!(plain, indexed);
+    }
+
+    #[test]
+    fn test_indexes_follow_updates() {
+        let mut graph = Graph::new().with_indexes(config());
+        graph.apply_edit(&make_graph_edit());
+        graph.apply_edit(&EditBuilder::new([8u8; 16])
+            .update_entity(DE, |u| u.set_text(ISO2, "XX", None))
+            .update_entity(FR, |u| u.unset_all(POPULATION))
+            .delete_relation([11u8; 16])
+            .build());
+        assert!(graph.entities_with_text(ISO2, "DE").is_empty());
+        assert_eq!(graph.entities_with_text(ISO2, "XX"), vec![DE]);
+        assert!(graph.entities_with_int64(POPULATION, 68).is_empty());
+        assert_eq!(graph.entities_of_type(&COUNTRY), Some(vec![DE]));
+
+        graph.apply_edit(&EditBuilder::new([7u8; 16]).delete_entity(DE).build());
+        assert!(graph.entities_with_text(ISO2, "XX").is_empty());
+        assert_eq!(graph.entities_of_type(&COUNTRY), Some(vec![]));
+    }
+
+}
@@ -0,0 +1,231 @@
+//! Referential integrity checks on a materialized graph.
+//!
+//! Edits are applied without checking that relation endpoints exist, so a
+//! graph can reference objects that were never created, or were deleted
+//! since. [`check_integrity`] reports such problems for review; none of
+//! them is an error under the protocol.
+
+use alloc::collections::BTreeMap;
+
+use crate::collections::{FxHashMap, FxHashSet};
+use crate::model::id::types_relation_type;
+use crate::model::{derived_uuid, Id};
+use crate::prelude::*;
+use crate::state::{Graph, ObjectState, Relation};
+
+/// A problem found by [`check_integrity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A live relation points at an object that is missing or deleted.
+    /// Endpoints pinned to another space are not checked.
+    DanglingRelation { relation: Id, endpoint: Id },
+    /// The reified entity of a live relation is missing or deleted.
+    MissingRelationEntity { relation: Id, entity: Id },
+    /// A live entity has no `Types` relation. Reified relation entities are
+    /// not reported.
+    Untyped { entity: Id },
+    /// Relations of a hierarchical type form a cycle. `entities` lists the
+    /// cycle in relation order, each pointing at the next and the last at
+    /// the first.
+    Cycle { relation_type: Id, entities: Vec<Id> },
+}
+
+/// Options for [`check_integrity_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityOptions {
+    /// Relation types that must not form cycles. Defaults to the Genesis
+    /// `PartOf` relation type.
+    pub hierarchical_types: Vec<Id>,
+    /// Report entities without a `Types` relation. On by default.
+    pub require_types: bool,
+}
+
+impl Default for IntegrityOptions {
+    fn default() -> Self {
+        Self { hierarchical_types: vec![derived_uuid(b"grc20:genesis:PartOf")], require_types: true }
+    }
+}
+
+impl IntegrityOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the relation types checked for cycles.
+    pub fn hierarchical_types(mut self, relation_types: impl IntoIterator<Item = Id>) -> Self {
+        self.hierarchical_types = relation_types.into_iter().collect();
+        self
+    }
+
+    /// Does not report entities without a `Types` relation.
+    pub fn allow_untyped(mut self) -> Self {
+        self.require_types = false;
+        self
+    }
+}
+
+/// Checks a graph with the default [`IntegrityOptions`].
+pub fn check_integrity(graph: &Graph) -> Vec<IntegrityIssue> {
+    check_integrity_with_options(graph, &IntegrityOptions::default())
+}
+
+/// Checks a graph for dangling relations, missing reified entities,
+/// untyped entities, and cycles in hierarchical relation types.
+///
+/// Issues are grouped by kind in that order, each sorted by ID.
+pub fn check_integrity_with_options(graph: &Graph, options: &IntegrityOptions) -> Vec<IntegrityIssue> {
+    let mut issues = Vec::new();
+    let mut relations: Vec<(&Id, &Relation)> = graph.relations().collect();
+    relations.sort_unstable_by_key(|(id, _)| **id);
+
+    for (id, relation) in &relations {
+        let endpoints = [
+            (relation.from, relation.from_is_value_ref, relation.from_space),
+            (relation.to, relation.to_is_value_ref, relation.to_space),
+        ];
+        for (endpoint, is_value_ref, space) in endpoints {
+            if space.is_none() && !endpoint_exists(graph, &endpoint, is_value_ref) {
+                issues.push(IntegrityIssue::DanglingRelation { relation: **id, endpoint });
+            }
+        }
+    }
+    for (id, relation) in &relations {
+        if graph.entity(&relation.entity).is_none() {
+            issues.push(IntegrityIssue::MissingRelationEntity { relation: **id, entity: relation.entity });
+        }
+    }
+
+    if options.require_types {
+        let types = types_relation_type();
+        let mut typed: FxHashSet<Id> = FxHashSet::default();
+        for (_, relation) in &relations {
+            typed.insert(relation.entity);
+            if relation.relation_type == types {
+                typed.insert(relation.from);
+            }
+        }
+        let mut untyped: Vec<Id> = graph.entities().map(|(id, _)| *id).filter(|id| !typed.contains(id)).collect();
+        untyped.sort_unstable();
+        issues.extend(untyped.into_iter().map(|entity| IntegrityIssue::Untyped { entity }));
+    }
+
+    for &relation_type in &options.hierarchical_types {
+        for entities in find_cycles(&relations, relation_type) {
+            issues.push(IntegrityIssue::Cycle { relation_type, entities });
+        }
+    }
+    issues
+}
+
+fn endpoint_exists(graph: &Graph, id: &Id, is_value_ref: bool) -> bool {
+    if is_value_ref {
+        return graph.value_ref(id).is_some();
+    }
+    matches!(graph.state(id), ObjectState::Entity(_) | ObjectState::Relation(_))
+}
+
+/// Finds cycles among relations of one type by depth-first search, one per
+/// back edge.
+fn find_cycles(relations: &[(&Id, &Relation)], relation_type: Id) -> Vec<Vec<Id>> {
+    let mut edges: BTreeMap<Id, Vec<Id>> = BTreeMap::new();
+    for (_, relation) in relations {
+        if relation.relation_type == relation_type && !relation.from_is_value_ref && !relation.to_is_value_ref {
+            edges.entry(relation.from).or_default().push(relation.to);
+        }
+    }
+    for targets in edges.values_mut() {
+        targets.sort_unstable();
+        targets.dedup();
+    }
+
+    // Nodes on the current path (false) or fully explored (true)
+    let mut visited: FxHashMap<Id, bool> = FxHashMap::default();
+    let mut cycles = Vec::new();
+    for &start in edges.keys() {
+        if visited.contains_key(&start) {
+            continue;
+        }
+        visited.insert(start, false);
+        // (node, index of the next edge to follow)
+        let mut path: Vec<(Id, usize)> = vec![(start, 0)];
+        while let Some(&(node, next)) = path.last() {
+            let Some(&target) = edges.get(&node).and_then(|targets| targets.get(next)) else {
+                visited.insert(node, true);
+                path.pop();
+                continue;
+            };
+            if let Some(last) = path.last_mut() {
+                last.1 += 1;
+            }
+            match visited.get(&target) {
+                None => {
+                    visited.insert(target, false);
+                    path.push((target, 0));
+                }
+                Some(false) => {
+                    let i = path.iter().position(|(n, _)| *n == target).unwrap_or(0);
+                    cycles.push(path[i..].iter().map(|(n, _)| *n).collect());
+                }
+                Some(true) => {}
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_check_integrity() {
+        let types = types_relation_type();
+        let part_of = derived_uuid(b"grc20:genesis:PartOf");
+        let topic = [9u8; 16];
+        let (a, b, c) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([0u8; 16])
+                .create_empty_entity(topic)
+                .create_empty_entity(a)
+                .create_empty_entity(b)
+                .create_empty_entity(c)
+                .create_relation_simple([11u8; 16], a, topic, types)
+                .create_relation_simple([12u8; 16], b, topic, types)
+                .create_relation_simple([13u8; 16], c, topic, types)
+                .create_relation_simple([14u8; 16], topic, topic, types)
+                .create_relation_simple([20u8; 16], a, b, part_of)
+                .create_relation_simple([21u8; 16], b, c, part_of)
+                .create_relation(|r| r.id([22u8; 16]).from(c).to([7u8; 16]).relation_type(part_of).to_space([8u8; 16]))
+                .build(),
+        );
+        assert_eq!(check_integrity(&graph), vec![]);
+
+        // Close the cycle a -> b -> c -> a, dangle a relation, and delete a
+        // reified entity
+        let relation_entity = graph.relation(&[11u8; 16]).unwrap().entity;
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_relation_simple([23u8; 16], c, a, part_of)
+                .create_relation_simple([24u8; 16], a, [5u8; 16], [6u8; 16])
+                .delete_entity(relation_entity)
+                .create_empty_entity([4u8; 16])
+                .build(),
+        );
+        let issues = check_integrity(&graph);
+        assert_eq!(
+            issues,
+            vec![
+                IntegrityIssue::DanglingRelation { relation: [24u8; 16], endpoint: [5u8; 16] },
+                IntegrityIssue::MissingRelationEntity { relation: [11u8; 16], entity: relation_entity },
+                IntegrityIssue::Untyped { entity: [4u8; 16] },
+                IntegrityIssue::Cycle { relation_type: part_of, entities: vec![a, b, c] },
+            ]
+        );
+
+        let options = IntegrityOptions::new().hierarchical_types([]).allow_untyped();
+        assert_eq!(check_integrity_with_options(&graph, &options).len(), 2);
+    }
+}
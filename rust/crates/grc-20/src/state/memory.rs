@@ -0,0 +1,203 @@
+//! Cardinality and memory usage of a [`Graph`], for capacity planning.
+//!
+//! Byte counts are estimates from the sizes of the stored types, the
+//! capacity of hash tables, and the heap data of values. They leave out
+//! allocator overhead and slack, so expect resident memory to be somewhat
+//! higher.
+
+use alloc::collections::BTreeMap;
+
+use crate::collections::{FxHashMap, FxHashSet};
+use crate::model::id::types_relation_type;
+use crate::model::{DecimalMantissa, Id, Value};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, Relation, ValueKey};
+
+/// Entries per B-tree node in the standard library.
+const BTREE_NODE_ENTRIES: usize = 11;
+
+/// Counts and approximate memory usage of a graph, from
+/// [`Graph::memory_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Live entities.
+    pub entities: usize,
+    /// Tombstoned entities.
+    pub deleted_entities: usize,
+    /// Live relations.
+    pub relations: usize,
+    /// Tombstoned relations.
+    pub deleted_relations: usize,
+    /// Value refs.
+    pub value_refs: usize,
+    /// Live entities per type, through live types relations. An entity
+    /// with several types counts once for each.
+    pub entities_by_type: BTreeMap<Id, usize>,
+    /// Values of live entities per property, counting each language of a
+    /// TEXT property.
+    pub values_by_property: BTreeMap<Id, usize>,
+    /// Approximate bytes per structure.
+    pub bytes: MemoryUsage,
+}
+
+/// Approximate bytes held by each structure of a graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The entity table and each entity's value map, without value data.
+    pub entities: usize,
+    /// Heap data of values: text, bytes, big decimals, and embeddings.
+    pub values: usize,
+    /// The relation table and positions.
+    pub relations: usize,
+    /// The value ref tables.
+    pub value_refs: usize,
+    /// Secondary indexes (see [`Graph::set_indexes`]).
+    pub indexes: usize,
+    /// Recorded history (see [`Graph::enable_history`]).
+    pub history: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the sum over all structures.
+    pub fn total(&self) -> usize {
+        self.entities + self.values + self.relations + self.value_refs + self.indexes + self.history
+    }
+}
+
+impl Graph {
+    /// Returns entity counts per type, value counts per property, and the
+    /// approximate memory usage of each structure.
+    ///
+    /// Types are read through the types relation of the type index if one
+    /// is configured (see [`IndexConfig::types`](crate::state::IndexConfig::types)),
+    /// and through the Genesis `Types` relation otherwise.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport {
+            value_refs: self.value_refs.len(),
+            ..MemoryReport::default()
+        };
+        for entity in self.entities.values() {
+            if entity.deleted {
+                report.deleted_entities += 1;
+                continue;
+            }
+            report.entities += 1;
+            for key in entity.values.keys() {
+                *report.values_by_property.entry(key.property).or_default() += 1;
+            }
+        }
+
+        let types = self.index_config().and_then(|c| c.types_relation).unwrap_or_else(types_relation_type);
+        let mut typed = FxHashSet::default();
+        for relation in self.relations.values() {
+            if relation.deleted {
+                report.deleted_relations += 1;
+                continue;
+            }
+            report.relations += 1;
+            if relation.relation_type == types
+                && self.entity(&relation.from).is_some()
+                && typed.insert((relation.from, relation.to))
+            {
+                *report.entities_by_type.entry(relation.to).or_default() += 1;
+            }
+        }
+
+        let (entities, values) = entities_bytes(&self.entities);
+        report.bytes = MemoryUsage {
+            entities,
+            values,
+            relations: relations_bytes(&self.relations),
+            value_refs: table_bytes(&self.value_refs) + table_bytes(&self.value_ref_slots),
+            indexes: self.indexes.as_ref().map_or(0, |ix| ix.heap_bytes()),
+            history: self.history.as_ref().map_or(0, |h| h.heap_bytes()),
+        };
+        report
+    }
+}
+
+/// Bytes of a hash table's buckets, without heap data of its entries.
+pub(crate) fn table_bytes<K, V>(table: &FxHashMap<K, V>) -> usize {
+    // One control byte per bucket; buckets are at least 8/7 of capacity
+    table.capacity() * 8 / 7 * (size_of::<(K, V)>() + 1)
+}
+
+/// Bytes of an entity's value map, and of its values' heap data.
+pub(crate) fn entity_bytes(entity: &Entity) -> (usize, usize) {
+    let nodes = entity.values.len().div_ceil(BTREE_NODE_ENTRIES);
+    let map = nodes * BTREE_NODE_ENTRIES * size_of::<(ValueKey, Value<'static>)>();
+    (map, entity.values.values().map(value_bytes).sum())
+}
+
+/// Bytes of an entity table, and of its values' heap data.
+pub(crate) fn entities_bytes(entities: &FxHashMap<Id, Entity>) -> (usize, usize) {
+    entities.values().map(entity_bytes).fold((table_bytes(entities), 0), |(m, v), (em, ev)| (m + em, v + ev))
+}
+
+/// Bytes of a relation table, including positions.
+pub(crate) fn relations_bytes(relations: &FxHashMap<Id, Relation>) -> usize {
+    table_bytes(relations) + relations.values().map(relation_bytes).sum::<usize>()
+}
+
+/// Heap bytes of a relation.
+pub(crate) fn relation_bytes(relation: &Relation) -> usize {
+    relation.position.as_ref().map_or(0, String::capacity)
+}
+
+/// Heap bytes of a value.
+pub(crate) fn value_bytes(value: &Value<'_>) -> usize {
+    match value {
+        Value::Text { value, .. }
+        | Value::Date(value)
+        | Value::Time(value)
+        | Value::Datetime(value)
+        | Value::Schedule(value) => value.len(),
+        Value::Bytes(bytes) | Value::Embedding { data: bytes, .. } => bytes.len(),
+        Value::Decimal { mantissa: DecimalMantissa::Big(bytes), .. } => bytes.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use crate::state::IndexConfig;
+
+    const NAME: Id = [10u8; 16];
+    const CITY: Id = [20u8; 16];
+    const TYPES: Id = [30u8; 16];
+
+    #[test]
+    fn test_memory_report() {
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_entity([2u8; 16], |e| e.text(NAME, "Berlin", None).text(NAME, "Berlino", Some([9u8; 16])))
+                .create_entity([3u8; 16], |e| e.text(NAME, "Hamburg", None))
+                .create_entity([4u8; 16], |e| e.text(NAME, "Bonn", None))
+                .create_relation_simple([5u8; 16], [2u8; 16], CITY, TYPES)
+                .create_relation_simple([6u8; 16], [3u8; 16], CITY, TYPES)
+                .create_relation_simple([7u8; 16], [4u8; 16], CITY, TYPES)
+                .create_relation_simple([8u8; 16], [2u8; 16], CITY, types_relation_type())
+                .delete_entity([4u8; 16])
+                .build(),
+        );
+
+        let report = graph.memory_report();
+        // Two cities and four reified relation entities
+        assert_eq!((report.entities, report.deleted_entities, report.relations), (6, 1, 4));
+        assert_eq!(report.values_by_property[&NAME], 3);
+        // Genesis Types by default
+        assert_eq!(report.entities_by_type.into_iter().collect::<Vec<_>>(), vec![(CITY, 1)]);
+        assert_eq!(report.bytes.values, "BerlinBerlinoHamburgBonn".len());
+        assert!(report.bytes.entities > 0 && report.bytes.relations > 0);
+        assert_eq!((report.bytes.indexes, report.bytes.history), (0, 0));
+
+        graph.set_indexes(IndexConfig::new().types(TYPES));
+        graph.enable_history();
+        let report = graph.memory_report();
+        assert_eq!(report.entities_by_type[&CITY], 2);
+        assert!(report.bytes.indexes > 0 && report.bytes.history > report.bytes.entities);
+    }
+}
@@ -0,0 +1,330 @@
+//! Materialized state of a space.
+//!
+//! A [`Graph`] is the result of replaying edits in log order under the
+//! resolution rules of spec Sections 2.7, 3, and 4.2: values are
+//! last-writer-wins per (entity, property, language), relations are
+//! immutable except for their mutable fields, deletes are tombstones that
+//! absorb later creates and updates, and value refs bind slots by LWW.
+//!
+//! With the `sqlite` feature, [`sqlite::SqliteGraph`] persists a graph in a
+//! SQLite database. [`store::StoreGraph`] materializes into a pluggable
+//! [`store::Store`] instead, for graphs that do not fit in memory, and
+//! [`compact::CompactStore`] keeps it in memory with TEXT values shared
+//! and embeddings pooled.
+//! [`hash_graph`] digests the state canonically, so nodes can check they
+//! converged. [`check_integrity`] reports dangling references and hierarchy cycles.
+//! [`Graph::reachable`] and [`Graph::shortest_path`] follow relations, and
+//! [`Graph::is_instance_of`] resolves types through a subtype hierarchy.
+//! [`Multiverse`] holds the graphs of several spaces and overlays them by
+//! precedence, and [`resolve_endpoint`] follows the space and version pins
+//! of relation endpoints across it ([`version`] pins and reads back
+//! `to_version`). [`Graph::add_view`] maintains
+//! projections of typed entities as rows.
+//! [`Graph::fork`] applies candidate edits to a copy-on-write view, and
+//! `Graph::subscribe` (with `std`) reports the changes edits make.
+//! [`Graph::memory_report`] counts entities per type and values per
+//! property, and estimates the memory each structure takes.
+
+mod apply;
+pub mod compact;
+mod fork;
+mod hash;
+mod history;
+mod index;
+mod integrity;
+mod memory;
+mod multiverse;
+#[cfg(feature = "std")]
+mod nearest;
+mod replay;
+mod resolve;
+mod sink;
+#[cfg(feature = "std")]
+mod subscribe;
+mod traverse;
+pub mod version;
+mod view;
+#[cfg(feature = "sled")]
+pub mod sled;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+
+use alloc::collections::BTreeMap;
+
+use crate::collections::FxHashMap;
+use crate::model::{Id, Value};
+use crate::prelude::*;
+
+pub use fork::{Fork, ObjectChange, ValueChange};
+pub use hash::{hash_graph, STATE_HASH_VERSION};
+pub use history::Version;
+pub use index::IndexConfig;
+pub use integrity::{check_integrity, check_integrity_with_options, IntegrityIssue, IntegrityOptions};
+pub use memory::{MemoryReport, MemoryUsage};
+pub use multiverse::{Multiverse, Overlay};
+pub use replay::{CreatedAtOrder, ReplayOptions, ReplayProgress};
+pub use resolve::{resolve_endpoint, Endpoint, RelationEnd};
+#[cfg(feature = "std")]
+pub use nearest::{embedding_to_f32, NearestNeighbors, Neighbor, Similarity};
+#[cfg(feature = "std")]
+pub(crate) use nearest::cosine;
+pub use sink::{EmbeddingSink, TextIndexSink};
+#[cfg(feature = "std")]
+pub use subscribe::{ChangeEvent, ChangeFilter};
+pub use traverse::Direction;
+pub use view::{MaterializedView, Row, View, ViewId};
+
+/// Key of a value slot on an entity.
+///
+/// Values are unique per (property, language); `language` is only set for
+/// TEXT values, and `None` is the default (English) slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ValueKey {
+    pub property: Id,
+    pub language: Option<Id>,
+}
+
+impl ValueKey {
+    /// Creates a key for a non-TEXT value, or the default-language TEXT slot.
+    pub fn new(property: Id) -> Self {
+        Self { property, language: None }
+    }
+
+    /// Creates a key for a TEXT value in a specific language.
+    pub fn with_language(property: Id, language: Id) -> Self {
+        Self { property, language: Some(language) }
+    }
+
+    /// Returns the slot key a value is stored under.
+    pub fn of(property: Id, value: &Value<'_>) -> Self {
+        match value {
+            Value::Text { language, .. } => Self { property, language: *language },
+            _ => Self::new(property),
+        }
+    }
+}
+
+/// Resolved state of an entity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entity {
+    /// Current values, ordered by slot key.
+    pub values: BTreeMap<ValueKey, Value<'static>>,
+    /// Whether the entity is tombstoned. Values are kept so that a
+    /// RestoreEntity reveals them again.
+    pub deleted: bool,
+}
+
+impl Entity {
+    /// Returns the value in the given slot.
+    pub fn value(&self, key: &ValueKey) -> Option<&Value<'static>> {
+        self.values.get(key)
+    }
+
+    /// Returns all values of a property (every language for TEXT).
+    pub fn values_of(&self, property: Id) -> impl Iterator<Item = (&ValueKey, &Value<'static>)> {
+        let start = ValueKey { property, language: None };
+        self.values.range(start..).take_while(move |(key, _)| key.property == property)
+    }
+}
+
+/// Resolved state of a relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    pub relation_type: Id,
+    pub from: Id,
+    pub from_is_value_ref: bool,
+    pub to: Id,
+    pub to_is_value_ref: bool,
+    /// The reified entity (explicit, or derived from the relation ID).
+    pub entity: Id,
+    pub position: Option<String>,
+    pub from_space: Option<Id>,
+    pub from_version: Option<Id>,
+    pub to_space: Option<Id>,
+    pub to_version: Option<Id>,
+    /// Whether the relation is tombstoned.
+    pub deleted: bool,
+}
+
+/// The value slot a value ref is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValueRefSlot {
+    pub entity: Id,
+    pub property: Id,
+    pub language: Option<Id>,
+    pub space: Option<Id>,
+}
+
+/// Resolved state of an object ID (spec Section 2.7).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectState<'g> {
+    Entity(&'g Entity),
+    Relation(&'g Relation),
+    ValueRef(&'g ValueRefSlot),
+    Deleted,
+    NotFound,
+}
+
+/// Materialized state of a single space.
+///
+/// Build one by applying edits in log order with [`Graph::apply_edit`].
+/// Secondary indexes (see [`Graph::set_indexes`]), history (see
+/// [`Graph::enable_history`]), the subtype relation (see
+/// [`Graph::set_subtype_relation`]), and views (see [`Graph::add_view`])
+/// are optional and do not take part in equality.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    pub(crate) entities: FxHashMap<Id, Entity>,
+    pub(crate) relations: FxHashMap<Id, Relation>,
+    /// Value ref ID -> slot, from the highest-positioned CreateValueRef.
+    pub(crate) value_refs: FxHashMap<Id, ValueRefSlot>,
+    /// Slot -> value ref ID (the authoritative LWW mapping).
+    pub(crate) value_ref_slots: FxHashMap<ValueRefSlot, Id>,
+    pub(crate) indexes: Option<Box<index::Indexes>>,
+    pub(crate) history: Option<Box<history::History>>,
+    /// Relation type from a type to its supertype (see
+    /// [`Graph::is_instance_of`]).
+    pub(crate) subtype_relation: Option<Id>,
+    pub(crate) views: Vec<Option<view::MaterializedView>>,
+    #[cfg(feature = "std")]
+    pub(crate) subscribers: subscribe::Subscribers,
+}
+
+impl PartialEq for Graph {
+    fn eq(&self, other: &Self) -> bool {
+        self.entities == other.entities
+            && self.relations == other.relations
+            && self.value_refs == other.value_refs
+            && self.value_ref_slots == other.value_ref_slots
+    }
+}
+
+impl Graph {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the resolved state of an object ID.
+    pub fn state(&self, id: &Id) -> ObjectState<'_> {
+        if let Some(entity) = self.entities.get(id) {
+            return if entity.deleted { ObjectState::Deleted } else { ObjectState::Entity(entity) };
+        }
+        if let Some(relation) = self.relations.get(id) {
+            return if relation.deleted { ObjectState::Deleted } else { ObjectState::Relation(relation) };
+        }
+        match self.value_refs.get(id) {
+            Some(slot) => ObjectState::ValueRef(slot),
+            None => ObjectState::NotFound,
+        }
+    }
+
+    /// Returns a live (not deleted) entity.
+    pub fn entity(&self, id: &Id) -> Option<&Entity> {
+        self.entities.get(id).filter(|e| !e.deleted)
+    }
+
+    /// Returns a live (not deleted) relation.
+    pub fn relation(&self, id: &Id) -> Option<&Relation> {
+        self.relations.get(id).filter(|r| !r.deleted)
+    }
+
+    /// Returns the slot a value ref is bound to.
+    pub fn value_ref(&self, id: &Id) -> Option<&ValueRefSlot> {
+        self.value_refs.get(id)
+    }
+
+    /// Returns the value of a live entity in the given slot.
+    pub fn value(&self, entity: &Id, key: &ValueKey) -> Option<&Value<'static>> {
+        self.entity(entity)?.value(key)
+    }
+
+    /// Iterates over live entities, in no particular order.
+    pub fn entities(&self) -> impl Iterator<Item = (&Id, &Entity)> {
+        self.entities.iter().filter(|(_, e)| !e.deleted)
+    }
+
+    /// Iterates over live relations, in no particular order.
+    pub fn relations(&self) -> impl Iterator<Item = (&Id, &Relation)> {
+        self.relations.iter().filter(|(_, r)| !r.deleted)
+    }
+
+    /// Returns the live relations from `entity` (optionally of one type),
+    /// in spec order: by position, then relation ID, unpositioned last.
+    pub fn outgoing(&self, entity: &Id, relation_type: Option<Id>) -> Vec<(&Id, &Relation)> {
+        let mut out: Vec<_> = self
+            .relations()
+            .filter(|(_, r)| r.from == *entity && relation_type.is_none_or(|t| r.relation_type == t))
+            .collect();
+        out.sort_by(|(a_id, a), (b_id, b)| relation_order(a_id, a, b_id, b));
+        out
+    }
+
+    /// Returns the number of live entities.
+    pub fn entity_count(&self) -> usize {
+        self.entities().count()
+    }
+
+    /// Returns the number of live relations.
+    pub fn relation_count(&self) -> usize {
+        self.relations().count()
+    }
+
+    /// Returns true if no object (live or deleted) has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.relations.is_empty() && self.value_refs.is_empty()
+    }
+}
+
+/// Orders relations per spec Section 2.6: positioned relations first, by
+/// position, then by relation ID.
+pub fn relation_order(a_id: &Id, a: &Relation, b_id: &Id, b: &Relation) -> core::cmp::Ordering {
+    match (&a.position, &b.position) {
+        (Some(pa), Some(pb)) => pa.cmp(pb).then_with(|| a_id.cmp(b_id)),
+        (Some(_), None) => core::cmp::Ordering::Less,
+        (None, Some(_)) => core::cmp::Ordering::Greater,
+        (None, None) => a_id.cmp(b_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_value_key_of() {
+        let text = Value::Text { value: "Hallo".into(), language: Some([9u8; 16]) };
+        assert_eq!(ValueKey::of([1u8; 16], &text), ValueKey::with_language([1u8; 16], [9u8; 16]));
+        let unit = Value::Int64 { value: 1, unit: Some([9u8; 16]) };
+        assert_eq!(ValueKey::of([1u8; 16], &unit), ValueKey::new([1u8; 16]));
+    }
+
+    #[test]
+    fn test_values_of_and_outgoing_order() {
+        let name = [10u8; 16];
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| {
+                e.text(name, "Hello", None)
+                    .text(name, "Hallo", Some([20u8; 16]))
+                    .int64([11u8; 16], 5, None)
+            })
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+            .create_relation(|r| {
+                r.id([8u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]).position("b")
+            })
+            .create_relation(|r| {
+                r.id([9u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]).position("a")
+            })
+            .build();
+        let mut graph = Graph::new();
+        graph.apply_edit(&edit);
+
+        let entity = graph.entity(&[3u8; 16]).unwrap();
+        assert_eq!(entity.values_of(name).count(), 2);
+        let ids: Vec<Id> = graph.outgoing(&[3u8; 16], Some([5u8; 16])).into_iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![[9u8; 16], [8u8; 16], [7u8; 16]]);
+        assert!(graph.outgoing(&[3u8; 16], Some([6u8; 16])).is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Graphs of several spaces.
+//!
+//! Spaces may hold conflicting views of the same entity. A [`Multiverse`]
+//! keeps one [`Graph`] per space ID, answers queries across spaces, and
+//! combines spaces into an [`Overlay`] where earlier spaces take precedence:
+//!
+//! ```
+//! use grc_20::state::{Multiverse, ValueKey};
+//! use grc_20::EditBuilder;
+//!
+//! let (community, official, berlin, name) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16]);
+//! let mut multiverse = Multiverse::new();
+//! let named = |text| EditBuilder::new([5u8; 16]).create_entity(berlin, |e| e.text(name, text, None)).build();
+//! multiverse.apply_edit(official, &named("Berlin"));
+//! multiverse.apply_edit(community, &named("Bärlin"));
+//!
+//! assert_eq!(multiverse.entity_across(&berlin).len(), 2);
+//! let (space, _) = multiverse.overlay(&[community, official]).value(&berlin, &ValueKey::new(name)).unwrap();
+//! assert_eq!(space, community);
+//! ```
+
+use alloc::collections::BTreeMap;
+
+use crate::collections::FxHashSet;
+use crate::model::{Edit, Id, Value};
+use crate::prelude::*;
+use crate::state::{relation_order, Entity, Graph, ObjectState, Relation, ValueKey};
+
+/// Graphs keyed by space ID.
+#[derive(Debug, Clone, Default)]
+pub struct Multiverse {
+    spaces: BTreeMap<Id, Graph>,
+}
+
+impl Multiverse {
+    /// Creates an empty multiverse.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the graph of a space, returning the previous one.
+    pub fn insert(&mut self, space: Id, graph: Graph) -> Option<Graph> {
+        self.spaces.insert(space, graph)
+    }
+
+    /// Removes the graph of a space.
+    pub fn remove(&mut self, space: &Id) -> Option<Graph> {
+        self.spaces.remove(space)
+    }
+
+    /// Returns the graph of a space.
+    pub fn space(&self, space: &Id) -> Option<&Graph> {
+        self.spaces.get(space)
+    }
+
+    /// Returns the graph of a space, creating an empty one if needed.
+    pub fn space_mut(&mut self, space: Id) -> &mut Graph {
+        self.spaces.entry(space).or_default()
+    }
+
+    /// Iterates over the spaces and their graphs, by space ID.
+    pub fn spaces(&self) -> impl Iterator<Item = (&Id, &Graph)> {
+        self.spaces.iter()
+    }
+
+    /// Applies an edit to the graph of a space, creating it if needed.
+    pub fn apply_edit(&mut self, space: Id, edit: &Edit<'_>) {
+        self.space_mut(space).apply_edit(edit);
+    }
+
+    /// Returns the live entity in every space that has it, by space ID.
+    pub fn entity_across(&self, id: &Id) -> Vec<(Id, &Entity)> {
+        self.spaces.iter().filter_map(|(space, graph)| Some((*space, graph.entity(id)?))).collect()
+    }
+
+    /// Returns the state of an object ID in every space that knows it
+    /// (live or deleted), by space ID.
+    pub fn state_across(&self, id: &Id) -> Vec<(Id, ObjectState<'_>)> {
+        self.spaces
+            .iter()
+            .map(|(space, graph)| (*space, graph.state(id)))
+            .filter(|(_, state)| *state != ObjectState::NotFound)
+            .collect()
+    }
+
+    /// Combines spaces, earlier ones taking precedence. Spaces not in the
+    /// multiverse are skipped.
+    pub fn overlay(&self, precedence: &[Id]) -> Overlay<'_> {
+        let layers = precedence.iter().filter_map(|space| Some((*space, self.spaces.get(space)?))).collect();
+        Overlay { layers }
+    }
+}
+
+/// Spaces of a [`Multiverse`] stacked by precedence, from
+/// [`Multiverse::overlay`].
+///
+/// An object is decided by the first space that knows its ID: if that space
+/// deleted it, lower spaces cannot bring it back. The values of an entity
+/// are combined from every space where it is live, slot by slot, the first
+/// space with a value in the slot winning.
+#[derive(Debug, Clone)]
+pub struct Overlay<'m> {
+    layers: Vec<(Id, &'m Graph)>,
+}
+
+impl<'m> Overlay<'m> {
+    /// Returns the spaces in precedence order.
+    pub fn spaces(&self) -> impl Iterator<Item = Id> + '_ {
+        self.layers.iter().map(|(space, _)| *space)
+    }
+
+    /// Returns the state of an object ID in the first space that knows it,
+    /// with that space.
+    pub fn state(&self, id: &Id) -> Option<(Id, ObjectState<'m>)> {
+        self.layers
+            .iter()
+            .map(|(space, graph)| (*space, graph.state(id)))
+            .find(|(_, state)| *state != ObjectState::NotFound)
+    }
+
+    fn visible(&self, id: &Id) -> bool {
+        !matches!(self.state(id), None | Some((_, ObjectState::Deleted)))
+    }
+
+    /// Returns the value in an entity's slot and the space it comes from.
+    pub fn value(&self, entity: &Id, key: &ValueKey) -> Option<(Id, &'m Value<'static>)> {
+        if !self.visible(entity) {
+            return None;
+        }
+        self.layers.iter().find_map(|(space, graph)| Some((*space, graph.value(entity, key)?)))
+    }
+
+    /// Returns an entity with the values of all spaces combined.
+    pub fn entity(&self, id: &Id) -> Option<Entity> {
+        if !self.visible(id) {
+            return None;
+        }
+        let mut combined: Option<Entity> = None;
+        for (_, graph) in &self.layers {
+            let Some(entity) = graph.entity(id) else {
+                continue;
+            };
+            let combined = combined.get_or_insert_with(Entity::default);
+            for (key, value) in &entity.values {
+                combined.values.entry(*key).or_insert_with(|| value.clone());
+            }
+        }
+        combined
+    }
+
+    /// Returns a live relation and the space that decides it.
+    pub fn relation(&self, id: &Id) -> Option<(Id, &'m Relation)> {
+        match self.state(id)? {
+            (space, ObjectState::Relation(relation)) => Some((space, relation)),
+            _ => None,
+        }
+    }
+
+    /// Returns the live relations from `entity` (optionally of one type)
+    /// across spaces, each with the space that decides it, in spec order.
+    pub fn outgoing(&self, entity: &Id, relation_type: Option<Id>) -> Vec<(Id, &'m Id, &'m Relation)> {
+        let mut seen = FxHashSet::default();
+        let mut out = Vec::new();
+        for (_, graph) in &self.layers {
+            for (id, _) in graph.outgoing(entity, relation_type) {
+                if seen.insert(*id) {
+                    if let Some((space, relation)) = self.relation(id) {
+                        out.push((space, id, relation));
+                    }
+                }
+            }
+        }
+        out.sort_by(|(_, a_id, a), (_, b_id, b)| relation_order(a_id, a, b_id, b));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const COMMUNITY: Id = [1u8; 16];
+    const OFFICIAL: Id = [2u8; 16];
+    const BERLIN: Id = [3u8; 16];
+    const NAME: Id = [10u8; 16];
+    const POPULATION: Id = [11u8; 16];
+    const PART_OF: Id = [12u8; 16];
+
+    fn multiverse() -> Multiverse {
+        let mut multiverse = Multiverse::new();
+        multiverse.apply_edit(
+            OFFICIAL,
+            &EditBuilder::new([5u8; 16])
+                .create_entity(BERLIN, |e| e.text(NAME, "Berlin", None).int64(POPULATION, 3_645_000, None))
+                .create_relation_simple([20u8; 16], BERLIN, [4u8; 16], PART_OF)
+                .create_relation_simple([21u8; 16], BERLIN, [5u8; 16], PART_OF)
+                .build(),
+        );
+        multiverse.apply_edit(
+            COMMUNITY,
+            &EditBuilder::new([6u8; 16])
+                .create_entity(BERLIN, |e| e.text(NAME, "Bärlin", None))
+                .create_relation_simple([22u8; 16], BERLIN, [6u8; 16], PART_OF)
+                // The community copied one of the official relations, then
+                // rejected it
+                .create_relation_simple([21u8; 16], BERLIN, [5u8; 16], PART_OF)
+                .delete_relation([21u8; 16])
+                .build(),
+        );
+        multiverse
+    }
+
+    #[test]
+    fn test_across_spaces() {
+        let multiverse = multiverse();
+        let spaces: Vec<Id> = multiverse.entity_across(&BERLIN).iter().map(|(space, _)| *space).collect();
+        assert_eq!(spaces, vec![COMMUNITY, OFFICIAL]);
+        let states = multiverse.state_across(&[21u8; 16]);
+        assert!(matches!(states[..], [(COMMUNITY, ObjectState::Deleted), (OFFICIAL, ObjectState::Relation(_))]));
+        assert!(multiverse.space(&[9u8; 16]).is_none());
+    }
+
+    #[test]
+    fn test_overlay_precedence() {
+        let multiverse = multiverse();
+        let overlay = multiverse.overlay(&[COMMUNITY, [9u8; 16], OFFICIAL]);
+        assert_eq!(overlay.spaces().collect::<Vec<_>>(), vec![COMMUNITY, OFFICIAL]);
+
+        let berlin = overlay.entity(&BERLIN).unwrap();
+        assert_eq!(berlin.value(&ValueKey::new(NAME)), Some(&Value::Text { value: "Bärlin".into(), language: None }));
+        assert!(berlin.value(&ValueKey::new(POPULATION)).is_some());
+        assert_eq!(overlay.value(&BERLIN, &ValueKey::new(POPULATION)).unwrap().0, OFFICIAL);
+
+        // The community's tombstone hides the official relation
+        let outgoing: Vec<Id> = overlay.outgoing(&BERLIN, Some(PART_OF)).iter().map(|(_, id, _)| **id).collect();
+        assert_eq!(outgoing, vec![[20u8; 16], [22u8; 16]]);
+        let outgoing = multiverse.overlay(&[OFFICIAL, COMMUNITY]).outgoing(&BERLIN, Some(PART_OF));
+        assert_eq!(outgoing.len(), 3);
+    }
+}
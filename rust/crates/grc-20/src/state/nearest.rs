@@ -0,0 +1,276 @@
+//! Brute-force nearest-neighbor search over EMBEDDING values.
+//!
+//! [`NearestNeighbors`] is an exact, in-memory [`EmbeddingSink`] meant for
+//! prototypes and small spaces; larger deployments plug an approximate
+//! index (HNSW, ...) into the same sink. Similarity kernels work on
+//! fixed-width lanes so the compiler can vectorize them.
+
+use crate::collections::FxHashMap;
+use crate::model::{EmbeddingSubType, Id};
+use crate::state::{EmbeddingSink, Graph};
+
+/// Number of f32 lanes the kernels accumulate in parallel.
+const LANES: usize = 8;
+
+/// Similarity measure for [`NearestNeighbors`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Similarity {
+    /// Cosine similarity (vectors are normalized on insert).
+    #[default]
+    Cosine,
+    /// Raw dot product.
+    Dot,
+}
+
+/// A search result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    pub entity: Id,
+    pub property: Id,
+    pub score: f32,
+}
+
+/// Exact nearest-neighbor index over embeddings.
+///
+/// All vectors share the dimension of the first one inserted; vectors of
+/// another dimension are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct NearestNeighbors {
+    similarity: Similarity,
+    dims: Option<usize>,
+    keys: Vec<(Id, Id)>,
+    /// Row-major vectors, `dims` floats per key.
+    vectors: Vec<f32>,
+    rows: FxHashMap<(Id, Id), usize>,
+}
+
+impl NearestNeighbors {
+    /// Creates an empty index.
+    pub fn new(similarity: Similarity) -> Self {
+        Self { similarity, ..Self::default() }
+    }
+
+    /// Builds an index over every visible embedding of `property`.
+    pub fn from_graph(graph: &Graph, property: Id, similarity: Similarity) -> Self {
+        let mut index = Self::new(similarity);
+        let mut sink = PropertyFilter { property, index: &mut index };
+        graph.index_all_embeddings(&mut sink);
+        index
+    }
+
+    /// Returns the number of indexed vectors.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns true if no vectors are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the dimension of the indexed vectors.
+    pub fn dims(&self) -> Option<usize> {
+        self.dims
+    }
+
+    /// Inserts or replaces the vector for an (entity, property) slot.
+    ///
+    /// Returns false if the dimension does not match the index.
+    pub fn insert(&mut self, entity: Id, property: Id, vector: &[f32]) -> bool {
+        let dims = *self.dims.get_or_insert(vector.len());
+        if vector.len() != dims {
+            return false;
+        }
+        let row = match self.rows.get(&(entity, property)) {
+            Some(&row) => row,
+            None => {
+                self.rows.insert((entity, property), self.keys.len());
+                self.keys.push((entity, property));
+                self.vectors.resize(self.vectors.len() + dims, 0.0);
+                self.keys.len() - 1
+            }
+        };
+        let slot = &mut self.vectors[row * dims..(row + 1) * dims];
+        slot.copy_from_slice(vector);
+        if self.similarity == Similarity::Cosine {
+            normalize(slot);
+        }
+        true
+    }
+
+    /// Removes the vector for an (entity, property) slot.
+    pub fn remove(&mut self, entity: Id, property: Id) -> bool {
+        let Some(row) = self.rows.remove(&(entity, property)) else {
+            return false;
+        };
+        let dims = self.dims.unwrap_or(0);
+        let last = self.keys.len() - 1;
+        self.keys.swap_remove(row);
+        if row != last {
+            self.vectors.copy_within(last * dims..(last + 1) * dims, row * dims);
+            self.rows.insert(self.keys[row], row);
+        }
+        self.vectors.truncate(last * dims);
+        true
+    }
+
+    /// Returns the `k` most similar vectors to `query`, best first.
+    ///
+    /// Returns nothing if the query dimension does not match the index.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<Neighbor> {
+        let Some(dims) = self.dims.filter(|d| *d == query.len() && *d > 0) else {
+            return Vec::new();
+        };
+        let mut query = query.to_vec();
+        if self.similarity == Similarity::Cosine {
+            normalize(&mut query);
+        }
+        let mut scored: Vec<(f32, usize)> =
+            self.vectors.chunks_exact(dims).map(|v| dot(v, &query)).zip(0..).collect();
+        let by_score = |a: &(f32, usize), b: &(f32, usize)| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1));
+        if k < scored.len() {
+            if k == 0 {
+                return Vec::new();
+            }
+            scored.select_nth_unstable_by(k - 1, by_score);
+            scored.truncate(k);
+        }
+        scored.sort_by(by_score);
+        scored
+            .into_iter()
+            .map(|(score, row)| {
+                let (entity, property) = self.keys[row];
+                Neighbor { entity, property, score }
+            })
+            .collect()
+    }
+}
+
+impl EmbeddingSink for NearestNeighbors {
+    fn index_embedding(&mut self, entity: Id, property: Id, sub_type: EmbeddingSubType, dims: usize, data: &[u8]) {
+        let vector = embedding_to_f32(sub_type, dims, data);
+        if !self.insert(entity, property, &vector) {
+            // A slot that changed dimension no longer belongs in the index
+            self.remove(entity, property);
+        }
+    }
+
+    fn remove_embedding(&mut self, entity: Id, property: Id) {
+        self.remove(entity, property);
+    }
+}
+
+/// Forwards only the embeddings of one property.
+struct PropertyFilter<'a> {
+    property: Id,
+    index: &'a mut NearestNeighbors,
+}
+
+impl EmbeddingSink for PropertyFilter<'_> {
+    fn index_embedding(&mut self, entity: Id, property: Id, sub_type: EmbeddingSubType, dims: usize, data: &[u8]) {
+        if property == self.property {
+            self.index.index_embedding(entity, property, sub_type, dims, data);
+        }
+    }
+
+    fn remove_embedding(&mut self, entity: Id, property: Id) {
+        self.index.remove_embedding(entity, property);
+    }
+}
+
+/// Decodes embedding bytes into floats.
+///
+/// INT8 components are widened as-is; BINARY bits map to +1.0 (set) and
+/// -1.0 (clear), so cosine similarity tracks Hamming distance.
+pub fn embedding_to_f32(sub_type: EmbeddingSubType, dims: usize, data: &[u8]) -> Vec<f32> {
+    match sub_type {
+        EmbeddingSubType::Float32 => data
+            .chunks_exact(4)
+            .take(dims)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect(),
+        EmbeddingSubType::Int8 => data.iter().take(dims).map(|&b| b as i8 as f32).collect(),
+        EmbeddingSubType::Binary => (0..dims)
+            .map(|i| {
+                let set = data.get(i / 8).is_some_and(|b| b & (1 << (i % 8)) != 0);
+                if set { 1.0 } else { -1.0 }
+            })
+            .collect(),
+    }
+}
+
+/// Dot product, accumulated in [`LANES`] independent sums.
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let mut acc = [0.0f32; LANES];
+    let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let tail: f32 = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(x, y)| x * y).sum();
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for ((acc, x), y) in acc.iter_mut().zip(x).zip(y) {
+            *acc += x * y;
+        }
+    }
+    acc.iter().sum::<f32>() + tail
+}
+
+/// Cosine similarity of two vectors of equal length; 0 if either is zero.
+pub(crate) fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let norms = (dot(a, a) * dot(b, b)).sqrt();
+    if norms > 0.0 { dot(a, b) / norms } else { 0.0 }
+}
+
+fn normalize(v: &mut [f32]) {
+    let norm = dot(v, v).sqrt();
+    if norm > 0.0 {
+        for x in v {
+            *x /= norm;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const EMBEDDING: Id = [40u8; 16];
+
+    fn floats(v: &[f32]) -> Vec<u8> {
+        v.iter().flat_map(|f| f.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_dot_matches_naive() {
+        let a: Vec<f32> = (0..19).map(|i| i as f32 * 0.5).collect();
+        let b: Vec<f32> = (0..19).map(|i| 3.0 - i as f32).collect();
+        let naive: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        assert!((dot(&a, &b) - naive).abs() < 1e-3);
+        assert_eq!(embedding_to_f32(EmbeddingSubType::Binary, 3, &[0b101]), vec![1.0, -1.0, 1.0]);
+        assert_eq!(embedding_to_f32(EmbeddingSubType::Int8, 2, &[0xFF, 2]), vec![-1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_search_and_sink() {
+        let mut graph = Graph::new();
+        let mut index = NearestNeighbors::new(Similarity::Cosine);
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([1u8; 16], |e| e.embedding(EMBEDDING, EmbeddingSubType::Float32, 2, floats(&[1.0, 0.0])))
+            .create_entity([2u8; 16], |e| e.embedding(EMBEDDING, EmbeddingSubType::Float32, 2, floats(&[0.0, 3.0])))
+            .create_entity([3u8; 16], |e| e.embedding(EMBEDDING, EmbeddingSubType::Float32, 2, floats(&[2.0, 2.1])))
+            .build();
+        graph.apply_edit_with_embedding_sink(&edit, &mut index);
+        assert_eq!(index.len(), 3);
+
+        let hits = index.search(&[0.9, 1.0], 2);
+        let ids: Vec<Id> = hits.iter().map(|n| n.entity).collect();
+        assert_eq!(ids, vec![[3u8; 16], [2u8; 16]]);
+        assert!(hits[0].score > hits[1].score);
+
+        graph.apply_edit_with_embedding_sink(&EditBuilder::new([2u8; 16]).delete_entity([3u8; 16]).build(), &mut index);
+        assert_eq!(index.search(&[0.9, 1.0], 1)[0].entity, [2u8; 16]);
+        assert_eq!(index.len(), 2);
+        assert!(index.search(&[1.0], 1).is_empty());
+
+        let rebuilt = NearestNeighbors::from_graph(&graph, EMBEDDING, Similarity::Dot);
+        assert_eq!(rebuilt.search(&[1.0, 0.0], 1)[0].entity, [1u8; 16]);
+    }
+}
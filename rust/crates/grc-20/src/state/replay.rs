@@ -0,0 +1,230 @@
+//! Incremental edit replay with checkpoints.
+//!
+//! Sync nodes bootstrap from a snapshot (see [`crate::snapshot`]), then
+//! replay the edits that follow it. [`Graph::replay_with`] reports progress,
+//! hands out periodic checkpoints to persist, and can check that edits
+//! arrive in the expected `created_at` order.
+
+use core::borrow::Borrow;
+
+use crate::error::ReplayError;
+use crate::model::{Edit, Id};
+use crate::prelude::*;
+use crate::state::Graph;
+
+/// Expected `created_at` order of replayed edits.
+///
+/// `created_at` is author-supplied metadata and the log order is
+/// authoritative, so nothing is checked by default. Nodes that expect
+/// timestamps to follow the log can opt in to a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CreatedAtOrder {
+    /// No check.
+    #[default]
+    Unchecked,
+    /// Each edit's `created_at` is at least the previous one's.
+    NonDecreasing,
+    /// Each edit's `created_at` is greater than the previous one's.
+    Increasing,
+}
+
+impl CreatedAtOrder {
+    fn check(self, previous: i64, created_at: i64) -> Result<(), &'static str> {
+        match self {
+            CreatedAtOrder::Unchecked => Ok(()),
+            CreatedAtOrder::NonDecreasing if created_at < previous => Err(">="),
+            CreatedAtOrder::Increasing if created_at <= previous => Err(">"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How far a replay has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReplayProgress {
+    /// Number of edits applied.
+    pub edits: usize,
+    /// Number of ops applied.
+    pub ops: usize,
+    /// ID of the last applied edit.
+    pub last_edit: Option<Id>,
+    /// `created_at` of the last applied edit.
+    pub last_created_at: Option<i64>,
+}
+
+type CheckpointFn<'f> = Box<dyn FnMut(&Graph, &ReplayProgress) + 'f>;
+type ProgressFn<'f> = Box<dyn FnMut(&ReplayProgress) + 'f>;
+
+/// Options for [`Graph::replay_with`].
+#[derive(Default)]
+pub struct ReplayOptions<'f> {
+    order: CreatedAtOrder,
+    resume: ReplayProgress,
+    checkpoint_interval: usize,
+    on_checkpoint: Option<CheckpointFn<'f>>,
+    on_progress: Option<ProgressFn<'f>>,
+}
+
+impl<'f> ReplayOptions<'f> {
+    /// Creates options with no checks and no callbacks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the expected `created_at` order.
+    pub fn created_at_order(mut self, order: CreatedAtOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Continues counting (and order checking) from an earlier replay, e.g.
+    /// the progress recorded with the snapshot the graph was loaded from.
+    pub fn resume(mut self, progress: ReplayProgress) -> Self {
+        self.resume = progress;
+        self
+    }
+
+    /// Calls `f` with the graph after every `interval` edits (counted from
+    /// the resumed position), e.g. to write a snapshot. An interval of 0
+    /// disables checkpoints.
+    pub fn checkpoint_every<F>(mut self, interval: usize, f: F) -> Self
+    where
+        F: FnMut(&Graph, &ReplayProgress) + 'f,
+    {
+        self.checkpoint_interval = interval;
+        self.on_checkpoint = Some(Box::new(f));
+        self
+    }
+
+    /// Calls `f` after every applied edit.
+    pub fn on_progress<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&ReplayProgress) + 'f,
+    {
+        self.on_progress = Some(Box::new(f));
+        self
+    }
+}
+
+impl Graph {
+    /// Applies edits in log order and returns the progress reached.
+    pub fn replay<'e, I, E>(&mut self, edits: I) -> Result<ReplayProgress, ReplayError>
+    where
+        I: IntoIterator<Item = E>,
+        E: Borrow<Edit<'e>>,
+    {
+        self.replay_with(edits, ReplayOptions::default())
+    }
+
+    /// Applies edits in log order with checks and callbacks.
+    ///
+    /// On an ordering error, the edits before the offending one stay
+    /// applied and the offending edit is not.
+    pub fn replay_with<'e, I, E>(
+        &mut self,
+        edits: I,
+        mut options: ReplayOptions<'_>,
+    ) -> Result<ReplayProgress, ReplayError>
+    where
+        I: IntoIterator<Item = E>,
+        E: Borrow<Edit<'e>>,
+    {
+        let mut progress = options.resume;
+        for edit in edits {
+            let edit = edit.borrow();
+            if let Some(previous) = progress.last_created_at {
+                if let Err(expected) = options.order.check(previous, edit.created_at) {
+                    return Err(ReplayError::OutOfOrder {
+                        index: progress.edits,
+                        edit: edit.id,
+                        created_at: edit.created_at,
+                        previous,
+                        expected,
+                    });
+                }
+            }
+
+            self.apply_edit(edit);
+            progress.edits += 1;
+            progress.ops += edit.ops.len();
+            progress.last_edit = Some(edit.id);
+            progress.last_created_at = Some(edit.created_at);
+
+            if let Some(f) = options.on_progress.as_mut() {
+                f(&progress);
+            }
+            let since_resume = progress.edits - options.resume.edits;
+            if options.checkpoint_interval > 0 && since_resume % options.checkpoint_interval == 0 {
+                if let Some(f) = options.on_checkpoint.as_mut() {
+                    f(self, &progress);
+                }
+            }
+        }
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use crate::snapshot::{read_snapshot, write_snapshot};
+
+    fn edit(id: u8, created_at: i64) -> Edit<'static> {
+        EditBuilder::new([id; 16])
+            .created_at(created_at)
+            .create_entity([id; 16], |e| e.int64([11u8; 16], created_at, None))
+            .build()
+    }
+
+    #[test]
+    fn test_replay_checkpoints_and_resume() {
+        let edits: Vec<_> = (1..=5).map(|i| edit(i, i as i64 * 10)).collect();
+
+        let mut checkpoints = Vec::new();
+        let mut seen = 0;
+        let mut graph = Graph::new();
+        let options = ReplayOptions::new()
+            .checkpoint_every(2, |g, p| checkpoints.push((write_snapshot(g).unwrap(), *p)))
+            .on_progress(|_| seen += 1);
+        let progress = graph.replay_with(&edits, options).unwrap();
+        assert_eq!(seen, 5);
+        assert_eq!(progress.ops, 5);
+        assert_eq!(progress.last_edit, Some([5u8; 16]));
+        assert_eq!(checkpoints.len(), 2);
+
+        // Bootstrap from the last checkpoint and replay the rest
+        let (bytes, at) = checkpoints.pop().unwrap();
+        assert_eq!(at.edits, 4);
+        let mut resumed = read_snapshot(&bytes).unwrap();
+        let options = ReplayOptions::new().resume(at).created_at_order(CreatedAtOrder::Increasing);
+        let end = resumed.replay_with(edits[at.edits..].iter(), options).unwrap();
+        assert_eq!(end, progress);
+        assert_eq!(resumed, graph);
+    }
+
+    #[test]
+    fn test_replay_created_at_order() {
+        let edits = [edit(1, 10), edit(2, 10), edit(3, 5)];
+        assert!(Graph::new().replay(&edits).is_ok());
+
+        let mut graph = Graph::new();
+        let options = ReplayOptions::new().created_at_order(CreatedAtOrder::NonDecreasing);
+        let err = graph.replay_with(&edits, options).unwrap_err();
+        assert_eq!(err, ReplayError::OutOfOrder {
+            index: 2,
+            edit: [3u8; 16],
+            created_at: 5,
+            previous: 10,
+            expected: ">=",
+        });
+        // Edits before the offending one stay applied
+        assert_eq!(graph.entity_count(), 2);
+
+        let options = ReplayOptions::new().created_at_order(CreatedAtOrder::Increasing);
+        assert!(matches!(
+            Graph::new().replay_with(&edits, options),
+            Err(ReplayError::OutOfOrder { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,270 @@
+//! Cross-space endpoint resolution (spec Section 5.2).
+//!
+//! A relation lives in one space, but its endpoints may be pinned to
+//! another space (`from_space`/`to_space`) and to the state at the end of
+//! an edit (`from_version`/`to_version`). [`resolve_endpoint`] follows those
+//! pins through a [`Multiverse`]:
+//!
+//! ```
+//! use grc_20::state::{resolve_endpoint, Endpoint, Multiverse, RelationEnd};
+//! use grc_20::EditBuilder;
+//!
+//! let (home, official, berlin, germany, part_of) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16], [5u8; 16]);
+//! let mut multiverse = Multiverse::new();
+//! multiverse.apply_edit(official, &EditBuilder::new([6u8; 16]).create_entity(germany, |e| e).build());
+//! multiverse.apply_edit(
+//!     home,
+//!     &EditBuilder::new([7u8; 16])
+//!         .create_relation(|r| r.id([8u8; 16]).from(berlin).to(germany).relation_type(part_of).to_space(official))
+//!         .build(),
+//! );
+//!
+//! let relation = multiverse.space(&home).unwrap().relation(&[8u8; 16]).unwrap();
+//! let to = resolve_endpoint(relation, &multiverse, home, RelationEnd::To);
+//! assert!(matches!(to, Endpoint::Entity { space, .. } if space == official));
+//! ```
+//!
+//! Version pins are resolved from history (see
+//! [`Graph::enable_history`](crate::state::Graph::enable_history)), so the
+//! graph of the pinned space must have been recording history when the
+//! pinned edit was applied.
+
+use crate::model::{Id, Value};
+use crate::state::version::entity_at_version;
+use crate::state::{Entity, Graph, Multiverse, ObjectState, Relation, ValueKey, ValueRefSlot};
+
+/// One end of a relation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelationEnd {
+    From,
+    To,
+}
+
+/// What a relation endpoint resolves to, from [`resolve_endpoint`].
+///
+/// `space` is the space the endpoint was read from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endpoint<'m> {
+    /// A live entity, as of the version pin if there is one.
+    Entity { space: Id, entity: &'m Entity },
+    /// The value in the slot a value ref is bound to.
+    Value { space: Id, slot: ValueRefSlot, value: &'m Value<'static> },
+    /// The entity (or the entity holding the value) is deleted.
+    Deleted { space: Id },
+    /// Nothing to resolve: no such entity, an unbound value ref, an empty
+    /// slot, or an ID that names a relation or value ref instead.
+    NotFound { space: Id },
+    /// The space is not in the multiverse.
+    UnknownSpace(Id),
+    /// The space's history does not know the pinned edit (or is not
+    /// enabled).
+    UnknownVersion { space: Id, version: Id },
+}
+
+/// Resolves one end of a relation of space `home`.
+///
+/// An entity endpoint is read from its pinned space, or `home` if unpinned.
+/// For a value ref endpoint, the pinned space (or `home`) is where the
+/// value ref's binding is looked up, and the value is read from the slot's
+/// own space if it names one (spec Section 5.1). A version pin selects the
+/// state at the end of that edit in the space the entity or value is read
+/// from.
+pub fn resolve_endpoint<'m>(
+    relation: &Relation,
+    multiverse: &'m Multiverse,
+    home: Id,
+    end: RelationEnd,
+) -> Endpoint<'m> {
+    let (id, is_value_ref, space, version) = match end {
+        RelationEnd::From => (relation.from, relation.from_is_value_ref, relation.from_space, relation.from_version),
+        RelationEnd::To => (relation.to, relation.to_is_value_ref, relation.to_space, relation.to_version),
+    };
+    let space = space.unwrap_or(home);
+    let Some(graph) = multiverse.space(&space) else {
+        return Endpoint::UnknownSpace(space);
+    };
+    if !is_value_ref {
+        return entity_endpoint(graph, space, &id, version);
+    }
+
+    let Some(slot) = graph.value_ref(&id) else {
+        return Endpoint::NotFound { space };
+    };
+    let space = slot.space.unwrap_or(space);
+    let Some(graph) = multiverse.space(&space) else {
+        return Endpoint::UnknownSpace(space);
+    };
+    match entity_endpoint(graph, space, &slot.entity, version) {
+        Endpoint::Entity { entity, .. } => {
+            match entity.value(&ValueKey { property: slot.property, language: slot.language }) {
+                Some(value) => Endpoint::Value { space, slot: *slot, value },
+                None => Endpoint::NotFound { space },
+            }
+        }
+        other => other,
+    }
+}
+
+fn entity_endpoint<'m>(graph: &'m Graph, space: Id, id: &Id, version: Option<Id>) -> Endpoint<'m> {
+    let state = match version {
+        Some(version) => match entity_at_version(graph, id, &version) {
+            Some(state) => state,
+            None => return Endpoint::UnknownVersion { space, version },
+        },
+        None => graph.state(id),
+    };
+    match state {
+        ObjectState::Entity(entity) => Endpoint::Entity { space, entity },
+        ObjectState::Deleted => Endpoint::Deleted { space },
+        _ => Endpoint::NotFound { space },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder, Op};
+
+    const HOME: Id = [1u8; 16];
+    const OFFICIAL: Id = [2u8; 16];
+    const BERLIN: Id = [3u8; 16];
+    const GERMANY: Id = [4u8; 16];
+    const POPULATION_REF: Id = [5u8; 16];
+    const POPULATION: Id = [10u8; 16];
+    const PART_OF: Id = [11u8; 16];
+
+    fn population(value: i64) -> Value<'static> {
+        Value::Int64 { value, unit: None }
+    }
+
+    fn multiverse() -> Multiverse {
+        let mut multiverse = Multiverse::new();
+        multiverse.insert(OFFICIAL, Graph::new().with_history());
+        multiverse.apply_edit(
+            OFFICIAL,
+            &EditBuilder::new([20u8; 16])
+                .create_entity(GERMANY, |e| e.int64(POPULATION, 83, None))
+                .create_entity(BERLIN, |e| e)
+                .build(),
+        );
+        multiverse.apply_edit(
+            OFFICIAL,
+            &EditBuilder::new([21u8; 16])
+                .update_entity(GERMANY, |u| u.set_int64(POPULATION, 84, None))
+                .delete_entity(BERLIN)
+                .build(),
+        );
+        // The home space binds a value ref to the official population
+        multiverse.apply_edit(
+            HOME,
+            &EditBuilder::new([22u8; 16])
+                .create_entity(BERLIN, |e| e)
+                .op(Op::CreateValueRef(CreateValueRef {
+                    id: POPULATION_REF,
+                    entity: GERMANY,
+                    property: POPULATION,
+                    language: None,
+                    space: Some(OFFICIAL),
+                }))
+                .build(),
+        );
+        multiverse
+    }
+
+    fn relation(configure: impl FnOnce(&mut Relation)) -> Relation {
+        let mut relation = Relation {
+            relation_type: PART_OF,
+            from: BERLIN,
+            from_is_value_ref: false,
+            to: GERMANY,
+            to_is_value_ref: false,
+            entity: [30u8; 16],
+            position: None,
+            from_space: None,
+            from_version: None,
+            to_space: None,
+            to_version: None,
+            deleted: false,
+        };
+        configure(&mut relation);
+        relation
+    }
+
+    #[test]
+    fn test_space_pins() {
+        let multiverse = multiverse();
+        let unpinned = relation(|_| {});
+        let from = resolve_endpoint(&unpinned, &multiverse, HOME, RelationEnd::From);
+        assert!(matches!(from, Endpoint::Entity { space: HOME, .. }));
+        // Germany only exists in the official space
+        let to = resolve_endpoint(&unpinned, &multiverse, HOME, RelationEnd::To);
+        assert_eq!(to, Endpoint::NotFound { space: HOME });
+
+        let pinned = relation(|r| {
+            r.from_space = Some(OFFICIAL);
+            r.to_space = Some(OFFICIAL);
+        });
+        let from = resolve_endpoint(&pinned, &multiverse, HOME, RelationEnd::From);
+        assert_eq!(from, Endpoint::Deleted { space: OFFICIAL });
+        let to = resolve_endpoint(&pinned, &multiverse, HOME, RelationEnd::To);
+        assert!(matches!(to, Endpoint::Entity { space: OFFICIAL, .. }));
+
+        let unknown = relation(|r| r.to_space = Some([9u8; 16]));
+        assert_eq!(resolve_endpoint(&unknown, &multiverse, HOME, RelationEnd::To), Endpoint::UnknownSpace([9u8; 16]));
+    }
+
+    #[test]
+    fn test_version_pins() {
+        let multiverse = multiverse();
+        let pinned = relation(|r| {
+            r.from_space = Some(OFFICIAL);
+            r.from_version = Some([20u8; 16]);
+            r.to_space = Some(OFFICIAL);
+            r.to_version = Some([20u8; 16]);
+        });
+        // Berlin was live at the end of the first edit
+        let from = resolve_endpoint(&pinned, &multiverse, HOME, RelationEnd::From);
+        assert!(matches!(from, Endpoint::Entity { space: OFFICIAL, .. }));
+        let Endpoint::Entity { entity, .. } = resolve_endpoint(&pinned, &multiverse, HOME, RelationEnd::To) else {
+            panic!("expected an entity");
+        };
+        assert_eq!(entity.value(&ValueKey::new(POPULATION)), Some(&population(83)));
+
+        // The home space has no history
+        let unrecorded = relation(|r| r.to_version = Some([22u8; 16]));
+        let to = resolve_endpoint(&unrecorded, &multiverse, HOME, RelationEnd::To);
+        assert_eq!(to, Endpoint::UnknownVersion { space: HOME, version: [22u8; 16] });
+    }
+
+    #[test]
+    fn test_value_ref_endpoint() {
+        let multiverse = multiverse();
+        let to_value = relation(|r| {
+            r.to = POPULATION_REF;
+            r.to_is_value_ref = true;
+        });
+        let Endpoint::Value { space, slot, value } = resolve_endpoint(&to_value, &multiverse, HOME, RelationEnd::To)
+        else {
+            panic!("expected a value");
+        };
+        assert_eq!((space, slot.entity), (OFFICIAL, GERMANY));
+        assert_eq!(value, &population(84));
+
+        let historical = relation(|r| {
+            r.to = POPULATION_REF;
+            r.to_is_value_ref = true;
+            r.to_version = Some([20u8; 16]);
+        });
+        let to = resolve_endpoint(&historical, &multiverse, HOME, RelationEnd::To);
+        assert!(matches!(to, Endpoint::Value { value, .. } if *value == population(83)));
+
+        // The binding is looked up in the pinned space, which has none
+        let unbound = relation(|r| {
+            r.to = POPULATION_REF;
+            r.to_is_value_ref = true;
+            r.to_space = Some(OFFICIAL);
+        });
+        let to = resolve_endpoint(&unbound, &multiverse, HOME, RelationEnd::To);
+        assert_eq!(to, Endpoint::NotFound { space: OFFICIAL });
+    }
+}
@@ -0,0 +1,155 @@
+//! Hooks for external search indexes.
+//!
+//! Applications that keep a full-text index (Tantivy, Meilisearch, ...) or
+//! a vector index (HNSW, ...) next to a [`Graph`] can apply edits through
+//! [`Graph::apply_edit_with_text_sink`] or
+//! [`Graph::apply_edit_with_embedding_sink`] and receive exactly the values
+//! that became visible or stopped being visible, instead of rescanning the
+//! graph after every edit.
+
+use crate::model::{Edit, EmbeddingSubType, Id, Value};
+use crate::state::{Graph, ValueKey};
+
+/// Receives changes to visible TEXT values.
+pub trait TextIndexSink {
+    /// A TEXT value was set, or became visible again on restore.
+    fn index_text(&mut self, entity: Id, property: Id, language: Option<Id>, text: &str);
+
+    /// A TEXT value was unset, replaced by a non-TEXT value, or hidden by
+    /// a delete.
+    fn remove_text(&mut self, entity: Id, property: Id, language: Option<Id>);
+}
+
+/// Receives changes to visible EMBEDDING values.
+pub trait EmbeddingSink {
+    /// An EMBEDDING value was set, or became visible again on restore.
+    fn index_embedding(&mut self, entity: Id, property: Id, sub_type: EmbeddingSubType, dims: usize, data: &[u8]);
+
+    /// An EMBEDDING value was unset, replaced by another type, or hidden by
+    /// a delete.
+    fn remove_embedding(&mut self, entity: Id, property: Id);
+}
+
+fn text<'v>(value: Option<&'v Value<'_>>) -> Option<&'v str> {
+    match value {
+        Some(Value::Text { value, .. }) => Some(value),
+        _ => None,
+    }
+}
+
+fn embedding<'v>(value: Option<&'v Value<'_>>) -> Option<(EmbeddingSubType, usize, &'v [u8])> {
+    match value {
+        Some(Value::Embedding { sub_type, dims, data }) => Some((*sub_type, *dims, data)),
+        _ => None,
+    }
+}
+
+impl Graph {
+    /// Applies an edit, reporting TEXT changes to `sink`.
+    pub fn apply_edit_with_text_sink<S>(&mut self, edit: &Edit<'_>, sink: &mut S)
+    where
+        S: TextIndexSink + ?Sized,
+    {
+        let mut observer = |entity: Id, key: &ValueKey, old: Option<&Value<'static>>, new: Option<&Value<'static>>| {
+            match (text(old), text(new)) {
+                (_, Some(new)) => sink.index_text(entity, key.property, key.language, new),
+                (Some(_), None) => sink.remove_text(entity, key.property, key.language),
+                (None, None) => {}
+            }
+        };
+        self.apply_edit_observed(edit, Some(&mut observer));
+    }
+
+    /// Applies an edit, reporting EMBEDDING changes to `sink`.
+    pub fn apply_edit_with_embedding_sink<S>(&mut self, edit: &Edit<'_>, sink: &mut S)
+    where
+        S: EmbeddingSink + ?Sized,
+    {
+        let mut observer = |entity: Id, key: &ValueKey, old: Option<&Value<'static>>, new: Option<&Value<'static>>| {
+            match (embedding(old), embedding(new)) {
+                (_, Some((sub_type, dims, data))) => sink.index_embedding(entity, key.property, sub_type, dims, data),
+                (Some(_), None) => sink.remove_embedding(entity, key.property),
+                (None, None) => {}
+            }
+        };
+        self.apply_edit_observed(edit, Some(&mut observer));
+    }
+
+    /// Sends every visible TEXT value to `sink`, e.g. to fill a new index
+    /// from a snapshot.
+    pub fn index_all_text<S>(&self, sink: &mut S)
+    where
+        S: TextIndexSink + ?Sized,
+    {
+        for (id, entity) in self.entities() {
+            for (key, value) in &entity.values {
+                if let Some(text) = text(Some(value)) {
+                    sink.index_text(*id, key.property, key.language, text);
+                }
+            }
+        }
+    }
+
+    /// Sends every visible EMBEDDING value to `sink`.
+    pub fn index_all_embeddings<S>(&self, sink: &mut S)
+    where
+        S: EmbeddingSink + ?Sized,
+    {
+        for (id, entity) in self.entities() {
+            for (key, value) in &entity.values {
+                if let Some((sub_type, dims, data)) = embedding(Some(value)) {
+                    sink.index_embedding(*id, key.property, sub_type, dims, data);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use crate::prelude::*;
+
+    const NAME: Id = [10u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl TextIndexSink for Log {
+        fn index_text(&mut self, entity: Id, _: Id, language: Option<Id>, text: &str) {
+            self.0.push(format!("+{} {} {}", entity[0], language.map_or(0, |l| l[0]), text));
+        }
+
+        fn remove_text(&mut self, entity: Id, _: Id, language: Option<Id>) {
+            self.0.push(format!("-{} {}", entity[0], language.map_or(0, |l| l[0])));
+        }
+    }
+
+    #[test]
+    fn test_text_sink_events() {
+        let mut graph = Graph::new();
+        let mut log = Log::default();
+        graph.apply_edit_with_text_sink(
+            &EditBuilder::new([1u8; 16])
+                .create_entity([3u8; 16], |e| e.text(NAME, "Alice", None).text(NAME, "Alicia", Some(GERMAN)))
+                .create_entity([3u8; 16], |e| e.text(NAME, "Alice", None).int64([11u8; 16], 30, None))
+                .update_entity([3u8; 16], |u| u.unset_english(NAME))
+                .build(),
+            &mut log,
+        );
+        assert_eq!(log.0, vec!["+3 0 Alice", "+3 20 Alicia", "-3 0"]);
+
+        log.0.clear();
+        graph.apply_edit_with_text_sink(
+            &EditBuilder::new([2u8; 16]).delete_entity([3u8; 16]).restore_entity([3u8; 16]).build(),
+            &mut log,
+        );
+        assert_eq!(log.0, vec!["-3 20", "+3 20 Alicia"]);
+
+        let mut all = Log::default();
+        graph.index_all_text(&mut all);
+        assert_eq!(all.0, vec!["+3 20 Alicia"]);
+    }
+}
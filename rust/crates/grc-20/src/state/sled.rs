@@ -0,0 +1,436 @@
+//! [`Store`] backend on the sled embedded database.
+//!
+//! Objects live in separate trees keyed by their 16-byte ID (slots by their
+//! encoded slot), so [`StoreGraph`](crate::state::store::StoreGraph) reads
+//! only what an edit touches and the graph can grow past RAM. Batches are
+//! written in a single multi-tree transaction.
+//!
+//! Values use the edit value encoding (spec Section 6.5), preceded by their
+//! property, data type, language, and unit; the other objects use a compact
+//! fixed layout of IDs and flags.
+//!
+//! ```no_run
+//! use grc_20::state::sled::SledStore;
+//! use grc_20::state::store::StoreGraph;
+//!
+//! let mut graph = StoreGraph::new(SledStore::open("space.sled").unwrap());
+//! # let edit = grc_20::EditBuilder::new([1u8; 16]).build();
+//! graph.apply_edit(&edit).unwrap();
+//! ```
+
+use std::path::Path;
+
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use sled::{Db, Transactional, Tree};
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::codec::value::{decode_value, encode_value};
+use crate::error::{DecodeError, EncodeError, StoreError};
+use crate::limits::MAX_POSITION_LEN;
+use crate::model::{DataType, DictionaryBuilder, Id, Value, WireDictionaries};
+use crate::state::store::{Store, StoreBatch};
+use crate::state::{Entity, Relation, ReplayProgress, ValueKey, ValueRefSlot};
+
+const PROGRESS_KEY: &[u8] = b"progress";
+
+// Relation flags
+const REL_DELETED: u8 = 0x01;
+const REL_FROM_IS_VALUE_REF: u8 = 0x02;
+const REL_TO_IS_VALUE_REF: u8 = 0x04;
+
+impl From<sled::Error> for StoreError {
+    fn from(e: sled::Error) -> Self {
+        StoreError::Database(e.to_string())
+    }
+}
+
+/// A [`Store`] in a sled database.
+#[derive(Debug, Clone)]
+pub struct SledStore {
+    db: Db,
+    entities: Tree,
+    relations: Tree,
+    value_refs: Tree,
+    slots: Tree,
+    meta: Tree,
+}
+
+impl SledStore {
+    /// Opens (or creates) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::from_db(sled::open(path)?)
+    }
+
+    /// Opens a temporary database that is removed on drop, mainly for tests.
+    pub fn temporary() -> Result<Self, StoreError> {
+        Self::from_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    /// Uses an open database.
+    pub fn from_db(db: Db) -> Result<Self, StoreError> {
+        Ok(Self {
+            entities: db.open_tree("entities")?,
+            relations: db.open_tree("relations")?,
+            value_refs: db.open_tree("value_refs")?,
+            slots: db.open_tree("value_ref_slots")?,
+            meta: db.open_tree("meta")?,
+            db,
+        })
+    }
+
+    /// Flushes pending writes to disk.
+    pub fn flush(&self) -> Result<(), StoreError> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+impl Store for SledStore {
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError> {
+        self.entities.get(id)?.map(|bytes| decode_entity(&bytes)).transpose()
+    }
+
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError> {
+        self.relations.get(id)?.map(|bytes| decode_relation(&bytes)).transpose()
+    }
+
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError> {
+        self.value_refs.get(id)?.map(|bytes| decode_slot(&bytes)).transpose()
+    }
+
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError> {
+        self.slots.get(encode_slot(slot))?.map(|bytes| decode_id(&bytes)).transpose()
+    }
+
+    fn progress(&self) -> Result<ReplayProgress, StoreError> {
+        match self.meta.get(PROGRESS_KEY)? {
+            Some(bytes) => decode_progress(&bytes),
+            None => Ok(ReplayProgress::default()),
+        }
+    }
+
+    fn write(&mut self, batch: StoreBatch) -> Result<(), StoreError> {
+        // Encode up front; the transaction closure may run more than once
+        let mut entities = Vec::with_capacity(batch.entities.len());
+        for (id, entity) in &batch.entities {
+            entities.push((id, entity.as_ref().map(encode_entity).transpose()?));
+        }
+        let relations: Vec<_> = batch.relations.iter().map(|(id, r)| (id, r.as_ref().map(encode_relation))).collect();
+        let value_refs: Vec<_> = batch.value_refs.iter().map(|(id, s)| (id, s.as_ref().map(encode_slot))).collect();
+        let slots: Vec<_> = batch.slots.iter().map(|(slot, id)| (encode_slot(slot), *id)).collect();
+        let progress = encode_progress(&batch.progress);
+
+        let trees = (&self.entities, &self.relations, &self.value_refs, &self.slots, &self.meta);
+        trees
+            .transaction(|(entity_tree, relation_tree, value_ref_tree, slot_tree, meta_tree)| {
+                for (id, bytes) in &entities {
+                    match bytes {
+                        Some(bytes) => entity_tree.insert(id.as_slice(), bytes.as_slice())?,
+                        None => entity_tree.remove(id.as_slice())?,
+                    };
+                }
+                for (id, bytes) in &relations {
+                    match bytes {
+                        Some(bytes) => relation_tree.insert(id.as_slice(), bytes.as_slice())?,
+                        None => relation_tree.remove(id.as_slice())?,
+                    };
+                }
+                for (id, bytes) in &value_refs {
+                    match bytes {
+                        Some(bytes) => value_ref_tree.insert(id.as_slice(), bytes.as_slice())?,
+                        None => value_ref_tree.remove(id.as_slice())?,
+                    };
+                }
+                for (key, id) in &slots {
+                    match id {
+                        Some(id) => slot_tree.insert(key.as_slice(), id.as_slice())?,
+                        None => slot_tree.remove(key.as_slice())?,
+                    };
+                }
+                meta_tree.insert(PROGRESS_KEY, progress.as_slice())?;
+                Ok::<(), ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Storage(e) => StoreError::from(e),
+                TransactionError::Abort(()) => StoreError::Database("transaction aborted".to_string()),
+            })
+    }
+}
+
+// =============================================================================
+// ENCODING
+// =============================================================================
+
+fn write_opt_id(writer: &mut Writer, id: Option<&Id>) {
+    match id {
+        Some(id) => {
+            writer.write_byte(1);
+            writer.write_id(id);
+        }
+        None => writer.write_byte(0),
+    }
+}
+
+fn read_opt_id(reader: &mut Reader<'_>, field: &'static str) -> Result<Option<Id>, DecodeError> {
+    match reader.read_byte(field)? {
+        0 => Ok(None),
+        1 => Ok(Some(reader.read_id(field)?)),
+        _ => Err(DecodeError::MalformedEncoding { context: "invalid option flag" }),
+    }
+}
+
+fn finish(reader: &Reader<'_>) -> Result<(), StoreError> {
+    if !reader.is_empty() {
+        return Err(StoreError::Corrupt("trailing bytes after stored object"));
+    }
+    Ok(())
+}
+
+fn encode_entity(entity: &Entity) -> Result<Vec<u8>, EncodeError> {
+    let mut writer = Writer::new();
+    writer.write_byte(u8::from(entity.deleted));
+    writer.write_varint(entity.values.len() as u64);
+    for (key, value) in &entity.values {
+        let unit = match value {
+            Value::Int64 { unit, .. } | Value::Float64 { unit, .. } | Value::Decimal { unit, .. } => *unit,
+            _ => None,
+        };
+        writer.write_id(&key.property);
+        writer.write_byte(value.data_type() as u8);
+        write_opt_id(&mut writer, key.language.as_ref());
+        write_opt_id(&mut writer, unit.as_ref());
+        encode_value(&mut writer, value, &mut DictionaryBuilder::default())?;
+    }
+    Ok(writer.into_bytes())
+}
+
+fn decode_entity(bytes: &[u8]) -> Result<Entity, StoreError> {
+    let mut reader = Reader::new(bytes);
+    let mut entity = Entity { deleted: reader.read_byte("deleted")? != 0, ..Entity::default() };
+    let count = reader.read_varint("values")?;
+    for _ in 0..count {
+        let property = reader.read_id("property")?;
+        let dt_byte = reader.read_byte("data_type")?;
+        let data_type = DataType::from_u8(dt_byte).ok_or(DecodeError::InvalidDataType { data_type: dt_byte })?;
+        let language = read_opt_id(&mut reader, "language")?;
+        let unit = read_opt_id(&mut reader, "unit")?;
+        let dicts = WireDictionaries {
+            languages: language.into_iter().collect(),
+            units: unit.into_iter().collect(),
+            ..WireDictionaries::default()
+        };
+        let value = decode_value(&mut reader, data_type, &dicts)?.into_owned();
+        let key = ValueKey { property, language };
+        if ValueKey::of(property, &value) != key {
+            return Err(StoreError::Corrupt("value stored under the wrong language"));
+        }
+        entity.values.insert(key, value);
+    }
+    finish(&reader)?;
+    Ok(entity)
+}
+
+fn encode_relation(relation: &Relation) -> Vec<u8> {
+    let mut flags = 0;
+    if relation.deleted {
+        flags |= REL_DELETED;
+    }
+    if relation.from_is_value_ref {
+        flags |= REL_FROM_IS_VALUE_REF;
+    }
+    if relation.to_is_value_ref {
+        flags |= REL_TO_IS_VALUE_REF;
+    }
+    let mut writer = Writer::new();
+    writer.write_byte(flags);
+    writer.write_id(&relation.relation_type);
+    writer.write_id(&relation.from);
+    writer.write_id(&relation.to);
+    writer.write_id(&relation.entity);
+    match &relation.position {
+        Some(position) => {
+            writer.write_byte(1);
+            writer.write_string(position);
+        }
+        None => writer.write_byte(0),
+    }
+    write_opt_id(&mut writer, relation.from_space.as_ref());
+    write_opt_id(&mut writer, relation.from_version.as_ref());
+    write_opt_id(&mut writer, relation.to_space.as_ref());
+    write_opt_id(&mut writer, relation.to_version.as_ref());
+    writer.into_bytes()
+}
+
+fn decode_relation(bytes: &[u8]) -> Result<Relation, StoreError> {
+    let mut reader = Reader::new(bytes);
+    let flags = reader.read_byte("relation_flags")?;
+    let relation_type = reader.read_id("relation_type")?;
+    let from = reader.read_id("from")?;
+    let to = reader.read_id("to")?;
+    let entity = reader.read_id("entity")?;
+    let position = match reader.read_byte("position")? {
+        0 => None,
+        _ => Some(reader.read_string(MAX_POSITION_LEN, "position")?.to_string()),
+    };
+    let relation = Relation {
+        relation_type,
+        from,
+        from_is_value_ref: flags & REL_FROM_IS_VALUE_REF != 0,
+        to,
+        to_is_value_ref: flags & REL_TO_IS_VALUE_REF != 0,
+        entity,
+        position,
+        from_space: read_opt_id(&mut reader, "from_space")?,
+        from_version: read_opt_id(&mut reader, "from_version")?,
+        to_space: read_opt_id(&mut reader, "to_space")?,
+        to_version: read_opt_id(&mut reader, "to_version")?,
+        deleted: flags & REL_DELETED != 0,
+    };
+    finish(&reader)?;
+    Ok(relation)
+}
+
+fn encode_slot(slot: &ValueRefSlot) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.write_id(&slot.entity);
+    writer.write_id(&slot.property);
+    write_opt_id(&mut writer, slot.language.as_ref());
+    write_opt_id(&mut writer, slot.space.as_ref());
+    writer.into_bytes()
+}
+
+fn decode_slot(bytes: &[u8]) -> Result<ValueRefSlot, StoreError> {
+    let mut reader = Reader::new(bytes);
+    let slot = ValueRefSlot {
+        entity: reader.read_id("entity")?,
+        property: reader.read_id("property")?,
+        language: read_opt_id(&mut reader, "language")?,
+        space: read_opt_id(&mut reader, "space")?,
+    };
+    finish(&reader)?;
+    Ok(slot)
+}
+
+fn decode_id(bytes: &[u8]) -> Result<Id, StoreError> {
+    bytes.try_into().map_err(|_| StoreError::Corrupt("stored ID is not 16 bytes"))
+}
+
+fn encode_progress(progress: &ReplayProgress) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.write_varint(progress.edits as u64);
+    writer.write_varint(progress.ops as u64);
+    write_opt_id(&mut writer, progress.last_edit.as_ref());
+    match progress.last_created_at {
+        Some(created_at) => {
+            writer.write_byte(1);
+            writer.write_signed_varint(created_at);
+        }
+        None => writer.write_byte(0),
+    }
+    writer.into_bytes()
+}
+
+fn decode_progress(bytes: &[u8]) -> Result<ReplayProgress, StoreError> {
+    let mut reader = Reader::new(bytes);
+    let progress = ReplayProgress {
+        edits: reader.read_varint("edits")? as usize,
+        ops: reader.read_varint("ops")? as usize,
+        last_edit: read_opt_id(&mut reader, "last_edit")?,
+        last_created_at: match reader.read_byte("last_created_at")? {
+            0 => None,
+            _ => Some(reader.read_signed_varint("last_created_at")?),
+        },
+    };
+    finish(&reader)?;
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, DecimalMantissa, Edit, EditBuilder, Op};
+    use crate::state::store::{MemoryStore, StoreGraph};
+    use crate::state::Graph;
+
+    fn edits() -> Vec<Edit<'static>> {
+        vec![
+            EditBuilder::new([1u8; 16])
+                .created_at(100)
+                .create_entity([3u8; 16], |e| {
+                    e.text([10u8; 16], "Berlin", Some([20u8; 16]))
+                        .int64([11u8; 16], 3_600_000, Some([30u8; 16]))
+                        .decimal([12u8; 16], -2, DecimalMantissa::I64(12345), None)
+                        .point([13u8; 16], 13.4, 52.5, Some(34.0))
+                        .bytes([14u8; 16], vec![0, 1, 2])
+                })
+                .create_relation(|r| {
+                    r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]).position("a0")
+                })
+                .build(),
+            EditBuilder::new([2u8; 16])
+                .created_at(200)
+                .update_relation([7u8; 16], |r| r.set_from_space([8u8; 16]))
+                .op(Op::CreateValueRef(CreateValueRef {
+                    id: [40u8; 16],
+                    entity: [3u8; 16],
+                    property: [10u8; 16],
+                    language: Some([20u8; 16]),
+                    space: Some([9u8; 16]),
+                }))
+                .delete_entity([6u8; 16])
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_matches_memory_store() {
+        let mut sled = StoreGraph::new(SledStore::temporary().unwrap());
+        let mut memory = StoreGraph::new(MemoryStore::new());
+        for edit in edits() {
+            sled.apply_edit(&edit).unwrap();
+            memory.apply_edit(&edit).unwrap();
+        }
+        let graph: &Graph = memory.store().graph();
+        for id in graph.entities.keys().chain(graph.relations.keys()).chain(graph.value_refs.keys()) {
+            assert_eq!(sled.store().entity(id).unwrap(), memory.store().entity(id).unwrap());
+            assert_eq!(sled.store().relation(id).unwrap(), memory.store().relation(id).unwrap());
+            assert_eq!(sled.value_ref(id).unwrap(), memory.value_ref(id).unwrap());
+        }
+        for slot in graph.value_ref_slots.keys() {
+            assert_eq!(sled.store().slot_value_ref(slot).unwrap(), Some([40u8; 16]));
+        }
+        assert_eq!(sled.progress().unwrap(), memory.progress().unwrap());
+        assert_eq!(sled.progress().unwrap().ops, 5);
+    }
+
+    #[test]
+    fn test_reopen() {
+        let path = std::env::temp_dir().join(format!("grc20-sled-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        // Reopen through the same handle: sled keeps the file lock until its
+        // background threads exit, which can outlive the last drop.
+        let db = sled::open(&path).unwrap();
+        {
+            let mut graph = StoreGraph::new(SledStore::from_db(db.clone()).unwrap());
+            graph.apply_edits(edits()).unwrap();
+            graph.store().flush().unwrap();
+        }
+        let graph = StoreGraph::new(SledStore::from_db(db).unwrap());
+        assert_eq!(graph.relation(&[7u8; 16]).unwrap().unwrap().from_space, Some([8u8; 16]));
+        assert_eq!(graph.entity(&[3u8; 16]).unwrap().unwrap().values.len(), 5);
+        assert_eq!(graph.entity(&[6u8; 16]).unwrap(), None);
+        assert_eq!(graph.progress().unwrap().last_edit, Some([2u8; 16]));
+        drop(graph);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_corrupt_objects() {
+        let store = SledStore::temporary().unwrap();
+        store.entities.insert([1u8; 16], &[0u8, 1, 0xFF][..]).unwrap();
+        store.relations.insert([1u8; 16], &[0u8; 3][..]).unwrap();
+        assert!(store.entity(&[1u8; 16]).is_err());
+        assert!(store.relation(&[1u8; 16]).is_err());
+        assert!(decode_slot(&[0u8; 33]).is_err());
+    }
+}
@@ -0,0 +1,557 @@
+//! SQLite-backed materialized state.
+//!
+//! [`SqliteGraph`] keeps a [`Graph`] in step with a SQLite database, so
+//! clients get durable local state without designing their own schema.
+//! Reads go through the in-memory graph (`SqliteGraph` derefs to
+//! [`Graph`]); [`SqliteGraph::apply_edits`] applies edits and writes every
+//! object they touched in a single transaction, together with a log of the
+//! applied edits.
+//!
+//! The schema stores one row per entity, value, relation, value ref, and
+//! value ref slot, with IDs as 16-byte BLOBs. Values use the edit value
+//! encoding (spec Section 6.5) in a `value` BLOB, with the language and
+//! unit in their own columns. `PRAGMA user_version` records the schema
+//! version.
+//!
+//! ```
+//! use grc_20::state::sqlite::SqliteGraph;
+//! use grc_20::state::ValueKey;
+//! use grc_20::EditBuilder;
+//!
+//! let mut graph = SqliteGraph::open_in_memory().unwrap();
+//! graph
+//!     .apply_edit(&EditBuilder::new([1u8; 16]).create_entity([2u8; 16], |e| e.int64([3u8; 16], 42, None)).build())
+//!     .unwrap();
+//! assert!(graph.value(&[2u8; 16], &ValueKey::new([3u8; 16])).is_some());
+//! assert_eq!(graph.progress().edits, 1);
+//! ```
+
+use std::borrow::Borrow;
+use std::ops::Deref;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::codec::value::{decode_value, encode_value};
+use crate::error::StoreError;
+use crate::model::{DataType, DictionaryBuilder, Edit, Id, Op, Value, WireDictionaries};
+use crate::state::{Entity, Graph, IndexConfig, Relation, ReplayProgress, ValueKey, ValueRefSlot};
+
+/// Current schema version (`PRAGMA user_version`).
+pub const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+BEGIN;
+CREATE TABLE entities (
+    id BLOB PRIMARY KEY,
+    deleted INTEGER NOT NULL
+) WITHOUT ROWID;
+CREATE TABLE entity_values (
+    entity BLOB NOT NULL,
+    property BLOB NOT NULL,
+    language BLOB,
+    data_type INTEGER NOT NULL,
+    unit BLOB,
+    value BLOB NOT NULL
+);
+CREATE INDEX entity_values_entity ON entity_values (entity);
+CREATE TABLE relations (
+    id BLOB PRIMARY KEY,
+    relation_type BLOB NOT NULL,
+    from_id BLOB NOT NULL,
+    from_is_value_ref INTEGER NOT NULL,
+    to_id BLOB NOT NULL,
+    to_is_value_ref INTEGER NOT NULL,
+    entity BLOB NOT NULL,
+    position TEXT,
+    from_space BLOB,
+    from_version BLOB,
+    to_space BLOB,
+    to_version BLOB,
+    deleted INTEGER NOT NULL
+) WITHOUT ROWID;
+CREATE TABLE value_refs (
+    id BLOB PRIMARY KEY,
+    entity BLOB NOT NULL,
+    property BLOB NOT NULL,
+    language BLOB,
+    space BLOB
+) WITHOUT ROWID;
+CREATE TABLE value_ref_slots (
+    entity BLOB NOT NULL,
+    property BLOB NOT NULL,
+    language BLOB,
+    space BLOB,
+    value_ref BLOB NOT NULL
+);
+CREATE INDEX value_ref_slots_slot ON value_ref_slots (entity, property);
+CREATE TABLE edits (
+    seq INTEGER PRIMARY KEY,
+    id BLOB NOT NULL,
+    created_at INTEGER NOT NULL,
+    op_count INTEGER NOT NULL
+);
+PRAGMA user_version = 1;
+COMMIT;
+";
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Database(e.to_string())
+    }
+}
+
+/// A [`Graph`] persisted in a SQLite database.
+#[derive(Debug)]
+pub struct SqliteGraph {
+    conn: Connection,
+    graph: Graph,
+    progress: ReplayProgress,
+    index_config: Option<IndexConfig>,
+}
+
+impl SqliteGraph {
+    /// Opens (or creates) the database at `path` and loads its state.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a fresh in-memory database, mainly for tests.
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Uses an open connection, creating the schema if the database is
+    /// empty.
+    pub fn from_connection(conn: Connection) -> Result<Self, StoreError> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        match version {
+            0 => conn.execute_batch(SCHEMA)?,
+            SCHEMA_VERSION => {}
+            version => return Err(StoreError::UnsupportedSchema { version }),
+        }
+        let graph = load_graph(&conn)?;
+        let progress = load_progress(&conn)?;
+        Ok(Self { conn, graph, progress, index_config: None })
+    }
+
+    /// Applies an edit and persists the result.
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) -> Result<(), StoreError> {
+        self.apply_edits([edit])
+    }
+
+    /// Applies edits in log order and persists them in one transaction.
+    ///
+    /// On error nothing is written and the in-memory state is reloaded from
+    /// the database.
+    pub fn apply_edits<'e, I, E>(&mut self, edits: I) -> Result<(), StoreError>
+    where
+        I: IntoIterator<Item = E>,
+        E: Borrow<Edit<'e>>,
+    {
+        let mut touched = Vec::new();
+        let mut slots = Vec::new();
+        let mut logged = Vec::new();
+        let mut progress = self.progress;
+        for edit in edits {
+            let edit = edit.borrow();
+            for op in &edit.ops {
+                self.graph.touched_ids(op, &mut touched);
+                if let Op::CreateValueRef(cvr) = op {
+                    slots.push(ValueRefSlot {
+                        entity: cvr.entity,
+                        property: cvr.property,
+                        language: cvr.language,
+                        space: cvr.space,
+                    });
+                }
+                self.graph.apply_op(op);
+            }
+            progress.edits += 1;
+            progress.ops += edit.ops.len();
+            progress.last_edit = Some(edit.id);
+            progress.last_created_at = Some(edit.created_at);
+            logged.push((edit.id, edit.created_at, edit.ops.len()));
+        }
+
+        touched.sort_unstable();
+        touched.dedup();
+        match persist(&mut self.conn, &self.graph, &touched, &slots, &logged) {
+            Ok(()) => {
+                self.progress = progress;
+                Ok(())
+            }
+            Err(e) => {
+                self.reload()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the in-memory graph.
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Returns how many edits and ops have been applied, and the last edit.
+    pub fn progress(&self) -> &ReplayProgress {
+        &self.progress
+    }
+
+    /// Returns the underlying connection, e.g. for SQL queries over the
+    /// tables. Writing to the tables directly is not supported.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Builds secondary indexes on the in-memory graph (see
+    /// [`Graph::set_indexes`]); they are rebuilt after a reload.
+    pub fn set_indexes(&mut self, config: IndexConfig) {
+        self.graph.set_indexes(config.clone());
+        self.index_config = Some(config);
+    }
+
+    /// Returns the in-memory graph, closing the database.
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+
+    /// Replaces the in-memory state with the database contents.
+    fn reload(&mut self) -> Result<(), StoreError> {
+        self.graph = load_graph(&self.conn)?;
+        self.progress = load_progress(&self.conn)?;
+        if let Some(config) = &self.index_config {
+            self.graph.set_indexes(config.clone());
+        }
+        Ok(())
+    }
+}
+
+impl Deref for SqliteGraph {
+    type Target = Graph;
+
+    fn deref(&self) -> &Graph {
+        &self.graph
+    }
+}
+
+// =============================================================================
+// WRITING
+// =============================================================================
+
+fn persist(
+    conn: &mut Connection,
+    graph: &Graph,
+    touched: &[Id],
+    slots: &[ValueRefSlot],
+    logged: &[(Id, i64, usize)],
+) -> Result<(), StoreError> {
+    let tx = conn.transaction()?;
+    for id in touched {
+        write_entity(&tx, id, graph.entities.get(id))?;
+        write_relation(&tx, id, graph.relations.get(id))?;
+        write_value_ref(&tx, id, graph.value_refs.get(id))?;
+    }
+    for slot in slots {
+        tx.prepare_cached(
+            "DELETE FROM value_ref_slots
+             WHERE entity = ?1 AND property = ?2 AND language IS ?3 AND space IS ?4",
+        )?
+        .execute(params![slot.entity, slot.property, slot.language, slot.space])?;
+        if let Some(value_ref) = graph.value_ref_slots.get(slot) {
+            tx.prepare_cached(
+                "INSERT INTO value_ref_slots (entity, property, language, space, value_ref)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![slot.entity, slot.property, slot.language, slot.space, value_ref])?;
+        }
+    }
+    for (id, created_at, op_count) in logged {
+        tx.prepare_cached("INSERT INTO edits (id, created_at, op_count) VALUES (?1, ?2, ?3)")?
+            .execute(params![id, created_at, *op_count as i64])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn write_entity(tx: &Transaction<'_>, id: &Id, entity: Option<&Entity>) -> Result<(), StoreError> {
+    tx.prepare_cached("DELETE FROM entity_values WHERE entity = ?1")?.execute([id])?;
+    let Some(entity) = entity else {
+        tx.prepare_cached("DELETE FROM entities WHERE id = ?1")?.execute([id])?;
+        return Ok(());
+    };
+    tx.prepare_cached("INSERT OR REPLACE INTO entities (id, deleted) VALUES (?1, ?2)")?
+        .execute(params![id, entity.deleted])?;
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO entity_values (entity, property, language, data_type, unit, value)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for (key, value) in &entity.values {
+        let mut writer = Writer::new();
+        encode_value(&mut writer, value, &mut DictionaryBuilder::default())?;
+        insert.execute(params![
+            id,
+            key.property,
+            key.language,
+            value.data_type() as u8,
+            value_unit(value),
+            writer.as_bytes(),
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_relation(tx: &Transaction<'_>, id: &Id, relation: Option<&Relation>) -> Result<(), StoreError> {
+    let Some(r) = relation else {
+        tx.prepare_cached("DELETE FROM relations WHERE id = ?1")?.execute([id])?;
+        return Ok(());
+    };
+    tx.prepare_cached(
+        "INSERT OR REPLACE INTO relations (id, relation_type, from_id, from_is_value_ref, to_id,
+             to_is_value_ref, entity, position, from_space, from_version, to_space, to_version, deleted)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+    )?
+    .execute(params![
+        id,
+        r.relation_type,
+        r.from,
+        r.from_is_value_ref,
+        r.to,
+        r.to_is_value_ref,
+        r.entity,
+        r.position,
+        r.from_space,
+        r.from_version,
+        r.to_space,
+        r.to_version,
+        r.deleted,
+    ])?;
+    Ok(())
+}
+
+fn write_value_ref(tx: &Transaction<'_>, id: &Id, slot: Option<&ValueRefSlot>) -> Result<(), StoreError> {
+    match slot {
+        Some(slot) => tx
+            .prepare_cached(
+                "INSERT OR REPLACE INTO value_refs (id, entity, property, language, space)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params![id, slot.entity, slot.property, slot.language, slot.space])?,
+        None => tx.prepare_cached("DELETE FROM value_refs WHERE id = ?1")?.execute([id])?,
+    };
+    Ok(())
+}
+
+fn value_unit(value: &Value<'_>) -> Option<Id> {
+    match value {
+        Value::Int64 { unit, .. } | Value::Float64 { unit, .. } | Value::Decimal { unit, .. } => *unit,
+        _ => None,
+    }
+}
+
+// =============================================================================
+// LOADING
+// =============================================================================
+
+fn load_graph(conn: &Connection) -> Result<Graph, StoreError> {
+    let mut graph = Graph::new();
+
+    let mut stmt = conn.prepare("SELECT id, deleted FROM entities")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let entity = Entity { deleted: row.get(1)?, ..Entity::default() };
+        graph.entities.insert(row.get(0)?, entity);
+    }
+
+    let mut stmt = conn.prepare("SELECT entity, property, language, data_type, unit, value FROM entity_values")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: Id = row.get(0)?;
+        let property: Id = row.get(1)?;
+        let language: Option<Id> = row.get(2)?;
+        let dt_byte: u8 = row.get(3)?;
+        let unit: Option<Id> = row.get(4)?;
+        let bytes: Vec<u8> = row.get(5)?;
+
+        let data_type = DataType::from_u8(dt_byte).ok_or(StoreError::Corrupt("unknown data type"))?;
+        let dicts = WireDictionaries {
+            languages: language.into_iter().collect(),
+            units: unit.into_iter().collect(),
+            ..WireDictionaries::default()
+        };
+        let mut reader = Reader::new(&bytes);
+        let value = decode_value(&mut reader, data_type, &dicts)?.into_owned();
+        if !reader.is_empty() {
+            return Err(StoreError::Corrupt("trailing bytes after value"));
+        }
+        let key = ValueKey { property, language };
+        if ValueKey::of(property, &value) != key {
+            return Err(StoreError::Corrupt("value stored under the wrong language"));
+        }
+        let entity = graph.entities.get_mut(&id).ok_or(StoreError::Corrupt("value of a missing entity"))?;
+        entity.values.insert(key, value);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, relation_type, from_id, from_is_value_ref, to_id, to_is_value_ref, entity, position,
+                from_space, from_version, to_space, to_version, deleted
+         FROM relations",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let relation = Relation {
+            relation_type: row.get(1)?,
+            from: row.get(2)?,
+            from_is_value_ref: row.get(3)?,
+            to: row.get(4)?,
+            to_is_value_ref: row.get(5)?,
+            entity: row.get(6)?,
+            position: row.get(7)?,
+            from_space: row.get(8)?,
+            from_version: row.get(9)?,
+            to_space: row.get(10)?,
+            to_version: row.get(11)?,
+            deleted: row.get(12)?,
+        };
+        graph.relations.insert(row.get(0)?, relation);
+    }
+
+    let mut stmt = conn.prepare("SELECT id, entity, property, language, space FROM value_refs")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let slot = ValueRefSlot { entity: row.get(1)?, property: row.get(2)?, language: row.get(3)?, space: row.get(4)? };
+        graph.value_refs.insert(row.get(0)?, slot);
+    }
+
+    let mut stmt = conn.prepare("SELECT entity, property, language, space, value_ref FROM value_ref_slots")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let slot = ValueRefSlot { entity: row.get(0)?, property: row.get(1)?, language: row.get(2)?, space: row.get(3)? };
+        graph.value_ref_slots.insert(slot, row.get(4)?);
+    }
+
+    Ok(graph)
+}
+
+fn load_progress(conn: &Connection) -> Result<ReplayProgress, StoreError> {
+    let (edits, ops): (i64, i64) =
+        conn.query_row("SELECT COUNT(*), COALESCE(SUM(op_count), 0) FROM edits", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+    let last: Option<(Id, i64)> = conn
+        .query_row("SELECT id, created_at FROM edits ORDER BY seq DESC LIMIT 1", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()?;
+    Ok(ReplayProgress {
+        edits: edits as usize,
+        ops: ops as usize,
+        last_edit: last.map(|(id, _)| id),
+        last_created_at: last.map(|(_, created_at)| created_at),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, DecimalMantissa, EditBuilder};
+
+    const NAME: Id = [10u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    fn edits() -> Vec<Edit<'static>> {
+        vec![
+            EditBuilder::new([1u8; 16])
+                .created_at(100)
+                .create_entity([3u8; 16], |e| {
+                    e.text(NAME, "Berlin", None)
+                        .text(NAME, "Berlin (de)", Some(GERMAN))
+                        .int64([11u8; 16], 3_600_000, Some([30u8; 16]))
+                        .float64([12u8; 16], 891.8, None)
+                        .bool([13u8; 16], true)
+                        .point([14u8; 16], 13.4, 52.5, None)
+                        .decimal([15u8; 16], -2, DecimalMantissa::I64(12345), Some([31u8; 16]))
+                        .bytes([16u8; 16], vec![1, 2, 3])
+                })
+                .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]).position("a"))
+                .delete_entity([6u8; 16])
+                .build(),
+            EditBuilder::new([2u8; 16])
+                .created_at(200)
+                .update_entity([3u8; 16], |u| u.unset_english(NAME).set_int64([11u8; 16], 3_700_000, None))
+                .update_relation([7u8; 16], |r| r.unset_position().set_to_space([8u8; 16]))
+                .delete_relation([7u8; 16])
+                .op(Op::CreateValueRef(CreateValueRef {
+                    id: [40u8; 16],
+                    entity: [3u8; 16],
+                    property: NAME,
+                    language: Some(GERMAN),
+                    space: None,
+                }))
+                .op(Op::CreateValueRef(CreateValueRef {
+                    id: [41u8; 16],
+                    entity: [3u8; 16],
+                    property: NAME,
+                    language: Some(GERMAN),
+                    space: None,
+                }))
+                .build(),
+        ]
+    }
+
+    fn temp_db(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("grc20-sqlite-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_persists_across_reopen() {
+        let edits = edits();
+        let mut expected = Graph::new();
+        for edit in &edits {
+            expected.apply_edit(edit);
+        }
+
+        let path = temp_db("reopen");
+        {
+            let mut graph = SqliteGraph::open(&path).unwrap();
+            graph.apply_edit(&edits[0]).unwrap();
+            graph.apply_edit(&edits[1]).unwrap();
+            assert_eq!(*graph.graph(), expected);
+        }
+        let graph = SqliteGraph::open(&path).unwrap();
+        assert_eq!(*graph.graph(), expected);
+        assert_eq!(graph.progress().edits, 2);
+        assert_eq!(graph.progress().ops, 8);
+        assert_eq!(graph.progress().last_edit, Some([2u8; 16]));
+        assert_eq!(graph.progress().last_created_at, Some(200));
+        assert_eq!(graph.value_ref(&[40u8; 16]), None);
+        assert!(graph.value_ref(&[41u8; 16]).is_some());
+        drop(graph);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_batch_matches_single_edits() {
+        let mut single = SqliteGraph::open_in_memory().unwrap();
+        let mut batch = SqliteGraph::open_in_memory().unwrap();
+        for edit in edits() {
+            single.apply_edit(&edit).unwrap();
+        }
+        batch.apply_edits(edits()).unwrap();
+        assert_eq!(*single.graph(), *batch.graph());
+        assert_eq!(single.progress(), batch.progress());
+
+        let rows: i64 = batch.connection().query_row("SELECT COUNT(*) FROM entity_values", [], |r| r.get(0)).unwrap();
+        assert_eq!(rows, 7);
+    }
+
+    #[test]
+    fn test_rejects_unknown_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", 99).unwrap();
+        assert_eq!(SqliteGraph::from_connection(conn).unwrap_err(), StoreError::UnsupportedSchema { version: 99 });
+    }
+}
@@ -0,0 +1,418 @@
+//! Materialization over pluggable storage.
+//!
+//! A [`Graph`] holds the whole state of a space in memory. For spaces that
+//! do not fit, [`StoreGraph`] keeps the state in a [`Store`] and loads only
+//! the objects an edit touches: each batch of edits is applied to a scratch
+//! [`Graph`] seeded with those objects, under exactly the same resolution
+//! rules, and the results are written back in one [`StoreBatch`].
+//!
+//! Backends implement [`Store`] as point lookups plus an atomic batch write.
+//! [`MemoryStore`] keeps everything in memory; with the `sled` feature,
+//! `state::sled::SledStore` persists to disk.
+//!
+//! ```
+//! use grc_20::state::store::{MemoryStore, StoreGraph};
+//! use grc_20::state::ValueKey;
+//! use grc_20::EditBuilder;
+//!
+//! let mut graph = StoreGraph::new(MemoryStore::new());
+//! graph
+//!     .apply_edit(&EditBuilder::new([1u8; 16]).create_entity([2u8; 16], |e| e.int64([3u8; 16], 42, None)).build())
+//!     .unwrap();
+//! assert!(graph.value(&[2u8; 16], &ValueKey::new([3u8; 16])).unwrap().is_some());
+//! ```
+
+use core::borrow::Borrow;
+
+use crate::collections::FxHashSet;
+use crate::error::StoreError;
+use crate::model::{relation_entity_id, Edit, Id, Op, Value};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, Relation, ReplayProgress, ValueKey, ValueRefSlot};
+
+/// Storage backend for [`StoreGraph`].
+///
+/// Lookups return the stored object, including tombstoned ones.
+pub trait Store {
+    /// Returns an entity.
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError>;
+
+    /// Returns a relation.
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError>;
+
+    /// Returns the slot a value ref is bound to.
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError>;
+
+    /// Returns the value ref that currently holds a slot.
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError>;
+
+    /// Returns the progress recorded by the last batch.
+    fn progress(&self) -> Result<ReplayProgress, StoreError>;
+
+    /// Writes a batch atomically.
+    fn write(&mut self, batch: StoreBatch) -> Result<(), StoreError>;
+}
+
+/// Changes produced by applying edits; `None` removes an object.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreBatch {
+    pub entities: Vec<(Id, Option<Entity>)>,
+    pub relations: Vec<(Id, Option<Relation>)>,
+    pub value_refs: Vec<(Id, Option<ValueRefSlot>)>,
+    pub slots: Vec<(ValueRefSlot, Option<Id>)>,
+    pub progress: ReplayProgress,
+}
+
+/// A [`Store`] in memory, backed by a [`Graph`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryStore {
+    graph: Graph,
+    progress: ReplayProgress,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the stored state as a graph.
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Returns the stored state as a graph.
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+}
+
+impl Store for MemoryStore {
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError> {
+        Ok(self.graph.entities.get(id).cloned())
+    }
+
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError> {
+        Ok(self.graph.relations.get(id).cloned())
+    }
+
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError> {
+        Ok(self.graph.value_refs.get(id).copied())
+    }
+
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError> {
+        Ok(self.graph.value_ref_slots.get(slot).copied())
+    }
+
+    fn progress(&self) -> Result<ReplayProgress, StoreError> {
+        Ok(self.progress)
+    }
+
+    fn write(&mut self, batch: StoreBatch) -> Result<(), StoreError> {
+        let graph = &mut self.graph;
+        for (id, entity) in batch.entities {
+            match entity {
+                Some(entity) => graph.entities.insert(id, entity),
+                None => graph.entities.remove(&id),
+            };
+        }
+        for (id, relation) in batch.relations {
+            match relation {
+                Some(relation) => graph.relations.insert(id, relation),
+                None => graph.relations.remove(&id),
+            };
+        }
+        for (id, slot) in batch.value_refs {
+            match slot {
+                Some(slot) => graph.value_refs.insert(id, slot),
+                None => graph.value_refs.remove(&id),
+            };
+        }
+        for (slot, id) in batch.slots {
+            match id {
+                Some(id) => graph.value_ref_slots.insert(slot, id),
+                None => graph.value_ref_slots.remove(&slot),
+            };
+        }
+        self.progress = batch.progress;
+        Ok(())
+    }
+}
+
+/// Materialized state kept in a [`Store`].
+#[derive(Debug, Clone, Default)]
+pub struct StoreGraph<S> {
+    store: S,
+}
+
+impl<S: Store> StoreGraph<S> {
+    /// Materializes into `store`, continuing from the state it holds.
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Returns the store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Applies an edit and writes the result.
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) -> Result<(), StoreError> {
+        self.apply_edits([edit])
+    }
+
+    /// Applies edits in log order and writes the result as one batch.
+    ///
+    /// Memory use grows with the number of objects the edits touch, so
+    /// split very large replays into several calls.
+    pub fn apply_edits<'e, I, E>(&mut self, edits: I) -> Result<(), StoreError>
+    where
+        I: IntoIterator<Item = E>,
+        E: Borrow<Edit<'e>>,
+    {
+        let mut scratch = Scratch::default();
+        let mut progress = self.store.progress()?;
+        for edit in edits {
+            let edit = edit.borrow();
+            for op in &edit.ops {
+                scratch.load_op(&self.store, op)?;
+            }
+            scratch.graph.apply_edit(edit);
+            progress.edits += 1;
+            progress.ops += edit.ops.len();
+            progress.last_edit = Some(edit.id);
+            progress.last_created_at = Some(edit.created_at);
+        }
+        self.store.write(scratch.into_batch(progress))
+    }
+
+    /// Returns a live (not deleted) entity.
+    pub fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError> {
+        Ok(self.store.entity(id)?.filter(|e| !e.deleted))
+    }
+
+    /// Returns a live (not deleted) relation.
+    pub fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError> {
+        Ok(self.store.relation(id)?.filter(|r| !r.deleted))
+    }
+
+    /// Returns the slot a value ref is bound to.
+    pub fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError> {
+        self.store.value_ref(id)
+    }
+
+    /// Returns the value of a live entity in the given slot.
+    pub fn value(&self, entity: &Id, key: &ValueKey) -> Result<Option<Value<'static>>, StoreError> {
+        Ok(self.entity(entity)?.and_then(|mut e| e.values.remove(key)))
+    }
+
+    /// Returns how many edits and ops have been applied, and the last edit.
+    pub fn progress(&self) -> Result<ReplayProgress, StoreError> {
+        self.store.progress()
+    }
+}
+
+/// Where a [`Scratch`] loads objects from: a [`Store`], or the base graph
+/// of a [`Fork`](crate::state::Fork).
+pub(crate) trait Source {
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError>;
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError>;
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError>;
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError>;
+}
+
+impl<S: Store> Source for S {
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError> {
+        Store::entity(self, id)
+    }
+
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError> {
+        Store::relation(self, id)
+    }
+
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError> {
+        Store::value_ref(self, id)
+    }
+
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError> {
+        Store::slot_value_ref(self, slot)
+    }
+}
+
+impl Source for Graph {
+    fn entity(&self, id: &Id) -> Result<Option<Entity>, StoreError> {
+        Ok(self.entities.get(id).cloned())
+    }
+
+    fn relation(&self, id: &Id) -> Result<Option<Relation>, StoreError> {
+        Ok(self.relations.get(id).cloned())
+    }
+
+    fn value_ref(&self, id: &Id) -> Result<Option<ValueRefSlot>, StoreError> {
+        Ok(self.value_refs.get(id).copied())
+    }
+
+    fn slot_value_ref(&self, slot: &ValueRefSlot) -> Result<Option<Id>, StoreError> {
+        Ok(self.value_ref_slots.get(slot).copied())
+    }
+}
+
+/// The objects loaded for a batch, and the graph they are applied to.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scratch {
+    pub(crate) graph: Graph,
+    pub(crate) ids: FxHashSet<Id>,
+    pub(crate) slots: FxHashSet<ValueRefSlot>,
+}
+
+impl Scratch {
+    /// Loads every object `op` reads (see `Graph::apply_op`).
+    pub(crate) fn load_op<S: Source>(&mut self, store: &S, op: &Op<'_>) -> Result<(), StoreError> {
+        match op {
+            Op::CreateEntity(ce) => self.load_id(store, ce.id),
+            Op::UpdateEntity(ue) => self.load_id(store, ue.id),
+            Op::DeleteEntity(de) => self.load_id(store, de.id),
+            Op::RestoreEntity(re) => self.load_id(store, re.id),
+            Op::CreateRelation(cr) => {
+                self.load_id(store, cr.id)?;
+                self.load_id(store, cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id)))
+            }
+            Op::UpdateRelation(ur) => self.load_id(store, ur.id),
+            Op::DeleteRelation(dr) => self.load_id(store, dr.id),
+            Op::RestoreRelation(rr) => self.load_id(store, rr.id),
+            Op::CreateValueRef(cvr) => {
+                self.load_id(store, cvr.id)?;
+                let slot = ValueRefSlot {
+                    entity: cvr.entity,
+                    property: cvr.property,
+                    language: cvr.language,
+                    space: cvr.space,
+                };
+                if self.slots.insert(slot) {
+                    if let Some(holder) = store.slot_value_ref(&slot)? {
+                        self.graph.value_ref_slots.insert(slot, holder);
+                        self.load_id(store, holder)?;
+                    }
+                }
+                Ok(())
+            }
+            Op::Unknown(_) => Ok(()),
+        }
+    }
+
+    /// Loads whatever object `id` names.
+    fn load_id<S: Source>(&mut self, store: &S, id: Id) -> Result<(), StoreError> {
+        if !self.ids.insert(id) {
+            return Ok(());
+        }
+        if let Some(entity) = store.entity(&id)? {
+            self.graph.entities.insert(id, entity);
+        }
+        if let Some(relation) = store.relation(&id)? {
+            self.graph.relations.insert(id, relation);
+        }
+        if let Some(slot) = store.value_ref(&id)? {
+            self.graph.value_refs.insert(id, slot);
+        }
+        Ok(())
+    }
+
+    /// Returns the state of every loaded object.
+    fn into_batch(mut self, progress: ReplayProgress) -> StoreBatch {
+        let mut batch = StoreBatch { progress, ..StoreBatch::default() };
+        for id in self.ids {
+            batch.entities.push((id, self.graph.entities.remove(&id)));
+            batch.relations.push((id, self.graph.relations.remove(&id)));
+            batch.value_refs.push((id, self.graph.value_refs.remove(&id)));
+        }
+        for slot in self.slots {
+            batch.slots.push((slot, self.graph.value_ref_slots.get(&slot).copied()));
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder};
+
+    const NAME: Id = [10u8; 16];
+
+    fn value_ref(id: u8) -> Op<'static> {
+        Op::CreateValueRef(CreateValueRef { id: [id; 16], entity: [3u8; 16], property: NAME, language: None, space: None })
+    }
+
+    fn edits() -> Vec<Edit<'static>> {
+        vec![
+            EditBuilder::new([1u8; 16])
+                .created_at(100)
+                .create_entity([3u8; 16], |e| e.text(NAME, "Berlin", None).int64([11u8; 16], 1, None))
+                .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([3u8; 16]).to([4u8; 16]))
+                .op(value_ref(40))
+                .create_entity([6u8; 16], |e| e)
+                .delete_entity([6u8; 16])
+                .build(),
+            EditBuilder::new([2u8; 16])
+                .created_at(200)
+                .update_entity([3u8; 16], |u| u.unset_english(NAME))
+                .update_relation([7u8; 16], |r| r.set_position("b"))
+                // Shared namespace: ignored, as [7; 16] is a relation
+                .create_entity([7u8; 16], |e| e.int64([11u8; 16], 2, None))
+                .create_entity([6u8; 16], |e| e.int64([11u8; 16], 3, None))
+                .op(value_ref(41))
+                .build(),
+            EditBuilder::new([3u8; 16])
+                .created_at(300)
+                .delete_relation([7u8; 16])
+                .restore_entity([6u8; 16])
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_matches_in_memory_graph() {
+        let edits = edits();
+        let mut expected = Graph::new();
+        for edit in &edits {
+            expected.apply_edit(edit);
+        }
+
+        // One batch per edit, and all edits in one batch
+        let mut single = StoreGraph::new(MemoryStore::new());
+        for edit in &edits {
+            single.apply_edit(edit).unwrap();
+        }
+        let mut batched = StoreGraph::new(MemoryStore::new());
+        batched.apply_edits(&edits).unwrap();
+
+        for graph in [single, batched] {
+            assert_eq!(graph.progress().unwrap().edits, 3);
+            assert_eq!(graph.progress().unwrap().last_created_at, Some(300));
+            assert_eq!(graph.relation(&[7u8; 16]).unwrap(), None);
+            assert!(graph.entity(&[6u8; 16]).unwrap().unwrap().values.is_empty());
+            assert_eq!(graph.value_ref(&[40u8; 16]).unwrap(), None);
+            assert_eq!(graph.into_store().into_graph(), expected);
+        }
+    }
+
+    #[test]
+    fn test_batch_holds_only_touched_objects() {
+        let edits = edits();
+        let mut graph = StoreGraph::new(MemoryStore::new());
+        graph.apply_edits(&edits[..1]).unwrap();
+
+        let mut scratch = Scratch::default();
+        for op in &edits[2].ops {
+            scratch.load_op(graph.store(), op).unwrap();
+        }
+        assert_eq!(scratch.ids.len(), 2);
+        assert_eq!(scratch.graph.entities.len(), 1);
+        assert_eq!(scratch.graph.relations.len(), 1);
+    }
+}
@@ -0,0 +1,301 @@
+//! Change notifications.
+//!
+//! [`Graph::subscribe`] returns a channel that receives a [`ChangeEvent`]
+//! for every entity, value, and relation an applied edit changes, so
+//! caches and push layers can follow a graph without diffing it:
+//!
+//! ```
+//! use grc_20::state::{ChangeEvent, ChangeFilter, Graph};
+//! use grc_20::EditBuilder;
+//!
+//! let population = [3u8; 16];
+//! let mut graph = Graph::new();
+//! let events = graph.subscribe(ChangeFilter::new().property(population));
+//! graph.apply_edit(&EditBuilder::new([1u8; 16]).create_entity([2u8; 16], |e| e.int64(population, 3, None)).build());
+//!
+//! let event = events.try_recv().unwrap();
+//! assert!(matches!(event, ChangeEvent::ValueChanged { entity, .. } if entity == [2u8; 16]));
+//! ```
+//!
+//! Events are sent once per edit applied with [`Graph::apply_edit`] (or the
+//! sink and replay variants), after the whole edit is applied, and compare
+//! the state before and after it: an entity created and deleted in the same
+//! edit sends nothing. Ops applied one at a time with [`Graph::apply_op`]
+//! are not reported. Subscriptions are not cloned with the graph, and end
+//! when their receiver is dropped.
+
+use std::collections::BTreeSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::collections::FxHashMap;
+use crate::model::{Edit, Id, Op, Value};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, Relation, ValueKey};
+
+/// A change made by an edit, from [`Graph::subscribe`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeEvent {
+    /// An entity became live, by a create or a restore. Its values follow as
+    /// [`ChangeEvent::ValueChanged`].
+    EntityAdded { edit: Id, id: Id },
+    /// A live entity was deleted. Its values are reported as removed.
+    EntityDeleted { edit: Id, id: Id },
+    /// A visible value slot changed; `None` is an empty slot.
+    ValueChanged {
+        edit: Id,
+        entity: Id,
+        key: ValueKey,
+        before: Option<Value<'static>>,
+        after: Option<Value<'static>>,
+    },
+    /// A relation became live, by a create or a restore.
+    RelationAdded { edit: Id, id: Id, relation: Relation },
+    /// A live relation's position or pins changed.
+    RelationUpdated { edit: Id, id: Id, relation: Relation },
+    /// A live relation was deleted.
+    RelationDeleted { edit: Id, id: Id, relation: Relation },
+}
+
+/// Which changes a subscription receives.
+///
+/// With no restrictions, every event is sent. [`ChangeFilter::property`]
+/// and [`ChangeFilter::relation_type`] select kinds of changes: once either
+/// is set, only value changes of the given properties and relation events
+/// of the given types are sent, and entity events are not.
+/// [`ChangeFilter::entity`] further limits events to the given entities,
+/// their values, and relations from or to them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeFilter {
+    pub entities: Vec<Id>,
+    pub properties: Vec<Id>,
+    pub relation_types: Vec<Id>,
+}
+
+impl ChangeFilter {
+    /// Creates a filter that sends every event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits events to an entity (repeatable).
+    pub fn entity(mut self, id: Id) -> Self {
+        self.entities.push(id);
+        self
+    }
+
+    /// Selects value changes of a property (repeatable).
+    pub fn property(mut self, id: Id) -> Self {
+        self.properties.push(id);
+        self
+    }
+
+    /// Selects relation events of a relation type (repeatable).
+    pub fn relation_type(mut self, id: Id) -> Self {
+        self.relation_types.push(id);
+        self
+    }
+
+    /// Returns whether the filter lets `event` through.
+    pub fn matches(&self, event: &ChangeEvent) -> bool {
+        let entity = |id: &Id| self.entities.is_empty() || self.entities.contains(id);
+        let kinds = self.properties.is_empty() && self.relation_types.is_empty();
+        match event {
+            ChangeEvent::EntityAdded { id, .. } | ChangeEvent::EntityDeleted { id, .. } => kinds && entity(id),
+            ChangeEvent::ValueChanged { entity: id, key, .. } => {
+                (kinds || self.properties.contains(&key.property)) && entity(id)
+            }
+            ChangeEvent::RelationAdded { relation, .. }
+            | ChangeEvent::RelationUpdated { relation, .. }
+            | ChangeEvent::RelationDeleted { relation, .. } => {
+                (kinds || self.relation_types.contains(&relation.relation_type))
+                    && (entity(&relation.from) || entity(&relation.to))
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Subscribes to the changes made by edits applied from now on.
+    pub fn subscribe(&mut self, filter: ChangeFilter) -> Receiver<ChangeEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.0.push(Subscriber { filter, sender });
+        receiver
+    }
+}
+
+/// Open subscriptions of a graph. Cloning a graph does not clone them.
+#[derive(Debug, Default)]
+pub(crate) struct Subscribers(Vec<Subscriber>);
+
+#[derive(Debug)]
+struct Subscriber {
+    filter: ChangeFilter,
+    sender: Sender<ChangeEvent>,
+}
+
+impl Clone for Subscribers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Subscribers {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The state of the objects an edit touches, from before their first op.
+#[derive(Default)]
+pub(crate) struct Before {
+    entities: FxHashMap<Id, Option<Entity>>,
+    relations: FxHashMap<Id, Option<Relation>>,
+    ids: Vec<Id>,
+}
+
+impl Before {
+    /// Records the objects `op` may change. Must be called before the op
+    /// is applied.
+    pub(crate) fn record(&mut self, graph: &Graph, op: &Op<'_>) {
+        let start = self.ids.len();
+        graph.touched_ids(op, &mut self.ids);
+        for id in &self.ids[start..] {
+            self.entities.entry(*id).or_insert_with(|| graph.entities.get(id).cloned());
+            self.relations.entry(*id).or_insert_with(|| graph.relations.get(id).cloned());
+        }
+    }
+}
+
+impl Graph {
+    /// Sends the changes `edit` made since `before` to the subscribers.
+    pub(crate) fn notify(&mut self, edit: &Edit<'_>, mut before: Before) {
+        let mut events = Vec::new();
+        before.ids.sort_unstable();
+        before.ids.dedup();
+        for id in &before.ids {
+            entity_events(&mut events, edit.id, *id, before.entities[id].as_ref(), self.entities.get(id));
+            relation_events(&mut events, edit.id, *id, before.relations[id].as_ref(), self.relations.get(id));
+        }
+        if events.is_empty() {
+            return;
+        }
+        self.subscribers.0.retain(|s| {
+            events.iter().filter(|e| s.filter.matches(e)).all(|e| s.sender.send(e.clone()).is_ok())
+        });
+    }
+}
+
+fn entity_events(events: &mut Vec<ChangeEvent>, edit: Id, id: Id, before: Option<&Entity>, after: Option<&Entity>) {
+    let before = before.filter(|e| !e.deleted);
+    let after = after.filter(|e| !e.deleted);
+    match (before, after) {
+        (None, Some(_)) => events.push(ChangeEvent::EntityAdded { edit, id }),
+        (Some(_), None) => events.push(ChangeEvent::EntityDeleted { edit, id }),
+        _ => {}
+    }
+    let old = before.map(|e| &e.values);
+    let new = after.map(|e| &e.values);
+    let keys: BTreeSet<&ValueKey> = old.into_iter().chain(new).flat_map(|values| values.keys()).collect();
+    for key in keys {
+        let (before, after) = (old.and_then(|v| v.get(key)), new.and_then(|v| v.get(key)));
+        if before != after {
+            events.push(ChangeEvent::ValueChanged {
+                edit,
+                entity: id,
+                key: *key,
+                before: before.cloned(),
+                after: after.cloned(),
+            });
+        }
+    }
+}
+
+fn relation_events(
+    events: &mut Vec<ChangeEvent>,
+    edit: Id,
+    id: Id,
+    before: Option<&Relation>,
+    after: Option<&Relation>,
+) {
+    let before = before.filter(|r| !r.deleted);
+    let live = after.filter(|r| !r.deleted);
+    let event = match (before, live) {
+        (None, Some(r)) => ChangeEvent::RelationAdded { edit, id, relation: r.clone() },
+        (Some(r), None) => ChangeEvent::RelationDeleted { edit, id, relation: r.clone() },
+        (Some(old), Some(r)) if old != r => ChangeEvent::RelationUpdated { edit, id, relation: r.clone() },
+        _ => return,
+    };
+    events.push(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const NAME: Id = [10u8; 16];
+    const POPULATION: Id = [11u8; 16];
+    const PART_OF: Id = [12u8; 16];
+    const BERLIN: Id = [2u8; 16];
+    const GERMANY: Id = [3u8; 16];
+
+    fn drain(events: &Receiver<ChangeEvent>) -> Vec<ChangeEvent> {
+        events.try_iter().collect()
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let mut graph = Graph::new();
+        let all = graph.subscribe(ChangeFilter::new());
+        let population = graph.subscribe(ChangeFilter::new().property(POPULATION));
+        let relations = graph.subscribe(ChangeFilter::new().entity(GERMANY).relation_type(PART_OF));
+
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_entity(BERLIN, |e| e.text(NAME, "Berlin", None).int64(POPULATION, 3, None))
+                .create_relation_simple([4u8; 16], BERLIN, GERMANY, PART_OF)
+                // Created and deleted within the edit: no events
+                .create_entity([5u8; 16], |e| e.text(NAME, "Bonn", None))
+                .delete_entity([5u8; 16])
+                .build(),
+        );
+        // Berlin, its two values, the relation, and its reified entity
+        assert_eq!(drain(&all).len(), 5);
+        assert_eq!(drain(&population).len(), 1);
+        assert!(matches!(drain(&relations)[..], [ChangeEvent::RelationAdded { id, .. }] if id == [4u8; 16]));
+
+        graph.apply_edit(
+            &EditBuilder::new([2u8; 16])
+                .update_entity(BERLIN, |u| u.set_int64(POPULATION, 4, None).set_text(NAME, "Berlin", None))
+                .update_relation([4u8; 16], |u| u.set_position("a0"))
+                .build(),
+        );
+        let events = drain(&population);
+        assert_eq!(events.len(), 1);
+        let ChangeEvent::ValueChanged { edit, before, after, .. } = &events[0] else {
+            panic!("expected ValueChanged");
+        };
+        assert_eq!(*edit, [2u8; 16]);
+        assert_eq!(before, &Some(Value::Int64 { value: 3, unit: None }));
+        assert_eq!(after, &Some(Value::Int64 { value: 4, unit: None }));
+        assert!(matches!(drain(&relations)[..], [ChangeEvent::RelationUpdated { .. }]));
+
+        // Deleting reports the values as removed
+        graph.apply_edit(&EditBuilder::new([3u8; 16]).delete_entity(BERLIN).build());
+        assert!(matches!(drain(&population)[..], [ChangeEvent::ValueChanged { after: None, .. }]));
+        // Two from the update, then Berlin and its two values
+        assert_eq!(drain(&all).len(), 5);
+    }
+
+    #[test]
+    fn test_dropped_receiver_unsubscribes() {
+        let mut graph = Graph::new();
+        drop(graph.subscribe(ChangeFilter::new()));
+        assert!(!graph.subscribers.is_empty());
+        graph.apply_edit(&EditBuilder::new([1u8; 16]).create_entity(BERLIN, |e| e).build());
+        assert!(graph.subscribers.is_empty());
+        // Clones do not share subscriptions
+        let _events = graph.subscribe(ChangeFilter::new());
+        assert!(graph.clone().subscribers.is_empty());
+    }
+}
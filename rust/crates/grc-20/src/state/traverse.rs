@@ -0,0 +1,273 @@
+//! Relation traversal over a materialized graph.
+//!
+//! ```ignore
+//! // All subregions of Europe, however deeply nested
+//! let subregions = graph.reachable(&europe, &[part_of], Direction::Incoming, usize::MAX);
+//! let path = graph.shortest_path(&berlin, &europe, &[part_of], Direction::Outgoing);
+//!
+//! // Cities are places if City -[subtype_of]-> Place
+//! graph.set_subtype_relation(Some(subtype_of));
+//! assert!(graph.is_instance_of(&berlin, &place));
+//! ```
+
+use alloc::collections::VecDeque;
+
+use crate::collections::FxHashMap;
+use crate::model::id::types_relation_type;
+use crate::model::Id;
+use crate::prelude::*;
+use crate::state::{relation_order, Graph, Relation};
+
+/// Which way to follow relations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// From `from` to `to`.
+    Outgoing,
+    /// From `to` to `from`.
+    Incoming,
+    /// Both ways.
+    Both,
+}
+
+impl Graph {
+    /// Returns the endpoints one live relation away from `id` (optionally
+    /// of one type), without duplicates. Outgoing neighbors come before
+    /// incoming ones, each in spec order.
+    pub fn neighbors(&self, id: &Id, relation_type: Option<Id>, direction: Direction) -> Vec<Id> {
+        let mut endpoints = Vec::new();
+        if direction != Direction::Incoming {
+            endpoints.extend(self.outgoing(id, relation_type).into_iter().map(|(_, r)| r.to));
+        }
+        if direction != Direction::Outgoing {
+            endpoints.extend(self.incoming(id, relation_type).into_iter().map(|(_, r)| r.from));
+        }
+        let mut seen = Vec::with_capacity(endpoints.len());
+        for neighbor in endpoints {
+            if !seen.contains(&neighbor) {
+                seen.push(neighbor);
+            }
+        }
+        seen
+    }
+
+    /// Returns every object reachable from `id` in at most `max_depth` hops
+    /// over live relations of the given types (any type if empty), with
+    /// its distance, in breadth-first order. `id` itself is not included.
+    pub fn reachable(
+        &self,
+        id: &Id,
+        relation_types: &[Id],
+        direction: Direction,
+        max_depth: usize,
+    ) -> Vec<(Id, usize)> {
+        let adjacency = self.adjacency(relation_types, direction);
+        let mut depths: FxHashMap<Id, usize> = FxHashMap::default();
+        depths.insert(*id, 0);
+        let mut out = Vec::new();
+        let mut queue = VecDeque::from([(*id, 0)]);
+        while let Some((node, depth)) = queue.pop_front() {
+            if depth == max_depth {
+                continue;
+            }
+            for next in adjacency.get(&node).into_iter().flatten() {
+                if !depths.contains_key(next) {
+                    depths.insert(*next, depth + 1);
+                    out.push((*next, depth + 1));
+                    queue.push_back((*next, depth + 1));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns a shortest path from `from` to `to` over live relations of
+    /// the given types (any type if empty), including both ends, or `None`
+    /// if `to` is unreachable.
+    ///
+    /// Ties are broken by spec relation order, so the result is stable.
+    pub fn shortest_path(&self, from: &Id, to: &Id, relation_types: &[Id], direction: Direction) -> Option<Vec<Id>> {
+        let adjacency = self.adjacency(relation_types, direction);
+        // Node -> the node it was first reached from
+        let mut parents: FxHashMap<Id, Id> = FxHashMap::default();
+        parents.insert(*from, *from);
+        let mut queue = VecDeque::from([*from]);
+        while let Some(node) = queue.pop_front() {
+            if node == *to {
+                let mut path = vec![node];
+                let mut current = node;
+                while current != *from {
+                    current = parents[&current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in adjacency.get(&node).into_iter().flatten() {
+                if !parents.contains_key(next) {
+                    parents.insert(*next, node);
+                    queue.push_back(*next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Designates the relation type that links a type to its supertype
+    /// (`subtype -[relation_type]-> supertype`), or clears it.
+    pub fn set_subtype_relation(&mut self, relation_type: Option<Id>) {
+        self.subtype_relation = relation_type;
+    }
+
+    /// Builder form of [`Graph::set_subtype_relation`].
+    pub fn with_subtype_relation(mut self, relation_type: Id) -> Self {
+        self.subtype_relation = Some(relation_type);
+        self
+    }
+
+    /// Returns the designated subtype relation type.
+    pub fn subtype_relation(&self) -> Option<Id> {
+        self.subtype_relation
+    }
+
+    /// Returns true if `subtype` is `supertype` or reaches it through a
+    /// chain of live subtype relations. Cycles in the hierarchy are
+    /// tolerated.
+    pub fn is_subtype_of(&self, subtype: &Id, supertype: &Id) -> bool {
+        if subtype == supertype {
+            return true;
+        }
+        let Some(relation_type) = self.subtype_relation else {
+            return false;
+        };
+        self.reachable(subtype, &[relation_type], Direction::Outgoing, usize::MAX)
+            .iter()
+            .any(|(id, _)| id == supertype)
+    }
+
+    /// Returns true if the live entity has `type_id`, or a subtype of it,
+    /// as a type.
+    ///
+    /// Types are read through the indexed types relation (see
+    /// [`IndexConfig::types`](crate::state::IndexConfig::types)), or the
+    /// Genesis `Types` relation if no type index is set.
+    pub fn is_instance_of(&self, entity: &Id, type_id: &Id) -> bool {
+        if self.entity(entity).is_none() {
+            return false;
+        }
+        self.outgoing(entity, Some(self.types_relation())).iter().any(|(_, r)| self.is_subtype_of(&r.to, type_id))
+    }
+
+    /// The relation type that assigns types: the indexed one, or the
+    /// Genesis `Types` relation.
+    pub(crate) fn types_relation(&self) -> Id {
+        self.index_config().and_then(|c| c.types_relation).unwrap_or_else(types_relation_type)
+    }
+
+    /// Builds the neighbor lists of one traversal in a single pass over
+    /// the relations, each in spec order.
+    fn adjacency(&self, relation_types: &[Id], direction: Direction) -> FxHashMap<Id, Vec<Id>> {
+        let mut relations: Vec<(&Id, &Relation)> = self
+            .relations()
+            .filter(|(_, r)| relation_types.is_empty() || relation_types.contains(&r.relation_type))
+            .collect();
+        relations.sort_by(|(a_id, a), (b_id, b)| relation_order(a_id, a, b_id, b));
+        let mut adjacency: FxHashMap<Id, Vec<Id>> = FxHashMap::default();
+        for (_, r) in relations {
+            if direction != Direction::Incoming {
+                adjacency.entry(r.from).or_default().push(r.to);
+            }
+            if direction != Direction::Outgoing {
+                adjacency.entry(r.to).or_default().push(r.from);
+            }
+        }
+        adjacency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const PART_OF: Id = [50u8; 16];
+    const BORDERS: Id = [51u8; 16];
+    const EUROPE: Id = [1u8; 16];
+    const GERMANY: Id = [2u8; 16];
+    const FRANCE: Id = [3u8; 16];
+    const BAVARIA: Id = [4u8; 16];
+    const MUNICH: Id = [5u8; 16];
+
+    fn make_graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([9u8; 16])
+                .create_relation_simple([10u8; 16], GERMANY, EUROPE, PART_OF)
+                .create_relation_simple([11u8; 16], FRANCE, EUROPE, PART_OF)
+                .create_relation_simple([12u8; 16], BAVARIA, GERMANY, PART_OF)
+                .create_relation_simple([13u8; 16], MUNICH, BAVARIA, PART_OF)
+                .create_relation_simple([14u8; 16], GERMANY, FRANCE, BORDERS)
+                .create_relation_simple([15u8; 16], FRANCE, GERMANY, BORDERS)
+                .build(),
+        );
+        graph
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let graph = make_graph();
+        assert_eq!(graph.neighbors(&GERMANY, Some(PART_OF), Direction::Outgoing), vec![EUROPE]);
+        assert_eq!(graph.neighbors(&EUROPE, Some(PART_OF), Direction::Incoming), vec![GERMANY, FRANCE]);
+        // FRANCE is both an outgoing and an incoming BORDERS neighbor
+        assert_eq!(graph.neighbors(&GERMANY, Some(BORDERS), Direction::Both), vec![FRANCE]);
+        assert_eq!(graph.neighbors(&GERMANY, None, Direction::Both), vec![EUROPE, FRANCE, BAVARIA]);
+    }
+
+    #[test]
+    fn test_reachable_and_shortest_path() {
+        let graph = make_graph();
+        assert_eq!(
+            graph.reachable(&EUROPE, &[PART_OF], Direction::Incoming, usize::MAX),
+            vec![(GERMANY, 1), (FRANCE, 1), (BAVARIA, 2), (MUNICH, 3)]
+        );
+        assert_eq!(graph.reachable(&EUROPE, &[PART_OF], Direction::Incoming, 1).len(), 2);
+        assert_eq!(graph.reachable(&MUNICH, &[], Direction::Outgoing, usize::MAX).len(), 4);
+
+        assert_eq!(
+            graph.shortest_path(&MUNICH, &EUROPE, &[PART_OF], Direction::Outgoing),
+            Some(vec![MUNICH, BAVARIA, GERMANY, EUROPE])
+        );
+        assert_eq!(
+            graph.shortest_path(&MUNICH, &FRANCE, &[PART_OF, BORDERS], Direction::Outgoing),
+            Some(vec![MUNICH, BAVARIA, GERMANY, FRANCE])
+        );
+        assert_eq!(graph.shortest_path(&EUROPE, &MUNICH, &[PART_OF], Direction::Outgoing), None);
+        assert_eq!(graph.shortest_path(&EUROPE, &EUROPE, &[], Direction::Both), Some(vec![EUROPE]));
+    }
+
+    #[test]
+    fn test_is_instance_of() {
+        let (types, subtype_of) = (types_relation_type(), [60u8; 16]);
+        let (place, region, city, person) = ([61u8; 16], [62u8; 16], [63u8; 16], [64u8; 16]);
+        let mut graph = make_graph();
+        graph.apply_edit(
+            &EditBuilder::new([8u8; 16])
+                .create_empty_entity(MUNICH)
+                .create_relation_simple([20u8; 16], MUNICH, city, types)
+                .create_relation_simple([21u8; 16], city, region, subtype_of)
+                .create_relation_simple([22u8; 16], region, place, subtype_of)
+                // A cycle must not hang the lookup
+                .create_relation_simple([23u8; 16], place, region, subtype_of)
+                .build(),
+        );
+        assert!(graph.is_instance_of(&MUNICH, &city));
+        assert!(!graph.is_instance_of(&MUNICH, &place));
+
+        graph.set_subtype_relation(Some(subtype_of));
+        assert!(graph.is_instance_of(&MUNICH, &place));
+        assert!(graph.is_subtype_of(&city, &region));
+        assert!(!graph.is_subtype_of(&place, &city));
+        assert!(!graph.is_instance_of(&MUNICH, &person));
+        // Not an entity of the graph
+        assert!(!graph.is_instance_of(&BAVARIA, &place));
+    }
+}
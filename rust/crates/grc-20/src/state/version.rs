@@ -0,0 +1,161 @@
+//! Version pins.
+//!
+//! A relation can pin its target to the state at the end of an edit
+//! (`to_version`, spec Section 5.2), e.g. to cite a claim as it read when
+//! it was cited. [`pin_relation_to_current`] fills the pin in from a graph,
+//! and [`entity_at_version`] and [`resolve_to_version`] read the pinned
+//! state back:
+//!
+//! ```
+//! use grc_20::state::version::{pin_relation_to_current, resolve_to_version};
+//! use grc_20::state::{Graph, ObjectState, ValueKey};
+//! use grc_20::{EditBuilder, Op, RelationBuilder, Value};
+//!
+//! let (claim, text) = ([2u8; 16], [3u8; 16]);
+//! let mut graph = Graph::new().with_history();
+//! graph.apply_edit(&EditBuilder::new([1u8; 16]).create_entity(claim, |e| e.text(text, "v1", None)).build());
+//!
+//! let citation = RelationBuilder::new().id([6u8; 16]).from([7u8; 16]).to(claim).relation_type([8u8; 16]);
+//! let mut citation = citation.build().unwrap();
+//! assert_eq!(pin_relation_to_current(&mut citation, &graph), Some([1u8; 16]));
+//! graph.apply_edit(&EditBuilder::new([4u8; 16]).op(Op::CreateRelation(citation)).build());
+//! graph.apply_edit(&EditBuilder::new([5u8; 16]).update_entity(claim, |u| u.set_text(text, "v2", None)).build());
+//!
+//! let relation = graph.relation(&[6u8; 16]).unwrap();
+//! let Some(ObjectState::Entity(cited)) = resolve_to_version(relation, &graph) else { panic!() };
+//! assert_eq!(cited.value(&ValueKey::new(text)), Some(&Value::Text { value: "v1".into(), language: None }));
+//! ```
+//!
+//! Pins are resolved from history (see [`Graph::enable_history`]), so only
+//! edits applied while the graph was recording can be pinned or resolved.
+
+use crate::model::{CreateRelation, Id};
+use crate::state::{Graph, ObjectState, Relation};
+
+/// Sets a relation's `to_version` to the edit that produced the current
+/// state of its target in `graph`, and returns that edit.
+///
+/// `graph` is the graph of the space the target is read from. For a value
+/// ref target, the entity holding the value is pinned. If the target has
+/// not changed since history was enabled, the last applied edit is used.
+/// Returns `None`, leaving the relation unchanged, if the graph has no
+/// history, applied no edit since enabling it, or does not know the target.
+pub fn pin_relation_to_current(relation: &mut CreateRelation<'_>, graph: &Graph) -> Option<Id> {
+    let target = match relation.to_is_value_ref {
+        true => graph.value_ref(&relation.to)?.entity,
+        false => relation.to,
+    };
+    let versions = graph.entity_history(&target);
+    let edit = versions.last()?.edit.or_else(|| graph.applied_edits().last().copied())?;
+    relation.to_version = Some(edit);
+    Some(edit)
+}
+
+/// Returns the state of an entity at the end of edit `version`: an entity,
+/// [`ObjectState::Deleted`], or [`ObjectState::NotFound`] if it did not
+/// exist yet.
+///
+/// Returns `None` if the graph has no history or did not record the edit.
+pub fn entity_at_version<'g>(graph: &'g Graph, entity: &Id, version: &Id) -> Option<ObjectState<'g>> {
+    Some(match graph.entity_at_edit(entity, version)? {
+        None => ObjectState::NotFound,
+        Some(entity) if entity.deleted => ObjectState::Deleted,
+        Some(entity) => ObjectState::Entity(entity),
+    })
+}
+
+/// Returns the state of a relation's target entity as of its `to_version`,
+/// or its current state if the relation is not pinned.
+///
+/// Value ref targets are not entities and resolve to `None`; see
+/// [`resolve_endpoint`](crate::state::resolve_endpoint) for those and for
+/// space pins.
+pub fn resolve_to_version<'g>(relation: &Relation, graph: &'g Graph) -> Option<ObjectState<'g>> {
+    if relation.to_is_value_ref {
+        return None;
+    }
+    match &relation.to_version {
+        Some(version) => entity_at_version(graph, &relation.to, version),
+        None => Some(graph.state(&relation.to)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder, Op, RelationBuilder, Value};
+    use crate::state::ValueKey;
+
+    const CLAIM: Id = [2u8; 16];
+    const TEXT: Id = [10u8; 16];
+
+    fn text(state: Option<ObjectState<'_>>) -> Option<Value<'static>> {
+        match state? {
+            ObjectState::Entity(entity) => entity.value(&ValueKey::new(TEXT)).cloned(),
+            _ => None,
+        }
+    }
+
+    fn citation(to: RelationBuilder<'static>) -> CreateRelation<'static> {
+        to.id([20u8; 16]).from([21u8; 16]).relation_type([22u8; 16]).build().unwrap()
+    }
+
+    fn set_text(graph: &mut Graph, edit: u8, value: &'static str) {
+        graph.apply_edit(&EditBuilder::new([edit; 16]).create_entity(CLAIM, |e| e.text(TEXT, value, None)).build());
+    }
+
+    #[test]
+    fn test_pin_and_resolve() {
+        let mut graph = Graph::new().with_history();
+        set_text(&mut graph, 1, "v1");
+        graph.apply_edit(&EditBuilder::new([3u8; 16]).create_entity([9u8; 16], |e| e).build());
+
+        // The pin names the edit that last changed the target
+        let mut cite = citation(RelationBuilder::new().to(CLAIM));
+        assert_eq!(pin_relation_to_current(&mut cite, &graph), Some([1u8; 16]));
+        graph.apply_edit(&EditBuilder::new([4u8; 16]).op(Op::CreateRelation(cite)).build());
+        set_text(&mut graph, 5, "v2");
+        graph.apply_edit(&EditBuilder::new([6u8; 16]).delete_entity(CLAIM).build());
+
+        let relation = graph.relation(&[20u8; 16]).unwrap().clone();
+        let v1 = Some(Value::Text { value: "v1".into(), language: None });
+        assert_eq!(text(resolve_to_version(&relation, &graph)), v1);
+        let unpinned = Relation { to_version: None, ..relation.clone() };
+        assert_eq!(resolve_to_version(&unpinned, &graph), Some(ObjectState::Deleted));
+        assert_eq!(entity_at_version(&graph, &CLAIM, &[6u8; 16]), Some(ObjectState::Deleted));
+        assert_eq!(entity_at_version(&graph, &[9u8; 16], &[1u8; 16]), Some(ObjectState::NotFound));
+        assert_eq!(entity_at_version(&graph, &CLAIM, &[7u8; 16]), None);
+    }
+
+    #[test]
+    fn test_pin_needs_history() {
+        let mut graph = Graph::new();
+        set_text(&mut graph, 1, "v1");
+        let mut cite = citation(RelationBuilder::new().to(CLAIM));
+        assert_eq!(pin_relation_to_current(&mut cite, &graph), None);
+        assert_eq!(cite.to_version, None);
+
+        // Unchanged since history started: the last applied edit stands in
+        graph.enable_history();
+        assert_eq!(pin_relation_to_current(&mut cite, &graph), None);
+        graph.apply_edit(&EditBuilder::new([3u8; 16]).create_entity([9u8; 16], |e| e).build());
+        assert_eq!(pin_relation_to_current(&mut cite, &graph), Some([3u8; 16]));
+        assert_eq!(text(entity_at_version(&graph, &CLAIM, &[3u8; 16])), text(Some(graph.state(&CLAIM))));
+
+        // A value ref target pins the entity holding the value
+        graph.apply_edit(
+            &EditBuilder::new([4u8; 16])
+                .update_entity(CLAIM, |u| u.set_text(TEXT, "v2", None))
+                .op(Op::CreateValueRef(CreateValueRef {
+                    id: [30u8; 16],
+                    entity: CLAIM,
+                    property: TEXT,
+                    language: None,
+                    space: None,
+                }))
+                .build(),
+        );
+        let mut quote = citation(RelationBuilder::new().to_value_ref([30u8; 16]));
+        assert_eq!(pin_relation_to_current(&mut quote, &graph), Some([4u8; 16]));
+    }
+}
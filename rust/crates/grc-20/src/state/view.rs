@@ -0,0 +1,273 @@
+//! Materialized views.
+//!
+//! A [`View`] selects the entities of a type and projects some of their
+//! properties into rows. Registered with [`Graph::add_view`], it is kept up
+//! to date as ops are applied, so API layers can serve the same projection
+//! on every request without recomputing it:
+//!
+//! ```
+//! # // Genesis IDs need `std`
+//! # #[cfg(feature = "std")] {
+//! use grc_20::genesis::{properties, types};
+//! use grc_20::state::{Graph, View};
+//! use grc_20::EditBuilder;
+//!
+//! let population = [7u8; 16];
+//! let mut graph = Graph::new();
+//! let cities = graph.add_view(View::new().of_type(types::place()).select([properties::name(), population]));
+//! graph.apply_edit(
+//!     &EditBuilder::new([1u8; 16])
+//!         .create_typed_entity([2u8; 16], types::place(), |e| e.text(properties::name(), "Berlin", None))
+//!         .build(),
+//! );
+//!
+//! let rows: Vec<_> = graph.view(cities).unwrap().rows().collect();
+//! assert_eq!(rows.len(), 1);
+//! assert!(rows[0].values[0].is_some() && rows[0].values[1].is_none());
+//! # }
+//! ```
+
+use alloc::collections::BTreeMap;
+
+use crate::collections::FxHashMap;
+use crate::model::id::types_relation_type;
+use crate::model::{Id, Value};
+use crate::prelude::*;
+use crate::state::{Graph, Relation};
+
+/// Definition of a materialized view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct View {
+    /// Only entities with a live types relation to this type; every live
+    /// entity if `None`.
+    pub type_id: Option<Id>,
+    /// Relation type that assigns types (the Genesis `Types` relation by
+    /// default).
+    pub types_relation: Id,
+    /// The properties projected into each row, in column order.
+    pub columns: Vec<Id>,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self { type_id: None, types_relation: types_relation_type(), columns: Vec::new() }
+    }
+}
+
+impl View {
+    /// Creates a view of every live entity, with no columns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the view to entities of a type.
+    pub fn of_type(mut self, type_id: Id) -> Self {
+        self.type_id = Some(type_id);
+        self
+    }
+
+    /// Sets the relation type that assigns types.
+    pub fn types_relation(mut self, relation_type: Id) -> Self {
+        self.types_relation = relation_type;
+        self
+    }
+
+    /// Adds columns for properties.
+    pub fn select(mut self, properties: impl IntoIterator<Item = Id>) -> Self {
+        self.columns.extend(properties);
+        self
+    }
+}
+
+/// Handle of a view registered with [`Graph::add_view`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ViewId(usize);
+
+/// A row of a [`MaterializedView`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Row<'v> {
+    pub entity: Id,
+    /// One value per column, preferring the default language for TEXT.
+    pub values: &'v [Option<Value<'static>>],
+}
+
+/// The maintained rows of a [`View`].
+#[derive(Debug, Clone)]
+pub struct MaterializedView {
+    view: View,
+    rows: BTreeMap<Id, Vec<Option<Value<'static>>>>,
+    /// Types relations to the view's type, and whether each is live.
+    typing: FxHashMap<Id, bool>,
+    /// Entity -> number of live types relations to the view's type.
+    typed: FxHashMap<Id, usize>,
+}
+
+impl MaterializedView {
+    fn build(view: View, graph: &Graph) -> Self {
+        let mut materialized =
+            Self { view, rows: BTreeMap::new(), typing: FxHashMap::default(), typed: FxHashMap::default() };
+        let touched: Vec<Id> = match materialized.view.type_id {
+            Some(_) => graph.relations.keys().copied().collect(),
+            None => graph.entities.keys().copied().collect(),
+        };
+        materialized.refresh(graph, &touched);
+        materialized
+    }
+
+    /// Returns the definition.
+    pub fn definition(&self) -> &View {
+        &self.view
+    }
+
+    /// Iterates over the rows, by entity ID.
+    pub fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        self.rows.iter().map(|(entity, values)| Row { entity: *entity, values })
+    }
+
+    /// Returns the row of an entity.
+    pub fn row(&self, entity: &Id) -> Option<Row<'_>> {
+        self.rows.get_key_value(entity).map(|(entity, values)| Row { entity: *entity, values })
+    }
+
+    /// Returns the number of rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns true if the view has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn is_typing(&self, relation: &Relation) -> bool {
+        relation.relation_type == self.view.types_relation && Some(relation.to) == self.view.type_id
+    }
+
+    /// Updates the rows for objects whose state may have changed.
+    fn refresh(&mut self, graph: &Graph, touched: &[Id]) {
+        let mut entities = Vec::new();
+        for id in touched {
+            if let Some(relation) = graph.relations.get(id).filter(|r| self.is_typing(r)) {
+                let live = !relation.deleted;
+                if self.typing.insert(*id, live) != Some(live) {
+                    let count = self.typed.entry(relation.from).or_default();
+                    if live {
+                        *count += 1;
+                    } else if *count > 1 {
+                        *count -= 1;
+                    } else {
+                        self.typed.remove(&relation.from);
+                    }
+                }
+                entities.push(relation.from);
+            }
+            if graph.entities.contains_key(id) {
+                entities.push(*id);
+            }
+        }
+        entities.sort_unstable();
+        entities.dedup();
+        for id in entities {
+            let member = self.view.type_id.is_none() || self.typed.contains_key(&id);
+            match graph.entity(&id).filter(|_| member) {
+                Some(entity) => {
+                    let values = self
+                        .view
+                        .columns
+                        .iter()
+                        .map(|p| entity.values_of(*p).next().map(|(_, value)| value.clone()))
+                        .collect();
+                    self.rows.insert(id, values);
+                }
+                None => {
+                    self.rows.remove(&id);
+                }
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Registers a view, built from the current state and then maintained
+    /// as ops are applied.
+    pub fn add_view(&mut self, view: View) -> ViewId {
+        let materialized = MaterializedView::build(view, self);
+        self.views.push(Some(materialized));
+        ViewId(self.views.len() - 1)
+    }
+
+    /// Returns a registered view.
+    pub fn view(&self, id: ViewId) -> Option<&MaterializedView> {
+        self.views.get(id.0)?.as_ref()
+    }
+
+    /// Stops maintaining a view and returns it.
+    pub fn remove_view(&mut self, id: ViewId) -> Option<MaterializedView> {
+        self.views.get_mut(id.0)?.take()
+    }
+
+    pub(crate) fn has_views(&self) -> bool {
+        self.views.iter().any(Option::is_some)
+    }
+
+    /// Updates every view for the IDs ops touched.
+    pub(crate) fn refresh_views(&mut self, touched: &[Id]) {
+        let mut views = core::mem::take(&mut self.views);
+        for view in views.iter_mut().flatten() {
+            view.refresh(self, touched);
+        }
+        self.views = views;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const NAME: Id = [10u8; 16];
+    const POPULATION: Id = [11u8; 16];
+    const CITY: Id = [20u8; 16];
+    const BERLIN: Id = [2u8; 16];
+    const HAMBURG: Id = [3u8; 16];
+
+    fn rows(graph: &Graph, id: ViewId) -> Vec<(Id, Vec<Option<Value<'static>>>)> {
+        graph.view(id).unwrap().rows().map(|row| (row.entity, row.values.to_vec())).collect()
+    }
+
+    #[test]
+    fn test_view_follows_edits() {
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_typed_entity(BERLIN, CITY, |e| e.text(NAME, "Berlin", None).int64(POPULATION, 3, None))
+                .create_entity(HAMBURG, |e| e.text(NAME, "Hamburg", Some([9u8; 16])))
+                .build(),
+        );
+        let cities = graph.add_view(View::new().of_type(CITY).select([NAME, POPULATION]));
+        let everything = graph.add_view(View::new().select([NAME]));
+        let berlin = || Some(Value::Text { value: "Berlin".into(), language: None });
+        assert_eq!(rows(&graph, cities), vec![(BERLIN, vec![berlin(), Some(Value::Int64 { value: 3, unit: None })])]);
+
+        // Typing, updating, and deleting change the rows
+        let typing = crate::model::id::unique_relation_id(&HAMBURG, &CITY, &types_relation_type());
+        graph.apply_edit(
+            &EditBuilder::new([2u8; 16])
+                .create_relation_simple(typing, HAMBURG, CITY, types_relation_type())
+                .update_entity(BERLIN, |u| u.unset_all(POPULATION))
+                .build(),
+        );
+        let hamburg = Some(Value::Text { value: "Hamburg".into(), language: Some([9u8; 16]) });
+        assert_eq!(rows(&graph, cities), vec![(BERLIN, vec![berlin(), None]), (HAMBURG, vec![hamburg, None])]);
+
+        graph.apply_edit(&EditBuilder::new([3u8; 16]).delete_relation(typing).delete_entity(BERLIN).build());
+        assert!(graph.view(cities).unwrap().is_empty());
+        graph.apply_op(&crate::model::Op::RestoreEntity(crate::model::RestoreEntity { id: BERLIN, context: None }));
+        assert_eq!(graph.view(cities).unwrap().len(), 1);
+
+        // The untyped view holds every live entity, reified ones included
+        assert_eq!(graph.view(everything).unwrap().len(), graph.entity_count());
+        assert!(graph.remove_view(cities).is_some());
+        assert!(graph.view(cities).is_none());
+    }
+}
@@ -0,0 +1,311 @@
+//! Invertible Bloom filters of edit IDs.
+
+use sha2::{Digest as _, Sha256};
+
+use crate::collections::FxHashSet;
+use crate::error::SyncError;
+use crate::model::{Edit, Id};
+use crate::prelude::*;
+use crate::state::Graph;
+
+/// Number of cells each ID is added to.
+pub const HASH_COUNT: usize = 3;
+
+/// Number of differing edits a [`digest`] is sized for.
+pub const DEFAULT_CAPACITY: usize = 32;
+
+/// One cell of a [`Digest`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Cell {
+    /// Number of IDs in the cell (negative in a difference).
+    pub count: i64,
+    /// XOR of the IDs.
+    pub id_sum: Id,
+    /// XOR of the IDs' check hashes.
+    pub hash_sum: u64,
+}
+
+/// A fixed-size summary of a set of edit IDs: an invertible Bloom filter.
+///
+/// Each ID is added to [`HASH_COUNT`] cells, one in each equal part of the
+/// digest. With `h = SHA-256("GRC2SYNC" || id)` and `w(i)` the
+/// little-endian `u64` at bytes `8i..8i + 8` of `h`, the ID's check hash is
+/// `w(0)` and its cell in part `j` is `w(j + 1) mod part_size`. A cell
+/// holds the number of IDs added, the XOR of the IDs, and the XOR of their
+/// check hashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    cells: Vec<Cell>,
+}
+
+/// The edit IDs that differ between two digests, each list sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Difference {
+    /// IDs only in this digest: edits the other peer is missing.
+    pub to_send: Vec<Id>,
+    /// IDs only in the other digest: edits this peer is missing.
+    pub to_request: Vec<Id>,
+}
+
+impl Difference {
+    /// Returns true if both sides hold the same edits.
+    pub fn is_empty(&self) -> bool {
+        self.to_send.is_empty() && self.to_request.is_empty()
+    }
+}
+
+/// Returns the check hash of an ID and the cells it is added to.
+fn cells_of(id: &Id, len: usize) -> (u64, [usize; HASH_COUNT]) {
+    let hash = Sha256::new().chain_update(b"GRC2SYNC").chain_update(id).finalize();
+    let word = |i: usize| u64::from_le_bytes(hash[i * 8..i * 8 + 8].try_into().unwrap());
+    let part = len / HASH_COUNT;
+    let mut cells = [0; HASH_COUNT];
+    for (j, cell) in cells.iter_mut().enumerate() {
+        *cell = j * part + (word(j + 1) % part as u64) as usize;
+    }
+    (word(0), cells)
+}
+
+fn xor(sum: &mut Id, id: &Id) {
+    for (a, b) in sum.iter_mut().zip(id) {
+        *a ^= b;
+    }
+}
+
+impl Digest {
+    /// Creates an empty digest of `cells` cells, rounded up to a multiple of
+    /// [`HASH_COUNT`].
+    pub fn new(cells: usize) -> Self {
+        let cells = cells.max(1).div_ceil(HASH_COUNT) * HASH_COUNT;
+        Self { cells: vec![Cell::default(); cells] }
+    }
+
+    /// Creates an empty digest that decodes differences of up to about
+    /// `differences` IDs.
+    pub fn with_capacity(differences: usize) -> Self {
+        Self::new(differences * 2 + 4 * HASH_COUNT)
+    }
+
+    /// Returns the cells.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Wraps decoded cells; the count must be a non-zero multiple of
+    /// [`HASH_COUNT`].
+    pub(crate) fn from_cells(cells: Vec<Cell>) -> Self {
+        debug_assert!(!cells.is_empty() && cells.len() % HASH_COUNT == 0);
+        Self { cells }
+    }
+
+    /// Adds an ID. Adding the same ID twice breaks decoding.
+    pub fn insert(&mut self, id: &Id) {
+        self.toggle(id, 1);
+    }
+
+    /// Removes an ID added before.
+    pub fn remove(&mut self, id: &Id) {
+        self.toggle(id, -1);
+    }
+
+    fn toggle(&mut self, id: &Id, count: i64) {
+        let (check, cells) = cells_of(id, self.cells.len());
+        for i in cells {
+            let cell = &mut self.cells[i];
+            cell.count += count;
+            xor(&mut cell.id_sum, id);
+            cell.hash_sum ^= check;
+        }
+    }
+
+    /// Recovers the IDs that are in only one of the two digests.
+    ///
+    /// Fails if the digests differ in size, or if the difference is too
+    /// large for them; peers can then retry with larger digests.
+    pub fn missing_from(&self, other: &Digest) -> Result<Difference, SyncError> {
+        if self.cells.len() != other.cells.len() {
+            return Err(SyncError::SizeMismatch { ours: self.cells.len(), theirs: other.cells.len() });
+        }
+        let undecodable = || SyncError::Undecodable { cells: self.cells.len() };
+        // Counts come from the peer, so overflow means a corrupt digest
+        let mut cells: Vec<Cell> = self
+            .cells
+            .iter()
+            .zip(&other.cells)
+            .map(|(a, b)| {
+                let mut id_sum = a.id_sum;
+                xor(&mut id_sum, &b.id_sum);
+                let count = a.count.checked_sub(b.count)?;
+                Some(Cell { count, id_sum, hash_sum: a.hash_sum ^ b.hash_sum })
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(undecodable)?;
+
+        // Peel cells holding a single ID until none is left
+        let mut difference = Difference::default();
+        let mut pending: Vec<usize> = (0..cells.len()).collect();
+        while let Some(i) = pending.pop() {
+            let Cell { count, id_sum: id, hash_sum } = cells[i];
+            if count != 1 && count != -1 {
+                continue;
+            }
+            let (check, peel) = cells_of(&id, cells.len());
+            if check != hash_sum || !peel.contains(&i) {
+                continue;
+            }
+            if count == 1 {
+                difference.to_send.push(id);
+            } else {
+                difference.to_request.push(id);
+            }
+            if difference.to_send.len() + difference.to_request.len() > cells.len() {
+                return Err(undecodable());
+            }
+            for j in peel {
+                let cell = &mut cells[j];
+                cell.count = cell.count.checked_sub(count).ok_or_else(undecodable)?;
+                xor(&mut cell.id_sum, &id);
+                cell.hash_sum ^= check;
+                pending.push(j);
+            }
+        }
+        if cells.iter().any(|cell| *cell != Cell::default()) {
+            return Err(undecodable());
+        }
+        difference.to_send.sort_unstable();
+        difference.to_request.sort_unstable();
+        Ok(difference)
+    }
+}
+
+/// A collection of edits that can be summarized in a [`Digest`].
+pub trait EditLog {
+    /// Returns the IDs of the edits.
+    fn edit_ids(&self) -> impl Iterator<Item = Id> + '_;
+}
+
+impl EditLog for [Edit<'_>] {
+    fn edit_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.iter().map(|edit| edit.id)
+    }
+}
+
+impl EditLog for Vec<Edit<'_>> {
+    fn edit_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.as_slice().edit_ids()
+    }
+}
+
+impl EditLog for [Id] {
+    fn edit_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.iter().copied()
+    }
+}
+
+impl EditLog for Vec<Id> {
+    fn edit_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.iter().copied()
+    }
+}
+
+/// The edits applied since history was enabled (see
+/// [`Graph::applied_edits`]); a graph without history has none.
+impl EditLog for Graph {
+    fn edit_ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.applied_edits().iter().copied()
+    }
+}
+
+/// Returns a digest of a log's edit IDs, sized for [`DEFAULT_CAPACITY`]
+/// differences. IDs that appear more than once are added once.
+pub fn digest<L: EditLog + ?Sized>(log: &L) -> Digest {
+    digest_with_capacity(log, DEFAULT_CAPACITY)
+}
+
+/// Returns a digest of a log's edit IDs, sized for about `differences`
+/// differences.
+pub fn digest_with_capacity<L: EditLog + ?Sized>(log: &L, differences: usize) -> Digest {
+    let mut digest = Digest::with_capacity(differences);
+    let mut seen = FxHashSet::default();
+    for id in log.edit_ids() {
+        if seen.insert(id) {
+            digest.insert(&id);
+        }
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    fn ids(range: core::ops::Range<u32>) -> Vec<Id> {
+        range
+            .map(|i| {
+                let mut id = [0u8; 16];
+                id[..4].copy_from_slice(&i.to_be_bytes());
+                id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_missing_from() {
+        // 1000 shared edits, 20 only ours, 10 only theirs
+        let ours = ids(0..1020);
+        let theirs: Vec<Id> = ids(0..1000).into_iter().chain(ids(2000..2010)).collect();
+        let difference = digest(&ours).missing_from(&digest(&theirs)).unwrap();
+        assert_eq!(difference.to_send, ids(1000..1020));
+        assert_eq!(difference.to_request, ids(2000..2010));
+
+        let reverse = digest(&theirs).missing_from(&digest(&ours)).unwrap();
+        assert_eq!((reverse.to_send, reverse.to_request), (difference.to_request, difference.to_send));
+        assert!(digest(&ours).missing_from(&digest(&ours)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_digest_limits() {
+        let ours = ids(0..500);
+        let theirs = ids(250..750);
+        assert_eq!(digest(&ours).missing_from(&digest(&theirs)), Err(SyncError::Undecodable { cells: 78 }));
+        let difference = digest_with_capacity(&ours, 500).missing_from(&digest_with_capacity(&theirs, 500)).unwrap();
+        assert_eq!(difference.to_send.len() + difference.to_request.len(), 500);
+
+        let small = Digest::new(10);
+        assert_eq!(small.cells().len(), 12);
+        assert_eq!(small.missing_from(&digest(&ours)), Err(SyncError::SizeMismatch { ours: 12, theirs: 78 }));
+    }
+
+    #[test]
+    fn test_extreme_counts() {
+        // A peer's counts must not overflow when subtracted
+        let mut cells = vec![Cell::default(); 6];
+        cells[0].count = i64::MIN;
+        cells[3].count = i64::MAX;
+        let bytes = crate::sync::Message::Digest(Digest::from_cells(cells)).encode();
+        let crate::sync::Message::Digest(theirs) = crate::sync::Message::decode(&bytes).unwrap() else { panic!() };
+        let mut ours = Digest::new(6);
+        ours.insert(&[1u8; 16]);
+        assert_eq!(ours.missing_from(&theirs), Err(SyncError::Undecodable { cells: 6 }));
+        assert_eq!(theirs.missing_from(&ours), Err(SyncError::Undecodable { cells: 6 }));
+    }
+
+    #[test]
+    fn test_edit_logs() {
+        let edits: Vec<Edit<'static>> = (1..=3u8).map(|i| EditBuilder::new([i; 16]).build()).collect();
+        let mut graph = Graph::new().with_history();
+        for edit in &edits[..2] {
+            graph.apply_edit(edit);
+        }
+        // Applying an edit twice does not break the digest
+        graph.apply_edit(&edits[0]);
+        let difference = digest(&edits).missing_from(&digest(&graph)).unwrap();
+        assert_eq!(difference.to_send, vec![[3u8; 16]]);
+        assert!(difference.to_request.is_empty());
+
+        let mut removed = digest(&edits);
+        removed.remove(&[3u8; 16]);
+        assert!(removed.missing_from(&digest(&graph)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+//! Wire messages of the sync protocol.
+
+use sha2::{Digest as _, Sha256};
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::DecodeError;
+use crate::limits::MAGIC_SYNC;
+use crate::model::Id;
+use crate::prelude::*;
+use crate::sync::{Cell, Digest, HASH_COUNT};
+
+/// Current sync message format version.
+pub const SYNC_VERSION: u8 = 1;
+
+const KIND_DIGEST: u8 = 0;
+const KIND_RANGES: u8 = 1;
+
+const MODE_SKIP: u8 = 0;
+const MODE_FINGERPRINT: u8 = 1;
+const MODE_IDS: u8 = 2;
+
+/// Exclusive upper end of a [`Range`].
+///
+/// `Id` bounds order before `End`, and IDs order bytewise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bound {
+    Id(Id),
+    /// The end of the ID space.
+    End,
+}
+
+/// Returns the fingerprint of a sorted run of IDs: the first 16 bytes of
+/// the SHA-256 of the IDs concatenated in order.
+pub fn fingerprint(ids: &[Id]) -> [u8; 16] {
+    let mut hasher = Sha256::new();
+    for id in ids {
+        hasher.update(id);
+    }
+    hasher.finalize()[..16].try_into().unwrap()
+}
+
+/// What the sender says about its IDs in a [`Range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeMode {
+    /// Nothing more to reconcile in the range.
+    Skip,
+    /// The number of IDs the sender has in the range, and their
+    /// [`fingerprint`].
+    Fingerprint { count: u64, fingerprint: [u8; 16] },
+    /// Every ID the sender has in the range, in order.
+    Ids(Vec<Id>),
+}
+
+/// A range of the ID space, from the previous range's upper bound (or the
+/// all-zero ID for the first range) up to `upper`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub upper: Bound,
+    pub mode: RangeMode,
+}
+
+/// A sync protocol message.
+///
+/// Encoded as `GRC2SY` magic, version byte, and kind byte, followed by:
+///
+/// ```text
+/// Digest (kind 0): varint cell count + (signed varint count, id_sum, hash_sum: u64 LE)[]
+/// Ranges (kind 1): varint range count + (bound, mode:u8, payload)[]
+///   bound:   0x00 for the end of the ID space, or 0x01 + ID
+///   payload: none (mode 0, skip), varint count + 16 bytes (mode 1, fingerprint),
+///            or varint count + IDs (mode 2, ID list)
+/// ```
+///
+/// Range bounds must increase, and a non-empty range list must end with
+/// [`Bound::End`]. An empty range list ends the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// A digest of the sender's edit IDs, to settle small differences in
+    /// one round trip.
+    Digest(Digest),
+    /// A round of range-based reconciliation.
+    Ranges(Vec<Range>),
+}
+
+impl Message {
+    /// Returns true if the message ends the session.
+    pub fn is_done(&self) -> bool {
+        matches!(self, Message::Ranges(ranges) if ranges.is_empty())
+    }
+
+    /// Encodes the message.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.write_bytes(MAGIC_SYNC);
+        writer.write_byte(SYNC_VERSION);
+        match self {
+            Message::Digest(digest) => {
+                writer.write_byte(KIND_DIGEST);
+                writer.write_varint(digest.cells().len() as u64);
+                for cell in digest.cells() {
+                    writer.write_signed_varint(cell.count);
+                    writer.write_id(&cell.id_sum);
+                    writer.write_bytes(&cell.hash_sum.to_le_bytes());
+                }
+            }
+            Message::Ranges(ranges) => {
+                writer.write_byte(KIND_RANGES);
+                writer.write_varint(ranges.len() as u64);
+                for range in ranges {
+                    match &range.upper {
+                        Bound::Id(id) => {
+                            writer.write_byte(1);
+                            writer.write_id(id);
+                        }
+                        Bound::End => writer.write_byte(0),
+                    }
+                    match &range.mode {
+                        RangeMode::Skip => writer.write_byte(MODE_SKIP),
+                        RangeMode::Fingerprint { count, fingerprint } => {
+                            writer.write_byte(MODE_FINGERPRINT);
+                            writer.write_varint(*count);
+                            writer.write_bytes(fingerprint);
+                        }
+                        RangeMode::Ids(ids) => {
+                            writer.write_byte(MODE_IDS);
+                            writer.write_id_vec(ids);
+                        }
+                    }
+                }
+            }
+        }
+        writer.into_bytes()
+    }
+
+    /// Decodes a message written by [`Message::encode`].
+    pub fn decode(input: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(input);
+        let magic = reader.read_bytes(MAGIC_SYNC.len(), "magic")?;
+        if magic != MAGIC_SYNC {
+            let mut found = [0u8; 4];
+            found.copy_from_slice(&magic[..4]);
+            return Err(DecodeError::InvalidMagic { found });
+        }
+        let version = reader.read_byte("version")?;
+        if version != SYNC_VERSION {
+            return Err(DecodeError::UnsupportedVersion { version });
+        }
+        let message = match reader.read_byte("kind")? {
+            KIND_DIGEST => Message::Digest(read_digest(&mut reader)?),
+            KIND_RANGES => Message::Ranges(read_ranges(&mut reader)?),
+            _ => return Err(DecodeError::MalformedEncoding { context: "unknown sync message kind" }),
+        };
+        if !reader.is_empty() {
+            return Err(DecodeError::MalformedEncoding { context: "trailing bytes after sync message" });
+        }
+        Ok(message)
+    }
+}
+
+fn read_count(reader: &mut Reader<'_>, min_size: usize, field: &'static str) -> Result<usize, DecodeError> {
+    let count = reader.read_varint(field)?;
+    let max = reader.remaining_len() / min_size;
+    if count > max as u64 {
+        return Err(DecodeError::LengthExceedsLimit { field, len: count as usize, max });
+    }
+    Ok(count as usize)
+}
+
+fn read_digest(reader: &mut Reader<'_>) -> Result<Digest, DecodeError> {
+    // Each cell takes at least 25 bytes
+    let count = read_count(reader, 25, "cells")?;
+    if count == 0 || count % HASH_COUNT != 0 {
+        return Err(DecodeError::MalformedEncoding { context: "digest cell count" });
+    }
+    let mut cells = Vec::with_capacity(count);
+    for _ in 0..count {
+        let count = reader.read_signed_varint("cell_count")?;
+        let id_sum = reader.read_id("id_sum")?;
+        let hash_sum = u64::from_le_bytes(reader.read_bytes(8, "hash_sum")?.try_into().unwrap());
+        cells.push(Cell { count, id_sum, hash_sum });
+    }
+    Ok(Digest::from_cells(cells))
+}
+
+fn read_ranges(reader: &mut Reader<'_>) -> Result<Vec<Range>, DecodeError> {
+    // Each range takes at least a bound and a mode byte
+    let count = read_count(reader, 2, "ranges")?;
+    let mut ranges: Vec<Range> = Vec::with_capacity(count);
+    let mut lower = [0u8; 16];
+    for _ in 0..count {
+        if ranges.last().is_some_and(|r| r.upper == Bound::End) {
+            return Err(DecodeError::MalformedEncoding { context: "range after the end of the ID space" });
+        }
+        let upper = match reader.read_byte("bound")? {
+            0 => Bound::End,
+            1 => Bound::Id(reader.read_id("bound")?),
+            _ => return Err(DecodeError::MalformedEncoding { context: "range bound" }),
+        };
+        if upper <= Bound::Id(lower) {
+            return Err(DecodeError::MalformedEncoding { context: "range bounds not increasing" });
+        }
+        let mode = match reader.read_byte("mode")? {
+            MODE_SKIP => RangeMode::Skip,
+            MODE_FINGERPRINT => {
+                let count = reader.read_varint("fingerprint_count")?;
+                let fingerprint = reader.read_bytes(16, "fingerprint")?.try_into().unwrap();
+                RangeMode::Fingerprint { count, fingerprint }
+            }
+            MODE_IDS => {
+                let ids = reader.read_id_vec(reader.remaining_len() / 16, "ids")?;
+                let sorted = ids.windows(2).all(|w| w[0] < w[1]);
+                let inside = ids.first().is_none_or(|id| *id >= lower)
+                    && ids.last().is_none_or(|id| Bound::Id(*id) < upper);
+                if !sorted || !inside {
+                    return Err(DecodeError::MalformedEncoding { context: "range IDs not sorted within the range" });
+                }
+                RangeMode::Ids(ids)
+            }
+            _ => return Err(DecodeError::MalformedEncoding { context: "range mode" }),
+        };
+        if let Bound::Id(id) = upper {
+            lower = id;
+        }
+        ranges.push(Range { upper, mode });
+    }
+    if ranges.last().is_some_and(|r| r.upper != Bound::End) {
+        return Err(DecodeError::MalformedEncoding { context: "ranges do not reach the end of the ID space" });
+    }
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(message: &Message) {
+        assert_eq!(&Message::decode(&message.encode()).unwrap(), message);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut digest = Digest::new(6);
+        digest.insert(&[1u8; 16]);
+        roundtrip(&Message::Digest(digest));
+        roundtrip(&Message::Ranges(Vec::new()));
+        roundtrip(&Message::Ranges(vec![
+            Range { upper: Bound::Id([5u8; 16]), mode: RangeMode::Skip },
+            Range { upper: Bound::Id([9u8; 16]), mode: RangeMode::Fingerprint { count: 3, fingerprint: [7u8; 16] } },
+            Range { upper: Bound::End, mode: RangeMode::Ids(vec![[9u8; 16], [200u8; 16]]) },
+        ]));
+        assert!(Message::Ranges(Vec::new()).is_done());
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        let malformed = |ranges: Vec<Range>| Message::decode(&Message::Ranges(ranges).encode()).is_err();
+        let skip = |upper| Range { upper, mode: RangeMode::Skip };
+        // Not reaching the end, bounds going backwards, a range after the end
+        assert!(malformed(vec![skip(Bound::Id([5u8; 16]))]));
+        assert!(malformed(vec![skip(Bound::Id([5u8; 16])), skip(Bound::Id([4u8; 16])), skip(Bound::End)]));
+        assert!(malformed(vec![skip(Bound::End), skip(Bound::End)]));
+        // IDs outside their range
+        let ids = Range { upper: Bound::End, mode: RangeMode::Ids(vec![[1u8; 16]]) };
+        assert!(malformed(vec![skip(Bound::Id([5u8; 16])), ids]));
+
+        let mut bytes = Message::Ranges(Vec::new()).encode();
+        bytes[6] = 2;
+        assert_eq!(Message::decode(&bytes), Err(DecodeError::UnsupportedVersion { version: 2 }));
+        let mut bytes = Message::Digest(Digest::new(3)).encode();
+        bytes[8] = 2; // two cells
+        assert!(Message::decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+//! Edit set reconciliation between peers.
+//!
+//! Two nodes that hold the same edits of a space converge to the same state
+//! (compare them with [`hash_graph`](crate::state::hash_graph)). To find the
+//! edits they should exchange without sending their full ID lists, each
+//! peer sends a [`Digest`] of its edit IDs: a fixed-size invertible Bloom
+//! filter, from which [`Digest::missing_from`] recovers the IDs only one
+//! side has, as long as the difference is small compared to the digest:
+//!
+//! ```
+//! use grc_20::sync::digest;
+//!
+//! let ours = vec![[1u8; 16], [2u8; 16], [3u8; 16]];
+//! let theirs = vec![[1u8; 16], [2u8; 16], [4u8; 16]];
+//! let difference = digest(&ours).missing_from(&digest(&theirs)).unwrap();
+//! assert_eq!(difference.to_send, vec![[3u8; 16]]);
+//! assert_eq!(difference.to_request, vec![[4u8; 16]]);
+//! ```
+//!
+//! Unlike a plain Bloom filter, which only tells a peer which of its own
+//! IDs the other side probably lacks, the digest yields the exact
+//! difference in both directions, or fails if it is too large to decode.
+//! Both peers must use the same digest size.
+//!
+//! For differences of any size, a [`Reconciler`] runs a range-based
+//! session: peers exchange fingerprints of ranges of the sorted ID space,
+//! skipping ranges that match and splitting the rest until they are small
+//! enough to list. Sessions are carried by [`Message`]s, whose wire format
+//! is documented on the type so other implementations can take part:
+//!
+//! ```
+//! use grc_20::sync::{Message, Reconciler};
+//!
+//! let ours = Reconciler::new(&vec![[1u8; 16], [2u8; 16], [3u8; 16]]);
+//! let theirs = Reconciler::new(&vec![[1u8; 16], [2u8; 16], [4u8; 16]]);
+//! let first = Message::decode(&ours.initiate().encode()).unwrap();
+//! let response = theirs.respond(&first);
+//! let response = ours.respond(response.reply.as_ref().unwrap());
+//! assert_eq!(response.to_send, vec![[3u8; 16]]);
+//! assert_eq!(response.to_request, vec![[4u8; 16]]);
+//! ```
+
+mod digest;
+mod message;
+mod reconcile;
+
+pub use digest::{digest, digest_with_capacity, Cell, Difference, Digest, EditLog, DEFAULT_CAPACITY, HASH_COUNT};
+pub use message::{fingerprint, Bound, Message, Range, RangeMode, SYNC_VERSION};
+pub use reconcile::{Reconciler, Response, DEFAULT_BRANCHES, DEFAULT_ID_LIST_THRESHOLD};
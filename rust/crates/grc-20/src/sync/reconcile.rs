@@ -0,0 +1,237 @@
+//! A range-based reconciliation session over a set of edit IDs.
+
+use crate::model::Id;
+use crate::prelude::*;
+use crate::sync::{fingerprint, Bound, Digest, EditLog, Message, Range, RangeMode};
+
+/// Default largest number of IDs sent as a list instead of a fingerprint.
+pub const DEFAULT_ID_LIST_THRESHOLD: usize = 16;
+
+/// Default number of sub-ranges a mismatched range is split into.
+pub const DEFAULT_BRANCHES: usize = 16;
+
+/// One peer's side of a sync session.
+///
+/// Peers take turns: one sends [`Reconciler::initiate`], and each then
+/// answers the other's message with [`Reconciler::respond`] until a reply is
+/// `None`. A range whose fingerprints match is skipped; a mismatched range
+/// is answered with the responder's IDs if it holds few of them, and split
+/// into sub-ranges otherwise. Every side learns which edits to send and to
+/// request along the way; exchanging the edits is left to the caller.
+#[derive(Debug, Clone)]
+pub struct Reconciler {
+    ids: Vec<Id>,
+    id_list_threshold: usize,
+    branches: usize,
+}
+
+/// A peer's answer to a sync message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Response {
+    /// The message to send back, or `None` if the session is over.
+    pub reply: Option<Message>,
+    /// Edits the other peer is missing, sorted.
+    pub to_send: Vec<Id>,
+    /// Edits this peer is missing, sorted.
+    pub to_request: Vec<Id>,
+}
+
+impl Reconciler {
+    /// Creates a reconciler over a log's edit IDs.
+    pub fn new<L: EditLog + ?Sized>(log: &L) -> Self {
+        let mut ids: Vec<Id> = log.edit_ids().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        Self { ids, id_list_threshold: DEFAULT_ID_LIST_THRESHOLD, branches: DEFAULT_BRANCHES }
+    }
+
+    /// Sets the largest number of IDs sent as a list (at least 1).
+    pub fn id_list_threshold(mut self, threshold: usize) -> Self {
+        self.id_list_threshold = threshold.max(1);
+        self
+    }
+
+    /// Sets the number of sub-ranges a mismatched range is split into (at
+    /// least 2).
+    pub fn branches(mut self, branches: usize) -> Self {
+        self.branches = branches.max(2);
+        self
+    }
+
+    /// Returns the first message of a session: a fingerprint of every ID.
+    pub fn initiate(&self) -> Message {
+        Message::Ranges(vec![Range { upper: Bound::End, mode: self.fingerprint(&self.ids) }])
+    }
+
+    /// Returns a first message that settles differences of up to about
+    /// `differences` IDs in one round trip. The responder falls back to
+    /// ranges if the difference is larger.
+    pub fn initiate_with_digest(&self, differences: usize) -> Message {
+        Message::Digest(crate::sync::digest_with_capacity(&self.ids, differences))
+    }
+
+    /// Answers a message from the other peer.
+    pub fn respond(&self, message: &Message) -> Response {
+        let mut response = Response::default();
+        match message {
+            Message::Digest(theirs) => {
+                let mut ours = Digest::new(theirs.cells().len());
+                for id in &self.ids {
+                    ours.insert(id);
+                }
+                match ours.missing_from(theirs) {
+                    Ok(difference) => {
+                        response.reply = Some(Message::Ranges(Vec::new()));
+                        response.to_send = difference.to_send;
+                        response.to_request = difference.to_request;
+                    }
+                    Err(_) => response.reply = Some(self.initiate()),
+                }
+            }
+            Message::Ranges(ranges) if ranges.is_empty() => {}
+            Message::Ranges(ranges) => {
+                let mut reply = Vec::new();
+                let mut lower = [0u8; 16];
+                for range in ranges {
+                    let ours = self.slice(&lower, &range.upper);
+                    match &range.mode {
+                        RangeMode::Skip => push(&mut reply, range.upper, RangeMode::Skip),
+                        RangeMode::Fingerprint { count, fingerprint: theirs } => {
+                            if *count == ours.len() as u64 && fingerprint(ours) == *theirs {
+                                push(&mut reply, range.upper, RangeMode::Skip);
+                            } else if ours.len() <= self.id_list_threshold {
+                                push(&mut reply, range.upper, RangeMode::Ids(ours.to_vec()));
+                            } else {
+                                self.split(&mut reply, ours, range.upper);
+                            }
+                        }
+                        RangeMode::Ids(theirs) => {
+                            response.to_send.extend(ours.iter().filter(|id| theirs.binary_search(id).is_err()));
+                            response.to_request.extend(theirs.iter().filter(|id| ours.binary_search(id).is_err()));
+                            push(&mut reply, range.upper, RangeMode::Skip);
+                        }
+                    }
+                    if let Bound::Id(id) = range.upper {
+                        lower = id;
+                    }
+                }
+                if reply.iter().all(|range| range.mode == RangeMode::Skip) {
+                    reply.clear();
+                }
+                response.reply = Some(Message::Ranges(reply));
+            }
+        }
+        response
+    }
+
+    fn fingerprint(&self, ids: &[Id]) -> RangeMode {
+        RangeMode::Fingerprint { count: ids.len() as u64, fingerprint: fingerprint(ids) }
+    }
+
+    /// Returns our IDs in `[lower, upper)`.
+    fn slice(&self, lower: &Id, upper: &Bound) -> &[Id] {
+        let start = self.ids.partition_point(|id| id < lower);
+        let end = self.ids.partition_point(|id| Bound::Id(*id) < *upper);
+        &self.ids[start..end.max(start)]
+    }
+
+    /// Splits a range holding `ours` into fingerprinted sub-ranges of about
+    /// equal size.
+    fn split(&self, reply: &mut Vec<Range>, ours: &[Id], upper: Bound) {
+        let mut start = 0;
+        for i in 1..self.branches {
+            let end = i * ours.len() / self.branches;
+            if end > start {
+                reply.push(Range { upper: Bound::Id(ours[end]), mode: self.fingerprint(&ours[start..end]) });
+                start = end;
+            }
+        }
+        reply.push(Range { upper, mode: self.fingerprint(&ours[start..]) });
+    }
+}
+
+/// Appends a range, merging it into the previous one if both are skipped.
+fn push(reply: &mut Vec<Range>, upper: Bound, mode: RangeMode) {
+    match reply.last_mut() {
+        Some(last) if last.mode == RangeMode::Skip && mode == RangeMode::Skip => last.upper = upper,
+        _ => reply.push(Range { upper, mode }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(range: core::ops::Range<u32>) -> Vec<Id> {
+        range
+            .map(|i| {
+                let mut id = [0u8; 16];
+                id[..4].copy_from_slice(&i.wrapping_mul(2_654_435_761).to_be_bytes());
+                id
+            })
+            .collect()
+    }
+
+    /// Runs a session over the wire, returning what each side learned and
+    /// the number of messages sent.
+    fn sync(a: &Reconciler, b: &Reconciler, first: Message) -> (Vec<Id>, Vec<Id>, usize) {
+        let (mut a_send, mut b_send) = (Vec::new(), Vec::new());
+        let mut message = Some(first);
+        let mut messages = 0;
+        let mut peers = [b, a];
+        while let Some(sent) = message {
+            messages += 1;
+            let received = Message::decode(&sent.encode()).unwrap();
+            let response = peers[0].respond(&received);
+            let (send, request) = match messages % 2 {
+                1 => (&mut b_send, &mut a_send),
+                _ => (&mut a_send, &mut b_send),
+            };
+            send.extend(response.to_send);
+            request.extend(response.to_request);
+            message = response.reply;
+            peers.swap(0, 1);
+        }
+        a_send.sort_unstable();
+        a_send.dedup();
+        b_send.sort_unstable();
+        b_send.dedup();
+        (a_send, b_send, messages)
+    }
+
+    #[test]
+    fn test_ranges_converge() {
+        // 5000 shared edits, 40 only in a, 25 only in b
+        let a_ids: Vec<Id> = ids(0..5040);
+        let b_ids: Vec<Id> = ids(0..5000).into_iter().chain(ids(6000..6025)).collect();
+        let (a, b) = (Reconciler::new(&a_ids), Reconciler::new(&b_ids));
+        let sorted = |mut ids: Vec<Id>| {
+            ids.sort_unstable();
+            ids
+        };
+        let (a_send, b_send, _) = sync(&a, &b, a.initiate());
+        assert_eq!(a_send, sorted(ids(5000..5040)));
+        assert_eq!(b_send, sorted(ids(6000..6025)));
+
+        // Equal sets settle in one round trip
+        let (a_send, b_send, messages) = sync(&a, &a.clone(), a.initiate());
+        assert!(a_send.is_empty() && b_send.is_empty());
+        assert_eq!(messages, 2);
+    }
+
+    #[test]
+    fn test_digest_first() {
+        let a_ids = ids(0..300);
+        let b_ids = ids(3..310);
+        let (a, b) = (Reconciler::new(&a_ids), Reconciler::new(&b_ids).branches(4).id_list_threshold(4));
+        let (a_send, b_send, messages) = sync(&a, &b, a.initiate_with_digest(32));
+        assert_eq!((a_send.len(), b_send.len(), messages), (3, 10, 2));
+
+        // A difference too large for the digest falls back to ranges
+        let b_ids = ids(100..400);
+        let b = Reconciler::new(&b_ids);
+        let (a_send, b_send, messages) = sync(&a, &b, a.initiate_with_digest(4));
+        assert_eq!((a_send.len(), b_send.len()), (100, 100));
+        assert!(messages > 2);
+    }
+}
@@ -0,0 +1,255 @@
+//! Dropping ops that would not change a graph.
+
+use crate::collections::FxHashSet;
+use crate::model::{
+    relation_entity_id, CreateValueRef, Edit, Id, Op, PropertyValue, UnsetLanguage, UnsetRelationField, UnsetValue,
+    UpdateRelation,
+};
+use crate::prelude::*;
+use crate::state::{Entity, Graph, Relation, ValueKey, ValueRefSlot};
+
+/// Returns `edit` without the ops, values, and unsets that `graph`
+/// already reflects, so a re-import of an external dataset publishes only
+/// actual changes. Applying the result to `graph` gives the same state as
+/// applying `edit`.
+///
+/// Dropped are values equal to the current value of their slot, unsets of
+/// empty slots, relation fields already set (or unset) that way, and ops
+/// that are then empty or have no effect at all (spec Section 8.2):
+/// creating an object ID that exists, updating or deleting a missing
+/// entity, updating what is deleted, deleting what is already deleted,
+/// restoring what is live. Empty CreateEntity ops are kept when they would
+/// create the entity.
+///
+/// Once a kept op touches an object, later ops on it are kept as they are,
+/// since the graph no longer shows their starting state.
+pub fn dedup_against<'a>(edit: &Edit<'a>, graph: &Graph) -> Edit<'a> {
+    let mut touched: FxHashSet<Id> = FxHashSet::default();
+    let mut ops = Vec::with_capacity(edit.ops.len());
+    for op in &edit.ops {
+        let id = op.id();
+        let kept = if touched.contains(&id) { Some(op.clone()) } else { dedup_op(op, graph) };
+        let Some(kept) = kept else {
+            continue;
+        };
+        touched.insert(id);
+        if let Op::CreateRelation(cr) = op {
+            touched.insert(cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id)));
+        }
+        ops.push(kept);
+    }
+    Edit {
+        id: edit.id,
+        name: edit.name.clone(),
+        authors: edit.authors.clone(),
+        parents: edit.parents.clone(),
+        created_at: edit.created_at,
+        ops,
+    }
+}
+
+/// Returns the part of `op` that changes `graph`, if any.
+fn dedup_op<'a>(op: &Op<'a>, graph: &Graph) -> Option<Op<'a>> {
+    let id = op.id();
+    let entity = graph.entities.get(&id);
+    let relation = graph.relations.get(&id);
+    let exists = entity.is_some() || relation.is_some() || graph.value_refs.contains_key(&id);
+    let live_entity = entity.filter(|e| !e.deleted);
+    let live_relation = relation.filter(|r| !r.deleted);
+    match op {
+        Op::CreateEntity(ce) => match live_entity {
+            Some(current) => {
+                let values = changed_values(&ce.values, &[], current);
+                let mut ce = ce.clone();
+                ce.values = values;
+                (!ce.values.is_empty()).then_some(Op::CreateEntity(ce))
+            }
+            // A tombstone absorbs the create
+            None => (!exists).then(|| op.clone()),
+        },
+        Op::UpdateEntity(ue) => {
+            let current = live_entity?;
+            let mut ue = ue.clone();
+            ue.set_properties = changed_values(&ue.set_properties, &ue.unset_values, current);
+            ue.unset_values.retain(|uv| !is_unset(current, uv));
+            (!ue.set_properties.is_empty() || !ue.unset_values.is_empty()).then_some(Op::UpdateEntity(ue))
+        }
+        Op::DeleteEntity(_) => live_entity.is_some().then(|| op.clone()),
+        Op::RestoreEntity(_) => entity.is_some_and(|e| e.deleted).then(|| op.clone()),
+        Op::CreateRelation(_) => (!exists).then(|| op.clone()),
+        Op::UpdateRelation(ur) => changed_relation_fields(ur, live_relation?).map(Op::UpdateRelation),
+        Op::DeleteRelation(_) => live_relation.is_some().then(|| op.clone()),
+        Op::RestoreRelation(_) => relation.is_some_and(|r| r.deleted).then(|| op.clone()),
+        Op::CreateValueRef(cvr) => {
+            (entity.is_none() && relation.is_none() && !value_ref_exists(graph, cvr)).then(|| op.clone())
+        }
+        Op::Unknown(_) => Some(op.clone()),
+    }
+}
+
+/// Values that change `current`. Values whose slot appears twice, or whose
+/// property is also unset by the op, are kept, since their order matters.
+fn changed_values<'a>(values: &[PropertyValue<'a>], unsets: &[UnsetValue], current: &Entity) -> Vec<PropertyValue<'a>> {
+    let keys: Vec<ValueKey> = values.iter().map(|pv| ValueKey::of(pv.property, &pv.value)).collect();
+    values
+        .iter()
+        .zip(&keys)
+        .filter(|(pv, key)| {
+            keys.iter().filter(|k| k == key).count() > 1
+                || unsets.iter().any(|uv| uv.property == pv.property)
+                || current.value(key) != Some(&pv.value)
+        })
+        .map(|(pv, _)| pv.clone())
+        .collect()
+}
+
+/// Whether the slots `uv` clears are already empty.
+fn is_unset(current: &Entity, uv: &UnsetValue) -> bool {
+    match uv.language {
+        UnsetLanguage::All => current.values_of(uv.property).next().is_none(),
+        UnsetLanguage::English => current.value(&ValueKey::new(uv.property)).is_none(),
+        UnsetLanguage::Specific(language) => current.value(&ValueKey::with_language(uv.property, language)).is_none(),
+    }
+}
+
+/// The fields of `ur` that change `current`. Fields that are both unset and
+/// set are kept.
+fn changed_relation_fields<'a>(ur: &UpdateRelation<'a>, current: &Relation) -> Option<UpdateRelation<'a>> {
+    let mut ur = ur.clone();
+    let unset = ur.unset.clone();
+    let keep = |field: UnsetRelationField, same: bool| unset.contains(&field) || !same;
+    if !keep(UnsetRelationField::FromSpace, ur.from_space == current.from_space) {
+        ur.from_space = None;
+    }
+    if !keep(UnsetRelationField::FromVersion, ur.from_version == current.from_version) {
+        ur.from_version = None;
+    }
+    if !keep(UnsetRelationField::ToSpace, ur.to_space == current.to_space) {
+        ur.to_space = None;
+    }
+    if !keep(UnsetRelationField::ToVersion, ur.to_version == current.to_version) {
+        ur.to_version = None;
+    }
+    if !keep(UnsetRelationField::Position, ur.position.as_deref() == current.position.as_deref()) {
+        ur.position = None;
+    }
+    ur.unset.retain(|field| match field {
+        UnsetRelationField::FromSpace => current.from_space.is_some(),
+        UnsetRelationField::FromVersion => current.from_version.is_some(),
+        UnsetRelationField::ToSpace => current.to_space.is_some(),
+        UnsetRelationField::ToVersion => current.to_version.is_some(),
+        UnsetRelationField::Position => current.position.is_some(),
+    });
+    let changes = ur.from_space.is_some()
+        || ur.from_version.is_some()
+        || ur.to_space.is_some()
+        || ur.to_version.is_some()
+        || ur.position.is_some()
+        || !ur.unset.is_empty();
+    changes.then_some(ur)
+}
+
+/// Whether `cvr` is already the value ref of its slot.
+fn value_ref_exists(graph: &Graph, cvr: &CreateValueRef) -> bool {
+    let slot = ValueRefSlot { entity: cvr.entity, property: cvr.property, language: cvr.language, space: cvr.space };
+    graph.value_refs.get(&cvr.id) == Some(&slot) && graph.value_ref_slots.get(&slot) == Some(&cvr.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const NAME: Id = [10u8; 16];
+    const POPULATION: Id = [11u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    fn import(population: i64) -> Edit<'static> {
+        EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.text(NAME, "Berlin", None).int64(POPULATION, population, None))
+            .create_entity([3u8; 16], |e| e.text(NAME, "Hamburg", None))
+            .create_relation_simple([4u8; 16], [2u8; 16], [9u8; 16], [8u8; 16])
+            .update_relation([4u8; 16], |u| u.set_position("a0"))
+            .delete_entity([5u8; 16])
+            .build()
+    }
+
+    #[test]
+    fn test_dedup_against() {
+        let mut graph = Graph::new();
+        graph.apply_edit(&import(3_645_000));
+
+        // Nothing new
+        assert!(dedup_against(&import(3_645_000), &graph).ops.is_empty());
+
+        // Only the changed value
+        let edit = import(3_700_000);
+        let dedup = dedup_against(&edit, &graph);
+        assert_eq!(dedup.ops.len(), 1);
+        let Op::CreateEntity(ce) = &dedup.ops[0] else {
+            panic!("expected CreateEntity");
+        };
+        assert_eq!(ce.values.len(), 1);
+        let mut expected = graph.clone();
+        expected.apply_edit(&edit);
+        graph.apply_edit(&dedup);
+        assert_eq!(graph, expected);
+    }
+
+    #[test]
+    fn test_dedup_against_keeps_ordered_ops() {
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .create_entity([2u8; 16], |e| e.text(NAME, "Berlin", None).text(NAME, "Berlin", Some(GERMAN)))
+                .build(),
+        );
+        let edit = EditBuilder::new([1u8; 16])
+            // The unset clears the German name too, so the set must stay
+            .update_entity([2u8; 16], |u| u.unset_all(NAME).set_text(NAME, "Berlin", None))
+            // Touched earlier in the edit, kept as is
+            .update_entity([2u8; 16], |u| u.set_text(NAME, "Berlin", None))
+            .delete_entity([2u8; 16])
+            .restore_entity([2u8; 16])
+            // Missing, so ignored
+            .update_entity([3u8; 16], |u| u.unset_all(POPULATION))
+            .build();
+        let dedup = dedup_against(&edit, &graph);
+        assert_eq!(dedup.ops, edit.ops[..4]);
+
+        let mut expected = graph.clone();
+        expected.apply_edit(&edit);
+        graph.apply_edit(&dedup);
+        assert_eq!(graph, expected);
+
+        assert!(graph.entity(&[3u8; 16]).is_none());
+        assert!(dedup_against(&edit, &graph).ops.iter().all(|op| op.id() == [2u8; 16]));
+    }
+
+    #[test]
+    fn test_dedup_against_tombstones() {
+        let mut graph = Graph::new();
+        graph.apply_edit(&import(3_645_000));
+        graph.apply_edit(&EditBuilder::new([2u8; 16]).delete_entity([2u8; 16]).build());
+
+        // Absorbed by the tombstone, a namespace collision, and a missing ID
+        let edit = EditBuilder::new([3u8; 16])
+            .create_entity([2u8; 16], |e| e.int64(POPULATION, 1, None))
+            .update_entity([2u8; 16], |u| u.set_text(NAME, "Berlin", Some(GERMAN)))
+            .delete_entity([2u8; 16])
+            .create_entity([4u8; 16], |e| e.int64(POPULATION, 2, None))
+            .delete_entity([9u8; 16])
+            .build();
+        assert!(dedup_against(&edit, &graph).ops.is_empty());
+        let mut expected = graph.clone();
+        expected.apply_edit(&edit);
+        assert_eq!(expected, graph);
+
+        // After a restore, the update applies
+        let edit = EditBuilder::new([3u8; 16])
+            .restore_entity([2u8; 16])
+            .update_entity([2u8; 16], |u| u.set_text(NAME, "Berlin", Some(GERMAN)))
+            .build();
+        assert_eq!(dedup_against(&edit, &graph).ops, edit.ops);
+    }
+}
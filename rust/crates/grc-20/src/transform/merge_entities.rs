@@ -0,0 +1,155 @@
+//! Merging duplicate entities.
+
+use alloc::borrow::Cow;
+
+use crate::collections::FxHashSet;
+use crate::model::{derived_uuid, CreateRelation, DeleteEntity, DeleteRelation, Edit, Id, Op, PropertyValue, UpdateEntity};
+use crate::prelude::*;
+use crate::state::{Graph, Relation, ValueKey};
+
+/// Builds an edit that merges the `drop` entities into `keep`.
+///
+/// The edit, in order:
+/// 1. sets on `keep` every value slot (property and language) that `keep`
+///    lacks, taking the first of `drop` that has it, so `keep` wins
+///    conflicts;
+/// 2. deletes every live relation from or to a dropped entity and
+///    recreates it on `keep`, with the same type, position, pins, and
+///    reified entity (so its values survive). Relations that would become
+///    `keep -> keep`, or that `keep` already has with the same type and
+///    other endpoint, are only deleted;
+/// 3. deletes the dropped entities.
+///
+/// New relation IDs and the edit ID are derived from the inputs, so the
+/// same merge always produces the same edit:
+///
+/// ```text
+/// relation = derived_uuid("grc20:merge:" || old_relation || keep)
+/// edit     = derived_uuid("grc20:merge:" || keep || drop[0] || drop[1] ...)
+/// ```
+///
+/// Value refs pointing into dropped entities are not rewritten.
+pub fn merge_entities(graph: &Graph, keep: Id, drop: &[Id]) -> Edit<'static> {
+    let drop: Vec<Id> = drop.iter().copied().filter(|id| *id != keep).collect();
+    let mut ops = Vec::new();
+
+    // 1. Values
+    let mut slots: FxHashSet<ValueKey> =
+        graph.entity(&keep).map(|e| e.values.keys().copied().collect()).unwrap_or_default();
+    let mut set_properties = Vec::new();
+    for id in &drop {
+        for (key, value) in graph.entity(id).into_iter().flat_map(|e| &e.values) {
+            if slots.insert(*key) {
+                set_properties.push(PropertyValue { property: key.property, value: value.clone() });
+            }
+        }
+    }
+    if !set_properties.is_empty() {
+        ops.push(Op::UpdateEntity(UpdateEntity { id: keep, set_properties, unset_values: Vec::new(), context: None }));
+    }
+
+    // 2. Relations
+    let is_dropped = |id: &Id, is_value_ref: bool| !is_value_ref && drop.contains(id);
+    let mut relations: Vec<(&Id, &Relation)> = graph
+        .relations()
+        .filter(|(_, r)| is_dropped(&r.from, r.from_is_value_ref) || is_dropped(&r.to, r.to_is_value_ref))
+        .collect();
+    relations.sort_unstable_by_key(|(id, _)| **id);
+    // (type, from, to) of the relations `keep` has, to skip duplicates
+    let mut existing: FxHashSet<(Id, Id, Id)> = graph
+        .relations()
+        .filter(|(_, r)| r.from == keep || r.to == keep)
+        .map(|(_, r)| (r.relation_type, r.from, r.to))
+        .collect();
+    for (id, relation) in relations {
+        ops.push(Op::DeleteRelation(DeleteRelation { id: *id, context: None }));
+        let from = if is_dropped(&relation.from, relation.from_is_value_ref) { keep } else { relation.from };
+        let to = if is_dropped(&relation.to, relation.to_is_value_ref) { keep } else { relation.to };
+        if (from == keep && to == keep) || !existing.insert((relation.relation_type, from, to)) {
+            continue;
+        }
+        ops.push(Op::CreateRelation(CreateRelation {
+            id: derive(&[id, &keep]),
+            relation_type: relation.relation_type,
+            from,
+            from_is_value_ref: relation.from_is_value_ref,
+            from_space: relation.from_space,
+            from_version: relation.from_version,
+            to,
+            to_is_value_ref: relation.to_is_value_ref,
+            to_space: relation.to_space,
+            to_version: relation.to_version,
+            entity: Some(relation.entity),
+            position: relation.position.clone().map(Cow::Owned),
+            context: None,
+        }));
+    }
+
+    // 3. Duplicates
+    ops.extend(drop.iter().map(|id| Op::DeleteEntity(DeleteEntity { id: *id, context: None })));
+
+    let ids: Vec<&Id> = core::iter::once(&keep).chain(&drop).collect();
+    Edit {
+        id: derive(&ids),
+        name: Cow::Borrowed("Merge entities"),
+        authors: Vec::new(),
+        parents: Vec::new(),
+        created_at: 0,
+        ops,
+    }
+}
+
+/// `derived_uuid("grc20:merge:" || ids...)`
+fn derive(ids: &[&Id]) -> Id {
+    let mut input = Vec::with_capacity(12 + 16 * ids.len());
+    input.extend_from_slice(b"grc20:merge:");
+    for id in ids {
+        input.extend_from_slice(*id);
+    }
+    derived_uuid(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{relation_entity_id, EditBuilder};
+    use crate::state::ObjectState;
+
+    #[test]
+    fn test_merge_entities() {
+        let (name, population, link, types) = ([50u8; 16], [51u8; 16], [52u8; 16], [53u8; 16]);
+        let (keep, dup, other, city) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16]);
+        let mut graph = Graph::new();
+        graph.apply_edit(
+            &EditBuilder::new([9u8; 16])
+                .create_entity(keep, |e| e.text(name, "Berlin", None))
+                .create_entity(dup, |e| e.text(name, "berlin", None).int64(population, 3_645_000, None))
+                .create_relation_simple([10u8; 16], keep, city, types)
+                .create_relation_simple([11u8; 16], dup, city, types)
+                .create_relation(|r| r.id([12u8; 16]).from(other).to(dup).relation_type(link).position("a0"))
+                .create_relation_simple([13u8; 16], dup, keep, link)
+                .update_entity(relation_entity_id(&[12u8; 16]), |u| u.set_int64(population, 1, None))
+                .build(),
+        );
+        let reified = graph.relation(&[12u8; 16]).unwrap().entity;
+
+        let edit = merge_entities(&graph, keep, &[dup, keep]);
+        assert_eq!(edit, merge_entities(&graph, keep, &[dup]));
+        assert_eq!(
+            edit.ops.iter().map(|op| op.op_type()).collect::<Vec<_>>(),
+            // update keep, delete 11, delete + create 12, delete 13, delete dup
+            vec![2, 7, 7, 5, 7, 3]
+        );
+        graph.apply_edit(&edit);
+
+        assert_eq!(graph.value(&keep, &ValueKey::new(name)).unwrap().to_string(), "\"Berlin\"");
+        assert!(graph.value(&keep, &ValueKey::new(population)).is_some());
+        assert_eq!(graph.state(&dup), ObjectState::Deleted);
+        assert_eq!(graph.outgoing(&keep, Some(types)).len(), 1);
+        let incoming = graph.incoming(&keep, Some(link));
+        assert_eq!(incoming.len(), 1);
+        assert_eq!((incoming[0].1.from, incoming[0].1.position.as_deref()), (other, Some("a0")));
+        assert_eq!(incoming[0].1.entity, reified);
+        assert!(graph.value(&reified, &ValueKey::new(population)).is_some());
+    }
+}
@@ -0,0 +1,23 @@
+//! Edit-to-edit transformations.
+//!
+//! Tools for rewriting existing edits, e.g. for schema evolution. Each
+//! transform takes an edit and returns a new one, leaving the input intact,
+//! except [`redact_entity`], which rewrites a log in place, and
+//! [`merge_entities`], [`upsert_relation`], and [`dedup_against`], which
+//! work from materialized state.
+
+mod dedup;
+mod merge_entities;
+mod redact;
+mod remap;
+mod retype;
+mod scrub;
+mod upsert;
+
+pub use dedup::dedup_against;
+pub use merge_entities::merge_entities;
+pub use redact::{redact_entity, RedactedEdit};
+pub use remap::{remap_ids, IdMap};
+pub use retype::{convert_value, retype_property, Retyped, Unconvertible};
+pub use scrub::{scrub, ScrubPolicy};
+pub use upsert::{upsert_relation, Cardinality};
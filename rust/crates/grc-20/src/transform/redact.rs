@@ -0,0 +1,174 @@
+//! Redaction of entity values in an edit log.
+//!
+//! Erasure requests have to reach edits that were already published, so
+//! [`redact_entity`] rewrites the values of one entity in place. The
+//! redacted edits stay structurally valid and keep their op order, IDs and
+//! relations; only the entity's values change:
+//!
+//! - TEXT values become `sha256:<hex>` (language kept).
+//! - BYTES values become the 32-byte digest.
+//! - Values of other types are removed, since they cannot hold a hash.
+//!
+//! The digest of a value is
+//!
+//! ```text
+//! SHA-256("grc20:redact:" || entity || property || language || payload)
+//! ```
+//!
+//! where `language` is 16 zero bytes for none and `payload` is the UTF-8
+//! text or the raw bytes. Digests let holders of the original data confirm
+//! what was removed; short or guessable values can be recovered by brute
+//! force, so drop them instead where that matters.
+//!
+//! Redacting changes the content hash of each affected edit. The report
+//! lists the SHA-256 of the canonical uncompressed encoding (spec Section
+//! 4.4) before and after, so the log can publish the old-to-new mapping.
+
+use alloc::borrow::Cow;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::{encode_edit_with_options, EncodeOptions};
+use crate::error::EncodeError;
+use crate::model::{Edit, Id, Op, PropertyValue, Value};
+use crate::prelude::*;
+
+/// An edit rewritten by [`redact_entity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactedEdit {
+    /// Index of the edit in the log.
+    pub index: usize,
+    /// ID of the edit.
+    pub edit: Id,
+    /// SHA-256 of the canonical encoding before redaction.
+    pub before: [u8; 32],
+    /// SHA-256 of the canonical encoding after redaction.
+    pub after: [u8; 32],
+    /// Number of values replaced by a digest.
+    pub hashed: usize,
+    /// Number of values removed.
+    pub removed: usize,
+}
+
+/// Replaces every value of `entity` in `log` with its redacted form.
+///
+/// Returns one entry per edit that changed. On error (an edit that cannot
+/// be encoded canonically) the log is left untouched.
+pub fn redact_entity(log: &mut [Edit<'_>], entity: Id) -> Result<Vec<RedactedEdit>, EncodeError> {
+    let mut rewritten = Vec::new();
+    for (index, edit) in log.iter().enumerate() {
+        let mut redacted = edit.clone();
+        let (mut hashed, mut removed) = (0, 0);
+        for op in &mut redacted.ops {
+            let values = match op {
+                Op::CreateEntity(ce) if ce.id == entity => &mut ce.values,
+                Op::UpdateEntity(ue) if ue.id == entity => &mut ue.set_properties,
+                _ => continue,
+            };
+            values.retain_mut(|pv| match redact_value(entity, pv) {
+                Some(value) => {
+                    pv.value = value;
+                    hashed += 1;
+                    true
+                }
+                None => {
+                    removed += 1;
+                    false
+                }
+            });
+        }
+        if hashed + removed == 0 {
+            continue;
+        }
+        let report = RedactedEdit {
+            index,
+            edit: edit.id,
+            before: content_hash(edit)?,
+            after: content_hash(&redacted)?,
+            hashed,
+            removed,
+        };
+        rewritten.push((report, redacted.ops));
+    }
+
+    let mut reports = Vec::with_capacity(rewritten.len());
+    for (report, ops) in rewritten {
+        log[report.index].ops = ops;
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// Returns the redacted form of a value, or `None` if it must be removed.
+fn redact_value<'a>(entity: Id, pv: &PropertyValue<'a>) -> Option<Value<'a>> {
+    let digest = |language: Option<Id>, payload: &[u8]| -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"grc20:redact:");
+        hasher.update(entity);
+        hasher.update(pv.property);
+        hasher.update(language.unwrap_or([0u8; 16]));
+        hasher.update(payload);
+        hasher.finalize().into()
+    };
+    match &pv.value {
+        Value::Text { value, language } => {
+            let mut hex = String::with_capacity(7 + 64);
+            hex.push_str("sha256:");
+            for b in digest(*language, value.as_bytes()) {
+                hex.push_str(&format!("{:02x}", b));
+            }
+            Some(Value::Text { value: Cow::Owned(hex), language: *language })
+        }
+        Value::Bytes(bytes) => Some(Value::Bytes(Cow::Owned(digest(None, bytes).to_vec()))),
+        _ => None,
+    }
+}
+
+fn content_hash(edit: &Edit<'_>) -> Result<[u8; 32], EncodeError> {
+    let bytes = encode_edit_with_options(edit, EncodeOptions::canonical())?;
+    Ok(Sha256::digest(&bytes).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit, encode_edit};
+    use crate::model::EditBuilder;
+
+    const ALICE: Id = [3u8; 16];
+    const BOB: Id = [4u8; 16];
+    const NAME: Id = [10u8; 16];
+    const AGE: Id = [11u8; 16];
+
+    #[test]
+    fn test_redact_entity() {
+        let mut log = vec![
+            EditBuilder::new([1u8; 16])
+                .create_entity(ALICE, |e| e.text(NAME, "Alice", None).int64(AGE, 30, None))
+                .create_entity(BOB, |e| e.text(NAME, "Bob", None))
+                .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from(ALICE).to(BOB))
+                .build(),
+            EditBuilder::new([2u8; 16]).update_entity(BOB, |u| u.set_int64(AGE, 40, None)).build(),
+            EditBuilder::new([3u8; 16]).update_entity(ALICE, |u| u.set_text(NAME, "Alicia", None)).build(),
+        ];
+        let original = log.clone();
+        let reports = redact_entity(&mut log, ALICE).unwrap();
+
+        assert_eq!(reports.iter().map(|r| r.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!((reports[0].hashed, reports[0].removed), (1, 1));
+        assert_ne!(reports[0].before, reports[0].after);
+        assert_eq!(log[1], original[1]);
+
+        let Op::CreateEntity(ce) = &log[0].ops[0] else { panic!() };
+        assert_eq!(ce.values.len(), 1);
+        let Value::Text { value, .. } = &ce.values[0].value else { panic!() };
+        assert!(value.starts_with("sha256:") && value.len() == 71);
+        // Other entities and relations are untouched
+        assert_eq!(log[0].ops[1..], original[0].ops[1..]);
+
+        // Redacted edits still round-trip
+        let bytes = encode_edit(&log[0]).unwrap();
+        assert_eq!(decode_edit(&bytes).unwrap(), log[0]);
+        assert_eq!(content_hash(&log[0]).unwrap(), reports[0].after);
+    }
+}
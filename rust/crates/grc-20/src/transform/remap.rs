@@ -0,0 +1,255 @@
+//! ID remapping.
+
+use crate::collections::{FxHashMap, FxHashSet};
+use crate::model::{derived_uuid, relation_entity_id, Context, Edit, Id, Op, PropertyValue, UnsetLanguage, Value};
+use crate::prelude::*;
+
+/// Mapping from old to new IDs for [`remap_ids`].
+///
+/// IDs are looked up in order: explicit mappings, then kept IDs (left
+/// unchanged), then derivation if enabled. Without derivation, unmapped
+/// IDs are left unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdMap {
+    map: FxHashMap<Id, Id>,
+    keep: FxHashSet<Id>,
+    namespace: Option<Vec<u8>>,
+}
+
+impl IdMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `old` to `new`.
+    pub fn map(mut self, old: Id, new: Id) -> Self {
+        self.insert(old, new);
+        self
+    }
+
+    /// Maps `old` to `new`.
+    pub fn insert(&mut self, old: Id, new: Id) {
+        self.map.insert(old, new);
+    }
+
+    /// Leaves `id` unchanged even when deriving (e.g. Genesis properties).
+    pub fn keep(mut self, id: Id) -> Self {
+        self.keep.insert(id);
+        self
+    }
+
+    /// Derives new IDs for unmapped object IDs:
+    ///
+    /// ```text
+    /// new = derived_uuid("grc20:remap:" || namespace || ":" || old)
+    /// ```
+    pub fn derive_unmapped(mut self, namespace: impl AsRef<[u8]>) -> Self {
+        self.namespace = Some(namespace.as_ref().to_vec());
+        self
+    }
+
+    /// Returns the new ID for an object ID.
+    pub fn get(&self, id: &Id) -> Id {
+        if let Some(new) = self.map.get(id) {
+            return *new;
+        }
+        match &self.namespace {
+            Some(namespace) if !self.keep.contains(id) => {
+                let mut input = Vec::with_capacity(13 + namespace.len() + 16);
+                input.extend_from_slice(b"grc20:remap:");
+                input.extend_from_slice(namespace);
+                input.push(b':');
+                input.extend_from_slice(id);
+                derived_uuid(&input)
+            }
+            _ => *id,
+        }
+    }
+
+    /// Returns the new ID for a reference outside the object namespace
+    /// (space and version pins): only explicit mappings apply.
+    fn get_pin(&self, id: &Id) -> Id {
+        self.map.get(id).copied().unwrap_or(*id)
+    }
+}
+
+/// Rewrites the IDs in an edit according to `map`.
+///
+/// Every object ID is remapped: entities, relations and their types and
+/// endpoints, reified entities, value refs, properties, languages, units,
+/// and context paths. Space and version pins only follow explicit
+/// mappings, and the edit's own ID and authors are left unchanged.
+///
+/// A relation's implicit reified entity is derived from the relation ID,
+/// so it follows the relation: ops on the old reified entity are rewritten
+/// to target `relation_entity_id(new_relation_id)` unless that entity is
+/// mapped explicitly, in which case the relation gets an explicit entity.
+pub fn remap_ids<'a>(edit: &Edit<'a>, map: &IdMap) -> Edit<'a> {
+    // Implicit reified entities follow their relation
+    let mut reified = map.clone();
+    for op in &edit.ops {
+        if let Op::CreateRelation(cr) = op {
+            let old = relation_entity_id(&cr.id);
+            if cr.entity.is_none() && !map.map.contains_key(&old) {
+                reified.insert(old, relation_entity_id(&map.get(&cr.id)));
+            }
+        }
+    }
+    let map = &reified;
+
+    let mut edit = edit.clone();
+    for op in &mut edit.ops {
+        match op {
+            Op::CreateEntity(ce) => {
+                ce.id = map.get(&ce.id);
+                remap_values(&mut ce.values, map);
+                remap_context(&mut ce.context, map);
+            }
+            Op::UpdateEntity(ue) => {
+                ue.id = map.get(&ue.id);
+                remap_values(&mut ue.set_properties, map);
+                for uv in &mut ue.unset_values {
+                    uv.property = map.get(&uv.property);
+                    if let UnsetLanguage::Specific(language) = &mut uv.language {
+                        *language = map.get(language);
+                    }
+                }
+                remap_context(&mut ue.context, map);
+            }
+            Op::DeleteEntity(de) => {
+                de.id = map.get(&de.id);
+                remap_context(&mut de.context, map);
+            }
+            Op::RestoreEntity(re) => {
+                re.id = map.get(&re.id);
+                remap_context(&mut re.context, map);
+            }
+            Op::CreateRelation(cr) => {
+                let old_entity = cr.entity_id();
+                cr.id = map.get(&cr.id);
+                cr.relation_type = map.get(&cr.relation_type);
+                cr.from = map.get(&cr.from);
+                cr.to = map.get(&cr.to);
+                let new_entity = map.get(&old_entity);
+                cr.entity = (cr.entity.is_some() || new_entity != relation_entity_id(&cr.id)).then_some(new_entity);
+                for pin in [&mut cr.from_space, &mut cr.from_version, &mut cr.to_space, &mut cr.to_version]
+                    .into_iter()
+                    .flatten()
+                {
+                    *pin = map.get_pin(pin);
+                }
+                remap_context(&mut cr.context, map);
+            }
+            Op::UpdateRelation(ur) => {
+                ur.id = map.get(&ur.id);
+                for pin in [&mut ur.from_space, &mut ur.from_version, &mut ur.to_space, &mut ur.to_version]
+                    .into_iter()
+                    .flatten()
+                {
+                    *pin = map.get_pin(pin);
+                }
+                remap_context(&mut ur.context, map);
+            }
+            Op::DeleteRelation(dr) => {
+                dr.id = map.get(&dr.id);
+                remap_context(&mut dr.context, map);
+            }
+            Op::RestoreRelation(rr) => {
+                rr.id = map.get(&rr.id);
+                remap_context(&mut rr.context, map);
+            }
+            Op::CreateValueRef(cvr) => {
+                cvr.id = map.get(&cvr.id);
+                cvr.entity = map.get(&cvr.entity);
+                cvr.property = map.get(&cvr.property);
+                cvr.language = cvr.language.map(|l| map.get(&l));
+                cvr.space = cvr.space.map(|s| map.get_pin(&s));
+            }
+            // Opaque payloads are left as they are
+            Op::Unknown(_) => {}
+        }
+    }
+    edit
+}
+
+fn remap_values(values: &mut [PropertyValue<'_>], map: &IdMap) {
+    for pv in values {
+        pv.property = map.get(&pv.property);
+        match &mut pv.value {
+            Value::Text { language: Some(id), .. }
+            | Value::Int64 { unit: Some(id), .. }
+            | Value::Float64 { unit: Some(id), .. }
+            | Value::Decimal { unit: Some(id), .. } => *id = map.get(id),
+            _ => {}
+        }
+    }
+}
+
+fn remap_context(context: &mut Option<Context>, map: &IdMap) {
+    if let Some(context) = context {
+        context.root_id = map.get(&context.root_id);
+        for edge in &mut context.edges {
+            edge.type_id = map.get(&edge.type_id);
+            edge.to_entity_id = map.get(&edge.to_entity_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EditBuilder, UpdateEntity};
+    use crate::state::{Graph, ValueKey};
+
+    const NAME: Id = [10u8; 16];
+    const KNOWS: Id = [11u8; 16];
+
+    fn make_edit() -> Edit<'static> {
+        let mut edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text(NAME, "Alice", Some([20u8; 16])))
+            .create_entity([4u8; 16], |e| e.int64([12u8; 16], 5, Some([21u8; 16])))
+            .create_relation(|r| r.id([7u8; 16]).relation_type(KNOWS).from([3u8; 16]).to([4u8; 16]).to_space([8u8; 16]))
+            .build();
+        // Annotate the implicit reified entity
+        let mut update = UpdateEntity::new(relation_entity_id(&[7u8; 16]));
+        update.set_properties.push(PropertyValue {
+            property: NAME,
+            value: Value::Text { value: "since 2020".into(), language: None },
+        });
+        edit.ops.push(Op::UpdateEntity(update));
+        edit
+    }
+
+    #[test]
+    fn test_explicit_mapping() {
+        let map = IdMap::new().map([3u8; 16], [33u8; 16]).map([20u8; 16], [40u8; 16]).map([8u8; 16], [88u8; 16]);
+        let remapped = remap_ids(&make_edit(), &map);
+        let Op::CreateEntity(ce) = &remapped.ops[0] else { panic!() };
+        assert_eq!(ce.id, [33u8; 16]);
+        assert!(matches!(ce.values[0].value, Value::Text { language: Some([40u8, ..]), .. }));
+        let Op::CreateRelation(cr) = &remapped.ops[2] else { panic!() };
+        assert_eq!((cr.from, cr.to, cr.to_space), ([33u8; 16], [4u8; 16], Some([88u8; 16])));
+        assert_eq!(cr.entity, None);
+    }
+
+    #[test]
+    fn test_derived_mapping_keeps_structure() {
+        let map = IdMap::new().derive_unmapped("fork").keep(NAME).keep(KNOWS);
+        let remapped = remap_ids(&make_edit(), &map);
+        assert_eq!(remapped, remap_ids(&make_edit(), &map));
+
+        let new_alice = map.get(&[3u8; 16]);
+        assert_ne!(new_alice, [3u8; 16]);
+        assert_eq!(map.get(&NAME), NAME);
+
+        let mut graph = Graph::new();
+        graph.apply_edit(&remapped);
+        let new_relation = map.get(&[7u8; 16]);
+        let relation = graph.relation(&new_relation).unwrap();
+        assert_eq!((relation.from, relation.relation_type), (new_alice, KNOWS));
+        // The annotation follows the reified entity
+        assert!(graph.value(&relation.entity, &ValueKey::new(NAME)).is_some());
+        assert_eq!(graph.entity_count(), 3);
+    }
+}
@@ -0,0 +1,194 @@
+//! Property data type migration.
+
+use alloc::borrow::Cow;
+
+use crate::model::{DataType, Edit, Id, Op, PropertyValue, UnsetLanguage, Value};
+use crate::prelude::*;
+use crate::util::{parse_date_rfc3339, parse_datetime_rfc3339, parse_time_rfc3339};
+
+/// A value that [`retype_property`] dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unconvertible<'a> {
+    /// Index of the op in the edit.
+    pub op_index: usize,
+    pub entity: Id,
+    pub value: Value<'a>,
+    pub reason: &'static str,
+}
+
+/// Result of [`retype_property`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Retyped<'a> {
+    /// The rewritten edit, without the unconvertible values.
+    pub edit: Edit<'a>,
+    pub unconvertible: Vec<Unconvertible<'a>>,
+}
+
+/// Rewrites every `from`-typed value of `property` to type `to`.
+///
+/// `converter` returns the new value, or `None` if a value cannot be
+/// converted; [`convert_value`] covers the common cases. Unconvertible
+/// values are dropped from the edit and reported. Values of `property` with
+/// another data type are left as they are.
+///
+/// When converting away from TEXT, unsets of a single language become unsets
+/// of the whole property (spec Section 3.2), and only the first converted
+/// language value per op is kept.
+pub fn retype_property<'a, F>(
+    edit: &Edit<'a>,
+    property: Id,
+    from: DataType,
+    to: DataType,
+    mut converter: F,
+) -> Retyped<'a>
+where
+    F: FnMut(&Value<'a>) -> Option<Value<'a>>,
+{
+    let mut edit = edit.clone();
+    let mut unconvertible = Vec::new();
+    for (op_index, op) in edit.ops.iter_mut().enumerate() {
+        let (entity, values, unset) = match op {
+            Op::CreateEntity(ce) => (ce.id, &mut ce.values, None),
+            Op::UpdateEntity(ue) => (ue.id, &mut ue.set_properties, Some(&mut ue.unset_values)),
+            _ => continue,
+        };
+
+        let mut converted_any = false;
+        let mut kept = Vec::with_capacity(values.len());
+        for pv in values.drain(..) {
+            if pv.property != property || pv.value.data_type() != from {
+                kept.push(pv);
+                continue;
+            }
+            let reason = match converter(&pv.value) {
+                Some(value) if value.data_type() != to => "converter returned another data type",
+                Some(_) if converted_any && to != DataType::Text => "more than one value in the op",
+                Some(value) => {
+                    converted_any = true;
+                    kept.push(PropertyValue { property, value });
+                    continue;
+                }
+                None => "conversion failed",
+            };
+            unconvertible.push(Unconvertible { op_index, entity, value: pv.value, reason });
+        }
+        *values = kept;
+
+        if let (Some(unset), true) = (unset, from == DataType::Text && to != DataType::Text) {
+            let mut seen = false;
+            unset.retain_mut(|uv| {
+                if uv.property != property {
+                    return true;
+                }
+                uv.language = UnsetLanguage::All;
+                !core::mem::replace(&mut seen, true)
+            });
+        }
+    }
+    Retyped { edit, unconvertible }
+}
+
+/// Converts a value to another data type where the meaning is preserved.
+///
+/// Supported: TEXT to and from BOOL, INT64, FLOAT64, DATE, TIME, and
+/// DATETIME (parsed strictly; numbers and dates must be well-formed),
+/// INT64 to FLOAT64, FLOAT64 to INT64 (integral values only), and BOOL to
+/// INT64. Units are kept between numeric types. Returns `None` otherwise.
+pub fn convert_value<'a>(value: &Value<'a>, to: DataType) -> Option<Value<'a>> {
+    if value.data_type() == to {
+        return Some(value.clone());
+    }
+    match (value, to) {
+        (Value::Text { value, .. }, _) => from_text(value, to),
+        (Value::Bool(b), DataType::Text) => Some(text(b.to_string())),
+        (Value::Int64 { value, .. }, DataType::Text) => Some(text(value.to_string())),
+        (Value::Float64 { value, .. }, DataType::Text) => Some(text(value.to_string())),
+        (Value::Date(s) | Value::Time(s) | Value::Datetime(s), DataType::Text) => {
+            Some(Value::Text { value: s.clone(), language: None })
+        }
+        (Value::Int64 { value, unit }, DataType::Float64) => Some(Value::Float64 { value: *value as f64, unit: *unit }),
+        (Value::Float64 { value, unit }, DataType::Int64) => {
+            let int = *value as i64;
+            (int as f64 == *value).then_some(Value::Int64 { value: int, unit: *unit })
+        }
+        (Value::Bool(b), DataType::Int64) => Some(Value::Int64 { value: *b as i64, unit: None }),
+        _ => None,
+    }
+}
+
+fn text<'a>(s: String) -> Value<'a> {
+    Value::Text { value: Cow::Owned(s), language: None }
+}
+
+fn from_text<'a>(s: &Cow<'a, str>, to: DataType) -> Option<Value<'a>> {
+    let trimmed = s.trim();
+    match to {
+        DataType::Bool => match trimmed {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        DataType::Int64 => trimmed.parse().ok().map(|value| Value::Int64 { value, unit: None }),
+        DataType::Float64 => trimmed
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(|value| Value::Float64 { value, unit: None }),
+        DataType::Date => parse_date_rfc3339(trimmed).ok().map(|_| Value::Date(Cow::Owned(trimmed.to_string()))),
+        DataType::Time => parse_time_rfc3339(trimmed).ok().map(|_| Value::Time(Cow::Owned(trimmed.to_string()))),
+        DataType::Datetime => {
+            parse_datetime_rfc3339(trimmed).ok().map(|_| Value::Datetime(Cow::Owned(trimmed.to_string())))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EditBuilder, UnsetValue};
+
+    const CODE: Id = [10u8; 16];
+    const OTHER: Id = [11u8; 16];
+    const GERMAN: Id = [20u8; 16];
+
+    #[test]
+    fn test_retype_text_to_int64() {
+        let edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text(CODE, "123", None).text(OTHER, "456", None))
+            .create_entity([4u8; 16], |e| e.text(CODE, "n/a", None))
+            .update_entity([5u8; 16], |u| {
+                u.unset_english(CODE).unset_language(CODE, GERMAN).set_text(CODE, " 7 ", Some(GERMAN))
+            })
+            .build();
+        let retyped = retype_property(&edit, CODE, DataType::Text, DataType::Int64, |v| {
+            convert_value(v, DataType::Int64)
+        });
+
+        let Op::CreateEntity(ce) = &retyped.edit.ops[0] else { panic!() };
+        assert_eq!(ce.values[0].value, Value::Int64 { value: 123, unit: None });
+        // Other properties are untouched
+        assert!(matches!(ce.values[1].value, Value::Text { .. }));
+
+        assert_eq!(retyped.unconvertible.len(), 1);
+        assert_eq!(retyped.unconvertible[0].entity, [4u8; 16]);
+        let Op::CreateEntity(ce) = &retyped.edit.ops[1] else { panic!() };
+        assert!(ce.values.is_empty());
+
+        let Op::UpdateEntity(ue) = &retyped.edit.ops[2] else { panic!() };
+        assert_eq!(ue.set_properties[0].value, Value::Int64 { value: 7, unit: None });
+        assert_eq!(ue.unset_values, vec![UnsetValue { property: CODE, language: UnsetLanguage::All }]);
+    }
+
+    #[test]
+    fn test_convert_value() {
+        let f = Value::Float64 { value: 3.0, unit: Some([9u8; 16]) };
+        assert_eq!(convert_value(&f, DataType::Int64), Some(Value::Int64 { value: 3, unit: Some([9u8; 16]) }));
+        assert_eq!(convert_value(&Value::Float64 { value: 3.5, unit: None }, DataType::Int64), None);
+        assert_eq!(convert_value(&text("true".into()), DataType::Bool), Some(Value::Bool(true)));
+        assert_eq!(convert_value(&text("2024-01-15".into()), DataType::Date), Some(Value::Date("2024-01-15".into())));
+        assert_eq!(convert_value(&text("2024-13-45".into()), DataType::Date), None);
+        assert_eq!(convert_value(&Value::Int64 { value: -2, unit: None }, DataType::Text), Some(text("-2".into())));
+        assert_eq!(convert_value(&Value::Bool(true), DataType::Point), None);
+    }
+}
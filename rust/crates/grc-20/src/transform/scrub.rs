@@ -0,0 +1,169 @@
+//! Scrubbing user data from edits before sharing them.
+
+use alloc::borrow::Cow;
+
+use sha2::{Digest, Sha256};
+
+use crate::model::{Edit, Id, Op, PropertyValue, Value};
+use crate::prelude::*;
+
+/// What [`scrub`] does to TEXT values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrubPolicy {
+    /// Properties whose TEXT values are replaced by pseudonyms (names,
+    /// emails, handles).
+    pub pseudonymize: Vec<Id>,
+
+    /// Properties whose TEXT values are kept as they are.
+    pub keep: Vec<Id>,
+
+    /// Replace every other TEXT value, and the edit name, with a run of `x`
+    /// of the same length. Defaults to true.
+    pub strip_free_text: bool,
+
+    /// Mixed into pseudonyms, so they cannot be reversed by hashing
+    /// guesses without it.
+    pub salt: Vec<u8>,
+}
+
+impl Default for ScrubPolicy {
+    fn default() -> Self {
+        Self { pseudonymize: Vec::new(), keep: Vec::new(), strip_free_text: true, salt: Vec::new() }
+    }
+}
+
+impl ScrubPolicy {
+    /// Creates a policy that strips all free text and pseudonymizes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the values of a property with pseudonyms.
+    pub fn pseudonymize(mut self, property: Id) -> Self {
+        if !self.pseudonymize.contains(&property) {
+            self.pseudonymize.push(property);
+        }
+        self
+    }
+
+    /// Keeps the values of a property.
+    pub fn keep(mut self, property: Id) -> Self {
+        if !self.keep.contains(&property) {
+            self.keep.push(property);
+        }
+        self
+    }
+
+    /// Sets whether other TEXT values are stripped.
+    pub fn strip_free_text(mut self, strip: bool) -> Self {
+        self.strip_free_text = strip;
+        self
+    }
+
+    /// Sets the pseudonym salt.
+    pub fn salt(mut self, salt: impl AsRef<[u8]>) -> Self {
+        self.salt = salt.as_ref().to_vec();
+        self
+    }
+}
+
+/// Returns a copy of `edit` with TEXT values scrubbed per `policy`.
+///
+/// Pseudonyms are deterministic: the same text in the same property maps
+/// to the same `anon-<12 hex digits>` everywhere, so a scrubbed edit still
+/// reproduces lookups and duplicates. Languages, IDs, and non-TEXT values
+/// are left as they are.
+pub fn scrub<'a>(edit: &Edit<'a>, policy: &ScrubPolicy) -> Edit<'a> {
+    let mut edit = edit.clone();
+    if policy.strip_free_text {
+        edit.name = Cow::Owned(strip(&edit.name));
+    }
+    for op in &mut edit.ops {
+        let values = match op {
+            Op::CreateEntity(ce) => &mut ce.values,
+            Op::UpdateEntity(ue) => &mut ue.set_properties,
+            _ => continue,
+        };
+        for pv in values {
+            scrub_value(pv, policy);
+        }
+    }
+    edit
+}
+
+fn scrub_value(pv: &mut PropertyValue<'_>, policy: &ScrubPolicy) {
+    let Value::Text { value, .. } = &mut pv.value else {
+        return;
+    };
+    if policy.pseudonymize.contains(&pv.property) {
+        *value = Cow::Owned(pseudonym(&policy.salt, &pv.property, value));
+    } else if policy.strip_free_text && !policy.keep.contains(&pv.property) {
+        *value = Cow::Owned(strip(value));
+    }
+}
+
+fn pseudonym(salt: &[u8], property: &Id, text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(property);
+    hasher.update(text.as_bytes());
+    let digest = hasher.finalize();
+    let mut out = String::with_capacity(5 + 12);
+    out.push_str("anon-");
+    for b in &digest[..6] {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+fn strip(text: &str) -> String {
+    "x".repeat(text.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const NAME: Id = [10u8; 16];
+    const BIO: Id = [11u8; 16];
+    const CODE: Id = [12u8; 16];
+
+    #[test]
+    fn test_scrub() {
+        let mut edit = EditBuilder::new([1u8; 16])
+            .create_entity([3u8; 16], |e| e.text(NAME, "Alice", None).text(BIO, "Lives in Zürich", None).int64([13u8; 16], 7, None))
+            .create_entity([4u8; 16], |e| e.text(NAME, "Alice", None).text(CODE, "CH", None))
+            .update_entity([5u8; 16], |u| u.set_text(NAME, "Bob", None))
+            .build();
+        edit.name = "import from alice@example.com".into();
+        let policy = ScrubPolicy::new().pseudonymize(NAME).keep(CODE).salt("s3cret");
+        let scrubbed = scrub(&edit, &policy);
+
+        let texts: Vec<_> = scrubbed
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::CreateEntity(ce) => Some(&ce.values),
+                Op::UpdateEntity(ue) => Some(&ue.set_properties),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|pv| match &pv.value {
+                Value::Text { value, .. } => Some(value.as_ref()),
+                _ => None,
+            })
+            .collect();
+        assert!(texts[0].starts_with("anon-") && texts[0].len() == 17);
+        assert_eq!(texts[1], "xxxxxxxxxxxxxxx");
+        // Same name, same pseudonym
+        assert_eq!(texts[0], texts[2]);
+        assert_eq!(texts[3], "CH");
+        assert_ne!(texts[4], texts[0]);
+        assert_eq!(scrubbed.name, "x".repeat(29));
+
+        let Op::CreateEntity(ce) = &scrubbed.ops[0] else { panic!() };
+        assert_eq!(ce.values[2].value, Value::Int64 { value: 7, unit: None });
+        assert_ne!(scrub(&edit, &policy.clone().salt("other")), scrubbed);
+    }
+}
@@ -0,0 +1,130 @@
+//! Idempotent relation writes.
+
+use crate::model::id::unique_relation_id;
+use crate::model::{CreateRelation, DeleteRelation, Id, Op, RestoreRelation};
+use crate::prelude::*;
+use crate::state::{Graph, ObjectState, Relation};
+
+/// How many relations of a type an entity may have, for
+/// [`upsert_relation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    /// Any number, but at most one to each target (e.g. Types, tags).
+    Many,
+    /// At most one; a new target replaces the old (e.g. a parent).
+    One,
+}
+
+/// Returns the ops that make `from -[relation_type]-> to` hold in `graph`,
+/// so re-running an import doesn't duplicate relations:
+///
+/// - nothing if an equivalent live relation (same type and endpoints, any
+///   ID) exists;
+/// - otherwise a CreateRelation with ID [`unique_relation_id`], or a
+///   RestoreRelation if that relation was deleted;
+/// - with [`Cardinality::One`], also a DeleteRelation for every other live
+///   relation of the type from `from`, before the create.
+///
+/// Relations from or to value refs are not considered equivalent.
+pub fn upsert_relation(
+    graph: &Graph,
+    from: Id,
+    to: Id,
+    relation_type: Id,
+    cardinality: Cardinality,
+) -> Vec<Op<'static>> {
+    let mut ops = Vec::new();
+    let existing = graph.outgoing(&from, Some(relation_type));
+    let is_equivalent = |r: &Relation| r.to == to && !r.from_is_value_ref && !r.to_is_value_ref;
+    let found = existing.iter().any(|(_, r)| is_equivalent(r));
+
+    if cardinality == Cardinality::One {
+        let mut kept = false;
+        for (id, relation) in &existing {
+            if is_equivalent(relation) && !kept {
+                kept = true;
+            } else {
+                ops.push(Op::DeleteRelation(DeleteRelation { id: **id, context: None }));
+            }
+        }
+    }
+    if found {
+        return ops;
+    }
+
+    let id = unique_relation_id(&from, &to, &relation_type);
+    if matches!(graph.state(&id), ObjectState::Deleted) {
+        ops.push(Op::RestoreRelation(RestoreRelation { id, context: None }));
+    } else {
+        ops.push(Op::CreateRelation(CreateRelation {
+            id,
+            relation_type,
+            from,
+            from_is_value_ref: false,
+            from_space: None,
+            from_version: None,
+            to,
+            to_is_value_ref: false,
+            to_space: None,
+            to_version: None,
+            entity: None,
+            position: None,
+            context: None,
+        }));
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    fn apply(graph: &mut Graph, ops: Vec<Op<'static>>) {
+        graph.apply_edit(&EditBuilder::new([0u8; 16]).ops(ops).build());
+    }
+
+    #[test]
+    fn test_upsert_relation_many() {
+        let (city, place, topic, types) = ([1u8; 16], [2u8; 16], [3u8; 16], [9u8; 16]);
+        let mut graph = Graph::new();
+        // An existing relation with an arbitrary ID counts
+        graph.apply_edit(&EditBuilder::new([0u8; 16]).create_relation_simple([5u8; 16], city, place, types).build());
+
+        assert!(upsert_relation(&graph, city, place, types, Cardinality::Many).is_empty());
+        let ops = upsert_relation(&graph, city, topic, types, Cardinality::Many);
+        assert_eq!(ops.iter().map(Op::op_type).collect::<Vec<_>>(), vec![5]);
+        apply(&mut graph, ops);
+        assert_eq!(graph.outgoing(&city, Some(types)).len(), 2);
+        assert!(upsert_relation(&graph, city, topic, types, Cardinality::Many).is_empty());
+
+        // A deleted relation is restored rather than recreated
+        let id = unique_relation_id(&city, &topic, &types);
+        apply(&mut graph, vec![Op::DeleteRelation(DeleteRelation { id, context: None })]);
+        let ops = upsert_relation(&graph, city, topic, types, Cardinality::Many);
+        assert_eq!(ops, vec![Op::RestoreRelation(RestoreRelation { id, context: None })]);
+        apply(&mut graph, ops);
+        assert!(graph.relation(&id).is_some());
+    }
+
+    #[test]
+    fn test_upsert_relation_one() {
+        let (block, page_a, page_b, parent) = ([1u8; 16], [2u8; 16], [3u8; 16], [9u8; 16]);
+        let mut graph = Graph::new();
+        let ops = upsert_relation(&graph, block, page_a, parent, Cardinality::One);
+        apply(&mut graph, ops);
+        assert!(upsert_relation(&graph, block, page_a, parent, Cardinality::One).is_empty());
+
+        let ops = upsert_relation(&graph, block, page_b, parent, Cardinality::One);
+        assert_eq!(ops.iter().map(Op::op_type).collect::<Vec<_>>(), vec![7, 5]);
+        apply(&mut graph, ops);
+        let outgoing = graph.outgoing(&block, Some(parent));
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].1.to, page_b);
+
+        // Duplicates of the target collapse to one
+        graph.apply_edit(&EditBuilder::new([0u8; 16]).create_relation_simple([6u8; 16], block, page_b, parent).build());
+        let ops = upsert_relation(&graph, block, page_b, parent, Cardinality::One);
+        assert_eq!(ops.iter().map(Op::op_type).collect::<Vec<_>>(), vec![7]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Measurement units for numerical values.
+//!
+//! Unit entity IDs are derived from a unit code the same way Genesis
+//! language IDs are derived from language tags:
+//!
+//! ```text
+//! id = derived_uuid("grc20:genesis:unit:" + code)
+//! ```
+//!
+//! Codes are case-sensitive ASCII (`km2` for km², `degC` for °C, ISO 4217
+//! codes for currencies). [`UNITS`] lists the well-known units with their
+//! conversion factors; `genesis::units` has constants for the common ones.
+
+use crate::model::{derived_uuid, Id};
+use crate::prelude::*;
+
+/// What a unit measures. Only units of the same dimension convert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Time,
+    Temperature,
+    Speed,
+    Ratio,
+    /// Currencies have no fixed rate, so they only convert to themselves.
+    Currency,
+}
+
+/// A well-known unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    pub code: &'static str,
+    pub dimension: Dimension,
+    /// `base = value * factor + offset`, in the dimension's base unit (m,
+    /// m², m³, kg, s, K, m/s, 1).
+    pub factor: f64,
+    pub offset: f64,
+}
+
+const fn unit(code: &'static str, dimension: Dimension, factor: f64) -> Unit {
+    Unit { code, dimension, factor, offset: 0.0 }
+}
+
+/// The well-known units.
+pub const UNITS: &[Unit] = &[
+    unit("m", Dimension::Length, 1.0),
+    unit("km", Dimension::Length, 1000.0),
+    unit("cm", Dimension::Length, 0.01),
+    unit("mm", Dimension::Length, 0.001),
+    unit("mi", Dimension::Length, 1609.344),
+    unit("ft", Dimension::Length, 0.3048),
+    unit("in", Dimension::Length, 0.0254),
+    unit("m2", Dimension::Area, 1.0),
+    unit("km2", Dimension::Area, 1e6),
+    unit("ha", Dimension::Area, 1e4),
+    unit("acre", Dimension::Area, 4046.8564224),
+    unit("mi2", Dimension::Area, 2_589_988.110336),
+    unit("m3", Dimension::Volume, 1.0),
+    unit("l", Dimension::Volume, 0.001),
+    unit("ml", Dimension::Volume, 1e-6),
+    unit("kg", Dimension::Mass, 1.0),
+    unit("g", Dimension::Mass, 0.001),
+    unit("t", Dimension::Mass, 1000.0),
+    unit("lb", Dimension::Mass, 0.45359237),
+    unit("oz", Dimension::Mass, 0.028349523125),
+    unit("s", Dimension::Time, 1.0),
+    unit("ms", Dimension::Time, 0.001),
+    unit("min", Dimension::Time, 60.0),
+    unit("h", Dimension::Time, 3600.0),
+    unit("d", Dimension::Time, 86400.0),
+    unit("K", Dimension::Temperature, 1.0),
+    Unit { code: "degC", dimension: Dimension::Temperature, factor: 1.0, offset: 273.15 },
+    Unit { code: "degF", dimension: Dimension::Temperature, factor: 5.0 / 9.0, offset: 273.15 - 32.0 * 5.0 / 9.0 },
+    unit("m/s", Dimension::Speed, 1.0),
+    unit("km/h", Dimension::Speed, 1.0 / 3.6),
+    unit("mph", Dimension::Speed, 0.44704),
+    unit("percent", Dimension::Ratio, 0.01),
+    unit("USD", Dimension::Currency, 1.0),
+    unit("EUR", Dimension::Currency, 1.0),
+    unit("GBP", Dimension::Currency, 1.0),
+    unit("JPY", Dimension::Currency, 1.0),
+    unit("CNY", Dimension::Currency, 1.0),
+];
+
+/// Derives the entity ID for a unit code.
+pub fn unit_id(code: &str) -> Id {
+    let mut input = Vec::with_capacity(19 + code.len());
+    input.extend_from_slice(b"grc20:genesis:unit:");
+    input.extend_from_slice(code.as_bytes());
+    derived_uuid(&input)
+}
+
+/// Returns the well-known unit with this ID.
+#[cfg(feature = "std")]
+pub fn lookup(id: &Id) -> Option<&'static Unit> {
+    use crate::collections::FxHashMap;
+
+    lazy_static::lazy_static! {
+        static ref BY_ID: FxHashMap<Id, &'static Unit> = UNITS.iter().map(|u| (unit_id(u.code), u)).collect();
+    }
+    BY_ID.get(id).copied()
+}
+
+/// Returns the well-known unit with this ID.
+///
+/// Without `std` this derives every ID on each call.
+#[cfg(not(feature = "std"))]
+pub fn lookup(id: &Id) -> Option<&'static Unit> {
+    UNITS.iter().find(|u| unit_id(u.code) == *id)
+}
+
+/// Converts `value` between two well-known units of the same dimension.
+///
+/// Returns `None` for unknown units, mismatched dimensions, and different
+/// currencies. Converting a unit to itself always succeeds.
+pub fn convert(value: f64, from: &Id, to: &Id) -> Option<f64> {
+    if from == to {
+        return Some(value);
+    }
+    let (from, to) = (lookup(from)?, lookup(to)?);
+    if from.dimension != to.dimension || from.dimension == Dimension::Currency {
+        return None;
+    }
+    Some((value * from.factor + from.offset - to.offset) / to.factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert_codes(value: f64, from: &str, to: &str) -> Option<f64> {
+        convert(value, &unit_id(from), &unit_id(to))
+    }
+
+    fn close(a: Option<f64>, b: f64) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-9 * b.abs().max(1.0))
+    }
+
+    #[test]
+    fn test_convert() {
+        assert!(close(convert_codes(1.5, "km", "m"), 1500.0));
+        assert!(close(convert_codes(1.0, "mi", "km"), 1.609344));
+        assert!(close(convert_codes(1.0, "km2", "ha"), 100.0));
+        assert!(close(convert_codes(100.0, "degC", "degF"), 212.0));
+        assert!(close(convert_codes(-40.0, "degF", "degC"), -40.0));
+        assert!(close(convert_codes(0.0, "degC", "K"), 273.15));
+        assert!(close(convert_codes(36.0, "km/h", "m/s"), 10.0));
+        assert_eq!(convert_codes(1.0, "kg", "m"), None);
+        assert_eq!(convert_codes(1.0, "USD", "EUR"), None);
+        assert_eq!(convert_codes(7.0, "USD", "USD"), Some(7.0));
+        assert_eq!(convert(1.0, &[1u8; 16], &unit_id("m")), None);
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup(&unit_id("kg")).map(|u| u.dimension), Some(Dimension::Mass));
+        assert!(lookup(&unit_id("KG")).is_none());
+        let mut codes: Vec<_> = UNITS.iter().map(|u| u.code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), UNITS.len());
+    }
+}
@@ -5,6 +5,8 @@
 //! - Time: microseconds since midnight (`time_micros`) + offset in minutes
 //! - Datetime: microseconds since Unix epoch (`epoch_micros`) + offset in minutes
 
+use crate::prelude::*;
+
 
 const MICROSECONDS_PER_SECOND: i64 = 1_000_000;
 const MICROSECONDS_PER_MINUTE: i64 = 60 * MICROSECONDS_PER_SECOND;
@@ -17,13 +19,13 @@ pub struct DateTimeParseError {
     pub message: String,
 }
 
-impl std::fmt::Display for DateTimeParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for DateTimeParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.message)
     }
 }
 
-impl std::error::Error for DateTimeParseError {}
+impl core::error::Error for DateTimeParseError {}
 
 /// Parses a timezone offset string (Z, +HH:MM, -HH:MM) and returns offset in minutes.
 fn parse_timezone_offset(offset: &str) -> Result<i16, DateTimeParseError> {
@@ -8,10 +8,18 @@
 //! The protocol does not enforce that a property always uses the same type
 //! across edits. Applications can use SchemaContext to opt-in to type checking.
 
-use std::collections::HashMap;
 
+mod report;
+
+pub use report::{Finding, Issue, Severity, ValidationReport, ValidationWarning};
+
+use crate::collections::{FxHashMap, FxHashSet};
 use crate::error::ValidationError;
-use crate::model::{DataType, Edit, Id, Op, PropertyValue, Value};
+use crate::limits::MAX_POSITION_LEN;
+use crate::model::id::{data_type_entity_id, data_type_relation_type, types_relation_type};
+use crate::model::{CreateRelation, DataType, Edit, Id, Op, PropertyValue, Value};
+use crate::prelude::*;
+use crate::state::{Graph, ObjectState};
 
 /// Schema context for semantic validation.
 ///
@@ -21,7 +29,17 @@ use crate::model::{DataType, Edit, Id, Op, PropertyValue, Value};
 #[derive(Debug, Clone, Default)]
 pub struct SchemaContext {
     /// Known property data types (advisory).
-    properties: HashMap<Id, DataType>,
+    properties: FxHashMap<Id, DataType>,
+    /// Constraints on TEXT values, per property.
+    text_constraints: FxHashMap<Id, TextConstraint>,
+}
+
+/// Limits on the TEXT values of one property.
+#[derive(Debug, Clone, Default)]
+struct TextConstraint {
+    max_bytes: Option<usize>,
+    #[cfg(feature = "regex")]
+    pattern: Option<regex::Regex>,
 }
 
 impl SchemaContext {
@@ -39,48 +57,324 @@ impl SchemaContext {
     pub fn get_property_type(&self, id: &Id) -> Option<DataType> {
         self.properties.get(id).copied()
     }
+
+    /// Returns true if the property is registered or constrained.
+    pub fn knows(&self, id: &Id) -> bool {
+        self.properties.contains_key(id) || self.text_constraints.contains_key(id)
+    }
+
+    /// Limits TEXT values of a property to `max_bytes` of UTF-8.
+    pub fn set_max_text_len(&mut self, property: Id, max_bytes: usize) {
+        self.text_constraints.entry(property).or_default().max_bytes = Some(max_bytes);
+    }
+
+    /// Requires TEXT values of a property to match `pattern` in full.
+    ///
+    /// The pattern is anchored at both ends, so `[a-z]+` rejects `"ab1"`.
+    #[cfg(feature = "regex")]
+    pub fn set_text_pattern(&mut self, property: Id, pattern: &str) -> Result<(), regex::Error> {
+        let pattern = regex::Regex::new(&format!("^(?:{})$", pattern))?;
+        self.text_constraints.entry(property).or_default().pattern = Some(pattern);
+        Ok(())
+    }
+}
+
+impl<'a> PropertyValue<'a> {
+    /// Creates a property value, checking it against the schema up front
+    /// instead of at validation time.
+    ///
+    /// Rejects values that are invalid on their own (NaN, out-of-range
+    /// points, ...), that do not match the property's registered type, or
+    /// that break its TEXT constraints. Unknown properties are accepted.
+    pub fn new_checked(
+        property: Id,
+        value: impl Into<Value<'a>>,
+        schema: &SchemaContext,
+    ) -> Result<Self, ValidationError> {
+        let value = value.into();
+        if let Some(reason) = value.validate() {
+            return Err(ValidationError::InvalidValue { property, reason });
+        }
+        if let Some(expected) = schema.get_property_type(&property) {
+            if value.data_type() != expected {
+                return Err(ValidationError::TypeMismatch { property, expected });
+            }
+        }
+        if let Value::Text { value, .. } = &value {
+            if let Some(constraint) = schema.text_constraints.get(&property) {
+                validate_text(property, value, constraint)?;
+            }
+        }
+        Ok(Self { property, value })
+    }
+}
+
+/// Recognizes property declarations: a `DataType` (or `Types`) relation
+/// from a property to a data type entity, as written by
+/// [`EditBuilder::create_property`](crate::model::EditBuilder::create_property).
+pub(crate) struct PropertyDeclarations {
+    relation_types: [Id; 2],
+    data_types: [Id; 13],
+}
+
+impl PropertyDeclarations {
+    pub(crate) fn new() -> Self {
+        Self {
+            relation_types: [data_type_relation_type(), types_relation_type()],
+            data_types: DataType::ALL.map(data_type_entity_id),
+        }
+    }
+
+    /// Returns the declared property and its data type, if `relation` is
+    /// a declaration.
+    pub(crate) fn get(&self, relation: &CreateRelation) -> Option<(Id, DataType)> {
+        if relation.from_is_value_ref || !self.relation_types.contains(&relation.relation_type) {
+            return None;
+        }
+        let i = self.data_types.iter().position(|id| *id == relation.to)?;
+        Some((relation.from, DataType::ALL[i]))
+    }
+}
+
+/// Options for [`validate_edit_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Reject properties that are not registered in the [`SchemaContext`]
+    /// (by [`SchemaContext::add_property`] or a TEXT constraint), for
+    /// curated spaces. Off by default.
+    pub deny_unknown_properties: bool,
+}
+
+impl ValidationOptions {
+    /// Creates the default (advisory) options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects properties missing from the schema.
+    pub fn deny_unknown_properties(mut self) -> Self {
+        self.deny_unknown_properties = true;
+        self
+    }
 }
 
 /// Validates an edit against a schema context.
 ///
 /// This performs semantic validation that requires context:
 /// - Value types match property data types (when registered in schema)
+/// - TEXT values fit the length and pattern constraints of their property
+/// - `parents` are distinct and do not include the edit itself
+/// - Properties declared in the edit (see
+///   [`EditBuilder::create_property`](crate::model::EditBuilder::create_property))
+///   are type-checked for the rest of the edit, and must not conflict with
+///   the schema or an earlier declaration
 ///
 /// Note: Type checking is advisory. Unknown properties are allowed.
 /// Entity lifecycle (DELETED/ACTIVE) validation requires state context
 /// and is not performed here.
 pub fn validate_edit(edit: &Edit, schema: &SchemaContext) -> Result<(), ValidationError> {
+    validate_edit_with_options(edit, schema, &ValidationOptions::default())
+}
+
+/// Validates an edit against a schema context with explicit options.
+///
+/// With `deny_unknown_properties`, every property set, unset, or
+/// referenced by a value ref must be in the schema; the error lists all
+/// offending IDs in order of first use.
+pub fn validate_edit_with_options(
+    edit: &Edit,
+    schema: &SchemaContext,
+    options: &ValidationOptions,
+) -> Result<(), ValidationError> {
+    validate_edit_report(edit, schema, options, None).into_result()
+}
+
+/// Validates an edit and collects every error and warning instead of
+/// stopping at the first error.
+///
+/// With `state` (the graph the edit will be applied to), values set on
+/// deleted entities are reported as warnings.
+pub fn validate_edit_report(
+    edit: &Edit,
+    schema: &SchemaContext,
+    options: &ValidationOptions,
+    state: Option<&Graph>,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut parents = FxHashSet::default();
+    for parent in &edit.parents {
+        if *parent == edit.id {
+            report.error(None, ValidationError::SelfParent);
+        } else if !parents.insert(*parent) {
+            report.error(None, ValidationError::DuplicateParent { parent: *parent });
+        }
+    }
+    let declarations = PropertyDeclarations::new();
+    if options.deny_unknown_properties {
+        let unknown = unknown_properties(edit, schema, &declarations);
+        if !unknown.is_empty() {
+            report.error(None, ValidationError::UnknownProperties { properties: unknown });
+        }
+    }
+
+    // The schema plus properties declared earlier in the edit, once any are
+    let mut declared: Option<SchemaContext> = None;
+    let mut declared_ids = FxHashSet::default();
+    // Entities deleted (true) or restored (false) earlier in the edit
+    let mut deleted: FxHashMap<Id, bool> = FxHashMap::default();
+    let is_deleted = |deleted: &FxHashMap<Id, bool>, id: &Id| match deleted.get(id) {
+        Some(d) => *d,
+        None => state.is_some_and(|g| matches!(g.state(id), ObjectState::Deleted)),
+    };
+    for (i, op) in edit.ops.iter().enumerate() {
+        let (entity, values) = match op {
+            Op::CreateEntity(ce) => (ce.id, &ce.values),
+            Op::UpdateEntity(ue) => (ue.id, &ue.set_properties),
+            Op::DeleteEntity(de) => {
+                deleted.insert(de.id, true);
+                continue;
+            }
+            Op::RestoreEntity(re) => {
+                deleted.insert(re.id, false);
+                continue;
+            }
+            Op::CreateRelation(cr) => {
+                check_position(&mut report, i, cr.id, cr.position.as_deref());
+                if let Some((property, data_type)) = declarations.get(cr) {
+                    let existing = declared.as_ref().unwrap_or(schema).get_property_type(&property);
+                    if let Some(existing) = existing.filter(|t| *t != data_type) {
+                        report.error(
+                            Some(i),
+                            ValidationError::DataTypeInconsistent { property, schema: existing, declared: data_type },
+                        );
+                    } else if !declared_ids.insert(property) {
+                        report.warn(i, ValidationWarning::DuplicateProperty { property });
+                    } else {
+                        declared.get_or_insert_with(|| schema.clone()).add_property(property, data_type);
+                    }
+                }
+                continue;
+            }
+            Op::UpdateRelation(ur) => {
+                check_position(&mut report, i, ur.id, ur.position.as_deref());
+                continue;
+            }
+            _ => continue,
+        };
+        if !values.is_empty() && is_deleted(&deleted, &entity) {
+            report.warn(i, ValidationWarning::ValueOnDeletedEntity { entity });
+        }
+        validate_property_values(&mut report, i, entity, values, declared.as_ref().unwrap_or(schema));
+    }
+    report
+}
+
+/// Validates many edits in parallel, returning one report per edit in
+/// input order.
+///
+/// Edits are validated independently, without state, so no
+/// [`ValidationWarning::ValueOnDeletedEntity`] is reported across edits.
+#[cfg(feature = "rayon")]
+pub fn validate_edits_parallel(edits: &[Edit<'_>], schema: &SchemaContext) -> Vec<ValidationReport> {
+    validate_edits_parallel_with_options(edits, schema, &ValidationOptions::default())
+}
+
+/// Like [`validate_edits_parallel`], with explicit options.
+#[cfg(feature = "rayon")]
+pub fn validate_edits_parallel_with_options(
+    edits: &[Edit<'_>],
+    schema: &SchemaContext,
+    options: &ValidationOptions,
+) -> Vec<ValidationReport> {
+    use rayon::prelude::*;
+
+    edits.par_iter().map(|edit| validate_edit_report(edit, schema, options, None)).collect()
+}
+
+/// Warns when a position has fewer than this many characters of headroom.
+const POSITION_HEADROOM: usize = 8;
+
+fn check_position(report: &mut ValidationReport, op_index: usize, relation: Id, position: Option<&str>) {
+    if let Some(position) = position {
+        if position.len() + POSITION_HEADROOM > MAX_POSITION_LEN {
+            report.warn(op_index, ValidationWarning::PositionNearLimit { relation, len: position.len() });
+        }
+    }
+}
+
+/// Collects the properties used by an edit that neither the schema nor an
+/// earlier declaration in the edit knows.
+fn unknown_properties(edit: &Edit, schema: &SchemaContext, declarations: &PropertyDeclarations) -> Vec<Id> {
+    let mut unknown = Vec::new();
+    let mut declared = FxHashSet::default();
     for op in &edit.ops {
-        match op {
-            Op::CreateEntity(ce) => {
-                validate_property_values(&ce.values, schema)?;
+        let mut check = |property: Id| {
+            if !schema.knows(&property) && !declared.contains(&property) && !unknown.contains(&property) {
+                unknown.push(property);
             }
+        };
+        match op {
+            Op::CreateEntity(ce) => ce.values.iter().for_each(|pv| check(pv.property)),
             Op::UpdateEntity(ue) => {
-                validate_property_values(&ue.set_properties, schema)?;
+                ue.set_properties.iter().for_each(|pv| check(pv.property));
+                ue.unset_values.iter().for_each(|uv| check(uv.property));
+            }
+            Op::CreateValueRef(cvr) => check(cvr.property),
+            Op::CreateRelation(cr) => {
+                if let Some((property, _)) = declarations.get(cr) {
+                    declared.insert(property);
+                }
             }
             _ => {}
         }
     }
-
-    Ok(())
+    unknown
 }
 
 /// Validates that property values match their declared types.
 fn validate_property_values(
+    report: &mut ValidationReport,
+    op_index: usize,
+    entity: Id,
     values: &[PropertyValue],
     schema: &SchemaContext,
-) -> Result<(), ValidationError> {
+) {
     for pv in values {
         if let Some(expected_type) = schema.get_property_type(&pv.property) {
             let actual_type = pv.value.data_type();
             if expected_type != actual_type {
-                return Err(ValidationError::TypeMismatch {
-                    property: pv.property,
-                    expected: expected_type,
-                });
+                report.error(
+                    Some(op_index),
+                    ValidationError::TypeMismatch { property: pv.property, expected: expected_type },
+                );
             }
         }
         // Note: If property is not in schema, we allow it (might be defined elsewhere)
+        if let Value::Text { value, .. } = &pv.value {
+            if value.is_empty() {
+                report.warn(op_index, ValidationWarning::EmptyText { entity, property: pv.property });
+            }
+            if let Some(constraint) = schema.text_constraints.get(&pv.property) {
+                if let Err(e) = validate_text(pv.property, value, constraint) {
+                    report.error(Some(op_index), e);
+                }
+            }
+        }
+    }
+}
+
+/// Checks a TEXT value against its property's constraints.
+fn validate_text(property: Id, text: &str, constraint: &TextConstraint) -> Result<(), ValidationError> {
+    if let Some(max) = constraint.max_bytes {
+        if text.len() > max {
+            return Err(ValidationError::TextTooLong { property, len: text.len(), max });
+        }
+    }
+    #[cfg(feature = "regex")]
+    if let Some(pattern) = &constraint.pattern {
+        if !pattern.is_match(text) {
+            return Err(ValidationError::PatternMismatch { property });
+        }
     }
     Ok(())
 }
@@ -107,7 +401,7 @@ pub fn validate_position(pos: &str) -> Result<(), &'static str> {
 
 #[cfg(test)]
 mod tests {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use super::*;
     use crate::model::CreateEntity;
@@ -121,6 +415,7 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![Op::CreateEntity(CreateEntity {
                 id: [2u8; 16],
@@ -148,6 +443,7 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![Op::CreateEntity(CreateEntity {
                 id: [2u8; 16],
@@ -171,6 +467,7 @@ mod tests {
             id: [0u8; 16],
             name: Cow::Borrowed(""),
             authors: vec![],
+            parents: vec![],
             created_at: 0,
                         ops: vec![Op::CreateEntity(CreateEntity {
                 id: [2u8; 16],
@@ -189,4 +486,178 @@ mod tests {
         let result = validate_edit(&edit, &schema);
         assert!(result.is_ok());
     }
+
+    fn text_edit(property: Id, text: &str) -> Edit<'_> {
+        crate::model::EditBuilder::new([0u8; 16]).create_entity([2u8; 16], |e| e.text(property, text, None)).build()
+    }
+
+    #[test]
+    fn test_validate_text_length() {
+        let mut schema = SchemaContext::new();
+        schema.set_max_text_len([1u8; 16], 5);
+
+        assert!(validate_edit(&text_edit([1u8; 16], "Alice"), &schema).is_ok());
+        // Limits are in bytes, not characters
+        let result = validate_edit(&text_edit([1u8; 16], "Zoë Z"), &schema);
+        assert_eq!(result, Err(ValidationError::TextTooLong { property: [1u8; 16], len: 6, max: 5 }));
+        assert!(validate_edit(&text_edit([2u8; 16], "unconstrained"), &schema).is_ok());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_validate_text_pattern() {
+        let mut schema = SchemaContext::new();
+        schema.set_text_pattern([1u8; 16], "[a-z]+").unwrap();
+        schema.set_max_text_len([1u8; 16], 8);
+        assert!(schema.set_text_pattern([1u8; 16], "(").is_err());
+
+        assert!(validate_edit(&text_edit([1u8; 16], "abc"), &schema).is_ok());
+        let result = validate_edit(&text_edit([1u8; 16], "ab1"), &schema);
+        assert_eq!(result, Err(ValidationError::PatternMismatch { property: [1u8; 16] }));
+        let result = validate_edit(&text_edit([1u8; 16], "abcdefghi"), &schema);
+        assert!(matches!(result, Err(ValidationError::TextTooLong { .. })));
+    }
+
+    #[test]
+    fn test_deny_unknown_properties() {
+        let mut schema = SchemaContext::new();
+        schema.add_property([1u8; 16], DataType::Text);
+        let edit = crate::model::EditBuilder::new([0u8; 16])
+            .create_entity([2u8; 16], |e| e.text([1u8; 16], "ok", None).text([7u8; 16], "x", None))
+            .update_entity([2u8; 16], |u| u.set_int64([8u8; 16], 1, None).unset_all([7u8; 16]))
+            .build();
+
+        assert!(validate_edit(&edit, &schema).is_ok());
+        let strict = ValidationOptions::new().deny_unknown_properties();
+        let result = validate_edit_with_options(&edit, &schema, &strict);
+        assert_eq!(result, Err(ValidationError::UnknownProperties { properties: vec![[7u8; 16], [8u8; 16]] }));
+
+        schema.add_property([7u8; 16], DataType::Text);
+        schema.set_max_text_len([8u8; 16], 10);
+        assert!(validate_edit_with_options(&edit, &schema, &strict).is_ok());
+    }
+
+    #[test]
+    fn test_validation_report() {
+        let mut schema = SchemaContext::new();
+        schema.add_property([1u8; 16], DataType::Int64);
+        let mut state = Graph::new();
+        state.apply_edit(&crate::model::EditBuilder::new([0u8; 16]).create_entity([4u8; 16], |e| e).delete_entity([4u8; 16]).build());
+
+        let edit = crate::model::EditBuilder::new([9u8; 16])
+            .create_entity([2u8; 16], |e| e.text([1u8; 16], "", None).text([3u8; 16], "a", None))
+            .update_entity([4u8; 16], |u| u.set_int64([1u8; 16], 5, None))
+            .update_entity([2u8; 16], |u| u.set_text([1u8; 16], "x", None))
+            .create_relation(|r| r.id([7u8; 16]).relation_type([5u8; 16]).from([2u8; 16]).to([4u8; 16]).position("a".repeat(60)))
+            .build();
+        let report = validate_edit_report(&edit, &schema, &ValidationOptions::new(), Some(&state));
+
+        // Both type mismatches are reported, not just the first
+        assert_eq!(report.errors().count(), 2);
+        assert!(!report.is_ok());
+        let warnings: Vec<_> = report.warnings().cloned().collect();
+        assert_eq!(warnings, vec![
+            ValidationWarning::EmptyText { entity: [2u8; 16], property: [1u8; 16] },
+            ValidationWarning::ValueOnDeletedEntity { entity: [4u8; 16] },
+            ValidationWarning::PositionNearLimit { relation: [7u8; 16], len: 60 },
+        ]);
+        assert_eq!(report.at_least(Severity::Error).map(|i| i.op_index).collect::<Vec<_>>(), vec![Some(0), Some(2)]);
+        assert_eq!(report.at_least(Severity::Warning).count(), 5);
+        assert_eq!(report.into_result(), validate_edit(&edit, &schema));
+
+        // Without state, only deletes inside the edit count
+        let report = validate_edit_report(&edit, &schema, &ValidationOptions::new(), None);
+        assert_eq!(report.warnings().count(), 2);
+    }
+
+    #[test]
+    fn test_parents_distinct() {
+        let schema = SchemaContext::new();
+        let edit = crate::model::EditBuilder::new([9u8; 16]).parent([1u8; 16]).parent([2u8; 16]).build();
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        let edit = crate::model::EditBuilder::new([9u8; 16]).parents([[1u8; 16], [9u8; 16], [1u8; 16]]).build();
+        let report = validate_edit_report(&edit, &schema, &ValidationOptions::new(), None);
+        assert_eq!(report.errors().cloned().collect::<Vec<_>>(), vec![
+            ValidationError::SelfParent,
+            ValidationError::DuplicateParent { parent: [1u8; 16] },
+        ]);
+    }
+
+    #[test]
+    fn test_property_value_new_checked() {
+        let mut schema = SchemaContext::new();
+        schema.add_property([1u8; 16], DataType::Int64);
+        schema.set_max_text_len([2u8; 16], 3);
+
+        let pv = PropertyValue::new_checked([1u8; 16], 42i64, &schema).unwrap();
+        assert_eq!(pv, PropertyValue::new([1u8; 16], 42i64));
+        assert_eq!(
+            PropertyValue::new_checked([1u8; 16], "42", &schema),
+            Err(ValidationError::TypeMismatch { property: [1u8; 16], expected: DataType::Int64 })
+        );
+        assert_eq!(
+            PropertyValue::new_checked([2u8; 16], "Alice", &schema),
+            Err(ValidationError::TextTooLong { property: [2u8; 16], len: 5, max: 3 })
+        );
+        assert_eq!(
+            PropertyValue::new_checked([3u8; 16], f64::NAN, &schema),
+            Err(ValidationError::InvalidValue { property: [3u8; 16], reason: "NaN is not allowed in Float64" })
+        );
+        assert!(PropertyValue::new_checked([3u8; 16], (40.7, -74.0), &schema).is_ok());
+    }
+
+    #[test]
+    fn test_declared_properties() {
+        let mut schema = SchemaContext::new();
+        schema.add_property([1u8; 16], DataType::Text);
+        let edit = crate::model::EditBuilder::new([9u8; 16])
+            .create_entity([2u8; 16], |e| e.text([5u8; 16], "before", None))
+            .create_property([5u8; 16], DataType::Int64, |e| e)
+            .create_entity([3u8; 16], |e| e.int64([5u8; 16], 1, None).text([5u8; 16], "after", None))
+            .create_property([5u8; 16], DataType::Int64, |e| e)
+            .create_property([1u8; 16], DataType::Int64, |e| e)
+            .build();
+        let report = validate_edit_report(&edit, &schema, &ValidationOptions::new(), None);
+        assert_eq!(report.errors().cloned().collect::<Vec<_>>(), vec![
+            ValidationError::TypeMismatch { property: [5u8; 16], expected: DataType::Int64 },
+            ValidationError::DataTypeInconsistent { property: [1u8; 16], schema: DataType::Text, declared: DataType::Int64 },
+        ]);
+        assert_eq!(report.warnings().cloned().collect::<Vec<_>>(), vec![
+            ValidationWarning::DuplicateProperty { property: [5u8; 16] },
+        ]);
+        assert_eq!(report.at_least(Severity::Error).map(|i| i.op_index).collect::<Vec<_>>(), vec![Some(3), Some(7)]);
+        // The caller's schema is unchanged
+        assert!(!schema.knows(&[5u8; 16]));
+
+        // Declared properties are known from the declaration on
+        let strict = ValidationOptions::new().deny_unknown_properties();
+        let result = validate_edit_with_options(&edit, &schema, &strict);
+        assert_eq!(result, Err(ValidationError::UnknownProperties { properties: vec![[5u8; 16]] }));
+        let edit = crate::model::EditBuilder::new([9u8; 16])
+            .create_property([5u8; 16], DataType::Int64, |e| e.text([1u8; 16], "Population", None))
+            .create_entity([3u8; 16], |e| e.int64([5u8; 16], 1, None))
+            .build();
+        assert!(validate_edit_with_options(&edit, &schema, &strict).is_ok());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_validate_edits_parallel() {
+        let mut schema = SchemaContext::new();
+        schema.add_property([1u8; 16], DataType::Int64);
+        let edits: Vec<Edit> = (0..64u8)
+            .map(|i| {
+                crate::model::EditBuilder::new([i; 16])
+                    .create_entity([2u8; 16], |e| if i % 3 == 0 { e.text([1u8; 16], "x", None) } else { e.int64([1u8; 16], 1, None) })
+                    .build()
+            })
+            .collect();
+        let reports = validate_edits_parallel(&edits, &schema);
+        assert_eq!(reports.len(), 64);
+        for (i, report) in reports.iter().enumerate() {
+            assert_eq!(report.is_ok(), i % 3 != 0);
+            assert_eq!(*report, validate_edit_report(&edits[i], &schema, &ValidationOptions::new(), None));
+        }
+    }
 }
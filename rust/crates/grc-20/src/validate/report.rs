@@ -0,0 +1,106 @@
+//! Validation reports with errors and warnings.
+
+use crate::error::ValidationError;
+use crate::model::Id;
+use crate::prelude::*;
+
+/// How serious an issue is. Ordered, so `>= Severity::Warning` selects all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Something legal but likely unintended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    /// A value is set on an entity that is deleted (in the given state, or
+    /// earlier in the edit), so it has no effect until a restore.
+    ValueOnDeletedEntity { entity: Id },
+    /// A relation position is close to the 64-character limit, leaving
+    /// little room for inserting between neighbors.
+    PositionNearLimit { relation: Id, len: usize },
+    /// A TEXT value is empty.
+    EmptyText { entity: Id, property: Id },
+    /// A property is declared more than once in the edit (with the same
+    /// data type).
+    DuplicateProperty { property: Id },
+}
+
+/// An error or a warning.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+    Error(ValidationError),
+    Warning(ValidationWarning),
+}
+
+impl Finding {
+    /// Returns the severity of the finding.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Finding::Error(_) => Severity::Error,
+            Finding::Warning(_) => Severity::Warning,
+        }
+    }
+}
+
+/// A finding and where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// Index of the op in the edit, or `None` for edit-wide findings.
+    pub op_index: Option<usize>,
+    pub finding: Finding,
+}
+
+/// All issues found in an edit, in op order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub(crate) fn error(&mut self, op_index: Option<usize>, error: ValidationError) {
+        self.issues.push(Issue { op_index, finding: Finding::Error(error) });
+    }
+
+    pub(crate) fn warn(&mut self, op_index: usize, warning: ValidationWarning) {
+        self.issues.push(Issue { op_index: Some(op_index), finding: Finding::Warning(warning) });
+    }
+
+    /// Returns true if there are no errors (warnings are allowed).
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Iterates over the issues of at least `severity`.
+    pub fn at_least(&self, severity: Severity) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(move |i| i.finding.severity() >= severity)
+    }
+
+    /// Iterates over the errors.
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationError> {
+        self.issues.iter().filter_map(|i| match &i.finding {
+            Finding::Error(e) => Some(e),
+            Finding::Warning(_) => None,
+        })
+    }
+
+    /// Iterates over the warnings.
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationWarning> {
+        self.issues.iter().filter_map(|i| match &i.finding {
+            Finding::Warning(w) => Some(w),
+            Finding::Error(_) => None,
+        })
+    }
+
+    /// Returns the first error, if any.
+    pub fn into_result(self) -> Result<(), ValidationError> {
+        match self.issues.into_iter().find_map(|i| match i.finding {
+            Finding::Error(e) => Some(e),
+            Finding::Warning(_) => None,
+        }) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
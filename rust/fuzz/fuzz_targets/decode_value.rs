@@ -0,0 +1,20 @@
+#![no_main]
+
+//! Input: one byte selecting the data type, then the encoded value.
+
+use grc_20::codec::{decode_value, Reader};
+use grc_20::{DataType, WireDictionaries};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&tag, bytes)) = data.split_first() else { return };
+    let Some(data_type) = DataType::from_u8(tag) else { return };
+    // A few entries so small dictionary indices resolve
+    let dicts = WireDictionaries {
+        languages: vec![[1u8; 16], [2u8; 16]],
+        units: vec![[3u8; 16], [4u8; 16]],
+        ..WireDictionaries::default()
+    };
+    let mut reader = Reader::new(bytes);
+    let _ = decode_value(&mut reader, data_type, &dicts);
+});
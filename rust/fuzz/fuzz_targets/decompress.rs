@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(bytes) = grc_20::decompress(data) {
+        let _ = grc_20::decode_edit(&bytes);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(lazy) = grc_20::codec::LazyEdit::parse(data) {
+        for i in 0..lazy.len() {
+            let _ = lazy.op_type(i);
+            let _ = lazy.op(i);
+        }
+    }
+});